@@ -69,11 +69,11 @@ impl Handler<MessageOne> for ActorThree {
 
 #[derive(Clone, Debug, Message)]
 #[rtype(result = "()")]
-struct MessageOne(String);
+struct MessageOne(#[allow(dead_code)] String);
 
 #[derive(Clone, Debug, Message)]
 #[rtype(result = "()")]
-struct MessageTwo(u8);
+struct MessageTwo(#[allow(dead_code)] u8);
 
 fn main() {
     println!("Starting");
@@ -9,7 +9,10 @@ use actix::prelude::*;
 use ahash::AHasher;
 use log::trace;
 
-use crate::msgs::*;
+use crate::{
+    msgs::*,
+    sequenced::{Sequenced, SubscriberId, Topic},
+};
 
 type TypeMap<A> = HashMap<TypeId, A, BuildHasherDefault<AHasher>>;
 
@@ -17,6 +20,8 @@ type TypeMap<A> = HashMap<TypeId, A, BuildHasherDefault<AHasher>>;
 pub struct Broker<T> {
     sub_map: TypeMap<Vec<(TypeId, Box<dyn Any>)>>,
     msg_map: TypeMap<Box<dyn Any>>,
+    topics: TypeMap<Box<dyn Any>>,
+    last_sub_id: u64,
     _t: PhantomData<T>,
 }
 
@@ -48,6 +53,37 @@ impl Broker<ArbiterBroker> {
     }
 }
 
+/// Sequenced delivery of broadcast messages.
+impl<T: RegisteredBroker> Broker<T> {
+    /// Issue a message to the sequenced subscribers of its type, stamped with the next sequence
+    /// number of that type.
+    ///
+    /// Subscribers registered with
+    /// [`subscribe_sequenced()`](crate::BrokerSubscribe::subscribe_sequenced) receive the
+    /// messages in sequence order, as [`Sequenced`] messages. A message counts as handled by a
+    /// subscriber once its handler returns. While the slowest subscriber is more than the
+    /// maximum lag set with [`set_max_lag()`](Self::set_max_lag) behind, messages are held back
+    /// by the broker.
+    pub fn issue_sequenced<M: BrokerMsg>(msg: M) {
+        T::get_broker().do_send(IssueSequenced(msg));
+    }
+
+    /// Set how many issued messages of type `M` the slowest subscriber may leave unhandled
+    /// before the broker pauses issuing them. `None`, the default, never pauses.
+    pub fn set_max_lag<M: BrokerMsg>(max_lag: Option<u64>) {
+        T::get_broker().do_send(SetMaxLag::<M>(max_lag, PhantomData));
+    }
+
+    /// Returns the number of issued messages of type `M` every sequenced subscriber has not
+    /// handled yet.
+    pub async fn subscriber_lag<M: BrokerMsg>() -> Vec<(SubscriberId, u64)> {
+        T::get_broker()
+            .send(SubscriberLag::<M>(PhantomData))
+            .await
+            .unwrap_or_default()
+    }
+}
+
 /// The system service actor that keeps track of subscriptions and routes messages to them.
 impl<T> Broker<T> {
     fn take_subs<M: BrokerMsg>(&mut self) -> Option<Vec<(TypeId, Recipient<M>)>> {
@@ -101,6 +137,40 @@ impl<T> Broker<T> {
         trace!("Broker: Adding first message value for {:?}", id);
         self.msg_map.insert(id, boxed);
     }
+
+    fn topic<M: BrokerMsg>(&mut self) -> &mut Topic<M> {
+        self.topics
+            .entry(TypeId::of::<M>())
+            .or_insert_with(|| Box::<Topic<M>>::default())
+            .downcast_mut()
+            .expect("topic of another message type")
+    }
+}
+
+impl<T: 'static + Unpin> Broker<T> {
+    /// Issues the sequenced messages of type `M` which are not held back.
+    fn issue_sequenced_backlog<M: BrokerMsg>(&mut self, ctx: &mut Context<Self>) {
+        while let Some((seq, msg, subs)) = self.topic::<M>().next() {
+            trace!("Broker: Issuing sequenced message {}", seq);
+            for (id, s) in subs {
+                let msg = Sequenced {
+                    seq,
+                    msg: msg.clone(),
+                };
+                s.send(msg)
+                    .into_actor(self)
+                    .map(move |res, act, ctx| {
+                        match res {
+                            Err(MailboxError::Closed) => act.topic::<M>().unsubscribe(id),
+                            // a message rejected by the subscriber is not retried
+                            _ => act.topic::<M>().ack(id, seq),
+                        }
+                        act.issue_sequenced_backlog::<M>(ctx);
+                    })
+                    .spawn(ctx);
+            }
+        }
+    }
 }
 
 impl<T: 'static + Unpin, M: BrokerMsg> Handler<SubscribeAsync<M>> for Broker<T> {
@@ -127,23 +197,20 @@ impl<T: 'static + Unpin, M: BrokerMsg> Handler<IssueAsync<M>> for Broker<T> {
 
     fn handle(&mut self, msg: IssueAsync<M>, _ctx: &mut Context<Self>) {
         trace!("Broker: Received IssueAsync");
-        if let Some(mut subs) = self.take_subs::<M>() {
-            subs.drain(..).for_each(|(id, s)| {
-                if id == msg.1 {
-                    self.add_sub::<M>(s, id);
-                } else {
-                    match s.try_send(msg.0.clone()) {
-                        Ok(_) => self.add_sub::<M>(s, id),
-                        Err(SendError::Full(_)) => {
-                            // Ensure that that the message is delivered even if the mailbox is full.
-                            // We do a try first to remove receiver that have closed their mailbox.
-                            s.do_send(msg.0.clone());
-                            self.add_sub::<M>(s, id);
-                        }
-                        Err(_) => (),
-                    }
-                }
-            });
+        if let Some(subs) = self.take_subs::<M>() {
+            let (own, others): (Vec<_>, Vec<_>) =
+                subs.into_iter().partition(|(id, _)| *id == msg.1);
+            let (mut ids, mut targets): (Vec<_>, Vec<_>) = others.into_iter().unzip();
+
+            // The message is delivered even if the mailbox is full, subscribers whose mailbox
+            // is closed are removed.
+            let report = actix::broadcast(&targets, &msg.0);
+            report.retain_delivered(&mut ids);
+            report.retain_delivered(&mut targets);
+
+            for (id, s) in own.into_iter().chain(ids.into_iter().zip(targets)) {
+                self.add_sub::<M>(s, id);
+            }
         }
         self.set_msg::<M>(msg.0);
     }
@@ -170,6 +237,45 @@ impl<T: 'static + Unpin, M: BrokerMsg> Handler<IssueSync<M>> for Broker<T> {
     }
 }
 
+impl<T: 'static + Unpin, M: BrokerMsg> Handler<SubscribeSequenced<M>> for Broker<T> {
+    type Result = ();
+
+    fn handle(&mut self, msg: SubscribeSequenced<M>, _ctx: &mut Context<Self>) {
+        trace!("Broker: Received SubscribeSequenced");
+        self.last_sub_id += 1;
+        let id = SubscriberId(self.last_sub_id);
+        self.topic::<M>().subscribe(id, msg.0);
+    }
+}
+
+impl<T: 'static + Unpin, M: BrokerMsg> Handler<IssueSequenced<M>> for Broker<T> {
+    type Result = ();
+
+    fn handle(&mut self, msg: IssueSequenced<M>, ctx: &mut Context<Self>) {
+        trace!("Broker: Received IssueSequenced");
+        self.topic::<M>().push(msg.0);
+        self.issue_sequenced_backlog::<M>(ctx);
+    }
+}
+
+impl<T: 'static + Unpin, M: BrokerMsg> Handler<SetMaxLag<M>> for Broker<T> {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetMaxLag<M>, ctx: &mut Context<Self>) {
+        trace!("Broker: Received SetMaxLag");
+        self.topic::<M>().set_max_lag(msg.0);
+        self.issue_sequenced_backlog::<M>(ctx);
+    }
+}
+
+impl<T: 'static + Unpin, M: BrokerMsg> Handler<SubscriberLag<M>> for Broker<T> {
+    type Result = MessageResult<SubscriberLag<M>>;
+
+    fn handle(&mut self, _msg: SubscriberLag<M>, _ctx: &mut Context<Self>) -> Self::Result {
+        MessageResult(self.topic::<M>().lag())
+    }
+}
+
 impl<T: 'static + Unpin> Actor for Broker<T> {
     type Context = Context<Self>;
 }
@@ -78,11 +78,13 @@
 mod broker;
 mod issue;
 mod msgs;
+mod sequenced;
 mod subscribe;
 
 pub use crate::{
     broker::{ArbiterBroker, Broker, SystemBroker},
     issue::BrokerIssue,
     msgs::BrokerMsg,
+    sequenced::{Sequenced, SubscriberId},
     subscribe::BrokerSubscribe,
 };
@@ -1,7 +1,9 @@
-use std::any::TypeId;
+use std::{any::TypeId, marker::PhantomData};
 
 use actix::prelude::*;
 
+use crate::sequenced::{Sequenced, SubscriberId};
+
 pub trait BrokerMsg: Message<Result = ()> + Send + Clone + 'static {}
 
 impl<M> BrokerMsg for M where M: Message<Result = ()> + Send + Clone + 'static {}
@@ -23,3 +25,19 @@ pub struct IssueAsync<M: BrokerMsg>(pub M, pub TypeId);
 #[derive(Message)]
 #[rtype(result = "()")]
 pub struct IssueSync<M: BrokerMsg>(pub M, pub TypeId);
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SubscribeSequenced<M: BrokerMsg>(pub Recipient<Sequenced<M>>);
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct IssueSequenced<M: BrokerMsg>(pub M);
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SetMaxLag<M: BrokerMsg>(pub Option<u64>, pub PhantomData<M>);
+
+#[derive(Message)]
+#[rtype(result = "Vec<(SubscriberId, u64)>")]
+pub struct SubscriberLag<M: BrokerMsg>(pub PhantomData<M>);
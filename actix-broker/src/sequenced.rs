@@ -0,0 +1,115 @@
+//! Sequenced delivery of broadcast messages.
+use std::collections::VecDeque;
+
+use actix::prelude::*;
+
+use crate::msgs::BrokerMsg;
+
+/// A broadcast message stamped with its sequence number, issued with
+/// [`Broker::issue_sequenced()`](crate::Broker::issue_sequenced).
+///
+/// Sequence numbers start at 1 and are increasing per message type and broker, so subscribers
+/// on different arbiters can order the messages they receive the same way.
+#[derive(Debug, Clone)]
+pub struct Sequenced<M> {
+    pub seq: u64,
+    pub msg: M,
+}
+
+impl<M: BrokerMsg> Message for Sequenced<M> {
+    type Result = ();
+}
+
+/// Identifier of a sequenced subscription, assigned by the broker in subscription order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SubscriberId(pub(crate) u64);
+
+pub(crate) type Recipients<M> = Vec<(SubscriberId, Recipient<Sequenced<M>>)>;
+
+struct Subscriber<M: BrokerMsg> {
+    id: SubscriberId,
+    recipient: Recipient<Sequenced<M>>,
+    /// Sequence number of the last message handled by the subscriber.
+    acked: u64,
+}
+
+/// Subscriptions and messages waiting to be issued for one message type.
+pub(crate) struct Topic<M: BrokerMsg> {
+    /// Sequence number of the last issued message.
+    seq: u64,
+    subs: Vec<Subscriber<M>>,
+    max_lag: Option<u64>,
+    backlog: VecDeque<M>,
+}
+
+impl<M: BrokerMsg> Default for Topic<M> {
+    fn default() -> Self {
+        Topic {
+            seq: 0,
+            subs: Vec::new(),
+            max_lag: None,
+            backlog: VecDeque::new(),
+        }
+    }
+}
+
+impl<M: BrokerMsg> Topic<M> {
+    /// Adds a subscriber, which receives the messages issued from now on.
+    pub(crate) fn subscribe(&mut self, id: SubscriberId, recipient: Recipient<Sequenced<M>>) {
+        self.subs.push(Subscriber {
+            id,
+            recipient,
+            acked: self.seq,
+        });
+    }
+
+    pub(crate) fn unsubscribe(&mut self, id: SubscriberId) {
+        self.subs.retain(|sub| sub.id != id);
+    }
+
+    pub(crate) fn set_max_lag(&mut self, max_lag: Option<u64>) {
+        self.max_lag = max_lag;
+    }
+
+    pub(crate) fn push(&mut self, msg: M) {
+        self.backlog.push_back(msg);
+    }
+
+    /// Records that subscriber `id` has handled the message `seq`.
+    pub(crate) fn ack(&mut self, id: SubscriberId, seq: u64) {
+        if let Some(sub) = self.subs.iter_mut().find(|sub| sub.id == id) {
+            sub.acked = sub.acked.max(seq);
+        }
+    }
+
+    /// Returns the number of issued messages each subscriber has not handled yet.
+    pub(crate) fn lag(&self) -> Vec<(SubscriberId, u64)> {
+        self.subs
+            .iter()
+            .map(|sub| (sub.id, self.seq - sub.acked))
+            .collect()
+    }
+
+    /// Returns `true` if the slowest subscriber is more than the maximum lag behind.
+    fn is_paused(&self) -> bool {
+        self.max_lag.map_or(false, |max_lag| {
+            self.subs.iter().any(|sub| self.seq - sub.acked > max_lag)
+        })
+    }
+
+    /// Takes the next message to issue with its sequence number and subscribers, unless issuing
+    /// is paused.
+    pub(crate) fn next(&mut self) -> Option<(u64, M, Recipients<M>)> {
+        if self.is_paused() {
+            return None;
+        }
+        let msg = self.backlog.pop_front()?;
+        self.seq += 1;
+        let subs = self
+            .subs
+            .iter()
+            .map(|sub| (sub.id, sub.recipient.clone()))
+            .collect();
+        Some((self.seq, msg, subs))
+    }
+}
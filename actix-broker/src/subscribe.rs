@@ -6,6 +6,7 @@ use actix::{dev::ToEnvelope, prelude::*};
 use crate::{
     broker::{ArbiterBroker, RegisteredBroker, SystemBroker},
     msgs::*,
+    sequenced::Sequenced,
 };
 
 /// The `BrokerSubscribe` trait has functions to register an actor's interest in different
@@ -49,6 +50,21 @@ where
             .wait(ctx);
     }
 
+    /// Asynchronously subscribe to the messages issued with
+    /// [`Broker::issue_sequenced()`](crate::Broker::issue_sequenced).
+    ///
+    /// The broker waits for this actor's handler of every [`Sequenced`] message to return, to
+    /// track how far behind the other subscribers this actor is.
+    fn subscribe_sequenced<T: RegisteredBroker, M: BrokerMsg>(&self, ctx: &mut Self::Context)
+    where
+        Self: Handler<Sequenced<M>>,
+        <Self as Actor>::Context: ToEnvelope<Self, Sequenced<M>>,
+    {
+        let broker = T::get_broker();
+        let recipient = ctx.address().recipient::<Sequenced<M>>();
+        broker.do_send(SubscribeSequenced(recipient));
+    }
+
     /// Helper to asynchronously subscribe to a system broker
     /// This is the equivalent of `self.subscribe_async::<SystemBroker, M>(ctx);`
     fn subscribe_system_async<M: BrokerMsg>(&self, ctx: &mut Self::Context)
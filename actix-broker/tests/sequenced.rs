@@ -0,0 +1,74 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix::{clock::sleep, prelude::*};
+use actix_broker::{Broker, BrokerSubscribe, Sequenced, SystemBroker};
+
+#[derive(Clone, Message)]
+#[rtype(result = "()")]
+struct Event(u64);
+
+struct Subscriber {
+    delay: Duration,
+    seen: Arc<Mutex<Vec<(u64, u64)>>>,
+}
+
+impl Actor for Subscriber {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.subscribe_sequenced::<SystemBroker, Event>(ctx);
+    }
+}
+
+impl Handler<Sequenced<Event>> for Subscriber {
+    type Result = AtomicResponse<Self, ()>;
+
+    fn handle(&mut self, msg: Sequenced<Event>, _ctx: &mut Self::Context) -> Self::Result {
+        AtomicResponse::new(Box::pin(
+            sleep(self.delay)
+                .into_actor(self)
+                .map(move |_, act, _| act.seen.lock().unwrap().push((msg.seq, msg.msg.0))),
+        ))
+    }
+}
+
+#[actix::test]
+async fn it_issues_sequenced_with_max_lag() {
+    let fast = Arc::new(Mutex::new(Vec::new()));
+    let slow = Arc::new(Mutex::new(Vec::new()));
+    Subscriber {
+        delay: Duration::ZERO,
+        seen: Arc::clone(&fast),
+    }
+    .start();
+    Subscriber {
+        delay: Duration::from_millis(50),
+        seen: Arc::clone(&slow),
+    }
+    .start();
+    sleep(Duration::from_millis(10)).await;
+
+    Broker::<SystemBroker>::set_max_lag::<Event>(Some(2));
+    for n in 0..10 {
+        Broker::<SystemBroker>::issue_sequenced(Event(n * 10));
+    }
+
+    // issuing is paused while the slow subscriber is more than 2 messages behind
+    sleep(Duration::from_millis(10)).await;
+    let lag = Broker::<SystemBroker>::subscriber_lag::<Event>().await;
+    assert_eq!(lag.len(), 2);
+    assert_eq!(lag[0].1, 0);
+    assert_eq!(lag[1].1, 3);
+    assert_eq!(fast.lock().unwrap().len(), 3);
+
+    sleep(Duration::from_millis(800)).await;
+    let expected: Vec<_> = (1..=10).map(|seq| (seq, (seq - 1) * 10)).collect();
+    assert_eq!(*fast.lock().unwrap(), expected);
+    assert_eq!(*slow.lock().unwrap(), expected);
+
+    let lag = Broker::<SystemBroker>::subscriber_lag::<Event>().await;
+    assert!(lag.iter().all(|&(_, lag)| lag == 0));
+}
@@ -0,0 +1,116 @@
+//! Spawn, complete and cancel churn of context futures at various concurrency levels.
+//!
+//! Run with `cargo bench -p actix --bench context`, adding `--features small_context` or
+//! `--features large_context` to compare the inline capacities of a context.
+
+use std::{
+    future::pending,
+    mem,
+    time::{Duration, Instant},
+};
+
+use actix::{dev::ContextFut, fut, prelude::*};
+
+const ITERATIONS: u32 = 2_000;
+const CONCURRENCY: [usize; 5] = [1, 3, 8, 12, 32];
+
+struct Churn;
+
+impl Actor for Churn {
+    type Context = Context<Self>;
+}
+
+/// Spawns futures which complete on their first poll.
+struct Complete(usize);
+
+impl Message for Complete {
+    type Result = ();
+}
+
+impl Handler<Complete> for Churn {
+    type Result = ();
+
+    fn handle(&mut self, Complete(n): Complete, ctx: &mut Self::Context) {
+        for _ in 0..n {
+            ctx.spawn(fut::ready(()));
+        }
+    }
+}
+
+/// Spawns futures which never complete and cancels them.
+struct Cancel(usize);
+
+impl Message for Cancel {
+    type Result = ();
+}
+
+impl Handler<Cancel> for Churn {
+    type Result = ();
+
+    fn handle(&mut self, Cancel(n): Cancel, ctx: &mut Self::Context) {
+        let handles: Vec<_> = (0..n)
+            .map(|_| ctx.spawn(fut::wrap_future(pending())))
+            .collect();
+        for handle in handles {
+            ctx.cancel_future(handle);
+        }
+    }
+}
+
+/// Spawns futures which complete on their first poll while as many others are pending, then
+/// cancels the pending ones.
+struct Mixed(usize);
+
+impl Message for Mixed {
+    type Result = ();
+}
+
+impl Handler<Mixed> for Churn {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, Mixed(n): Mixed, ctx: &mut Self::Context) -> Self::Result {
+        let pending: Vec<_> = (0..n)
+            .map(|_| ctx.spawn(fut::wrap_future(pending())))
+            .collect();
+        for _ in 0..n {
+            ctx.spawn(fut::ready(()));
+        }
+        Box::pin(fut::ready(()).map(move |_, _, ctx: &mut Context<Self>| {
+            for handle in pending {
+                ctx.cancel_future(handle);
+            }
+        }))
+    }
+}
+
+async fn run<M>(addr: &Addr<Churn>, msg: impl Fn() -> M) -> Duration
+where
+    M: Message<Result = ()> + Send + 'static,
+    Churn: Handler<M>,
+{
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        addr.send(msg()).await.unwrap();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    println!(
+        "size of a running actor without state: {} bytes",
+        mem::size_of::<ContextFut<Churn, Context<Churn>>>()
+    );
+
+    let sys = System::new();
+    sys.block_on(async {
+        let addr = Churn.start();
+        for n in CONCURRENCY {
+            let complete = run(&addr, || Complete(n)).await;
+            let cancel = run(&addr, || Cancel(n)).await;
+            let mixed = run(&addr, || Mixed(n)).await;
+            println!(
+                "{n:>3} futures: complete {complete:>10?}  cancel {cancel:>10?}  mixed {mixed:>10?}"
+            );
+        }
+    });
+}
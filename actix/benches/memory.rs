@@ -0,0 +1,76 @@
+//! Memory held by a running actor with an empty mailbox, with `Context` and `MinimalContext`.
+//!
+//! Run with `cargo bench -p actix --bench memory`. The heap is measured with a counting
+//! allocator, so the task and mailbox allocations of an actor are included.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System as SystemAlloc},
+    mem,
+    sync::atomic::{AtomicIsize, Ordering},
+    time::Duration,
+};
+
+use actix::{dev::ContextFut, dev::MinimalContextFut, prelude::*, MinimalContext};
+
+const ACTORS: usize = 100_000;
+
+struct Counting;
+
+static ALLOCATED: AtomicIsize = AtomicIsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size() as isize, Ordering::Relaxed);
+        SystemAlloc.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size() as isize, Ordering::Relaxed);
+        SystemAlloc.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+struct Full;
+
+impl Actor for Full {
+    type Context = Context<Self>;
+}
+
+struct Slim;
+
+impl Actor for Slim {
+    type Context = MinimalContext<Self>;
+}
+
+/// Returns the bytes allocated per actor started by `start` once they all run, along with
+/// their addresses which keep them running.
+async fn per_actor<A: Actor>(start: impl Fn() -> Addr<A>) -> (isize, Vec<Addr<A>>) {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let addrs: Vec<_> = (0..ACTORS).map(|_| start()).collect();
+    actix_rt::time::sleep(Duration::from_millis(100)).await;
+    let after = ALLOCATED.load(Ordering::Relaxed);
+    let addrs_size = (addrs.capacity() * mem::size_of::<Addr<A>>()) as isize;
+    ((after - before - addrs_size) / ACTORS as isize, addrs)
+}
+
+fn main() {
+    let sys = System::new();
+    sys.block_on(async {
+        let (full, _full) = per_actor(|| Full.start()).await;
+        let (slim, _slim) = per_actor(|| MinimalContext::new().run(Slim)).await;
+
+        println!(
+            "       Context: {:>4} bytes per actor, {:>4} bytes inline",
+            full,
+            mem::size_of::<ContextFut<Full, Context<Full>>>()
+        );
+        println!(
+            "MinimalContext: {:>4} bytes per actor, {:>4} bytes inline",
+            slim,
+            mem::size_of::<MinimalContextFut<Slim>>()
+        );
+    });
+}
@@ -0,0 +1,72 @@
+//! Request latency and send throughput of an actor on the sender's arbiter and on another one.
+//!
+//! Run with `cargo bench -p actix --bench send`.
+//!
+//! `Addr` has a single channel: a sender on the actor's own arbiter enqueues into the mailbox
+//! directly and wakes the actor on the same thread, so only the cross-arbiter case pays for a
+//! wakeup of another thread.
+
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+
+const ITERATIONS: u32 = 20_000;
+
+struct Echo;
+
+impl Actor for Echo {
+    type Context = Context<Self>;
+}
+
+struct Ping(u32);
+
+impl Message for Ping {
+    type Result = u32;
+}
+
+impl Handler<Ping> for Echo {
+    type Result = MessageResult<Ping>;
+
+    fn handle(&mut self, Ping(n): Ping, _: &mut Self::Context) -> Self::Result {
+        MessageResult(n)
+    }
+}
+
+/// Awaits each request before sending the next one.
+async fn latency(addr: &Addr<Echo>) -> Duration {
+    let start = Instant::now();
+    for n in 0..ITERATIONS {
+        addr.send(Ping(n)).await.unwrap();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+/// Sends all requests before awaiting the replies.
+async fn throughput(addr: &Addr<Echo>) -> Duration {
+    let start = Instant::now();
+    let replies: Vec<_> = (0..ITERATIONS).map(|n| addr.send(Ping(n))).collect();
+    for reply in replies {
+        reply.await.unwrap();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let sys = System::new();
+    sys.block_on(async {
+        let arbiter = Arbiter::new();
+        let targets = [
+            ("same arbiter", Echo.start()),
+            (
+                "other arbiter",
+                Echo::start_in_arbiter(&arbiter.handle(), |_| Echo),
+            ),
+        ];
+        for (name, addr) in &targets {
+            let latency = latency(addr).await;
+            let throughput = throughput(addr).await;
+            println!("{name:>13}: latency {latency:>10?}  throughput {throughput:>10?}");
+        }
+        arbiter.stop();
+    });
+}
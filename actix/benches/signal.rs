@@ -0,0 +1,95 @@
+//! Memory allocated while sending signals to an actor, compared with `do_send`.
+//!
+//! Run with `cargo bench -p actix --bench signal`. The heap is measured with a counting
+//! allocator, which only counts allocations, so messages freed once handled are included.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System as SystemAlloc},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use actix::prelude::*;
+
+const SIGNALS: usize = 1_000_000;
+
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        SystemAlloc.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        SystemAlloc.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+#[derive(Default, Message)]
+#[rtype(result = "()")]
+struct Tick;
+
+#[derive(Message)]
+#[rtype(result = "usize")]
+struct Count;
+
+struct Counter(usize);
+
+impl Actor for Counter {
+    type Context = Context<Self>;
+}
+
+impl Handler<Tick> for Counter {
+    type Result = ();
+
+    fn handle(&mut self, _: Tick, _: &mut Self::Context) {
+        self.0 += 1;
+    }
+}
+
+impl Handler<Count> for Counter {
+    type Result = usize;
+
+    fn handle(&mut self, _: Count, _: &mut Self::Context) -> usize {
+        self.0
+    }
+}
+
+/// Sends `SIGNALS` ticks with `send` and waits for them to be handled, returns the bytes
+/// allocated meanwhile.
+async fn allocated(name: &str, send: impl Fn(&Addr<Counter>)) {
+    let addr = Counter(0).start();
+    // the first signal allocates the counter of its type
+    addr.signal::<Tick>();
+    addr.send(Count).await.unwrap();
+
+    let start = Instant::now();
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    for _ in 0..SIGNALS {
+        send(&addr);
+    }
+    assert_eq!(addr.send(Count).await.unwrap(), SIGNALS + 1);
+    let after = ALLOCATED.load(Ordering::Relaxed);
+
+    println!(
+        "{:>7}: {:>10} bytes allocated for {} ticks in {:?}",
+        name,
+        after - before,
+        SIGNALS,
+        start.elapsed()
+    );
+}
+
+fn main() {
+    let sys = System::new();
+    sys.block_on(async {
+        allocated("signal", |addr| addr.signal::<Tick>()).await;
+        allocated("do_send", |addr| addr.do_send(Tick)).await;
+    });
+}
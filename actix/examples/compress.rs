@@ -0,0 +1,65 @@
+//! Compresses large messages while they are queued in an actor's mailbox.
+
+use std::io::{Read, Write};
+
+use actix::prelude::*;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+
+/// Large message, which is deflate-compressed while queued
+struct Payload(Vec<u8>);
+
+impl Message for Payload {
+    type Result = usize;
+}
+
+impl TransformOnSend for Payload {
+    type Encoded = Vec<u8>;
+
+    fn encode(self) -> Vec<u8> {
+        let mut enc = DeflateEncoder::new(Vec::new(), Compression::fast());
+        enc.write_all(&self.0).unwrap();
+        let compressed = enc.finish().unwrap();
+        println!("compressed {} bytes to {}", self.0.len(), compressed.len());
+        compressed
+    }
+
+    fn decode(enc: Vec<u8>) -> Self {
+        let mut data = Vec::new();
+        DeflateDecoder::new(&enc[..])
+            .read_to_end(&mut data)
+            .unwrap();
+        Payload(data)
+    }
+}
+
+/// Actor running on a separate arbiter
+struct Storage {
+    stored: usize,
+}
+
+impl Actor for Storage {
+    type Context = Context<Self>;
+}
+
+impl Handler<Payload> for Storage {
+    type Result = usize;
+
+    fn handle(&mut self, msg: Payload, _: &mut Context<Self>) -> Self::Result {
+        self.stored += msg.0.len();
+        self.stored
+    }
+}
+
+#[actix::main]
+async fn main() {
+    let arbiter = Arbiter::new();
+    let addr = Storage::start_in_arbiter(&arbiter.handle(), |_| Storage { stored: 0 });
+
+    // encoded on this thread, decoded on the storage arbiter
+    let payload = Payload(b"actix ".repeat(1024 * 1024));
+    let res = addr.send_transformed(payload).await;
+    println!("stored {} bytes", res.unwrap());
+
+    arbiter.stop();
+    System::current().stop();
+}
@@ -0,0 +1,106 @@
+//! Three-level supervision tree, whose failures escalate from a leaf to its group.
+//!
+//! The `shards` group restarts a crashing shard twice, then fails. The `storage` group owning it
+//! restarts all its children, including the `shards` group with fresh shards.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix::prelude::*;
+
+/// Latest address of each running shard.
+type Shards = Arc<Mutex<HashMap<&'static str, Addr<Shard>>>>;
+
+struct Shard {
+    name: &'static str,
+    shards: Shards,
+}
+
+impl Actor for Shard {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        println!("started shard {}", self.name);
+        self.shards.lock().unwrap().insert(self.name, ctx.address());
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Crash;
+
+impl Handler<Crash> for Shard {
+    type Result = ();
+
+    fn handle(&mut self, _: Crash, ctx: &mut Self::Context) {
+        println!("crashing shard {}", self.name);
+        ctx.stop();
+    }
+}
+
+struct Cache;
+
+impl Actor for Cache {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _: &mut Self::Context) {
+        println!("started cache");
+    }
+}
+
+struct Monitor;
+
+impl Actor for Monitor {
+    type Context = Context<Self>;
+}
+
+impl Handler<SupervisionFailed> for Monitor {
+    type Result = ();
+
+    fn handle(&mut self, failed: SupervisionFailed, _: &mut Self::Context) {
+        println!(
+            "{} failed after {} restarts",
+            failed.path.join("/"),
+            failed.restarts
+        );
+    }
+}
+
+fn shard(shards: &Shards, name: &'static str) -> impl Fn() -> Addr<Shard> {
+    let shards = Arc::clone(shards);
+    move || {
+        Shard {
+            name,
+            shards: Arc::clone(&shards),
+        }
+        .start()
+    }
+}
+
+#[actix::main]
+async fn main() {
+    let shards = Shards::default();
+
+    let shard_group = SupervisorGroup::new("shards")
+        .restart_policy(RestartPolicy::Limit(2))
+        .child("a", shard(&shards, "a"))
+        .child("b", shard(&shards, "b"));
+    let storage = SupervisorGroup::new("storage")
+        .strategy(GroupStrategy::OneForAll)
+        .child("cache", || Cache.start())
+        .group(shard_group);
+    let _root = SupervisorGroup::new("root")
+        .on_failure(Monitor.start().recipient())
+        .group(storage)
+        .start();
+
+    for _ in 0..3 {
+        actix_rt::time::sleep(Duration::from_millis(50)).await;
+        let addr = shards.lock().unwrap()["a"].clone();
+        addr.do_send(Crash);
+    }
+    actix_rt::time::sleep(Duration::from_millis(50)).await;
+}
@@ -0,0 +1,73 @@
+//! Tokio runtime example
+//!
+//! Actors running on a Tokio runtime owned by the application, next to plain
+//! Tokio tasks. Both share the reactor and the timer of the runtime, and the
+//! runtime keeps running once the actix system has stopped.
+
+use std::{sync::Arc, time::Duration};
+
+use actix::{prelude::*, SystemConfig};
+use tokio::sync::mpsc;
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Tick(u32);
+
+struct Counter {
+    ticks: u32,
+}
+
+impl Actor for Counter {
+    type Context = Context<Self>;
+}
+
+impl Handler<Tick> for Counter {
+    type Result = ();
+
+    fn handle(&mut self, msg: Tick, ctx: &mut Self::Context) {
+        self.ticks += 1;
+        println!("actor received tick {}", msg.0);
+        if self.ticks == 3 {
+            ctx.stop();
+            System::current().stop();
+        }
+    }
+}
+
+fn main() {
+    let rt = Arc::new(
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap(),
+    );
+
+    // a plain Tokio task producing ticks
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let producer = rt.spawn(async move {
+        for tick in 1.. {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            if tx.send(tick).is_err() {
+                break;
+            }
+        }
+        println!("tokio task done");
+    });
+
+    let sys = SystemConfig::new().build_with_tokio_rt(Arc::clone(&rt));
+    sys.block_on(async move {
+        let addr = Counter { ticks: 0 }.start();
+        actix::spawn(async move {
+            while let Some(tick) = rx.recv().await {
+                if addr.send(Tick(tick)).await.is_err() {
+                    break;
+                }
+            }
+        });
+    });
+    sys.run().unwrap();
+    println!("actix system stopped");
+
+    // the runtime is still up, the producer notices the ticks are not received anymore
+    rt.block_on(producer).unwrap();
+}
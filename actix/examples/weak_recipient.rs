@@ -11,7 +11,7 @@ use actix::{prelude::*, Actor, Context, WeakRecipient};
 
 #[derive(Message, Debug)]
 #[rtype(result = "()")]
-pub struct TimePing(Instant);
+pub struct TimePing(#[allow(dead_code)] Instant);
 
 #[derive(Message, Debug)]
 #[rtype(result = "()")]
@@ -1,16 +1,33 @@
-use std::time::Duration;
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    future::Future,
+    rc::Rc,
+    task::Waker,
+    time::Duration,
+};
 
-use actix_rt::ArbiterHandle;
+use actix_rt::{task::JoinHandle, ArbiterHandle};
 use futures_core::stream::Stream;
 use log::error;
 
 use crate::{
-    address::{channel, Addr},
+    address::{channel, Addr, LinkedExit, Request, ToEnvelope, UnhandledMessage},
+    arbiter::spawn_actor,
+    clock::Instant,
+    config::SystemConfig,
     context::Context,
-    contextitems::{ActorDelayedMessageItem, ActorMessageItem, ActorMessageStreamItem},
-    fut::{ActorFuture, ActorStreamExt},
-    handler::{Handler, Message},
-    mailbox::DEFAULT_CAPACITY,
+    contextimpl::{AsyncContextParts, Barrier},
+    contextitems::{
+        ActorDelayedMessageItem, ActorMessageItem, ActorMessageStreamItem, ActorScopedItem,
+        ActorWaitTimeoutItem,
+    },
+    fut::{ActorFuture, ActorFutureExt, ActorStreamExt},
+    handler::{Handler, Message, ResponseActFuture},
+    journal::{Journal, ReplayError},
+    mailbox::PressureLevel,
+    panic_report::PanicReport,
+    settings::ActorSettings,
     stream::StreamHandler,
     utils::{IntervalFunc, TimerFunc},
 };
@@ -93,6 +110,47 @@ pub trait Actor: Sized + Unpin + 'static {
         Running::Stop
     }
 
+    /// Called once an actor has agreed to stop, before [`Actor::stopped`].
+    ///
+    /// Returning a future defers stopping the actor until the future resolves, for cleanup which
+    /// is asynchronous, like flushing a buffer to a connection. Meanwhile the mailbox is closed,
+    /// so queued and newly sent messages fail, and only this future is polled: futures spawned
+    /// by it or before are dropped with the context. If the future has not resolved within the
+    /// finalize timeout of the context, see [`Context::set_finalize_timeout()`], it is dropped
+    /// and the actor stops anyway.
+    ///
+    /// Not called for sync actors, nor when the actor is terminated or its context is dropped.
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// use actix::prelude::*;
+    ///
+    /// struct Committer;
+    ///
+    /// impl Actor for Committer {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn finalizing(&mut self, _: &mut Self::Context) -> Option<ResponseActFuture<Self, ()>> {
+    ///         // e.g. commit a transaction
+    ///         let commit = actix_rt::time::sleep(Duration::from_millis(10));
+    ///         Some(Box::pin(commit.into_actor(self)))
+    ///     }
+    ///
+    ///     fn stopped(&mut self, _: &mut Self::Context) {
+    ///         System::current().stop();
+    ///     }
+    /// }
+    ///
+    /// # fn main() {
+    /// let sys = System::new();
+    /// sys.block_on(async { Committer.start(); });
+    /// # sys.run().unwrap();
+    /// # }
+    /// ```
+    fn finalizing(&mut self, ctx: &mut Self::Context) -> Option<ResponseActFuture<Self, ()>> {
+        None
+    }
+
     /// Called after an actor is stopped.
     ///
     /// This method can be used to perform any needed cleanup work or
@@ -100,6 +158,52 @@ pub trait Actor: Sized + Unpin + 'static {
     /// method got called, the actor will be dropped.
     fn stopped(&mut self, ctx: &mut Self::Context) {}
 
+    /// Called when the context of an actor is dropped before the actor has stopped.
+    ///
+    /// The context is dropped without stopping the actor when its arbiter shuts down while the
+    /// actor refuses to stop, or when it is dropped by other means than by its arbiter. The
+    /// futures spawned into the context, including wait futures, have already been dropped and
+    /// [`Actor::stopped`] is not called. The actor is dropped right after this method returns.
+    fn abandoned(&mut self) {}
+
+    /// Called when the actor has panicked, before its context is dropped.
+    ///
+    /// The report names the message or named timer the actor was running when it panicked.
+    /// The actor may be left in an inconsistent state by the panic and this method must not
+    /// panic itself, since it is usually called while the panic unwinds. Actors linked with
+    /// the actor receive the same report in their [`LinkedExit`]. Only actors running in a
+    /// [`Context`] report their panics.
+    fn handler_panicked(&mut self, report: &PanicReport) {}
+
+    /// Called when the pressure of the mailbox changes, once watermarks are set with
+    /// [`Context::set_mailbox_watermarks()`].
+    ///
+    /// The actor is told with [`PressureLevel::Rising`] that it is falling behind, and with
+    /// [`PressureLevel::Falling`] that it has caught up, at most once per change. Changes are
+    /// reported between messages, a rise and fall happening in between are not reported.
+    fn mailbox_pressure(&mut self, level: PressureLevel, ctx: &mut Self::Context) {}
+
+    /// Called when an actor linked with [`AsyncContext::link()`] has exited.
+    ///
+    /// By default the actor stops as well. Override this method to trap exits of linked actors.
+    fn linked_exit(&mut self, exit: LinkedExit, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+
+    /// Called when a message reaches the actor but is not handled.
+    ///
+    /// This happens to messages sent through a recipient which was revoked with
+    /// [`RevokeHandle::revoke()`](crate::RevokeHandle::revoke) while they were queued. By
+    /// default a warning is logged and the message is dropped, so a waiting sender receives
+    /// [`MailboxError::Closed`](crate::MailboxError::Closed).
+    fn unhandled(&mut self, msg: UnhandledMessage, ctx: &mut Self::Context) {
+        log::warn!(
+            "{} dropped unhandled message {}",
+            std::any::type_name::<Self>(),
+            msg.type_name()
+        );
+    }
+
     /// Start a new asynchronous actor, returning its address.
     ///
     /// # Examples
@@ -138,21 +242,51 @@ pub trait Actor: Sized + Unpin + 'static {
         Self::default().start()
     }
 
+    /// Start an already constructed actor in arbiter's thread, returning its address.
+    ///
+    /// Use [`start_in_arbiter()`](Self::start_in_arbiter) if the actor is not `Send`, or
+    /// needs its `Context` during construction.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// struct MyActor;
+    /// impl Actor for MyActor {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let arbiter = Arbiter::new();
+    ///     let addr = MyActor.start_in(&arbiter.handle());
+    ///     # arbiter.stop();
+    ///     # System::current().stop();
+    /// }
+    /// ```
+    fn start_in(self, wrk: &ArbiterHandle) -> Addr<Self>
+    where
+        Self: Actor<Context = Context<Self>> + Send,
+    {
+        Self::start_in_arbiter(wrk, move |_| self)
+    }
+
     /// Start new actor in arbiter's thread.
     fn start_in_arbiter<F>(wrk: &ArbiterHandle, f: F) -> Addr<Self>
     where
         Self: Actor<Context = Context<Self>>,
         F: FnOnce(&mut Context<Self>) -> Self + Send + 'static,
     {
-        let (tx, rx) = channel::channel(DEFAULT_CAPACITY);
+        let (tx, rx) = channel::channel(SystemConfig::current().get_mailbox_capacity());
 
         // create actor
         wrk.spawn_fn(move || {
             let mut ctx = Context::with_receiver(rx);
-            let act = f(&mut ctx);
-            let fut = ctx.into_future(act);
-
-            actix_rt::spawn(fut);
+            match ctx.create_with(f) {
+                Some(act) => spawn_actor(ctx.into_future(act)),
+                None => ctx.abandon(),
+            }
         });
 
         Addr::new(tx)
@@ -163,6 +297,12 @@ pub trait Actor: Sized + Unpin + 'static {
     /// Use this method if you need the `Context` object during actor
     /// initialization.
     ///
+    /// If `f` panics, the panic is logged and the actor is not started: its address is
+    /// disconnected, messages sent to it fail with
+    /// [`MailboxError::Closed`](crate::MailboxError::Closed), and its
+    /// [`state_stream()`](Addr::state_stream) yields [`ActorState::Stopped`]. Other actors of the
+    /// arbiter keep running.
+    ///
     /// # Examples
     ///
     /// ```
@@ -187,9 +327,185 @@ pub trait Actor: Sized + Unpin + 'static {
         F: FnOnce(&mut Context<Self>) -> Self,
     {
         let mut ctx = Context::new();
+        match ctx.create_with(f) {
+            Some(act) => ctx.run(act),
+            None => {
+                ctx.abandon();
+                ctx.address()
+            }
+        }
+    }
+
+    /// Construct a new asynchronous actor, replay the messages of its journal and start it,
+    /// returning its address.
+    ///
+    /// `f` registers the journaled message types with [`Context::journal_message()`]. The
+    /// journaled messages are handled before [`Actor::started()`] is called and before the
+    /// mailbox is opened, with [`Context::is_replaying()`] returning `true`. Messages handled
+    /// afterwards are appended to `journal`.
+    ///
+    /// Fails without starting the actor if the journal can't be read, one of its messages has
+    /// an unknown type or fails to decode, or the actor stops during replay. See the
+    /// [`journal`](crate::journal) module for an example.
+    fn create_replayed<F>(
+        journal: Box<dyn Journal>,
+        persistence_id: &str,
+        f: F,
+    ) -> Result<Addr<Self>, ReplayError>
+    where
+        Self: Actor<Context = Context<Self>>,
+        F: FnOnce(&mut Context<Self>) -> Self,
+    {
+        let mut ctx = Context::new();
+        ctx.set_journal(journal, persistence_id);
+        let mut act = f(&mut ctx);
+        ctx.replay(&mut act)?;
+        Ok(ctx.run(act))
+    }
+
+    /// Start a new asynchronous actor with the given execution settings, returning its
+    /// address.
+    ///
+    /// The settings are applied before the actor starts, so they apply to the first messages
+    /// too.
+    /// ```
+    /// use actix::{prelude::*, ActorSettings};
+    ///
+    /// struct MyActor;
+    /// impl Actor for MyActor {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let addr = MyActor.start_with(ActorSettings::default().mailbox(512));
+    ///     # System::current().stop();
+    /// }
+    /// ```
+    fn start_with(self, settings: ActorSettings) -> Addr<Self>
+    where
+        Self: Actor<Context = Context<Self>>,
+    {
+        let mut ctx = Context::new();
+        ctx.apply_settings(&settings);
+        ctx.run(self)
+    }
+
+    /// Construct and start a new asynchronous actor with the given execution settings,
+    /// returning its address.
+    ///
+    /// The settings are applied before `f` is called, which may still change them.
+    fn create_with<F>(settings: ActorSettings, f: F) -> Addr<Self>
+    where
+        Self: Actor<Context = Context<Self>>,
+        F: FnOnce(&mut Context<Self>) -> Self,
+    {
+        let mut ctx = Context::new();
+        ctx.apply_settings(&settings);
         let act = f(&mut ctx);
         ctx.run(act)
     }
+
+    /// Start a new asynchronous actor given a `Context`, using a fallible
+    /// factory.
+    ///
+    /// If the factory returns an error the context is torn down without
+    /// calling [`Actor::started`]: any futures spawned into it are dropped
+    /// and addresses obtained from it become disconnected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// struct MyActor {
+    ///     val: usize,
+    /// }
+    /// impl Actor for MyActor {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let res = MyActor::try_create(|_| "10".parse().map(|val| MyActor { val }));
+    ///     assert!(res.is_ok());
+    ///
+    ///     let res = MyActor::try_create(|_| "ten".parse().map(|val| MyActor { val }));
+    ///     assert!(res.is_err());
+    ///     # System::current().stop();
+    /// }
+    /// ```
+    fn try_create<F, E>(f: F) -> Result<Addr<Self>, E>
+    where
+        Self: Actor<Context = Context<Self>>,
+        F: FnOnce(&mut Context<Self>) -> Result<Self, E>,
+    {
+        let mut ctx = Context::new();
+        let act = f(&mut ctx)?;
+        Ok(ctx.run(act))
+    }
+
+    /// Start a new asynchronous actor whose construction completes
+    /// asynchronously, returning its address immediately.
+    ///
+    /// Messages sent to the address are buffered in the mailbox until the
+    /// returned future resolves, and are then handled in order after
+    /// [`Actor::started`]. If the future resolves to an error the context is
+    /// torn down without calling `started` and the address becomes
+    /// disconnected; pending and future requests fail with
+    /// [`MailboxError::Closed`](crate::MailboxError::Closed).
+    ///
+    /// The returned handle resolves once the actor is started, or with the
+    /// error of the factory. Dropping it does not affect the actor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// struct MyActor {
+    ///     val: usize,
+    /// }
+    /// impl Actor for MyActor {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let (addr, started) = MyActor::try_create_async(|_ctx| async {
+    ///         // e.g. open a connection
+    ///         Ok::<_, std::io::Error>(MyActor { val: 10 })
+    ///     });
+    ///     assert!(started.await.unwrap().is_ok());
+    ///     # System::current().stop();
+    /// }
+    /// ```
+    fn try_create_async<F, Fut, E>(f: F) -> (Addr<Self>, JoinHandle<Result<(), E>>)
+    where
+        Self: Actor<Context = Context<Self>>,
+        F: FnOnce(&mut Context<Self>) -> Fut,
+        Fut: Future<Output = Result<Self, E>> + 'static,
+        E: 'static,
+    {
+        let mut ctx = Context::new();
+        let fut = f(&mut ctx);
+        let addr = ctx.address();
+
+        let handle = actix_rt::spawn(async move {
+            match fut.await {
+                Ok(act) => {
+                    spawn_actor(ctx.into_future(act));
+                    Ok(())
+                }
+                Err(err) => {
+                    ctx.abandon();
+                    Err(err)
+                }
+            }
+        });
+
+        (addr, handle)
+    }
 }
 
 #[allow(unused_variables)]
@@ -258,8 +574,27 @@ pub trait ActorContext: Sized {
     /// Terminate actor execution unconditionally. This sets the actor
     /// into the `stopped` state. This causes future attempts to queue
     /// messages to fail.
+    ///
+    /// [`Actor::stopping()`] is not called. Spawned futures of an asynchronous context are polled
+    /// once more, with [`terminating()`](Self::terminating) returning `true`, so they can flush
+    /// small buffers; futures still pending afterwards are dropped. Then [`Actor::stopped()`] is
+    /// called and watchers and linked actors are notified.
     fn terminate(&mut self);
 
+    /// Terminate actor execution like [`terminate()`](Self::terminate), without polling spawned
+    /// futures again.
+    ///
+    /// Contexts which don't poll futures after terminating terminate the same way.
+    fn terminate_hard(&mut self) {
+        self.terminate()
+    }
+
+    /// Returns `true` once the actor was terminated with [`terminate()`](Self::terminate), which
+    /// spawned futures polled a last time can check to wind down.
+    fn terminating(&self) -> bool {
+        false
+    }
+
     /// Retrieve the current Actor execution state.
     fn state(&self) -> ActorState;
 }
@@ -291,6 +626,23 @@ where
     where
         F: ActorFuture<A, Output = ()> + 'static;
 
+    /// Spawns a future into the context, waiting for it to resolve for at most `timeout`.
+    ///
+    /// Like [`wait()`](Self::wait), this stops processing any incoming events until the future
+    /// resolves. If `timeout` elapses first, the future is dropped, `on_timeout` is called and
+    /// the context resumes processing events.
+    ///
+    /// The timeout starts when the future is queued. A future which is still queued behind
+    /// other wait futures at its deadline is dropped without being polled once it reaches the
+    /// front of the queue.
+    fn wait_timeout<F, T>(&mut self, fut: F, timeout: Duration, on_timeout: T)
+    where
+        F: ActorFuture<A, Output = ()> + 'static,
+        T: FnOnce(&mut A, &mut A::Context) + 'static,
+    {
+        self.wait(ActorWaitTimeoutItem::new(fut, timeout, on_timeout));
+    }
+
     /// Checks if the context is paused (waiting for future completion or stopping).
     fn waiting(&self) -> bool;
 
@@ -435,6 +787,209 @@ where
         }
     }
 
+    /// Spawns `fut` and sends its output, converted into the message `M`, to self once it
+    /// resolves.
+    ///
+    /// The output is handled like a message sent with [`notify()`](Self::notify), so the
+    /// actor's state is only changed by its handlers. Returns a spawn handle which can be used
+    /// for cancellation, in which case no message is sent.
+    ///
+    /// ```
+    /// # use actix::prelude::*;
+    /// struct Fetcher;
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "()")]
+    /// struct Fetched(Result<u32, ()>);
+    ///
+    /// impl From<Result<u32, ()>> for Fetched {
+    ///     fn from(res: Result<u32, ()>) -> Self {
+    ///         Fetched(res)
+    ///     }
+    /// }
+    ///
+    /// impl Actor for Fetcher {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         let fut = async { Ok(42) }.into_actor(self);
+    ///         ctx.spawn_into::<Fetched, _>(fut);
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Fetched> for Fetcher {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, msg: Fetched, _: &mut Self::Context) {
+    ///         assert_eq!(msg.0, Ok(42));
+    ///         System::current().stop();
+    ///     }
+    /// }
+    /// # fn main() {
+    /// #     let sys = System::new();
+    /// #     sys.block_on(async { Fetcher.start() });
+    /// #     sys.run().unwrap();
+    /// # }
+    /// ```
+    fn spawn_into<M, F>(&mut self, fut: F) -> SpawnHandle
+    where
+        F: ActorFuture<A> + 'static,
+        A: Handler<M>,
+        M: Message + From<F::Output> + 'static,
+    {
+        self.spawn(fut.map(|res, _, ctx: &mut A::Context| ctx.notify(M::from(res))))
+    }
+
+    /// Sends the message `msg` to self once the timer `name` expires after `after`.
+    ///
+    /// A timer which is already armed under the same name is cancelled first, so restarting a
+    /// keepalive or retransmit timeout is a single call. Like
+    /// [`notify_later()`](Self::notify_later), the message bypasses the mailbox, and the timers
+    /// are cancelled when the actor stops. All timers of an actor share a single spawned future.
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actix::prelude::*;
+    /// struct Session;
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "()")]
+    /// struct Expired;
+    ///
+    /// impl Actor for Session {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         ctx.set_timer("expiry", Duration::from_secs(60), Expired);
+    ///         // replaces the first timer
+    ///         ctx.set_timer("expiry", Duration::from_millis(10), Expired);
+    ///         assert!(ctx.timer_remaining("expiry").unwrap() <= Duration::from_millis(10));
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Expired> for Session {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, _: Expired, _: &mut Self::Context) {
+    ///         System::current().stop();
+    ///     }
+    /// }
+    /// # fn main() {
+    /// #     let sys = System::new();
+    /// #     sys.block_on(async { Session.start() });
+    /// #     sys.run().unwrap();
+    /// # }
+    /// ```
+    fn set_timer<M>(&mut self, name: &'static str, after: Duration, msg: M)
+    where
+        A: Handler<M>,
+        M: Message + 'static;
+
+    /// Cancels the timer `name` set with [`set_timer()`](Self::set_timer).
+    ///
+    /// Returns `false` if no such timer is armed, including when it has already fired.
+    fn cancel_timer(&mut self, name: &'static str) -> bool;
+
+    /// Returns the time left until the timer `name` fires, or `None` if it is not armed.
+    fn timer_remaining(&self, name: &'static str) -> Option<Duration>;
+
+    /// Returns `true` if an interrupt was requested with [`Addr::interrupt()`].
+    ///
+    /// Long running handlers and futures can check it to abort their work cooperatively. The
+    /// interrupt is cleared before the next message is handled, unless it is sticky.
+    ///
+    /// ```
+    /// # use actix::prelude::*;
+    /// struct Crawler;
+    ///
+    /// impl Actor for Crawler {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "usize")]
+    /// struct Crawl(Vec<String>);
+    ///
+    /// impl Handler<Crawl> for Crawler {
+    ///     type Result = usize;
+    ///
+    ///     fn handle(&mut self, msg: Crawl, ctx: &mut Self::Context) -> usize {
+    ///         let mut crawled = 0;
+    ///         for _url in msg.0 {
+    ///             if ctx.interrupted() {
+    ///                 break;
+    ///             }
+    ///             crawled += 1;
+    ///         }
+    ///         crawled
+    ///     }
+    /// }
+    /// ```
+    fn interrupted(&self) -> bool;
+
+    /// Clears a requested interrupt.
+    fn clear_interrupt(&mut self);
+
+    /// Returns a [`Barrier`] which resolves once every future spawned into the context so far
+    /// has completed or been cancelled.
+    ///
+    /// Futures spawned afterwards, including the barrier itself, are not waited for. Spawned
+    /// futures which never complete, like streams and intervals, hold the barrier until they are
+    /// cancelled, and so do timers armed with [`set_timer()`](Self::set_timer) until they fire.
+    /// Spawned futures are not polled while the actor waits, so the barrier has to be spawned
+    /// rather than passed to [`wait()`](Self::wait).
+    ///
+    /// ```
+    /// # use actix::prelude::*;
+    /// # use std::time::Duration;
+    /// struct Journal;
+    ///
+    /// impl Actor for Journal {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         for n in 0..3 {
+    ///             let delay = Duration::from_millis(10 * n);
+    ///             ctx.spawn(actix::clock::sleep(delay).into_actor(self));
+    ///         }
+    ///         let barrier = ctx.barrier();
+    ///         ctx.spawn(barrier.map(|_, _, _| System::current().stop()));
+    ///     }
+    /// }
+    ///
+    /// # fn main() {
+    /// # let sys = System::new();
+    /// # sys.block_on(async { Journal.start(); });
+    /// # sys.run().unwrap();
+    /// # }
+    /// ```
+    fn barrier(&mut self) -> Barrier<A>;
+
+    /// Returns the deadline of the request being handled, if it was sent with
+    /// [`Addr::send_with_deadline()`].
+    ///
+    /// The deadline is only available while [`Handler::handle()`] runs, asynchronous responses
+    /// have to capture it. A handler can skip work its caller will not wait for, and pass the
+    /// remaining time on with [`send_downstream()`](Self::send_downstream).
+    fn request_deadline(&self) -> Option<Instant>;
+
+    /// Sends a message to `addr` with the deadline of the request being handled, see
+    /// [`request_deadline()`](Self::request_deadline).
+    ///
+    /// Outside of a request with a deadline the message is sent with [`Addr::send()`].
+    fn send_downstream<B, M>(&self, addr: &Addr<B>, msg: M) -> Request<B, M>
+    where
+        B: Handler<M>,
+        B::Context: AsyncContextParts<B> + ToEnvelope<B, M>,
+        M: Message + Send + 'static,
+        M::Result: Send,
+    {
+        match self.request_deadline() {
+            Some(deadline) => addr.send_with_deadline(msg, deadline),
+            None => addr.send(msg),
+        }
+    }
+
     /// Executes a closure after a specified period of time.
     ///
     /// The closure gets passed the same actor and its
@@ -455,6 +1010,232 @@ where
     {
         self.spawn(IntervalFunc::new(dur, f).finish())
     }
+
+    /// Creates a new cancellation scope.
+    ///
+    /// Futures spawned with [`ScopeHandle::spawn()`] can be cancelled together with
+    /// [`ScopeHandle::cancel_all()`].
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actix::prelude::*;
+    /// struct MyActor;
+    ///
+    /// impl Actor for MyActor {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         let scope = ctx.scope();
+    ///         scope.spawn(ctx, actix::clock::sleep(Duration::from_secs(1)).into_actor(self));
+    ///         scope.spawn(ctx, actix::clock::sleep(Duration::from_secs(2)).into_actor(self));
+    ///
+    ///         // abort both sleeps at once
+    ///         scope.cancel_all();
+    ///         System::current().stop();
+    ///     }
+    /// }
+    /// # fn main() {
+    /// #     let sys = System::new();
+    /// #     sys.block_on(async { MyActor.start() });
+    /// #     sys.run().unwrap();
+    /// # }
+    /// ```
+    fn scope(&mut self) -> ScopeHandle {
+        ScopeHandle::new(None)
+    }
+
+    /// Attaches a resource to the actor's lifetime.
+    ///
+    /// Attached resources are dropped right after [`Actor::stopped`] returns, in reverse
+    /// attach order, including when the actor is stopped with
+    /// [`ActorContext::terminate()`]. Use [`ResourceHandle::detach()`] to take the resource
+    /// back earlier.
+    ///
+    /// ```
+    /// # use actix::prelude::*;
+    /// struct Connection;
+    ///
+    /// impl Drop for Connection {
+    ///     fn drop(&mut self) {
+    ///         System::current().stop();
+    ///     }
+    /// }
+    ///
+    /// struct MyActor;
+    ///
+    /// impl Actor for MyActor {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         // dropped, and the system stopped, once the actor has stopped
+    ///         ctx.attach_resource(Connection);
+    ///         ctx.stop();
+    ///     }
+    /// }
+    /// # fn main() {
+    /// #     let sys = System::new();
+    /// #     sys.block_on(async { MyActor.start() });
+    /// #     sys.run().unwrap();
+    /// # }
+    /// ```
+    fn attach_resource<R: 'static>(&mut self, res: R) -> ResourceHandle<R>;
+
+    /// Hands the mailbox over to `target` and stops the actor.
+    ///
+    /// The actor doesn't handle its queued messages anymore, they are forwarded to `target` in
+    /// order, with their replies still going to the original senders. Addresses and recipients
+    /// of the actor then deliver to `target`, including sends racing with the handoff on other
+    /// threads, which never overtake the forwarded messages. This allows replacing a stateful
+    /// actor, e.g. with a new instance holding its migrated state, without losing messages.
+    ///
+    /// Messages already taken into a batch or a partition lane are not forwarded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `target` is the address of the actor itself.
+    ///
+    /// ```
+    /// # use actix::prelude::*;
+    /// struct Counter(u64);
+    ///
+    /// impl Actor for Counter {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "u64")]
+    /// struct Increment;
+    ///
+    /// impl Handler<Increment> for Counter {
+    ///     type Result = u64;
+    ///
+    ///     fn handle(&mut self, _: Increment, _: &mut Self::Context) -> u64 {
+    ///         self.0 += 1;
+    ///         self.0
+    ///     }
+    /// }
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "()")]
+    /// struct Upgrade;
+    ///
+    /// impl Handler<Upgrade> for Counter {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, _: Upgrade, ctx: &mut Self::Context) {
+    ///         let replacement = Counter(self.0 * 1000).start();
+    ///         ctx.handoff_to(replacement);
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let counter = Counter(0).start();
+    ///     counter.do_send(Increment);
+    ///     counter.do_send(Upgrade);
+    ///     // handled by the replacement, through the same address
+    ///     assert_eq!(counter.send(Increment).await.unwrap(), 1001);
+    /// }
+    /// ```
+    fn handoff_to(&mut self, target: Addr<A>);
+
+    /// Defers a function until the message or future being handled has completed.
+    ///
+    /// Deferred functions run in the order they were deferred, once the context has processed
+    /// its currently queued messages and ready futures, and before the actor stops. Functions
+    /// deferred by a deferred function run right after it, up to a nesting depth of 16; deeper
+    /// ones run the next time the context is polled.
+    ///
+    /// Unlike spawning a future, no [`SpawnHandle`] is allocated. The default implementation
+    /// spawns the function as a future.
+    ///
+    /// ```
+    /// # use actix::prelude::*;
+    /// struct MyActor(Vec<u32>);
+    ///
+    /// impl Actor for MyActor {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         ctx.defer_fn(|act, _| {
+    ///             assert_eq!(act.0, [1]);
+    ///             System::current().stop();
+    ///         });
+    ///         self.0.push(1);
+    ///     }
+    /// }
+    /// # fn main() {
+    /// #     let sys = System::new();
+    /// #     sys.block_on(async { MyActor(Vec::new()).start() });
+    /// #     sys.run().unwrap();
+    /// # }
+    /// ```
+    fn defer_fn<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut A, &mut Self) + 'static,
+    {
+        self.spawn(crate::fut::ready(()).map(move |_, act, ctx| f(act, ctx)));
+    }
+
+    /// Asks for the spawned future being polled to be polled again after `dur`, for futures
+    /// which poll a resource that cannot wake them up, e.g. a library only offering a
+    /// readiness check.
+    ///
+    /// The future returns [`Poll::Pending`](std::task::Poll::Pending) after calling this
+    /// method. It is not polled again during the current poll of the context, and is polled
+    /// again no later than `dur` plus the granularity of the timer, or earlier if the actor is
+    /// woken up otherwise. [`Context`] arms a single timer for the shortest backoff requested
+    /// by its futures, instead of one timer per future.
+    fn backoff(&mut self, dur: Duration) {
+        self.run_later(dur, |_, _| {});
+    }
+
+    /// Links the actor with `other`, so that either of them exiting stops the other.
+    ///
+    /// Once one of the actors has stopped, the other one is notified through
+    /// [`Actor::linked_exit()`], which stops it unless overridden. If `other` is not running
+    /// anymore, this actor is notified right away. Links do not keep the actors alive.
+    ///
+    /// ```
+    /// # use actix::prelude::*;
+    /// struct Reader;
+    ///
+    /// impl Actor for Reader {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn stopped(&mut self, _: &mut Self::Context) {
+    ///         System::current().stop();
+    ///     }
+    /// }
+    ///
+    /// struct Writer;
+    ///
+    /// impl Actor for Writer {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// # fn main() {
+    /// #     let sys = System::new();
+    /// #     sys.block_on(async {
+    /// let writer = Writer.start();
+    /// let _reader = Reader::create(|ctx| {
+    ///     ctx.link(&writer);
+    ///     Reader
+    /// });
+    ///
+    /// // stopping the writer stops the reader as well
+    /// drop(writer);
+    /// #     });
+    /// #     sys.run().unwrap();
+    /// # }
+    /// ```
+    fn link<B>(&mut self, other: &Addr<B>)
+    where
+        B: Actor,
+        B::Context: AsyncContext<B>,
+    {
+        crate::address::link::<A, B>(self, other)
+    }
 }
 
 /// A handle to a spawned future.
@@ -473,3 +1254,176 @@ impl SpawnHandle {
         self.0
     }
 }
+
+/// A handle to a future queued with [`Context::wait_with_handle()`].
+///
+/// Can be used to cancel or promote the future while it is queued.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct WaitHandle(usize);
+
+impl WaitHandle {
+    /// Gets the next handle.
+    pub(crate) fn next(self) -> WaitHandle {
+        WaitHandle(self.0 + 1)
+    }
+}
+
+/// A handle to a group of spawned futures that can be cancelled together.
+///
+/// Created by [`AsyncContext::scope()`]. Cancelling a scope also cancels all of its
+/// nested scopes. Futures of a scope are polled by the actor's context like any other spawned
+/// future, so they are dropped as usual when the actor stops.
+#[derive(Clone)]
+pub struct ScopeHandle(Rc<ScopeInner>);
+
+struct ScopeInner {
+    cancelled: Cell<bool>,
+    parent: Option<ScopeHandle>,
+    waker: RefCell<Option<Waker>>,
+}
+
+impl fmt::Debug for ScopeHandle {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ScopeHandle")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+impl ScopeHandle {
+    pub(crate) fn new(parent: Option<ScopeHandle>) -> Self {
+        ScopeHandle(Rc::new(ScopeInner {
+            cancelled: Cell::new(false),
+            parent,
+            waker: RefCell::new(None),
+        }))
+    }
+
+    /// Creates a scope nested in this one.
+    pub fn scope(&self) -> ScopeHandle {
+        ScopeHandle::new(Some(self.clone()))
+    }
+
+    /// Spawns a future into the context as part of this scope.
+    ///
+    /// A future spawned into an already cancelled scope never runs.
+    pub fn spawn<A, C, F>(&self, ctx: &mut C, fut: F) -> SpawnHandle
+    where
+        A: Actor<Context = C>,
+        C: AsyncContext<A>,
+        F: ActorFuture<A, Output = ()> + 'static,
+    {
+        ctx.spawn(ActorScopedItem::new(fut, self.clone()))
+    }
+
+    /// Cancels every still pending future of this scope and its nested scopes.
+    ///
+    /// Can be called from within one of the scope's own futures.
+    pub fn cancel_all(&self) {
+        self.0.cancelled.set(true);
+        if let Some(waker) = self.0.waker.borrow_mut().take() {
+            waker.wake();
+        }
+    }
+
+    /// Checks if this scope or any of its parents has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.get()
+            || self
+                .0
+                .parent
+                .as_ref()
+                .map_or(false, ScopeHandle::is_cancelled)
+    }
+
+    /// Returns a guard which cancels this scope when dropped.
+    pub fn guard(&self) -> ScopeGuard {
+        ScopeGuard(self.clone())
+    }
+
+    /// Registers the actor's task in this scope and all of its parents to be woken on cancel.
+    pub(crate) fn register(&self, waker: &Waker) {
+        let mut slot = self.0.waker.borrow_mut();
+        if !slot.as_ref().map_or(false, |w| w.will_wake(waker)) {
+            *slot = Some(waker.clone());
+        }
+        drop(slot);
+
+        if let Some(ref parent) = self.0.parent {
+            parent.register(waker);
+        }
+    }
+}
+
+/// Cancels its scope when dropped.
+///
+/// Created by [`ScopeHandle::guard()`].
+#[derive(Debug)]
+#[must_use = "the scope is cancelled as soon as the guard is dropped"]
+pub struct ScopeGuard(ScopeHandle);
+
+impl ScopeGuard {
+    /// Returns the guarded scope.
+    pub fn handle(&self) -> &ScopeHandle {
+        &self.0
+    }
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        self.0.cancel_all();
+    }
+}
+
+/// A handle to a resource attached with [`AsyncContext::attach_resource()`].
+///
+/// Dropping the handle does not drop the resource, the context keeps owning it.
+pub struct ResourceHandle<R>(Rc<RefCell<Option<R>>>);
+
+impl<R> fmt::Debug for ResourceHandle<R> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ResourceHandle")
+            .field("attached", &self.is_attached())
+            .finish()
+    }
+}
+
+impl<R: 'static> ResourceHandle<R> {
+    pub(crate) fn new(res: R) -> (Self, Rc<dyn AttachedResource>) {
+        let slot = Rc::new(RefCell::new(Some(res)));
+        (ResourceHandle(Rc::clone(&slot)), slot)
+    }
+}
+
+impl<R> ResourceHandle<R> {
+    /// Takes the resource back from the context.
+    ///
+    /// Returns `None` if the resource was already dropped because the actor has stopped.
+    pub fn detach(self) -> Option<R> {
+        self.0.borrow_mut().take()
+    }
+
+    /// Checks if the resource is still owned by the context.
+    pub fn is_attached(&self) -> bool {
+        self.0.borrow().is_some()
+    }
+}
+
+/// Type-erased slot of an attached resource, as stored by the context.
+pub(crate) trait AttachedResource {
+    fn is_attached(&self) -> bool;
+
+    fn release(&self);
+}
+
+impl<R> AttachedResource for RefCell<Option<R>> {
+    fn is_attached(&self) -> bool {
+        self.borrow().is_some()
+    }
+
+    fn release(&self) {
+        // the slot must not be borrowed while the resource is dropped
+        let res = self.borrow_mut().take();
+        drop(res);
+    }
+}
@@ -0,0 +1,181 @@
+//! Actors behind trait objects.
+//!
+//! An actor type has to be known where it is started, which plugin hosts don't know. A plugin
+//! implements [`DynHandler`] for the messages the host sends instead, and the host starts the
+//! boxed handler with [`start_dyn()`], which wraps it in an actor and returns a [`Recipient`] of
+//! those messages.
+//!
+//! ```
+//! use actix::{actors::dynamic::{start_dyn, DynContext, DynHandler}, prelude::*};
+//!
+//! #[derive(Message)]
+//! #[rtype(result = "String")]
+//! struct Describe;
+//!
+//! struct Greeter {
+//!     name: String,
+//! }
+//!
+//! impl DynHandler<Describe> for Greeter {
+//!     fn handle(&mut self, _: Describe, _: &mut DynContext<'_, Describe>) -> String {
+//!         format!("hello from {}", self.name)
+//!     }
+//! }
+//!
+//! // a host only knows plugins as boxed factories
+//! type Plugin = Box<dyn FnOnce() -> Box<dyn DynHandler<Describe>>>;
+//!
+//! # #[actix::main]
+//! # async fn main() {
+//! let plugin: Plugin = Box::new(|| Box::new(Greeter { name: "greeter".to_owned() }));
+//! let recipient = start_dyn(move |_| plugin());
+//! assert_eq!(recipient.send(Describe).await.unwrap(), "hello from greeter");
+//! # }
+//! ```
+
+use std::{future::Future, time::Duration};
+
+use crate::{fut::wrap_future, prelude::*};
+
+/// Handler of messages `M` behind a trait object, started with [`start_dyn()`].
+///
+/// The lifecycle hooks mirror the ones of [`Actor`].
+pub trait DynHandler<M>: 'static
+where
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    /// Called when the actor is started, see [`Actor::started()`].
+    fn started(&mut self, ctx: &mut DynContext<'_, M>) {
+        let _ = ctx;
+    }
+
+    /// Handles a message.
+    fn handle(&mut self, msg: M, ctx: &mut DynContext<'_, M>) -> M::Result;
+
+    /// Called when the actor is about to stop, see [`Actor::stopping()`].
+    fn stopping(&mut self, ctx: &mut DynContext<'_, M>) -> Running {
+        let _ = ctx;
+        Running::Stop
+    }
+
+    /// Called when the actor has stopped, see [`Actor::stopped()`].
+    fn stopped(&mut self, ctx: &mut DynContext<'_, M>) {
+        let _ = ctx;
+    }
+}
+
+/// Context of a [`DynHandler`], exposing the commonly needed part of [`Context`].
+pub struct DynContext<'a, M>
+where
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    ctx: &'a mut Context<DynActor<M>>,
+}
+
+impl<M> DynContext<'_, M>
+where
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    /// Spawns a future into the context, see [`AsyncContext::spawn()`].
+    pub fn spawn<F>(&mut self, fut: F) -> SpawnHandle
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        self.ctx.spawn(wrap_future(fut))
+    }
+
+    /// Cancels a spawned future, see [`AsyncContext::cancel_future()`].
+    pub fn cancel_future(&mut self, handle: SpawnHandle) -> bool {
+        self.ctx.cancel_future(handle)
+    }
+
+    /// Sends a message to the handler itself, bypassing the mailbox capacity, see
+    /// [`AsyncContext::notify()`].
+    pub fn notify(&mut self, msg: M) {
+        self.ctx.notify(msg)
+    }
+
+    /// Sends a message to the handler itself after a delay, see
+    /// [`AsyncContext::notify_later()`].
+    pub fn notify_later(&mut self, msg: M, after: Duration) -> SpawnHandle {
+        self.ctx.notify_later(msg, after)
+    }
+
+    /// Returns a recipient of the handler.
+    pub fn recipient(&self) -> Recipient<M> {
+        self.ctx.address().recipient()
+    }
+
+    /// Returns the state of the actor.
+    pub fn state(&self) -> ActorState {
+        self.ctx.state()
+    }
+
+    /// Gracefully stops the actor, see [`ActorContext::stop()`].
+    pub fn stop(&mut self) {
+        self.ctx.stop()
+    }
+
+    /// Terminates the actor, see [`ActorContext::terminate()`].
+    pub fn terminate(&mut self) {
+        self.ctx.terminate()
+    }
+}
+
+/// Actor forwarding messages to a boxed [`DynHandler`].
+struct DynActor<M>
+where
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    handler: Box<dyn DynHandler<M>>,
+}
+
+impl<M> Actor for DynActor<M>
+where
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.handler.started(&mut DynContext { ctx })
+    }
+
+    fn stopping(&mut self, ctx: &mut Self::Context) -> Running {
+        self.handler.stopping(&mut DynContext { ctx })
+    }
+
+    fn stopped(&mut self, ctx: &mut Self::Context) {
+        self.handler.stopped(&mut DynContext { ctx })
+    }
+}
+
+impl<M> Handler<M> for DynActor<M>
+where
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    type Result = MessageResult<M>;
+
+    fn handle(&mut self, msg: M, ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.handler.handle(msg, &mut DynContext { ctx }))
+    }
+}
+
+/// Starts a [`DynHandler`] created by `factory` on the current arbiter, and returns a recipient
+/// of its messages.
+pub fn start_dyn<M, F>(factory: F) -> Recipient<M>
+where
+    M: Message + Send + 'static,
+    M::Result: Send,
+    F: FnOnce(&mut DynContext<'_, M>) -> Box<dyn DynHandler<M>> + 'static,
+{
+    DynActor::create(|ctx| DynActor {
+        handler: factory(&mut DynContext { ctx }),
+    })
+    .recipient()
+}
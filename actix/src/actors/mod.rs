@@ -1,3 +1,4 @@
 //! Helper actors
 
+pub mod dynamic;
 pub mod mocker;
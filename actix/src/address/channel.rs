@@ -1,8 +1,12 @@
 //! This is copy of [sync/mpsc/](https://github.com/rust-lang/futures-rs)
 
 use std::{
+    any::type_name,
+    collections::VecDeque,
     fmt,
     hash::{Hash, Hasher},
+    marker::PhantomData,
+    mem,
     pin::Pin,
     sync::{
         atomic::{
@@ -13,20 +17,37 @@ use std::{
     },
     task::{self, Poll},
     thread,
+    time::Duration,
 };
 
+use actix_rt::System;
 use futures_core::{stream::Stream, task::__internal::AtomicWaker};
-use parking_lot::Mutex;
-use tokio::sync::oneshot::{channel as oneshot_channel, Receiver as OneshotReceiver};
+use once_cell::sync::OnceCell;
+use parking_lot::{Mutex, RwLock};
 
 use super::{
-    envelope::{Envelope, ToEnvelope},
+    closed::{CloseWatch, Closed},
+    envelope::{Envelope, EnvelopeProxy, ToEnvelope},
+    limit::{RateLimit, RateLimitPolicy, TokenBucket},
     queue::Queue,
+    signal::{handle_each, SignalFn, Signals},
+    state::{StateStream, StateWatch},
+    tap::Tap,
     SendError,
 };
+#[cfg(feature = "testing")]
+use crate::fault::Faults;
+#[cfg(feature = "telemetry")]
+use crate::residency::{ResidencyHistogram, ResidencyTracker};
 use crate::{
-    actor::Actor,
+    actor::{Actor, ActorState, AsyncContext},
+    arbiter::SystemShutdown,
+    deadletter::{DeadLetterReason, DeadLetterSink},
     handler::{Handler, Message},
+    logging::ActorId,
+    mailbox::PressureLevel,
+    middleware::{self, Rejection},
+    queue::{oneshot, OneshotReceiver, OneshotSender},
 };
 
 pub trait Sender<M>: Send
@@ -46,6 +67,28 @@ where
 
     fn connected(&self) -> bool;
 
+    /// Polls whether the receiver has room for a message, registering `cx` to be woken once it
+    /// has. Senders without a bounded mailbox are always ready.
+    fn poll_ready(&self, _: &mut task::Context<'_>) -> Poll<()> {
+        Poll::Ready(())
+    }
+
+    /// Returns whether the sender was revoked by its producer.
+    fn revoked(&self) -> bool {
+        false
+    }
+
+    /// Returns the shutdown state of the system of the receiver, if it belongs to one.
+    fn system_shutdown(&self) -> Option<SystemShutdown> {
+        None
+    }
+
+    /// Returns the faults injected into the receiver, see [`crate::test::inject`].
+    #[cfg(feature = "testing")]
+    fn faults(&self) -> Option<Arc<Faults>> {
+        None
+    }
+
     /// Returns a downgraded sender, where the sender is downgraded into its weak counterpart.
     fn downgrade(&self) -> Box<dyn WeakSender<M> + Sync + 'static>;
 }
@@ -80,6 +123,23 @@ where
         (**self).connected()
     }
 
+    fn poll_ready(&self, cx: &mut task::Context<'_>) -> Poll<()> {
+        (**self).poll_ready(cx)
+    }
+
+    fn revoked(&self) -> bool {
+        (**self).revoked()
+    }
+
+    fn system_shutdown(&self) -> Option<SystemShutdown> {
+        (**self).system_shutdown()
+    }
+
+    #[cfg(feature = "testing")]
+    fn faults(&self) -> Option<Arc<Faults>> {
+        (**self).faults()
+    }
+
     fn downgrade(&self) -> Box<dyn WeakSender<M> + Sync> {
         (**self).downgrade()
     }
@@ -108,11 +168,14 @@ pub struct AddressSender<A: Actor> {
     // Handle to the task that is blocked on this sender. This handle is sent
     // to the receiver half in order to be notified when the sender becomes
     // unblocked.
-    sender_task: Arc<Mutex<SenderTask>>,
+    sender_task: Arc<Mutex<SenderTask<A>>>,
 
     // True if the sender might be blocked. This is an optimization to avoid
     // having to lock the mutex most of the time.
     maybe_parked: Arc<AtomicBool>,
+
+    // Observer of the messages sent by this sender and its clones.
+    tap: Option<Arc<dyn Tap>>,
 }
 
 impl<A: Actor> fmt::Debug for AddressSender<A> {
@@ -129,12 +192,14 @@ impl<A: Actor> fmt::Debug for AddressSender<A> {
 /// This is created by the `AddressSender::downgrade` method.
 pub struct WeakAddressSender<A: Actor> {
     inner: Weak<Inner<A>>,
+    tap: Option<Arc<dyn Tap>>,
 }
 
 impl<A: Actor> Clone for WeakAddressSender<A> {
     fn clone(&self) -> WeakAddressSender<A> {
         WeakAddressSender {
             inner: self.inner.clone(),
+            tap: self.tap.clone(),
         }
     }
 }
@@ -153,6 +218,7 @@ impl<A: Actor> PartialEq for WeakAddressSender<A> {
 
 impl<A: Actor> Eq for WeakAddressSender<A> {}
 
+#[allow(dead_code)]
 trait AssertKinds: Send + Sync + Clone {}
 
 /// The receiving end of a channel which implements the `Stream` trait.
@@ -181,13 +247,77 @@ struct Inner<A: Actor> {
     message_queue: Queue<Envelope<A>>,
 
     // Atomic, FIFO queue used to send parked task handles to the receiver.
-    parked_queue: Queue<Arc<Mutex<SenderTask>>>,
+    parked_queue: Queue<Arc<Mutex<SenderTask<A>>>>,
 
     // Number of senders in existence.
     num_senders: AtomicUsize,
 
     // Handle to the receiver's task.
     recv_task: AtomicWaker,
+
+    // Set once the receiver was woken up. Cleared by the receiver right before
+    // it checks the queue one last time and parks, so senders only wake up the
+    // receiver once per park.
+    wake_pending: AtomicBool,
+
+    // Number of wakeups skipped because the receiver was already woken up.
+    suppressed_wakeups: AtomicUsize,
+
+    // State of the actor, published by its context.
+    actor_state: StateWatch,
+
+    // Tasks waiting for the channel to close.
+    close_watch: Arc<CloseWatch>,
+
+    // True if a rate limit is set. This is an optimization to avoid having to
+    // lock the mutex on every send.
+    rate_limited: AtomicBool,
+
+    // Rate limit of the actor.
+    rate_limit: Mutex<Option<TokenBucket>>,
+
+    // Set by `interrupt()`, cleared before each message unless the interrupt is sticky.
+    interrupted: AtomicBool,
+
+    // Keeps `interrupted` set until the actor clears it.
+    interrupt_sticky: AtomicBool,
+
+    // Context task to wake on `interrupt()`, registered on every poll of the context.
+    interrupt_task: AtomicWaker,
+
+    // Sender of the actor which took over the channel with `handoff()`. Set before the channel
+    // is closed and locked until the queued messages are forwarded, so that the messages which
+    // the closed channel rejects reach the new actor after them.
+    redirect: RwLock<Option<AddressSender<A>>>,
+
+    // System which created the channel, whose dead letters record the undelivered messages.
+    system: Option<usize>,
+
+    // Id of the actor, set by its context.
+    actor_id: OnceCell<ActorId>,
+
+    // Time the messages spent in the queue.
+    #[cfg(feature = "telemetry")]
+    residency: ResidencyTracker,
+
+    // Shutdown state of the system which created the channel.
+    shutdown: Option<SystemShutdown>,
+
+    // Signals sent to the actor, see `Addr::signal()`.
+    signals: Arc<Signals<A>>,
+
+    // Number of queued messages at which the mailbox pressure falls back and rises. The
+    // pressure is not tracked while the high watermark is zero.
+    low_watermark: AtomicUsize,
+    high_watermark: AtomicUsize,
+
+    // Set once the number of queued messages reached the high watermark, cleared once it
+    // dropped back to the low watermark.
+    pressure: AtomicBool,
+
+    // Faults injected into the channel, see `test::inject()`.
+    #[cfg(feature = "testing")]
+    faults: RwLock<Option<Arc<Faults>>>,
 }
 
 // Struct representation of `Inner::state`.
@@ -220,18 +350,39 @@ const MAX_CAPACITY: usize = !(OPEN_MASK);
 // a channel. This is because each sender gets a guaranteed slot.
 const MAX_BUFFER: usize = MAX_CAPACITY >> 1;
 
+// Fails the build for signals which are not zero-sized, once `OK` is evaluated
+// for their type.
+struct AssertZst<M>(PhantomData<M>);
+
+impl<M> AssertZst<M> {
+    const OK: () = assert!(mem::size_of::<M>() == 0, "Signals must be zero-sized");
+}
+
 // Sent to the consumer to wake up blocked producers
-#[derive(Debug)]
-struct SenderTask {
+struct SenderTask<A: Actor> {
     task: Option<task::Waker>,
     is_parked: bool,
+    // Envelopes sent while the sender was parked, queued by the receiver in
+    // order as capacity becomes available. Bounded by the mailbox capacity.
+    backlog: VecDeque<Envelope<A>>,
+}
+
+impl<A: Actor> fmt::Debug for SenderTask<A> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("SenderTask")
+            .field("task", &self.task)
+            .field("is_parked", &self.is_parked)
+            .field("backlog", &self.backlog.len())
+            .finish()
+    }
 }
 
-impl SenderTask {
+impl<A: Actor> SenderTask<A> {
     fn new() -> Self {
         SenderTask {
             task: None,
             is_parked: false,
+            backlog: VecDeque::new(),
         }
     }
 
@@ -272,12 +423,34 @@ pub fn channel<A: Actor>(buffer: usize) -> (AddressSender<A>, AddressReceiver<A>
         parked_queue: Queue::new(),
         num_senders: AtomicUsize::new(1),
         recv_task: AtomicWaker::new(),
+        wake_pending: AtomicBool::new(false),
+        suppressed_wakeups: AtomicUsize::new(0),
+        actor_state: StateWatch::new(),
+        close_watch: Arc::new(CloseWatch::new()),
+        rate_limited: AtomicBool::new(false),
+        rate_limit: Mutex::new(None),
+        interrupted: AtomicBool::new(false),
+        interrupt_sticky: AtomicBool::new(false),
+        interrupt_task: AtomicWaker::new(),
+        redirect: RwLock::new(None),
+        system: System::try_current().map(|sys| sys.id()),
+        actor_id: OnceCell::new(),
+        #[cfg(feature = "telemetry")]
+        residency: ResidencyTracker::new(),
+        shutdown: SystemShutdown::current(),
+        signals: Arc::new(Signals::new()),
+        low_watermark: AtomicUsize::new(0),
+        high_watermark: AtomicUsize::new(0),
+        pressure: AtomicBool::new(false),
+        #[cfg(feature = "testing")]
+        faults: RwLock::new(Faults::for_type::<A>()),
     });
 
     let tx = AddressSender {
         inner: Arc::clone(&inner),
         sender_task: Arc::new(Mutex::new(SenderTask::new())),
         maybe_parked: Arc::new(AtomicBool::new(false)),
+        tap: None,
     };
 
     let rx = AddressReceiver { inner };
@@ -296,7 +469,41 @@ impl<A: Actor> AddressSender<A> {
         let curr = self.inner.state.load(SeqCst);
         let state = decode_state(curr);
 
-        state.is_open
+        state.is_open || self.redirect().map_or(false, |target| target.connected())
+    }
+
+    /// Subscribes to state transitions of the actor.
+    pub fn state_stream(&self) -> StateStream {
+        self.inner.actor_state.subscribe()
+    }
+
+    /// Requests the actor to interrupt its current work, and wakes up its context.
+    pub fn interrupt(&self) {
+        self.inner.interrupted.store(true, SeqCst);
+        self.inner.interrupt_task.wake();
+    }
+
+    /// Counts a signal of `M` and wakes up the context for the first pending one
+    pub fn signal<M>(&self)
+    where
+        M: Message + Default + 'static,
+        A: Handler<M>,
+        A::Context: AsyncContext<A>,
+    {
+        let () = AssertZst::<M>::OK;
+        if !decode_state(self.inner.state.load(SeqCst)).is_open {
+            return;
+        }
+        if self.inner.signals.raise::<M>(handle_each::<A, M>) {
+            // contexts which do not watch for interrupts park on the receiver
+            self.inner.interrupt_task.wake();
+            self.inner.recv_task.wake();
+        }
+    }
+
+    /// Returns a future which resolves once the channel is closed.
+    pub fn closed(&self) -> Closed {
+        Closed::new(Arc::clone(&self.inner.close_watch))
     }
 
     /// Attempts to send a message on this `Sender<A>` with blocking.
@@ -307,11 +514,70 @@ impl<A: Actor> AddressSender<A> {
         A: Handler<M>,
         A::Context: ToEnvelope<A, M>,
         M::Result: Send,
-        M: Message + Send,
+        M: Message + Send + 'static,
     {
-        // If the sender is currently blocked, reject the message
-        if !self.poll_unparked(false, None).is_ready() {
-            return Err(SendError::Full(msg));
+        self.send_with(msg, |env| env)
+    }
+
+    /// Same as [`send`](Self::send), applying `wrap` to the envelope before queueing it.
+    pub(crate) fn send_with<M, F>(
+        &self,
+        msg: M,
+        wrap: F,
+    ) -> Result<OneshotReceiver<M::Result>, SendError<M>>
+    where
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+        M::Result: Send,
+        M: Message + Send + 'static,
+        F: FnOnce(Envelope<A>) -> Envelope<A>,
+    {
+        self.send_packed(msg, |msg, tx| {
+            wrap(<A::Context as ToEnvelope<A, M>>::pack(msg, tx))
+        })
+    }
+
+    /// Same as [`send`](Self::send), packing the message into an envelope with `pack`.
+    pub(crate) fn send_packed<M, F>(
+        &self,
+        msg: M,
+        pack: F,
+    ) -> Result<OneshotReceiver<M::Result>, SendError<M>>
+    where
+        M: Message + 'static,
+        F: FnOnce(M, Option<OneshotSender<M::Result>>) -> Envelope<A>,
+    {
+        if let Some(rejection) = self.reject(&msg, true) {
+            return Err(SendError::Rejected(msg, rejection));
+        }
+        if !self.inner.admit() {
+            return Err(SendError::RateLimited(msg));
+        }
+
+        // the reply sender of a dropped message is dropped with it, failing the request
+        #[cfg(feature = "testing")]
+        if self.inner.drop_send() {
+            return Ok(oneshot().1);
+        }
+
+        // If the sender is currently blocked, the message waits in its backlog,
+        // behind the messages sent before it. The backlog holds up to the
+        // capacity of the mailbox, beyond that the sender is full.
+        if self.maybe_parked.load(Relaxed) {
+            let mut task = self.sender_task.lock();
+            if task.is_parked {
+                if task.backlog.len() >= self.inner.buffer.load(Relaxed) {
+                    return Err(SendError::Full(msg));
+                }
+                self.record(&msg, true);
+                let (tx, rx) = oneshot();
+                let env = pack(msg, Some(tx));
+                #[cfg(feature = "telemetry")]
+                let env = env.track();
+                task.backlog.push_back(env);
+                return Ok(rx);
+            }
+            self.maybe_parked.store(false, Relaxed);
         }
 
         // First, increment the number of messages contained by the channel.
@@ -327,7 +593,15 @@ impl<A: Actor> AddressSender<A> {
                 let buffer = self.inner.buffer.load(Relaxed);
                 buffer != 0 && num_messages >= buffer
             }
-            None => return Err(SendError::Closed(msg)),
+            None => {
+                return match self.redirect() {
+                    Some(target) => target.send_packed(msg, pack),
+                    None => {
+                        self.dead_letter::<M>(true);
+                        Err(SendError::Closed(msg))
+                    }
+                }
+            }
         };
 
         // If the channel has reached capacity, then the sender task needs to
@@ -335,8 +609,11 @@ impl<A: Actor> AddressSender<A> {
         if park_self {
             self.park();
         }
-        let (tx, rx) = oneshot_channel();
-        let env = <A::Context as ToEnvelope<A, M>>::pack(msg, Some(tx));
+        self.record(&msg, true);
+        let (tx, rx) = oneshot();
+        let env = pack(msg, Some(tx));
+        #[cfg(feature = "telemetry")]
+        let env = env.track();
         self.queue_push_and_signal(env);
         Ok(rx)
     }
@@ -349,6 +626,51 @@ impl<A: Actor> AddressSender<A> {
         M::Result: Send,
         M: Message + Send + 'static,
     {
+        self.try_send_with(msg, park, |env| env)
+    }
+
+    /// Same as [`try_send`](Self::try_send), applying `wrap` to the envelope before queueing it.
+    pub(crate) fn try_send_with<M, F>(
+        &self,
+        msg: M,
+        park: bool,
+        wrap: F,
+    ) -> Result<(), SendError<M>>
+    where
+        A: Handler<M>,
+        <A as Actor>::Context: ToEnvelope<A, M>,
+        M::Result: Send,
+        M: Message + Send + 'static,
+        F: FnOnce(Envelope<A>) -> Envelope<A>,
+    {
+        self.try_send_packed(msg, park, |msg| {
+            wrap(<A::Context as ToEnvelope<A, M>>::pack(msg, None))
+        })
+    }
+
+    /// Same as [`try_send`](Self::try_send), packing the message into an envelope with `pack`.
+    pub(crate) fn try_send_packed<M, F>(
+        &self,
+        msg: M,
+        park: bool,
+        pack: F,
+    ) -> Result<(), SendError<M>>
+    where
+        M: Message + 'static,
+        F: FnOnce(M) -> Envelope<A>,
+    {
+        if let Some(rejection) = self.reject(&msg, false) {
+            return Err(SendError::Rejected(msg, rejection));
+        }
+        if !self.inner.admit() {
+            return Err(SendError::RateLimited(msg));
+        }
+
+        #[cfg(feature = "testing")]
+        if self.inner.drop_send() {
+            return Ok(());
+        }
+
         // If the sender is currently blocked, reject the message
         if !self.poll_unparked(false, None).is_ready() {
             return Err(SendError::Full(msg));
@@ -360,14 +682,22 @@ impl<A: Actor> AddressSender<A> {
                 let buffer = self.inner.buffer.load(Relaxed);
                 buffer != 0 && num_messages >= buffer
             }
-            None => return Err(SendError::Closed(msg)),
+            None => {
+                return match self.redirect() {
+                    Some(target) => target.try_send_packed(msg, park, pack),
+                    None => {
+                        self.dead_letter::<M>(false);
+                        Err(SendError::Closed(msg))
+                    }
+                }
+            }
         };
 
         if park_self && park {
             self.park();
         }
-        let env = <A::Context as ToEnvelope<A, M>>::pack(msg, None);
-        self.queue_push_and_signal(env);
+        self.record(&msg, false);
+        self.queue_push_and_signal(pack(msg));
         Ok(())
     }
 
@@ -379,16 +709,86 @@ impl<A: Actor> AddressSender<A> {
         A: Handler<M>,
         <A as Actor>::Context: ToEnvelope<A, M>,
         M::Result: Send,
-        M: Message + Send,
+        M: Message + Send + 'static,
+    {
+        self.do_send_with(msg, |env| env)
+    }
+
+    /// Same as [`do_send`](Self::do_send), applying `wrap` to the envelope before queueing it.
+    pub(crate) fn do_send_with<M, F>(&self, msg: M, wrap: F) -> Result<(), SendError<M>>
+    where
+        A: Handler<M>,
+        <A as Actor>::Context: ToEnvelope<A, M>,
+        M::Result: Send,
+        M: Message + Send + 'static,
+        F: FnOnce(Envelope<A>) -> Envelope<A>,
     {
+        self.do_send_packed(msg, |msg| {
+            wrap(<A::Context as ToEnvelope<A, M>>::pack(msg, None))
+        })
+    }
+
+    /// Same as [`do_send`](Self::do_send), packing the message into an envelope with `pack`.
+    pub(crate) fn do_send_packed<M, F>(&self, msg: M, pack: F) -> Result<(), SendError<M>>
+    where
+        M: Message + 'static,
+        F: FnOnce(M) -> Envelope<A>,
+    {
+        if let Some(rejection) = self.reject(&msg, false) {
+            return Err(SendError::Rejected(msg, rejection));
+        }
+        if !self.inner.admit() {
+            return Err(SendError::RateLimited(msg));
+        }
+
+        #[cfg(feature = "testing")]
+        if self.inner.drop_send() {
+            return Ok(());
+        }
+
+        // The backlog of a blocked sender is queued first, so the message does not overtake it.
+        if self.maybe_parked.load(Relaxed) {
+            let mut task = self.sender_task.lock();
+            while let Some(env) = task.backlog.pop_front() {
+                if self.inc_num_messages().is_none() {
+                    // closed, the requests of the backlog fail once it is dropped, unless the
+                    // channel was handed off
+                    let backlog = mem::take(&mut task.backlog);
+                    drop(task);
+                    return match self.redirect() {
+                        Some(target) => {
+                            for env in Some(env).into_iter().chain(backlog) {
+                                target.push_envelope(env);
+                            }
+                            target.do_send_packed(msg, pack)
+                        }
+                        None => {
+                            for env in Some(env).into_iter().chain(backlog) {
+                                self.inner.discard(env);
+                            }
+                            self.dead_letter::<M>(false);
+                            Err(SendError::Closed(msg))
+                        }
+                    };
+                }
+                self.queue_push_and_signal(env);
+            }
+        }
+
         if self.inc_num_messages().is_none() {
-            Err(SendError::Closed(msg))
+            match self.redirect() {
+                Some(target) => target.do_send_packed(msg, pack),
+                None => {
+                    self.dead_letter::<M>(false);
+                    Err(SendError::Closed(msg))
+                }
+            }
         } else {
             // If inc_num_messages returned Some(park_self), then the mailbox is still active.
             // We ignore the boolean (indicating to park and wait) in the Some, and queue the
             // message regardless.
-            let env = <A::Context as ToEnvelope<A, M>>::pack(msg, None);
-            self.queue_push_and_signal(env);
+            self.record(&msg, false);
+            self.queue_push_and_signal(pack(msg));
             Ok(())
         }
     }
@@ -397,42 +797,96 @@ impl<A: Actor> AddressSender<A> {
     pub fn downgrade(&self) -> WeakAddressSender<A> {
         WeakAddressSender {
             inner: Arc::downgrade(&self.inner),
+            tap: self.tap.clone(),
+        }
+    }
+
+    /// Returns a sender of the same channel which reports the messages it sends to `tap`.
+    pub(crate) fn tapped(&self, tap: Arc<dyn Tap>) -> Self {
+        let mut tx = self.clone();
+        tx.tap = Some(tap);
+        tx
+    }
+
+    fn record<M: 'static>(&self, msg: &M, ask: bool) {
+        if let Some(tap) = &self.tap {
+            tap.record(msg, type_name::<M>(), ask);
         }
     }
 
+    /// Runs the `before()` hooks of the middleware of `M`, recording a rejected message as a
+    /// dead letter.
+    fn reject<M: Message + 'static>(&self, msg: &M, ask: bool) -> Option<Rejection> {
+        let rejection = middleware::before(self.inner.system, msg)?;
+        self.inner
+            .dead_letters()
+            .record(type_name::<M>(), ask, DeadLetterReason::Rejected);
+        Some(rejection)
+    }
+
+    /// Records a message of type `M` which the closed channel rejected as a dead letter.
+    fn dead_letter<M: 'static>(&self, ask: bool) {
+        self.inner
+            .dead_letters()
+            .record(type_name::<M>(), ask, DeadLetterReason::Closed);
+    }
+
+    /// Returns the shutdown state of the system which created the channel.
+    pub(crate) fn system_shutdown(&self) -> Option<SystemShutdown> {
+        self.inner.shutdown.clone()
+    }
+
+    /// Returns the sink recording the undelivered messages of the channel.
+    pub(crate) fn dead_letters(&self) -> DeadLetterSink {
+        self.inner.dead_letters()
+    }
+
+    /// Returns the faults injected into the channel.
+    #[cfg(feature = "testing")]
+    pub(crate) fn faults(&self) -> Option<Arc<Faults>> {
+        self.inner.faults.read().clone()
+    }
+
+    /// Injects `faults` into the channel, replacing the previous ones.
+    #[cfg(feature = "testing")]
+    pub(crate) fn set_faults(&self, faults: Arc<Faults>) {
+        *self.inner.faults.write() = Some(faults);
+    }
+
+    /// Queues an envelope which is not sent on behalf of a message.
+    ///
+    /// Like [`do_send`](Self::do_send), the envelope is queued even if the mailbox is full.
+    /// Returns `false` if the channel is closed.
+    pub(crate) fn push_envelope(&self, env: Envelope<A>) -> bool {
+        if self.inc_num_messages().is_none() {
+            self.redirect()
+                .map_or(false, |target| target.push_envelope(env))
+        } else {
+            self.queue_push_and_signal(env);
+            true
+        }
+    }
+
+    /// Returns a sender of the actor which took over the channel, once it is closed.
+    ///
+    /// Waits for a handoff in progress to forward the queued messages first.
+    fn redirect(&self) -> Option<AddressSender<A>> {
+        let target = self.inner.redirect.read().clone()?;
+        Some(match self.tap {
+            Some(ref tap) => target.tapped(Arc::clone(tap)),
+            None => target,
+        })
+    }
+
     // Push message to the queue and signal to the receiver
     fn queue_push_and_signal(&self, msg: Envelope<A>) {
-        // Push the message onto the message queue
-        self.inner.message_queue.push(msg);
-
-        // Signal to the receiver that a message has been enqueued. If the
-        // receiver is parked, this will unpark the task.
-        self.inner.recv_task.wake();
+        self.inner.push_and_signal(msg)
     }
 
     // Increment the number of queued messages. Returns if the sender should
     // block.
     fn inc_num_messages(&self) -> Option<usize> {
-        let mut curr = self.inner.state.load(SeqCst);
-        loop {
-            let mut state = decode_state(curr);
-            if !state.is_open {
-                return None;
-            }
-            state.num_messages += 1;
-
-            let next = encode_state(&state);
-            match self
-                .inner
-                .state
-                .compare_exchange(curr, next, SeqCst, SeqCst)
-            {
-                Ok(_) => {
-                    return Some(state.num_messages);
-                }
-                Err(actual) => curr = actual,
-            }
-        }
+        self.inner.inc_num_messages()
     }
 
     // TODO: Not sure about this one, I modified code to match the futures one, might still be buggy
@@ -451,6 +905,14 @@ impl<A: Actor> AddressSender<A> {
         self.maybe_parked.store(state.is_open, Relaxed);
     }
 
+    /// Polls whether the sender is unparked, registering `cx` to be woken once it is.
+    ///
+    /// A sender is parked once a message it queued filled the mailbox, until the receiver
+    /// takes it out.
+    pub(crate) fn poll_ready(&self, cx: &mut task::Context<'_>) -> Poll<()> {
+        self.poll_unparked(true, Some(cx))
+    }
+
     fn poll_unparked(&self, do_park: bool, cx: Option<&mut task::Context<'_>>) -> Poll<()> {
         // First check the `maybe_parked` variable. This avoids acquiring the
         // lock in most cases
@@ -511,10 +973,21 @@ where
         self.connected()
     }
 
+    fn system_shutdown(&self) -> Option<SystemShutdown> {
+        self.system_shutdown()
+    }
+
+    #[cfg(feature = "testing")]
+    fn faults(&self) -> Option<Arc<Faults>> {
+        self.faults()
+    }
+
+    fn poll_ready(&self, cx: &mut task::Context<'_>) -> Poll<()> {
+        AddressSender::poll_ready(self, cx)
+    }
+
     fn downgrade(&self) -> Box<dyn WeakSender<M> + Sync + 'static> {
-        Box::new(WeakAddressSender {
-            inner: Arc::downgrade(&self.inner),
-        })
+        Box::new(self.downgrade())
     }
 }
 
@@ -544,6 +1017,7 @@ impl<A: Actor> Clone for AddressSender<A> {
                     inner: Arc::clone(&self.inner),
                     sender_task: Arc::new(Mutex::new(SenderTask::new())),
                     maybe_parked: Arc::new(AtomicBool::new(false)),
+                    tap: self.tap.clone(),
                 };
             }
 
@@ -588,7 +1062,11 @@ impl<A: Actor> WeakAddressSender<A> {
     ///
     /// Returns [`None`] if the actor has since been dropped.
     pub fn upgrade(&self) -> Option<AddressSender<A>> {
-        Weak::upgrade(&self.inner).map(|inner| AddressSenderProducer { inner }.sender())
+        Weak::upgrade(&self.inner).map(|inner| {
+            let mut tx = AddressSenderProducer { inner }.sender();
+            tx.tap = self.tap.clone();
+            tx
+        })
     }
 }
 
@@ -617,6 +1095,14 @@ where
 // ===== impl SenderProducer =====
 //
 //
+impl<A: Actor> Clone for AddressSenderProducer<A> {
+    fn clone(&self) -> Self {
+        AddressSenderProducer {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
 impl<A: Actor> AddressSenderProducer<A> {
     /// Are any senders connected
     pub fn connected(&self) -> bool {
@@ -638,12 +1124,90 @@ impl<A: Actor> AddressSenderProducer<A> {
 
         // wake up all
         if cap > buffer {
-            while let Some(task) = unsafe { self.inner.parked_queue.pop_spin() } {
-                task.lock().notify();
-            }
+            self.inner.unpark_all();
         }
     }
 
+    /// Handle the signals of `M` with `dispatch`
+    pub(crate) fn set_signal_dispatch<M: 'static>(&self, dispatch: SignalFn<A>) {
+        self.inner.signals.set_dispatch::<M>(dispatch);
+    }
+
+    /// Set the watermarks of the mailbox pressure, `high == 0` disables it
+    pub fn set_watermarks(&self, low: usize, high: usize) {
+        assert!(
+            high == 0 || low < high,
+            "Low watermark must be below the high one"
+        );
+        self.inner.low_watermark.store(low, SeqCst);
+        self.inner.high_watermark.store(high, SeqCst);
+        if high == 0 {
+            self.inner.pressure.store(false, SeqCst);
+        } else {
+            let num_messages = decode_state(self.inner.state.load(SeqCst)).num_messages;
+            self.inner.check_rising(num_messages);
+            self.inner.check_falling(num_messages);
+        }
+    }
+
+    /// Current pressure level of the mailbox
+    pub fn pressure(&self) -> PressureLevel {
+        self.inner.pressure_level()
+    }
+
+    /// Set or remove the rate limit of the actor
+    pub fn set_rate_limit(&self, limit: Option<RateLimit>) {
+        *self.inner.rate_limit.lock() = limit.map(TokenBucket::new);
+        self.inner.rate_limited.store(limit.is_some(), SeqCst);
+    }
+
+    /// Number of receiver wakeups skipped because the receiver was already woken up
+    pub fn suppressed_wakeups(&self) -> usize {
+        self.inner.suppressed_wakeups.load(Relaxed)
+    }
+
+    /// Histogram of the time messages were queued
+    #[cfg(feature = "telemetry")]
+    pub(crate) fn residency(&self) -> ResidencyHistogram {
+        self.inner.residency.histogram()
+    }
+
+    /// Warn once messages are queued for longer than `threshold`
+    #[cfg(feature = "telemetry")]
+    pub(crate) fn set_residency_warning(&self, threshold: Option<Duration>) {
+        self.inner.residency.set_threshold(threshold)
+    }
+
+    /// Whether an interrupt was requested
+    pub fn interrupted(&self) -> bool {
+        self.inner.interrupted.load(SeqCst)
+    }
+
+    /// Clear a requested interrupt
+    pub fn clear_interrupt(&self) {
+        self.inner.interrupted.store(false, SeqCst);
+    }
+
+    /// Register the context task to be woken up by `interrupt()`
+    pub(crate) fn register_interrupt(&self, waker: &task::Waker) {
+        self.inner.interrupt_task.register(waker);
+    }
+
+    /// Keep requested interrupts across messages until they are cleared
+    pub fn set_interrupt_sticky(&self, sticky: bool) {
+        self.inner.interrupt_sticky.store(sticky, SeqCst);
+    }
+
+    /// Set the id of the actor, recorded with its dead letters
+    pub(crate) fn set_actor_id(&self, id: ActorId) {
+        let _ = self.inner.actor_id.set(id);
+    }
+
+    /// Publish actor state to state stream subscribers
+    pub fn publish_state(&self, state: ActorState) {
+        self.inner.actor_state.publish(state);
+    }
+
     /// Get sender side of the channel
     pub fn sender(&self) -> AddressSender<A> {
         // this code same as Sender::clone
@@ -666,6 +1230,7 @@ impl<A: Actor> AddressSenderProducer<A> {
                     inner: Arc::clone(&self.inner),
                     sender_task: Arc::new(Mutex::new(SenderTask::new())),
                     maybe_parked: Arc::new(AtomicBool::new(false)),
+                    tap: None,
                 };
             }
 
@@ -680,6 +1245,108 @@ impl<A: Actor> AddressSenderProducer<A> {
 //
 //
 impl<A: Actor> AddressReceiver<A> {
+    /// Handles the pending signals sent to the actor
+    pub(crate) fn dispatch_signals(&self, act: &mut A, ctx: &mut A::Context)
+    where
+        A::Context: AsyncContext<A>,
+    {
+        self.inner.signals.dispatch(act, ctx);
+    }
+
+    /// Returns the signals of the actor if any is pending, for contexts owning the receiver
+    pub(crate) fn raised_signals(&self) -> Option<Arc<Signals<A>>> {
+        self.inner
+            .signals
+            .is_raised()
+            .then(|| Arc::clone(&self.inner.signals))
+    }
+
+    /// Counts a message taken out of the channel for its handler, panicking if a fault was
+    /// injected for it. Envelopes which don't carry a message are not counted.
+    #[cfg(feature = "testing")]
+    pub(crate) fn dispatch_faults(&self, env: &Envelope<A>) {
+        if env.message_info().is_none() {
+            return;
+        }
+        if let Some(faults) = self.inner.faults.read().clone() {
+            faults.dispatch();
+        }
+    }
+
+    /// Closes the channel, failing the messages which are still queued.
+    ///
+    /// Senders are rejected from now on, as if the receiver was dropped.
+    pub(crate) fn close(&mut self) {
+        self.inner.set_closed();
+        self.inner.close_watch.close();
+
+        // Wake up any threads waiting as they'll see that we've closed the
+        // channel and will continue on their merry way. Their backlogs are
+        // dropped, failing the requests.
+        while let Some(task) = unsafe { self.inner.parked_queue.pop_spin() } {
+            self.inner.unpark(task);
+        }
+
+        // Drain the channel of all pending messages
+        loop {
+            match self.next_message() {
+                Poll::Ready(Some(env)) => self.inner.discard(env),
+                Poll::Ready(None) => break,
+                Poll::Pending => {
+                    let state = decode_state(self.inner.state.load(SeqCst));
+
+                    // If the channel is closed, then there is no need to park.
+                    if state.is_closed() {
+                        break;
+                    }
+
+                    // TODO: Spinning isn't ideal, it might be worth
+                    // investigating using a condvar or some other strategy
+                    // here. That said, if this case is hit, then another thread
+                    // is about to push the value into the queue and this isn't
+                    // the only spinlock in the impl right now.
+                    thread::yield_now();
+                }
+            }
+        }
+    }
+
+    /// Closes the channel, forwarding the messages which are still queued to `target` in order.
+    ///
+    /// Senders are redirected to `target` from now on. Their messages which race with the
+    /// handoff wait for the queued ones to be forwarded, so they don't overtake them.
+    pub(crate) fn handoff(&mut self, target: AddressSender<A>) {
+        assert!(
+            !Arc::ptr_eq(&self.inner, &target.inner),
+            "an actor can't hand off its mailbox to itself"
+        );
+        let mut redirect = self.inner.redirect.write();
+        *redirect = Some(target.clone());
+        self.inner.set_closed();
+
+        // messages counted before the channel was closed are about to be pushed
+        loop {
+            match unsafe { self.inner.message_queue.pop_spin() } {
+                Some(env) => {
+                    self.dec_num_messages();
+                    target.push_envelope(env);
+                }
+                None if decode_state(self.inner.state.load(SeqCst)).is_closed() => break,
+                None => thread::yield_now(),
+            }
+        }
+
+        // backlogs of blocked senders come after the messages they queued
+        while let Some(task) = unsafe { self.inner.parked_queue.pop_spin() } {
+            let mut sender = task.lock();
+            let backlog = mem::take(&mut sender.backlog);
+            sender.notify();
+            drop(sender);
+            for env in backlog {
+                target.push_envelope(env);
+            }
+        }
+    }
     /// Returns whether any senders are still connected.
     pub fn connected(&self) -> bool {
         self.inner.num_senders.load(SeqCst) != 0
@@ -700,8 +1367,38 @@ impl<A: Actor> AddressReceiver<A> {
 
         // wake up all
         if cap > buffer {
-            while let Some(task) = unsafe { self.inner.parked_queue.pop_spin() } {
-                task.lock().notify();
+            self.inner.unpark_all();
+        }
+    }
+
+    /// Clears a requested interrupt before the next message is handled, unless it is sticky.
+    pub(crate) fn reset_interrupt(&self) {
+        if !self.inner.interrupt_sticky.load(SeqCst) {
+            self.inner.interrupted.store(false, SeqCst);
+        }
+    }
+
+    /// Returns the time until the rate limit allows receiving the next message.
+    ///
+    /// Always `None` unless the rate limit policy is [`RateLimitPolicy::Delay`].
+    pub(crate) fn rate_limit_delay(&self) -> Option<Duration> {
+        if !self.inner.rate_limited.load(SeqCst) {
+            return None;
+        }
+        match *self.inner.rate_limit.lock() {
+            Some(ref mut bucket) if bucket.policy() == RateLimitPolicy::Delay => bucket.delay(),
+            _ => None,
+        }
+    }
+
+    /// Takes a rate limit token for a received message.
+    pub(crate) fn rate_limit_consume(&self) {
+        if !self.inner.rate_limited.load(SeqCst) {
+            return;
+        }
+        if let Some(ref mut bucket) = *self.inner.rate_limit.lock() {
+            if bucket.policy() == RateLimitPolicy::Delay {
+                bucket.consume();
             }
         }
     }
@@ -728,6 +1425,7 @@ impl<A: Actor> AddressReceiver<A> {
                     inner: Arc::clone(&self.inner),
                     sender_task: Arc::new(Mutex::new(SenderTask::new())),
                     maybe_parked: Arc::new(AtomicBool::new(false)),
+                    tap: None,
                 };
             }
 
@@ -753,6 +1451,14 @@ impl<A: Actor> AddressReceiver<A> {
                 // Decrement number of messages
                 self.dec_num_messages();
 
+                #[cfg(feature = "telemetry")]
+                self.inner.residency.dequeued::<A>(
+                    msg.stamp(),
+                    msg.message_info().map(|(ty, _)| ty),
+                    decode_state(self.inner.state.load(SeqCst)).num_messages,
+                    self.inner.actor_id.get().copied(),
+                );
+
                 Poll::Ready(Some(msg))
             }
             None => {
@@ -778,7 +1484,7 @@ impl<A: Actor> AddressReceiver<A> {
     // Unpark a single task handle if there is one pending in the parked queue
     fn unpark_one(&mut self) {
         if let Some(task) = unsafe { self.inner.parked_queue.pop_spin() } {
-            task.lock().notify();
+            self.inner.unpark(task);
         }
     }
 
@@ -786,7 +1492,14 @@ impl<A: Actor> AddressReceiver<A> {
         // OPEN_MASK is highest bit, so it's unaffected by subtraction
         // unless there's underflow, and we know there's no underflow
         // because number of messages at this point is always > 0.
-        self.inner.state.fetch_sub(1, SeqCst);
+        let prev = self.inner.state.fetch_sub(1, SeqCst);
+        self.inner
+            .check_falling(decode_state(prev).num_messages - 1);
+    }
+
+    /// Current pressure level of the mailbox
+    pub(crate) fn pressure(&self) -> PressureLevel {
+        self.inner.pressure_level()
     }
 }
 
@@ -800,6 +1513,9 @@ impl<A: Actor> Stream for AddressReceiver<A> {
             Poll::Pending => {
                 // There are no messages to read, in this case, park.
                 this.inner.recv_task.register(cx.waker());
+                // Senders have to wake up the parked task again. Messages queued
+                // by senders which skipped the wakeup are seen by the check below.
+                this.inner.wake_pending.store(false, SeqCst);
                 // Check queue again after parking to prevent race condition:
                 // a message could be added to the queue after previous `next_message`
                 // before `register` call.
@@ -811,37 +1527,8 @@ impl<A: Actor> Stream for AddressReceiver<A> {
 
 impl<A: Actor> Drop for AddressReceiver<A> {
     fn drop(&mut self) {
-        // close
-        self.inner.set_closed();
-
-        // Wake up any threads waiting as they'll see that we've closed the
-        // channel and will continue on their merry way.
-        while let Some(task) = unsafe { self.inner.parked_queue.pop_spin() } {
-            task.lock().notify();
-        }
-
-        // Drain the channel of all pending messages
-        loop {
-            match self.next_message() {
-                Poll::Ready(Some(_)) => {}
-                Poll::Ready(None) => break,
-                Poll::Pending => {
-                    let state = decode_state(self.inner.state.load(SeqCst));
-
-                    // If the channel is closed, then there is no need to park.
-                    if state.is_closed() {
-                        break;
-                    }
-
-                    // TODO: Spinning isn't ideal, it might be worth
-                    // investigating using a condvar or some other strategy
-                    // here. That said, if this case is hit, then another thread
-                    // is about to push the value into the queue and this isn't
-                    // the only spinlock in the impl right now.
-                    thread::yield_now();
-                }
-            }
-        }
+        self.close();
+        self.inner.actor_state.close();
     }
 }
 
@@ -857,6 +1544,108 @@ impl<A: Actor> Inner<A> {
         MAX_CAPACITY - self.buffer.load(Relaxed)
     }
 
+    // Push message to the queue and signal to the receiver
+    fn push_and_signal(&self, msg: Envelope<A>) {
+        // Push the message onto the message queue
+        self.message_queue.push(msg);
+
+        // Signal to the receiver that a message has been enqueued. If the
+        // receiver is parked, this will unpark the task. Busy receivers are
+        // woken up only once, until they park again.
+        if self.wake_pending.swap(true, SeqCst) {
+            self.suppressed_wakeups.fetch_add(1, Relaxed);
+        } else {
+            self.recv_task.wake();
+        }
+    }
+
+    // Increment the number of queued messages. Returns the new number, or
+    // `None` if the channel is closed.
+    fn inc_num_messages(&self) -> Option<usize> {
+        let mut curr = self.state.load(SeqCst);
+        loop {
+            let mut state = decode_state(curr);
+            if !state.is_open {
+                return None;
+            }
+            state.num_messages += 1;
+
+            let next = encode_state(&state);
+            match self.state.compare_exchange(curr, next, SeqCst, SeqCst) {
+                Ok(_) => {
+                    self.check_rising(state.num_messages);
+                    return Some(state.num_messages);
+                }
+                Err(actual) => curr = actual,
+            }
+        }
+    }
+
+    // Raise the mailbox pressure once the number of queued messages reaches the high watermark.
+    fn check_rising(&self, num_messages: usize) {
+        let high = self.high_watermark.load(Relaxed);
+        if high != 0 && num_messages >= high {
+            let _ = self.pressure.compare_exchange(false, true, SeqCst, Relaxed);
+        }
+    }
+
+    // Clear the mailbox pressure once the number of queued messages dropped to the low
+    // watermark. Both watermarks apart, the pressure does not flap around either of them.
+    fn check_falling(&self, num_messages: usize) {
+        if self.pressure.load(Relaxed) && num_messages <= self.low_watermark.load(Relaxed) {
+            let _ = self.pressure.compare_exchange(true, false, SeqCst, Relaxed);
+        }
+    }
+
+    fn pressure_level(&self) -> PressureLevel {
+        if self.pressure.load(SeqCst) {
+            PressureLevel::Rising
+        } else {
+            PressureLevel::Falling
+        }
+    }
+
+    // Unpark a sender task. Its backlog is queued first, as long as the
+    // channel has capacity; if it fills up again, the task stays parked.
+    fn unpark(&self, task: Arc<Mutex<SenderTask<A>>>) {
+        let mut sender = task.lock();
+        while let Some(env) = sender.backlog.pop_front() {
+            let num_messages = match self.inc_num_messages() {
+                Some(num_messages) => num_messages,
+                None => {
+                    // closed, the requests of the backlog fail once it is dropped
+                    let backlog = mem::take(&mut sender.backlog);
+                    sender.notify();
+                    drop(sender);
+                    for env in backlog {
+                        self.discard(env);
+                    }
+                    return;
+                }
+            };
+            self.push_and_signal(env);
+
+            let buffer = self.buffer.load(Relaxed);
+            if buffer != 0 && num_messages >= buffer {
+                drop(sender);
+                self.parked_queue.push(task);
+                return;
+            }
+        }
+        sender.notify();
+    }
+
+    // Unpark all parked sender tasks, after the capacity grew.
+    fn unpark_all(&self) {
+        let mut tasks = Vec::new();
+        while let Some(task) = unsafe { self.parked_queue.pop_spin() } {
+            tasks.push(task);
+        }
+        for task in tasks {
+            self.unpark(task);
+        }
+    }
+
     // Clear `open` flag in the state, keep `num_messages` intact.
     fn set_closed(&self) {
         let curr = self.state.load(SeqCst);
@@ -866,6 +1655,42 @@ impl<A: Actor> Inner<A> {
 
         self.state.fetch_and(!OPEN_MASK, SeqCst);
     }
+
+    // Count a sent message, returns `true` if an injected fault drops it.
+    #[cfg(feature = "testing")]
+    fn drop_send(&self) -> bool {
+        self.faults
+            .read()
+            .as_ref()
+            .map_or(false, |faults| faults.drop_send())
+    }
+
+    fn dead_letters(&self) -> DeadLetterSink {
+        DeadLetterSink {
+            system: self.system,
+            recipient_type: type_name::<A>(),
+            recipient: self.actor_id.get().copied(),
+        }
+    }
+
+    // Drop an undelivered envelope, recording its message as a dead letter.
+    fn discard(&self, env: Envelope<A>) {
+        if let Some((message_type, ask)) = env.message_info() {
+            self.dead_letters()
+                .record(message_type, ask, DeadLetterReason::Dropped);
+        }
+    }
+
+    // Take a token for a sent message, `false` if the rate limit rejects it.
+    fn admit(&self) -> bool {
+        if !self.rate_limited.load(SeqCst) {
+            return true;
+        }
+        match *self.rate_limit.lock() {
+            Some(ref mut bucket) if bucket.policy() == RateLimitPolicy::Reject => bucket.acquire(),
+            _ => true,
+        }
+    }
 }
 
 unsafe impl<A: Actor> Send for Inner<A> {}
@@ -949,9 +1774,10 @@ mod tests {
 
             recv.set_capacity(10);
 
+            // the message sent while `s2` was parked is queued now
             thread::sleep(time::Duration::from_millis(100));
             let state = decode_state(recv.inner.state.load(SeqCst));
-            assert_eq!(state.num_messages, 2);
+            assert_eq!(state.num_messages, 3);
 
             let p = loop {
                 match unsafe { recv.inner.parked_queue.pop() } {
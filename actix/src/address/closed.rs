@@ -0,0 +1,101 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, Waker},
+};
+
+use parking_lot::Mutex;
+
+/// Tasks waiting for an actor's mailbox to close.
+pub(crate) struct CloseWatch {
+    inner: Mutex<CloseWatchInner>,
+}
+
+struct CloseWatchInner {
+    closed: bool,
+    next_key: usize,
+    waiters: HashMap<usize, Waker>,
+}
+
+impl CloseWatch {
+    pub(crate) fn new() -> Self {
+        CloseWatch {
+            inner: Mutex::new(CloseWatchInner {
+                closed: false,
+                next_key: 0,
+                waiters: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Marks the mailbox as closed and wakes up all waiting tasks.
+    pub(crate) fn close(&self) {
+        let waiters = {
+            let mut inner = self.inner.lock();
+            inner.closed = true;
+            std::mem::take(&mut inner.waiters)
+        };
+        for waker in waiters.into_values() {
+            waker.wake();
+        }
+    }
+}
+
+/// Future which resolves once an actor's mailbox is closed, created by
+/// [`Addr::closed`](super::Addr::closed).
+///
+/// The future resolves immediately if the mailbox is already closed. It does not keep the actor
+/// alive.
+pub struct Closed {
+    watch: Arc<CloseWatch>,
+    key: Option<usize>,
+}
+
+impl Closed {
+    pub(crate) fn new(watch: Arc<CloseWatch>) -> Self {
+        Closed { watch, key: None }
+    }
+}
+
+impl fmt::Debug for Closed {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Closed").finish()
+    }
+}
+
+impl Future for Closed {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+        let mut inner = this.watch.inner.lock();
+        if inner.closed {
+            this.key = None;
+            return Poll::Ready(());
+        }
+
+        let key = *this.key.get_or_insert_with(|| {
+            inner.next_key = inner.next_key.wrapping_add(1);
+            inner.next_key
+        });
+        match inner.waiters.get_mut(&key) {
+            Some(waker) if waker.will_wake(cx.waker()) => {}
+            Some(waker) => *waker = cx.waker().clone(),
+            None => {
+                inner.waiters.insert(key, cx.waker().clone());
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Closed {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.watch.inner.lock().waiters.remove(&key);
+        }
+    }
+}
@@ -1,9 +1,18 @@
-use tokio::sync::oneshot::Sender;
+use std::{
+    any::{type_name, Any},
+    fmt,
+};
 
+#[cfg(feature = "telemetry")]
+use crate::residency::Stamp;
 use crate::{
-    actor::{Actor, AsyncContext},
+    actor::{Actor, ActorContext, AsyncContext},
+    clock::{self, Instant},
     context::Context,
+    contextimpl::AsyncContextParts,
     handler::{Handler, Message, MessageResponse},
+    middleware,
+    queue::{self, OneshotReceiver, OneshotSender as Sender},
 };
 
 /// Converter trait, packs message into a suitable envelope.
@@ -16,9 +25,100 @@ where
     fn pack(msg: M, tx: Option<Sender<M::Result>>) -> Envelope<A>;
 }
 
+/// Packs a message into an envelope for the actor's context.
+///
+/// If `tx` is set, the handler's response is sent to it.
+/// Handling the returned envelope with [`EnvelopeProxy::handle`] invokes the handler directly,
+/// which allows testing a handler without a running system, see [`TestEnvelopeSink`].
+pub fn to_envelope<A, M>(msg: M, tx: Option<Sender<M::Result>>) -> Envelope<A>
+where
+    A: Actor + Handler<M>,
+    A::Context: ToEnvelope<A, M>,
+    M: Message,
+{
+    <A::Context as ToEnvelope<A, M>>::pack(msg, tx)
+}
+
+/// Captures the response of a handler invoked through an envelope.
+///
+/// Responses which are produced synchronously are available as soon as the envelope
+/// is handled. Asynchronous responses, e.g. [`ResponseFuture`](crate::ResponseFuture), still
+/// require the execution context to drive them.
+///
+/// ```
+/// use actix::{dev::*, prelude::*};
+///
+/// struct Sum(usize);
+///
+/// impl Actor for Sum {
+///     type Context = Context<Self>;
+/// }
+///
+/// #[derive(Message)]
+/// #[rtype(result = "usize")]
+/// struct Add(usize);
+///
+/// impl Handler<Add> for Sum {
+///     type Result = usize;
+///
+///     fn handle(&mut self, msg: Add, _: &mut Context<Self>) -> usize {
+///         self.0 += msg.0;
+///         self.0
+///     }
+/// }
+///
+/// // no system is required
+/// let mut act = Sum(1);
+/// let mut ctx = Context::new();
+///
+/// let (mut env, mut sink) = TestEnvelopeSink::envelope(Add(2));
+/// env.handle(&mut act, &mut ctx);
+/// assert_eq!(sink.try_recv(), Some(3));
+/// ```
+pub struct TestEnvelopeSink<R> {
+    rx: OneshotReceiver<R>,
+}
+
+impl<R> fmt::Debug for TestEnvelopeSink<R> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("TestEnvelopeSink").finish()
+    }
+}
+
+impl<R> TestEnvelopeSink<R> {
+    /// Packs a message into an envelope whose response is captured by the returned sink.
+    pub fn envelope<A, M>(msg: M) -> (Envelope<A>, Self)
+    where
+        A: Actor + Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+        M: Message<Result = R>,
+    {
+        let (tx, rx) = queue::oneshot();
+        (to_envelope(msg, Some(tx)), TestEnvelopeSink { rx })
+    }
+
+    /// Takes the captured response, if the handler replied.
+    pub fn try_recv(&mut self) -> Option<R> {
+        self.rx.try_recv()
+    }
+}
+
 pub trait EnvelopeProxy<A: Actor> {
     /// handle message within new actor and context
     fn handle(&mut self, act: &mut A, ctx: &mut A::Context);
+
+    /// Exposes the proxy for downcasting, used by the mailbox to recognize batched messages.
+    #[doc(hidden)]
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        None
+    }
+
+    /// Returns the type name of the message and whether it was sent as a request, `None` for
+    /// envelopes which are not sent on behalf of a message. Used to record dead letters.
+    #[doc(hidden)]
+    fn message_info(&self) -> Option<(&'static str, bool)> {
+        None
+    }
 }
 
 impl<A, M> ToEnvelope<A, M> for Context<A>
@@ -32,7 +132,11 @@ where
     }
 }
 
-pub struct Envelope<A: Actor>(Box<dyn EnvelopeProxy<A> + Send>);
+pub struct Envelope<A: Actor> {
+    proxy: Box<dyn EnvelopeProxy<A> + Send>,
+    #[cfg(feature = "telemetry")]
+    stamp: Stamp,
+}
 
 impl<A: Actor> Envelope<A> {
     pub fn new<M>(msg: M, tx: Option<Sender<M::Result>>) -> Self
@@ -42,17 +146,115 @@ impl<A: Actor> Envelope<A> {
         M: Message + Send + 'static,
         M::Result: Send,
     {
-        Envelope(Box::new(SyncEnvelopeProxy { tx, msg: Some(msg) }))
+        Self::with_proxy(Box::new(SyncEnvelopeProxy { tx, msg: Some(msg) }))
     }
 
     pub fn with_proxy(proxy: Box<dyn EnvelopeProxy<A> + Send>) -> Self {
-        Envelope(proxy)
+        Envelope {
+            proxy,
+            #[cfg(feature = "telemetry")]
+            stamp: Stamp::now(),
+        }
+    }
+
+    /// Reports the time the envelope is queued for to the request being sent, see
+    /// `Request::queued_for()`.
+    #[cfg(feature = "telemetry")]
+    pub(crate) fn track(mut self) -> Self {
+        self.stamp.track();
+        self
+    }
+
+    #[cfg(feature = "telemetry")]
+    pub(crate) fn stamp(&self) -> &Stamp {
+        &self.stamp
     }
 }
 
 impl<A: Actor> EnvelopeProxy<A> for Envelope<A> {
     fn handle(&mut self, act: &mut A, ctx: &mut <A as Actor>::Context) {
-        self.0.handle(act, ctx)
+        self.proxy.handle(act, ctx)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        self.proxy.as_any_mut()
+    }
+
+    fn message_info(&self) -> Option<(&'static str, bool)> {
+        self.proxy.message_info()
+    }
+}
+
+/// Envelope which stops the actor once it is handled.
+pub(crate) struct StopEnvelope;
+
+impl<A: Actor> EnvelopeProxy<A> for StopEnvelope {
+    fn handle(&mut self, _: &mut A, ctx: &mut A::Context) {
+        ctx.stop();
+    }
+}
+
+/// Envelope of a request with a deadline, see [`Addr::send_with_deadline()`].
+///
+/// [`Addr::send_with_deadline()`]: crate::Addr::send_with_deadline
+pub(crate) struct DeadlineEnvelope<M>
+where
+    M: Message + Send,
+    M::Result: Send,
+{
+    msg: Option<M>,
+    tx: Option<Sender<M::Result>>,
+    deadline: Instant,
+}
+
+impl<M> DeadlineEnvelope<M>
+where
+    M: Message + Send,
+    M::Result: Send,
+{
+    pub(crate) fn new(msg: M, tx: Option<Sender<M::Result>>, deadline: Instant) -> Self {
+        DeadlineEnvelope {
+            msg: Some(msg),
+            tx,
+            deadline,
+        }
+    }
+}
+
+impl<A, M> EnvelopeProxy<A> for DeadlineEnvelope<M>
+where
+    M: Message + Send + 'static,
+    M::Result: Send,
+    A: Actor + Handler<M>,
+    A::Context: AsyncContextParts<A>,
+{
+    fn handle(&mut self, act: &mut A, ctx: &mut A::Context) {
+        let tx = self.tx.take();
+        let msg = match self.msg.take() {
+            Some(msg) => msg,
+            None => return,
+        };
+
+        // the caller has given up already
+        if tx.as_ref().map_or(false, |tx| tx.is_closed()) || clock::now() >= self.deadline {
+            ctx.parts().expire_request();
+            return;
+        }
+
+        let prev = ctx.parts().set_request_deadline(Some(self.deadline));
+        let fut = <A as Handler<M>>::handle(act, msg, ctx);
+        ctx.parts().set_request_deadline(prev);
+
+        // nobody waits for the reply anymore
+        if clock::now() >= self.deadline {
+            ctx.parts().expire_request();
+            return;
+        }
+        fut.handle(ctx, tx)
+    }
+
+    fn message_info(&self) -> Option<(&'static str, bool)> {
+        Some((type_name::<M>(), self.tx.is_some()))
     }
 }
 
@@ -65,6 +267,31 @@ where
     tx: Option<Sender<M::Result>>,
 }
 
+impl<M> SyncEnvelopeProxy<M>
+where
+    M: Message + Send,
+    M::Result: Send,
+{
+    /// Takes the message out if nobody is waiting for its result.
+    pub(crate) fn take_unanswered(&mut self) -> Option<M> {
+        if self.tx.is_none() {
+            self.msg.take()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the message, `None` once it was handled.
+    pub(crate) fn message(&self) -> Option<&M> {
+        self.msg.as_ref()
+    }
+
+    /// Takes the message out together with the sender of its result.
+    pub(crate) fn take(&mut self) -> Option<(M, Option<Sender<M::Result>>)> {
+        self.msg.take().map(|msg| (msg, self.tx.take()))
+    }
+}
+
 impl<A, M> EnvelopeProxy<A> for SyncEnvelopeProxy<M>
 where
     M: Message + Send + 'static,
@@ -79,8 +306,15 @@ where
         }
 
         if let Some(msg) = self.msg.take() {
-            let fut = <A as Handler<M>>::handle(act, msg, ctx);
-            fut.handle(ctx, tx)
+            middleware::handle(act, ctx, msg, tx)
         }
     }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        Some(self)
+    }
+
+    fn message_info(&self) -> Option<(&'static str, bool)> {
+        Some((type_name::<M>(), self.tx.is_some()))
+    }
 }
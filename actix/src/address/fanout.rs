@@ -0,0 +1,446 @@
+use std::{
+    fmt,
+    future::Future,
+    mem,
+    pin::Pin,
+    task::{self, Poll},
+    time::Duration,
+};
+
+use super::{Addr, MailboxError, Recipient, SendError, ToEnvelope};
+use crate::{
+    clock::{sleep, Sleep},
+    handler::{Handler, Message},
+};
+
+type PendingRequest<R> = Pin<Box<dyn Future<Output = Result<R, MailboxError>>>>;
+
+/// Sends a clone of `msg` to every address and collects the responses.
+///
+/// Responses are returned in the order of `addrs`, regardless of which actor answers first.
+/// The returned future fails with the first [`MailboxError`]; use [`SendAll::settled`] to wait
+/// for every target instead.
+///
+/// ```
+/// # use actix::prelude::*;
+/// struct Echo;
+///
+/// impl Actor for Echo {
+///     type Context = Context<Self>;
+/// }
+///
+/// #[derive(Clone)]
+/// struct Ping(usize);
+///
+/// impl Message for Ping {
+///     type Result = usize;
+/// }
+///
+/// impl Handler<Ping> for Echo {
+///     type Result = usize;
+///
+///     fn handle(&mut self, msg: Ping, _: &mut Context<Self>) -> usize {
+///         msg.0
+///     }
+/// }
+///
+/// # fn main() {
+/// # System::new().block_on(async {
+/// let addrs = vec![Echo.start(), Echo.start()];
+/// let res = actix::send_all(&addrs, Ping(1)).await;
+/// assert_eq!(res.unwrap(), vec![1, 1]);
+/// # });
+/// # }
+/// ```
+pub fn send_all<A, M>(addrs: &[Addr<A>], msg: M) -> SendAll<M::Result>
+where
+    A: Handler<M>,
+    A::Context: ToEnvelope<A, M>,
+    M: Message + Clone + Send + 'static,
+    M::Result: Send,
+{
+    SendAll::new(
+        addrs
+            .iter()
+            .map(|addr| Box::pin(addr.send(msg.clone())) as PendingRequest<_>)
+            .collect(),
+    )
+}
+
+/// Sends a clone of `msg` to every recipient and collects the responses.
+///
+/// Behaves like [`send_all`].
+pub fn send_all_recipients<M>(recipients: &[Recipient<M>], msg: M) -> SendAll<M::Result>
+where
+    M: Message + Clone + Send + 'static,
+    M::Result: Send,
+{
+    SendAll::new(
+        recipients
+            .iter()
+            .map(|rcp| Box::pin(rcp.send(msg.clone())) as PendingRequest<_>)
+            .collect(),
+    )
+}
+
+/// A `Future` which resolves with the responses of all targets, see [`send_all`].
+///
+/// Once a target fails, requests which are still pending are dropped. Their messages are
+/// cancelled if they could not be delivered yet.
+#[must_use = "You must wait on the request otherwise the Message will not be delivered"]
+pub struct SendAll<R> {
+    inner: Inner<R>,
+}
+
+/// A `Future` which waits for every target and records each outcome, see [`SendAll::settled`].
+#[must_use = "You must wait on the request otherwise the Message will not be delivered"]
+pub struct SendAllSettled<R> {
+    inner: Inner<R>,
+}
+
+struct Inner<R> {
+    pending: Vec<Option<PendingRequest<R>>>,
+    results: Vec<Option<Result<R, MailboxError>>>,
+    remaining: usize,
+    timeout: Option<Pin<Box<Sleep>>>,
+}
+
+impl<R> SendAll<R> {
+    fn new(pending: Vec<PendingRequest<R>>) -> Self {
+        let remaining = pending.len();
+        Self {
+            inner: Inner {
+                results: pending.iter().map(|_| None).collect(),
+                pending: pending.into_iter().map(Some).collect(),
+                remaining,
+                timeout: None,
+            },
+        }
+    }
+
+    /// Sets an overall timeout for all responses.
+    ///
+    /// Targets which did not respond in time fail with [`MailboxError::Timeout`].
+    pub fn timeout(mut self, dur: Duration) -> Self {
+        self.inner.timeout = Some(Box::pin(actix_rt::time::sleep(dur)));
+        self
+    }
+
+    /// Waits for every target instead of failing early.
+    pub fn settled(self) -> SendAllSettled<R> {
+        SendAllSettled { inner: self.inner }
+    }
+}
+
+impl<R> SendAllSettled<R> {
+    /// Sets an overall timeout for all responses.
+    ///
+    /// Targets which did not respond in time are recorded as [`MailboxError::Timeout`].
+    pub fn timeout(mut self, dur: Duration) -> Self {
+        self.inner.timeout = Some(Box::pin(actix_rt::time::sleep(dur)));
+        self
+    }
+}
+
+// responses are never pinned
+impl<R> Unpin for Inner<R> {}
+
+impl<R> Inner<R> {
+    /// Polls pending requests, returns the first error if `fail_fast` is set.
+    fn poll_settle(
+        &mut self,
+        fail_fast: bool,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Result<(), MailboxError>> {
+        for (idx, slot) in self.pending.iter_mut().enumerate() {
+            if let Some(fut) = slot {
+                if let Poll::Ready(res) = fut.as_mut().poll(cx) {
+                    *slot = None;
+                    self.remaining -= 1;
+
+                    match res {
+                        Err(err) if fail_fast => return Poll::Ready(Err(err)),
+                        res => self.results[idx] = Some(res),
+                    }
+                }
+            }
+        }
+
+        if self.remaining == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        if let Some(timeout) = self.timeout.as_mut() {
+            if timeout.as_mut().poll(cx).is_ready() {
+                if fail_fast {
+                    return Poll::Ready(Err(MailboxError::Timeout));
+                }
+
+                for (idx, slot) in self.pending.iter_mut().enumerate() {
+                    if slot.take().is_some() {
+                        self.results[idx] = Some(Err(MailboxError::Timeout));
+                    }
+                }
+                self.remaining = 0;
+                return Poll::Ready(Ok(()));
+            }
+        }
+
+        Poll::Pending
+    }
+
+    fn take_results(&mut self) -> impl Iterator<Item = Result<R, MailboxError>> {
+        mem::take(&mut self.results)
+            .into_iter()
+            .map(|res| res.expect("SendAll polled after completion"))
+    }
+}
+
+impl<R> Future for SendAll<R> {
+    type Output = Result<Vec<R>, MailboxError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let inner = &mut self.get_mut().inner;
+
+        match inner.poll_settle(true, cx) {
+            Poll::Ready(Ok(())) => Poll::Ready(inner.take_results().collect()),
+            Poll::Ready(Err(err)) => {
+                inner.pending.clear();
+                Poll::Ready(Err(err))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<R> Future for SendAllSettled<R> {
+    type Output = Vec<Result<R, MailboxError>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let inner = &mut self.get_mut().inner;
+
+        match inner.poll_settle(false, cx) {
+            Poll::Ready(_) => Poll::Ready(inner.take_results().collect()),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<R> fmt::Debug for SendAll<R> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("SendAll")
+            .field("remaining", &self.inner.remaining)
+            .finish()
+    }
+}
+
+impl<R> fmt::Debug for SendAllSettled<R> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("SendAllSettled")
+            .field("remaining", &self.inner.remaining)
+            .finish()
+    }
+}
+
+/// Queues a clone of `msg` into the mailbox of every recipient, without waiting for the
+/// responses.
+///
+/// Messages are queued like with [`Recipient::do_send`], even if a mailbox is full. The
+/// returned report lists the recipients which did not accept their message, e.g. because their
+/// actor has stopped.
+///
+/// ```
+/// # use actix::prelude::*;
+/// # struct Echo;
+/// # impl Actor for Echo {
+/// #     type Context = Context<Self>;
+/// # }
+/// #[derive(Clone, Message)]
+/// #[rtype(result = "()")]
+/// struct Tick;
+/// # impl Handler<Tick> for Echo {
+/// #     type Result = ();
+/// #     fn handle(&mut self, _: Tick, _: &mut Context<Self>) {}
+/// # }
+///
+/// # fn main() {
+/// # System::new().block_on(async {
+/// let targets = vec![Echo.start().recipient(), Echo.start().recipient()];
+/// let report = actix::broadcast(&targets, &Tick);
+/// assert_eq!(report.delivered(), 2);
+/// assert!(report.failed().is_empty());
+/// # });
+/// # }
+/// ```
+pub fn broadcast<M>(targets: &[Recipient<M>], msg: &M) -> BroadcastReport
+where
+    M: Message + Clone + Send,
+    M::Result: Send,
+{
+    let mut report = BroadcastReport::default();
+    for (idx, rcp) in targets.iter().enumerate() {
+        report.record(idx, rcp.tx.do_send(msg.clone()).map_err(mailbox_error));
+    }
+    report
+}
+
+/// Queues a clone of `msg` into the mailbox of every recipient, waiting for full mailboxes to
+/// have room for it.
+///
+/// The returned future resolves once every recipient accepted its message or failed. Each
+/// recipient is given `timeout` to accept it, recipients whose mailbox is still full by then
+/// are reported with [`MailboxError::Timeout`] and do not receive the message. Like with
+/// [`Recipient::sink`], waiting for room only applies to messages sent through the same
+/// recipient.
+pub fn broadcast_async<M>(
+    targets: &[Recipient<M>],
+    msg: M,
+    timeout: Duration,
+) -> BroadcastAsync<'_, M>
+where
+    M: Message + Clone + Send,
+    M::Result: Send,
+{
+    BroadcastAsync {
+        pending: targets.iter().map(Some).collect(),
+        msg,
+        report: BroadcastReport::default(),
+        timeout: Box::pin(sleep(timeout)),
+    }
+}
+
+/// Outcome of a [`broadcast`] or [`broadcast_async`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BroadcastReport {
+    delivered: usize,
+    failed: Vec<(usize, MailboxError)>,
+}
+
+impl BroadcastReport {
+    /// Returns the number of recipients which accepted the message.
+    pub fn delivered(&self) -> usize {
+        self.delivered
+    }
+
+    /// Returns the indices of the recipients which did not accept the message, in increasing
+    /// order, along with the reason.
+    pub fn failed(&self) -> &[(usize, MailboxError)] {
+        &self.failed
+    }
+
+    /// Returns whether every recipient accepted the message.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Removes the targets which did not accept the message from `targets`, e.g. to drop
+    /// subscribers whose actor has stopped.
+    ///
+    /// `targets` is expected to be in the order of the broadcast recipients.
+    pub fn retain_delivered<T>(&self, targets: &mut Vec<T>) {
+        let mut failed = self.failed.iter().map(|(idx, _)| *idx).peekable();
+        let mut idx = 0;
+        targets.retain(|_| {
+            let keep = failed.next_if_eq(&idx).is_none();
+            idx += 1;
+            keep
+        });
+    }
+
+    fn record(&mut self, idx: usize, res: Result<(), MailboxError>) {
+        match res {
+            Ok(()) => self.delivered += 1,
+            Err(err) => self.failed.push((idx, err)),
+        }
+    }
+}
+
+fn mailbox_error<M>(err: SendError<M>) -> MailboxError {
+    match err {
+        SendError::RateLimited(_) => MailboxError::RateLimited,
+        SendError::Rejected(_, rejection) => MailboxError::Rejected(rejection),
+        _ => MailboxError::Closed,
+    }
+}
+
+/// A `Future` which resolves with the [`BroadcastReport`] of a [`broadcast_async`].
+#[must_use = "You must wait on the broadcast otherwise the Message will not be delivered"]
+pub struct BroadcastAsync<'a, M>
+where
+    M: Message + Send,
+    M::Result: Send,
+{
+    pending: Vec<Option<&'a Recipient<M>>>,
+    msg: M,
+    report: BroadcastReport,
+    timeout: Pin<Box<Sleep>>,
+}
+
+// the message is never pinned
+impl<M> Unpin for BroadcastAsync<'_, M>
+where
+    M: Message + Send,
+    M::Result: Send,
+{
+}
+
+impl<M> Future for BroadcastAsync<'_, M>
+where
+    M: Message + Clone + Send,
+    M::Result: Send,
+{
+    type Output = BroadcastReport;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let timed_out = this.timeout.as_mut().poll(cx).is_ready();
+
+        let mut remaining = false;
+        for (idx, slot) in this.pending.iter_mut().enumerate() {
+            let rcp = match slot {
+                Some(rcp) => *rcp,
+                None => continue,
+            };
+            let res = if !rcp.tx.connected() {
+                Err(MailboxError::Closed)
+            } else if rcp.tx.poll_ready(cx).is_ready() {
+                match rcp.tx.try_send(this.msg.clone()) {
+                    // another sender filled the mailbox in the meantime
+                    Err(SendError::Full(_)) if !timed_out => {
+                        remaining = true;
+                        continue;
+                    }
+                    Err(SendError::Full(_)) => Err(MailboxError::Timeout),
+                    res => res.map_err(mailbox_error),
+                }
+            } else if timed_out {
+                Err(MailboxError::Timeout)
+            } else {
+                remaining = true;
+                continue;
+            };
+            *slot = None;
+            this.report.record(idx, res);
+        }
+
+        if remaining {
+            return Poll::Pending;
+        }
+        // failures were recorded in the order of the polls which found them
+        this.report.failed.sort_unstable_by_key(|(idx, _)| *idx);
+        Poll::Ready(mem::take(&mut this.report))
+    }
+}
+
+impl<M> fmt::Debug for BroadcastAsync<'_, M>
+where
+    M: Message + Send,
+    M::Result: Send,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("BroadcastAsync")
+            .field("remaining", &self.pending.iter().flatten().count())
+            .field("report", &self.report)
+            .finish()
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    any::type_name,
+    sync::Arc,
+    task::{self, Poll},
+};
+
+use super::{
+    channel::{AddressSender, Sender, WeakAddressSender, WeakSender},
+    Envelope, EnvelopeProxy, SendError,
+};
+use crate::{
+    actor::{Actor, AsyncContext},
+    fut::wrap_future,
+    handler::{Handler, Message, MessageResponse},
+    queue::{oneshot, OneshotReceiver, OneshotSender},
+};
+
+type Convert<T, U> = Box<dyn Fn(T) -> U + Send + Sync>;
+
+/// Converters of a recipient created by
+/// [`Addr::legacy_recipient`](super::Addr::legacy_recipient).
+struct Converters<Old: Message, New: Message> {
+    msg: Convert<Old, New>,
+    reply: Convert<New::Result, Old::Result>,
+}
+
+/// Sender which queues `Old` messages, converted to `New` when they are handled.
+pub(crate) struct LegacySender<A: Actor, Old: Message, New: Message> {
+    tx: AddressSender<A>,
+    conv: Arc<Converters<Old, New>>,
+}
+
+impl<A, Old, New> LegacySender<A, Old, New>
+where
+    A: Actor,
+    Old: Message,
+    New: Message,
+{
+    pub(crate) fn new<F, R>(tx: AddressSender<A>, msg: F, reply: R) -> Self
+    where
+        F: Fn(Old) -> New + Send + Sync + 'static,
+        R: Fn(New::Result) -> Old::Result + Send + Sync + 'static,
+    {
+        LegacySender {
+            tx,
+            conv: Arc::new(Converters {
+                msg: Box::new(msg),
+                reply: Box::new(reply),
+            }),
+        }
+    }
+}
+
+impl<A, Old, New> Sender<Old> for LegacySender<A, Old, New>
+where
+    A: Handler<New>,
+    A::Context: AsyncContext<A>,
+    Old: Message + Send + 'static,
+    Old::Result: Send,
+    New: Message + 'static,
+{
+    fn do_send(&self, msg: Old) -> Result<(), SendError<Old>> {
+        self.tx
+            .do_send_packed(msg, |msg| LegacyEnvelope::pack(msg, None, &self.conv))
+    }
+
+    fn try_send(&self, msg: Old) -> Result<(), SendError<Old>> {
+        self.tx
+            .try_send_packed(msg, true, |msg| LegacyEnvelope::pack(msg, None, &self.conv))
+    }
+
+    fn send(&self, msg: Old) -> Result<OneshotReceiver<Old::Result>, SendError<Old>> {
+        self.tx
+            .send_packed(msg, |msg, tx| LegacyEnvelope::pack(msg, tx, &self.conv))
+    }
+
+    fn boxed(&self) -> Box<dyn Sender<Old> + Sync> {
+        Box::new(LegacySender {
+            tx: self.tx.clone(),
+            conv: Arc::clone(&self.conv),
+        })
+    }
+
+    fn hash(&self) -> usize {
+        // distinguishes legacy recipients from the actor's other recipients
+        Arc::as_ptr(&self.conv) as *const () as usize
+    }
+
+    fn connected(&self) -> bool {
+        self.tx.connected()
+    }
+
+    fn poll_ready(&self, cx: &mut task::Context<'_>) -> Poll<()> {
+        self.tx.poll_ready(cx)
+    }
+
+    fn downgrade(&self) -> Box<dyn WeakSender<Old> + Sync + 'static> {
+        Box::new(WeakLegacySender {
+            tx: self.tx.downgrade(),
+            conv: Arc::clone(&self.conv),
+        })
+    }
+}
+
+struct WeakLegacySender<A: Actor, Old: Message, New: Message> {
+    tx: WeakAddressSender<A>,
+    conv: Arc<Converters<Old, New>>,
+}
+
+impl<A, Old, New> WeakSender<Old> for WeakLegacySender<A, Old, New>
+where
+    A: Handler<New>,
+    A::Context: AsyncContext<A>,
+    Old: Message + Send + 'static,
+    Old::Result: Send,
+    New: Message + 'static,
+{
+    fn upgrade(&self) -> Option<Box<dyn Sender<Old> + Sync>> {
+        self.tx.upgrade().map(|tx| {
+            Box::new(LegacySender {
+                tx,
+                conv: Arc::clone(&self.conv),
+            }) as _
+        })
+    }
+
+    fn boxed(&self) -> Box<dyn WeakSender<Old> + Sync> {
+        Box::new(WeakLegacySender {
+            tx: self.tx.clone(),
+            conv: Arc::clone(&self.conv),
+        })
+    }
+}
+
+/// Envelope which converts its message before handling it, and the response afterwards.
+struct LegacyEnvelope<Old: Message, New: Message> {
+    msg: Option<Old>,
+    tx: Option<OneshotSender<Old::Result>>,
+    conv: Arc<Converters<Old, New>>,
+}
+
+impl<Old, New> LegacyEnvelope<Old, New>
+where
+    Old: Message + Send + 'static,
+    Old::Result: Send,
+    New: Message + 'static,
+{
+    fn pack<A>(
+        msg: Old,
+        tx: Option<OneshotSender<Old::Result>>,
+        conv: &Arc<Converters<Old, New>>,
+    ) -> Envelope<A>
+    where
+        A: Handler<New>,
+        A::Context: AsyncContext<A>,
+    {
+        Envelope::with_proxy(Box::new(LegacyEnvelope {
+            msg: Some(msg),
+            tx,
+            conv: Arc::clone(conv),
+        }))
+    }
+}
+
+impl<A, Old, New> EnvelopeProxy<A> for LegacyEnvelope<Old, New>
+where
+    A: Handler<New>,
+    A::Context: AsyncContext<A>,
+    Old: Message + Send + 'static,
+    Old::Result: Send,
+    New: Message + 'static,
+{
+    fn handle(&mut self, act: &mut A, ctx: &mut A::Context) {
+        let tx = self.tx.take();
+        if tx.as_ref().map_or(false, |tx| tx.is_closed()) {
+            return;
+        }
+
+        let msg = match self.msg.take() {
+            Some(msg) => (self.conv.msg)(msg),
+            None => return,
+        };
+
+        let tx = match tx {
+            Some(tx) => tx,
+            None => return <A as Handler<New>>::handle(act, msg, ctx).handle(ctx, None),
+        };
+
+        let (new_tx, mut new_rx) = oneshot();
+        <A as Handler<New>>::handle(act, msg, ctx).handle(ctx, Some(new_tx));
+
+        match new_rx.try_recv() {
+            Some(res) => {
+                let _ = tx.send((self.conv.reply)(res));
+            }
+            // the response is produced asynchronously, convert it once it is available;
+            // if the handler dropped the response, so does the request
+            None => {
+                let conv = Arc::clone(&self.conv);
+                ctx.spawn(wrap_future(async move {
+                    if let Ok(res) = new_rx.await {
+                        let _ = tx.send((conv.reply)(res));
+                    }
+                }));
+            }
+        }
+    }
+
+    fn message_info(&self) -> Option<(&'static str, bool)> {
+        Some((type_name::<Old>(), self.tx.is_some()))
+    }
+}
@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use crate::clock::{self, Instant};
+
+/// Limits the rate of messages handled by an actor.
+///
+/// The limit is a token bucket: the actor can handle up to `burst` messages at once, after which
+/// messages are admitted at `per_second` on average. See
+/// [`Context::set_rate_limit()`](crate::Context::set_rate_limit).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Average number of messages per second.
+    pub per_second: u32,
+    /// Number of messages which can be handled at once.
+    pub burst: u32,
+    /// What happens to messages over the limit.
+    pub policy: RateLimitPolicy,
+}
+
+/// Describes what happens to messages exceeding a [`RateLimit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitPolicy {
+    /// Keep messages queued until the limit allows handling them.
+    ///
+    /// Senders are subject to the usual mailbox capacity meanwhile.
+    Delay,
+
+    /// Reject messages over the limit when they are sent.
+    ///
+    /// Requests resolve with [`MailboxError::RateLimited`](super::MailboxError::RateLimited)
+    /// and `try_send` fails with [`SendError::RateLimited`](super::SendError::RateLimited).
+    Reject,
+}
+
+/// Token bucket enforcing a [`RateLimit`].
+pub(crate) struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        assert!(limit.per_second > 0, "Rate limit must be greater than zero");
+        assert!(
+            limit.burst > 0,
+            "Rate limit burst must be greater than zero"
+        );
+        TokenBucket {
+            limit,
+            tokens: f64::from(limit.burst),
+            last: clock::now(),
+        }
+    }
+
+    pub(crate) fn policy(&self) -> RateLimitPolicy {
+        self.limit.policy
+    }
+
+    fn refill(&mut self) {
+        let now = clock::now();
+        let elapsed = now.duration_since(self.last).as_secs_f64();
+        self.last = now;
+        self.tokens = (self.tokens + elapsed * f64::from(self.limit.per_second))
+            .min(f64::from(self.limit.burst));
+    }
+
+    /// Returns the time until a token is available, `None` if one is available now.
+    pub(crate) fn delay(&mut self) -> Option<Duration> {
+        self.refill();
+        if self.tokens >= 1.0 {
+            None
+        } else {
+            let missing = 1.0 - self.tokens;
+            // round up, so the token is available once the delay has passed
+            let nanos = (missing * 1e9 / f64::from(self.limit.per_second)).ceil();
+            Some(Duration::from_nanos(nanos as u64))
+        }
+    }
+
+    /// Takes a token, returns `false` if none is available.
+    pub(crate) fn acquire(&mut self) -> bool {
+        if self.delay().is_none() {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Takes a token, even if none is available.
+    pub(crate) fn consume(&mut self) {
+        self.refill();
+        self.tokens -= 1.0;
+    }
+}
@@ -0,0 +1,131 @@
+use std::{sync::Arc, thread};
+
+use super::{Addr, Envelope, EnvelopeProxy, WeakAddr};
+use crate::{
+    actor::{Actor, AsyncContext},
+    panic_report::{self, PanicReport},
+};
+
+/// Notification that a linked actor has exited, see [`AsyncContext::link()`].
+///
+/// Delivered to [`Actor::linked_exit()`].
+#[derive(Debug, Clone)]
+pub struct LinkedExit {
+    /// Why the linked actor exited.
+    pub reason: ExitReason,
+    /// Report of the panic, if the linked actor panicked while running a message or a timer
+    /// and its context could report it.
+    pub panic: Option<Arc<PanicReport>>,
+}
+
+/// Reason of a [`LinkedExit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ExitReason {
+    /// The linked actor has stopped.
+    Stopped,
+    /// The linked actor panicked.
+    Panicked,
+    /// The linked actor was not running anymore when the link was created.
+    NotRunning,
+}
+
+/// Links the actor of `ctx` with `other`.
+pub(crate) fn link<A, B>(ctx: &mut A::Context, other: &Addr<B>)
+where
+    A: Actor,
+    A::Context: AsyncContext<A>,
+    B: Actor,
+    B::Context: AsyncContext<B>,
+{
+    let this = ctx.address();
+    ctx.attach_resource(LinkGuard {
+        peer: other.downgrade(),
+    });
+
+    // if `other` has stopped, dropping the envelope notifies this actor
+    let _ = other.push_envelope(Envelope::with_proxy(Box::new(LinkBackEnvelope {
+        peer: Some(this.downgrade()),
+    })));
+}
+
+fn exit<A>(peer: &WeakAddr<A>, reason: ExitReason, panic: Option<Arc<PanicReport>>)
+where
+    A: Actor,
+    A::Context: AsyncContext<A>,
+{
+    if let Some(addr) = peer.upgrade() {
+        let exit = LinkedExit { reason, panic };
+        let _ = addr.push_envelope(Envelope::with_proxy(Box::new(ExitEnvelope(Some(exit)))));
+    }
+}
+
+/// Resource notifying the linked actor once the actor has exited.
+///
+/// Only holds a weak address, so linked actors do not keep each other alive.
+struct LinkGuard<A: Actor>
+where
+    A::Context: AsyncContext<A>,
+{
+    peer: WeakAddr<A>,
+}
+
+impl<A: Actor> Drop for LinkGuard<A>
+where
+    A::Context: AsyncContext<A>,
+{
+    fn drop(&mut self) {
+        let panic = panic_report::current();
+        let reason = if panic.is_some() || thread::panicking() {
+            ExitReason::Panicked
+        } else {
+            ExitReason::Stopped
+        };
+        exit(&self.peer, reason, panic);
+    }
+}
+
+/// Envelope linking the receiving actor back to the actor which created the link.
+struct LinkBackEnvelope<A: Actor>
+where
+    A::Context: AsyncContext<A>,
+{
+    peer: Option<WeakAddr<A>>,
+}
+
+impl<A, B> EnvelopeProxy<B> for LinkBackEnvelope<A>
+where
+    A: Actor,
+    A::Context: AsyncContext<A>,
+    B: Actor,
+    B::Context: AsyncContext<B>,
+{
+    fn handle(&mut self, _: &mut B, ctx: &mut B::Context) {
+        if let Some(peer) = self.peer.take() {
+            ctx.attach_resource(LinkGuard { peer });
+        }
+    }
+}
+
+impl<A: Actor> Drop for LinkBackEnvelope<A>
+where
+    A::Context: AsyncContext<A>,
+{
+    fn drop(&mut self) {
+        // the linked actor stopped before it was linked back
+        if let Some(peer) = self.peer.take() {
+            exit(&peer, ExitReason::NotRunning, None);
+        }
+    }
+}
+
+/// Envelope delivering a [`LinkedExit`].
+struct ExitEnvelope(Option<LinkedExit>);
+
+impl<A: Actor> EnvelopeProxy<A> for ExitEnvelope {
+    fn handle(&mut self, act: &mut A, ctx: &mut A::Context) {
+        if let Some(exit) = self.0.take() {
+            act.linked_exit(exit, ctx);
+        }
+    }
+}
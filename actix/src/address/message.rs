@@ -1,18 +1,20 @@
 use std::{
     future::Future,
+    marker::PhantomData,
     pin::Pin,
     task::{self, Poll},
     time::Duration,
 };
 
 use pin_project_lite::pin_project;
-use tokio::sync::oneshot;
 
 use super::{
     channel::{AddressSender, Sender},
     MailboxError, SendError,
 };
-use crate::{clock::Sleep, handler::Message};
+#[cfg(feature = "telemetry")]
+use crate::residency::{self, ResidencySlot};
+use crate::{arbiter::SystemShutdown, clock::Timer, handler::Message, queue::OneshotReceiver};
 
 pub type Request<A, M> = MsgRequest<AddressSender<A>, M>;
 
@@ -28,10 +30,15 @@ pin_project! {
         M: Send,
         M::Result: Send
     {
-        rx: Option<oneshot::Receiver<M::Result>>,
-        info: Option<(S, M)>,
+        rx: Option<OneshotReceiver<M::Result>>,
+        err: MailboxError,
+        // a reply dropped once the system is stopping fails with `SystemStopping`
+        shutdown: Option<SystemShutdown>,
         #[pin]
-        timeout: Option<Sleep>,
+        timeout: Option<Timer>,
+        reply_delay: ReplyDelay<M::Result>,
+        queued: QueuedFor,
+        _sender: PhantomData<fn(S, M)>,
     }
 }
 
@@ -41,11 +48,65 @@ where
     M: Message + Send,
     M::Result: Send,
 {
-    pub(crate) fn new(rx: Option<oneshot::Receiver<M::Result>>, info: Option<(S, M)>) -> Self {
+    pub(crate) fn new(rx: Option<OneshotReceiver<M::Result>>) -> Self {
         Self {
             rx,
-            info,
+            err: MailboxError::Closed,
+            shutdown: None,
             timeout: None,
+            reply_delay: ReplyDelay::none(),
+            queued: QueuedFor::default(),
+            _sender: PhantomData,
+        }
+    }
+
+    /// Creates a request which resolves with `err` right away.
+    pub(crate) fn rejected(err: MailboxError) -> Self {
+        Self {
+            err,
+            ..Self::new(None)
+        }
+    }
+
+    /// Creates a request for the reply of a message sent to the receiver of `tx` by `send`.
+    ///
+    /// Resolves with [`MailboxError::SystemStopping`] right away, without sending the message,
+    /// once the system of the receiver is stopping.
+    pub(crate) fn sent<T, F>(tx: &T, send: F) -> Self
+    where
+        T: Sender<M> + ?Sized,
+        F: FnOnce() -> Result<OneshotReceiver<M::Result>, SendError<M>>,
+    {
+        // checked before `send`, so a message rejected here is never queued
+        let shutdown = tx.system_shutdown();
+        if shutdown.as_ref().map_or(false, SystemShutdown::has_begun) {
+            return Self::rejected(MailboxError::SystemStopping);
+        }
+        #[cfg(feature = "testing")]
+        let faults = tx.faults();
+        #[cfg(feature = "testing")]
+        if let Some(err) = faults.as_ref().and_then(|faults| faults.fail_calls()) {
+            return Self::rejected(err);
+        }
+        #[cfg(feature = "telemetry")]
+        let (res, slot) = residency::tracked(send);
+        #[cfg(not(feature = "telemetry"))]
+        let res = send();
+        match res {
+            Ok(rx) => Self {
+                shutdown,
+                #[cfg(feature = "telemetry")]
+                queued: QueuedFor { slot },
+                #[cfg(feature = "testing")]
+                reply_delay: ReplyDelay::new(faults.and_then(|faults| faults.reply_delay())),
+                ..Self::new(Some(rx))
+            },
+            Err(SendError::Full(_)) => Self::rejected(MailboxError::Full),
+            Err(SendError::RateLimited(_)) => Self::rejected(MailboxError::RateLimited),
+            Err(SendError::Rejected(_, rejection)) => {
+                Self::rejected(MailboxError::Rejected(rejection))
+            }
+            Err(_) => Self::new(None),
         }
     }
 
@@ -56,9 +117,39 @@ where
 
     /// Set message delivery timeout
     pub fn timeout(mut self, dur: Duration) -> Self {
-        self.timeout = Some(actix_rt::time::sleep(dur));
+        self.timeout = Some(Timer::after(dur));
         self
     }
+
+    /// Returns how long the message was queued in the mailbox of the actor before it was
+    /// handled, `None` until the actor took it out of its mailbox.
+    ///
+    /// Separates the queueing delay from the time the handler took, once the request has
+    /// completed:
+    ///
+    /// ```
+    /// # use actix::prelude::*;
+    /// # struct Echo;
+    /// # impl Actor for Echo { type Context = Context<Self>; }
+    /// # #[derive(Message)]
+    /// # #[rtype(result = "u32")]
+    /// # struct Ping(u32);
+    /// # impl Handler<Ping> for Echo {
+    /// #     type Result = u32;
+    /// #     fn handle(&mut self, msg: Ping, _: &mut Context<Self>) -> u32 { msg.0 }
+    /// # }
+    /// # #[actix::main]
+    /// # async fn main() {
+    /// let addr = Echo.start();
+    /// let mut req = Box::pin(addr.send(Ping(1)));
+    /// assert_eq!(req.as_mut().await, Ok(1));
+    /// assert!(req.queued_for().is_some());
+    /// # }
+    /// ```
+    #[cfg(feature = "telemetry")]
+    pub fn queued_for(&self) -> Option<Duration> {
+        self.queued.slot.as_ref().and_then(residency::read)
+    }
 }
 
 impl<S, M> Future for MsgRequest<S, M>
@@ -72,26 +163,114 @@ where
     fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let this = self.project();
 
-        if let Some((sender, msg)) = this.info.take() {
-            match sender.send(msg) {
-                Ok(rx) => *this.rx = Some(rx),
-                Err(SendError::Full(msg)) => {
-                    *this.info = Some((sender, msg));
-                    return Poll::Pending;
-                }
-                Err(SendError::Closed(_)) => return Poll::Ready(Err(MailboxError::Closed)),
-            }
+        #[cfg(feature = "testing")]
+        if let Some(res) = this.reply_delay.poll_delayed(cx) {
+            let timed_out = this
+                .timeout
+                .as_pin_mut()
+                .map_or(false, |timeout| timeout.poll(cx).is_ready());
+            return if timed_out {
+                Poll::Ready(Err(MailboxError::Timeout))
+            } else {
+                res.map(Ok)
+            };
         }
 
         match this.rx {
             Some(rx) => match Pin::new(rx).poll(cx) {
-                Poll::Ready(res) => Poll::Ready(res.map_err(|_| MailboxError::Closed)),
+                Poll::Ready(Ok(res)) => {
+                    #[cfg(feature = "testing")]
+                    let res = match this.reply_delay.hold(res, cx) {
+                        Some(res) => res,
+                        None => return Poll::Pending,
+                    };
+                    Poll::Ready(Ok(res))
+                }
+                // a reply dropped past the timeout, e.g. by an expired deadline, timed out
+                Poll::Ready(Err(_)) => match this.timeout.as_pin_mut() {
+                    Some(timeout) if timeout.is_elapsed() => {
+                        Poll::Ready(Err(MailboxError::Timeout))
+                    }
+                    _ if this
+                        .shutdown
+                        .as_ref()
+                        .map_or(false, SystemShutdown::has_begun) =>
+                    {
+                        Poll::Ready(Err(MailboxError::SystemStopping))
+                    }
+                    _ => Poll::Ready(Err(MailboxError::Closed)),
+                },
                 Poll::Pending => match this.timeout.as_pin_mut() {
                     Some(timeout) => timeout.poll(cx).map(|_| Err(MailboxError::Timeout)),
                     None => Poll::Pending,
                 },
             },
-            None => Poll::Ready(Err(MailboxError::Closed)),
+            None => Poll::Ready(Err(*this.err)),
+        }
+    }
+}
+
+/// Residency of the envelope of a request, see [`MsgRequest::queued_for()`].
+///
+/// Empty without the `telemetry` feature.
+#[derive(Default)]
+struct QueuedFor {
+    #[cfg(feature = "telemetry")]
+    slot: Option<ResidencySlot>,
+}
+
+/// Delay injected into the reply of a request, see [`crate::test::FaultPlan::delay_replies`].
+///
+/// Empty without the `testing` feature.
+struct ReplyDelay<R> {
+    #[cfg(feature = "testing")]
+    delay: Option<Duration>,
+    // the reply, held until the delay has elapsed
+    #[cfg(feature = "testing")]
+    held: Option<(R, Pin<Box<Timer>>)>,
+    _reply: PhantomData<fn() -> R>,
+}
+
+impl<R> ReplyDelay<R> {
+    fn none() -> Self {
+        ReplyDelay {
+            #[cfg(feature = "testing")]
+            delay: None,
+            #[cfg(feature = "testing")]
+            held: None,
+            _reply: PhantomData,
         }
     }
+
+    #[cfg(feature = "testing")]
+    fn new(delay: Option<Duration>) -> Self {
+        ReplyDelay {
+            delay,
+            ..Self::none()
+        }
+    }
+
+    /// Holds `res` until the delay has elapsed, returns it if there is no delay.
+    #[cfg(feature = "testing")]
+    fn hold(&mut self, res: R, cx: &mut task::Context<'_>) -> Option<R> {
+        match self.delay.take() {
+            Some(delay) => {
+                self.held = Some((res, Box::pin(Timer::after(delay))));
+                // polls the delay right away
+                cx.waker().wake_by_ref();
+                None
+            }
+            None => Some(res),
+        }
+    }
+
+    /// Polls the held reply, returns `None` if no reply is held.
+    #[cfg(feature = "testing")]
+    fn poll_delayed(&mut self, cx: &mut task::Context<'_>) -> Option<Poll<R>> {
+        let (_, delay) = self.held.as_mut()?;
+        Some(match delay.as_mut().poll(cx) {
+            Poll::Ready(()) => Poll::Ready(self.held.take().unwrap().0),
+            Poll::Pending => Poll::Pending,
+        })
+    }
 }
@@ -1,34 +1,95 @@
 use std::{
     error, fmt,
     hash::{Hash, Hasher},
+    sync::Arc,
 };
 
 pub(crate) mod channel;
+mod closed;
 mod envelope;
+mod fanout;
+mod legacy;
+mod limit;
+mod link;
 mod message;
+mod protocol;
 mod queue;
+mod revocable;
+mod signal;
+mod sink;
+mod state;
+mod stop;
+mod tap;
+mod transform;
+mod unhandled;
 
 pub(crate) use self::channel::{AddressReceiver, AddressSenderProducer};
 use self::channel::{AddressSender, Sender, WeakAddressSender, WeakSender};
+pub(crate) use self::envelope::SyncEnvelopeProxy;
+use self::envelope::{DeadlineEnvelope, StopEnvelope};
+use self::legacy::LegacySender;
+pub(crate) use self::link::link;
+use self::revocable::RevocableSender;
+pub(crate) use self::signal::handle_coalesced;
+pub(crate) use self::tap::Tap;
+use self::transform::TransformEnvelope;
 pub use self::{
-    envelope::{Envelope, EnvelopeProxy, ToEnvelope},
+    closed::Closed,
+    envelope::{to_envelope, Envelope, EnvelopeProxy, TestEnvelopeSink, ToEnvelope},
+    fanout::{
+        broadcast, broadcast_async, send_all, send_all_recipients, BroadcastAsync, BroadcastReport,
+        SendAll, SendAllSettled,
+    },
+    limit::{RateLimit, RateLimitPolicy},
+    link::{ExitReason, LinkedExit},
     message::{RecipientRequest, Request},
+    protocol::{ProtoAddr, ProtoRequest, ProtocolEnd, ProtocolState, ProtocolStep},
+    revocable::RevokeHandle,
+    sink::{AddressSink, RecipientSink},
+    state::StateStream,
+    stop::{StopReason, StopWait, StopWaitError},
+    transform::TransformOnSend,
+    unhandled::UnhandledMessage,
 };
+
 use crate::{
-    actor::Actor,
-    handler::{Handler, Message},
+    actor::{Actor, AsyncContext},
+    clock::{self, Instant},
+    contextimpl::AsyncContextParts,
+    handler::{Handler, Message, Query},
+    middleware::Rejection,
+    queue::oneshot,
+    sync::{Progress, ProgressEnvelope, SyncContext},
 };
 
+#[non_exhaustive]
 pub enum SendError<T> {
     Full(T),
     Closed(T),
+    /// The recipient was revoked through its [`RevokeHandle`].
+    Revoked(T),
+    /// The message was rejected by the actor's [`RateLimit`].
+    RateLimited(T),
+    /// The message was rejected by a [`MessageMiddleware`](crate::MessageMiddleware).
+    Rejected(T, Rejection),
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 /// The errors that can occur during the message delivery process.
+#[non_exhaustive]
 pub enum MailboxError {
     Closed,
     Timeout,
+    /// The mailbox is full, and so are the messages waiting behind it for the sending address.
+    Full,
+    /// The message was rejected by the actor's [`RateLimit`].
+    RateLimited,
+    /// The system is stopping, the message will not be handled, see [`stop_gracefully()`].
+    ///
+    /// [`stop_gracefully()`]: crate::stop_gracefully
+    SystemStopping,
+    /// The message was rejected by a [`MessageMiddleware`](crate::MessageMiddleware).
+    Rejected(Rejection),
 }
 
 impl fmt::Debug for MailboxError {
@@ -42,6 +103,10 @@ impl fmt::Display for MailboxError {
         match self {
             MailboxError::Closed => write!(fmt, "Mailbox has closed"),
             MailboxError::Timeout => write!(fmt, "Message delivery timed out"),
+            MailboxError::Full => write!(fmt, "Mailbox is full"),
+            MailboxError::RateLimited => write!(fmt, "Message was rejected by rate limit"),
+            MailboxError::SystemStopping => write!(fmt, "System is stopping"),
+            MailboxError::Rejected(rejection) => write!(fmt, "Message was rejected: {}", rejection),
         }
     }
 }
@@ -51,7 +116,11 @@ impl error::Error for MailboxError {}
 impl<T> SendError<T> {
     pub fn into_inner(self) -> T {
         match self {
-            SendError::Full(msg) | SendError::Closed(msg) => msg,
+            SendError::Full(msg)
+            | SendError::Closed(msg)
+            | SendError::Revoked(msg)
+            | SendError::RateLimited(msg)
+            | SendError::Rejected(msg, _) => msg,
         }
     }
 }
@@ -63,6 +132,11 @@ impl<T> fmt::Debug for SendError<T> {
         match *self {
             SendError::Full(_) => write!(fmt, "SendError::Full(..)"),
             SendError::Closed(_) => write!(fmt, "SendError::Closed(..)"),
+            SendError::Revoked(_) => write!(fmt, "SendError::Revoked(..)"),
+            SendError::RateLimited(_) => write!(fmt, "SendError::RateLimited(..)"),
+            SendError::Rejected(_, rejection) => {
+                write!(fmt, "SendError::Rejected(.., {:?})", rejection)
+            }
         }
     }
 }
@@ -72,11 +146,27 @@ impl<T> fmt::Display for SendError<T> {
         match *self {
             SendError::Full(_) => write!(fmt, "send failed because receiver is full"),
             SendError::Closed(_) => write!(fmt, "send failed because receiver is gone"),
+            SendError::Revoked(_) => write!(fmt, "send failed because recipient was revoked"),
+            SendError::RateLimited(_) => write!(fmt, "send failed because of rate limit"),
+            SendError::Rejected(_, rejection) => {
+                write!(
+                    fmt,
+                    "send failed because message was rejected: {}",
+                    rejection
+                )
+            }
         }
     }
 }
 
 /// The address of an actor.
+///
+/// Messages sent through the same address reach the actor in the order they were sent, whether
+/// they are sent with [`send()`](Self::send), [`do_send()`](Self::do_send) or
+/// [`try_send()`](Self::try_send). This holds for [`Context`](crate::Context) based actors as
+/// well as for a [`SyncArbiter`](crate::SyncArbiter), whose threads take messages in that order.
+/// Clones of an address are separate senders, so messages sent through different clones may be
+/// interleaved.
 pub struct Addr<A: Actor> {
     tx: AddressSender<A>,
 }
@@ -92,6 +182,111 @@ impl<A: Actor> Addr<A> {
         self.tx.connected()
     }
 
+    /// Returns a future which resolves once the actor's mailbox is closed.
+    ///
+    /// The mailbox is closed when the actor has stopped and its context was dropped. The future
+    /// resolves immediately if that has already happened. Like [`connected()`](Self::connected),
+    /// it does not require sending a message to the actor, and it does not keep the actor alive.
+    pub fn closed(&self) -> Closed {
+        self.tx.closed()
+    }
+
+    /// Returns a stream of the actor's state transitions.
+    ///
+    /// The stream yields the current state first and completes once the actor has stopped.
+    /// Every subscriber sees all transitions in order. The stream does not keep the actor alive.
+    ///
+    /// States are published by [`Context`](crate::Context) and
+    /// [`MinimalContext`](crate::MinimalContext) based actors. For actors running in
+    /// a [`SyncArbiter`](crate::SyncArbiter) the stream only yields `Started` and completes
+    /// once the arbiter is gone.
+    pub fn state_stream(&self) -> StateStream {
+        self.tx.state_stream()
+    }
+
+    /// Requests the actor to interrupt the message or wait future it is working on.
+    ///
+    /// The request does not wait in the mailbox: the actor observes it through
+    /// [`AsyncContext::interrupted()`] right away, including within a running handler, and
+    /// futures made [`interruptible`](crate::ActorFutureExt::interruptible) resolve early. The
+    /// interrupt is cleared before the next message is handled, unless the actor made it sticky
+    /// with [`Context::set_interrupt_sticky()`](crate::Context::set_interrupt_sticky).
+    /// Actors running in a [`SyncArbiter`](crate::SyncArbiter) are not interrupted.
+    pub fn interrupt(&self) {
+        self.tx.interrupt()
+    }
+
+    /// Asks the actor to stop once it has handled the messages queued so far, and returns a
+    /// future which resolves once it has stopped.
+    ///
+    /// The future resolves with [`StopReason::AlreadyStopped`] right away if the actor had
+    /// already stopped. It fails with [`StopWaitError::Vetoed`] if the actor refuses to stop in
+    /// [`Actor::stopping()`], and with [`StopWaitError::Timeout`] once `deadline` has passed.
+    /// It can be awaited from any thread and does not keep the actor alive.
+    ///
+    /// Like [`state_stream()`](Self::state_stream), the future relies on the states published
+    /// by [`Context`](crate::Context) and [`MinimalContext`](crate::MinimalContext) based
+    /// actors. For actors running in a [`SyncArbiter`](crate::SyncArbiter) it fails with
+    /// [`StopWaitError::Disconnected`] once the arbiter is gone.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actix::prelude::*;
+    /// use actix::{clock::Instant, StopReason};
+    ///
+    /// struct Server;
+    ///
+    /// impl Actor for Server {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let addr = Server.start();
+    ///     let deadline = Instant::now() + Duration::from_secs(1);
+    ///
+    ///     // the port of the server can be reused from now on
+    ///     assert_eq!(addr.stop_and_wait(deadline).await, Ok(StopReason::Stopped));
+    /// }
+    /// ```
+    pub fn stop_and_wait(&self, deadline: Instant) -> StopWait {
+        // subscribe first, so the transitions caused by the request are seen
+        let states = self.state_stream();
+        self.request_stop();
+        StopWait::new(states, deadline)
+    }
+
+    /// Asks the actor to stop once it has handled the messages queued so far.
+    ///
+    /// Returns `false` if the actor is already gone.
+    pub(crate) fn request_stop(&self) -> bool {
+        self.push_envelope(Envelope::with_proxy(Box::new(StopEnvelope)))
+    }
+
+    /// Queues an envelope regardless of the mailbox capacity.
+    ///
+    /// Returns `false` if the actor is already gone.
+    pub(crate) fn push_envelope(&self, env: Envelope<A>) -> bool {
+        self.tx.push_envelope(env)
+    }
+
+    pub(crate) fn into_sender(self) -> AddressSender<A> {
+        self.tx
+    }
+
+    /// Returns an address of the same actor which reports the messages sent through it, and
+    /// its clones, to `tap`.
+    pub(crate) fn tapped(&self, tap: Arc<dyn Tap>) -> Self {
+        Addr::new(self.tx.tapped(tap))
+    }
+
+    /// Injects `faults` into the mailbox of the actor, see [`crate::test::inject`].
+    #[cfg(feature = "testing")]
+    pub(crate) fn set_faults(&self, faults: Arc<crate::fault::Faults>) {
+        self.tx.set_faults(faults)
+    }
+
     /// Sends a message unconditionally, ignoring any potential errors.
     ///
     /// The message is always queued, even if the mailbox for the receiver is full. If the mailbox
@@ -99,7 +294,7 @@ impl<A: Actor> Addr<A> {
     #[inline]
     pub fn do_send<M>(&self, msg: M)
     where
-        M: Message + Send,
+        M: Message + Send + 'static,
         M::Result: Send,
         A: Handler<M>,
         A::Context: ToEnvelope<A, M>,
@@ -107,6 +302,58 @@ impl<A: Actor> Addr<A> {
         let _ = self.tx.do_send(msg);
     }
 
+    /// Sends a signal, a zero-sized message without reply, bypassing the mailbox.
+    ///
+    /// Signals are counted per message type instead of being queued, so sending them does not
+    /// allocate once the first signal of their type was sent. Only the first pending signal of a
+    /// type wakes up the actor. The actor handles the pending signals at the start of its next
+    /// poll, before the messages queued in its mailbox, with messages synthesized by `Default`:
+    /// [`Handler::handle`] is called once per signal, or
+    /// [`SignalHandler::handle_signals()`](crate::SignalHandler::handle_signals) once with
+    /// their number if [`Context::coalesce_signals()`](crate::Context::coalesce_signals) was
+    /// called. Signals sent to a stopped actor are dropped. Sending a signal which is not
+    /// zero-sized fails to build.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix::prelude::*;
+    /// #[derive(Default, Message)]
+    /// #[rtype(result = "()")]
+    /// struct Tick;
+    ///
+    /// struct Clock {
+    ///     ticks: usize,
+    /// }
+    ///
+    /// impl Actor for Clock {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<Tick> for Clock {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, _: Tick, _: &mut Self::Context) {
+    ///         self.ticks += 1;
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let addr = Clock { ticks: 0 }.start();
+    ///     for _ in 0..1000 {
+    ///         addr.signal::<Tick>();
+    ///     }
+    /// }
+    /// ```
+    pub fn signal<M>(&self)
+    where
+        M: Message + Default + 'static,
+        A: Handler<M>,
+        A::Context: AsyncContext<A>,
+    {
+        self.tx.signal::<M>()
+    }
+
     /// Tries to send a message.
     ///
     /// This method fails if actor's mailbox is full or closed. This
@@ -123,8 +370,50 @@ impl<A: Actor> Addr<A> {
 
     /// Sends an asynchronous message and waits for a response.
     ///
-    /// The communication channel to the actor is bounded. If the returned request future gets
-    /// dropped, the message is cancelled.
+    /// The communication channel to the actor is bounded. Once the mailbox is full, further
+    /// messages sent through this address wait in order behind the ones already sent, and are
+    /// queued as the actor makes room. Up to the mailbox capacity of messages can wait this way,
+    /// beyond that the request fails with [`MailboxError::Full`].
+    ///
+    /// The message is queued whether or not the returned request future is kept. If it gets
+    /// dropped, the message still takes its place in the mailbox, but its handler is skipped.
+    ///
+    /// The request is a plain [`Future`](std::future::Future), so it can be awaited outside of
+    /// any actor, e.g. in a free function or a future spawned on an arbiter. Its `timeout()`
+    /// method makes it fail with [`MailboxError::Timeout`] after a delay.
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actix::prelude::*;
+    /// struct Counter(usize);
+    ///
+    /// impl Actor for Counter {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "usize")]
+    /// struct Increment;
+    ///
+    /// impl Handler<Increment> for Counter {
+    ///     type Result = usize;
+    ///
+    ///     fn handle(&mut self, _: Increment, _: &mut Self::Context) -> usize {
+    ///         self.0 += 1;
+    ///         self.0
+    ///     }
+    /// }
+    ///
+    /// async fn increment(counter: &Addr<Counter>) -> Result<usize, MailboxError> {
+    ///     counter.send(Increment).timeout(Duration::from_secs(1)).await
+    /// }
+    ///
+    /// # #[actix::main]
+    /// # async fn main() {
+    /// let counter = Counter(0).start();
+    /// assert_eq!(increment(&counter).await, Ok(1));
+    /// # }
+    /// ```
     #[inline]
     pub fn send<M>(&self, msg: M) -> Request<A, M>
     where
@@ -133,24 +422,267 @@ impl<A: Actor> Addr<A> {
         A: Handler<M>,
         A::Context: ToEnvelope<A, M>,
     {
-        match self.tx.send(msg) {
-            Ok(rx) => Request::new(Some(rx), None),
-            Err(SendError::Full(msg)) => Request::new(None, Some((self.tx.clone(), msg))),
-            Err(SendError::Closed(_)) => Request::new(None, None),
-        }
+        Request::sent(&self.tx, || self.tx.send(msg))
+    }
+
+    /// Sends a query and asynchronously waits for the response chosen by the actor's handler,
+    /// see [`Query`].
+    ///
+    /// The response type is inferred from the [`Handler`] impl of the actor, and has to be
+    /// named if the actor handles the query with several response types.
+    pub fn query<Q, R>(&self, query: Q) -> Request<A, Query<Q, R>>
+    where
+        Q: Send + 'static,
+        R: Send + 'static,
+        A: Handler<Query<Q, R>>,
+        A::Context: ToEnvelope<A, Query<Q, R>>,
+    {
+        self.send(Query::new(query))
+    }
+
+    /// Sends a message regardless of the mailbox capacity, like [`do_send()`](Self::do_send),
+    /// and waits for a response.
+    pub(crate) fn send_queued<M>(&self, msg: M) -> Request<A, M>
+    where
+        M: Message + Send + 'static,
+        M::Result: Send,
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+    {
+        let (tx, rx) = oneshot();
+        let pack = |msg| <A::Context as ToEnvelope<A, M>>::pack(msg, Some(tx));
+        Request::sent(&self.tx, || self.tx.do_send_packed(msg, pack).map(|()| rx))
+    }
+
+    /// Sends a message to a sync actor and waits for a response, forwarding the progress reported
+    /// by the handler to `progress`.
+    ///
+    /// The handler obtains a [`ProgressSender`](crate::sync::ProgressSender) with [`SyncContext::progress()`]. Progress is
+    /// delivered through the mailbox of `progress`, so it is not ordered with respect to the
+    /// response.
+    ///
+    /// ```
+    /// # use actix::prelude::*;
+    /// use actix::sync::{Progress, ProgressSender};
+    ///
+    /// struct Worker;
+    ///
+    /// impl Actor for Worker {
+    ///     type Context = SyncContext<Self>;
+    /// }
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "u32")]
+    /// struct Job(u32);
+    ///
+    /// impl Handler<Job> for Worker {
+    ///     type Result = u32;
+    ///
+    ///     fn handle(&mut self, job: Job, ctx: &mut Self::Context) -> u32 {
+    ///         let progress: Option<ProgressSender<u32>> = ctx.progress();
+    ///         for step in 0..job.0 {
+    ///             if let Some(progress) = &progress {
+    ///                 progress.send(step);
+    ///             }
+    ///         }
+    ///         job.0
+    ///     }
+    /// }
+    ///
+    /// struct Caller;
+    ///
+    /// impl Actor for Caller {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<Progress<u32>> for Caller {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, Progress(step): Progress<u32>, _: &mut Self::Context) {
+    ///         println!("step {step}");
+    ///     }
+    /// }
+    ///
+    /// # #[actix::main]
+    /// # async fn main() {
+    /// let worker = SyncArbiter::start(1, || Worker);
+    /// let caller = Caller.start();
+    /// let res = worker.send_with_progress(Job(3), caller.recipient()).await;
+    /// assert_eq!(res, Ok(3));
+    /// # }
+    /// ```
+    pub fn send_with_progress<M, P>(
+        &self,
+        msg: M,
+        progress: Recipient<Progress<P>>,
+    ) -> Request<A, M>
+    where
+        A: Actor<Context = SyncContext<A>> + Handler<M>,
+        M: Message + Send + 'static,
+        M::Result: Send,
+        P: Send + 'static,
+    {
+        let (tx, rx) = oneshot();
+        let pack = |msg| ProgressEnvelope::pack(msg, Some(tx), progress);
+        Request::sent(&self.tx, || self.tx.do_send_packed(msg, pack).map(|()| rx))
+    }
+
+    /// Sends a message whose handler can read `deadline` with
+    /// [`AsyncContext::request_deadline()`], and times out the request at `deadline`.
+    ///
+    /// The request is dropped without running the handler if the deadline has passed once the
+    /// actor takes it from the mailbox, and its reply is dropped if the deadline passes while the
+    /// handler runs. [`ContextStats::expired_requests`](crate::ContextStats::expired_requests)
+    /// counts the dropped requests.
+    pub fn send_with_deadline<M>(&self, msg: M, deadline: Instant) -> Request<A, M>
+    where
+        M: Message + Send + 'static,
+        M::Result: Send,
+        A: Handler<M>,
+        A::Context: AsyncContextParts<A> + ToEnvelope<A, M>,
+    {
+        let pack =
+            |msg, tx| Envelope::with_proxy(Box::new(DeadlineEnvelope::new(msg, tx, deadline)));
+        Request::sent(&self.tx, || self.tx.send_packed(msg, pack))
+            .timeout(deadline.saturating_duration_since(clock::now()))
+    }
+
+    /// Sends a message, encoding it with [`TransformOnSend`] while it is queued.
+    ///
+    /// The message is encoded on the current thread and decoded on the actor's arbiter right
+    /// before it is handled. Like [`do_send()`](Self::do_send), the message is queued even if the
+    /// mailbox is full.
+    pub fn send_transformed<M>(&self, msg: M) -> Request<A, M>
+    where
+        A: Handler<M>,
+        A::Context: AsyncContext<A> + ToEnvelope<A, M>,
+        M: TransformOnSend + Send + 'static,
+        M::Result: Send,
+    {
+        let (tx, rx) = oneshot();
+        Request::sent(&self.tx, || {
+            self.tx
+                .do_send_packed(msg, |msg| TransformEnvelope::pack(msg, Some(tx)))
+                .map(|()| rx)
+        })
+    }
+
+    /// Sends a message unconditionally, encoding it with [`TransformOnSend`] while it is queued.
+    ///
+    /// See [`send_transformed()`](Self::send_transformed).
+    pub fn do_send_transformed<M>(&self, msg: M)
+    where
+        A: Handler<M>,
+        A::Context: AsyncContext<A>,
+        M: TransformOnSend + 'static,
+        M::Result: Send,
+    {
+        let _ = self
+            .tx
+            .do_send_packed(msg, |msg| TransformEnvelope::pack(msg, None));
     }
 
     /// Returns the [`Recipient`] for a specific message type.
-    pub fn recipient<M: 'static>(self) -> Recipient<M>
+    pub fn recipient<M>(self) -> Recipient<M>
     where
         A: Handler<M>,
         A::Context: ToEnvelope<A, M>,
-        M: Message + Send,
+        M: Message + Send + 'static,
         M::Result: Send,
     {
         self.into()
     }
 
+    /// Returns a [`Sink`](futures_sink::Sink) of the messages `M`, e.g. for forwarding a stream
+    /// to the actor.
+    ///
+    /// The sink waits while the actor's mailbox is full, so a fast stream is slowed down to
+    /// the pace of the actor.
+    pub fn into_sink<M>(self) -> AddressSink<A, M>
+    where
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+        M: Message + Send + 'static,
+        M::Result: Send,
+    {
+        AddressSink::new(self.tx)
+    }
+
+    /// Returns a [`Recipient`] which can be invalidated through the returned [`RevokeHandle`].
+    ///
+    /// Once revoked, the recipient stops delivering messages, including ones that are already
+    /// queued in the actor's mailbox. Other recipients and addresses of the actor are not
+    /// affected.
+    pub fn revocable_recipient<M>(&self) -> (Recipient<M>, RevokeHandle)
+    where
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+        M: Message + Send + 'static,
+        M::Result: Send,
+    {
+        let handle = RevokeHandle::new();
+        let tx = RevocableSender::new(self.tx.clone(), handle.clone());
+        (Recipient::new(Box::new(tx)), handle)
+    }
+
+    /// Returns a [`Recipient`] accepting a previous version `Old` of a message the actor handles
+    /// as `New`.
+    ///
+    /// Queued messages are converted with `msg` right before they are handled by
+    /// `Handler<New>`, and responses are converted back with `reply`. This allows keeping
+    /// senders of the old message working while the actor is upgraded to the new one.
+    ///
+    /// ```
+    /// # use actix::prelude::*;
+    /// struct Greeter;
+    ///
+    /// impl Actor for Greeter {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "String")]
+    /// struct GreetV1(String);
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "Result<String, ()>")]
+    /// struct GreetV2 {
+    ///     name: String,
+    ///     excited: bool,
+    /// }
+    ///
+    /// impl Handler<GreetV2> for Greeter {
+    ///     type Result = Result<String, ()>;
+    ///
+    ///     fn handle(&mut self, msg: GreetV2, _: &mut Self::Context) -> Self::Result {
+    ///         let end = if msg.excited { "!" } else { "." };
+    ///         Ok(format!("Hello {}{}", msg.name, end))
+    ///     }
+    /// }
+    ///
+    /// # #[actix::main]
+    /// # async fn main() {
+    /// let legacy = Greeter.start().legacy_recipient(
+    ///     |GreetV1(name)| GreetV2 { name, excited: false },
+    ///     |res: Result<String, ()>| res.unwrap_or_default(),
+    /// );
+    /// assert_eq!(legacy.send(GreetV1("Bob".into())).await.unwrap(), "Hello Bob.");
+    /// # }
+    /// ```
+    pub fn legacy_recipient<Old, New, F, R>(&self, msg: F, reply: R) -> Recipient<Old>
+    where
+        A: Handler<New>,
+        A::Context: AsyncContext<A>,
+        Old: Message + Send + 'static,
+        Old::Result: Send,
+        New: Message + 'static,
+        F: Fn(Old) -> New + Send + Sync + 'static,
+        R: Fn(New::Result) -> Old::Result + Send + Sync + 'static,
+    {
+        let tx = LegacySender::new(self.tx.clone(), msg, reply);
+        Recipient::new(Box::new(tx))
+    }
+
     /// Returns a downgraded [`WeakAddr`].
     pub fn downgrade(&self) -> WeakAddr<A> {
         WeakAddr {
@@ -210,11 +742,11 @@ impl<A: Actor> WeakAddr<A> {
         }
     }
 
-    pub fn recipient<M: 'static>(self) -> WeakRecipient<M>
+    pub fn recipient<M>(self) -> WeakRecipient<M>
     where
         A: Handler<M>,
         A::Context: ToEnvelope<A, M>,
-        M: Message + Send,
+        M: Message + Send + 'static,
         M::Result: Send,
     {
         self.into()
@@ -285,20 +817,32 @@ where
 
     /// Sends a message and asynchronously wait for a response.
     ///
-    /// The communication channel to the actor is bounded. If the returned `RecipientRequest` object
-    /// gets dropped, the message is cancelled.
+    /// The communication channel to the actor is bounded, see [`Addr::send`] for what happens
+    /// once the mailbox is full. If the returned `RecipientRequest` gets dropped, the message
+    /// stays queued, but its handler is skipped.
     pub fn send(&self, msg: M) -> RecipientRequest<M> {
-        match self.tx.send(msg) {
-            Ok(rx) => RecipientRequest::new(Some(rx), None),
-            Err(SendError::Full(msg)) => RecipientRequest::new(None, Some((self.tx.boxed(), msg))),
-            Err(SendError::Closed(_)) => RecipientRequest::new(None, None),
-        }
+        RecipientRequest::sent(&self.tx, || self.tx.send(msg))
     }
 
     pub fn connected(&self) -> bool {
         self.tx.connected()
     }
 
+    /// Returns whether the recipient was revoked, see [`Addr::revocable_recipient`].
+    ///
+    /// Revoked recipients can never deliver messages again, so they can be discarded.
+    pub fn is_revoked(&self) -> bool {
+        self.tx.revoked()
+    }
+
+    /// Returns a [`Sink`](futures_sink::Sink) of the messages `M`, e.g. for forwarding a stream
+    /// to the actor.
+    ///
+    /// Like [`Addr::into_sink`], the sink waits while the actor's mailbox is full.
+    pub fn into_sink(self) -> RecipientSink<M> {
+        RecipientSink::new(self.tx)
+    }
+
     /// Returns a downgraded `WeakRecipient`
     pub fn downgrade(&self) -> WeakRecipient<M> {
         WeakRecipient {
@@ -499,8 +1043,9 @@ mod tests {
                 let addr2 = addr.clone();
                 let send2 = addr2.send(SetCounter(2));
                 assert!(send2.rx_is_some());
+                // the mailbox is full, the message waits behind the earlier ones of `addr2`
                 let send3 = addr2.send(SetCounter(3));
-                assert!(!send3.rx_is_some());
+                assert!(send3.rx_is_some());
 
                 let _ = send.await;
                 let _ = send2.await;
@@ -0,0 +1,209 @@
+use std::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{self, Poll},
+};
+
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+use super::{Addr, MailboxError, Request, ToEnvelope};
+use crate::{
+    actor::Actor,
+    handler::{Handler, Message},
+};
+
+/// Declares a protocol, the sequence of messages an actor expects from its peer, as the
+/// initial state of a [`ProtoAddr`].
+///
+/// Each step names a message and its response type, which has to match the `Message::Result`
+/// of the message. Once all steps are done the address is in the [`ProtocolEnd`] state, in
+/// which no message can be sent anymore.
+///
+/// # Examples
+///
+/// ```
+/// use actix::prelude::*;
+/// use actix::{protocol, ProtoAddr};
+///
+/// #[derive(Message)]
+/// #[rtype(result = "bool")]
+/// struct Hello;
+///
+/// #[derive(Message)]
+/// #[rtype(result = "()")]
+/// struct Data(u32);
+///
+/// protocol!(type Session = [Hello -> bool, Data -> ()]);
+///
+/// struct Server;
+///
+/// impl Actor for Server {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<Hello> for Server {
+///     type Result = bool;
+///
+///     fn handle(&mut self, _: Hello, _: &mut Self::Context) -> bool {
+///         true
+///     }
+/// }
+///
+/// impl Handler<Data> for Server {
+///     type Result = ();
+///
+///     fn handle(&mut self, _: Data, _: &mut Self::Context) {}
+/// }
+///
+/// # fn main() {
+/// #     System::new().block_on(async {
+/// let session = ProtoAddr::<_, Session>::new(Server.start());
+///
+/// // `session.send(Data(1))` does not compile, `Hello` has to be sent first
+/// let (accepted, session) = session.send(Hello).await.unwrap();
+/// assert!(accepted);
+/// let ((), _done) = session.send(Data(1)).await.unwrap();
+/// #     });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! protocol {
+    ($(#[$meta:meta])* $vis:vis type $name:ident = [$($steps:tt)+]) => {
+        $(#[$meta])*
+        $vis type $name = $crate::protocol!(@steps $($steps)+);
+    };
+
+    (@steps $($msg:ident)::+ -> $res:ty $(,)?) => {
+        $crate::ProtocolStep<$($msg)::+, $res, $crate::ProtocolEnd>
+    };
+
+    (@steps $($msg:ident)::+ -> $res:ty, $($rest:tt)+) => {
+        $crate::ProtocolStep<$($msg)::+, $res, $crate::protocol!(@steps $($rest)+)>
+    };
+}
+
+/// State of a [`ProtoAddr`] which accepts `M`, answered with `R`, and then moves to `Next`.
+///
+/// Protocols are usually declared with [`protocol!`](crate::protocol).
+pub struct ProtocolStep<M, R, Next> {
+    _step: PhantomData<fn(M) -> (R, Next)>,
+}
+
+/// State of a [`ProtoAddr`] whose protocol is done.
+#[derive(Debug)]
+pub struct ProtocolEnd;
+
+/// State of a [`ProtoAddr`] in which the message `M` can be sent.
+pub trait ProtocolState<M: Message> {
+    /// State after `M` was answered.
+    type Next;
+}
+
+impl<M, R, Next> ProtocolState<M> for ProtocolStep<M, R, Next>
+where
+    M: Message<Result = R>,
+{
+    type Next = Next;
+}
+
+/// Address of an actor which only allows sending the messages of a protocol in order.
+///
+/// `S` is the state of the protocol, usually declared with [`protocol!`](crate::protocol).
+/// Sending the message expected in the current state consumes the address and returns it
+/// in the next state along with the response, so sending a message out of order fails to
+/// compile. Messages are delivered like with [`Addr::send()`], the states only exist at
+/// compile time.
+pub struct ProtoAddr<A: Actor, S> {
+    addr: Addr<A>,
+    _state: PhantomData<fn() -> S>,
+}
+
+impl<A: Actor, S> ProtoAddr<A, S> {
+    /// Starts the protocol `S` with the actor of `addr`.
+    pub fn new(addr: Addr<A>) -> Self {
+        ProtoAddr {
+            addr,
+            _state: PhantomData,
+        }
+    }
+
+    /// Returns the address of the actor, which is not restricted to the protocol.
+    pub fn addr(&self) -> &Addr<A> {
+        &self.addr
+    }
+
+    /// Gives up the protocol, returning the address of the actor.
+    pub fn into_inner(self) -> Addr<A> {
+        self.addr
+    }
+
+    /// Sends the message expected in the current state and asynchronously waits for the
+    /// response, along with the address in the next state.
+    pub fn send<M>(self, msg: M) -> ProtoRequest<A, M, S::Next>
+    where
+        S: ProtocolState<M>,
+        M: Message + Send + 'static,
+        M::Result: Send,
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+    {
+        ProtoRequest {
+            request: self.addr.send(msg),
+            addr: Some(self.addr),
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<A: Actor, S> fmt::Debug for ProtoAddr<A, S> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ProtoAddr")
+            .field("addr", &self.addr)
+            .field("state", &std::any::type_name::<S>())
+            .finish()
+    }
+}
+
+pin_project! {
+    /// Future returned by [`ProtoAddr::send()`], resolving with the response and the address
+    /// in the next state of the protocol.
+    #[must_use = "You must wait on the request otherwise the Message will not be delivered"]
+    pub struct ProtoRequest<A, M, Next>
+    where
+        A: Actor,
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+        M: Message,
+        M: Send,
+        M: 'static,
+        M::Result: Send
+    {
+        #[pin]
+        request: Request<A, M>,
+        addr: Option<Addr<A>>,
+        _state: PhantomData<fn() -> Next>,
+    }
+}
+
+impl<A, M, Next> Future for ProtoRequest<A, M, Next>
+where
+    A: Actor + Handler<M>,
+    A::Context: ToEnvelope<A, M>,
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    type Output = Result<(M::Result, ProtoAddr<A, Next>), MailboxError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.request.poll(cx))?;
+        let addr = this
+            .addr
+            .take()
+            .expect("ProtoRequest polled after completion");
+        Poll::Ready(Ok((res, ProtoAddr::new(addr))))
+    }
+}
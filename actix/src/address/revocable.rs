@@ -0,0 +1,207 @@
+use std::{
+    any::type_name,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{self, Poll},
+};
+
+use super::{
+    channel::{AddressSender, Sender, WeakAddressSender, WeakSender},
+    Envelope, EnvelopeProxy, SendError, ToEnvelope, UnhandledMessage,
+};
+use crate::{
+    actor::Actor,
+    arbiter::SystemShutdown,
+    deadletter::{DeadLetterReason, DeadLetterSink},
+    handler::{Handler, Message},
+    queue::OneshotReceiver,
+};
+
+/// Handle which revokes a recipient created by
+/// [`Addr::revocable_recipient`](super::Addr::revocable_recipient).
+///
+/// The handle is cheap to clone and can be moved to other threads.
+#[derive(Debug, Clone)]
+pub struct RevokeHandle {
+    revoked: Arc<AtomicBool>,
+}
+
+impl RevokeHandle {
+    pub(crate) fn new() -> Self {
+        RevokeHandle {
+            revoked: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Revokes the recipient.
+    ///
+    /// Further sends fail with [`SendError::Revoked`] and messages which are already queued are
+    /// passed to [`Actor::unhandled()`] instead of being handled, which by default drops them,
+    /// so their requests fail with [`MailboxError::Closed`](super::MailboxError::Closed). A
+    /// message whose handling started before this call is not interrupted.
+    pub fn revoke(&self) {
+        self.revoked.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns whether the recipient was revoked.
+    pub fn is_revoked(&self) -> bool {
+        self.revoked.load(Ordering::SeqCst)
+    }
+}
+
+/// Sender which checks the revoke flag on every send and again before handling.
+pub(crate) struct RevocableSender<A: Actor> {
+    tx: AddressSender<A>,
+    handle: RevokeHandle,
+}
+
+impl<A: Actor> RevocableSender<A> {
+    pub(crate) fn new(tx: AddressSender<A>, handle: RevokeHandle) -> Self {
+        RevocableSender { tx, handle }
+    }
+
+    fn wrap<M: 'static>(&self, ask: bool) -> impl FnOnce(Envelope<A>) -> Envelope<A> {
+        let handle = self.handle.clone();
+        let dead_letters = self.tx.dead_letters();
+        move |env| {
+            Envelope::with_proxy(Box::new(RevocableEnvelope {
+                env: Some(env),
+                handle,
+                type_name: type_name::<M>(),
+                ask,
+                dead_letters,
+            }))
+        }
+    }
+
+    /// Rejects a message sent through the revoked recipient.
+    fn revoked<M: 'static>(&self, msg: M, ask: bool) -> SendError<M> {
+        self.tx
+            .dead_letters()
+            .record(type_name::<M>(), ask, DeadLetterReason::Revoked);
+        SendError::Revoked(msg)
+    }
+}
+
+impl<A, M> Sender<M> for RevocableSender<A>
+where
+    A: Handler<M>,
+    A::Context: ToEnvelope<A, M>,
+    M::Result: Send,
+    M: Message + Send + 'static,
+{
+    fn do_send(&self, msg: M) -> Result<(), SendError<M>> {
+        if self.handle.is_revoked() {
+            return Err(self.revoked(msg, false));
+        }
+        self.tx.do_send_with(msg, self.wrap::<M>(false))
+    }
+
+    fn try_send(&self, msg: M) -> Result<(), SendError<M>> {
+        if self.handle.is_revoked() {
+            return Err(self.revoked(msg, false));
+        }
+        self.tx.try_send_with(msg, true, self.wrap::<M>(false))
+    }
+
+    fn send(&self, msg: M) -> Result<OneshotReceiver<M::Result>, SendError<M>> {
+        if self.handle.is_revoked() {
+            return Err(self.revoked(msg, true));
+        }
+        self.tx.send_with(msg, self.wrap::<M>(true))
+    }
+
+    fn boxed(&self) -> Box<dyn Sender<M> + Sync> {
+        Box::new(RevocableSender::new(self.tx.clone(), self.handle.clone()))
+    }
+
+    fn hash(&self) -> usize {
+        // distinguishes subscriptions to the same actor
+        Arc::as_ptr(&self.handle.revoked) as usize
+    }
+
+    fn connected(&self) -> bool {
+        !self.handle.is_revoked() && self.tx.connected()
+    }
+
+    fn poll_ready(&self, cx: &mut task::Context<'_>) -> Poll<()> {
+        self.tx.poll_ready(cx)
+    }
+
+    fn revoked(&self) -> bool {
+        self.handle.is_revoked()
+    }
+
+    fn system_shutdown(&self) -> Option<SystemShutdown> {
+        self.tx.system_shutdown()
+    }
+
+    #[cfg(feature = "testing")]
+    fn faults(&self) -> Option<Arc<crate::fault::Faults>> {
+        self.tx.faults()
+    }
+
+    fn downgrade(&self) -> Box<dyn WeakSender<M> + Sync + 'static> {
+        Box::new(WeakRevocableSender {
+            tx: self.tx.downgrade(),
+            handle: self.handle.clone(),
+        })
+    }
+}
+
+struct WeakRevocableSender<A: Actor> {
+    tx: WeakAddressSender<A>,
+    handle: RevokeHandle,
+}
+
+impl<A, M> WeakSender<M> for WeakRevocableSender<A>
+where
+    A: Handler<M>,
+    A::Context: ToEnvelope<A, M>,
+    M::Result: Send,
+    M: Message + Send + 'static,
+{
+    fn upgrade(&self) -> Option<Box<dyn Sender<M> + Sync>> {
+        self.tx
+            .upgrade()
+            .map(|tx| Box::new(RevocableSender::new(tx, self.handle.clone())) as _)
+    }
+
+    fn boxed(&self) -> Box<dyn WeakSender<M> + Sync> {
+        Box::new(WeakRevocableSender {
+            tx: self.tx.clone(),
+            handle: self.handle.clone(),
+        })
+    }
+}
+
+/// Envelope which is passed to `Actor::unhandled()` instead of handled once revoked.
+struct RevocableEnvelope<A: Actor> {
+    env: Option<Envelope<A>>,
+    handle: RevokeHandle,
+    type_name: &'static str,
+    ask: bool,
+    dead_letters: DeadLetterSink,
+}
+
+impl<A: Actor> EnvelopeProxy<A> for RevocableEnvelope<A> {
+    fn handle(&mut self, act: &mut A, ctx: &mut A::Context) {
+        let mut env = match self.env.take() {
+            Some(env) => env,
+            None => return,
+        };
+        if self.handle.is_revoked() {
+            self.dead_letters
+                .record(self.type_name, self.ask, DeadLetterReason::Revoked);
+            act.unhandled(UnhandledMessage::new(self.type_name, self.ask, env), ctx);
+        } else {
+            env.handle(act, ctx)
+        }
+    }
+
+    fn message_info(&self) -> Option<(&'static str, bool)> {
+        Some((self.type_name, self.ask))
+    }
+}
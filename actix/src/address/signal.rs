@@ -0,0 +1,148 @@
+use std::{
+    any::TypeId,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use parking_lot::RwLock;
+
+use crate::{
+    actor::{Actor, AsyncContext},
+    handler::{Handler, Message, MessageResponse, SignalHandler},
+};
+
+/// Handles `count` signals of a type, returns the number of signals left to handle.
+pub(crate) type SignalFn<A> = fn(&mut A, &mut <A as Actor>::Context, usize) -> usize;
+
+/// Pending signals of a single message type.
+struct SignalSlot<A: Actor> {
+    msg_type: TypeId,
+    pending: AtomicUsize,
+    dispatch: SignalFn<A>,
+}
+
+/// Counters of the signals sent to an actor, see [`Addr::signal()`](crate::Addr::signal).
+///
+/// A slot is allocated for each message type on its first signal, further signals only
+/// increment its counter.
+pub(crate) struct Signals<A: Actor> {
+    slots: RwLock<Vec<SignalSlot<A>>>,
+    /// Set once a signal is pending, so the mailbox skips the slots until then.
+    raised: AtomicBool,
+}
+
+impl<A: Actor> Signals<A> {
+    pub(crate) fn new() -> Self {
+        Signals {
+            slots: RwLock::new(Vec::new()),
+            raised: AtomicBool::new(false),
+        }
+    }
+
+    /// Counts a signal of `M`, returns `true` if it is the first pending one of its type.
+    pub(crate) fn raise<M: 'static>(&self, dispatch: SignalFn<A>) -> bool {
+        let msg_type = TypeId::of::<M>();
+        let first = {
+            let slots = self.slots.read();
+            slots
+                .iter()
+                .find(|slot| slot.msg_type == msg_type)
+                .map(|slot| slot.pending.fetch_add(1, Ordering::AcqRel) == 0)
+        };
+        let first = first.unwrap_or_else(|| {
+            let mut slots = self.slots.write();
+            match slots.iter().find(|slot| slot.msg_type == msg_type) {
+                Some(slot) => slot.pending.fetch_add(1, Ordering::AcqRel) == 0,
+                None => {
+                    slots.push(SignalSlot {
+                        msg_type,
+                        pending: AtomicUsize::new(1),
+                        dispatch,
+                    });
+                    true
+                }
+            }
+        });
+        self.raised.store(true, Ordering::Release);
+        first
+    }
+
+    /// Handles the signals of `M` with `dispatch`, instead of the one passed by their senders.
+    pub(crate) fn set_dispatch<M: 'static>(&self, dispatch: SignalFn<A>) {
+        let msg_type = TypeId::of::<M>();
+        let mut slots = self.slots.write();
+        match slots.iter_mut().find(|slot| slot.msg_type == msg_type) {
+            Some(slot) => slot.dispatch = dispatch,
+            None => slots.push(SignalSlot {
+                msg_type,
+                pending: AtomicUsize::new(0),
+                dispatch,
+            }),
+        }
+    }
+
+    /// Returns `true` if a signal may be pending.
+    pub(crate) fn is_raised(&self) -> bool {
+        self.raised.load(Ordering::Acquire)
+    }
+
+    /// Handles the pending signals, until the actor waits for a future.
+    pub(crate) fn dispatch(&self, act: &mut A, ctx: &mut A::Context)
+    where
+        A::Context: AsyncContext<A>,
+    {
+        if !self.raised.swap(false, Ordering::AcqRel) {
+            return;
+        }
+
+        // the slots are not locked while handlers run, which may send signals themselves
+        let len = self.slots.read().len();
+        for idx in 0..len {
+            if ctx.waiting() {
+                self.raised.store(true, Ordering::Release);
+                return;
+            }
+            let (count, dispatch) = {
+                let slots = self.slots.read();
+                let slot = &slots[idx];
+                (slot.pending.swap(0, Ordering::AcqRel), slot.dispatch)
+            };
+            if count == 0 {
+                continue;
+            }
+            let left = dispatch(act, ctx, count);
+            if left > 0 {
+                self.slots.read()[idx]
+                    .pending
+                    .fetch_add(left, Ordering::AcqRel);
+                self.raised.store(true, Ordering::Release);
+            }
+        }
+    }
+}
+
+/// Calls the handler of `M` once per signal, with a message synthesized by `Default`.
+pub(crate) fn handle_each<A, M>(act: &mut A, ctx: &mut A::Context, count: usize) -> usize
+where
+    A: Handler<M>,
+    A::Context: AsyncContext<A>,
+    M: Message + Default,
+{
+    for handled in 0..count {
+        // the handler may wait for a future, which holds back the following signals
+        if ctx.waiting() {
+            return count - handled;
+        }
+        <A as Handler<M>>::handle(act, M::default(), ctx).handle(ctx, None);
+    }
+    0
+}
+
+/// Calls the signal handler of `M` once with the number of signals.
+pub(crate) fn handle_coalesced<A, M>(act: &mut A, ctx: &mut A::Context, count: usize) -> usize
+where
+    A: SignalHandler<M>,
+    M: Message,
+{
+    act.handle_signals(count, ctx);
+    0
+}
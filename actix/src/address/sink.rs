@@ -0,0 +1,165 @@
+use std::{
+    fmt,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_sink::Sink;
+
+use super::{
+    channel::{AddressSender, Sender},
+    MailboxError, SendError, ToEnvelope,
+};
+use crate::{
+    actor::Actor,
+    handler::{Handler, Message},
+};
+
+/// [`Sink`] of the messages `M` of an actor, created by [`Addr::into_sink`](super::Addr::into_sink).
+///
+/// The sink is ready as long as the actor's mailbox has capacity left. Once a message fills the
+/// mailbox, the sink waits until the actor takes messages out of it. Accepted messages are
+/// queued right away, so flushing and closing the sink complete immediately. Messages are sent
+/// like with [`Addr::do_send`](super::Addr::do_send), their results are dropped.
+pub struct AddressSink<A: Actor, M> {
+    tx: AddressSender<A>,
+    _msg: PhantomData<fn(M)>,
+}
+
+impl<A: Actor, M> AddressSink<A, M> {
+    pub(crate) fn new(tx: AddressSender<A>) -> Self {
+        AddressSink {
+            tx,
+            _msg: PhantomData,
+        }
+    }
+}
+
+impl<A: Actor, M> Clone for AddressSink<A, M> {
+    fn clone(&self) -> Self {
+        AddressSink::new(self.tx.clone())
+    }
+}
+
+impl<A: Actor, M> fmt::Debug for AddressSink<A, M> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("AddressSink")
+            .field("tx", &self.tx)
+            .finish()
+    }
+}
+
+impl<A, M> Sink<M> for AddressSink<A, M>
+where
+    A: Handler<M>,
+    A::Context: ToEnvelope<A, M>,
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    type Error = MailboxError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if !self.tx.connected() {
+            return Poll::Ready(Err(MailboxError::Closed));
+        }
+        self.tx.poll_ready(cx).map(Ok)
+    }
+
+    fn start_send(self: Pin<&mut Self>, msg: M) -> Result<(), Self::Error> {
+        let res = match self.tx.try_send(msg, true) {
+            // `poll_ready()` was not awaited, exceed the capacity like `do_send()`
+            Err(SendError::Full(msg)) => self.tx.do_send(msg),
+            res => res,
+        };
+        res.map_err(|err| match err {
+            SendError::RateLimited(_) => MailboxError::RateLimited,
+            _ => MailboxError::Closed,
+        })
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// [`Sink`] of the messages `M` of a recipient, created by
+/// [`Recipient::into_sink`](super::Recipient::into_sink).
+///
+/// Behaves like [`AddressSink`], for code which only knows the message type of the actor.
+pub struct RecipientSink<M>
+where
+    M: Message + Send,
+    M::Result: Send,
+{
+    tx: Box<dyn Sender<M> + Sync>,
+}
+
+impl<M> RecipientSink<M>
+where
+    M: Message + Send,
+    M::Result: Send,
+{
+    pub(crate) fn new(tx: Box<dyn Sender<M> + Sync>) -> Self {
+        RecipientSink { tx }
+    }
+}
+
+impl<M> Clone for RecipientSink<M>
+where
+    M: Message + Send,
+    M::Result: Send,
+{
+    fn clone(&self) -> Self {
+        RecipientSink::new(self.tx.boxed())
+    }
+}
+
+impl<M> fmt::Debug for RecipientSink<M>
+where
+    M: Message + Send,
+    M::Result: Send,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("RecipientSink").finish_non_exhaustive()
+    }
+}
+
+impl<M> Sink<M> for RecipientSink<M>
+where
+    M: Message + Send,
+    M::Result: Send,
+{
+    type Error = MailboxError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if !self.tx.connected() {
+            return Poll::Ready(Err(MailboxError::Closed));
+        }
+        self.tx.poll_ready(cx).map(Ok)
+    }
+
+    fn start_send(self: Pin<&mut Self>, msg: M) -> Result<(), Self::Error> {
+        let res = match self.tx.try_send(msg) {
+            // `poll_ready()` was not awaited, exceed the capacity like `do_send()`
+            Err(SendError::Full(msg)) => self.tx.do_send(msg),
+            res => res,
+        };
+        res.map_err(|err| match err {
+            SendError::RateLimited(_) => MailboxError::RateLimited,
+            _ => MailboxError::Closed,
+        })
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::stream::Stream;
+use parking_lot::Mutex;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::actor::ActorState;
+
+/// Last published actor state and the subscribers waiting for the next one.
+pub(crate) struct StateWatch {
+    inner: Mutex<StateWatchInner>,
+}
+
+struct StateWatchInner {
+    state: ActorState,
+    subscribers: Vec<UnboundedSender<ActorState>>,
+    closed: bool,
+}
+
+impl StateWatch {
+    pub(crate) fn new() -> Self {
+        StateWatch {
+            inner: Mutex::new(StateWatchInner {
+                state: ActorState::Started,
+                subscribers: Vec::new(),
+                closed: false,
+            }),
+        }
+    }
+
+    /// Publishes `state` to all subscribers, unless it is the current state.
+    ///
+    /// Subscribers are dropped once [`ActorState::Stopped`] is published, which completes
+    /// their streams.
+    pub(crate) fn publish(&self, state: ActorState) {
+        let mut inner = self.inner.lock();
+        if inner.state == state {
+            return;
+        }
+        inner.state = state;
+        inner.subscribers.retain(|tx| tx.send(state).is_ok());
+        if state == ActorState::Stopped {
+            inner.subscribers.clear();
+        }
+    }
+
+    /// Completes all streams, the actor will never publish a state again.
+    pub(crate) fn close(&self) {
+        let mut inner = self.inner.lock();
+        inner.closed = true;
+        inner.subscribers.clear();
+    }
+
+    pub(crate) fn subscribe(&self) -> StateStream {
+        let mut inner = self.inner.lock();
+        let (tx, rx) = unbounded_channel();
+        // late subscribers receive the current state first
+        let _ = tx.send(inner.state);
+        if !inner.closed && inner.state != ActorState::Stopped {
+            inner.subscribers.push(tx);
+        }
+        StateStream { rx }
+    }
+}
+
+/// Stream of an actor's state transitions, created by
+/// [`Addr::state_stream`](super::Addr::state_stream).
+///
+/// The stream yields the state of the actor at the time of subscription first, followed by
+/// every later transition, and completes after [`ActorState::Stopped`]. It does not keep the
+/// actor alive.
+pub struct StateStream {
+    rx: UnboundedReceiver<ActorState>,
+}
+
+impl fmt::Debug for StateStream {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("StateStream").finish()
+    }
+}
+
+impl Stream for StateStream {
+    type Item = ActorState;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ActorState>> {
+        self.get_mut().rx.poll_recv(cx)
+    }
+}
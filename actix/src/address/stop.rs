@@ -0,0 +1,118 @@
+use std::{
+    error, fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::stream::Stream;
+
+use super::state::StateStream;
+use crate::{
+    actor::ActorState,
+    clock::{Instant, Timer},
+};
+
+/// How the actor awaited by [`StopWait`] stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StopReason {
+    /// The actor stopped after the stop request.
+    Stopped,
+    /// The actor had already stopped when the stop was requested.
+    AlreadyStopped,
+}
+
+/// Error of [`StopWait`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StopWaitError {
+    /// The deadline passed before the actor stopped.
+    Timeout,
+    /// The actor refused to stop in [`Actor::stopping()`](crate::Actor::stopping).
+    Vetoed,
+    /// The mailbox of the actor was closed without the actor reporting that it stopped, e.g.
+    /// because it runs in a [`SyncArbiter`](crate::SyncArbiter).
+    Disconnected,
+}
+
+impl fmt::Display for StopWaitError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopWaitError::Timeout => write!(fmt, "Actor did not stop before the deadline"),
+            StopWaitError::Vetoed => write!(fmt, "Actor refused to stop"),
+            StopWaitError::Disconnected => write!(fmt, "Actor is gone without stopping"),
+        }
+    }
+}
+
+impl error::Error for StopWaitError {}
+
+/// Future which resolves once an actor has stopped, created by
+/// [`Addr::stop_and_wait()`](super::Addr::stop_and_wait).
+///
+/// The future is `Send` and does not keep the actor alive.
+#[must_use = "futures do nothing unless polled"]
+pub struct StopWait {
+    states: StateStream,
+    deadline: Instant,
+    /// Created on the first poll, so the future can be created outside of a runtime.
+    timer: Option<Pin<Box<Timer>>>,
+    /// `false` until the state of the actor at the time of the request is received.
+    subscribed: bool,
+    stopping: bool,
+}
+
+impl StopWait {
+    pub(crate) fn new(states: StateStream, deadline: Instant) -> Self {
+        StopWait {
+            states,
+            deadline,
+            timer: None,
+            subscribed: false,
+            stopping: false,
+        }
+    }
+}
+
+impl fmt::Debug for StopWait {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("StopWait")
+            .field("deadline", &self.deadline)
+            .field("stopping", &self.stopping)
+            .finish()
+    }
+}
+
+impl Future for StopWait {
+    type Output = Result<StopReason, StopWaitError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        while let Poll::Ready(state) = Pin::new(&mut this.states).poll_next(cx) {
+            let current = !this.subscribed;
+            this.subscribed = true;
+            match state {
+                Some(ActorState::Stopped) if current => {
+                    return Poll::Ready(Ok(StopReason::AlreadyStopped))
+                }
+                Some(ActorState::Stopped) => return Poll::Ready(Ok(StopReason::Stopped)),
+                Some(ActorState::Stopping) => this.stopping = true,
+                // the actor kept running once `stopping()` was called
+                Some(_) if this.stopping => return Poll::Ready(Err(StopWaitError::Vetoed)),
+                Some(_) => {}
+                None => return Poll::Ready(Err(StopWaitError::Disconnected)),
+            }
+        }
+
+        let deadline = this.deadline;
+        let timer = this
+            .timer
+            .get_or_insert_with(|| Box::pin(Timer::at(deadline)));
+        match timer.as_mut().poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Err(StopWaitError::Timeout)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,8 @@
+use std::any::Any;
+
+/// Observer of the messages sent through a tapped address, see [`crate::test::tap()`].
+pub(crate) trait Tap: Send + Sync {
+    /// Called right before a message of type `type_name` is queued, `ask` is `true` if the
+    /// sender waits for a response.
+    fn record(&self, msg: &dyn Any, type_name: &'static str, ask: bool);
+}
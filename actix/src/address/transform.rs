@@ -0,0 +1,76 @@
+use std::any::type_name;
+
+use tokio::sync::oneshot::Sender;
+
+use super::{Envelope, EnvelopeProxy};
+use crate::{
+    actor::{Actor, AsyncContext},
+    handler::{Handler, Message, MessageResponse},
+};
+
+/// Message which is encoded while it is queued in an actor's mailbox.
+///
+/// Messages sent with [`Addr::send_transformed()`](super::Addr::send_transformed) and
+/// [`Addr::do_send_transformed()`](super::Addr::do_send_transformed) are encoded on the sending
+/// thread and decoded on the actor's arbiter right before the handler is called. This allows,
+/// for example, compressing large payloads instead of keeping them alive until they are handled.
+///
+/// Messages which are not sent through a mailbox, e.g. with
+/// [`AsyncContext::notify()`](crate::AsyncContext::notify), are never encoded, neither are
+/// messages sent with the regular send methods.
+pub trait TransformOnSend: Message {
+    /// Encoded form of the message.
+    type Encoded: Send + 'static;
+
+    /// Encodes the message, called on the sending thread.
+    fn encode(self) -> Self::Encoded;
+
+    /// Decodes the message, called on the actor's arbiter before the message is handled.
+    fn decode(enc: Self::Encoded) -> Self;
+}
+
+/// Envelope which decodes its message before handling it.
+pub(crate) struct TransformEnvelope<M: TransformOnSend> {
+    enc: Option<M::Encoded>,
+    tx: Option<Sender<M::Result>>,
+}
+
+impl<M> TransformEnvelope<M>
+where
+    M: TransformOnSend + 'static,
+    M::Result: Send,
+{
+    pub(crate) fn pack<A>(msg: M, tx: Option<Sender<M::Result>>) -> Envelope<A>
+    where
+        A: Handler<M>,
+        A::Context: AsyncContext<A>,
+    {
+        Envelope::with_proxy(Box::new(TransformEnvelope {
+            enc: Some(msg.encode()),
+            tx,
+        }))
+    }
+}
+
+impl<A, M> EnvelopeProxy<A> for TransformEnvelope<M>
+where
+    A: Actor + Handler<M>,
+    A::Context: AsyncContext<A>,
+    M: TransformOnSend + 'static,
+    M::Result: Send,
+{
+    fn handle(&mut self, act: &mut A, ctx: &mut A::Context) {
+        let tx = self.tx.take();
+        if tx.as_ref().map_or(false, |tx| tx.is_closed()) {
+            return;
+        }
+
+        if let Some(enc) = self.enc.take() {
+            <A as Handler<M>>::handle(act, M::decode(enc), ctx).handle(ctx, tx)
+        }
+    }
+
+    fn message_info(&self) -> Option<(&'static str, bool)> {
+        Some((type_name::<M>(), self.tx.is_some()))
+    }
+}
@@ -0,0 +1,51 @@
+use std::{any::Any, fmt};
+
+use super::Envelope;
+use crate::actor::Actor;
+
+/// Message which reached an actor without being handled, see [`Actor::unhandled()`].
+///
+/// Dropping it drops the message and its reply channel, so a sender waiting for a response
+/// receives [`MailboxError::Closed`](super::MailboxError::Closed).
+pub struct UnhandledMessage {
+    type_name: &'static str,
+    expects_reply: bool,
+    _env: Box<dyn Any + Send>,
+}
+
+impl UnhandledMessage {
+    pub(crate) fn new<A: Actor>(
+        type_name: &'static str,
+        expects_reply: bool,
+        env: Envelope<A>,
+    ) -> Self {
+        UnhandledMessage {
+            type_name,
+            expects_reply,
+            _env: Box::new(env),
+        }
+    }
+
+    /// Returns the type name of the message.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns `true` if the message was sent with `send()`, so its sender waits for a response.
+    pub fn expects_reply(&self) -> bool {
+        self.expects_reply
+    }
+
+    /// Drops the message, failing its request with
+    /// [`MailboxError::Closed`](super::MailboxError::Closed).
+    pub fn reject(self) {}
+}
+
+impl fmt::Debug for UnhandledMessage {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("UnhandledMessage")
+            .field("type_name", &self.type_name)
+            .field("expects_reply", &self.expects_reply)
+            .finish()
+    }
+}
@@ -0,0 +1,705 @@
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    error, fmt,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use actix_rt::{Arbiter, ArbiterHandle, System};
+use futures_util::future::{select, Either};
+use log::{error, warn};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use tokio::sync::{oneshot, Notify};
+
+use crate::{
+    actor::Actor,
+    address::Addr,
+    clock::{sleep, Sleep},
+    config::SystemConfig,
+    queue::{self, OneshotReceiver},
+};
+
+/// Exit code of the system when an actor panic is escalated.
+const PANIC_EXIT_CODE: i32 = 101;
+
+/// Tracked tasks and shutdown state of each running system.
+static TRACKERS: Lazy<Mutex<HashMap<usize, Arc<Tracker>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+thread_local!(
+    static POLICY: Cell<PanicPolicy> = const { Cell::new(PanicPolicy::Ignore) };
+    static PANICS: Cell<u32> = const { Cell::new(0) };
+    static STATS: Counters = const { Counters::new() };
+);
+
+/// Describes what happens when an actor running in an arbiter panics.
+///
+/// The execution context of a panicked actor is always dropped, so addresses of the actor start
+/// returning [`MailboxError::Closed`](crate::MailboxError::Closed) errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanicPolicy {
+    /// Stop the whole system with a nonzero exit code.
+    Escalate,
+
+    /// Drop the panicked actor and keep serving the arbiter's other actors.
+    ///
+    /// Once more than `max` actors panicked, the panic is escalated.
+    RestartLoop {
+        /// Maximum number of tolerated panics.
+        max: u32,
+    },
+
+    /// Drop the panicked actor without further action.
+    #[default]
+    Ignore,
+}
+
+impl PanicPolicy {
+    /// Returns the policy of the current arbiter.
+    pub fn current() -> PanicPolicy {
+        POLICY.with(Cell::get)
+    }
+
+    /// Sets the policy of the current arbiter.
+    ///
+    /// Applies to actors started afterwards.
+    pub fn set_current(policy: PanicPolicy) {
+        POLICY.with(|p| p.set(policy));
+        PANICS.with(|p| p.set(0));
+    }
+
+    fn handle_panic(self) {
+        let escalate = match self {
+            PanicPolicy::Escalate => true,
+            PanicPolicy::RestartLoop { max } => {
+                let panics = PANICS.with(|p| {
+                    p.set(p.get() + 1);
+                    p.get()
+                });
+                panics > max
+            }
+            PanicPolicy::Ignore => false,
+        };
+
+        if escalate {
+            error!("Actor panicked, stopping system");
+            System::current().stop_with_code(PANIC_EXIT_CODE);
+        } else {
+            error!("Actor panicked, its context is dropped");
+        }
+    }
+}
+
+/// Builder for an [`Arbiter`] with custom actor panic behavior and statistics.
+///
+/// ```
+/// # use actix::prelude::*;
+/// use actix::{ArbiterBuilder, PanicPolicy};
+///
+/// # fn main() {
+/// # let sys = System::new();
+/// # sys.block_on(async {
+/// // stop the system if more than 3 actors panic
+/// let arbiter = ArbiterBuilder::new()
+///     .panic_policy(PanicPolicy::RestartLoop { max: 3 })
+///     .build();
+/// # arbiter.stop();
+/// # System::current().stop();
+/// # });
+/// # sys.run().unwrap();
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct ArbiterBuilder {
+    policy: PanicPolicy,
+    stats: bool,
+    slow_poll: Option<Duration>,
+}
+
+impl ArbiterBuilder {
+    /// Creates a new builder with the [`PanicPolicy::Ignore`] policy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the policy applied when an actor of the arbiter panics.
+    pub fn panic_policy(mut self, policy: PanicPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Collects [`ArbiterStats`] of the actors started on the arbiter.
+    ///
+    /// Every poll of an actor's execution context is timed, so this is disabled by default.
+    pub fn stats(mut self, enabled: bool) -> Self {
+        self.stats = enabled;
+        self
+    }
+
+    /// Logs a warning whenever a single poll of an actor takes longer than `threshold`.
+    ///
+    /// A slow poll blocks all other actors of the arbiter, e.g. because a handler runs blocking
+    /// code. Enables [`stats()`](Self::stats).
+    pub fn slow_poll_warning(mut self, threshold: Duration) -> Self {
+        self.stats = true;
+        self.slow_poll = Some(threshold);
+        self
+    }
+
+    /// Spawns the arbiter's thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a running system.
+    pub fn build(self) -> Arbiter {
+        let arbiter = Arbiter::new();
+        let ArbiterBuilder {
+            policy,
+            stats,
+            slow_poll,
+        } = self;
+        arbiter.spawn_fn(move || {
+            PanicPolicy::set_current(policy);
+            STATS.with(|c| {
+                c.enabled.set(stats);
+                c.slow_poll.set(slow_poll);
+            });
+        });
+        arbiter
+    }
+}
+
+/// Counters of the current arbiter, only touched from its own thread.
+struct Counters {
+    enabled: Cell<bool>,
+    slow_poll: Cell<Option<Duration>>,
+    resident: Cell<usize>,
+    polls: Cell<u64>,
+    interval_polls: Cell<u64>,
+    interval_max: Cell<Duration>,
+}
+
+impl Counters {
+    const fn new() -> Self {
+        Counters {
+            enabled: Cell::new(false),
+            slow_poll: Cell::new(None),
+            resident: Cell::new(0),
+            polls: Cell::new(0),
+            interval_polls: Cell::new(0),
+            interval_max: Cell::new(Duration::ZERO),
+        }
+    }
+
+    fn record(&self, elapsed: Duration) {
+        self.polls.set(self.polls.get() + 1);
+        self.interval_polls.set(self.interval_polls.get() + 1);
+        if elapsed > self.interval_max.get() {
+            self.interval_max.set(elapsed);
+        }
+        if let Some(threshold) = self.slow_poll.get() {
+            if elapsed > threshold {
+                warn!(
+                    "Actor poll took {:?}, exceeding the threshold of {:?}",
+                    elapsed, threshold
+                );
+            }
+        }
+    }
+}
+
+/// Statistics of the actors running on an arbiter built with [`ArbiterBuilder::stats()`].
+///
+/// Every snapshot starts a new interval, which the `*_last_interval` fields refer to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ArbiterStats {
+    /// Number of actors currently spawned on the arbiter.
+    pub resident_actors: usize,
+    /// Number of actor polls since the previous snapshot.
+    pub polls_last_interval: u64,
+    /// Longest single actor poll since the previous snapshot.
+    pub max_poll_last_interval: Duration,
+    /// Number of actor polls since the arbiter started.
+    pub total_polls: u64,
+}
+
+impl ArbiterStats {
+    /// Takes a snapshot of the current arbiter's statistics.
+    ///
+    /// Returns `None` if statistics are not enabled for the current arbiter.
+    pub fn snapshot() -> Option<ArbiterStats> {
+        STATS.with(|c| {
+            if !c.enabled.get() {
+                return None;
+            }
+            Some(ArbiterStats {
+                resident_actors: c.resident.get(),
+                polls_last_interval: c.interval_polls.replace(0),
+                max_poll_last_interval: c.interval_max.replace(Duration::ZERO),
+                total_polls: c.polls.get(),
+            })
+        })
+    }
+
+    /// Takes a snapshot of the statistics of `arbiter` on its own thread.
+    ///
+    /// Resolves to `None` if statistics are not enabled for the arbiter, or it has stopped.
+    pub fn query(arbiter: &ArbiterHandle) -> impl Future<Output = Option<ArbiterStats>> {
+        let (tx, rx) = queue::oneshot();
+        arbiter.spawn_fn(move || {
+            let _ = tx.send(ArbiterStats::snapshot());
+        });
+        async move { rx.await.ok().flatten() }
+    }
+}
+
+/// Requests run by an arbiter on its own thread, answered through an [`ArbiterReply`].
+///
+/// ```
+/// # use std::time::Duration;
+/// use actix::prelude::*;
+///
+/// struct Worker;
+///
+/// impl Actor for Worker {
+///     type Context = Context<Self>;
+/// }
+///
+/// # fn main() {
+/// # let sys = System::new();
+/// # sys.block_on(async {
+/// let arbiter = Arbiter::new().handle();
+/// let _addr = arbiter.spawn_actor(|_| Worker).await.unwrap();
+/// assert_eq!(arbiter.actor_count().await, Ok(1));
+/// assert!(arbiter.ping(Duration::from_secs(1)).await.is_ok());
+/// # arbiter.stop();
+/// # System::current().stop();
+/// # });
+/// # sys.run().unwrap();
+/// # }
+/// ```
+pub trait ArbiterHandleExt {
+    /// Measures how long the arbiter takes to run a request, failing with
+    /// [`ArbiterError::Timeout`] if it does not run it within `timeout`.
+    fn ping(&self, timeout: Duration) -> ArbiterReply<Duration>;
+
+    /// Returns the number of actors running on the arbiter.
+    fn actor_count(&self) -> ArbiterReply<usize>;
+
+    /// Starts an actor created by `factory` on the arbiter, resolving with its address once it
+    /// was created.
+    fn spawn_actor<A, F>(&self, factory: F) -> ArbiterReply<Addr<A>>
+    where
+        A: Actor<Context = crate::Context<A>>,
+        F: FnOnce(&mut crate::Context<A>) -> A + Send + 'static;
+
+    /// Spawns a future on the arbiter which [`stop_gracefully()`] waits for.
+    ///
+    /// Futures spawned with [`ArbiterHandle::spawn()`] are dropped as soon as the system stops.
+    /// A tracked future is given until the shutdown timeout of the system to complete, see
+    /// [`SystemConfig::shutdown_timeout()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a system.
+    fn spawn_tracked<F>(&self, fut: F) -> TrackedHandle
+    where
+        F: Future<Output = ()> + Send + 'static;
+}
+
+impl ArbiterHandleExt for ArbiterHandle {
+    fn ping(&self, timeout: Duration) -> ArbiterReply<Duration> {
+        let sent = Instant::now();
+        ArbiterReply::new(self, move || sent.elapsed()).timeout(timeout)
+    }
+
+    fn actor_count(&self) -> ArbiterReply<usize> {
+        ArbiterReply::new(self, || STATS.with(|c| c.resident.get()))
+    }
+
+    fn spawn_actor<A, F>(&self, factory: F) -> ArbiterReply<Addr<A>>
+    where
+        A: Actor<Context = crate::Context<A>>,
+        F: FnOnce(&mut crate::Context<A>) -> A + Send + 'static,
+    {
+        ArbiterReply::new(self, move || A::create(factory))
+    }
+
+    fn spawn_tracked<F>(&self, fut: F) -> TrackedHandle
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let (abort_tx, abort_rx) = oneshot::channel();
+        let finished = Arc::new(AtomicBool::new(false));
+        // counted right away, so the task is not missed by a shutdown before it starts
+        let guard = TrackedGuard::new(Tracker::current(), Arc::clone(&finished));
+        self.spawn(Tracked {
+            fut: Box::pin(fut),
+            abort: Some(abort_rx),
+            _guard: guard,
+        });
+        TrackedHandle {
+            abort: Mutex::new(Some(abort_tx)),
+            finished,
+        }
+    }
+}
+
+/// Stops the current system once its tracked tasks have completed.
+///
+/// Tasks spawned with [`ArbiterHandleExt::spawn_tracked()`] are given until the shutdown timeout
+/// of the system to complete, see [`SystemConfig::shutdown_timeout()`]; tasks still running
+/// then are dropped when the system stops. Resolves with the number of tracked tasks which were
+/// abandoned.
+///
+/// Once the tracked tasks are done, requests to the actors of the system fail with
+/// [`MailboxError::SystemStopping`](crate::MailboxError::SystemStopping), including those
+/// still queued in a mailbox when its actor is dropped, so callers on other threads are not
+/// left waiting for a reply.
+///
+/// # Panics
+///
+/// Panics if called outside of a system.
+///
+/// ```
+/// # use std::time::Duration;
+/// use actix::prelude::*;
+///
+/// # fn main() {
+/// let sys = System::new();
+/// sys.block_on(async {
+///     Arbiter::current().spawn_tracked(async {
+///         // flush metrics
+///     });
+///     assert_eq!(actix::stop_gracefully().await, 0);
+/// });
+/// sys.run().unwrap();
+/// # }
+/// ```
+pub async fn stop_gracefully() -> usize {
+    let tracker = Tracker::current();
+    let deadline = sleep(SystemConfig::current().get_shutdown_timeout());
+    tokio::pin!(deadline);
+
+    loop {
+        // register before checking, so the last task completing meanwhile is not missed
+        let idle = tracker.idle.notified();
+        tokio::pin!(idle);
+        idle.as_mut().enable();
+        if tracker.tasks.load(Ordering::Acquire) == 0 {
+            break;
+        }
+        if let Either::Right(_) = select(idle, deadline.as_mut()).await {
+            break;
+        }
+    }
+
+    let abandoned = tracker.tasks.load(Ordering::Acquire);
+    if abandoned > 0 {
+        warn!(
+            "Stopping system with {} tracked tasks still running",
+            abandoned
+        );
+    }
+    tracker.stopping.store(true, Ordering::Release);
+    System::current().stop();
+    abandoned
+}
+
+/// Number of tracked tasks of a system, see [`ArbiterHandleExt::spawn_tracked()`].
+struct Tracker {
+    tasks: AtomicUsize,
+    idle: Notify,
+    /// Set by [`stop_gracefully()`] right before the system is stopped.
+    stopping: AtomicBool,
+}
+
+impl Tracker {
+    fn current() -> Arc<Tracker> {
+        Self::of(System::current().id())
+    }
+
+    fn of(id: usize) -> Arc<Tracker> {
+        let mut trackers = TRACKERS.lock();
+        let tracker = trackers.entry(id).or_insert_with(|| {
+            Arc::new(Tracker {
+                tasks: AtomicUsize::new(0),
+                idle: Notify::new(),
+                stopping: AtomicBool::new(false),
+            })
+        });
+        Arc::clone(tracker)
+    }
+}
+
+/// Shutdown state of a system, shared by the mailboxes of its actors and their requests.
+#[derive(Clone)]
+pub struct SystemShutdown(Arc<Tracker>);
+
+impl SystemShutdown {
+    /// Returns the shutdown state of the current system, `None` outside of a system.
+    pub(crate) fn current() -> Option<Self> {
+        System::try_current().map(|sys| SystemShutdown(Tracker::of(sys.id())))
+    }
+
+    /// Returns whether the system is stopping, see [`stop_gracefully()`].
+    pub(crate) fn has_begun(&self) -> bool {
+        self.0.stopping.load(Ordering::Acquire)
+    }
+}
+
+impl fmt::Debug for SystemShutdown {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("SystemShutdown")
+            .field("has_begun", &self.has_begun())
+            .finish()
+    }
+}
+
+/// Counts a tracked task until it has completed, was aborted or was dropped.
+struct TrackedGuard {
+    tracker: Arc<Tracker>,
+    finished: Arc<AtomicBool>,
+}
+
+impl TrackedGuard {
+    fn new(tracker: Arc<Tracker>, finished: Arc<AtomicBool>) -> Self {
+        tracker.tasks.fetch_add(1, Ordering::AcqRel);
+        TrackedGuard { tracker, finished }
+    }
+}
+
+impl Drop for TrackedGuard {
+    fn drop(&mut self) {
+        self.finished.store(true, Ordering::Release);
+        if self.tracker.tasks.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.tracker.idle.notify_waiters();
+        }
+    }
+}
+
+/// Tracked task, runs until the future completes or the task is aborted.
+struct Tracked<F> {
+    fut: Pin<Box<F>>,
+    /// Dropped once the handle is dropped without aborting the task.
+    abort: Option<oneshot::Receiver<()>>,
+    _guard: TrackedGuard,
+}
+
+impl<F: Future<Output = ()>> Future for Tracked<F> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if let Some(abort) = self.abort.as_mut() {
+            match Pin::new(abort).poll(cx) {
+                Poll::Ready(Ok(())) => return Poll::Ready(()),
+                // the handle was dropped, the task is detached
+                Poll::Ready(Err(_)) => self.abort = None,
+                Poll::Pending => {}
+            }
+        }
+        self.fut.as_mut().poll(cx)
+    }
+}
+
+/// Handle of a task spawned with [`ArbiterHandleExt::spawn_tracked()`].
+///
+/// Dropping the handle does not cancel the task.
+#[derive(Debug)]
+pub struct TrackedHandle {
+    abort: Mutex<Option<oneshot::Sender<()>>>,
+    finished: Arc<AtomicBool>,
+}
+
+impl TrackedHandle {
+    /// Cancels the task, which no longer holds back [`stop_gracefully()`] once it was dropped
+    /// by its arbiter.
+    pub fn abort(&self) {
+        if let Some(tx) = self.abort.lock().take() {
+            let _ = tx.send(());
+        }
+    }
+
+    /// Returns `true` once the task has completed, was aborted or was dropped.
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Acquire)
+    }
+}
+
+/// Errors of the requests of [`ArbiterHandleExt`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArbiterError {
+    /// The arbiter has stopped.
+    Stopped,
+    /// The arbiter did not answer in time.
+    Timeout,
+}
+
+impl fmt::Display for ArbiterError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArbiterError::Stopped => write!(fmt, "Arbiter has stopped"),
+            ArbiterError::Timeout => write!(fmt, "Arbiter request timed out"),
+        }
+    }
+}
+
+impl error::Error for ArbiterError {}
+
+/// Future resolving with the answer of an arbiter to an [`ArbiterHandleExt`] request.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct ArbiterReply<T> {
+    rx: OneshotReceiver<T>,
+    timeout: Option<Pin<Box<Sleep>>>,
+}
+
+impl<T: Send + 'static> ArbiterReply<T> {
+    fn new<F>(arbiter: &ArbiterHandle, f: F) -> Self
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (tx, rx) = queue::oneshot();
+        arbiter.spawn_fn(move || {
+            let _ = tx.send(f());
+        });
+        ArbiterReply { rx, timeout: None }
+    }
+
+    fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(Box::pin(sleep(timeout)));
+        self
+    }
+}
+
+impl<T> Future for ArbiterReply<T> {
+    type Output = Result<T, ArbiterError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(res) = Pin::new(&mut self.rx).poll(cx) {
+            return Poll::Ready(res.map_err(|_| ArbiterError::Stopped));
+        }
+        match &mut self.timeout {
+            Some(timeout) => timeout
+                .as_mut()
+                .poll(cx)
+                .map(|_| Err(ArbiterError::Timeout)),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// Spawns an actor's execution context, applying the current arbiter's panic policy.
+pub(crate) fn spawn_actor<F>(fut: F)
+where
+    F: Future<Output = ()> + 'static,
+{
+    let fut = Resident::new(fut);
+    if STATS.with(|c| c.enabled.get()) {
+        spawn_with_policy(Instrumented::new(fut));
+    } else {
+        spawn_with_policy(fut);
+    }
+}
+
+fn spawn_with_policy<F>(fut: F)
+where
+    F: Future<Output = ()> + 'static,
+{
+    match PanicPolicy::current() {
+        PanicPolicy::Ignore => {
+            actix_rt::spawn(fut);
+        }
+        policy => {
+            actix_rt::spawn(CatchPanic {
+                fut: Some(Box::pin(fut)),
+                policy,
+            });
+        }
+    }
+}
+
+struct CatchPanic<F> {
+    fut: Option<Pin<Box<F>>>,
+    policy: PanicPolicy,
+}
+
+impl<F: Future<Output = ()>> Future for CatchPanic<F> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let fut = match self.fut.as_mut() {
+            Some(fut) => fut,
+            None => return Poll::Ready(()),
+        };
+
+        match panic::catch_unwind(AssertUnwindSafe(|| fut.as_mut().poll(cx))) {
+            Ok(res) => res,
+            Err(_) => {
+                self.policy.handle_panic();
+                // drop poisoned context, the destructors of its futures may panic as well
+                let _ = panic::catch_unwind(AssertUnwindSafe(|| self.fut = None));
+                Poll::Ready(())
+            }
+        }
+    }
+}
+
+/// Counts an actor as resident while its execution context is alive.
+struct Resident<F> {
+    fut: Pin<Box<F>>,
+}
+
+impl<F> Resident<F> {
+    fn new(fut: F) -> Self {
+        STATS.with(|c| c.resident.set(c.resident.get() + 1));
+        Resident { fut: Box::pin(fut) }
+    }
+}
+
+impl<F> Drop for Resident<F> {
+    fn drop(&mut self) {
+        // the thread local is gone if the arbiter's thread exits
+        let _ = STATS.try_with(|c| c.resident.set(c.resident.get() - 1));
+    }
+}
+
+impl<F: Future<Output = ()>> Future for Resident<F> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.fut.as_mut().poll(cx)
+    }
+}
+
+/// Times the polls of an actor.
+struct Instrumented<F> {
+    fut: F,
+}
+
+impl<F> Instrumented<F> {
+    fn new(fut: F) -> Self {
+        Instrumented { fut }
+    }
+}
+
+impl<F: Future<Output = ()> + Unpin> Future for Instrumented<F> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let start = Instant::now();
+        let res = Pin::new(&mut self.fut).poll(cx);
+        STATS.with(|c| c.record(start.elapsed()));
+        res
+    }
+}
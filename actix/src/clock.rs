@@ -4,6 +4,186 @@
 //!
 //! See [`tokio::time` module] for full documentation.
 //!
+//! The timers of actors, e.g. [`run_later()`](crate::AsyncContext::run_later),
+//! [`run_interval()`](crate::AsyncContext::run_interval) and request timeouts, follow the
+//! [`Clock`] of their arbiter instead, which is the tokio timer unless another clock is set with
+//! [`SystemConfig::clock()`](crate::SystemConfig::clock) or [`set_arbiter_clock()`].
+//!
 //! [`tokio::time` module]: https://docs.rs/tokio/1.0.1/tokio/time/index.html
 
+use std::{
+    cell::RefCell,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
 pub use actix_rt::time::*;
+use pin_project_lite::pin_project;
+
+use crate::config::SystemConfig;
+
+/// Future returned by [`Clock::sleep()`].
+pub type ClockSleep = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Source of time of the timers of actors.
+///
+/// Clocks replace the tokio timer for deterministic tests and simulations, see
+/// [`ManualClock`](crate::test::ManualClock).
+pub trait Clock: fmt::Debug + Send + Sync + 'static {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+
+    /// Returns a future which completes once `dur` has elapsed.
+    fn sleep(&self, dur: Duration) -> ClockSleep;
+}
+
+/// Clock of the tokio timer, used unless another clock is set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, dur: Duration) -> ClockSleep {
+        Box::pin(sleep(dur))
+    }
+}
+
+/// Set once any clock is set, so timers skip looking one up until then.
+static CLOCKS_SET: AtomicBool = AtomicBool::new(false);
+
+thread_local!(
+    static ARBITER_CLOCK: RefCell<Option<Arc<dyn Clock>>> = const { RefCell::new(None) };
+);
+
+/// Sets the clock of the current arbiter, overriding the clock of its system.
+///
+/// Applies to timers created afterwards.
+pub fn set_arbiter_clock<C: Clock>(clock: C) {
+    mark_clock_set();
+    ARBITER_CLOCK.with(|c| *c.borrow_mut() = Some(Arc::new(clock)));
+}
+
+/// Removes the clock of the current arbiter set with [`set_arbiter_clock()`].
+pub fn clear_arbiter_clock() {
+    ARBITER_CLOCK.with(|c| c.borrow_mut().take());
+}
+
+pub(crate) fn mark_clock_set() {
+    CLOCKS_SET.store(true, Ordering::Relaxed);
+}
+
+/// Returns the clock of the current arbiter, `None` for the tokio timer.
+fn current() -> Option<Arc<dyn Clock>> {
+    if !CLOCKS_SET.load(Ordering::Relaxed) {
+        return None;
+    }
+    ARBITER_CLOCK
+        .with(|c| c.borrow().clone())
+        .or_else(|| SystemConfig::current().get_clock())
+}
+
+/// Returns the current time of the clock of the current arbiter.
+pub fn now() -> Instant {
+    match current() {
+        Some(clock) => clock.now(),
+        None => Instant::now(),
+    }
+}
+
+pin_project! {
+    /// Timer following the clock of the arbiter which created it.
+    #[project = TimerProj]
+    pub(crate) enum Timer {
+        Tokio {
+            #[pin]
+            sleep: Sleep,
+        },
+        Clock {
+            deadline: Instant,
+            sleep: ClockSleep,
+            clock: Arc<dyn Clock>,
+        },
+    }
+}
+
+impl Timer {
+    /// Creates a timer which completes once `dur` has elapsed.
+    pub(crate) fn after(dur: Duration) -> Self {
+        match current() {
+            Some(clock) => Timer::Clock {
+                deadline: clock.now() + dur,
+                sleep: clock.sleep(dur),
+                clock,
+            },
+            None => Timer::Tokio { sleep: sleep(dur) },
+        }
+    }
+
+    /// Creates a timer which completes at `deadline`.
+    pub(crate) fn at(deadline: Instant) -> Self {
+        match current() {
+            Some(clock) => Timer::Clock {
+                deadline,
+                sleep: clock.sleep(deadline.saturating_duration_since(clock.now())),
+                clock,
+            },
+            None => Timer::Tokio {
+                sleep: sleep_until(deadline),
+            },
+        }
+    }
+
+    /// Returns the instant at which the timer completes.
+    pub(crate) fn deadline(&self) -> Instant {
+        match self {
+            Timer::Tokio { sleep } => sleep.deadline(),
+            Timer::Clock { deadline, .. } => *deadline,
+        }
+    }
+
+    /// Returns `true` if the deadline of the timer has passed.
+    pub(crate) fn is_elapsed(&self) -> bool {
+        match self {
+            Timer::Tokio { sleep } => sleep.deadline() <= Instant::now(),
+            Timer::Clock {
+                deadline, clock, ..
+            } => *deadline <= clock.now(),
+        }
+    }
+
+    /// Moves the deadline of the timer to `deadline`.
+    pub(crate) fn reset(self: Pin<&mut Self>, deadline: Instant) {
+        match self.project() {
+            TimerProj::Tokio { sleep } => sleep.reset(deadline),
+            TimerProj::Clock {
+                deadline: current,
+                sleep,
+                clock,
+            } => {
+                *current = deadline;
+                *sleep = clock.sleep(deadline.saturating_duration_since(clock.now()));
+            }
+        }
+    }
+}
+
+impl Future for Timer {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        match self.project() {
+            TimerProj::Tokio { sleep } => sleep.poll(cx),
+            TimerProj::Clock { sleep, .. } => sleep.as_mut().poll(cx),
+        }
+    }
+}
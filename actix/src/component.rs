@@ -0,0 +1,209 @@
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    pin::Pin,
+    rc::Rc,
+    task::{self, Poll},
+    time::Duration,
+};
+
+use pin_project_lite::pin_project;
+
+use crate::{
+    actor::{Actor, AsyncContext, ScopeHandle, SpawnHandle},
+    contextitems::ActorScopedItem,
+    fut::{ActorFuture, ActorStreamExt},
+    utils::{IntervalFunc, TimerFunc},
+};
+
+/// A handle to the futures of a logical component of an actor, e.g. a heartbeat or a metrics
+/// flusher.
+///
+/// Created by [`Context::component()`](crate::Context::component). Every future spawned through
+/// the handle, or through its clones, is cancelled by [`shutdown()`](Self::shutdown). The
+/// component can be used again afterwards, futures spawned after a shutdown run as usual.
+///
+/// Futures of a component don't keep the actor alive on their own, the actor stops once it has
+/// no addresses and nothing else to do even if they are still pending. Futures spawned after
+/// the component was marked [`essential()`](Self::essential) keep the actor alive like
+/// futures spawned with [`AsyncContext::spawn()`].
+///
+/// ```
+/// # use std::time::Duration;
+/// # use actix::prelude::*;
+/// struct Node {
+///     resync: Option<ComponentHandle>,
+/// }
+///
+/// impl Actor for Node {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, ctx: &mut Self::Context) {
+///         let resync = ctx.component("resync");
+///         resync.run_interval(ctx, Duration::from_secs(10), |_, _| {
+///             // fetch missed updates
+///         });
+///         assert!(!resync.is_empty());
+///
+///         // tear down the component alone, the actor keeps running
+///         resync.shutdown();
+///         assert!(resync.is_empty());
+///         self.resync = Some(resync);
+///     }
+/// }
+/// # fn main() {
+/// #     let sys = System::new();
+/// #     sys.block_on(async { Node { resync: None }.start() });
+/// #     System::current().stop();
+/// #     sys.run().unwrap();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ComponentHandle(Rc<ComponentInner>);
+
+struct ComponentInner {
+    name: &'static str,
+    /// Scope of the futures spawned since the last shutdown, with their number.
+    generation: RefCell<(ScopeHandle, Rc<Cell<usize>>)>,
+    essential: Cell<bool>,
+    /// Number of futures of the context which don't keep the actor alive.
+    detached: Rc<Cell<usize>>,
+}
+
+impl fmt::Debug for ComponentHandle {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ComponentHandle")
+            .field("name", &self.0.name)
+            .field("essential", &self.0.essential.get())
+            .field("pending", &self.0.generation.borrow().1.get())
+            .finish()
+    }
+}
+
+impl ComponentHandle {
+    pub(crate) fn new(name: &'static str, detached: Rc<Cell<usize>>) -> Self {
+        ComponentHandle(Rc::new(ComponentInner {
+            name,
+            generation: RefCell::new((ScopeHandle::new(None), Rc::new(Cell::new(0)))),
+            essential: Cell::new(false),
+            detached,
+        }))
+    }
+
+    /// Marks the component as essential, its futures spawned from now on keep the actor alive.
+    pub fn essential(self) -> Self {
+        self.0.essential.set(true);
+        self
+    }
+
+    /// Returns the name of the component.
+    pub fn name(&self) -> &'static str {
+        self.0.name
+    }
+
+    /// Spawns a future into the context as part of this component.
+    pub fn spawn<A, C, F>(&self, ctx: &mut C, fut: F) -> SpawnHandle
+    where
+        A: Actor<Context = C>,
+        C: AsyncContext<A>,
+        F: ActorFuture<A, Output = ()> + 'static,
+    {
+        let (scope, pending) = self.0.generation.borrow().clone();
+        let detached = if self.0.essential.get() {
+            None
+        } else {
+            Some(Rc::clone(&self.0.detached))
+        };
+        ctx.spawn(ComponentItem {
+            fut: ActorScopedItem::new(fut, scope),
+            _guard: PendingGuard::new(pending, detached),
+        })
+    }
+
+    /// Runs `f` after `dur` as part of this component, see [`AsyncContext::run_later()`].
+    pub fn run_later<A, C, F>(&self, ctx: &mut C, dur: Duration, f: F) -> SpawnHandle
+    where
+        A: Actor<Context = C>,
+        C: AsyncContext<A>,
+        F: FnOnce(&mut A, &mut C) + 'static,
+    {
+        self.spawn(ctx, TimerFunc::new(dur, f))
+    }
+
+    /// Runs `f` every `dur` as part of this component, see [`AsyncContext::run_interval()`].
+    pub fn run_interval<A, C, F>(&self, ctx: &mut C, dur: Duration, f: F) -> SpawnHandle
+    where
+        A: Actor<Context = C>,
+        C: AsyncContext<A>,
+        F: FnMut(&mut A, &mut C) + 'static,
+    {
+        self.spawn(ctx, IntervalFunc::new(dur, f).finish())
+    }
+
+    /// Cancels every still pending future of the component.
+    ///
+    /// Can be called from within one of the component's own futures.
+    pub fn shutdown(&self) {
+        let (scope, _) = self
+            .0
+            .generation
+            .replace((ScopeHandle::new(None), Rc::new(Cell::new(0))));
+        scope.cancel_all();
+    }
+
+    /// Returns `true` if none of the futures spawned since the last shutdown is pending.
+    pub fn is_empty(&self) -> bool {
+        self.0.generation.borrow().1.get() == 0
+    }
+}
+
+/// Counts a future of a component until it is dropped.
+struct PendingGuard {
+    pending: Rc<Cell<usize>>,
+    detached: Option<Rc<Cell<usize>>>,
+}
+
+impl PendingGuard {
+    fn new(pending: Rc<Cell<usize>>, detached: Option<Rc<Cell<usize>>>) -> Self {
+        pending.set(pending.get() + 1);
+        if let Some(ref detached) = detached {
+            detached.set(detached.get() + 1);
+        }
+        PendingGuard { pending, detached }
+    }
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        self.pending.set(self.pending.get() - 1);
+        if let Some(ref detached) = self.detached {
+            detached.set(detached.get() - 1);
+        }
+    }
+}
+
+pin_project! {
+    /// Future of a component, counted until it is dropped.
+    struct ComponentItem<F> {
+        #[pin]
+        fut: ActorScopedItem<F>,
+        _guard: PendingGuard,
+    }
+}
+
+impl<A, F> ActorFuture<A> for ComponentItem<F>
+where
+    A: Actor,
+    F: ActorFuture<A, Output = ()>,
+{
+    type Output = ();
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut task::Context<'_>,
+    ) -> Poll<()> {
+        self.project().fut.poll(act, ctx, task)
+    }
+}
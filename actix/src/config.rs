@@ -0,0 +1,225 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use actix_rt::{Runtime, System, SystemRunner};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::{
+    clock::{self, Clock},
+    mailbox::DEFAULT_CAPACITY,
+};
+
+/// Default time granted to the system to shut down.
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default number of dead letters retained by a system.
+const DEFAULT_DEAD_LETTER_CAPACITY: usize = 128;
+
+static CONFIGS: Lazy<Mutex<HashMap<usize, SystemConfig>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Configuration shared by all arbiters of a system.
+///
+/// Systems created with [`System::new()`] use the defaults documented on each setter. The
+/// configuration is cheap to clone and can be read from any arbiter of the system with
+/// [`SystemConfig::current()`].
+///
+/// ```
+/// # use std::time::Duration;
+/// # use actix::prelude::*;
+/// use actix::SystemConfig;
+///
+/// # fn main() {
+/// let sys = SystemConfig::new()
+///     .mailbox_capacity(1024)
+///     .message_budget(16)
+///     .shutdown_timeout(Duration::from_secs(10))
+///     .build();
+///
+/// sys.block_on(async {
+///     assert_eq!(SystemConfig::current().get_mailbox_capacity(), 1024);
+///     System::current().stop();
+/// });
+/// sys.run().unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SystemConfig(Arc<ConfigInner>);
+
+#[derive(Debug, Clone)]
+struct ConfigInner {
+    mailbox_capacity: usize,
+    message_budget: Option<usize>,
+    shutdown_timeout: Duration,
+    dead_letter_capacity: usize,
+    clock: Option<Arc<dyn Clock>>,
+}
+
+impl Default for ConfigInner {
+    fn default() -> Self {
+        ConfigInner {
+            mailbox_capacity: DEFAULT_CAPACITY,
+            message_budget: None,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            dead_letter_capacity: DEFAULT_DEAD_LETTER_CAPACITY,
+            clock: None,
+        }
+    }
+}
+
+impl SystemConfig {
+    /// Creates a configuration with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the configuration of the current system.
+    ///
+    /// Returns the default configuration outside of a system, or if the system was not created
+    /// with [`SystemConfig::build()`].
+    pub fn current() -> SystemConfig {
+        System::try_current()
+            .map(|sys| Self::of(sys.id()))
+            .unwrap_or_default()
+    }
+
+    /// Returns the configuration of the system with id `system`.
+    pub(crate) fn of(system: usize) -> SystemConfig {
+        CONFIGS.lock().get(&system).cloned().unwrap_or_default()
+    }
+
+    /// Sets the mailbox capacity of newly started actors.
+    ///
+    /// Defaults to 16. Actors can change their own capacity with
+    /// [`Context::set_mailbox_capacity()`](crate::Context::set_mailbox_capacity).
+    pub fn mailbox_capacity(mut self, cap: usize) -> Self {
+        Arc::make_mut(&mut self.0).mailbox_capacity = cap;
+        self
+    }
+
+    /// Sets the maximum number of messages an actor handles before yielding to other tasks.
+    ///
+    /// By default actors handle queued messages until their mailbox is empty. Actors can change
+    /// their own budget with
+    /// [`Context::set_message_budget()`](crate::Context::set_message_budget).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `budget` is zero.
+    pub fn message_budget(mut self, budget: usize) -> Self {
+        assert!(budget > 0, "Message budget must be greater than zero");
+        Arc::make_mut(&mut self.0).message_budget = Some(budget);
+        self
+    }
+
+    /// Sets the time granted to the system to shut down gracefully.
+    ///
+    /// Defaults to 30 seconds.
+    pub fn shutdown_timeout(mut self, timeout: Duration) -> Self {
+        Arc::make_mut(&mut self.0).shutdown_timeout = timeout;
+        self
+    }
+
+    /// Sets the number of dead letters retained by the system, see
+    /// [`SystemExt::dead_letters()`](crate::SystemExt::dead_letters).
+    ///
+    /// Defaults to 128. Dead letters are not retained if `cap` is zero.
+    pub fn dead_letter_capacity(mut self, cap: usize) -> Self {
+        Arc::make_mut(&mut self.0).dead_letter_capacity = cap;
+        self
+    }
+
+    /// Sets the clock of the timers of actors, see [`Clock`].
+    ///
+    /// Defaults to the tokio timer. Arbiters can override it with
+    /// [`clock::set_arbiter_clock()`].
+    pub fn clock<C: Clock>(mut self, clock: C) -> Self {
+        Arc::make_mut(&mut self.0).clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Returns the mailbox capacity of newly started actors.
+    pub fn get_mailbox_capacity(&self) -> usize {
+        self.0.mailbox_capacity
+    }
+
+    /// Returns the message budget of newly started actors, `None` if it is unlimited.
+    pub fn get_message_budget(&self) -> Option<usize> {
+        self.0.message_budget
+    }
+
+    /// Returns the time granted to the system to shut down gracefully.
+    pub fn get_shutdown_timeout(&self) -> Duration {
+        self.0.shutdown_timeout
+    }
+
+    /// Returns the number of dead letters retained by the system.
+    pub fn get_dead_letter_capacity(&self) -> usize {
+        self.0.dead_letter_capacity
+    }
+
+    /// Returns the clock set with [`clock()`](Self::clock), `None` for the tokio timer.
+    pub fn get_clock(&self) -> Option<Arc<dyn Clock>> {
+        self.0.clock.clone()
+    }
+
+    /// Creates a new system using this configuration.
+    ///
+    /// This is the configurable counterpart of [`System::new()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the underlying Tokio runtime can not be created.
+    pub fn build(self) -> SystemRunner {
+        self.register(System::new())
+    }
+
+    /// Creates a new system using this configuration on a Tokio runtime owned by the
+    /// application, e.g. an `Arc<tokio::runtime::Runtime>`.
+    ///
+    /// The system arbiter runs on the thread calling this method and drives the runtime, so
+    /// actors share its reactor and timer with the plain Tokio tasks of the application
+    /// instead of running a second event loop. Stopping the system stops its actors and
+    /// arbiters, while the runtime and its tasks keep running as long as the application holds
+    /// on to it.
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use actix::prelude::*;
+    /// use actix::SystemConfig;
+    ///
+    /// # fn main() {
+    /// let rt = Arc::new(
+    ///     tokio::runtime::Builder::new_current_thread()
+    ///         .enable_all()
+    ///         .build()
+    ///         .unwrap(),
+    /// );
+    /// let task = rt.spawn(async { 42 });
+    ///
+    /// let sys = SystemConfig::new().build_with_tokio_rt(Arc::clone(&rt));
+    /// sys.block_on(async { System::current().stop() });
+    /// sys.run().unwrap();
+    ///
+    /// assert_eq!(rt.block_on(task).unwrap(), 42);
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from within an asynchronous context of a runtime.
+    pub fn build_with_tokio_rt<R>(self, runtime: R) -> SystemRunner
+    where
+        R: Into<Runtime>,
+    {
+        self.register(System::with_tokio_rt(|| runtime))
+    }
+
+    fn register(self, sys: SystemRunner) -> SystemRunner {
+        if self.0.clock.is_some() {
+            clock::mark_clock_set();
+        }
+        CONFIGS.lock().insert(System::current().id(), self);
+        sys
+    }
+}
@@ -1,11 +1,31 @@
-use std::fmt;
+use std::{
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    rc::Rc,
+    time::Duration,
+};
 
+#[cfg(feature = "telemetry")]
+use crate::wakeup::WakeupCause;
 use crate::{
-    actor::{Actor, ActorContext, ActorState, AsyncContext, SpawnHandle},
-    address::{Addr, AddressReceiver},
-    contextimpl::{AsyncContextParts, ContextFut, ContextParts},
+    actor::{
+        Actor, ActorContext, ActorState, AsyncContext, ResourceHandle, SpawnHandle, WaitHandle,
+    },
+    address::{Addr, AddressReceiver, RateLimit},
+    arbiter::spawn_actor,
+    clock::Instant,
+    component::ComponentHandle,
+    contextimpl::{
+        AsyncContextParts, Barrier, ContextFut, ContextParts, ContextStats, SwapOptions,
+    },
     fut::ActorFuture,
-    mailbox::Mailbox,
+    handler::{BatchHandler, Handler, Keyed, Message, ResponseActFuture, Sequenced, SignalHandler},
+    journal::{Journal, ReplayError},
+    logging::{ActorId, ActorLog},
+    mailbox::{Mailbox, SequenceGapPolicy, SequenceStats},
+    queue::OneshotReceiver,
+    settings::{ActorSettings, StopPolicy},
+    spill::PersistentMessage,
 };
 
 /// An actor execution context.
@@ -39,6 +59,14 @@ where
         self.parts.terminate()
     }
     #[inline]
+    fn terminate_hard(&mut self) {
+        self.parts.terminate_hard()
+    }
+    #[inline]
+    fn terminating(&self) -> bool {
+        self.parts.terminating()
+    }
+    #[inline]
     fn state(&self) -> ActorState {
         self.parts.state()
     }
@@ -78,6 +106,71 @@ where
     fn address(&self) -> Addr<A> {
         self.parts.address()
     }
+
+    #[inline]
+    fn attach_resource<R: 'static>(&mut self, res: R) -> ResourceHandle<R> {
+        self.parts.attach_resource(res)
+    }
+
+    fn set_timer<M>(&mut self, name: &'static str, after: Duration, msg: M)
+    where
+        A: Handler<M>,
+        M: Message + 'static,
+    {
+        if self.parts.state() == ActorState::Stopped {
+            log::error!("Context::set_timer called for stopped actor.");
+        } else {
+            self.parts.set_timer(name, after, msg)
+        }
+    }
+
+    #[inline]
+    fn cancel_timer(&mut self, name: &'static str) -> bool {
+        self.parts.cancel_timer(name)
+    }
+
+    #[inline]
+    fn timer_remaining(&self, name: &'static str) -> Option<Duration> {
+        self.parts.timer_remaining(name)
+    }
+
+    #[inline]
+    fn interrupted(&self) -> bool {
+        self.parts.interrupted()
+    }
+
+    #[inline]
+    fn clear_interrupt(&mut self) {
+        self.parts.clear_interrupt()
+    }
+
+    #[inline]
+    fn barrier(&mut self) -> Barrier<A> {
+        self.parts.barrier()
+    }
+
+    #[inline]
+    fn request_deadline(&self) -> Option<Instant> {
+        self.parts.request_deadline()
+    }
+
+    #[inline]
+    fn handoff_to(&mut self, target: Addr<A>) {
+        self.parts.handoff_to(target)
+    }
+
+    #[inline]
+    fn defer_fn<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut A, &mut Self) + 'static,
+    {
+        self.parts.defer_fn(f)
+    }
+
+    #[inline]
+    fn backoff(&mut self, dur: Duration) {
+        self.parts.backoff(dur)
+    }
 }
 
 impl<A> Context<A>
@@ -87,6 +180,8 @@ where
     /// Create a context without spawning it.
     ///
     /// The context can be spawned into an actor using its [`run`](`Context::run`) method.
+    /// Creating a context does not require a running system, so it can also be used to invoke
+    /// handlers directly in tests, see [`TestEnvelopeSink`](crate::dev::TestEnvelopeSink).
     ///
     /// ```
     /// # use actix::prelude::*;
@@ -145,10 +240,35 @@ where
     pub fn run(self, act: A) -> Addr<A> {
         let fut = self.into_future(act);
         let addr = fut.address();
-        actix_rt::spawn(fut);
+        spawn_actor(fut);
         addr
     }
 
+    pub(crate) fn mailbox_mut(&mut self) -> Option<&mut Mailbox<A>> {
+        self.mb.as_mut()
+    }
+
+    /// Creates the actor with `f`, returning `None` if `f` panicked.
+    pub(crate) fn create_with<F>(&mut self, f: F) -> Option<A>
+    where
+        F: FnOnce(&mut Self) -> A,
+    {
+        match panic::catch_unwind(AssertUnwindSafe(|| f(self))) {
+            Ok(act) => Some(act),
+            Err(_) => {
+                self.log().error(format_args!("factory panicked"));
+                None
+            }
+        }
+    }
+
+    /// Reports an actor which could not be created as stopped to the subscribers of its state.
+    ///
+    /// Its mailbox is closed once the context is dropped.
+    pub(crate) fn abandon(&mut self) {
+        self.parts.publish_state(ActorState::Stopped);
+    }
+
     pub fn into_future(mut self, act: A) -> ContextFut<A, Self> {
         let mb = self.mb.take().unwrap();
         ContextFut::new(self, act, mb)
@@ -164,7 +284,8 @@ where
 
     /// Sets the mailbox capacity.
     ///
-    /// The default mailbox capacity is 16 messages.
+    /// The default mailbox capacity is 16 messages, unless configured otherwise with
+    /// [`SystemConfig::mailbox_capacity()`](crate::SystemConfig::mailbox_capacity).
     /// #Examples
     /// ```
     /// # use actix::prelude::*;
@@ -188,6 +309,653 @@ where
         self.parts.set_mailbox_capacity(cap)
     }
 
+    /// Keeps interrupts requested with [`Addr::interrupt()`] until they are cleared with
+    /// [`AsyncContext::clear_interrupt()`], instead of clearing them before the next message.
+    pub fn set_interrupt_sticky(&mut self, sticky: bool) {
+        self.parts.set_interrupt_sticky(sticky)
+    }
+
+    /// Limits the rate of messages handled by the actor, `None` removes the limit.
+    ///
+    /// Depending on the [`RateLimitPolicy`](crate::RateLimitPolicy), messages over the limit
+    /// are either kept queued until the limit allows handling them or rejected when sent.
+    ///
+    /// ```
+    /// # use actix::prelude::*;
+    /// use actix::{RateLimit, RateLimitPolicy};
+    ///
+    /// struct MyActor;
+    ///
+    /// impl Actor for MyActor {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         ctx.set_rate_limit(Some(RateLimit {
+    ///             per_second: 100,
+    ///             burst: 10,
+    ///             policy: RateLimitPolicy::Reject,
+    ///         }));
+    ///         System::current().stop();
+    ///     }
+    /// }
+    /// # fn main() {
+    /// #     let sys = System::new();
+    /// #     sys.block_on(async { MyActor.start() });
+    /// #     sys.run().unwrap();
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `per_second` or `burst` is zero.
+    pub fn set_rate_limit(&mut self, limit: Option<RateLimit>) {
+        self.parts.set_rate_limit(limit)
+    }
+
+    /// Sets the watermarks of the mailbox pressure reported to
+    /// [`Actor::mailbox_pressure()`], `high == 0` disables the reports.
+    ///
+    /// The pressure rises once `high` messages are queued and falls once no more than `low`
+    /// messages are left, so a queue hovering around one of the watermarks is reported once.
+    /// The current level is also returned by [`stats()`](Self::stats).
+    ///
+    /// ```
+    /// # use actix::prelude::*;
+    /// use actix::PressureLevel;
+    ///
+    /// struct Ingest {
+    ///     shedding: bool,
+    /// }
+    ///
+    /// impl Actor for Ingest {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         ctx.set_mailbox_watermarks(10, 100);
+    ///     }
+    ///
+    ///     fn mailbox_pressure(&mut self, level: PressureLevel, _: &mut Self::Context) {
+    ///         // drop optional work while falling behind
+    ///         self.shedding = level == PressureLevel::Rising;
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `high` is not zero and `low` is not below `high`.
+    pub fn set_mailbox_watermarks(&mut self, low: usize, high: usize) {
+        self.parts.set_mailbox_watermarks(low, high)
+    }
+
+    /// Returns the maximum number of messages handled before yielding, `None` if unlimited.
+    pub fn message_budget(&self) -> Option<usize> {
+        self.parts.message_budget()
+    }
+
+    /// Sets the maximum number of messages handled before yielding to other tasks.
+    ///
+    /// The default is taken from [`SystemConfig::message_budget()`](crate::SystemConfig),
+    /// `None` lets the actor handle queued messages until its mailbox is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `budget` is `Some(0)`.
+    pub fn set_message_budget(&mut self, budget: Option<usize>) {
+        self.parts.set_message_budget(budget)
+    }
+
+    /// Returns how long [`Actor::finalizing()`] may defer stopping the actor.
+    pub fn finalize_timeout(&self) -> Duration {
+        self.parts.finalize_timeout()
+    }
+
+    /// Sets how long the future returned by [`Actor::finalizing()`] may run before the actor
+    /// stops regardless.
+    ///
+    /// The default is taken from [`SystemConfig::shutdown_timeout()`](crate::SystemConfig).
+    pub fn set_finalize_timeout(&mut self, timeout: Duration) {
+        self.parts.set_finalize_timeout(timeout)
+    }
+
+    /// Returns how long the actor may go without receiving a message, `None` if unlimited.
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.parts.idle_timeout()
+    }
+
+    /// Stops the actor once it has received no message for `timeout`, `None` removes the
+    /// timeout.
+    ///
+    /// The timeout only runs while the mailbox is empty, it starts over with every received
+    /// message. The actor is stopped as with [`ActorContext::stop()`].
+    pub fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.parts.set_idle_timeout(timeout)
+    }
+
+    /// Returns what happens to queued messages when the actor stops.
+    pub fn stop_policy(&self) -> StopPolicy {
+        self.parts.stop_policy()
+    }
+
+    /// Sets what happens to queued messages when the actor stops, by default they are
+    /// discarded.
+    pub fn set_stop_policy(&mut self, policy: StopPolicy) {
+        self.parts.set_stop_policy(policy)
+    }
+
+    /// Applies all execution settings at once, see [`Actor::start_with()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rate limit of `settings` has a zero `per_second` or `burst`.
+    pub fn apply_settings(&mut self, settings: &ActorSettings) {
+        self.parts.apply_settings(settings)
+    }
+
+    /// Returns the effective execution settings of the actor.
+    pub fn settings(&self) -> ActorSettings {
+        self.parts.settings()
+    }
+
+    /// Appends the handled messages of the types registered with
+    /// [`journal_message()`](Self::journal_message) to `journal`, under `persistence_id`.
+    ///
+    /// See [`Actor::create_replayed()`] to recover an actor from its journal.
+    pub fn set_journal(&mut self, journal: Box<dyn Journal>, persistence_id: impl Into<String>) {
+        self.parts.set_journal(journal, persistence_id.into())
+    }
+
+    /// Journals the messages of type `M`, and replays them with [`Actor::create_replayed()`].
+    ///
+    /// A message is appended to the journal once its handler has returned, messages which are
+    /// not handled because their sender gave up are not. Messages sent with
+    /// [`Addr::send_with_deadline()`] or collected by [`enable_batching()`](Self::enable_batching)
+    /// are not journaled.
+    pub fn journal_message<M>(&mut self)
+    where
+        A: Handler<M>,
+        M: PersistentMessage + Send + 'static,
+        M::Result: Send,
+    {
+        self.parts.journal_message::<M>()
+    }
+
+    /// Returns `true` while the journaled messages of the actor are replayed.
+    pub fn is_replaying(&self) -> bool {
+        self.parts.is_replaying()
+    }
+
+    /// Handles the messages of the journal before the actor starts.
+    pub(crate) fn replay(&mut self, act: &mut A) -> Result<(), ReplayError> {
+        let state = Rc::clone(self.parts.journal_state());
+        let Some((mut journal, persistence_id)) = state.borrow_mut().take_journal() else {
+            return Ok(());
+        };
+
+        self.parts.set_replaying(true);
+        let res = (|| {
+            for entry in journal.replay(&persistence_id)? {
+                let entry = entry?;
+                let ty = state
+                    .borrow()
+                    .find(&entry.type_name)
+                    .ok_or_else(|| ReplayError::UnknownType(entry.type_name.clone()))?;
+                ty.replay(&entry.bytes, act, self)
+                    .map_err(|error| ReplayError::Decode {
+                        type_name: entry.type_name,
+                        error,
+                    })?;
+                if self.parts.state() != ActorState::Running {
+                    return Err(ReplayError::Stopped);
+                }
+            }
+            Ok(())
+        })();
+        self.parts.set_replaying(false);
+
+        state.borrow_mut().restore_journal(journal);
+        res
+    }
+
+    /// Holds incoming messages in the mailbox until [`set_ready()`](Self::set_ready) is called.
+    ///
+    /// Messages are never handled before [`Actor::started()`] returns. Calling this method from
+    /// `started` extends that window, e.g. while the actor initializes itself asynchronously.
+    /// Spawned futures keep running meanwhile, and held messages are handled in the order they
+    /// were sent once the actor is ready. Senders are still subject to the mailbox capacity.
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actix::prelude::*;
+    /// struct MyActor;
+    ///
+    /// impl Actor for MyActor {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         ctx.buffer_until_ready();
+    ///         actix::clock::sleep(Duration::from_millis(10))
+    ///             .into_actor(self)
+    ///             .map(|_, _, ctx| ctx.set_ready())
+    ///             .spawn(ctx);
+    ///     }
+    /// }
+    /// ```
+    pub fn buffer_until_ready(&mut self) {
+        self.parts.buffer_until_ready()
+    }
+
+    /// Resumes handling messages held since [`buffer_until_ready()`](Self::buffer_until_ready).
+    pub fn set_ready(&mut self) {
+        self.parts.set_ready()
+    }
+
+    /// Returns `false` while incoming messages are held by
+    /// [`buffer_until_ready()`](Self::buffer_until_ready).
+    pub fn is_ready(&self) -> bool {
+        self.parts.is_ready()
+    }
+
+    /// Returns statistics of the context.
+    ///
+    /// Senders wake up a busy actor only once until it has drained its mailbox;
+    /// [`ContextStats::suppressed_wakeups`] counts the skipped wakeups.
+    pub fn stats(&self) -> ContextStats {
+        self.parts.stats()
+    }
+
+    /// Logs a warning through the [`log()`](Self::log) of the actor once a message was queued
+    /// in its mailbox for longer than `threshold`, `None` disables the warnings.
+    ///
+    /// The warning names the message type and the number of messages left queued. At most one
+    /// warning is logged per second. [`ContextStats::residency`] records the time every message
+    /// was queued for, regardless of the threshold.
+    #[cfg(feature = "telemetry")]
+    pub fn set_residency_warning(&mut self, threshold: Option<Duration>) {
+        self.parts.set_residency_warning(threshold)
+    }
+
+    /// Returns the causes of the recent wakeups of the actor, oldest first.
+    ///
+    /// Up to 32 causes are kept. A poll following several wakeups records each of their
+    /// causes, while [`WakeupCause::Modified`] marks the context looping again within a poll
+    /// because futures were spawned or cancelled. [`ContextStats::wakeups`] counts all of them.
+    #[cfg(feature = "telemetry")]
+    pub fn recent_wakeups(&self) -> Vec<WakeupCause> {
+        self.parts.recent_wakeups()
+    }
+
+    /// Replaces the actor instance, keeping the mailbox, addresses and spawned futures.
+    ///
+    /// The swap happens once the message or future being handled has completed, and after the
+    /// wait futures have completed, so every message is handled entirely by one instance.
+    /// Futures spawned by the previous instance, including the responses of
+    /// [`ResponseActFuture`](crate::ResponseActFuture) handlers, are polled with the new instance
+    /// unless [`SwapOptions::cancel_futures`] is set.
+    ///
+    /// The returned receiver resolves with the previous instance, which does not get its
+    /// [`Actor::stopped()`] called. If the actor stops before the swap, the new instance is
+    /// dropped and the receiver resolves with an error.
+    ///
+    /// ```
+    /// # use actix::prelude::*;
+    /// # use actix::SwapOptions;
+    /// struct Greeter(&'static str);
+    ///
+    /// impl Actor for Greeter {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         if self.0 == "hello" {
+    ///             ctx.swap_actor(Greeter("hi"), SwapOptions { run_started: true, ..Default::default() })
+    ///                 .map(|old, act: &mut Self, _| {
+    ///                     assert_eq!((old.unwrap().0, act.0), ("hello", "hi"));
+    ///                     System::current().stop();
+    ///                 })
+    ///                 .spawn(ctx);
+    ///         }
+    ///     }
+    /// }
+    /// # fn main() {
+    /// #     let sys = System::new();
+    /// #     sys.block_on(async { Greeter("hello").start() });
+    /// #     sys.run().unwrap();
+    /// # }
+    /// ```
+    pub fn swap_actor(&mut self, act: A, opts: SwapOptions) -> OneshotReceiver<A> {
+        self.parts.swap_actor(act, opts)
+    }
+
+    /// Creates a handle to the futures of the logical component `name`, see
+    /// [`ComponentHandle`].
+    ///
+    /// Futures spawned through the handle are cancelled together by
+    /// [`ComponentHandle::shutdown()`], and don't keep the actor alive unless the component is
+    /// [`essential()`](ComponentHandle::essential).
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actix::prelude::*;
+    /// struct Peer;
+    ///
+    /// impl Actor for Peer {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         let heartbeat = ctx.component("heartbeat").essential();
+    ///         heartbeat.run_interval(ctx, Duration::from_secs(1), |_, _| {
+    ///             // ping the remote peer
+    ///         });
+    ///
+    ///         let metrics = ctx.component("metrics");
+    ///         metrics.run_later(ctx, Duration::from_millis(10), |_, _| {
+    ///             System::current().stop();
+    ///         });
+    ///     }
+    /// }
+    /// # fn main() {
+    /// #     let sys = System::new();
+    /// #     sys.block_on(async { Peer.start() });
+    /// #     sys.run().unwrap();
+    /// # }
+    /// ```
+    pub fn component(&mut self, name: &'static str) -> ComponentHandle {
+        self.parts.component(name)
+    }
+
+    /// Returns the id of the actor.
+    ///
+    /// The id is assigned when the context is created and is kept across supervisor restarts.
+    pub fn actor_id(&self) -> ActorId {
+        self.parts.actor_id()
+    }
+
+    /// Returns a logger stamping records with the actor's type, id and arbiter.
+    ///
+    /// The context also emits `trace` records with the same stamp when the actor starts and
+    /// stops.
+    pub fn log(&self) -> ActorLog {
+        self.parts.log()
+    }
+
+    /// Spawns a future into the context, waiting for it to resolve, and returns a handle to it.
+    ///
+    /// Wait futures are executed strictly one at a time and the context does not
+    /// process messages or poll spawned futures until the wait queue is empty. The most recently
+    /// queued future executes first. The returned handle can be used to cancel the queued future
+    /// with [`cancel_wait`](Self::cancel_wait), and its position in the queue can be changed with
+    /// [`promote_wait`](Self::promote_wait).
+    pub fn wait_with_handle<F>(&mut self, fut: F) -> WaitHandle
+    where
+        F: ActorFuture<A, Output = ()> + 'static,
+    {
+        self.parts.wait_with_handle(fut)
+    }
+
+    /// Returns the number of wait futures, including the one currently executing.
+    pub fn wait_queue_len(&self) -> usize {
+        self.parts.wait_queue_len()
+    }
+
+    /// Returns the time since which the context has been blocked by wait futures, or `None` if
+    /// the wait queue is empty.
+    ///
+    /// The time is taken when a future is queued into an empty wait queue, so a watchdog can
+    /// detect actors which have not processed messages for too long.
+    pub fn waiting_since(&self) -> Option<Instant> {
+        self.parts.waiting_since()
+    }
+
+    /// Moves the wait future at `index` to the front of the wait queue.
+    ///
+    /// Index `0` is the wait future which executes next; a wait future that is already running
+    /// is suspended until the promoted one completes. Returns `false` if there is no future at
+    /// `index`.
+    pub fn promote_wait(&mut self, index: usize) -> bool {
+        self.parts.promote_wait(index)
+    }
+
+    /// Cancels a queued wait future.
+    ///
+    /// Returns `false` if the future has already completed or was cancelled.
+    pub fn cancel_wait(&mut self, handle: WaitHandle) -> bool {
+        self.parts.cancel_wait(handle)
+    }
+
+    /// Enables batching of queued messages of type `M`.
+    ///
+    /// Consecutive `M` messages waiting in the mailbox are collected and passed to
+    /// [`BatchHandler::handle_batch()`] once `max_batch` messages are collected, once `max_delay`
+    /// has elapsed since the first message of the batch, or once a message of another type has to
+    /// be handled, so the order of messages is preserved. Messages sent with `send` are handled
+    /// individually, see [`BatchHandler`] for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_batch` is zero.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actix::prelude::*;
+    /// #[derive(Message)]
+    /// #[rtype(result = "()")]
+    /// struct Insert(u32);
+    ///
+    /// struct Writer;
+    ///
+    /// impl Actor for Writer {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         ctx.enable_batching::<Insert>(100, Duration::from_millis(10));
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Insert> for Writer {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, msg: Insert, ctx: &mut Self::Context) {
+    ///         self.handle_batch(vec![msg], ctx);
+    ///     }
+    /// }
+    ///
+    /// impl BatchHandler<Insert> for Writer {
+    ///     fn handle_batch(&mut self, rows: Vec<Insert>, _: &mut Self::Context) {
+    ///         // write all rows in one round trip
+    ///     }
+    /// }
+    /// ```
+    pub fn enable_batching<M>(&mut self, max_batch: usize, max_delay: Duration)
+    where
+        A: BatchHandler<M>,
+        M: Message + Send + 'static,
+        M::Result: Send,
+    {
+        self.parts.enable_batching::<M>(max_batch, max_delay)
+    }
+
+    /// Enables partitioning of messages of type `M` by their [`Keyed::key()`].
+    ///
+    /// Messages are moved from the mailbox into a lane per key. The handler future of a message
+    /// is spawned once the previous message of its key has completed, so messages of the same key
+    /// are handled in order and never overlap, while up to `max_concurrent_keys` keys are handled
+    /// concurrently. A handler which waits for a future with [`AsyncContext::wait()`] holds back
+    /// all keys, and the handlers in flight are dropped with the other spawned futures once the
+    /// actor stops, failing their requests.
+    ///
+    /// Lanes are freed once idle for `idle_timeout`. Once lanes hold as many messages as the
+    /// mailbox, no more messages are received until they make progress.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_concurrent_keys` is zero.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actix::prelude::*;
+    /// #[derive(Message)]
+    /// #[rtype(result = "()")]
+    /// struct Event {
+    ///     account: u64,
+    /// }
+    ///
+    /// impl Keyed for Event {
+    ///     fn key(&self) -> u64 {
+    ///         self.account
+    ///     }
+    /// }
+    ///
+    /// struct Ledger;
+    ///
+    /// impl Actor for Ledger {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         ctx.enable_partitioning::<Event>(8, Duration::from_secs(60));
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Event> for Ledger {
+    ///     type Result = ResponseActFuture<Self, ()>;
+    ///
+    ///     fn handle(&mut self, event: Event, _: &mut Self::Context) -> Self::Result {
+    ///         Box::pin(fut::wrap_future(async move {
+    ///             // events of other accounts are applied meanwhile
+    ///         }))
+    ///     }
+    /// }
+    /// ```
+    pub fn enable_partitioning<M>(&mut self, max_concurrent_keys: usize, idle_timeout: Duration)
+    where
+        A: Handler<M, Result = ResponseActFuture<A, M::Result>>,
+        M: Message + Keyed + Send + 'static,
+        M::Result: Send,
+    {
+        self.parts
+            .enable_partitioning::<M>(max_concurrent_keys, idle_timeout)
+    }
+
+    /// Handles the signals of type `M` sent with [`Addr::signal()`] with
+    /// [`SignalHandler::handle_signals()`], once per poll with the number of pending signals,
+    /// instead of calling [`Handler::handle`] once per signal.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix::prelude::*;
+    /// #[derive(Default, Message)]
+    /// #[rtype(result = "()")]
+    /// struct Dirty;
+    ///
+    /// struct Renderer {
+    ///     frames: usize,
+    /// }
+    ///
+    /// impl Actor for Renderer {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         ctx.coalesce_signals::<Dirty>();
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Dirty> for Renderer {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, _: Dirty, ctx: &mut Self::Context) {
+    ///         self.handle_signals(1, ctx);
+    ///     }
+    /// }
+    ///
+    /// impl SignalHandler<Dirty> for Renderer {
+    ///     fn handle_signals(&mut self, _count: usize, _: &mut Self::Context) {
+    ///         // render once, however often the state changed
+    ///         self.frames += 1;
+    ///     }
+    /// }
+    /// ```
+    pub fn coalesce_signals<M>(&mut self)
+    where
+        A: SignalHandler<M>,
+        M: Message + 'static,
+    {
+        self.parts.coalesce_signals::<M>()
+    }
+
+    /// Enables sequencing of messages of type `M` by their [`Sequenced::seq()`].
+    ///
+    /// Messages are moved from the mailbox into a reorder buffer and handled strictly in the
+    /// order of their sequence numbers, starting with `start_seq`, whatever order they were sent
+    /// in. Messages whose sequence number was already handled or is already buffered are
+    /// dropped, failing their requests. A handler which waits for a future with
+    /// [`AsyncContext::wait()`] holds back the following messages.
+    ///
+    /// Once `max_gap_buffer` messages are buffered while a message is missing, the
+    /// [`SequenceGapPolicy`] set with [`set_sequence_gap_policy()`](Self::set_sequence_gap_policy)
+    /// applies, by default the missing messages are skipped. Enabling sequencing of `M` again
+    /// only changes `max_gap_buffer`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix::prelude::*;
+    /// #[derive(Message)]
+    /// #[rtype(result = "()")]
+    /// struct Chunk {
+    ///     seq: u64,
+    ///     data: Vec<u8>,
+    /// }
+    ///
+    /// impl Sequenced for Chunk {
+    ///     fn seq(&self) -> u64 {
+    ///         self.seq
+    ///     }
+    /// }
+    ///
+    /// struct Assembler {
+    ///     data: Vec<u8>,
+    /// }
+    ///
+    /// impl Actor for Assembler {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         ctx.enable_sequencing::<Chunk>(0, 64);
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Chunk> for Assembler {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, chunk: Chunk, _: &mut Self::Context) {
+    ///         // chunks arrive in order, even if several producers sent them
+    ///         self.data.extend(chunk.data);
+    ///     }
+    /// }
+    /// ```
+    pub fn enable_sequencing<M>(&mut self, start_seq: u64, max_gap_buffer: usize)
+    where
+        A: Handler<M>,
+        M: Message + Sequenced + Send + 'static,
+        M::Result: Send,
+    {
+        self.parts.enable_sequencing::<M>(start_seq, max_gap_buffer)
+    }
+
+    /// Sets the policy applied once the reorder buffer of messages of type `M` is full while a
+    /// message is missing, see [`enable_sequencing()`](Self::enable_sequencing).
+    ///
+    /// Returns `false` if sequencing of `M` is not enabled.
+    pub fn set_sequence_gap_policy<M: 'static>(&mut self, policy: SequenceGapPolicy) -> bool {
+        self.parts.set_sequence_gap_policy::<M>(policy)
+    }
+
+    /// Returns the next expected sequence number and the number of buffered messages of type
+    /// `M`, or `None` if sequencing of `M` is not enabled.
+    pub fn sequence_stats<M: 'static>(&self) -> Option<SequenceStats> {
+        self.parts.sequence_stats::<M>()
+    }
+
     /// Returns whether any addresses are still connected.
     pub fn connected(&self) -> bool {
         self.parts.connected()
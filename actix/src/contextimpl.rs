@@ -1,35 +1,222 @@
 use std::{
+    any::{Any, TypeId},
+    cell::{Cell, RefCell},
     fmt,
     future::Future,
+    marker::PhantomData,
+    mem,
+    panic::{self, AssertUnwindSafe},
     pin::Pin,
+    rc::{Rc, Weak},
+    sync::Arc,
     task::{Context, Poll},
+    time::Duration,
 };
 
 use bitflags::bitflags;
-use futures_core::ready;
 use smallvec::SmallVec;
 
 use crate::{
-    actor::{Actor, ActorContext, ActorState, AsyncContext, Running, SpawnHandle, Supervised},
-    address::{Addr, AddressSenderProducer},
-    contextitems::ActorWaitItem,
+    actor::{
+        Actor, ActorContext, ActorState, AsyncContext, AttachedResource, ResourceHandle, Running,
+        SpawnHandle, Supervised, WaitHandle,
+    },
+    address::{handle_coalesced, Addr, AddressSenderProducer, RateLimit},
+    clock::{self, Instant, Timer},
+    component::ComponentHandle,
+    config::SystemConfig,
+    contextitems::{ActorTimers, ActorWaitItem},
     fut::ActorFuture,
-    mailbox::Mailbox,
+    handler::{BatchHandler, Handler, Keyed, Message, ResponseActFuture, Sequenced, SignalHandler},
+    journal::{Journal, JournalState, JournaledType},
+    logging::{ActorId, ActorLog},
+    mailbox::{
+        Batcher, Mailbox, MessageBatcher, MessagePartitioner, MessageSequencer, Partitioner,
+        PressureLevel, SequenceGapPolicy, SequenceStats, Sequencer,
+    },
+    panic_report::{self, Attribution, PanicReport},
+    queue::{self, OneshotReceiver, OneshotSender},
+    settings::{ActorSettings, StopPolicy},
+    spill::PersistentMessage,
+};
+#[cfg(feature = "telemetry")]
+use crate::{
+    residency::ResidencyHistogram,
+    wakeup::{WakeupCause, WakeupCounts, WakeupTracker},
 };
 
 bitflags! {
     /// Internal context state.
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     struct ContextFlags: u8 {
         const STARTED =  0b0000_0001;
         const RUNNING =  0b0000_0010;
         const STOPPING = 0b0000_0100;
+        /// Set by `terminate()`, spawned futures are polled a last time before the actor stops.
+        const TERMINATING = 0b0000_1000;
         const STOPPED =  0b0001_0000;
         const MB_CAP_CHANGED = 0b0010_0000;
+        const MB_BUDGET_CHANGED = 0b0100_0000;
+        const BUFFERING = 0b1000_0000;
+
+        /// Lifecycle flags, exactly one of them is set at any time.
+        const LIFECYCLE = Self::RUNNING.bits() | Self::STOPPING.bits() | Self::STOPPED.bits();
+    }
+}
+
+impl ContextFlags {
+    /// Moves the context to the lifecycle state `to`, keeping all other flags.
+    ///
+    /// Illegal transitions panic in debug builds and are logged otherwise.
+    fn transition(&mut self, to: ContextFlags) {
+        let from = *self & ContextFlags::LIFECYCLE;
+        let legal = from.bits().count_ones() == 1
+            && to.bits().count_ones() == 1
+            && ContextFlags::LIFECYCLE.contains(to)
+            && (from == ContextFlags::RUNNING && to == ContextFlags::STOPPING
+                || from == ContextFlags::STOPPING && to == ContextFlags::RUNNING
+                || to == ContextFlags::STOPPED);
+
+        if !legal {
+            if cfg!(debug_assertions) {
+                panic!(
+                    "Illegal context state transition from {:?} to {:?}",
+                    self, to
+                );
+            }
+            log::error!(
+                "Illegal context state transition from {:?} to {:?}",
+                self,
+                to
+            );
+        }
+
+        self.remove(ContextFlags::LIFECYCLE);
+        self.insert(to);
+    }
+}
+
+struct Item<A> {
+    handle: SpawnHandle,
+    fut: Pin<Box<dyn ActorFuture<A, Output = ()>>>,
+    /// Tombstone of a cancelled item, removed once no item is being polled.
+    cancelled: bool,
+    /// Set when the item asked for a backoff, it is skipped until the next poll.
+    backoff: bool,
+}
+
+impl<A> Item<A> {
+    fn new(handle: SpawnHandle, fut: Box<dyn ActorFuture<A, Output = ()>>) -> Self {
+        Item {
+            handle,
+            fut: Pin::from(fut),
+            cancelled: false,
+            backoff: false,
+        }
     }
 }
 
-type Item<A> = (SpawnHandle, Pin<Box<dyn ActorFuture<A, Output = ()>>>);
+/// Statistics of an actor context, see [`Context::stats()`](crate::Context::stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ContextStats {
+    /// Number of times the actor was polled by its arbiter.
+    pub polls: u64,
+    /// Number of mailbox wakeups skipped because the actor was already woken up.
+    pub suppressed_wakeups: u64,
+    /// Number of requests sent with a deadline which were dropped without a reply, because
+    /// the deadline passed before or while they were handled.
+    pub expired_requests: u64,
+    /// Number of lanes of partitioned messages, including idle lanes which are not freed yet.
+    pub partition_lanes: u64,
+    /// Pressure level of the mailbox, see
+    /// [`Context::set_mailbox_watermarks()`](crate::Context::set_mailbox_watermarks).
+    pub mailbox_pressure: PressureLevel,
+    /// Number of wakeups of the actor per cause.
+    #[cfg(feature = "telemetry")]
+    pub wakeups: WakeupCounts,
+    /// Time messages were queued in the mailbox before the actor took them out.
+    #[cfg(feature = "telemetry")]
+    pub residency: ResidencyHistogram,
+}
+
+/// Future which resolves once the futures spawned before it have completed, created by
+/// [`AsyncContext::barrier()`].
+#[must_use = "futures do nothing unless polled"]
+pub struct Barrier<A> {
+    released: Rc<Cell<bool>>,
+    _act: PhantomData<fn() -> A>,
+}
+
+impl<A> fmt::Debug for Barrier<A> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Barrier")
+            .field("released", &self.released.get())
+            .finish()
+    }
+}
+
+impl<A: Actor> ActorFuture<A> for Barrier<A> {
+    type Output = ();
+
+    fn poll(
+        self: Pin<&mut Self>,
+        _: &mut A,
+        _: &mut A::Context,
+        _: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        // the context polls its futures again once it releases a barrier
+        if self.released.get() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Options of [`Context::swap_actor()`](crate::Context::swap_actor).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapOptions {
+    /// Call [`Actor::started()`] on the new instance once it is swapped in.
+    pub run_started: bool,
+    /// Cancel the futures spawned into the context, including streams and intervals, instead of
+    /// polling them with the new instance.
+    pub cancel_futures: bool,
+}
+
+/// Actor instance waiting to be swapped in.
+struct PendingSwap<A> {
+    act: A,
+    opts: SwapOptions,
+    tx: OneshotSender<A>,
+}
+
+/// Future returned by [`Actor::finalizing`] and the deadline it has to resolve by.
+struct Finalizer<A> {
+    fut: ResponseActFuture<A, ()>,
+    deadline: Pin<Box<Timer>>,
+}
+
+/// Function deferred with [`AsyncContext::defer_fn`].
+type Microtask<A> = Box<dyn FnOnce(&mut A, &mut <A as Actor>::Context)>;
+
+// Wait futures and spawned futures a context holds without allocating, chosen with the
+// `small_context` and `large_context` features.
+#[cfg(feature = "large_context")]
+const INLINE_WAIT: usize = 4;
+#[cfg(feature = "large_context")]
+const INLINE_ITEMS: usize = 12;
+#[cfg(all(feature = "small_context", not(feature = "large_context")))]
+const INLINE_WAIT: usize = 1;
+#[cfg(all(feature = "small_context", not(feature = "large_context")))]
+const INLINE_ITEMS: usize = 1;
+#[cfg(not(any(feature = "small_context", feature = "large_context")))]
+const INLINE_WAIT: usize = 2;
+#[cfg(not(any(feature = "small_context", feature = "large_context")))]
+const INLINE_ITEMS: usize = 3;
+
+/// Number of times functions deferred by deferred functions run in a row.
+const MICROTASK_DEPTH: usize = 16;
 
 pub trait AsyncContextParts<A>: ActorContext + AsyncContext<A>
 where
@@ -45,9 +232,42 @@ where
 {
     addr: AddressSenderProducer<A>,
     flags: ContextFlags,
-    wait: SmallVec<[ActorWaitItem<A>; 2]>,
-    items: SmallVec<[Item<A>; 3]>,
+    wait: SmallVec<[ActorWaitItem<A>; INLINE_WAIT]>,
+    items: SmallVec<[Item<A>; INLINE_ITEMS]>,
     handles: SmallVec<[SpawnHandle; 2]>,
+    wait_handle: WaitHandle,
+    /// Time at which the wait queue last became non-empty.
+    waiting_since: Option<Instant>,
+    batchers: Vec<Box<dyn Batcher<A>>>,
+    partitioners: Vec<Rc<dyn Partitioner<A>>>,
+    sequencers: Vec<Rc<dyn Sequencer<A>>>,
+    resources: Vec<Rc<dyn AttachedResource>>,
+    timers: Weak<RefCell<ActorTimers<A>>>,
+    microtasks: SmallVec<[Microtask<A>; 2]>,
+    swaps: Vec<PendingSwap<A>>,
+    /// Barriers with the last handle spawned before them.
+    barriers: Vec<(SpawnHandle, Rc<Cell<bool>>)>,
+    message_budget: Option<usize>,
+    rate_limit: Option<RateLimit>,
+    finalize_timeout: Duration,
+    idle_timeout: Option<Duration>,
+    stop_policy: StopPolicy,
+    journal: Option<Rc<RefCell<JournalState<A>>>>,
+    /// Set while journaled messages are replayed.
+    replaying: bool,
+    /// Deadline of the request being handled.
+    deadline: Option<Instant>,
+    /// Shortest backoff requested by the item being polled.
+    backoff: Option<Duration>,
+    /// Actor taking over the mailbox, see `handoff_to()`.
+    handoff: Option<Addr<A>>,
+    /// Number of spawned futures which don't keep the actor alive, see `component()`.
+    detached: Rc<Cell<usize>>,
+    expired_requests: u64,
+    polls: u64,
+    #[cfg(feature = "telemetry")]
+    wakeups: WakeupTracker,
+    id: ActorId,
 }
 
 impl<A> fmt::Debug for ContextParts<A>
@@ -57,6 +277,7 @@ where
 {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt.debug_struct("ContextParts")
+            .field("id", &self.id)
             .field("flags", &self.flags)
             .finish()
     }
@@ -70,15 +291,60 @@ where
     #[inline]
     /// Create new [`ContextParts`] instance
     pub fn new(addr: AddressSenderProducer<A>) -> Self {
+        let id = ActorId::next();
+        addr.set_actor_id(id);
         ContextParts {
             addr,
             flags: ContextFlags::RUNNING,
             wait: SmallVec::new(),
             items: SmallVec::new(),
             handles: SmallVec::from_slice(&[SpawnHandle::default(), SpawnHandle::default()]),
+            wait_handle: WaitHandle::default(),
+            waiting_since: None,
+            batchers: Vec::new(),
+            partitioners: Vec::new(),
+            sequencers: Vec::new(),
+            resources: Vec::new(),
+            timers: Weak::new(),
+            microtasks: SmallVec::new(),
+            swaps: Vec::new(),
+            barriers: Vec::new(),
+            message_budget: SystemConfig::current().get_message_budget(),
+            rate_limit: None,
+            finalize_timeout: SystemConfig::current().get_shutdown_timeout(),
+            idle_timeout: None,
+            stop_policy: StopPolicy::Discard,
+            journal: None,
+            replaying: false,
+            deadline: None,
+            backoff: None,
+            handoff: None,
+            detached: Rc::new(Cell::new(0)),
+            expired_requests: 0,
+            polls: 0,
+            #[cfg(feature = "telemetry")]
+            wakeups: WakeupTracker::new(),
+            id,
         }
     }
 
+    /// Publishes the state of the actor to the subscribers of its address.
+    pub(crate) fn publish_state(&self, state: ActorState) {
+        self.addr.publish_state(state);
+    }
+
+    /// Returns the id of the actor.
+    #[inline]
+    pub fn actor_id(&self) -> ActorId {
+        self.id
+    }
+
+    /// Returns a logger stamping records with the actor's type and id.
+    #[inline]
+    pub fn log(&self) -> ActorLog {
+        ActorLog::new::<A>(self.id)
+    }
+
     #[inline]
     /// Initiate stop process for actor execution
     ///
@@ -86,26 +352,52 @@ where
     /// `Actor::stopping()` method.
     pub fn stop(&mut self) {
         if self.flags.contains(ContextFlags::RUNNING) {
-            self.flags.remove(ContextFlags::RUNNING);
-            self.flags.insert(ContextFlags::STOPPING);
+            self.flags.transition(ContextFlags::STOPPING);
+            self.addr.publish_state(ActorState::Stopping);
         }
     }
 
     #[inline]
     /// Terminate actor execution
+    ///
+    /// Spawned futures are polled once more before the actor stops.
     pub fn terminate(&mut self) {
-        self.flags = ContextFlags::STOPPED;
+        self.flags.transition(ContextFlags::STOPPED);
+        self.flags.insert(ContextFlags::TERMINATING);
+    }
+
+    #[inline]
+    /// Terminate actor execution without polling spawned futures again
+    pub fn terminate_hard(&mut self) {
+        self.flags.transition(ContextFlags::STOPPED);
+        self.flags.remove(ContextFlags::TERMINATING);
+    }
+
+    #[inline]
+    /// Whether the actor was terminated with `terminate()`
+    pub fn terminating(&self) -> bool {
+        self.flags.contains(ContextFlags::TERMINATING)
     }
 
     #[inline]
     /// Actor execution state
+    ///
+    /// The state is determined by the lifecycle flag of the context:
+    ///
+    /// - `STOPPED`: [`ActorState::Stopped`], the actor was terminated or is about to stop.
+    /// - `STOPPING`: [`ActorState::Stopping`], [`Actor::stopping()`] is going to be called.
+    /// - `RUNNING`: [`ActorState::Running`], including before [`Actor::started()`] was called.
+    ///
+    /// Exactly one of them is set, other flags do not affect the state. Should the flags ever be
+    /// inconsistent, the most advanced state wins, and no flags at all map to
+    /// [`ActorState::Started`].
     pub fn state(&self) -> ActorState {
-        if self.flags.contains(ContextFlags::RUNNING) {
-            ActorState::Running
-        } else if self.flags.contains(ContextFlags::STOPPED) {
+        if self.flags.contains(ContextFlags::STOPPED) {
             ActorState::Stopped
         } else if self.flags.contains(ContextFlags::STOPPING) {
             ActorState::Stopping
+        } else if self.flags.contains(ContextFlags::RUNNING) {
+            ActorState::Running
         } else {
             ActorState::Started
         }
@@ -115,6 +407,7 @@ where
     /// Is context waiting for future completion
     pub fn waiting(&self) -> bool {
         !self.wait.is_empty()
+            || !self.swaps.is_empty()
             || self
                 .flags
                 .intersects(ContextFlags::STOPPING | ContextFlags::STOPPED)
@@ -135,7 +428,7 @@ where
         let handle = self.handles[0].next();
         self.handles[0] = handle;
         let fut: Box<dyn ActorFuture<A, Output = ()>> = Box::new(fut);
-        self.items.push((handle, Pin::from(fut)));
+        self.items.push(Item::new(handle, fut));
         handle
     }
 
@@ -147,7 +440,65 @@ where
     where
         F: ActorFuture<A, Output = ()> + 'static,
     {
-        self.wait.push(ActorWaitItem::new(f));
+        self.wait_with_handle(f);
+    }
+
+    /// Spawn new future to this context and wait future completion.
+    ///
+    /// Returns a handle that can be used to cancel or promote the queued future.
+    pub fn wait_with_handle<F>(&mut self, f: F) -> WaitHandle
+    where
+        F: ActorFuture<A, Output = ()> + 'static,
+    {
+        let handle = self.wait_handle.next();
+        self.wait_handle = handle;
+        if self.wait.is_empty() {
+            self.waiting_since = Some(clock::now());
+        }
+        self.wait.push(ActorWaitItem::new(f, handle));
+        handle
+    }
+
+    /// Time since which the context has been blocked by wait futures, `None` if it is not.
+    pub fn waiting_since(&self) -> Option<Instant> {
+        if self.wait.is_empty() {
+            None
+        } else {
+            self.waiting_since
+        }
+    }
+
+    #[inline]
+    /// Number of wait futures, including the one currently executing.
+    pub fn wait_queue_len(&self) -> usize {
+        self.wait.len()
+    }
+
+    /// Move wait future at `index` to the front of the wait queue.
+    ///
+    /// Index `0` is the wait future that executes next. Returns `false` if there is no
+    /// future at `index`.
+    pub fn promote_wait(&mut self, index: usize) -> bool {
+        if index >= self.wait.len() {
+            return false;
+        }
+        // wait futures are executed from the back of the list
+        let item = self.wait.remove(self.wait.len() - 1 - index);
+        self.wait.push(item);
+        true
+    }
+
+    /// Cancel queued wait future.
+    ///
+    /// Returns `false` if the future has already completed.
+    pub fn cancel_wait(&mut self, handle: WaitHandle) -> bool {
+        match self.wait.iter().position(|item| item.handle() == handle) {
+            Some(idx) => {
+                self.wait.remove(idx);
+                true
+            }
+            None => false,
+        }
     }
 
     #[inline]
@@ -157,6 +508,137 @@ where
         true
     }
 
+    /// Attach a resource which is dropped once the actor has stopped.
+    pub fn attach_resource<R: 'static>(&mut self, res: R) -> ResourceHandle<R> {
+        // forget resources which were detached in the meantime
+        self.resources.retain(|res| res.is_attached());
+        let (handle, res) = ResourceHandle::new(res);
+        self.resources.push(res);
+        handle
+    }
+
+    /// Creates a handle to the futures of the logical component `name`.
+    pub fn component(&mut self, name: &'static str) -> ComponentHandle {
+        ComponentHandle::new(name, Rc::clone(&self.detached))
+    }
+
+    /// Returns a future which resolves once the futures spawned so far have completed.
+    pub fn barrier(&mut self) -> Barrier<A> {
+        let released = Rc::new(Cell::new(false));
+        self.barriers.push((self.handles[0], Rc::clone(&released)));
+        Barrier {
+            released,
+            _act: PhantomData,
+        }
+    }
+
+    /// Arm the timer `name`, replacing a previous one of the same name.
+    pub fn set_timer<M>(&mut self, name: &'static str, after: Duration, msg: M)
+    where
+        A: Handler<M>,
+        M: Message + 'static,
+    {
+        let timers = match self.timers.upgrade() {
+            Some(timers) => timers,
+            None => {
+                // the item firing the timers completes once none is left
+                let (timers, item) = ActorTimers::new();
+                let _handle = self.spawn(item);
+                #[cfg(feature = "telemetry")]
+                {
+                    self.wakeups.timers = _handle;
+                }
+                self.timers = Rc::downgrade(&timers);
+                timers
+            }
+        };
+        timers.borrow_mut().set(name, after, msg);
+    }
+
+    /// Returns the named timer being fired, see [`PanicReport`].
+    fn firing_timer(&self) -> Attribution {
+        let timers = self.timers.upgrade()?;
+        let timers = timers.try_borrow().ok()?;
+        timers.firing()
+    }
+
+    /// Cancel the timer `name`, returning `false` if it is not armed.
+    pub fn cancel_timer(&mut self, name: &'static str) -> bool {
+        self.timers
+            .upgrade()
+            .map_or(false, |timers| timers.borrow_mut().cancel(name))
+    }
+
+    /// Returns the time left until the timer `name` fires.
+    pub fn timer_remaining(&self, name: &'static str) -> Option<Duration> {
+        self.timers
+            .upgrade()
+            .and_then(|timers| timers.borrow().remaining(name))
+    }
+
+    /// Whether an interrupt was requested with [`Addr::interrupt()`].
+    pub fn interrupted(&self) -> bool {
+        self.addr.interrupted()
+    }
+
+    /// Clear a requested interrupt.
+    pub fn clear_interrupt(&mut self) {
+        self.addr.clear_interrupt()
+    }
+
+    /// Returns the deadline of the request being handled.
+    pub fn request_deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Hands the mailbox over to `target` once the current message is handled, and stops.
+    pub fn handoff_to(&mut self, target: Addr<A>) {
+        self.handoff = Some(target);
+        self.stop();
+    }
+
+    /// Sets the deadline of the request being handled, returning the previous one.
+    pub(crate) fn set_request_deadline(&mut self, deadline: Option<Instant>) -> Option<Instant> {
+        mem::replace(&mut self.deadline, deadline)
+    }
+
+    /// Counts a request dropped because its deadline passed.
+    pub(crate) fn expire_request(&mut self) {
+        self.expired_requests += 1;
+    }
+
+    /// Keep requested interrupts across messages until they are cleared.
+    pub fn set_interrupt_sticky(&mut self, sticky: bool) {
+        self.addr.set_interrupt_sticky(sticky)
+    }
+
+    /// Asks for the item being polled to be polled again after `dur`.
+    pub fn backoff(&mut self, dur: Duration) {
+        self.backoff = Some(self.backoff.map_or(dur, |backoff| backoff.min(dur)));
+    }
+
+    /// Defer a function until the current message or future has completed.
+    pub fn defer_fn<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut A, &mut A::Context) + 'static,
+    {
+        self.microtasks.push(Box::new(f));
+    }
+
+    /// Replace the actor instance once the current message or future has completed.
+    pub fn swap_actor(&mut self, act: A, opts: SwapOptions) -> OneshotReceiver<A> {
+        let (tx, rx) = queue::oneshot();
+        self.swaps.push(PendingSwap { act, opts, tx });
+        rx
+    }
+
+    /// Drop attached resources in reverse attach order.
+    fn release_resources(&mut self) {
+        while let Some(res) = self.resources.pop() {
+            res.release();
+        }
+    }
+
     #[inline]
     pub fn capacity(&mut self) -> usize {
         self.addr.capacity()
@@ -168,17 +650,266 @@ where
         self.addr.set_capacity(cap);
     }
 
+    #[inline]
+    pub fn set_rate_limit(&mut self, limit: Option<RateLimit>) {
+        self.addr.set_rate_limit(limit);
+        self.rate_limit = limit;
+    }
+
+    #[inline]
+    pub fn set_mailbox_watermarks(&mut self, low: usize, high: usize) {
+        self.addr.set_watermarks(low, high);
+    }
+
+    #[inline]
+    pub fn message_budget(&self) -> Option<usize> {
+        self.message_budget
+    }
+
+    #[inline]
+    pub fn set_message_budget(&mut self, budget: Option<usize>) {
+        assert!(
+            budget != Some(0),
+            "Message budget must be greater than zero"
+        );
+        self.flags.insert(ContextFlags::MB_BUDGET_CHANGED);
+        self.message_budget = budget;
+    }
+
+    #[inline]
+    pub fn finalize_timeout(&self) -> Duration {
+        self.finalize_timeout
+    }
+
+    #[inline]
+    pub fn set_finalize_timeout(&mut self, timeout: Duration) {
+        self.finalize_timeout = timeout;
+    }
+
+    #[inline]
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+    }
+
+    #[inline]
+    pub fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.idle_timeout = timeout;
+    }
+
+    #[inline]
+    pub fn stop_policy(&self) -> StopPolicy {
+        self.stop_policy
+    }
+
+    #[inline]
+    pub fn set_stop_policy(&mut self, policy: StopPolicy) {
+        self.stop_policy = policy;
+    }
+
+    /// Returns the journal state, created on first use.
+    pub(crate) fn journal_state(&mut self) -> &Rc<RefCell<JournalState<A>>> {
+        self.journal
+            .get_or_insert_with(|| Rc::new(RefCell::new(JournalState::new())))
+    }
+
+    /// Appends handled messages of the journaled types to `journal`.
+    pub fn set_journal(&mut self, journal: Box<dyn Journal>, persistence_id: String) {
+        self.journal_state()
+            .borrow_mut()
+            .set_journal(journal, persistence_id);
+    }
+
+    /// Journals and replays messages of type `M`.
+    pub fn journal_message<M>(&mut self)
+    where
+        A: Handler<M>,
+        M: PersistentMessage + Send + 'static,
+        M::Result: Send,
+    {
+        self.journal_state()
+            .borrow_mut()
+            .add_type(JournaledType::new::<M>());
+    }
+
+    #[inline]
+    pub fn is_replaying(&self) -> bool {
+        self.replaying
+    }
+
+    #[inline]
+    pub(crate) fn set_replaying(&mut self, replaying: bool) {
+        self.replaying = replaying;
+    }
+
+    /// Applies all execution settings at once.
+    pub fn apply_settings(&mut self, settings: &ActorSettings) {
+        self.set_mailbox_capacity(settings.get_mailbox_capacity());
+        self.set_message_budget(settings.get_message_budget());
+        self.set_rate_limit(settings.get_rate_limit());
+        self.set_finalize_timeout(settings.get_finalize_timeout());
+        self.set_idle_timeout(settings.get_idle_timeout());
+        self.set_stop_policy(settings.get_stop_policy());
+    }
+
+    /// Returns the effective execution settings.
+    pub fn settings(&self) -> ActorSettings {
+        ActorSettings::default()
+            .mailbox(self.addr.capacity())
+            .budget(self.message_budget)
+            .rate_limit(self.rate_limit)
+            .finalize_timeout(self.finalize_timeout)
+            .idle_timeout(self.idle_timeout)
+            .stop_policy(self.stop_policy)
+    }
+
+    /// Hold messages in the mailbox until `set_ready` is called.
+    #[inline]
+    pub fn buffer_until_ready(&mut self) {
+        self.flags.insert(ContextFlags::BUFFERING);
+    }
+
+    /// Resume handling messages held by `buffer_until_ready`.
+    #[inline]
+    pub fn set_ready(&mut self) {
+        self.flags.remove(ContextFlags::BUFFERING);
+    }
+
+    /// Returns `false` while messages are held by `buffer_until_ready`.
+    #[inline]
+    pub fn is_ready(&self) -> bool {
+        !self.flags.contains(ContextFlags::BUFFERING)
+    }
+
+    /// Returns statistics of the context.
+    pub fn stats(&self) -> ContextStats {
+        ContextStats {
+            polls: self.polls,
+            suppressed_wakeups: self.addr.suppressed_wakeups() as u64,
+            expired_requests: self.expired_requests,
+            partition_lanes: self.partitioners.iter().map(|p| p.lanes() as u64).sum(),
+            mailbox_pressure: self.addr.pressure(),
+            #[cfg(feature = "telemetry")]
+            wakeups: self.wakeups.counts(),
+            #[cfg(feature = "telemetry")]
+            residency: self.addr.residency(),
+        }
+    }
+
+    /// Logs a warning once a message was queued for longer than `threshold`, `None` disables
+    /// the warnings.
+    #[cfg(feature = "telemetry")]
+    pub fn set_residency_warning(&mut self, threshold: Option<Duration>) {
+        self.addr.set_residency_warning(threshold)
+    }
+
+    /// Returns the causes of the recent wakeups of the actor, oldest first.
+    #[cfg(feature = "telemetry")]
+    pub fn recent_wakeups(&self) -> Vec<WakeupCause> {
+        self.wakeups.recent()
+    }
+
     #[inline]
     pub fn address(&self) -> Addr<A> {
         Addr::new(self.addr.sender())
     }
 
+    /// Enable batching of queued messages of type `M`
+    pub fn enable_batching<M>(&mut self, max_batch: usize, max_delay: Duration)
+    where
+        A: BatchHandler<M>,
+        M: Message + Send + 'static,
+        M::Result: Send,
+    {
+        self.batchers
+            .push(Box::new(MessageBatcher::<M>::new(max_batch, max_delay)));
+    }
+
+    /// Enable partitioning of messages of type `M` by key
+    pub fn enable_partitioning<M>(&mut self, max_concurrent_keys: usize, idle_timeout: Duration)
+    where
+        A: Handler<M, Result = ResponseActFuture<A, M::Result>>,
+        M: Message + Keyed + Send + 'static,
+        M::Result: Send,
+    {
+        let partitioner = self
+            .partitioners
+            .iter()
+            .find(|p| p.msg_type() == TypeId::of::<M>());
+        match partitioner {
+            Some(partitioner) => {
+                assert!(
+                    max_concurrent_keys > 0,
+                    "Number of concurrent keys must be greater than zero"
+                );
+                partitioner.set_limits(max_concurrent_keys, idle_timeout);
+            }
+            None => self
+                .partitioners
+                .push(Rc::new(RefCell::new(MessagePartitioner::<M>::new(
+                    max_concurrent_keys,
+                    idle_timeout,
+                )))),
+        }
+    }
+
+    /// Handle the signals of type `M` with `SignalHandler::handle_signals()`
+    pub fn coalesce_signals<M>(&mut self)
+    where
+        A: SignalHandler<M>,
+        M: Message + 'static,
+    {
+        self.addr.set_signal_dispatch::<M>(handle_coalesced::<A, M>);
+    }
+
+    /// Enable sequencing of messages of type `M`
+    pub fn enable_sequencing<M>(&mut self, start_seq: u64, max_gap_buffer: usize)
+    where
+        A: Handler<M>,
+        M: Message + Sequenced + Send + 'static,
+        M::Result: Send,
+    {
+        match self.sequencer::<M>() {
+            Some(sequencer) => sequencer.set_max_gap_buffer(max_gap_buffer),
+            None => self
+                .sequencers
+                .push(Rc::new(RefCell::new(MessageSequencer::<M>::new(
+                    start_seq,
+                    max_gap_buffer,
+                )))),
+        }
+    }
+
+    fn sequencer<M: 'static>(&self) -> Option<&Rc<dyn Sequencer<A>>> {
+        self.sequencers
+            .iter()
+            .find(|s| s.msg_type() == TypeId::of::<M>())
+    }
+
+    /// Set the policy applied to gaps in the sequence of messages of type `M`
+    pub fn set_sequence_gap_policy<M: 'static>(&mut self, policy: SequenceGapPolicy) -> bool {
+        match self.sequencer::<M>() {
+            Some(sequencer) => {
+                sequencer.set_gap_policy(policy);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// State of the sequencing of messages of type `M`
+    pub fn sequence_stats<M: 'static>(&self) -> Option<SequenceStats> {
+        self.sequencer::<M>().map(|s| s.stats())
+    }
+
     /// Restart context. Cleanup all futures, except address queue.
     #[inline]
     pub(crate) fn restart(&mut self) {
+        // a restart resets the context, so any state may be left
         self.flags = ContextFlags::RUNNING;
         self.wait = SmallVec::new();
         self.items = SmallVec::new();
+        self.microtasks = SmallVec::new();
+        self.swaps = Vec::new();
         self.handles[0] = SpawnHandle::default();
     }
 
@@ -194,6 +925,17 @@ where
     }
 }
 
+impl<A> Drop for ContextParts<A>
+where
+    A: Actor,
+    A::Context: AsyncContext<A>,
+{
+    fn drop(&mut self) {
+        // the context could be dropped without being stopped, e.g. when its arbiter stops
+        self.release_resources();
+    }
+}
+
 pub struct ContextFut<A, C>
 where
     C: AsyncContextParts<A> + Unpin,
@@ -202,8 +944,19 @@ where
     ctx: C,
     act: A,
     mailbox: Mailbox<A>,
-    wait: SmallVec<[ActorWaitItem<A>; 2]>,
-    items: SmallVec<[Item<A>; 3]>,
+    items: SmallVec<[Item<A>; INLINE_ITEMS]>,
+    /// Future returned by `Actor::finalizing()` and its deadline, polled instead of anything else.
+    finalizer: Option<Box<Finalizer<A>>>,
+    /// Cleared when the context is dropped, nothing could drive a finalizer anymore.
+    finalize: bool,
+    /// Set while an actor which agreed to stop handles its queued messages.
+    draining: bool,
+    /// Set once the actor has stopped and `Actor::stopped()` was called.
+    done: bool,
+    /// Timer armed for the earliest backoff requested by the items.
+    backoff: Option<Pin<Box<Timer>>>,
+    /// Set while the context is polled, a context dropped while it is set has panicked.
+    polling: bool,
 }
 
 impl<A, C> fmt::Debug for ContextFut<A, C>
@@ -222,12 +975,53 @@ where
     A: Actor<Context = C>,
 {
     fn drop(&mut self) {
-        if self.alive() {
+        let panic = if self.polling {
+            self.polling = false;
+            let attribution = self
+                .mailbox
+                .handling()
+                .or_else(|| self.ctx.parts().firing_timer());
+            let report = Arc::new(PanicReport::new::<A>(attribution));
+            self.ctx.parts().log().error(format_args!("{}", report));
+            self.act.handler_panicked(&report);
+            // linked actors are notified with the report
+            panic_report::releasing(&report, || self.release())
+        } else {
+            self.release()
+        };
+        if let Some(panic) = panic {
+            panic::resume_unwind(panic);
+        }
+    }
+}
+
+impl<A, C> ContextFut<A, C>
+where
+    C: AsyncContextParts<A> + Unpin,
+    A: Actor<Context = C>,
+{
+    /// Stops the dropped context and releases its futures and resources.
+    ///
+    /// Returns the first panic raised while dropping its futures.
+    fn release(&mut self) -> Option<Box<dyn Any + Send>> {
+        // give the actor a chance to stop, or to run `stopped()` if it was terminated, nothing
+        // could drive draining its mailbox anymore
+        if !self.done {
+            self.finalize = false;
+            self.ctx.parts().set_stop_policy(StopPolicy::Discard);
             self.ctx.parts().stop();
             let waker = futures_task::noop_waker();
             let mut cx = std::task::Context::from_waker(&waker);
-            let _ = Pin::new(self).poll(&mut cx);
+            let _ = Pin::new(&mut *self).poll(&mut cx);
+        }
+
+        // futures could refer to the actor, they are dropped first
+        let panic = self.drop_futures();
+        if !self.done {
+            self.act.abandoned();
         }
+        self.ctx.parts().release_resources();
+        panic
     }
 }
 
@@ -237,12 +1031,19 @@ where
     A: Actor<Context = C>,
 {
     pub fn new(ctx: C, act: A, mailbox: Mailbox<A>) -> Self {
+        #[cfg(feature = "backtrace")]
+        panic_report::install_hook();
         ContextFut {
             ctx,
             act,
             mailbox,
-            wait: SmallVec::new(),
             items: SmallVec::new(),
+            finalizer: None,
+            finalize: true,
+            draining: false,
+            done: false,
+            backoff: None,
+            polling: false,
         }
     }
 
@@ -264,15 +1065,26 @@ where
             .intersects(ContextFlags::STOPPING | ContextFlags::STOPPED)
     }
 
+    #[inline]
+    fn has_wait(&mut self) -> bool {
+        let parts = self.ctx.parts();
+        (!parts.wait.is_empty() || !parts.swaps.is_empty()) && !self.stopping()
+    }
+
     #[inline]
     pub fn alive(&mut self) -> bool {
         if self.ctx.parts().flags.contains(ContextFlags::STOPPED) {
             false
+        } else if self.draining {
+            !self.mailbox.is_drained() || !self.ctx.parts().wait.is_empty()
         } else {
-            !self.ctx.parts().flags.contains(ContextFlags::STARTED)
+            let parts = self.ctx.parts();
+            // futures of components which are not essential don't count
+            let items = self.items.len() + parts.items.len();
+            !parts.flags.contains(ContextFlags::STARTED)
                 || self.mailbox.connected()
-                || !self.items.is_empty()
-                || !self.wait.is_empty()
+                || items > parts.detached.get()
+                || !self.ctx.parts().wait.is_empty()
         }
     }
 
@@ -283,8 +1095,8 @@ where
         A: Supervised,
     {
         if self.mailbox.connected() {
-            self.wait = SmallVec::new();
             self.items = SmallVec::new();
+            self.draining = false;
             self.ctx.parts().restart();
             self.act.restarting(&mut self.ctx);
             true
@@ -297,54 +1109,284 @@ where
         let mut modified = false;
 
         let parts = self.ctx.parts();
-        if !parts.wait.is_empty() {
-            modified = true;
-            self.wait.extend(parts.wait.drain(0..));
-        }
         if !parts.items.is_empty() {
             modified = true;
             self.items.extend(parts.items.drain(0..));
         }
         //
+        if !parts.batchers.is_empty() {
+            modified = true;
+            for batcher in parts.batchers.drain(..) {
+                self.mailbox.add_batcher(batcher);
+            }
+        }
+        let added = self.mailbox.partitioners();
+        if parts.partitioners.len() > added {
+            modified = true;
+            for partitioner in &parts.partitioners[added..] {
+                self.mailbox.add_partitioner(Rc::clone(partitioner));
+            }
+        }
+        let added = self.mailbox.sequencers();
+        if parts.sequencers.len() > added {
+            modified = true;
+            for sequencer in &parts.sequencers[added..] {
+                self.mailbox.add_sequencer(Rc::clone(sequencer));
+            }
+        }
+        if let Some(ref journal) = parts.journal {
+            if !self.mailbox.has_journal() {
+                self.mailbox.set_journal(Rc::clone(journal));
+            }
+        }
         if parts.flags.contains(ContextFlags::MB_CAP_CHANGED) {
             modified = true;
             parts.flags.remove(ContextFlags::MB_CAP_CHANGED);
         }
-        if parts.handles.len() > 2 {
+        if parts.flags.contains(ContextFlags::MB_BUDGET_CHANGED) {
+            modified = true;
+            parts.flags.remove(ContextFlags::MB_BUDGET_CHANGED);
+            self.mailbox.set_budget(parts.message_budget);
+        }
+        if self.cancel_items() {
             modified = true;
         }
 
         modified
     }
 
-    fn clean_canceled_handle(&mut self) {
-        fn remove_item_by_handle<C>(
-            items: &mut SmallVec<[Item<C>; 3]>,
-            handle: &SpawnHandle,
-        ) -> bool {
-            let mut idx = 0;
-            let mut removed = false;
-            while idx < items.len() {
-                if &items[idx].0 == handle {
-                    items.swap_remove(idx);
-                    removed = true;
-                } else {
-                    idx += 1;
+    /// Marks items of cancelled handles, returns `true` if any item was cancelled.
+    ///
+    /// Items are not removed here, because this can run while an item is being polled.
+    /// Cancelled items are skipped by the poll loop and removed by `remove_cancelled()`.
+    fn cancel_items(&mut self) -> bool {
+        let mut cancelled = false;
+        while self.ctx.parts().handles.len() > 2 {
+            let handle = self.ctx.parts().handles.pop().unwrap();
+            for item in self.items.iter_mut() {
+                if item.handle == handle && !item.cancelled {
+                    item.cancelled = true;
+                    cancelled = true;
+                }
+            }
+            // item is not merged into ContextFut.items yet
+            for item in self.ctx.parts().items.iter_mut() {
+                if item.handle == handle && !item.cancelled {
+                    item.cancelled = true;
+                    cancelled = true;
                 }
             }
-            removed
         }
+        cancelled
+    }
 
-        while self.ctx.parts().handles.len() > 2 {
-            let handle = self.ctx.parts().handles.pop().unwrap();
-            // remove item from ContextFut.items in case associated item is already merged
-            if !remove_item_by_handle(&mut self.items, &handle) {
-                // item is not merged into ContextFut.items yet,
-                // so it should be in ContextParts.items
-                remove_item_by_handle(&mut self.ctx.parts().items, &handle);
+    /// Runs deferred functions, returns `true` if deferred functions are left over.
+    fn run_microtasks(&mut self) -> bool {
+        for _ in 0..MICROTASK_DEPTH {
+            let microtasks = mem::take(&mut self.ctx.parts().microtasks);
+            if microtasks.is_empty() {
+                return false;
+            }
+            for f in microtasks {
+                f(&mut self.act, &mut self.ctx);
+            }
+        }
+        !self.ctx.parts().microtasks.is_empty()
+    }
+
+    /// Swaps in pending actor instances, handing the previous ones back to the requesters.
+    ///
+    /// Must only be called between messages, once the wait futures have completed.
+    fn swap_actors(&mut self) {
+        if self.ctx.parts().swaps.is_empty() {
+            return;
+        }
+        // functions deferred by the previous instance run on it
+        self.run_microtasks();
+
+        // swaps requested by the `started()` of a new instance are applied afterwards
+        for swap in mem::take(&mut self.ctx.parts().swaps) {
+            let old = mem::replace(&mut self.act, swap.act);
+            if swap.opts.cancel_futures {
+                self.items = SmallVec::new();
+                self.ctx.parts().items = SmallVec::new();
+            }
+            self.ctx.parts().log().trace(format_args!("swapped"));
+            if swap.opts.run_started {
+                Actor::started(&mut self.act, &mut self.ctx);
+            }
+            let _ = swap.tx.send(old);
+        }
+        self.merge();
+    }
+
+    /// Drops wait futures, spawned futures, deferred functions and pending swaps, in this order.
+    ///
+    /// Returns the first panic raised while dropping them; the remaining ones are still dropped.
+    fn drop_futures(&mut self) -> Option<Box<dyn Any + Send>> {
+        fn drop_all<T>(
+            values: impl IntoIterator<Item = T>,
+            panic: &mut Option<Box<dyn Any + Send>>,
+        ) {
+            for value in values {
+                if let Err(err) = panic::catch_unwind(AssertUnwindSafe(|| drop(value))) {
+                    panic.get_or_insert(err);
+                }
+            }
+        }
+
+        let mut panic = None;
+        let parts = self.ctx.parts();
+        drop_all(mem::take(&mut parts.wait), &mut panic);
+        let pending = mem::take(&mut parts.items);
+        drop_all(mem::take(&mut self.items), &mut panic);
+        drop_all(pending, &mut panic);
+        let parts = self.ctx.parts();
+        drop_all(mem::take(&mut parts.microtasks), &mut panic);
+        drop_all(mem::take(&mut parts.swaps), &mut panic);
+        drop_all(self.finalizer.take(), &mut panic);
+        panic
+    }
+
+    /// Keeps an actor which agreed to stop running to handle its queued messages, if its
+    /// [`StopPolicy`] asks for it.
+    ///
+    /// Returns `true` if the actor drains its mailbox, it stops once `alive()` returns `false`.
+    fn start_draining(&mut self) -> bool {
+        if self.draining || self.ctx.parts().stop_policy() != StopPolicy::Drain {
+            return false;
+        }
+        self.draining = true;
+        self.ctx.parts().flags.transition(ContextFlags::RUNNING);
+        self.ctx.parts().log().trace(format_args!("draining"));
+        true
+    }
+
+    /// Stops an actor which agreed to stop, once the future returned by `Actor::finalizing()`
+    /// has resolved or its deadline has passed.
+    fn finish(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        self.handoff();
+        if self.finalize && self.finalizer.is_none() {
+            self.finalize = false;
+            if let Some(fut) = Actor::finalizing(&mut self.act, &mut self.ctx) {
+                self.mailbox.close();
+                let deadline = Box::pin(Timer::after(self.ctx.parts().finalize_timeout()));
+                self.finalizer = Some(Box::new(Finalizer { fut, deadline }));
+                self.ctx.parts().log().trace(format_args!("finalizing"));
+            }
+        }
+
+        if let Some(Finalizer { fut, deadline }) = self.finalizer.as_deref_mut() {
+            if fut
+                .as_mut()
+                .poll(&mut self.act, &mut self.ctx, cx)
+                .is_pending()
+            {
+                if deadline.as_mut().poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+                self.ctx
+                    .parts()
+                    .log()
+                    .warn(format_args!("finalization timed out"));
+            }
+            self.finalizer = None;
+        }
+
+        self.final_pass(cx);
+        if !self.ctx.parts().flags.contains(ContextFlags::STOPPED) {
+            self.ctx.parts().flags.transition(ContextFlags::STOPPED);
+        }
+        Actor::stopped(&mut self.act, &mut self.ctx);
+        self.ctx.parts().release_resources();
+        self.ctx.parts().log().trace(format_args!("stopped"));
+        self.publish_state(ActorState::Stopped);
+        self.done = true;
+        Poll::Ready(())
+    }
+
+    /// Polls every spawned future once after `terminate()`, so it can flush what it buffers.
+    ///
+    /// Futures which are still pending are dropped with the context, none of them can delay the
+    /// termination. Futures spawned meanwhile are not polled.
+    fn final_pass(&mut self, cx: &mut Context<'_>) {
+        if !self.ctx.parts().terminating() {
+            return;
+        }
+        self.merge();
+        self.remove_cancelled();
+
+        for idx in 0..self.items.len() {
+            if self.items[idx].cancelled {
+                continue;
+            }
+            self.ctx.parts().handles[1] = self.items[idx].handle;
+            let res = self.items[idx]
+                .fut
+                .as_mut()
+                .poll(&mut self.act, &mut self.ctx, cx);
+            if res.is_ready() {
+                self.items[idx].cancelled = true;
+            }
+            // the item could cancel any other item while it was polled
+            self.cancel_items();
+        }
+        self.ctx.parts().handles[1] = SpawnHandle::default();
+        self.remove_cancelled();
+    }
+
+    /// Releases the barriers which no future spawned before them is pending for anymore.
+    ///
+    /// Returns `true` if a barrier was released.
+    fn release_barriers(&mut self) -> bool {
+        let parts = self.ctx.parts();
+        if parts.barriers.is_empty() {
+            return false;
+        }
+
+        let oldest = self
+            .items
+            .iter()
+            .chain(parts.items.iter())
+            .filter(|item| !item.cancelled)
+            .map(|item| item.handle.into_usize())
+            .min();
+        let mut released = false;
+        parts.barriers.retain(|(last, barrier)| {
+            if Rc::strong_count(barrier) == 1 {
+                // the barrier was dropped
+                return false;
+            }
+            if oldest.map_or(false, |oldest| oldest <= last.into_usize()) {
+                return true;
             }
+            barrier.set(true);
+            released = true;
+            false
+        });
+        released
+    }
+
+    /// Forwards the mailbox to the actor given to `handoff_to()`, if any.
+    fn handoff(&mut self) {
+        if let Some(target) = self.ctx.parts().handoff.take() {
+            self.ctx
+                .parts()
+                .log()
+                .trace(format_args!("handing off mailbox"));
+            self.mailbox.handoff(target);
         }
     }
+
+    /// Publishes a state transition to subscribers of `Addr::state_stream()`.
+    fn publish_state(&mut self, state: ActorState) {
+        self.ctx.parts().addr.publish_state(state);
+    }
+
+    /// Drops cancelled items. Must not be called while an item is being polled.
+    fn remove_cancelled(&mut self) {
+        self.items.retain(|item| !item.cancelled);
+    }
 }
 
 #[doc(hidden)]
@@ -357,58 +1399,169 @@ where
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
+        this.polling = true;
+        let res = this.poll_context(cx);
+        this.polling = false;
+        res
+    }
+}
+
+impl<A, C> ContextFut<A, C>
+where
+    C: AsyncContextParts<A> + Unpin,
+    A: Actor<Context = C>,
+{
+    fn poll_context(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self;
+        this.ctx.parts().polls += 1;
+        #[cfg(feature = "telemetry")]
+        this.ctx.parts().wakeups.polled();
+
+        {
+            #[cfg(feature = "telemetry")]
+            let waker = (this.ctx.parts().wakeups).waker(WakeupCause::Interrupt, cx.waker());
+            #[cfg(feature = "telemetry")]
+            let cx = &Context::from_waker(&waker);
+            this.ctx.parts().addr.register_interrupt(cx.waker());
+        }
+
+        // a finalizing actor only waits for its finalizer
+        if this.finalizer.is_some() {
+            return this.finish(cx);
+        }
 
         if !this.ctx.parts().flags.contains(ContextFlags::STARTED) {
             this.ctx.parts().flags.insert(ContextFlags::STARTED);
+            this.ctx.parts().log().trace(format_args!("starting"));
+            this.publish_state(ActorState::Started);
             Actor::started(&mut this.act, &mut this.ctx);
+            let state = this.ctx.parts().state();
+            this.publish_state(state);
 
             // check cancelled handles, just in case
-            if this.merge() {
-                this.clean_canceled_handle();
-            }
+            this.merge();
+        }
+
+        // items which asked for a backoff are skipped until the next poll
+        let mut backoff = None;
+        for item in &mut this.items {
+            item.backoff = false;
         }
 
         'outer: loop {
+            // run functions deferred by the previous iteration
+            let deferred = this.run_microtasks();
+
             // check wait futures. order does matter
             // ctx.wait() always add to the back of the list
             // and we always have to check most recent future
-            while !this.wait.is_empty() && !this.stopping() {
-                let idx = this.wait.len() - 1;
-                let item = this.wait.last_mut().unwrap();
-                ready!(Pin::new(item).poll(&mut this.act, &mut this.ctx, cx));
-                this.wait.remove(idx);
+            while !this.stopping() {
+                let parts = this.ctx.parts();
+                let (handle, mut fut) = match parts.wait.last_mut() {
+                    Some(item) => (item.handle(), item.take().unwrap()),
+                    None => break,
+                };
+
+                #[cfg(feature = "telemetry")]
+                let waker = (this.ctx.parts().wakeups).waker(WakeupCause::Future, cx.waker());
+                #[cfg(feature = "telemetry")]
+                let cx = &mut Context::from_waker(&waker);
+                let res = ActorWaitItem::poll(&mut fut, &mut this.act, &mut this.ctx, cx);
+
+                // the future could be cancelled or moved while it was polled
+                let parts = this.ctx.parts();
+                if let Some(idx) = parts.wait.iter().position(|item| item.handle() == handle) {
+                    if res.is_ready() {
+                        parts.wait.remove(idx);
+                    } else {
+                        parts.wait[idx].restore(fut);
+                        if idx == parts.wait.len() - 1 {
+                            this.arm_backoff(backoff, cx);
+                            return Poll::Pending;
+                        }
+                    }
+                }
                 this.merge();
             }
 
-            // process mailbox
-            this.mailbox.poll(&mut this.act, &mut this.ctx, cx);
-            if !this.wait.is_empty() && !this.stopping() {
-                continue;
+            // no message is handled partially by the previous actor instance
+            if this.ctx.parts().wait.is_empty() {
+                this.swap_actors();
+                if this.has_wait() {
+                    continue;
+                }
+            }
+
+            // process mailbox, unless the actor holds messages until it is ready
+            let ready = this.ctx.parts().is_ready();
+            if ready {
+                #[cfg(feature = "telemetry")]
+                let waker = (this.ctx.parts().wakeups).waker(WakeupCause::Mailbox, cx.waker());
+                #[cfg(feature = "telemetry")]
+                let cx = &mut Context::from_waker(&waker);
+                let idle_timeout = this.ctx.parts().idle_timeout();
+                this.mailbox.set_idle_timeout(idle_timeout);
+                this.mailbox.poll(&mut this.act, &mut this.ctx, cx);
+                if this.has_wait() {
+                    continue;
+                }
+                if !this.draining && this.mailbox.poll_idle(cx) {
+                    this.ctx.parts().log().trace(format_args!("idle timeout"));
+                    this.ctx.parts().stop();
+                }
             }
 
-            // process items
+            // process items, handlers could have cancelled some of them
+            this.cancel_items();
+            this.remove_cancelled();
             let mut idx = 0;
             while idx < this.items.len() && !this.stopping() {
-                this.ctx.parts().handles[1] = this.items[idx].0;
-                match Pin::new(&mut this.items[idx].1).poll(&mut this.act, &mut this.ctx, cx) {
+                if this.items[idx].cancelled || this.items[idx].backoff {
+                    idx += 1;
+                    continue;
+                }
+                this.ctx.parts().handles[1] = this.items[idx].handle;
+                this.ctx.parts().backoff = None;
+                #[cfg(feature = "telemetry")]
+                let waker = {
+                    let wakeups = &mut this.ctx.parts().wakeups;
+                    let cause = if this.items[idx].handle == wakeups.timers {
+                        WakeupCause::Timer
+                    } else {
+                        WakeupCause::Future
+                    };
+                    wakeups.waker(cause, cx.waker())
+                };
+                #[cfg(feature = "telemetry")]
+                let cx = &mut Context::from_waker(&waker);
+                let res = this.items[idx]
+                    .fut
+                    .as_mut()
+                    .poll(&mut this.act, &mut this.ctx, cx);
+
+                // the item asked to be polled again later instead of being woken up
+                if let Some(dur) = this.ctx.parts().backoff.take() {
+                    if res.is_pending() {
+                        this.items[idx].backoff = true;
+                        let deadline = clock::now() + dur;
+                        backoff = Some(
+                            backoff.map_or(deadline, |earliest: Instant| earliest.min(deadline)),
+                        );
+                    }
+                }
+
+                // the item could cancel itself or any other item while it was polled
+                this.cancel_items();
+
+                match res {
                     Poll::Pending => {
                         // got new waiting item. merge
                         if this.ctx.waiting() {
                             this.merge();
                         }
 
-                        // check cancelled handles
-                        if this.ctx.parts().handles.len() > 2 {
-                            // this code is not very efficient, relaying on fact that
-                            // cancellation should be rear also number of futures
-                            // in actor context should be small
-                            this.clean_canceled_handle();
-
-                            continue 'outer;
-                        }
-
                         // item scheduled wait future
-                        if !this.wait.is_empty() && !this.stopping() {
+                        if this.has_wait() {
                             // move current item to end of poll queue
                             // otherwise it is possible that same item generate wait
                             // future and prevents polling
@@ -431,7 +1584,7 @@ where
                         }
 
                         // one of the items scheduled wait future
-                        if !this.wait.is_empty() && !this.stopping() {
+                        if this.has_wait() {
                             continue 'outer;
                         }
                     }
@@ -439,42 +1592,117 @@ where
             }
             this.ctx.parts().handles[1] = SpawnHandle::default();
 
-            // merge returns true if context contains new items or handles to be cancelled
-            if this.merge() && !this.ctx.parts().flags.contains(ContextFlags::STOPPING) {
-                // if we have no item to process, cancelled handles wouldn't be
-                // reaped in the above loop. this means this.merge() will never
-                // be false and the poll() never ends. so, discard the handles
-                // as we're sure there are no more items to be cancelled.
-                if this.items.is_empty() {
-                    this.ctx.parts().handles.truncate(2);
-                }
+            // merge returns true if context contains new or cancelled items
+            let modified = this.merge();
+            this.remove_cancelled();
+            if modified && !this.ctx.parts().flags.contains(ContextFlags::STOPPING) {
+                #[cfg(feature = "telemetry")]
+                this.ctx.parts().wakeups.record(WakeupCause::Modified);
+                continue;
+            }
+            if this.release_barriers() {
                 continue;
             }
 
+            // handle messages which were held until the actor became ready
+            if !ready && this.ctx.parts().is_ready() {
+                continue;
+            }
+
+            // deferred functions run before the actor stops, unless they keep deferring
+            if !deferred && !this.ctx.parts().microtasks.is_empty() {
+                continue;
+            }
+
+            // hand the mailbox over before the actor stops
+            this.handoff();
+
             // check state
             if this.ctx.parts().flags.contains(ContextFlags::RUNNING) {
                 // possible stop condition
-                if !this.alive() && Actor::stopping(&mut this.act, &mut this.ctx) == Running::Stop {
-                    this.ctx.parts().flags = ContextFlags::STOPPED | ContextFlags::STARTED;
-                    Actor::stopped(&mut this.act, &mut this.ctx);
-                    return Poll::Ready(());
+                if !this.alive() {
+                    if this.draining {
+                        return this.finish(cx);
+                    }
+                    this.ctx.parts().flags.transition(ContextFlags::STOPPING);
+                    this.publish_state(ActorState::Stopping);
+                    if Actor::stopping(&mut this.act, &mut this.ctx) == Running::Stop {
+                        this.run_microtasks();
+                        if this.start_draining() {
+                            continue;
+                        }
+                        return this.finish(cx);
+                    }
+                    if this.ctx.parts().flags.contains(ContextFlags::STOPPING) {
+                        this.ctx.parts().flags.transition(ContextFlags::RUNNING);
+                    }
+                    let state = this.ctx.parts().state();
+                    this.publish_state(state);
+
+                    // run the work `stopping()` gave the actor to keep it alive, or stop it if
+                    // it terminated itself
+                    this.merge();
+                    if this.alive() || this.ctx.parts().flags.contains(ContextFlags::STOPPED) {
+                        continue;
+                    }
                 }
             } else if this.ctx.parts().flags.contains(ContextFlags::STOPPING) {
                 if Actor::stopping(&mut this.act, &mut this.ctx) == Running::Stop {
-                    this.ctx.parts().flags = ContextFlags::STOPPED | ContextFlags::STARTED;
-                    Actor::stopped(&mut this.act, &mut this.ctx);
-                    return Poll::Ready(());
+                    this.run_microtasks();
+                    if this.start_draining() {
+                        continue;
+                    }
+                    return this.finish(cx);
                 } else {
-                    this.ctx.parts().flags.remove(ContextFlags::STOPPING);
-                    this.ctx.parts().flags.insert(ContextFlags::RUNNING);
+                    // an actor which terminated itself stops regardless
+                    if this.ctx.parts().flags.contains(ContextFlags::STOPPING) {
+                        this.ctx.parts().flags.transition(ContextFlags::RUNNING);
+                        this.publish_state(ActorState::Running);
+                    }
                     continue;
                 }
             } else if this.ctx.parts().flags.contains(ContextFlags::STOPPED) {
+                this.final_pass(cx);
                 Actor::stopped(&mut this.act, &mut this.ctx);
+                this.ctx.parts().release_resources();
+                this.ctx.parts().log().trace(format_args!("stopped"));
+                this.publish_state(ActorState::Stopped);
+                this.done = true;
                 return Poll::Ready(());
             }
 
+            if deferred {
+                #[cfg(feature = "telemetry")]
+                this.ctx.parts().wakeups.note(WakeupCause::Deferred);
+                cx.waker().wake_by_ref();
+            }
+            this.arm_backoff(backoff, cx);
             return Poll::Pending;
         }
     }
+
+    /// Arms the backoff timer for `deadline`, unless it already fires earlier.
+    fn arm_backoff(&mut self, deadline: Option<Instant>, cx: &mut Context<'_>) {
+        let deadline = match deadline {
+            Some(deadline) => deadline,
+            None => return,
+        };
+        let timer = match self.backoff {
+            Some(ref mut timer) => {
+                if timer.is_elapsed() || timer.deadline() > deadline {
+                    timer.as_mut().reset(deadline);
+                }
+                timer
+            }
+            None => self.backoff.insert(Box::pin(Timer::at(deadline))),
+        };
+
+        #[cfg(feature = "telemetry")]
+        let waker = (self.ctx.parts().wakeups).waker(WakeupCause::Timer, cx.waker());
+        #[cfg(feature = "telemetry")]
+        let cx = &mut Context::from_waker(&waker);
+        if timer.as_mut().poll(cx).is_ready() {
+            cx.waker().wake_by_ref();
+        }
+    }
 }
@@ -1,7 +1,11 @@
 use std::{
+    cell::RefCell,
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
     future::Future,
     pin::Pin,
-    task::{self, Poll},
+    rc::Rc,
+    task::{self, Poll, Waker},
     time::Duration,
 };
 
@@ -9,13 +13,20 @@ use futures_core::{ready, stream::Stream};
 use pin_project_lite::pin_project;
 
 use crate::{
-    actor::{Actor, ActorContext, AsyncContext},
-    clock::Sleep,
+    actor::{Actor, ActorContext, AsyncContext, ScopeHandle, WaitHandle},
+    clock::{self, Instant, Sleep},
     fut::ActorFuture,
     handler::{Handler, Message, MessageResponse},
+    panic_report::{attribute, Attribution, PanicSource},
 };
 
-pub(crate) struct ActorWaitItem<A: Actor>(Pin<Box<dyn ActorFuture<A, Output = ()>>>);
+type WaitFuture<A> = Pin<Box<dyn ActorFuture<A, Output = ()>>>;
+
+/// Queued wait future, taken out of its slot while being polled.
+pub(crate) struct ActorWaitItem<A: Actor> {
+    handle: WaitHandle,
+    fut: Option<WaitFuture<A>>,
+}
 
 impl<A> ActorWaitItem<A>
 where
@@ -23,20 +34,38 @@ where
     A::Context: ActorContext + AsyncContext<A>,
 {
     #[inline]
-    pub fn new<F>(fut: F) -> Self
+    pub fn new<F>(fut: F, handle: WaitHandle) -> Self
     where
         F: ActorFuture<A, Output = ()> + 'static,
     {
-        ActorWaitItem(Box::pin(fut))
+        ActorWaitItem {
+            handle,
+            fut: Some(Box::pin(fut)),
+        }
+    }
+
+    #[inline]
+    pub fn handle(&self) -> WaitHandle {
+        self.handle
+    }
+
+    #[inline]
+    pub fn take(&mut self) -> Option<WaitFuture<A>> {
+        self.fut.take()
+    }
+
+    #[inline]
+    pub fn restore(&mut self, fut: WaitFuture<A>) {
+        self.fut = Some(fut);
     }
 
     pub fn poll(
-        mut self: Pin<&mut Self>,
+        fut: &mut WaitFuture<A>,
         act: &mut A,
         ctx: &mut A::Context,
         task: &mut task::Context<'_>,
     ) -> Poll<()> {
-        match self.0.as_mut().poll(act, ctx, task) {
+        match fut.as_mut().poll(act, ctx, task) {
             Poll::Pending => {
                 if ctx.state().alive() {
                     Poll::Pending
@@ -49,6 +78,65 @@ where
     }
 }
 
+pin_project! {
+    /// Wait future bounded by a deadline, see [`AsyncContext::wait_timeout()`].
+    ///
+    /// The deadline is set when the future is queued, so a future which is still behind other
+    /// wait futures at its deadline times out as soon as it reaches the front of the queue,
+    /// without being polled.
+    pub(crate) struct ActorWaitTimeoutItem<F, T> {
+        fut: Option<Pin<Box<F>>>,
+        #[pin]
+        timer: clock::Timer,
+        on_timeout: Option<T>,
+    }
+}
+
+impl<F, T> ActorWaitTimeoutItem<F, T> {
+    pub fn new(fut: F, timeout: Duration, on_timeout: T) -> Self {
+        ActorWaitTimeoutItem {
+            fut: Some(Box::pin(fut)),
+            timer: clock::Timer::after(timeout),
+            on_timeout: Some(on_timeout),
+        }
+    }
+}
+
+impl<A, F, T> ActorFuture<A> for ActorWaitTimeoutItem<F, T>
+where
+    A: Actor,
+    F: ActorFuture<A, Output = ()>,
+    T: FnOnce(&mut A, &mut A::Context),
+{
+    type Output = ();
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut task::Context<'_>,
+    ) -> Poll<Self::Output> {
+        let this = self.project();
+        let fut = match this.fut {
+            Some(fut) => fut,
+            None => return Poll::Ready(()),
+        };
+
+        if this.timer.poll(task).is_ready() {
+            // drop the inner future before running the callback
+            *this.fut = None;
+            if let Some(on_timeout) = this.on_timeout.take() {
+                on_timeout(act, ctx);
+            }
+            return Poll::Ready(());
+        }
+
+        ready!(fut.as_mut().poll(act, ctx, task));
+        *this.fut = None;
+        Poll::Ready(())
+    }
+}
+
 pin_project! {
     pub(crate) struct ActorDelayedMessageItem<M: Message>{
         msg: Option<M>,
@@ -88,6 +176,165 @@ where
     }
 }
 
+type TimerFn<A> = Box<dyn FnOnce(&mut A, &mut <A as Actor>::Context)>;
+
+struct Timer<A: Actor> {
+    deadline: Instant,
+    seq: u64,
+    fire: TimerFn<A>,
+}
+
+/// Named timers of an actor, fired by a single [`ActorTimersItem`].
+///
+/// Replaced and cancelled timers leave stale entries in the heap, which are skipped once they
+/// reach its top.
+pub(crate) struct ActorTimers<A: Actor> {
+    timers: HashMap<&'static str, Timer<A>>,
+    heap: BinaryHeap<Reverse<(Instant, u64, &'static str)>>,
+    seq: u64,
+    waker: Option<Waker>,
+    /// Timer being fired, kept if it panics.
+    firing: Attribution,
+}
+
+impl<A: Actor> ActorTimers<A> {
+    /// Creates the timers, along with the item firing them which is to be spawned.
+    pub fn new() -> (Rc<RefCell<Self>>, ActorTimersItem<A>) {
+        let timers = Rc::new(RefCell::new(ActorTimers {
+            timers: HashMap::new(),
+            heap: BinaryHeap::new(),
+            seq: 0,
+            waker: None,
+            firing: None,
+        }));
+        let item = ActorTimersItem {
+            timers: Rc::clone(&timers),
+            sleep: Box::pin(clock::sleep(Duration::ZERO)),
+            armed: None,
+        };
+        (timers, item)
+    }
+
+    pub fn set<M>(&mut self, name: &'static str, after: Duration, msg: M)
+    where
+        A: Handler<M>,
+        M: Message + 'static,
+    {
+        self.seq += 1;
+        let deadline = Instant::now() + after;
+        let fire: TimerFn<A> = Box::new(move |act, ctx| A::handle(act, msg, ctx).handle(ctx, None));
+        self.timers.insert(
+            name,
+            Timer {
+                deadline,
+                seq: self.seq,
+                fire,
+            },
+        );
+        self.heap.push(Reverse((deadline, self.seq, name)));
+        self.wake();
+    }
+
+    pub fn cancel(&mut self, name: &'static str) -> bool {
+        let cancelled = self.timers.remove(name).is_some();
+        if cancelled {
+            self.wake();
+        }
+        cancelled
+    }
+
+    pub fn remaining(&self, name: &'static str) -> Option<Duration> {
+        self.timers
+            .get(name)
+            .map(|timer| timer.deadline.saturating_duration_since(Instant::now()))
+    }
+
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Returns the timer being fired and the time it was fired at.
+    pub fn firing(&self) -> Attribution {
+        self.firing
+    }
+
+    /// Returns the deadline of the next live timer, dropping stale heap entries.
+    fn next_deadline(&mut self) -> Option<Instant> {
+        while let Some(Reverse((deadline, seq, name))) = self.heap.peek().copied() {
+            match self.timers.get(name) {
+                Some(timer) if timer.seq == seq => return Some(deadline),
+                _ => {
+                    self.heap.pop();
+                }
+            }
+        }
+        None
+    }
+
+    /// Removes the next timer if it is due.
+    fn pop_due(&mut self, now: Instant) -> Option<(&'static str, TimerFn<A>)> {
+        match self.next_deadline() {
+            Some(deadline) if deadline <= now => {
+                let Reverse((_, _, name)) = self.heap.pop()?;
+                self.timers.remove(name).map(|timer| (name, timer.fire))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Future firing the [`ActorTimers`] of an actor, on a single timer.
+///
+/// Completes once no timer is left, and is spawned again by the next one.
+pub(crate) struct ActorTimersItem<A: Actor> {
+    timers: Rc<RefCell<ActorTimers<A>>>,
+    sleep: Pin<Box<Sleep>>,
+    armed: Option<Instant>,
+}
+
+impl<A> ActorFuture<A> for ActorTimersItem<A>
+where
+    A: Actor,
+    A::Context: AsyncContext<A>,
+{
+    type Output = ();
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut task::Context<'_>,
+    ) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        loop {
+            // fired without borrowing the timers, which the handler may change
+            let fire = this.timers.borrow_mut().pop_due(Instant::now());
+            if let Some((name, fire)) = fire {
+                this.timers.borrow_mut().firing = attribute(PanicSource::Timer(name));
+                fire(act, ctx);
+                this.timers.borrow_mut().firing = None;
+                continue;
+            }
+
+            let mut timers = this.timers.borrow_mut();
+            let deadline = match timers.next_deadline() {
+                Some(deadline) => deadline,
+                None => return Poll::Ready(()),
+            };
+            if this.armed != Some(deadline) {
+                this.sleep.as_mut().reset(deadline);
+                this.armed = Some(deadline);
+            }
+            if this.sleep.as_mut().poll(task).is_pending() {
+                timers.waker = Some(task.waker().clone());
+                return Poll::Pending;
+            }
+        }
+    }
+}
+
 pub(crate) struct ActorMessageItem<M: Message> {
     msg: Option<M>,
 }
@@ -162,3 +409,43 @@ where
         Poll::Ready(())
     }
 }
+
+pin_project! {
+    pub(crate) struct ActorScopedItem<F>{
+        #[pin]
+        fut: F,
+        scope: ScopeHandle,
+    }
+}
+
+impl<F> ActorScopedItem<F> {
+    pub fn new(fut: F, scope: ScopeHandle) -> Self {
+        Self { fut, scope }
+    }
+}
+
+impl<A, F> ActorFuture<A> for ActorScopedItem<F>
+where
+    A: Actor,
+    F: ActorFuture<A, Output = ()>,
+{
+    type Output = ();
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut task::Context<'_>,
+    ) -> Poll<Self::Output> {
+        let this = self.project();
+        if this.scope.is_cancelled() {
+            return Poll::Ready(());
+        }
+
+        this.scope.register(task.waker());
+        match this.fut.poll(act, ctx, task) {
+            Poll::Pending if !this.scope.is_cancelled() => Poll::Pending,
+            _ => Poll::Ready(()),
+        }
+    }
+}
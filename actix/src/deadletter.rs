@@ -0,0 +1,212 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::SystemTime,
+};
+
+use actix_rt::System;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::{
+    config::SystemConfig,
+    handler::Message,
+    logging::ActorId,
+    middleware::{self, MessageMiddleware},
+    placement::Placement,
+};
+
+/// Most recent dead letters of each system, keyed by system id.
+static DEAD_LETTERS: Lazy<Mutex<HashMap<usize, VecDeque<DeadLetterRecord>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Why a message was not delivered, see [`DeadLetterRecord`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DeadLetterReason {
+    /// The message was sent to a closed mailbox.
+    Closed,
+    /// The message was queued when the mailbox closed, and was dropped with it.
+    Dropped,
+    /// The message was sent through a revoked recipient, or was queued when it was revoked.
+    Revoked,
+    /// The message was rejected by a [`MessageMiddleware`](crate::MessageMiddleware).
+    Rejected,
+}
+
+/// Message which was not delivered, see [`SystemExt::dead_letters()`].
+///
+/// Records only describe the message, which is dropped as usual.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DeadLetterRecord {
+    /// Type name of the message.
+    pub message_type: &'static str,
+    /// Type name of the actor the message was sent to.
+    pub recipient_type: &'static str,
+    /// Id of the actor the message was sent to, `None` for actors without one, e.g. those
+    /// running in a [`MinimalContext`](crate::MinimalContext).
+    pub recipient: Option<ActorId>,
+    /// When the message was found to be undeliverable.
+    pub timestamp: SystemTime,
+    /// Whether the message was sent as a request.
+    pub expects_reply: bool,
+    /// Why the message was not delivered.
+    pub reason: DeadLetterReason,
+}
+
+/// Mailbox whose undelivered messages are recorded as dead letters.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DeadLetterSink {
+    /// Id of the system which created the mailbox.
+    pub(crate) system: Option<usize>,
+    pub(crate) recipient_type: &'static str,
+    pub(crate) recipient: Option<ActorId>,
+}
+
+impl DeadLetterSink {
+    /// Records a dead letter, dropping the oldest one once the buffer of the system is full.
+    pub(crate) fn record(
+        &self,
+        message_type: &'static str,
+        expects_reply: bool,
+        reason: DeadLetterReason,
+    ) {
+        let system = match self.system {
+            Some(system) => system,
+            None => return,
+        };
+        let cap = SystemConfig::of(system).get_dead_letter_capacity();
+        if cap == 0 {
+            return;
+        }
+
+        let record = DeadLetterRecord {
+            message_type,
+            recipient_type: self.recipient_type,
+            recipient: self.recipient,
+            timestamp: SystemTime::now(),
+            expects_reply,
+            reason,
+        };
+        let mut systems = DEAD_LETTERS.lock();
+        let records = systems.entry(system).or_default();
+        while records.len() >= cap {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+}
+
+/// Inspection of the messages a system could not deliver.
+///
+/// Messages sent to a stopped actor, dropped with the mailbox of an actor which stopped, or
+/// sent through a revoked recipient are recorded as dead letters. The system keeps the most
+/// recent ones, up to [`SystemConfig::dead_letter_capacity()`], so they can be inspected after
+/// the fact from any thread. Records only hold the metadata of a message, never the message
+/// itself.
+///
+/// ```
+/// use actix::prelude::*;
+///
+/// struct Worker;
+///
+/// impl Actor for Worker {
+///     type Context = Context<Self>;
+/// }
+///
+/// #[derive(Message)]
+/// #[rtype(result = "()")]
+/// struct Job;
+///
+/// impl Handler<Job> for Worker {
+///     type Result = ();
+///
+///     fn handle(&mut self, _: Job, ctx: &mut Self::Context) {
+///         ctx.stop();
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let addr = Worker.start();
+///     addr.send(Job).await.unwrap();
+///     addr.closed().await;
+///     assert!(addr.send(Job).await.is_err());
+///
+///     let dead_letters = System::current().dead_letters();
+///     assert_eq!(dead_letters.len(), 1);
+///     assert!(dead_letters[0].message_type.ends_with("Job"));
+///     assert!(dead_letters[0].expects_reply);
+/// }
+/// ```
+pub trait SystemExt {
+    /// Returns the retained dead letters of the system, oldest first.
+    fn dead_letters(&self) -> Vec<DeadLetterRecord>;
+
+    /// Discards the retained dead letters of the system.
+    fn clear_dead_letters(&self);
+
+    /// Registers middleware for every message of type `M` sent to an actor of the system.
+    ///
+    /// Middleware registered for the same type runs in the order of registration. It can not
+    /// be unregistered, and only sees messages sent after it was registered.
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// use actix::{prelude::*, MessageMiddleware, Rejection, SystemExt};
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "usize")]
+    /// struct DbQuery(String);
+    ///
+    /// struct Timing;
+    ///
+    /// impl MessageMiddleware<DbQuery> for Timing {
+    ///     fn before(&self, query: &DbQuery) -> Option<Rejection> {
+    ///         query.0.is_empty().then(|| Rejection::new("empty query"))
+    ///     }
+    ///
+    ///     fn after(&self, rows: &usize, elapsed: Duration) {
+    ///         println!("query returned {} rows in {:?}", rows, elapsed);
+    ///     }
+    /// }
+    ///
+    /// # fn main() {
+    /// # let sys = System::new();
+    /// # sys.block_on(async {
+    /// System::current().register_message_middleware(Box::new(Timing));
+    /// # });
+    /// # }
+    /// ```
+    fn register_message_middleware<M>(&self, middleware: Box<dyn MessageMiddleware<M>>)
+    where
+        M: Message + 'static;
+
+    /// Returns the [`Placement`] of the system, which starts actors on its arbiters.
+    fn placement(&self) -> Placement;
+}
+
+impl SystemExt for System {
+    fn dead_letters(&self) -> Vec<DeadLetterRecord> {
+        DEAD_LETTERS
+            .lock()
+            .get(&self.id())
+            .map(|records| records.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn clear_dead_letters(&self) {
+        DEAD_LETTERS.lock().remove(&self.id());
+    }
+
+    fn register_message_middleware<M>(&self, middleware: Box<dyn MessageMiddleware<M>>)
+    where
+        M: Message + 'static,
+    {
+        middleware::register(self.id(), middleware)
+    }
+
+    fn placement(&self) -> Placement {
+        Placement::of(self.id(), self.arbiter())
+    }
+}
@@ -0,0 +1,197 @@
+use std::{
+    any::{type_name, TypeId},
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::{
+    actor::Actor,
+    address::{Addr, MailboxError},
+};
+
+/// Plans of the actor types passed to [`inject_type()`].
+static TYPE_PLANS: Lazy<Mutex<HashMap<TypeId, FaultPlan>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Faults injected into an actor's mailbox, see [`inject()`].
+///
+/// A plan starts without faults, each method adds one:
+///
+/// - sends are dropped in the send path of the mailbox, their requests fail with
+///   [`MailboxError::Closed`];
+/// - replies are delayed in the request, once the handler has replied;
+/// - requests fail before their message is sent;
+/// - handlers panic when the actor dispatches a message, like a panicking handler would.
+///
+/// ```
+/// # use std::time::Duration;
+/// use actix::{test::FaultPlan, MailboxError};
+///
+/// let plan = FaultPlan::new()
+///     .drop_every_nth_send(10)
+///     .delay_replies(Duration::from_millis(50))
+///     .fail_calls_with(MailboxError::Timeout)
+///     .panic_in_handler_after(100);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FaultPlan {
+    drop_every: Option<usize>,
+    reply_delay: Option<Duration>,
+    fail_calls: Option<MailboxError>,
+    panic_after: Option<usize>,
+}
+
+impl FaultPlan {
+    /// Creates a plan without faults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drops every `n`th message sent to the actor, e.g. every 10th one for `n == 10`.
+    ///
+    /// Dropped messages are counted as sent, so `do_send()` and `try_send()` succeed and
+    /// requests fail with [`MailboxError::Closed`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn drop_every_nth_send(mut self, n: usize) -> Self {
+        assert!(n > 0, "n must be greater than zero");
+        self.drop_every = Some(n);
+        self
+    }
+
+    /// Delays the replies to requests by `delay` once the handler has replied.
+    ///
+    /// A request timeout shorter than the delay makes the request fail with
+    /// [`MailboxError::Timeout`].
+    pub fn delay_replies(mut self, delay: Duration) -> Self {
+        self.reply_delay = Some(delay);
+        self
+    }
+
+    /// Fails requests with `err` right away, without sending their message.
+    ///
+    /// Messages sent with `do_send()` and `try_send()` are not affected.
+    pub fn fail_calls_with(mut self, err: MailboxError) -> Self {
+        self.fail_calls = Some(err);
+        self
+    }
+
+    /// Panics in the actor once it has handled `n` messages, when it dispatches the next one.
+    pub fn panic_in_handler_after(mut self, n: usize) -> Self {
+        self.panic_after = Some(n);
+        self
+    }
+}
+
+/// Faults of a mailbox, with the counters of its plan.
+#[derive(Debug)]
+pub struct Faults {
+    plan: FaultPlan,
+    actor: &'static str,
+    sends: AtomicUsize,
+    dispatched: AtomicUsize,
+}
+
+impl Faults {
+    fn new<A>(plan: FaultPlan) -> Arc<Self> {
+        Arc::new(Faults {
+            plan,
+            actor: type_name::<A>(),
+            sends: AtomicUsize::new(0),
+            dispatched: AtomicUsize::new(0),
+        })
+    }
+
+    /// Returns the faults planned for a new mailbox of actor `A`.
+    pub(crate) fn for_type<A: 'static>() -> Option<Arc<Self>> {
+        let plans = TYPE_PLANS.lock();
+        if plans.is_empty() {
+            return None;
+        }
+        plans.get(&TypeId::of::<A>()).cloned().map(Self::new::<A>)
+    }
+
+    /// Counts a sent message, returns `true` if it has to be dropped.
+    pub(crate) fn drop_send(&self) -> bool {
+        match self.plan.drop_every {
+            Some(n) => (self.sends.fetch_add(1, Ordering::Relaxed) + 1) % n == 0,
+            None => false,
+        }
+    }
+
+    pub(crate) fn reply_delay(&self) -> Option<Duration> {
+        self.plan.reply_delay
+    }
+
+    pub(crate) fn fail_calls(&self) -> Option<MailboxError> {
+        self.plan.fail_calls
+    }
+
+    /// Counts a dispatched message, panics once the planned number of messages was handled.
+    pub(crate) fn dispatch(&self) {
+        if let Some(n) = self.plan.panic_after {
+            if self.dispatched.fetch_add(1, Ordering::Relaxed) >= n {
+                panic!("injected panic in a handler of {}", self.actor);
+            }
+        }
+    }
+}
+
+/// Injects the faults of `plan` into the mailbox of the actor of `addr`.
+///
+/// Faults apply to the messages sent through all addresses and recipients of the actor, from
+/// now on. A previous plan of the actor is replaced, along with its counters; inject
+/// [`FaultPlan::new()`] to remove it.
+///
+/// ```
+/// use actix::{prelude::*, test::FaultPlan};
+///
+/// struct Echo;
+///
+/// impl Actor for Echo {
+///     type Context = Context<Self>;
+/// }
+///
+/// #[derive(Message)]
+/// #[rtype(result = "()")]
+/// struct Ping;
+///
+/// impl Handler<Ping> for Echo {
+///     type Result = ();
+///
+///     fn handle(&mut self, _: Ping, _: &mut Self::Context) {}
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let addr = Echo.start();
+///     actix::test::inject(&addr, FaultPlan::new().fail_calls_with(MailboxError::Timeout));
+///     assert_eq!(addr.send(Ping).await, Err(MailboxError::Timeout));
+/// }
+/// ```
+pub fn inject<A: Actor>(addr: &Addr<A>, plan: FaultPlan) {
+    addr.set_faults(Faults::new::<A>(plan));
+}
+
+/// Injects the faults of `plan` into the mailboxes of the actors of type `A` started from now
+/// on, see [`inject()`].
+///
+/// Each actor counts the faults of its plan on its own. Actors which are already running are
+/// not affected.
+pub fn inject_type<A: Actor>(plan: FaultPlan) {
+    TYPE_PLANS.lock().insert(TypeId::of::<A>(), plan);
+}
+
+/// Removes the plan of the actor type `A`, see [`inject_type()`].
+pub fn clear_type_faults<A: Actor>() {
+    TYPE_PLANS.lock().remove(&TypeId::of::<A>());
+}
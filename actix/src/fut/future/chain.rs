@@ -0,0 +1,139 @@
+use std::{
+    fmt,
+    pin::Pin,
+    task::{self, Poll},
+    vec,
+};
+
+use crate::{
+    actor::Actor,
+    fut::{err, ActorFuture, LocalBoxActorFuture},
+};
+
+/// Stage of a [`Chain`], creating the next future from the output of the previous one.
+type Stage<A, T> =
+    Box<dyn FnOnce(T, &mut A, &mut <A as Actor>::Context) -> LocalBoxActorFuture<A, T>>;
+
+/// Builder of a [`Chain`], created by [`ChainBuilder::new`] or
+/// [`ActorFutureExt::into_chain`](super::ActorFutureExt::into_chain).
+///
+/// Stages are added like with the [`then`](super::ActorFutureExt::then) and
+/// [`and_then`](crate::fut::ActorTryFutureExt::and_then) combinators, but every stage has to
+/// resolve with the same output type.
+#[must_use = "the chain has to be built to be polled"]
+pub struct ChainBuilder<A: Actor, T> {
+    first: LocalBoxActorFuture<A, T>,
+    stages: Vec<Stage<A, T>>,
+}
+
+impl<A: Actor, T: 'static> ChainBuilder<A, T> {
+    /// Creates a chain starting with `fut`.
+    pub fn new<F>(fut: F) -> Self
+    where
+        F: ActorFuture<A, Output = T> + 'static,
+    {
+        ChainBuilder {
+            first: Box::pin(fut),
+            stages: Vec::new(),
+        }
+    }
+
+    /// Adds a stage, passing the output of the previous stage to `f`.
+    pub fn then<F, Fut>(mut self, f: F) -> Self
+    where
+        F: FnOnce(T, &mut A, &mut A::Context) -> Fut + 'static,
+        Fut: ActorFuture<A, Output = T> + 'static,
+    {
+        self.stages.push(Box::new(move |output, act, ctx| {
+            Box::pin(f(output, act, ctx))
+        }));
+        self
+    }
+
+    /// Builds the chain.
+    pub fn build(self) -> Chain<A, T> {
+        Chain {
+            current: Some(self.first),
+            stages: self.stages.into_iter(),
+        }
+    }
+}
+
+impl<A: Actor, T: 'static, E: 'static> ChainBuilder<A, Result<T, E>> {
+    /// Adds a stage, passing the successful output of the previous stage to `f`.
+    ///
+    /// Once a stage fails, the following stages are skipped and the chain resolves with the
+    /// error.
+    pub fn and_then<F, Fut>(self, f: F) -> Self
+    where
+        F: FnOnce(T, &mut A, &mut A::Context) -> Fut + 'static,
+        Fut: ActorFuture<A, Output = Result<T, E>> + 'static,
+    {
+        self.then(move |res, act, ctx| match res {
+            Ok(output) => Box::pin(f(output, act, ctx)) as LocalBoxActorFuture<A, _>,
+            Err(e) => Box::pin(err(e)),
+        })
+    }
+}
+
+impl<A: Actor, T> fmt::Debug for ChainBuilder<A, T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ChainBuilder")
+            .field("stages", &self.stages.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Future running the stages of a [`ChainBuilder`] one after the other.
+///
+/// Unlike nested [`Then`](super::Then) combinators, which poll each other recursively, the
+/// chain polls its current stage only, so arbitrarily long chains can be polled and dropped
+/// without exhausting the stack.
+#[must_use = "futures do nothing unless polled"]
+pub struct Chain<A: Actor, T> {
+    current: Option<LocalBoxActorFuture<A, T>>,
+    stages: vec::IntoIter<Stage<A, T>>,
+}
+
+impl<A: Actor, T> Chain<A, T> {
+    /// Returns the number of stages which were not started yet.
+    pub fn remaining(&self) -> usize {
+        self.stages.len()
+    }
+}
+
+impl<A: Actor, T> ActorFuture<A> for Chain<A, T> {
+    type Output = T;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut task::Context<'_>,
+    ) -> Poll<T> {
+        let this = self.get_mut();
+        loop {
+            let current = this
+                .current
+                .as_mut()
+                .expect("ActorFuture polled after finish");
+            let output = match current.as_mut().poll(act, ctx, task) {
+                Poll::Ready(output) => output,
+                Poll::Pending => return Poll::Pending,
+            };
+            this.current = None;
+            match this.stages.next() {
+                Some(stage) => this.current = Some(stage(output, act, ctx)),
+                None => return Poll::Ready(output),
+            }
+        }
+    }
+}
+
+impl<A: Actor, T> fmt::Debug for Chain<A, T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Chain")
+            .field("remaining", &self.stages.len())
+            .finish_non_exhaustive()
+    }
+}
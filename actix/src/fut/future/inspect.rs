@@ -0,0 +1,63 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+use crate::{actor::Actor, fut::ActorFuture};
+
+pin_project! {
+    /// Future for the [`inspect`](super::ActorFutureExt::inspect) method.
+    #[project = InspectProj]
+    #[project_replace = InspectProjReplace]
+    #[derive(Debug)]
+    #[must_use = "futures do nothing unless you `.await` or poll them"]
+    pub enum Inspect<Fut, F> {
+        Incomplete {
+            #[pin]
+            future: Fut,
+            f: F,
+        },
+        Complete,
+    }
+}
+
+impl<Fut, F> Inspect<Fut, F> {
+    pub(super) fn new(future: Fut, f: F) -> Self {
+        Self::Incomplete { future, f }
+    }
+}
+
+impl<Fut, A, F> ActorFuture<A> for Inspect<Fut, F>
+where
+    Fut: ActorFuture<A>,
+    A: Actor,
+    F: FnOnce(&Fut::Output, &mut A, &mut A::Context),
+{
+    type Output = Fut::Output;
+
+    fn poll(
+        mut self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        match self.as_mut().project() {
+            InspectProj::Incomplete { future, .. } => {
+                let output = ready!(future.poll(act, ctx, task));
+                match self.project_replace(Inspect::Complete) {
+                    InspectProjReplace::Incomplete { f, .. } => {
+                        f(&output, act, ctx);
+                        Poll::Ready(output)
+                    }
+                    InspectProjReplace::Complete => unreachable!(),
+                }
+            }
+            InspectProj::Complete => {
+                panic!("Inspect must not be polled after it returned `Poll::Ready`")
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use pin_project_lite::pin_project;
+
+use crate::{
+    actor::{Actor, AsyncContext},
+    fut::ActorFuture,
+};
+
+pin_project! {
+    /// Future for the [`interruptible`](super::ActorFutureExt::interruptible) combinator,
+    /// resolves early once the actor is interrupted.
+    ///
+    /// This is created by the [`interruptible`](super::ActorFutureExt::interruptible) method.
+    #[derive(Debug)]
+    #[must_use = "futures do nothing unless polled"]
+    pub struct Interruptible<F> {
+        #[pin]
+        fut: F,
+    }
+}
+
+impl<F> Interruptible<F> {
+    pub(super) fn new(fut: F) -> Self {
+        Self { fut }
+    }
+}
+
+impl<F, A> ActorFuture<A> for Interruptible<F>
+where
+    F: ActorFuture<A>,
+    A: Actor,
+    A::Context: AsyncContext<A>,
+{
+    type Output = Result<F::Output, ()>;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        if ctx.interrupted() {
+            return Poll::Ready(Err(()));
+        }
+        self.project().fut.poll(act, ctx, task).map(Ok)
+    }
+}
@@ -7,18 +7,30 @@ use std::{
     time::Duration,
 };
 
+pub use chain::{Chain, ChainBuilder};
+pub use inspect::Inspect;
+pub use interruptible::Interruptible;
 pub use map::Map;
 use pin_project_lite::pin_project;
+pub use race::{race_ok, RaceOk};
+pub use retry::{retry, Retry};
 pub use then::Then;
 pub use timeout::Timeout;
+pub use traced::Traced;
 
 use crate::actor::Actor;
 
+mod chain;
 mod either;
+mod inspect;
+mod interruptible;
 mod map;
+mod race;
 pub mod result;
+mod retry;
 mod then;
 mod timeout;
+mod traced;
 
 /// Trait for types which are a placeholder of a value that may become
 /// available at some later point in time.
@@ -136,6 +148,32 @@ pub trait ActorFutureExt<A: Actor>: ActorFuture<A> {
         Map::new(self, f)
     }
 
+    /// Do something with the output of this future before passing it on.
+    ///
+    /// The closure `f` is called with a reference to the output once the future resolves.
+    fn inspect<F>(self, f: F) -> Inspect<Self, F>
+    where
+        F: FnOnce(&Self::Output, &mut A, &mut A::Context),
+        Self: Sized,
+    {
+        Inspect::new(self, f)
+    }
+
+    /// Traces this stage of a futures chain as `name`.
+    ///
+    /// With the `telemetry` feature enabled, a record is logged at the trace level when the
+    /// stage is first polled, and another one with its latency when it resolves. Records use the
+    /// actor's type path as their target. Every traced stage of a chain logs its own records,
+    /// outer stages are polled before and resolve after the stages they wrap.
+    ///
+    /// Without the `telemetry` feature, the stage is polled as is.
+    fn traced(self, name: &'static str) -> Traced<Self>
+    where
+        Self: Sized,
+    {
+        Traced::new(self, name)
+    }
+
     /// Chain on a computation for when a future finished, passing the result of
     /// the future to the provided closure `f`.
     fn then<F, Fut>(self, f: F) -> Then<Self, Fut, F>
@@ -157,6 +195,17 @@ pub trait ActorFutureExt<A: Actor>: ActorFuture<A> {
         Timeout::new(self, timeout)
     }
 
+    /// Resolve early once the actor is interrupted with
+    /// [`Addr::interrupt()`](crate::Addr::interrupt).
+    ///
+    /// `Err(())` returned as an interrupt error, the inner future is not polled anymore.
+    fn interruptible(self) -> Interruptible<Self>
+    where
+        Self: Sized,
+    {
+        Interruptible::new(self)
+    }
+
     /// Wrap the future in a Box, pinning it.
     ///
     /// A shortcut for wrapping in [`Box::pin`].
@@ -166,6 +215,32 @@ pub trait ActorFutureExt<A: Actor>: ActorFuture<A> {
     {
         Box::pin(self)
     }
+
+    /// Starts a [`ChainBuilder`] with this future as its first stage.
+    ///
+    /// Chains are polled without recursing into their stages, which makes them suitable for
+    /// programmatically built chains of many stages.
+    ///
+    /// ```
+    /// # use actix::prelude::*;
+    /// # struct MyActor;
+    /// # impl Actor for MyActor {
+    /// #     type Context = Context<Self>;
+    /// # }
+    /// # fn build(_: &mut MyActor) -> fut::Chain<MyActor, u64> {
+    /// let chain = (0..10_000).fold(fut::ready(0).into_chain(), |chain, _| {
+    ///     chain.then(|n, _: &mut MyActor, _| fut::ready(n + 1))
+    /// });
+    /// chain.build()
+    /// # }
+    /// ```
+    fn into_chain(self) -> ChainBuilder<A, Self::Output>
+    where
+        Self: Sized + 'static,
+        Self::Output: 'static,
+    {
+        ChainBuilder::new(self)
+    }
 }
 
 impl<F, A> ActorFutureExt<A> for F
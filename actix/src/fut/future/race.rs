@@ -0,0 +1,140 @@
+use std::{
+    fmt, mem,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{actor::Actor, fut::ActorFuture};
+
+/// Creates a future which resolves with the first of `futs` to succeed.
+///
+/// Resolves with the index of the winner and its result. The remaining futures are dropped as
+/// soon as one succeeds, and failed futures as soon as they fail, releasing what they hold. A
+/// dropped [`Request`](crate::dev::Request) closes its response slot, so a message which is still
+/// queued is not handled by the recipient. Fails with the errors of all futures, in the order of
+/// `futs`, if none succeeds.
+///
+/// # Examples
+///
+/// ```
+/// use actix::{fut, prelude::*};
+///
+/// struct Replica(Option<u64>);
+///
+/// impl Actor for Replica {
+///     type Context = Context<Self>;
+/// }
+///
+/// #[derive(Message)]
+/// #[rtype(result = "Option<u64>")]
+/// struct Query;
+///
+/// impl Handler<Query> for Replica {
+///     type Result = Option<u64>;
+///
+///     fn handle(&mut self, _: Query, _: &mut Self::Context) -> Option<u64> {
+///         self.0
+///     }
+/// }
+///
+/// struct Client {
+///     replicas: Vec<Addr<Replica>>,
+/// }
+///
+/// impl Actor for Client {
+///     type Context = Context<Self>;
+/// }
+///
+/// #[derive(Message)]
+/// #[rtype(result = "Option<(usize, u64)>")]
+/// struct Fetch;
+///
+/// impl Handler<Fetch> for Client {
+///     type Result = ResponseActFuture<Self, Option<(usize, u64)>>;
+///
+///     fn handle(&mut self, _: Fetch, _: &mut Self::Context) -> Self::Result {
+///         let queries = self.replicas.iter().map(|replica| {
+///             let query = replica.send(Query);
+///             fut::wrap_future(async move { query.await.ok().flatten().ok_or(()) })
+///         });
+///         Box::pin(fut::race_ok(queries).map(|res, _, _| res.ok()))
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let replicas = vec![Replica(None).start(), Replica(Some(7)).start()];
+///     let client = Client { replicas }.start();
+///     assert_eq!(client.send(Fetch).await.unwrap(), Some((1, 7)));
+/// }
+/// ```
+pub fn race_ok<A, I, Fut, T, E>(futs: I) -> RaceOk<Fut, E>
+where
+    A: Actor,
+    I: IntoIterator<Item = Fut>,
+    Fut: ActorFuture<A, Output = Result<T, E>>,
+{
+    let futs: Vec<_> = futs.into_iter().map(|fut| Some(Box::pin(fut))).collect();
+    let errors = futs.iter().map(|_| None).collect();
+    RaceOk { futs, errors }
+}
+
+/// Future for the [`race_ok`] function.
+#[must_use = "futures do nothing unless polled"]
+pub struct RaceOk<Fut, E> {
+    /// Pending futures, `None` once failed.
+    futs: Vec<Option<Pin<Box<Fut>>>>,
+    errors: Vec<Option<E>>,
+}
+
+// futures are boxed
+impl<Fut, E> Unpin for RaceOk<Fut, E> {}
+
+impl<Fut, E> fmt::Debug for RaceOk<Fut, E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("RaceOk")
+            .field(
+                "pending",
+                &self.futs.iter().filter(|fut| fut.is_some()).count(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A, Fut, T, E> ActorFuture<A> for RaceOk<Fut, E>
+where
+    A: Actor,
+    Fut: ActorFuture<A, Output = Result<T, E>>,
+{
+    type Output = Result<(usize, T), Vec<E>>;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        for (idx, slot) in this.futs.iter_mut().enumerate() {
+            let Some(fut) = slot else { continue };
+            match fut.as_mut().poll(act, ctx, task) {
+                Poll::Ready(Ok(res)) => {
+                    // the losers are cancelled right away
+                    this.futs.clear();
+                    return Poll::Ready(Ok((idx, res)));
+                }
+                Poll::Ready(Err(err)) => {
+                    *slot = None;
+                    this.errors[idx] = Some(err);
+                }
+                Poll::Pending => {}
+            }
+        }
+
+        if this.futs.iter().any(Option::is_some) {
+            return Poll::Pending;
+        }
+        let errors = mem::take(&mut this.errors);
+        Poll::Ready(Err(errors.into_iter().flatten().collect()))
+    }
+}
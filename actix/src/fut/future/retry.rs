@@ -0,0 +1,163 @@
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use crate::{
+    actor::Actor,
+    clock::{sleep, Sleep},
+    fut::ActorFuture,
+};
+
+/// Creates a future which retries failed attempts created by `factory`.
+///
+/// Every attempt is created anew by calling `factory` with the actor and its context. When an
+/// attempt fails, `policy` is called with the error and the number of failed attempts so far,
+/// and returns the delay before the next attempt, or `None` to give up and resolve with the
+/// error. The failed attempt is dropped before the delay starts, releasing what it holds, like
+/// the response slot of a request. Cancelling the future, e.g. through its
+/// [`SpawnHandle`](crate::SpawnHandle), drops the pending attempt or delay.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use actix::{fut, prelude::*, utils::Backoff};
+///
+/// struct Store;
+///
+/// impl Actor for Store {
+///     type Context = Context<Self>;
+/// }
+///
+/// #[derive(Message)]
+/// #[rtype(result = "u64")]
+/// struct Get;
+///
+/// impl Handler<Get> for Store {
+///     type Result = u64;
+///
+///     fn handle(&mut self, _: Get, _: &mut Self::Context) -> u64 {
+///         42
+///     }
+/// }
+///
+/// struct Cache {
+///     store: Addr<Store>,
+/// }
+///
+/// impl Actor for Cache {
+///     type Context = Context<Self>;
+/// }
+///
+/// #[derive(Message)]
+/// #[rtype(result = "Result<u64, MailboxError>")]
+/// struct Fetch;
+///
+/// impl Handler<Fetch> for Cache {
+///     type Result = ResponseActFuture<Self, Result<u64, MailboxError>>;
+///
+///     fn handle(&mut self, _: Fetch, _: &mut Self::Context) -> Self::Result {
+///         let backoff = Backoff::new(Duration::from_millis(10), Duration::from_secs(1));
+///         Box::pin(fut::retry(
+///             // up to 3 attempts
+///             move |_, attempt| (attempt < 3).then(|| backoff.delay(attempt)),
+///             |act: &mut Self, _| act.store.send(Get).into_actor(act),
+///         ))
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let cache = Cache { store: Store.start() }.start();
+///     assert_eq!(cache.send(Fetch).await.unwrap(), Ok(42));
+/// }
+/// ```
+pub fn retry<A, F, Fut, P, T, E>(policy: P, factory: F) -> Retry<F, Fut, P>
+where
+    A: Actor,
+    F: FnMut(&mut A, &mut A::Context) -> Fut,
+    Fut: ActorFuture<A, Output = Result<T, E>>,
+    P: FnMut(&E, usize) -> Option<Duration>,
+{
+    Retry {
+        policy,
+        factory,
+        failures: 0,
+        attempt: None,
+        delay: None,
+    }
+}
+
+/// Future for the [`retry`] function.
+#[must_use = "futures do nothing unless polled"]
+pub struct Retry<F, Fut, P> {
+    policy: P,
+    factory: F,
+    failures: usize,
+    attempt: Option<Pin<Box<Fut>>>,
+    delay: Option<Pin<Box<Sleep>>>,
+}
+
+// the factory and the policy are never pinned, attempts and delays are boxed
+impl<F, Fut, P> Unpin for Retry<F, Fut, P> {}
+
+impl<F, Fut, P> fmt::Debug for Retry<F, Fut, P> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Retry")
+            .field("failures", &self.failures)
+            .field("waiting", &self.delay.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A, F, Fut, P, T, E> ActorFuture<A> for Retry<F, Fut, P>
+where
+    A: Actor,
+    F: FnMut(&mut A, &mut A::Context) -> Fut,
+    Fut: ActorFuture<A, Output = Result<T, E>>,
+    P: FnMut(&E, usize) -> Option<Duration>,
+{
+    type Output = Result<T, E>;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        loop {
+            if let Some(delay) = &mut this.delay {
+                if delay.as_mut().poll(task).is_pending() {
+                    return Poll::Pending;
+                }
+                this.delay = None;
+            }
+
+            let attempt = this
+                .attempt
+                .get_or_insert_with(|| Box::pin((this.factory)(act, ctx)));
+            let err = match attempt.as_mut().poll(act, ctx, task) {
+                Poll::Ready(Ok(res)) => {
+                    this.attempt = None;
+                    return Poll::Ready(Ok(res));
+                }
+                Poll::Ready(Err(err)) => err,
+                Poll::Pending => return Poll::Pending,
+            };
+
+            // the failed attempt is released before waiting for the next one
+            this.attempt = None;
+            this.failures += 1;
+            match (this.policy)(&err, this.failures) {
+                Some(delay) => this.delay = Some(Box::pin(sleep(delay))),
+                None => return Poll::Ready(Err(err)),
+            }
+        }
+    }
+}
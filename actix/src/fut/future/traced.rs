@@ -0,0 +1,104 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+use crate::{actor::Actor, fut::ActorFuture};
+
+pin_project! {
+    /// Future for the [`traced`](super::ActorFutureExt::traced) method.
+    #[derive(Debug)]
+    #[must_use = "futures do nothing unless you `.await` or poll them"]
+    pub struct Traced<Fut> {
+        #[pin]
+        future: Fut,
+        span: Span,
+    }
+}
+
+impl<Fut> Traced<Fut> {
+    pub(super) fn new(future: Fut, name: &'static str) -> Self {
+        Traced {
+            future,
+            span: Span::new(name),
+        }
+    }
+}
+
+impl<Fut, A> ActorFuture<A> for Traced<Fut>
+where
+    Fut: ActorFuture<A>,
+    A: Actor,
+{
+    type Output = Fut::Output;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        let this = self.project();
+        this.span.polled::<A>();
+        let output = ready!(this.future.poll(act, ctx, task));
+        this.span.resolved::<A>();
+        Poll::Ready(output)
+    }
+}
+
+/// Records when a traced stage is first polled and when it resolves.
+#[cfg(feature = "telemetry")]
+#[derive(Debug)]
+struct Span {
+    name: &'static str,
+    started: Option<crate::clock::Instant>,
+}
+
+#[cfg(feature = "telemetry")]
+impl Span {
+    fn new(name: &'static str) -> Self {
+        Span {
+            name,
+            started: None,
+        }
+    }
+
+    fn polled<A>(&mut self) {
+        if self.started.is_none() {
+            self.started = Some(crate::clock::Instant::now());
+            log::trace!(target: std::any::type_name::<A>(), "{}: polled", self.name);
+        }
+    }
+
+    fn resolved<A>(&mut self) {
+        let latency = self.started.map(|started| started.elapsed());
+        log::trace!(
+            target: std::any::type_name::<A>(),
+            "{}: resolved in {:?}",
+            self.name,
+            latency.unwrap_or_default()
+        );
+    }
+}
+
+/// Tracing is disabled without the `telemetry` feature.
+#[cfg(not(feature = "telemetry"))]
+#[derive(Debug)]
+struct Span;
+
+#[cfg(not(feature = "telemetry"))]
+impl Span {
+    #[inline(always)]
+    fn new(_: &'static str) -> Self {
+        Span
+    }
+
+    #[inline(always)]
+    fn polled<A>(&mut self) {}
+
+    #[inline(always)]
+    fn resolved<A>(&mut self) {}
+}
@@ -6,8 +6,10 @@ pub mod try_future;
 
 pub use self::{
     future::{
+        race_ok,
         result::{err, ok, ready, result, Ready},
-        wrap_future, ActorFuture, ActorFutureExt, LocalBoxActorFuture, WrapFuture,
+        retry, wrap_future, ActorFuture, ActorFutureExt, Chain, ChainBuilder, LocalBoxActorFuture,
+        WrapFuture,
     },
     stream::{wrap_stream, ActorStream, ActorStreamExt, WrapStream},
     try_future::{ActorTryFuture, ActorTryFutureExt},
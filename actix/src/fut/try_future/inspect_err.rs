@@ -0,0 +1,68 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+use crate::{
+    fut::{future::ActorFuture, try_future::ActorTryFuture},
+    Actor,
+};
+
+pin_project! {
+    /// Future for the [`inspect_err`](super::ActorTryFutureExt::inspect_err) method.
+    #[project = InspectErrProj]
+    #[project_replace = InspectErrProjReplace]
+    #[derive(Debug)]
+    #[must_use = "futures do nothing unless you `.await` or poll them"]
+    pub enum InspectErr<Fut, F> {
+        Incomplete {
+            #[pin]
+            future: Fut,
+            f: F,
+        },
+        Complete,
+    }
+}
+
+impl<Fut, F> InspectErr<Fut, F> {
+    pub(crate) fn new(future: Fut, f: F) -> Self {
+        Self::Incomplete { future, f }
+    }
+}
+
+impl<Fut, A, F> ActorFuture<A> for InspectErr<Fut, F>
+where
+    Fut: ActorTryFuture<A>,
+    A: Actor,
+    F: FnOnce(&Fut::Error, &mut A, &mut A::Context),
+{
+    type Output = Result<Fut::Ok, Fut::Error>;
+
+    fn poll(
+        mut self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        match self.as_mut().project() {
+            InspectErrProj::Incomplete { future, .. } => {
+                let output = ready!(future.try_poll(act, ctx, task));
+                match self.project_replace(InspectErr::Complete) {
+                    InspectErrProjReplace::Incomplete { f, .. } => {
+                        if let Err(ref err) = output {
+                            f(err, act, ctx);
+                        }
+                        Poll::Ready(output)
+                    }
+                    InspectErrProjReplace::Complete => unreachable!(),
+                }
+            }
+            InspectErrProj::Complete => {
+                panic!("InspectErr must not be polled after it returned `Poll::Ready`")
+            }
+        }
+    }
+}
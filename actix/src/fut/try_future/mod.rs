@@ -6,10 +6,12 @@ use std::{
 use crate::{actor::Actor, fut::future::ActorFuture};
 
 mod and_then;
+mod inspect_err;
 mod map_err;
 mod map_ok;
 
 pub use and_then::AndThen;
+pub use inspect_err::InspectErr;
 pub use map_err::MapErr;
 pub use map_ok::MapOk;
 
@@ -129,6 +131,18 @@ pub trait ActorTryFutureExt<A: Actor>: ActorTryFuture<A> {
     {
         MapErr::new(self, f)
     }
+
+    /// Do something with the error value of this actor future before passing it on.
+    ///
+    /// The provided closure `f` will only be called if this actor future is
+    /// resolved to an [`Err`].
+    fn inspect_err<F>(self, f: F) -> InspectErr<Self, F>
+    where
+        F: FnOnce(&Self::Error, &mut A, &mut A::Context),
+        Self: Sized,
+    {
+        InspectErr::new(self, f)
+    }
 }
 
 impl<A, F> ActorTryFutureExt<A> for F
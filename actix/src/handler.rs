@@ -1,11 +1,21 @@
-use std::{fmt, future::Future, pin::Pin, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    sync::Arc,
+    task::{self, Poll},
+};
 
 pub use tokio::sync::oneshot::Sender as OneshotSender;
 
 use crate::{
     actor::{Actor, AsyncContext},
-    address::Addr,
-    fut::{ActorFuture, ActorFutureExt, LocalBoxActorFuture},
+    address::{Addr, MailboxError},
+    fut::{wrap_future, ActorFuture, ActorFutureExt, LocalBoxActorFuture},
+    sync::Chunked,
 };
 
 /// Describes how to handle messages of a specific type.
@@ -30,7 +40,109 @@ where
     fn handle(&mut self, msg: M, ctx: &mut Self::Context) -> Self::Result;
 }
 
+/// Describes how to handle a batch of queued messages of a specific type.
+///
+/// Batching is opt-in and has to be enabled per message type with
+/// [`Context::enable_batching()`](crate::Context::enable_batching). Once enabled, consecutive
+/// messages of type `M` waiting in the mailbox are collected and passed to
+/// [`handle_batch`](BatchHandler::handle_batch) together.
+///
+/// Only messages sent without waiting for a response (`do_send`/`try_send`) are batched. A
+/// message sent with `send` expects its own reply, so any pending batch is dispatched first and
+/// the message is then handled individually by [`Handler::handle`].
+pub trait BatchHandler<M>: Handler<M>
+where
+    M: Message,
+{
+    /// This method is called with a batch of consecutive messages, in the order they were sent.
+    fn handle_batch(&mut self, msgs: Vec<M>, ctx: &mut Self::Context);
+}
+
+/// Describes how to handle the pending signals of a zero-sized message type at once.
+///
+/// Signals sent with [`Addr::signal()`](crate::Addr::signal) are handled by [`Handler::handle`]
+/// once per signal, unless coalescing was enabled with
+/// [`Context::coalesce_signals()`](crate::Context::coalesce_signals). Coalesced signals are
+/// passed to [`handle_signals`](SignalHandler::handle_signals) with their number instead.
+pub trait SignalHandler<M>: Actor
+where
+    M: Message,
+{
+    /// This method is called with the number of signals which were sent since the last call.
+    fn handle_signals(&mut self, count: usize, ctx: &mut Self::Context);
+}
+
+/// Message which belongs to one of many independent entities, identified by its key.
+///
+/// Once partitioning is enabled with
+/// [`Context::enable_partitioning()`](crate::Context::enable_partitioning), messages with the same
+/// key are handled in order, one at a time, while messages of different keys are handled
+/// concurrently.
+pub trait Keyed {
+    /// Returns the key of the entity the message belongs to.
+    fn key(&self) -> u64;
+}
+
+/// Message which belongs to an ordered stream, identified by its sequence number.
+///
+/// Once sequencing is enabled with
+/// [`Context::enable_sequencing()`](crate::Context::enable_sequencing), messages are handled in
+/// the order of their sequence numbers, whatever order they were sent in.
+pub trait Sequenced {
+    /// Returns the sequence number of the message.
+    fn seq(&self) -> u64;
+}
+
 /// Represent message that can be handled by an actor.
+///
+/// Messages which can't fail use their response type directly, there is no need for a dummy
+/// error type. Fallible messages respond with a `Result`. In both cases sending the message only
+/// adds [`MailboxError`](crate::MailboxError) for delivery failures.
+///
+/// ```
+/// # use actix::prelude::*;
+/// struct Count;
+///
+/// impl Message for Count {
+///     type Result = usize;
+/// }
+///
+/// struct Parse(&'static str);
+///
+/// impl Message for Parse {
+///     type Result = Result<usize, std::num::ParseIntError>;
+/// }
+///
+/// struct MyActor;
+/// # impl Actor for MyActor {
+/// #     type Context = Context<Self>;
+/// # }
+///
+/// impl Handler<Count> for MyActor {
+///     type Result = Response<usize>;
+///
+///     fn handle(&mut self, _: Count, _: &mut Context<Self>) -> Self::Result {
+///         Response::reply(42)
+///     }
+/// }
+///
+/// impl Handler<Parse> for MyActor {
+///     type Result = Result<usize, std::num::ParseIntError>;
+///
+///     fn handle(&mut self, msg: Parse, _: &mut Context<Self>) -> Self::Result {
+///         msg.0.parse()
+///     }
+/// }
+///
+/// # #[actix::main]
+/// # async fn main() {
+/// let addr = MyActor.start();
+/// let count: usize = addr.send(Count).await.unwrap();
+/// let parsed: Result<usize, _> = addr.send(Parse("x")).await.unwrap();
+/// assert_eq!(count, 42);
+/// assert!(parsed.is_err());
+/// # }
+/// ```
 pub trait Message {
     /// The type of value that this message will resolved with if it is
     /// successful.
@@ -53,6 +165,105 @@ where
     type Result = M::Result;
 }
 
+/// Message whose response type is chosen by the actor handling it.
+///
+/// The response type of a [`Message`] is shared by all of its handlers. Wrapping a query `Q` in
+/// `Query<Q, R>` lets each actor pick its own response type `R` in its [`Handler`] impl, and
+/// [`Addr::query()`] infers it from the impl of the target actor. Plain messages and queries
+/// can be mixed freely, even for the same type `Q`.
+///
+/// ```
+/// use actix::prelude::*;
+///
+/// struct Lookup(u32);
+///
+/// struct Summary(String);
+///
+/// struct Record {
+///     id: u32,
+///     name: String,
+/// }
+///
+/// struct Cache;
+///
+/// impl Actor for Cache {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<Query<Lookup, Summary>> for Cache {
+///     type Result = MessageResult<Query<Lookup, Summary>>;
+///
+///     fn handle(&mut self, query: Query<Lookup, Summary>, _: &mut Self::Context) -> Self::Result {
+///         MessageResult(Summary(format!("user {}", query.0)))
+///     }
+/// }
+///
+/// struct Store;
+///
+/// impl Actor for Store {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<Query<Lookup, Record>> for Store {
+///     type Result = MessageResult<Query<Lookup, Record>>;
+///
+///     fn handle(&mut self, query: Query<Lookup, Record>, _: &mut Self::Context) -> Self::Result {
+///         let id = query.into_inner().0;
+///         MessageResult(Record { id, name: format!("user {id}") })
+///     }
+/// }
+///
+/// # #[actix::main]
+/// # async fn main() {
+/// let summary = Cache.start().query(Lookup(7)).await.unwrap();
+/// let record = Store.start().query(Lookup(7)).await.unwrap();
+/// assert_eq!(summary.0, record.name);
+/// # }
+/// ```
+pub struct Query<Q, R> {
+    query: Q,
+    reply: PhantomData<fn() -> R>,
+}
+
+impl<Q, R> Query<Q, R> {
+    /// Wraps `query`.
+    pub fn new(query: Q) -> Self {
+        Query {
+            query,
+            reply: PhantomData,
+        }
+    }
+
+    /// Returns the wrapped query.
+    pub fn into_inner(self) -> Q {
+        self.query
+    }
+}
+
+impl<Q: fmt::Debug, R> fmt::Debug for Query<Q, R> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_tuple("Query").field(&self.query).finish()
+    }
+}
+
+impl<Q, R> Deref for Query<Q, R> {
+    type Target = Q;
+
+    fn deref(&self) -> &Q {
+        &self.query
+    }
+}
+
+impl<Q, R> DerefMut for Query<Q, R> {
+    fn deref_mut(&mut self) -> &mut Q {
+        &mut self.query
+    }
+}
+
+impl<Q, R: 'static> Message for Query<Q, R> {
+    type Result = R;
+}
+
 /// A helper type that implements the [`MessageResponse`] trait.
 ///
 /// # Examples
@@ -235,8 +446,11 @@ pub type ResponseFuture<I> = Pin<Box<dyn Future<Output = I>>>;
 
 /// A trait which defines message responses.
 ///
-/// We offer implementation for some common language types, if you need
-/// to respond with a new type you can use [`MessageResult`].
+/// We offer implementation for some common language types: `()`, `bool`, `char`, numbers,
+/// `String`, and `Option`, `Result`, `Box`, `Arc`, `Vec`, `VecDeque`, `HashMap`, `HashSet`,
+/// `BTreeMap` and `BTreeSet` of any type. A handler can return these when they are the
+/// message's result type. If you need to respond with a new type you can use
+/// [`MessageResult`], or derive `MessageResponse` for it.
 ///
 /// If `Actor::Context` implements [`AsyncContext`] it's possible to handle
 /// the message asynchronously.
@@ -309,6 +523,76 @@ where
     }
 }
 
+impl<A, M, I> MessageResponse<A, M> for Box<I>
+where
+    A: Actor,
+    M: Message<Result = Self>,
+    I: 'static,
+{
+    fn handle(self, _: &mut A::Context, tx: Option<OneshotSender<Self>>) {
+        tx.send(self)
+    }
+}
+
+impl<A, M, I> MessageResponse<A, M> for VecDeque<I>
+where
+    A: Actor,
+    M: Message<Result = Self>,
+    I: 'static,
+{
+    fn handle(self, _: &mut A::Context, tx: Option<OneshotSender<Self>>) {
+        tx.send(self)
+    }
+}
+
+impl<A, M, I> MessageResponse<A, M> for BTreeSet<I>
+where
+    A: Actor,
+    M: Message<Result = Self>,
+    I: 'static,
+{
+    fn handle(self, _: &mut A::Context, tx: Option<OneshotSender<Self>>) {
+        tx.send(self)
+    }
+}
+
+impl<A, M, I, S> MessageResponse<A, M> for HashSet<I, S>
+where
+    A: Actor,
+    M: Message<Result = Self>,
+    I: 'static,
+    S: 'static,
+{
+    fn handle(self, _: &mut A::Context, tx: Option<OneshotSender<Self>>) {
+        tx.send(self)
+    }
+}
+
+impl<A, M, K, V> MessageResponse<A, M> for BTreeMap<K, V>
+where
+    A: Actor,
+    M: Message<Result = Self>,
+    K: 'static,
+    V: 'static,
+{
+    fn handle(self, _: &mut A::Context, tx: Option<OneshotSender<Self>>) {
+        tx.send(self)
+    }
+}
+
+impl<A, M, K, V, S> MessageResponse<A, M> for HashMap<K, V, S>
+where
+    A: Actor,
+    M: Message<Result = Self>,
+    K: 'static,
+    V: 'static,
+    S: 'static,
+{
+    fn handle(self, _: &mut A::Context, tx: Option<OneshotSender<Self>>) {
+        tx.send(self)
+    }
+}
+
 impl<A, M, B> MessageResponse<A, M> for Addr<B>
 where
     A: Actor,
@@ -400,6 +684,8 @@ where
 enum ResponseTypeItem<I> {
     Result(I),
     Fut(Pin<Box<dyn Future<Output = I>>>),
+    // the reply was sent by the handler
+    Chunked,
 }
 
 /// Helper type for representing different type of message responses
@@ -413,6 +699,7 @@ impl<I> fmt::Debug for Response<I> {
         match self.item {
             ResponseTypeItem::Result(_) => fmt.field("item", &"Result(_)".to_string()),
             ResponseTypeItem::Fut(_) => fmt.field("item", &"Fut(_)".to_string()),
+            ResponseTypeItem::Chunked => fmt.field("item", &"Chunked".to_string()),
         }
         .finish()
     }
@@ -437,6 +724,19 @@ impl<I> Response<I> {
     }
 }
 
+impl<T> Response<Chunked<T>> {
+    /// Creates the response of a handler which replied with
+    /// [`SyncContext::chunked_reply()`](crate::SyncContext::chunked_reply).
+    ///
+    /// If the handler did not take the chunked reply, the request fails with
+    /// [`MailboxError::Closed`](crate::MailboxError::Closed).
+    pub fn chunked() -> Self {
+        Self {
+            item: ResponseTypeItem::Chunked,
+        }
+    }
+}
+
 impl<A, M> MessageResponse<A, M> for Response<M::Result>
 where
     A: Actor,
@@ -448,6 +748,7 @@ where
                 actix_rt::spawn(async { tx.send(fut.await) });
             }
             ResponseTypeItem::Result(res) => tx.send(res),
+            ResponseTypeItem::Chunked => {}
         }
     }
 }
@@ -455,6 +756,8 @@ where
 enum ActorResponseTypeItem<A, I> {
     Result(I),
     Fut(Pin<Box<dyn ActorFuture<A, Output = I>>>),
+    // dropped once the requester is gone
+    Proxy(Pin<Box<dyn ActorFuture<A, Output = I>>>),
 }
 
 /// A helper type for representing different types of message responses.
@@ -468,6 +771,7 @@ impl<A, I> fmt::Debug for ActorResponse<A, I> {
         match self.item {
             ActorResponseTypeItem::Result(_) => fmt.field("item", &"Result(_)".to_string()),
             ActorResponseTypeItem::Fut(_) => fmt.field("item", &"Fut(_)".to_string()),
+            ActorResponseTypeItem::Proxy(_) => fmt.field("item", &"Proxy(_)".to_string()),
         }
         .finish()
     }
@@ -490,6 +794,80 @@ impl<A: Actor, I> ActorResponse<A, I> {
             item: ActorResponseTypeItem::Fut(Box::pin(fut)),
         }
     }
+
+    /// Creates a response from the reply to a request sent to another actor, mapped with `map`.
+    ///
+    /// `map` receives the outcome of the request, including the [`MailboxError`] it failed
+    /// with, and the actor and its context to turn it into the response. If the requester stops
+    /// waiting for the response, the request is dropped, so the other actor skips its message
+    /// if it has not handled it yet, and `map` is not called.
+    ///
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// struct Backend;
+    ///
+    /// impl Actor for Backend {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "u32")]
+    /// struct Lookup(String);
+    ///
+    /// impl Handler<Lookup> for Backend {
+    ///     type Result = u32;
+    ///
+    ///     fn handle(&mut self, msg: Lookup, _: &mut Self::Context) -> u32 {
+    ///         msg.0.len() as u32
+    ///     }
+    /// }
+    ///
+    /// struct Gateway {
+    ///     backend: Addr<Backend>,
+    ///     served: usize,
+    /// }
+    ///
+    /// impl Actor for Gateway {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "Result<String, MailboxError>")]
+    /// struct Get(String);
+    ///
+    /// impl Handler<Get> for Gateway {
+    ///     type Result = ActorResponse<Self, Result<String, MailboxError>>;
+    ///
+    ///     fn handle(&mut self, msg: Get, _: &mut Self::Context) -> Self::Result {
+    ///         let lookup = self.backend.send(Lookup(msg.0));
+    ///         ActorResponse::from_request(lookup, |res, act: &mut Self, _| {
+    ///             act.served += 1;
+    ///             Ok(format!("id {}", res?))
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let gateway = Gateway {
+    ///         backend: Backend.start(),
+    ///         served: 0,
+    ///     }
+    ///     .start();
+    ///     let res = gateway.send(Get("actix".to_owned())).await.unwrap();
+    ///     assert_eq!(res, Ok("id 5".to_owned()));
+    /// }
+    /// ```
+    pub fn from_request<R, T, F>(req: R, map: F) -> Self
+    where
+        R: Future<Output = Result<T, MailboxError>> + 'static,
+        F: FnOnce(Result<T, MailboxError>, &mut A, &mut A::Context) -> I + 'static,
+    {
+        Self {
+            item: ActorResponseTypeItem::Proxy(Box::pin(wrap_future(req).map(map))),
+        }
+    }
 }
 
 impl<A, M> MessageResponse<A, M> for ActorResponse<A, M::Result>
@@ -504,11 +882,45 @@ where
                 let fut = fut.map(|res, _, _| tx.send(res));
                 ctx.spawn(fut);
             }
+            ActorResponseTypeItem::Proxy(fut) => {
+                ctx.spawn(ProxyReply { fut, tx });
+            }
             ActorResponseTypeItem::Result(res) => tx.send(res),
         }
     }
 }
 
+/// Future of a proxied response, dropped once the requester is gone.
+struct ProxyReply<A, I> {
+    fut: Pin<Box<dyn ActorFuture<A, Output = I>>>,
+    tx: Option<OneshotSender<I>>,
+}
+
+impl<A: Actor, I> ActorFuture<A> for ProxyReply<A, I> {
+    type Output = ();
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut task::Context<'_>,
+    ) -> Poll<()> {
+        let this = self.get_mut();
+        if let Some(tx) = this.tx.as_mut() {
+            if tx.poll_closed(task).is_ready() {
+                return Poll::Ready(());
+            }
+        }
+        match this.fut.as_mut().poll(act, ctx, task) {
+            Poll::Ready(res) => {
+                this.tx.take().send(res);
+                Poll::Ready(())
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
 impl<A: Actor, I> From<Pin<Box<dyn ActorFuture<A, Output = I>>>> for ActorResponse<A, I> {
     fn from(fut: Pin<Box<dyn ActorFuture<A, Output = I>>>) -> Self {
         Self {
@@ -536,15 +948,18 @@ SIMPLE_RESULT!(u8);
 SIMPLE_RESULT!(u16);
 SIMPLE_RESULT!(u32);
 SIMPLE_RESULT!(u64);
+SIMPLE_RESULT!(u128);
 SIMPLE_RESULT!(usize);
 SIMPLE_RESULT!(i8);
 SIMPLE_RESULT!(i16);
 SIMPLE_RESULT!(i32);
 SIMPLE_RESULT!(i64);
+SIMPLE_RESULT!(i128);
 SIMPLE_RESULT!(isize);
 SIMPLE_RESULT!(f32);
 SIMPLE_RESULT!(f64);
 SIMPLE_RESULT!(String);
+SIMPLE_RESULT!(char);
 SIMPLE_RESULT!(bool);
 
 // Helper trait for send one shot message from Option<Sender> type.
@@ -1,7 +1,7 @@
 use std::{
     cell::RefCell,
     collections::VecDeque,
-    io,
+    fmt, io,
     marker::PhantomData,
     ops::DerefMut,
     pin::Pin,
@@ -13,8 +13,8 @@ use std::{
 use bitflags::bitflags;
 use bytes::BytesMut;
 use futures_sink::Sink;
-use tokio::io::{AsyncWrite, AsyncWriteExt};
-use tokio_util::codec::Encoder;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio_util::codec::{Decoder, Encoder};
 
 use crate::{
     actor::{Actor, ActorContext, AsyncContext, Running, SpawnHandle},
@@ -48,6 +48,7 @@ where
 }
 
 bitflags! {
+    #[derive(Clone, Copy)]
     struct Flags: u8 {
         const CLOSING = 0b0000_0001;
         const CLOSED = 0b0000_0010;
@@ -399,6 +400,51 @@ impl<I, T: AsyncWrite + Unpin, U: Encoder<I>> FramedWrite<I, T, U> {
     pub fn handle(&self) -> SpawnHandle {
         self.inner.0.borrow().handle
     }
+
+    /// Replaces the encoder, e.g. to upgrade the protocol spoken over the connection.
+    ///
+    /// Items encoded by the previous encoder but not yet written are written before any item
+    /// encoded by the new one. The writer future of this `FramedWrite` is cancelled and a new one
+    /// is spawned in `ctx`, so the returned writer has a different [`handle`](Self::handle). An
+    /// encoding error of the previous encoder that was not reported yet is discarded.
+    ///
+    /// The read half of a connection is a stream added to the context, for example a
+    /// `tokio_util::codec::FramedRead`, whose decoder can be replaced without losing buffered
+    /// bytes with `FramedRead::map_decoder()` before adding it with
+    /// [`AsyncContext::add_stream`].
+    pub fn replace_encoder<I2, U2, A, C>(self, enc: U2, ctx: &mut C) -> FramedWrite<I2, T, U2>
+    where
+        A: Actor<Context = C> + WriteHandler<U2::Error>,
+        C: AsyncContext<A>,
+        U2: Encoder<I2>,
+        U2::Error: 'static,
+        T: 'static,
+    {
+        let mut old = self.inner.0.borrow_mut();
+        ctx.cancel_future(old.handle);
+
+        let inner = UnsafeWriter(
+            Rc::new(RefCell::new(InnerWriter {
+                buffer: std::mem::take(&mut old.buffer),
+                flags: old.flags,
+                error: None,
+                low: old.low,
+                high: old.high,
+                handle: SpawnHandle::default(),
+                task: None,
+            })),
+            self.inner.1.clone(),
+        );
+        drop(old);
+
+        let h = ctx.spawn(WriterFut {
+            inner: inner.clone(),
+        });
+
+        let writer = FramedWrite { enc, inner };
+        writer.inner.0.borrow_mut().handle = h;
+        writer
+    }
 }
 
 impl<I, T: AsyncWrite + Unpin, U: Encoder<I>> Drop for FramedWrite<I, T, U> {
@@ -414,6 +460,176 @@ impl<I, T: AsyncWrite + Unpin, U: Encoder<I>> Drop for FramedWrite<I, T, U> {
     }
 }
 
+/// The error that ended or interrupted reading a [`FramedReader`].
+#[derive(Debug)]
+pub enum FramedError<E> {
+    /// Reading from the transport failed.
+    Io(io::Error),
+    /// The decoder rejected a frame.
+    Decode(E),
+}
+
+impl<E: fmt::Display> fmt::Display for FramedError<E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FramedError::Io(err) => write!(fmt, "Reading from transport failed: {}", err),
+            FramedError::Decode(err) => write!(fmt, "Decoding frame failed: {}", err),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for FramedError<E> {}
+
+/// A helper trait for framed read handling.
+///
+/// Implementation of this trait is required for [`FramedReader`] support.
+#[allow(unused_variables)]
+pub trait ReadHandler<D>
+where
+    Self: Actor,
+    Self::Context: ActorContext,
+    D: Decoder,
+{
+    /// Called for every decoded frame.
+    fn handle(&mut self, item: D::Item, ctx: &mut Self::Context);
+
+    /// Called when the reader reaches a clean end of the transport, with `None`, or fails.
+    ///
+    /// Returning `Running::Continue` keeps the actor running, for instance to reconnect. After
+    /// a [`FramedError::Decode`] reading also continues, skipping the rejected frame. By default
+    /// this method returns `Running::Stop`, which stops the actor.
+    fn closed(&mut self, err: Option<FramedError<D::Error>>, ctx: &mut Self::Context) -> Running {
+        Running::Stop
+    }
+}
+
+/// Size of the chunks read from the transport of a [`FramedReader`].
+const READ_CHUNK: usize = 4 * 1024;
+
+/// An actor future reading frames from an `AsyncRead` with a `Decoder`, passing them and the
+/// errors of the transport and the decoder to the actor's [`ReadHandler`].
+///
+/// Unlike a `tokio_util::codec::FramedRead` added as a stream, the actor can tell a clean end of
+/// the transport from a failing transport and from a rejected frame, and can skip rejected
+/// frames. A decoder which returns an error without consuming the rejected bytes from the buffer
+/// has all buffered bytes skipped.
+///
+/// ```
+/// # use actix::{io::{FramedReader, ReadHandler}, prelude::*};
+/// # use tokio_util::codec::LinesCodec;
+/// struct Session;
+///
+/// impl Actor for Session {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, ctx: &mut Self::Context) {
+///         let io: &'static [u8] = b"hello\nworld\n";
+///         ctx.spawn(FramedReader::new(io, LinesCodec::new()));
+///     }
+/// }
+///
+/// impl ReadHandler<LinesCodec> for Session {
+///     fn handle(&mut self, line: String, _: &mut Self::Context) {
+///         println!("{}", line);
+///     }
+/// }
+/// ```
+pub struct FramedReader<T, D> {
+    io: T,
+    decoder: D,
+    buffer: BytesMut,
+    eof: bool,
+}
+
+impl<T, D> FramedReader<T, D>
+where
+    T: AsyncRead + Unpin,
+    D: Decoder,
+{
+    pub fn new(io: T, decoder: D) -> Self {
+        FramedReader {
+            io,
+            decoder,
+            buffer: BytesMut::new(),
+            eof: false,
+        }
+    }
+}
+
+impl<T, D, A> ActorFuture<A> for FramedReader<T, D>
+where
+    T: AsyncRead + Unpin,
+    D: Decoder + Unpin,
+    A: Actor + ReadHandler<D>,
+    A::Context: AsyncContext<A>,
+{
+    type Output = ();
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut polled = 0;
+        loop {
+            // hand out the buffered frames first
+            loop {
+                let len = this.buffer.len();
+                let res = if this.eof {
+                    this.decoder.decode_eof(&mut this.buffer)
+                } else {
+                    this.decoder.decode(&mut this.buffer)
+                };
+                match res {
+                    Ok(Some(item)) => act.handle(item, ctx),
+                    Ok(None) => break,
+                    Err(err) => {
+                        if this.buffer.len() == len {
+                            this.buffer.clear();
+                        }
+                        if act.closed(Some(FramedError::Decode(err)), ctx) == Running::Stop {
+                            ctx.stop();
+                            return Poll::Ready(());
+                        }
+                    }
+                }
+
+                polled += 1;
+                if ctx.waiting() {
+                    return Poll::Pending;
+                } else if polled == 16 {
+                    // yield like streams do, not to starve other actor futures
+                    task.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+            }
+
+            if this.eof {
+                if act.closed(None, ctx) == Running::Stop {
+                    ctx.stop();
+                }
+                return Poll::Ready(());
+            }
+
+            let mut chunk = [0; READ_CHUNK];
+            let mut buf = ReadBuf::new(&mut chunk);
+            match Pin::new(&mut this.io).poll_read(task, &mut buf) {
+                Poll::Ready(Ok(())) if buf.filled().is_empty() => this.eof = true,
+                Poll::Ready(Ok(())) => this.buffer.extend_from_slice(buf.filled()),
+                Poll::Ready(Err(err)) => {
+                    if act.closed(Some(FramedError::Io(err)), ctx) == Running::Stop {
+                        ctx.stop();
+                    }
+                    return Poll::Ready(());
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
 /// A wrapper for the `Sink` type.
 pub struct SinkWrite<I, S: Sink<I> + Unpin> {
     inner: Rc<RefCell<InnerSinkWrite<I, S>>>,
@@ -0,0 +1,332 @@
+//! Journaling of handled messages, for actors which recover their state by replaying them.
+//!
+//! An actor created with [`Actor::create_replayed()`] first handles the messages found in its
+//! [`Journal`], then every message of a type registered with [`Context::journal_message()`]
+//! which it handles is appended to the journal. Messages are encoded with
+//! [`PersistentMessage`] and tagged with their type name.
+//!
+//! During replay, [`Context::is_replaying()`] returns `true`, so that handlers can skip side
+//! effects which already happened. Replay happens before [`Actor::started()`] and before the
+//! mailbox is opened, and a journal which can't be replayed fails the creation of the actor.
+//!
+//! ```
+//! use actix::{
+//!     journal::MemoryJournal,
+//!     prelude::*,
+//!     spill::PersistentMessage,
+//! };
+//!
+//! #[derive(Message)]
+//! #[rtype(result = "u64")]
+//! struct Deposit(u64);
+//!
+//! impl PersistentMessage for Deposit {
+//!     fn to_bytes(&self) -> Vec<u8> {
+//!         self.0.to_le_bytes().to_vec()
+//!     }
+//!
+//!     fn from_bytes(bytes: &[u8]) -> std::io::Result<Self> {
+//!         let bytes = bytes.try_into().map_err(|_| std::io::ErrorKind::InvalidData)?;
+//!         Ok(Deposit(u64::from_le_bytes(bytes)))
+//!     }
+//! }
+//!
+//! struct Account {
+//!     balance: u64,
+//! }
+//!
+//! impl Actor for Account {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! impl Handler<Deposit> for Account {
+//!     type Result = u64;
+//!
+//!     fn handle(&mut self, Deposit(amount): Deposit, ctx: &mut Self::Context) -> u64 {
+//!         self.balance += amount;
+//!         if !ctx.is_replaying() {
+//!             println!("deposited {}", amount);
+//!         }
+//!         self.balance
+//!     }
+//! }
+//!
+//! fn open(journal: &MemoryJournal) -> Addr<Account> {
+//!     Account::create_replayed(Box::new(journal.clone()), "account-1", |ctx| {
+//!         ctx.journal_message::<Deposit>();
+//!         Account { balance: 0 }
+//!     })
+//!     .unwrap()
+//! }
+//!
+//! #[actix::main]
+//! async fn main() {
+//!     let journal = MemoryJournal::new();
+//!     let account = open(&journal);
+//!     account.send(Deposit(10)).await.unwrap();
+//!     account.send(Deposit(5)).await.unwrap();
+//!
+//!     // a new instance recovers the balance
+//!     let account = open(&journal);
+//!     assert_eq!(account.send(Deposit(1)).await.unwrap(), 16);
+//! }
+//! ```
+//!
+//! [`Actor::create_replayed()`]: crate::Actor::create_replayed
+//! [`Actor::started()`]: crate::Actor::started
+//! [`Context::journal_message()`]: crate::Context::journal_message
+//! [`Context::is_replaying()`]: crate::Context::is_replaying
+
+use std::{
+    any::{self, TypeId},
+    collections::HashMap,
+    fmt, io,
+    sync::Arc,
+};
+
+use parking_lot::Mutex;
+
+use crate::{
+    actor::{Actor, AsyncContext},
+    address::{Envelope, EnvelopeProxy, SyncEnvelopeProxy},
+    handler::{Handler, MessageResponse},
+    spill::PersistentMessage,
+};
+
+/// Storage of the messages handled by journaled actors.
+pub trait Journal {
+    /// Appends a handled message of the actor `persistence_id`.
+    fn append(&mut self, persistence_id: &str, type_name: &str, bytes: &[u8]) -> io::Result<()>;
+
+    /// Returns the messages appended for the actor `persistence_id`, oldest first.
+    fn replay(
+        &mut self,
+        persistence_id: &str,
+    ) -> io::Result<Box<dyn Iterator<Item = io::Result<JournalEntry>> + '_>>;
+}
+
+/// A message read back from a [`Journal`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    /// Type name of the message, as given by [`std::any::type_name()`].
+    pub type_name: String,
+    /// The message, as encoded by [`PersistentMessage::to_bytes()`].
+    pub bytes: Vec<u8>,
+}
+
+/// A [`Journal`] kept in memory, shared by its clones.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryJournal(Arc<Mutex<HashMap<String, Vec<JournalEntry>>>>);
+
+impl MemoryJournal {
+    /// Creates an empty journal.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the entries appended for the actor `persistence_id`.
+    pub fn entries(&self, persistence_id: &str) -> Vec<JournalEntry> {
+        self.0
+            .lock()
+            .get(persistence_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+impl Journal for MemoryJournal {
+    fn append(&mut self, persistence_id: &str, type_name: &str, bytes: &[u8]) -> io::Result<()> {
+        self.0
+            .lock()
+            .entry(persistence_id.to_owned())
+            .or_default()
+            .push(JournalEntry {
+                type_name: type_name.to_owned(),
+                bytes: bytes.to_vec(),
+            });
+        Ok(())
+    }
+
+    fn replay(
+        &mut self,
+        persistence_id: &str,
+    ) -> io::Result<Box<dyn Iterator<Item = io::Result<JournalEntry>> + '_>> {
+        let entries = self.entries(persistence_id);
+        Ok(Box::new(entries.into_iter().map(Ok)))
+    }
+}
+
+/// Error of [`Actor::create_replayed()`](crate::Actor::create_replayed).
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The journal failed to read the messages.
+    Io(io::Error),
+
+    /// A message has a type which is not journaled by the actor.
+    UnknownType(String),
+
+    /// A message failed to decode.
+    Decode {
+        /// Type name of the message.
+        type_name: String,
+        /// Error returned by [`PersistentMessage::from_bytes()`].
+        error: io::Error,
+    },
+
+    /// The actor stopped while its messages were replayed.
+    Stopped,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(fmt, "failed to read journal: {}", err),
+            ReplayError::UnknownType(name) => write!(fmt, "unknown journaled message {}", name),
+            ReplayError::Decode { type_name, error } => {
+                write!(fmt, "failed to decode {}: {}", type_name, error)
+            }
+            ReplayError::Stopped => write!(fmt, "actor stopped during replay"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReplayError::Io(err) | ReplayError::Decode { error: err, .. } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ReplayError {
+    fn from(err: io::Error) -> Self {
+        ReplayError::Io(err)
+    }
+}
+
+/// Encoding and replay functions of a journaled message type.
+pub(crate) struct JournaledType<A: Actor> {
+    type_id: TypeId,
+    name: &'static str,
+    /// Encodes the message of an envelope of this type.
+    encode: fn(&mut Envelope<A>) -> Option<Vec<u8>>,
+    /// Checks if the message of the envelope was taken by its handler.
+    handled: fn(&mut Envelope<A>) -> bool,
+    /// Decodes a message and handles it.
+    replay: fn(&[u8], &mut A, &mut A::Context) -> io::Result<()>,
+}
+
+impl<A: Actor> Clone for JournaledType<A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<A: Actor> Copy for JournaledType<A> {}
+
+impl<A> JournaledType<A>
+where
+    A: Actor,
+    A::Context: AsyncContext<A>,
+{
+    pub(crate) fn new<M>() -> Self
+    where
+        A: Handler<M>,
+        M: PersistentMessage + Send + 'static,
+        M::Result: Send,
+    {
+        fn proxy<A: Actor, M>(env: &mut Envelope<A>) -> Option<&mut SyncEnvelopeProxy<M>>
+        where
+            M: PersistentMessage + Send + 'static,
+            M::Result: Send,
+        {
+            env.as_any_mut()
+                .and_then(|proxy| proxy.downcast_mut::<SyncEnvelopeProxy<M>>())
+        }
+
+        JournaledType {
+            type_id: TypeId::of::<M>(),
+            name: any::type_name::<M>(),
+            encode: |env| proxy::<A, M>(env)?.message().map(M::to_bytes),
+            handled: |env| proxy::<A, M>(env).map_or(false, |p| p.message().is_none()),
+            replay: |bytes, act, ctx| {
+                let msg = M::from_bytes(bytes)?;
+                <A as Handler<M>>::handle(act, msg, ctx).handle(ctx, None);
+                Ok(())
+            },
+        }
+    }
+}
+
+/// Journal of an actor and the message types it journals, shared by its context and mailbox.
+pub(crate) struct JournalState<A: Actor> {
+    /// Taken out while the journal is replayed.
+    journal: Option<Box<dyn Journal>>,
+    persistence_id: String,
+    types: Vec<JournaledType<A>>,
+}
+
+impl<A> JournalState<A>
+where
+    A: Actor,
+    A::Context: AsyncContext<A>,
+{
+    pub(crate) fn new() -> Self {
+        JournalState {
+            journal: None,
+            persistence_id: String::new(),
+            types: Vec::new(),
+        }
+    }
+
+    pub(crate) fn set_journal(&mut self, journal: Box<dyn Journal>, persistence_id: String) {
+        self.journal = Some(journal);
+        self.persistence_id = persistence_id;
+    }
+
+    pub(crate) fn add_type(&mut self, ty: JournaledType<A>) {
+        if !self.types.iter().any(|t| t.type_id == ty.type_id) {
+            self.types.push(ty);
+        }
+    }
+
+    /// Encodes the message of the envelope if its type is journaled.
+    pub(crate) fn encode(&self, env: &mut Envelope<A>) -> Option<(JournaledType<A>, Vec<u8>)> {
+        self.journal.as_ref()?;
+        self.types
+            .iter()
+            .find_map(|ty| (ty.encode)(env).map(|bytes| (*ty, bytes)))
+    }
+
+    /// Appends the message encoded by `encode()` once its handler has taken it.
+    pub(crate) fn append(&mut self, env: &mut Envelope<A>, ty: JournaledType<A>, bytes: &[u8]) {
+        if !(ty.handled)(env) {
+            return;
+        }
+        if let Some(ref mut journal) = self.journal {
+            if let Err(err) = journal.append(&self.persistence_id, ty.name, bytes) {
+                log::error!("Failed to journal {}: {}", ty.name, err);
+            }
+        }
+    }
+
+    pub(crate) fn take_journal(&mut self) -> Option<(Box<dyn Journal>, String)> {
+        let journal = self.journal.take()?;
+        Some((journal, self.persistence_id.clone()))
+    }
+
+    pub(crate) fn restore_journal(&mut self, journal: Box<dyn Journal>) {
+        self.journal = Some(journal);
+    }
+
+    pub(crate) fn find(&self, type_name: &str) -> Option<JournaledType<A>> {
+        self.types.iter().find(|ty| ty.name == type_name).copied()
+    }
+}
+
+impl<A: Actor> JournaledType<A> {
+    pub(crate) fn replay(&self, bytes: &[u8], act: &mut A, ctx: &mut A::Context) -> io::Result<()> {
+        (self.replay)(bytes, act, ctx)
+    }
+}
@@ -29,12 +29,29 @@
 doc_comment::doctest!("../README.md");
 
 mod actor;
+mod arbiter;
+mod component;
+mod config;
 mod context;
 mod contextimpl;
 mod contextitems;
+mod deadletter;
+#[cfg(feature = "testing")]
+mod fault;
 mod handler;
+mod logging;
+mod middleware;
+mod minimal;
+mod panic_report;
+mod placement;
+#[cfg(feature = "telemetry")]
+mod residency;
+mod settings;
 mod stream;
 mod supervisor;
+mod supervisor_group;
+#[cfg(feature = "telemetry")]
+mod wakeup;
 
 mod address;
 mod mailbox;
@@ -43,8 +60,14 @@ pub mod actors;
 pub mod clock;
 pub mod fut;
 pub mod io;
+pub mod journal;
+pub mod queue;
 pub mod registry;
+pub mod spill;
+#[cfg(feature = "stress")]
+pub mod stress;
 pub mod sync;
+pub mod test;
 pub mod utils;
 
 #[cfg(feature = "macros")]
@@ -60,21 +83,51 @@ pub mod __private {
 #[doc(hidden)]
 pub use crate::context::ContextFutureSpawner;
 pub use crate::{
-    actor::{Actor, ActorContext, ActorState, AsyncContext, Running, SpawnHandle, Supervised},
-    address::{Addr, MailboxError, Recipient, WeakAddr, WeakRecipient},
+    actor::{
+        Actor, ActorContext, ActorState, AsyncContext, ResourceHandle, Running, ScopeGuard,
+        ScopeHandle, SpawnHandle, Supervised, WaitHandle,
+    },
+    address::{
+        broadcast, broadcast_async, send_all, send_all_recipients, Addr, AddressSink,
+        BroadcastAsync, BroadcastReport, Closed, ExitReason, LinkedExit, MailboxError, ProtoAddr,
+        ProtoRequest, ProtocolEnd, ProtocolState, ProtocolStep, RateLimit, RateLimitPolicy,
+        Recipient, RecipientSink, RevokeHandle, SendAll, SendAllSettled, StateStream, StopReason,
+        StopWait, StopWaitError, TransformOnSend, UnhandledMessage, WeakAddr, WeakRecipient,
+    },
+    arbiter::{
+        stop_gracefully, ArbiterBuilder, ArbiterError, ArbiterHandleExt, ArbiterReply,
+        ArbiterStats, PanicPolicy, TrackedHandle,
+    },
+    component::ComponentHandle,
+    config::SystemConfig,
     context::Context,
+    contextimpl::{Barrier, ContextStats, SwapOptions},
+    deadletter::{DeadLetterReason, DeadLetterRecord, SystemExt},
     fut::{
         ActorFuture, ActorFutureExt, ActorStream, ActorStreamExt, ActorTryFuture,
         ActorTryFutureExt, WrapFuture, WrapStream,
     },
     handler::{
-        ActorResponse, AtomicResponse, Handler, Message, MessageResult, Response,
-        ResponseActFuture, ResponseFuture,
+        ActorResponse, AtomicResponse, BatchHandler, Handler, Keyed, Message, MessageResult, Query,
+        Response, ResponseActFuture, ResponseFuture, Sequenced, SignalHandler,
     },
-    registry::{ArbiterService, Registry, SystemRegistry, SystemService},
+    logging::{ActorId, ActorLog},
+    mailbox::{PressureLevel, SequenceGap, SequenceGapPolicy, SequenceStats},
+    middleware::{MessageMiddleware, Rejection},
+    minimal::MinimalContext,
+    panic_report::{PanicReport, PanicSource},
+    placement::{Placement, PlacementStrategy},
+    registry::{ArbiterService, Registry, RegistryEntry, SystemRegistry, SystemService},
+    settings::{ActorSettings, StopPolicy},
     stream::StreamHandler,
-    supervisor::Supervisor,
-    sync::{SyncArbiter, SyncContext},
+    supervisor::{RestartPolicy, Supervisor, SupervisorBuilder},
+    supervisor_group::{GroupStrategy, SupervisionFailed, SupervisorGroup},
+    sync::{EnvelopeScope, SyncArbiter, SyncContext},
+};
+#[cfg(feature = "telemetry")]
+pub use crate::{
+    residency::ResidencyHistogram,
+    wakeup::{WakeupCause, WakeupCounts},
 };
 
 pub mod prelude {
@@ -97,23 +150,36 @@ pub mod prelude {
     #[allow(deprecated)]
     pub use crate::utils::Condition;
     pub use crate::{
-        actor::{Actor, ActorContext, ActorState, AsyncContext, Running, SpawnHandle, Supervised},
+        actor::{
+            Actor, ActorContext, ActorState, AsyncContext, ResourceHandle, Running, ScopeGuard,
+            ScopeHandle, SpawnHandle, Supervised, WaitHandle,
+        },
         actors,
-        address::{Addr, MailboxError, Recipient, RecipientRequest, Request, SendError},
+        address::{
+            Addr, ExitReason, LinkedExit, MailboxError, Recipient, RecipientRequest, Request,
+            RevokeHandle, SendError, TransformOnSend, UnhandledMessage,
+        },
+        arbiter::ArbiterHandleExt,
+        component::ComponentHandle,
         context::{Context, ContextFutureSpawner},
+        deadletter::SystemExt,
         dev, fut,
         fut::{
             ActorFuture, ActorFutureExt, ActorStream, ActorStreamExt, ActorTryFuture,
             ActorTryFutureExt, WrapFuture, WrapStream,
         },
         handler::{
-            ActorResponse, AtomicResponse, Handler, Message, MessageResult, Response,
-            ResponseActFuture, ResponseFuture,
+            ActorResponse, AtomicResponse, BatchHandler, Handler, Keyed, Message, MessageResult,
+            Query, Response, ResponseActFuture, ResponseFuture, Sequenced, SignalHandler,
         },
         io,
+        logging::{ActorId, ActorLog},
+        mailbox::PressureLevel,
+        panic_report::PanicReport,
         registry::{ArbiterService, SystemService},
         stream::StreamHandler,
-        supervisor::Supervisor,
+        supervisor::{RestartPolicy, Supervisor, SupervisorBuilder},
+        supervisor_group::{GroupStrategy, SupervisionFailed, SupervisorGroup},
         sync::{SyncArbiter, SyncContext},
         utils::{IntervalFunc, TimerFunc},
     };
@@ -131,7 +197,10 @@ pub mod dev {
     //! ```
 
     pub use crate::{
-        address::{Envelope, EnvelopeProxy, RecipientRequest, Request, ToEnvelope},
+        address::{
+            to_envelope, Envelope, EnvelopeProxy, RecipientRequest, Request, TestEnvelopeSink,
+            ToEnvelope,
+        },
         prelude::*,
     };
     pub mod channel {
@@ -141,6 +210,7 @@ pub mod dev {
         contextimpl::{AsyncContextParts, ContextFut, ContextParts},
         handler::{MessageResponse, OneshotSender},
         mailbox::Mailbox,
+        minimal::MinimalContextFut,
         registry::{Registry, SystemRegistry},
     };
 }
@@ -0,0 +1,147 @@
+use std::{
+    any::type_name,
+    cell::Cell,
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use log::Level;
+
+static ARBITERS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local!(
+    static ARBITER: usize = ARBITERS.fetch_add(1, Ordering::Relaxed);
+    static ACTORS: Cell<u64> = const { Cell::new(0) };
+);
+
+/// Returns the index of the current arbiter, see [`ActorId::arbiter()`].
+pub(crate) fn arbiter_index() -> usize {
+    ARBITER.with(|id| *id)
+}
+
+/// Identifier of an actor's execution context.
+///
+/// Ids are assigned in creation order per arbiter. The arbiter index makes them unique within
+/// the process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ActorId {
+    arbiter: usize,
+    seq: u64,
+}
+
+impl ActorId {
+    pub(crate) fn next() -> Self {
+        ActorId {
+            arbiter: arbiter_index(),
+            seq: ACTORS.with(|seq| {
+                seq.set(seq.get() + 1);
+                seq.get()
+            }),
+        }
+    }
+
+    /// Returns the index of the arbiter which created the context.
+    pub fn arbiter(&self) -> usize {
+        self.arbiter
+    }
+
+    /// Returns the sequence number of the context within its arbiter.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+}
+
+impl fmt::Display for ActorId {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}.{}", self.arbiter, self.seq)
+    }
+}
+
+/// Logger which stamps records with the actor's type, id and arbiter.
+///
+/// Records use the actor's type path as their target, so they can be filtered per actor type.
+/// Created by [`Context::log`](crate::Context::log).
+///
+/// ```
+/// # use actix::prelude::*;
+/// struct MyActor;
+///
+/// impl Actor for MyActor {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, ctx: &mut Self::Context) {
+///         // logs "actor_type=MyActor actor_id=0.1 arbiter=...: started"
+///         ctx.log().info(format_args!("started"));
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ActorLog {
+    target: &'static str,
+    id: ActorId,
+}
+
+impl ActorLog {
+    pub(crate) fn new<A>(id: ActorId) -> Self {
+        ActorLog {
+            target: type_name::<A>(),
+            id,
+        }
+    }
+
+    /// Returns the id of the actor.
+    pub fn actor_id(&self) -> ActorId {
+        self.id
+    }
+
+    /// Logs a record at the given level.
+    pub fn log(&self, level: Level, args: fmt::Arguments<'_>) {
+        if log::log_enabled!(target: self.target, level) {
+            log::log!(target: self.target, level, "{}: {}", self, args);
+        }
+    }
+
+    /// Logs a record at the error level.
+    pub fn error(&self, args: fmt::Arguments<'_>) {
+        self.log(Level::Error, args)
+    }
+
+    /// Logs a record at the warn level.
+    pub fn warn(&self, args: fmt::Arguments<'_>) {
+        self.log(Level::Warn, args)
+    }
+
+    /// Logs a record at the info level.
+    pub fn info(&self, args: fmt::Arguments<'_>) {
+        self.log(Level::Info, args)
+    }
+
+    /// Logs a record at the debug level.
+    pub fn debug(&self, args: fmt::Arguments<'_>) {
+        self.log(Level::Debug, args)
+    }
+
+    /// Logs a record at the trace level.
+    pub fn trace(&self, args: fmt::Arguments<'_>) {
+        self.log(Level::Trace, args)
+    }
+
+    fn actor_type(&self) -> &'static str {
+        // strip module path and generic parameters
+        let name = self.target.split('<').next().unwrap_or(self.target);
+        name.rsplit("::").next().unwrap_or(name)
+    }
+}
+
+impl fmt::Display for ActorLog {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "actor_type={} actor_id={} arbiter={}",
+            self.actor_type(),
+            self.id,
+            thread::current().name().unwrap_or("<unnamed>")
+        )
+    }
+}
@@ -1,10 +1,34 @@
-use std::{fmt, pin::Pin, task, task::Poll};
+use std::{
+    any::{type_name, TypeId},
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task,
+    task::Poll,
+    time::Duration,
+};
 
-use futures_core::stream::Stream;
+use futures_core::{ready, stream::Stream};
 
 use crate::{
     actor::{Actor, AsyncContext},
-    address::{channel, Addr, AddressReceiver, AddressSenderProducer, EnvelopeProxy},
+    address::Recipient,
+    address::{
+        channel, Addr, AddressReceiver, AddressSenderProducer, Envelope, EnvelopeProxy,
+        SyncEnvelopeProxy,
+    },
+    clock::{self, Instant, Timer},
+    config::SystemConfig,
+    fut::ActorFuture,
+    handler::{
+        BatchHandler, Handler, Keyed, Message, MessageResponse, OneshotSender, ResponseActFuture,
+        Sequenced,
+    },
+    journal::JournalState,
+    panic_report::{attribute, Attribution, PanicSource},
 };
 
 /// Default address channel capacity
@@ -16,6 +40,29 @@ where
     A::Context: AsyncContext<A>,
 {
     msgs: AddressReceiver<A>,
+    batchers: Vec<Box<dyn Batcher<A>>>,
+    partitioners: Vec<Rc<dyn Partitioner<A>>>,
+    sequencers: Vec<Rc<dyn Sequencer<A>>>,
+    /// Index of the batcher holding messages that are not dispatched yet.
+    active: Option<usize>,
+    /// Envelope received while a batch was pending, handled once the batch is dispatched.
+    next: Option<Envelope<A>>,
+    /// Maximum number of messages handled per poll.
+    budget: Option<usize>,
+    /// Timer armed while the rate limit delays the next message.
+    throttle: Option<Pin<Box<Timer>>>,
+    /// Set when the last poll found no message left to handle.
+    empty: bool,
+    /// Stop the actor once no message was received for this long.
+    idle_timeout: Option<Duration>,
+    last_message: Instant,
+    /// Timer armed while the mailbox is empty and an idle timeout is set.
+    idle: Option<Pin<Box<Timer>>>,
+    journal: Option<Rc<RefCell<JournalState<A>>>>,
+    /// Message being handled, kept if its handler panics.
+    handling: Attribution,
+    /// Pressure level last reported to the actor.
+    pressure: PressureLevel,
 }
 
 impl<A> fmt::Debug for Mailbox<A>
@@ -37,8 +84,8 @@ where
 {
     #[inline]
     fn default() -> Self {
-        let (_, rx) = channel::channel(DEFAULT_CAPACITY);
-        Mailbox { msgs: rx }
+        let (_, rx) = channel::channel(SystemConfig::current().get_mailbox_capacity());
+        Mailbox::new(rx)
     }
 }
 
@@ -49,7 +96,23 @@ where
 {
     #[inline]
     pub fn new(msgs: AddressReceiver<A>) -> Self {
-        Self { msgs }
+        Self {
+            msgs,
+            batchers: Vec::new(),
+            partitioners: Vec::new(),
+            sequencers: Vec::new(),
+            active: None,
+            next: None,
+            budget: SystemConfig::current().get_message_budget(),
+            throttle: None,
+            empty: false,
+            idle_timeout: None,
+            last_message: clock::now(),
+            idle: None,
+            journal: None,
+            handling: None,
+            pressure: PressureLevel::Falling,
+        }
     }
 
     pub fn capacity(&self) -> usize {
@@ -60,6 +123,52 @@ where
         self.msgs.set_capacity(cap);
     }
 
+    pub(crate) fn set_budget(&mut self, budget: Option<usize>) {
+        self.budget = budget;
+    }
+
+    pub(crate) fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        if self.idle_timeout.is_none() {
+            self.last_message = clock::now();
+        }
+        self.idle_timeout = timeout;
+        if timeout.is_none() {
+            self.idle = None;
+        }
+    }
+
+    /// Returns `true` if the last poll handled every queued message, including the messages
+    /// held by batches and partitions.
+    pub(crate) fn is_drained(&self) -> bool {
+        self.empty
+            && self.next.is_none()
+            && self.active.is_none()
+            && self.partitioners.iter().all(|p| p.queued() == 0)
+    }
+
+    /// Returns `true` once no message was received for the idle timeout.
+    ///
+    /// The timer only runs while the mailbox is empty, it wakes the task when it expires.
+    pub(crate) fn poll_idle(&mut self, task: &mut task::Context<'_>) -> bool {
+        let Some(timeout) = self.idle_timeout else {
+            return false;
+        };
+        if !self.empty {
+            return false;
+        }
+        let deadline = self.last_message + timeout;
+        let idle = match self.idle {
+            Some(ref mut idle) => {
+                if idle.deadline() != deadline {
+                    idle.as_mut().reset(deadline);
+                }
+                idle
+            }
+            None => self.idle.insert(Box::pin(Timer::at(deadline))),
+        };
+        idle.as_mut().poll(task).is_ready()
+    }
+
     #[inline]
     pub fn connected(&self) -> bool {
         self.msgs.connected()
@@ -69,27 +178,794 @@ where
         Addr::new(self.msgs.sender())
     }
 
+    /// Closes the mailbox, failing the messages which are still queued.
+    pub(crate) fn close(&mut self) {
+        self.msgs.close();
+    }
+
+    /// Closes the mailbox, forwarding the messages which are still queued to `target`.
+    pub(crate) fn handoff(&mut self, target: Addr<A>) {
+        if let Some(env) = self.next.take() {
+            target.push_envelope(env);
+        }
+        self.msgs.handoff(target.into_sender());
+    }
+
     pub fn sender_producer(&self) -> AddressSenderProducer<A> {
         self.msgs.sender_producer()
     }
 
+    /// Receives the next queued envelope without handling it.
+    pub(crate) fn poll_message(
+        &mut self,
+        task: &mut task::Context<'_>,
+    ) -> Poll<Option<Envelope<A>>> {
+        Pin::new(&mut self.msgs).poll_next(task)
+    }
+
+    /// Receives the next queued envelope, unless the rate limit delays it.
+    ///
+    /// While delayed, a timer is armed which wakes the task once the next message is allowed.
+    fn poll_limited(&mut self, task: &mut task::Context<'_>) -> Poll<Option<Envelope<A>>> {
+        if let Some(delay) = self.msgs.rate_limit_delay() {
+            let deadline = clock::now() + delay;
+            let throttle = match self.throttle {
+                Some(ref mut throttle) => {
+                    throttle.as_mut().reset(deadline);
+                    throttle
+                }
+                None => self.throttle.insert(Box::pin(Timer::at(deadline))),
+            };
+            if throttle.as_mut().poll(task).is_pending() {
+                self.empty = false;
+                return Poll::Pending;
+            }
+        }
+
+        let res = Pin::new(&mut self.msgs).poll_next(task);
+        if let Poll::Ready(Some(_)) = res {
+            self.msgs.rate_limit_consume();
+            if self.idle_timeout.is_some() {
+                self.last_message = clock::now();
+            }
+        }
+        self.empty = !matches!(res, Poll::Ready(Some(_)));
+        res
+    }
+
+    /// Puts an envelope in front of all queued ones.
+    pub(crate) fn push_front(&mut self, msg: Envelope<A>) {
+        debug_assert!(self.next.is_none());
+        self.next = Some(msg);
+    }
+
+    /// Registers a batcher, replacing the limits of an existing one for the same message type.
+    pub(crate) fn add_batcher(&mut self, batcher: Box<dyn Batcher<A>>) {
+        match self
+            .batchers
+            .iter_mut()
+            .find(|b| b.msg_type() == batcher.msg_type())
+        {
+            Some(existing) => existing.set_limits(batcher.limits()),
+            None => self.batchers.push(batcher),
+        }
+    }
+
+    pub(crate) fn has_journal(&self) -> bool {
+        self.journal.is_some()
+    }
+
+    pub(crate) fn set_journal(&mut self, journal: Rc<RefCell<JournalState<A>>>) {
+        self.journal = Some(journal);
+    }
+
+    /// Number of partitioners registered with the mailbox.
+    pub(crate) fn partitioners(&self) -> usize {
+        self.partitioners.len()
+    }
+
+    pub(crate) fn add_partitioner(&mut self, partitioner: Rc<dyn Partitioner<A>>) {
+        self.partitioners.push(partitioner);
+    }
+
+    /// Number of sequencers registered with the mailbox.
+    pub(crate) fn sequencers(&self) -> usize {
+        self.sequencers.len()
+    }
+
+    pub(crate) fn add_sequencer(&mut self, sequencer: Rc<dyn Sequencer<A>>) {
+        self.sequencers.push(sequencer);
+    }
+
+    /// Starts handlers of partitioned messages whose keys became idle, and handles sequenced
+    /// messages held back by a wait future.
+    fn dispatch_partitions(&mut self, act: &mut A, ctx: &mut A::Context) {
+        for partitioner in &self.partitioners {
+            Rc::clone(partitioner).dispatch(act, ctx);
+        }
+        for sequencer in &self.sequencers {
+            sequencer.dispatch(act, ctx);
+        }
+    }
+
+    /// Lanes hold as many messages as the mailbox, don't receive more until they make progress.
+    fn partitions_full(&self) -> bool {
+        let cap = self.capacity().max(1);
+        self.partitioners.iter().any(|p| p.queued() >= cap)
+    }
+
+    /// Moves the message into the matching batch, returns index of the batcher.
+    fn collect(&mut self, msg: &mut Envelope<A>) -> Option<usize> {
+        self.batchers.iter_mut().position(|b| b.collect(msg))
+    }
+
+    fn dispatch_batch(&mut self, act: &mut A, ctx: &mut A::Context) {
+        if let Some(idx) = self.active.take() {
+            self.msgs.reset_interrupt();
+            self.batchers[idx].dispatch(act, ctx);
+        }
+    }
+
+    fn handle(&mut self, mut msg: Envelope<A>, act: &mut A, ctx: &mut A::Context) {
+        self.msgs.reset_interrupt();
+        self.handling = msg
+            .message_info()
+            .and_then(|(ty, _)| attribute(PanicSource::Message(ty)));
+        #[cfg(feature = "testing")]
+        self.msgs.dispatch_faults(&msg);
+        let journaled = match self.journal {
+            Some(ref journal) => journal.borrow().encode(&mut msg),
+            None => None,
+        };
+        if let Some(sequencer) = self.sequencers.iter().find(|s| s.route(&mut msg)) {
+            sequencer.dispatch(act, ctx);
+        } else if let Some(partitioner) = self.partitioners.iter().find(|p| p.route(&mut msg)) {
+            Rc::clone(partitioner).dispatch(act, ctx);
+        } else {
+            msg.handle(act, ctx);
+        }
+        if let (Some((ty, bytes)), Some(journal)) = (journaled, &self.journal) {
+            journal.borrow_mut().append(&mut msg, ty, &bytes);
+        }
+        self.handling = None;
+    }
+
+    /// Returns the message being handled and the time it was dispatched at.
+    pub(crate) fn handling(&self) -> Attribution {
+        self.handling
+    }
+
+    /// Calls `Actor::mailbox_pressure()` if the pressure level changed since it was last called.
+    fn report_pressure(&mut self, act: &mut A, ctx: &mut A::Context) {
+        let level = self.msgs.pressure();
+        if level != self.pressure {
+            self.pressure = level;
+            act.mailbox_pressure(level, ctx);
+        }
+    }
+
     pub fn poll(&mut self, act: &mut A, ctx: &mut A::Context, task: &mut task::Context<'_>) {
         #[cfg(feature = "mailbox_assert")]
         let mut n_polls = 0u16;
+        let mut budget = self.budget;
+        self.msgs.dispatch_signals(act, ctx);
+        self.report_pressure(act, ctx);
+        self.dispatch_partitions(act, ctx);
 
         while !ctx.waiting() {
-            match Pin::new(&mut self.msgs).poll_next(task) {
-                Poll::Ready(Some(mut msg)) => {
-                    msg.handle(act, ctx);
-                    #[cfg(feature = "mailbox_assert")]
-                    {
-                        n_polls += 1;
-                        // Maximum number of consecutive polls in a loop is 256.
-                        assert!(n_polls < 256u16, "Too many messages are being processed. Use Self::Context::notify() instead of direct use of address");
+            // yield to other tasks once the budget is used up
+            if budget == Some(0) {
+                self.empty = false;
+                task.waker().wake_by_ref();
+                return;
+            }
+            budget = budget.map(|n| n - 1);
+
+            let mut msg = match self.next.take() {
+                Some(msg) => msg,
+                None if self.partitions_full() => {
+                    self.empty = false;
+                    return;
+                }
+                None => match self.poll_limited(task) {
+                    Poll::Ready(Some(msg)) => msg,
+                    Poll::Ready(None) => {
+                        self.dispatch_batch(act, ctx);
+                        return;
                     }
+                    Poll::Pending => {
+                        // wait for more messages until the batch deadline,
+                        // unless no more messages can arrive
+                        if let Some(idx) = self.active {
+                            let connected = self.msgs.connected();
+                            let batcher = &mut self.batchers[idx];
+                            if !connected
+                                || batcher.is_ready()
+                                || batcher.poll_deadline(task).is_ready()
+                            {
+                                self.dispatch_batch(act, ctx);
+                                continue;
+                            }
+                            self.empty = false;
+                        }
+                        return;
+                    }
+                },
+            };
+
+            if self.batchers.is_empty() {
+                self.handle(msg, act, ctx);
+            } else if let Some(idx) = self.collect(&mut msg) {
+                if self.active.map_or(false, |active| active != idx) {
+                    self.dispatch_batch(act, ctx);
+                }
+                self.active = Some(idx);
+                if self.batchers[idx].is_ready() {
+                    self.dispatch_batch(act, ctx);
+                }
+            } else if self.active.is_some() {
+                // preserve order, pending batch goes first
+                self.next = Some(msg);
+                self.dispatch_batch(act, ctx);
+            } else {
+                self.handle(msg, act, ctx);
+            }
+            self.report_pressure(act, ctx);
+
+            #[cfg(feature = "mailbox_assert")]
+            {
+                n_polls += 1;
+                // Maximum number of consecutive polls in a loop is 256.
+                assert!(n_polls < 256u16, "Too many messages are being processed. Use Self::Context::notify() instead of direct use of address");
+            }
+        }
+        // the handler made the actor wait, more messages could be queued
+        self.empty = false;
+    }
+}
+
+/// Collects consecutive messages of a single type for a [`BatchHandler`].
+pub(crate) trait Batcher<A: Actor> {
+    fn msg_type(&self) -> TypeId;
+
+    fn limits(&self) -> (usize, Duration);
+
+    fn set_limits(&mut self, limits: (usize, Duration));
+
+    /// Moves the message out of the envelope if it belongs to this batch.
+    fn collect(&mut self, msg: &mut Envelope<A>) -> bool;
+
+    /// Batch is full or its deadline has passed.
+    fn is_ready(&self) -> bool;
+
+    fn poll_deadline(&mut self, task: &mut task::Context<'_>) -> Poll<()>;
+
+    fn dispatch(&mut self, act: &mut A, ctx: &mut A::Context);
+}
+
+pub(crate) struct MessageBatcher<M> {
+    max_batch: usize,
+    max_delay: Duration,
+    msgs: Vec<M>,
+    deadline: Option<Pin<Box<Timer>>>,
+}
+
+impl<M> MessageBatcher<M> {
+    pub(crate) fn new(max_batch: usize, max_delay: Duration) -> Self {
+        assert!(max_batch > 0, "Batch size must be greater than zero");
+        MessageBatcher {
+            max_batch,
+            max_delay,
+            msgs: Vec::new(),
+            deadline: None,
+        }
+    }
+}
+
+impl<A, M> Batcher<A> for MessageBatcher<M>
+where
+    A: Actor + BatchHandler<M>,
+    A::Context: AsyncContext<A>,
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    fn msg_type(&self) -> TypeId {
+        TypeId::of::<M>()
+    }
+
+    fn limits(&self) -> (usize, Duration) {
+        (self.max_batch, self.max_delay)
+    }
+
+    fn set_limits(&mut self, (max_batch, max_delay): (usize, Duration)) {
+        self.max_batch = max_batch;
+        self.max_delay = max_delay;
+    }
+
+    fn collect(&mut self, msg: &mut Envelope<A>) -> bool {
+        let msg = msg
+            .as_any_mut()
+            .and_then(|proxy| proxy.downcast_mut::<SyncEnvelopeProxy<M>>())
+            .and_then(SyncEnvelopeProxy::take_unanswered);
+
+        match msg {
+            Some(msg) => {
+                if self.msgs.is_empty() {
+                    self.deadline = Some(Box::pin(Timer::after(self.max_delay)));
+                }
+                self.msgs.push(msg);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.msgs.len() >= self.max_batch
+            || self
+                .deadline
+                .as_ref()
+                .map_or(false, |deadline| deadline.is_elapsed())
+    }
+
+    fn poll_deadline(&mut self, task: &mut task::Context<'_>) -> Poll<()> {
+        match self.deadline {
+            Some(ref mut deadline) => deadline.as_mut().poll(task),
+            None => Poll::Ready(()),
+        }
+    }
+
+    fn dispatch(&mut self, act: &mut A, ctx: &mut A::Context) {
+        self.deadline = None;
+        let msgs = std::mem::take(&mut self.msgs);
+        if !msgs.is_empty() {
+            act.handle_batch(msgs, ctx);
+        }
+    }
+}
+
+/// Routes messages of a single type into per-key lanes, see
+/// [`Context::enable_partitioning()`](crate::Context::enable_partitioning).
+pub(crate) trait Partitioner<A: Actor> {
+    fn msg_type(&self) -> TypeId;
+
+    fn set_limits(&self, max_concurrent_keys: usize, idle_timeout: Duration);
+
+    /// Moves the message out of the envelope into its lane if it is partitioned.
+    fn route(&self, msg: &mut Envelope<A>) -> bool;
+
+    /// Starts handlers of queued messages whose keys are idle, up to the concurrency limit.
+    fn dispatch(self: Rc<Self>, act: &mut A, ctx: &mut A::Context);
+
+    /// Number of messages waiting in lanes.
+    fn queued(&self) -> usize;
+
+    /// Number of lanes, including idle ones which are not freed yet.
+    fn lanes(&self) -> usize;
+}
+
+struct Lane<M: Message> {
+    msgs: VecDeque<(M, Option<OneshotSender<M::Result>>)>,
+    /// A handler of the key is in flight.
+    busy: bool,
+    idle_since: Instant,
+}
+
+pub(crate) struct MessagePartitioner<M: Message> {
+    max_concurrent_keys: usize,
+    idle_timeout: Duration,
+    lanes: HashMap<u64, Lane<M>>,
+    /// Keys with queued messages and no handler in flight, in the order they became ready.
+    ready: VecDeque<u64>,
+    in_flight: usize,
+    queued: usize,
+    last_sweep: Instant,
+}
+
+impl<M: Message> MessagePartitioner<M> {
+    pub(crate) fn new(max_concurrent_keys: usize, idle_timeout: Duration) -> Self {
+        assert!(
+            max_concurrent_keys > 0,
+            "Number of concurrent keys must be greater than zero"
+        );
+        MessagePartitioner {
+            max_concurrent_keys,
+            idle_timeout,
+            lanes: HashMap::new(),
+            ready: VecDeque::new(),
+            in_flight: 0,
+            queued: 0,
+            last_sweep: clock::now(),
+        }
+    }
+
+    fn push(&mut self, key: u64, msg: M, tx: Option<OneshotSender<M::Result>>) {
+        let lane = self.lanes.entry(key).or_insert_with(|| Lane {
+            msgs: VecDeque::new(),
+            busy: false,
+            idle_since: clock::now(),
+        });
+        if !lane.busy && lane.msgs.is_empty() {
+            self.ready.push_back(key);
+        }
+        lane.msgs.push_back((msg, tx));
+        self.queued += 1;
+    }
+
+    /// Takes the next message of a ready lane, unless the concurrency limit is reached.
+    #[allow(clippy::type_complexity)]
+    fn next(&mut self) -> Option<(u64, M, Option<OneshotSender<M::Result>>)> {
+        while self.in_flight < self.max_concurrent_keys {
+            let key = self.ready.pop_front()?;
+            let lane = self
+                .lanes
+                .get_mut(&key)
+                .expect("ready lane has no messages");
+            let (msg, tx) = lane.msgs.pop_front().expect("ready lane has no messages");
+            self.queued -= 1;
+
+            // nobody waits for the result anymore
+            if tx.as_ref().map_or(false, |tx| tx.is_closed()) {
+                if lane.msgs.is_empty() {
+                    lane.idle_since = clock::now();
+                } else {
+                    self.ready.push_front(key);
                 }
-                Poll::Ready(None) | Poll::Pending => return,
+                continue;
+            }
+
+            lane.busy = true;
+            self.in_flight += 1;
+            return Some((key, msg, tx));
+        }
+        None
+    }
+
+    /// Marks the handler of the key as completed.
+    fn release(&mut self, key: u64) {
+        self.in_flight -= 1;
+        if let Some(lane) = self.lanes.get_mut(&key) {
+            lane.busy = false;
+            if lane.msgs.is_empty() {
+                lane.idle_since = clock::now();
+            } else {
+                self.ready.push_back(key);
+            }
+        }
+    }
+
+    /// Frees lanes idle for longer than the idle timeout, at most once per idle timeout.
+    fn sweep(&mut self) {
+        let now = clock::now();
+        if now < self.last_sweep + self.idle_timeout {
+            return;
+        }
+        self.last_sweep = now;
+
+        let timeout = self.idle_timeout;
+        self.lanes.retain(|_, lane| {
+            lane.busy || !lane.msgs.is_empty() || now.duration_since(lane.idle_since) < timeout
+        });
+    }
+}
+
+impl<A, M> Partitioner<A> for RefCell<MessagePartitioner<M>>
+where
+    A: Actor + Handler<M, Result = ResponseActFuture<A, M::Result>>,
+    A::Context: AsyncContext<A>,
+    M: Message + Keyed + Send + 'static,
+    M::Result: Send,
+{
+    fn msg_type(&self) -> TypeId {
+        TypeId::of::<M>()
+    }
+
+    fn set_limits(&self, max_concurrent_keys: usize, idle_timeout: Duration) {
+        let mut this = self.borrow_mut();
+        this.max_concurrent_keys = max_concurrent_keys;
+        this.idle_timeout = idle_timeout;
+    }
+
+    fn route(&self, msg: &mut Envelope<A>) -> bool {
+        let msg = msg
+            .as_any_mut()
+            .and_then(|proxy| proxy.downcast_mut::<SyncEnvelopeProxy<M>>())
+            .and_then(SyncEnvelopeProxy::take);
+
+        match msg {
+            Some((msg, tx)) => {
+                self.borrow_mut().push(msg.key(), msg, tx);
+                true
             }
+            None => false,
+        }
+    }
+
+    fn dispatch(self: Rc<Self>, act: &mut A, ctx: &mut A::Context) {
+        self.borrow_mut().sweep();
+
+        // the handler may wait for a future, which holds back the following messages
+        while !ctx.waiting() {
+            let Some((key, msg, tx)) = self.borrow_mut().next() else {
+                break;
+            };
+            let fut = <A as Handler<M>>::handle(act, msg, ctx);
+            ctx.spawn(LaneFuture {
+                fut,
+                tx,
+                key,
+                lanes: Some(Rc::clone(&self)),
+            });
+        }
+    }
+
+    fn queued(&self) -> usize {
+        self.borrow().queued
+    }
+
+    fn lanes(&self) -> usize {
+        self.borrow().lanes.len()
+    }
+}
+
+/// Holds messages of a single type until they are next in sequence, see
+/// [`Context::enable_sequencing()`](crate::Context::enable_sequencing).
+pub(crate) trait Sequencer<A: Actor> {
+    fn msg_type(&self) -> TypeId;
+
+    fn set_max_gap_buffer(&self, max_gap_buffer: usize);
+
+    fn set_gap_policy(&self, policy: SequenceGapPolicy);
+
+    /// Moves the message into the reorder buffer if it is sequenced.
+    fn route(&self, msg: &mut Envelope<A>) -> bool;
+
+    /// Handles the buffered messages which are next in sequence.
+    fn dispatch(&self, act: &mut A, ctx: &mut A::Context);
+
+    fn stats(&self) -> SequenceStats;
+}
+
+/// Gap in the sequence of messages of a type, see
+/// [`Context::enable_sequencing()`](crate::Context::enable_sequencing).
+///
+/// Sent to the recipient of the [`SequenceGapPolicy`] once the reorder buffer is full while a
+/// message is missing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceGap {
+    /// Type name of the sequenced message.
+    pub message: &'static str,
+    /// Sequence number of the first missing message.
+    pub expected: u64,
+    /// Sequence number of the message which did not fit into the reorder buffer.
+    pub received: u64,
+}
+
+impl fmt::Display for SequenceGap {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "Missing {} #{}, received #{}",
+            self.message, self.expected, self.received
+        )
+    }
+}
+
+impl std::error::Error for SequenceGap {}
+
+impl Message for SequenceGap {
+    type Result = ();
+}
+
+/// What a sequenced mailbox does once its reorder buffer is full while a message is missing.
+#[derive(Debug, Default)]
+pub enum SequenceGapPolicy {
+    /// Skips the missing messages and handles the buffered ones in order, starting with the
+    /// lowest sequence number.
+    #[default]
+    Skip,
+    /// Skips the missing messages like [`Skip`](Self::Skip) and sends the gap to the recipient,
+    /// e.g. to the actor itself.
+    SkipAndNotify(Recipient<SequenceGap>),
+    /// Keeps waiting for the missing message. The message which does not fit into the buffer is
+    /// dropped, failing its request, and the gap is sent to the recipient.
+    Reject(Recipient<SequenceGap>),
+}
+
+/// Pressure level of a mailbox with watermarks, see
+/// [`Context::set_mailbox_watermarks()`](crate::Context::set_mailbox_watermarks).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PressureLevel {
+    /// The number of queued messages has reached the high watermark, and has not dropped back
+    /// to the low watermark since.
+    Rising,
+    /// The number of queued messages has dropped back to the low watermark, or never reached
+    /// the high watermark.
+    #[default]
+    Falling,
+}
+
+/// State of the sequencing of a message type, see
+/// [`Context::sequence_stats()`](crate::Context::sequence_stats).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SequenceStats {
+    /// Sequence number of the next message to handle.
+    pub expected: u64,
+    /// Number of messages held in the reorder buffer.
+    pub buffered: usize,
+}
+
+/// Buffered message along with the sender of its reply.
+type Pending<M> = (M, Option<OneshotSender<<M as Message>::Result>>);
+
+pub(crate) struct MessageSequencer<M: Message> {
+    expected: u64,
+    max_gap_buffer: usize,
+    policy: SequenceGapPolicy,
+    buffer: BTreeMap<u64, Pending<M>>,
+}
+
+impl<M: Message + Send> MessageSequencer<M>
+where
+    M::Result: Send,
+{
+    pub(crate) fn new(start_seq: u64, max_gap_buffer: usize) -> Self {
+        MessageSequencer {
+            expected: start_seq,
+            max_gap_buffer,
+            policy: SequenceGapPolicy::Skip,
+            buffer: BTreeMap::new(),
+        }
+    }
+
+    fn push(&mut self, seq: u64, msg: M, tx: Option<OneshotSender<M::Result>>) {
+        // late or duplicate messages are dropped, failing their requests
+        if seq < self.expected || self.buffer.contains_key(&seq) {
+            return;
+        }
+
+        if seq != self.expected && self.buffer.len() >= self.max_gap_buffer {
+            let gap = SequenceGap {
+                message: type_name::<M>(),
+                expected: self.expected,
+                received: seq,
+            };
+            match self.policy {
+                SequenceGapPolicy::Skip => {}
+                SequenceGapPolicy::SkipAndNotify(ref recipient) => recipient.do_send(gap),
+                SequenceGapPolicy::Reject(ref recipient) => {
+                    recipient.do_send(gap);
+                    return;
+                }
+            }
+            log::warn!("{}, skipping the missing messages", gap);
+            self.buffer.insert(seq, (msg, tx));
+            self.expected = self.buffer.keys().next().copied().unwrap_or(seq);
+            return;
+        }
+
+        self.buffer.insert(seq, (msg, tx));
+    }
+
+    /// Takes the buffered message which is next in sequence.
+    fn next(&mut self) -> Option<Pending<M>> {
+        let entry = self.buffer.first_entry()?;
+        if *entry.key() != self.expected {
+            return None;
+        }
+        self.expected += 1;
+        Some(entry.remove())
+    }
+}
+
+impl<A, M> Sequencer<A> for RefCell<MessageSequencer<M>>
+where
+    A: Actor + Handler<M>,
+    A::Context: AsyncContext<A>,
+    M: Message + Sequenced + Send + 'static,
+    M::Result: Send,
+{
+    fn msg_type(&self) -> TypeId {
+        TypeId::of::<M>()
+    }
+
+    fn set_max_gap_buffer(&self, max_gap_buffer: usize) {
+        self.borrow_mut().max_gap_buffer = max_gap_buffer;
+    }
+
+    fn set_gap_policy(&self, policy: SequenceGapPolicy) {
+        self.borrow_mut().policy = policy;
+    }
+
+    fn route(&self, msg: &mut Envelope<A>) -> bool {
+        let msg = msg
+            .as_any_mut()
+            .and_then(|proxy| proxy.downcast_mut::<SyncEnvelopeProxy<M>>())
+            .and_then(SyncEnvelopeProxy::take);
+
+        match msg {
+            Some((msg, tx)) => {
+                self.borrow_mut().push(msg.seq(), msg, tx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn dispatch(&self, act: &mut A, ctx: &mut A::Context) {
+        // the handler may wait for a future, which holds back the following messages
+        while !ctx.waiting() {
+            let Some((msg, tx)) = self.borrow_mut().next() else {
+                break;
+            };
+            // nobody waits for the result anymore
+            if tx.as_ref().map_or(false, |tx| tx.is_closed()) {
+                continue;
+            }
+            let fut = <A as Handler<M>>::handle(act, msg, ctx);
+            fut.handle(ctx, tx);
+        }
+    }
+
+    fn stats(&self) -> SequenceStats {
+        let this = self.borrow();
+        SequenceStats {
+            expected: this.expected,
+            buffered: this.buffer.len(),
+        }
+    }
+}
+
+/// Handler of a partitioned message in flight, releases its key once completed or dropped.
+struct LaneFuture<A, M>
+where
+    A: Actor,
+    M: Message,
+{
+    fut: ResponseActFuture<A, M::Result>,
+    tx: Option<OneshotSender<M::Result>>,
+    key: u64,
+    lanes: Option<Rc<RefCell<MessagePartitioner<M>>>>,
+}
+
+impl<A, M> ActorFuture<A> for LaneFuture<A, M>
+where
+    A: Actor + Handler<M, Result = ResponseActFuture<A, M::Result>>,
+    A::Context: AsyncContext<A>,
+    M: Message + Keyed + Send + 'static,
+    M::Result: Send,
+{
+    type Output = ();
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut task::Context<'_>,
+    ) -> Poll<()> {
+        let this = self.get_mut();
+        let res = ready!(this.fut.as_mut().poll(act, ctx, task));
+        if let Some(tx) = this.tx.take() {
+            let _ = tx.send(res);
+        }
+        if let Some(lanes) = this.lanes.take() {
+            lanes.borrow_mut().release(this.key);
+            Partitioner::<A>::dispatch(lanes, act, ctx);
+        }
+        Poll::Ready(())
+    }
+}
+
+impl<A, M> Drop for LaneFuture<A, M>
+where
+    A: Actor,
+    M: Message,
+{
+    fn drop(&mut self) {
+        // cancelled, e.g. by a restart; queued messages of the key are started by the mailbox
+        if let Some(lanes) = self.lanes.take() {
+            lanes.borrow_mut().release(self.key);
         }
     }
 }
@@ -0,0 +1,171 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use actix_rt::System;
+use once_cell::sync::Lazy;
+use parking_lot::{const_mutex, Mutex, RwLock};
+
+use crate::{
+    actor::{Actor, AsyncContext},
+    clock,
+    fut::WrapFuture,
+    handler::{Handler, Message, MessageResponse, OneshotSender},
+    queue,
+};
+
+/// Middleware of a message type, registered with
+/// [`SystemExt::register_message_middleware()`](crate::SystemExt::register_message_middleware).
+///
+/// The middleware sees every message of its type sent to any actor of the system, so
+/// cross-cutting concerns like timing or tagging queries do not need to be set up on each
+/// actor.
+#[allow(unused_variables)]
+pub trait MessageMiddleware<M: Message>: Send + Sync + 'static {
+    /// Called when the message is sent, before it is queued.
+    ///
+    /// Returning a [`Rejection`] drops the message without invoking its handler, requests
+    /// fail with [`MailboxError::Rejected`](crate::MailboxError::Rejected).
+    fn before(&self, msg: &M) -> Option<Rejection> {
+        None
+    }
+
+    /// Called with the result of the handler, along with the time from the start of the
+    /// handler to the result.
+    ///
+    /// Results produced asynchronously, e.g. by a [`ResponseFuture`](crate::ResponseFuture),
+    /// are seen once they are ready. Results of messages sent with `do_send()` are seen too,
+    /// even though they are dropped afterwards.
+    fn after(&self, result: &M::Result, elapsed: Duration) {}
+}
+
+/// Reason of a message rejected by a [`MessageMiddleware`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rejection {
+    reason: &'static str,
+}
+
+impl Rejection {
+    /// Creates a rejection with the given reason.
+    pub fn new(reason: &'static str) -> Self {
+        Rejection { reason }
+    }
+
+    /// Returns the reason of the rejection.
+    pub fn reason(&self) -> &'static str {
+        self.reason
+    }
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(self.reason)
+    }
+}
+
+/// Middleware of a message type, in the order of registration.
+type Chain<M> = Vec<Arc<dyn MessageMiddleware<M>>>;
+
+/// Chains of the systems, keyed by system id and message type. Registrations replace the
+/// snapshot, which senders and actors read without holding the lock.
+type Registry = HashMap<(usize, TypeId), Arc<dyn Any + Send + Sync>>;
+
+static REGISTRY: Lazy<RwLock<Arc<Registry>>> = Lazy::new(Default::default);
+
+/// Serializes registrations, so none of them is lost while the snapshot is replaced.
+static REGISTERING: Mutex<()> = const_mutex(());
+
+/// Number of registered middleware, so the common case without any costs a single load.
+static REGISTERED: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn register<M>(system: usize, middleware: Box<dyn MessageMiddleware<M>>)
+where
+    M: Message + 'static,
+{
+    let _guard = REGISTERING.lock();
+    let key = (system, TypeId::of::<M>());
+
+    let mut registry = Registry::clone(&REGISTRY.read());
+    let mut chain = registry
+        .get(&key)
+        .and_then(|chain| chain.downcast_ref::<Chain<M>>())
+        .cloned()
+        .unwrap_or_default();
+    chain.push(Arc::from(middleware));
+    registry.insert(key, Arc::new(chain));
+
+    *REGISTRY.write() = Arc::new(registry);
+    REGISTERED.fetch_add(1, Ordering::Release);
+}
+
+/// Returns the middleware of `M` in the system with the given id.
+fn chain<M: Message + 'static>(system: Option<usize>) -> Option<Arc<dyn Any + Send + Sync>> {
+    if REGISTERED.load(Ordering::Acquire) == 0 {
+        return None;
+    }
+    let system = system.or_else(|| System::try_current().map(|sys| sys.id()))?;
+    let registry = Arc::clone(&REGISTRY.read());
+    registry.get(&(system, TypeId::of::<M>())).cloned()
+}
+
+/// Runs the `before()` hooks of `M` in the system with the given id, stopping at the first
+/// rejection.
+pub(crate) fn before<M: Message + 'static>(system: Option<usize>, msg: &M) -> Option<Rejection> {
+    let chain = chain::<M>(system)?;
+    let chain = chain.downcast_ref::<Chain<M>>()?;
+    chain.iter().find_map(|middleware| middleware.before(msg))
+}
+
+/// Handles `msg`, running the `after()` hooks of `M` in the current system with the result.
+pub(crate) fn handle<A, M>(
+    act: &mut A,
+    ctx: &mut A::Context,
+    msg: M,
+    tx: Option<OneshotSender<M::Result>>,
+) where
+    A: Actor + Handler<M>,
+    A::Context: AsyncContext<A>,
+    M: Message + 'static,
+{
+    let chain = match chain::<M>(None) {
+        Some(chain) => chain,
+        None => return <A as Handler<M>>::handle(act, msg, ctx).handle(ctx, tx),
+    };
+
+    let start = clock::now();
+    let (result_tx, mut result_rx) = queue::oneshot();
+    <A as Handler<M>>::handle(act, msg, ctx).handle(ctx, Some(result_tx));
+
+    let finish = move |result: M::Result| {
+        let elapsed = clock::now().saturating_duration_since(start);
+        if let Some(chain) = chain.downcast_ref::<Chain<M>>() {
+            for middleware in chain {
+                middleware.after(&result, elapsed);
+            }
+        }
+        if let Some(tx) = tx {
+            let _ = tx.send(result);
+        }
+    };
+    match result_rx.try_recv() {
+        Some(result) => finish(result),
+        // the response is still being produced, forward it once it is ready
+        None => {
+            ctx.spawn(
+                async move {
+                    if let Ok(result) = result_rx.await {
+                        finish(result);
+                    }
+                }
+                .into_actor(act),
+            );
+        }
+    }
+}
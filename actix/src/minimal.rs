@@ -0,0 +1,373 @@
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{self, Poll},
+    time::Duration,
+};
+
+use futures_core::stream::Stream;
+
+use crate::{
+    actor::{Actor, ActorContext, ActorState, AsyncContext, ResourceHandle, Running, SpawnHandle},
+    address::{
+        channel::{self, AddressReceiver, AddressSenderProducer},
+        Addr, Envelope, EnvelopeProxy, ToEnvelope,
+    },
+    arbiter::spawn_actor,
+    clock::Instant,
+    config::SystemConfig,
+    contextimpl::Barrier,
+    fut::ActorFuture,
+    handler::{Handler, Message, OneshotSender},
+};
+
+/// A slim execution context, for large numbers of actors which only handle messages.
+///
+/// Selected with `type Context = MinimalContext<Self>`, the actor goes through the same
+/// lifecycle as with [`Context`](crate::Context) and its handlers are dispatched the same way,
+/// but the context only keeps the actor's mailbox and state: it has no spawned futures, wait
+/// futures, timers, attached resources nor deferred functions.
+///
+/// On a 64-bit target with default features, an idle actor holds about 1.6 KB with `Context`
+/// and 0.6 KB with `MinimalContext`, mostly its mailbox channel and its task, as printed by
+/// `benches/memory.rs`.
+///
+/// # Panics
+///
+/// The methods of [`AsyncContext`] which spawn a future into the context panic, including
+/// [`spawn()`](AsyncContext::spawn), [`wait()`](AsyncContext::wait),
+/// [`notify()`](AsyncContext::notify), [`run_later()`](AsyncContext::run_later),
+/// [`add_stream()`](AsyncContext::add_stream) and [`set_timer()`](AsyncContext::set_timer), as
+/// does returning a [`ResponseActFuture`](crate::ResponseActFuture) from a handler. Handlers
+/// can still reply with a [`ResponseFuture`](crate::ResponseFuture), which runs on the arbiter.
+///
+/// The registry and supervisors restart their actors in place, services and supervised actors
+/// need a `Context`. Addresses, recipients and subscriptions work with either context.
+///
+/// ```
+/// use actix::prelude::*;
+/// use actix::MinimalContext;
+///
+/// struct Cell(bool);
+///
+/// impl Actor for Cell {
+///     type Context = MinimalContext<Self>;
+/// }
+///
+/// #[derive(Message)]
+/// #[rtype(result = "bool")]
+/// struct Toggle;
+///
+/// impl Handler<Toggle> for Cell {
+///     type Result = bool;
+///
+///     fn handle(&mut self, _: Toggle, _: &mut Self::Context) -> bool {
+///         self.0 = !self.0;
+///         self.0
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let cells: Vec<_> = (0..1000)
+///         .map(|_| MinimalContext::new().run(Cell(false)))
+///         .collect();
+///     assert!(cells[0].send(Toggle).await.unwrap());
+/// }
+/// ```
+pub struct MinimalContext<A>
+where
+    A: Actor<Context = MinimalContext<A>>,
+{
+    addr: AddressSenderProducer<A>,
+    rx: AddressReceiver<A>,
+    state: ActorState,
+}
+
+impl<A: Actor<Context = MinimalContext<A>>> fmt::Debug for MinimalContext<A> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("MinimalContext")
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+impl<A> MinimalContext<A>
+where
+    A: Actor<Context = Self>,
+{
+    /// Creates a context with a mailbox of the configured default capacity.
+    #[inline]
+    pub fn new() -> Self {
+        let (_, rx) = channel::channel(SystemConfig::current().get_mailbox_capacity());
+        Self::with_receiver(rx)
+    }
+
+    #[inline]
+    pub fn with_receiver(rx: AddressReceiver<A>) -> Self {
+        MinimalContext {
+            addr: rx.sender_producer(),
+            rx,
+            state: ActorState::Running,
+        }
+    }
+
+    /// Starts the actor on the current arbiter.
+    #[inline]
+    pub fn run(self, act: A) -> Addr<A> {
+        let fut = self.into_future(act);
+        let addr = fut.ctx.address();
+        spawn_actor(fut);
+        addr
+    }
+
+    pub fn into_future(self, act: A) -> MinimalContextFut<A> {
+        MinimalContextFut {
+            ctx: self,
+            act,
+            started: false,
+            done: false,
+        }
+    }
+
+    /// Sets the mailbox capacity.
+    ///
+    /// The default mailbox capacity is 16 messages.
+    pub fn set_mailbox_capacity(&mut self, cap: usize) {
+        self.addr.set_capacity(cap)
+    }
+
+    fn unsupported(&self, method: &str) -> ! {
+        panic!(
+            "MinimalContext::{}() is not supported, {} needs a Context",
+            method,
+            std::any::type_name::<A>()
+        )
+    }
+}
+
+impl<A> Default for MinimalContext<A>
+where
+    A: Actor<Context = Self>,
+{
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A> ActorContext for MinimalContext<A>
+where
+    A: Actor<Context = Self>,
+{
+    fn stop(&mut self) {
+        if self.state == ActorState::Running {
+            self.state = ActorState::Stopping;
+            self.addr.publish_state(ActorState::Stopping);
+        }
+    }
+
+    fn terminate(&mut self) {
+        self.state = ActorState::Stopped;
+    }
+
+    #[inline]
+    fn state(&self) -> ActorState {
+        self.state
+    }
+}
+
+impl<A> AsyncContext<A> for MinimalContext<A>
+where
+    A: Actor<Context = Self>,
+{
+    #[inline]
+    fn address(&self) -> Addr<A> {
+        Addr::new(self.addr.sender())
+    }
+
+    fn spawn<F>(&mut self, _: F) -> SpawnHandle
+    where
+        F: ActorFuture<A, Output = ()> + 'static,
+    {
+        self.unsupported("spawn")
+    }
+
+    fn wait<F>(&mut self, _: F)
+    where
+        F: ActorFuture<A, Output = ()> + 'static,
+    {
+        self.unsupported("wait")
+    }
+
+    #[inline]
+    fn waiting(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn cancel_future(&mut self, _: SpawnHandle) -> bool {
+        false
+    }
+
+    fn attach_resource<R: 'static>(&mut self, _: R) -> ResourceHandle<R> {
+        self.unsupported("attach_resource")
+    }
+
+    fn set_timer<M>(&mut self, _: &'static str, _: Duration, _: M)
+    where
+        A: Handler<M>,
+        M: Message + 'static,
+    {
+        self.unsupported("set_timer")
+    }
+
+    #[inline]
+    fn cancel_timer(&mut self, _: &'static str) -> bool {
+        false
+    }
+
+    #[inline]
+    fn timer_remaining(&self, _: &'static str) -> Option<Duration> {
+        None
+    }
+
+    #[inline]
+    fn interrupted(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn clear_interrupt(&mut self) {}
+
+    fn barrier(&mut self) -> Barrier<A> {
+        self.unsupported("barrier")
+    }
+
+    #[inline]
+    fn request_deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    fn defer_fn<F>(&mut self, _: F)
+    where
+        F: FnOnce(&mut A, &mut Self) + 'static,
+    {
+        self.unsupported("defer_fn")
+    }
+
+    fn handoff_to(&mut self, target: Addr<A>) {
+        self.rx.handoff(target.into_sender());
+        self.stop();
+    }
+}
+
+impl<A, M> ToEnvelope<A, M> for MinimalContext<A>
+where
+    A: Actor<Context = MinimalContext<A>> + Handler<M>,
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    fn pack(msg: M, tx: Option<OneshotSender<M::Result>>) -> Envelope<A> {
+        Envelope::new(msg, tx)
+    }
+}
+
+/// Future which runs an actor in a [`MinimalContext`].
+pub struct MinimalContextFut<A>
+where
+    A: Actor<Context = MinimalContext<A>>,
+{
+    ctx: MinimalContext<A>,
+    act: A,
+    started: bool,
+    done: bool,
+}
+
+impl<A: Actor<Context = MinimalContext<A>>> fmt::Debug for MinimalContextFut<A> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("MinimalContextFut")
+            .field("ctx", &self.ctx)
+            .finish()
+    }
+}
+
+impl<A> Future for MinimalContextFut<A>
+where
+    A: Actor<Context = MinimalContext<A>>,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+
+        if !this.started {
+            this.started = true;
+            this.ctx.addr.publish_state(ActorState::Started);
+            Actor::started(&mut this.act, &mut this.ctx);
+            this.ctx.addr.publish_state(this.ctx.state);
+        }
+
+        loop {
+            match this.ctx.state {
+                ActorState::Started | ActorState::Running => {
+                    if let Some(signals) = this.ctx.rx.raised_signals() {
+                        signals.dispatch(&mut this.act, &mut this.ctx);
+                        // a handler may have stopped the actor
+                        continue;
+                    }
+                    match Pin::new(&mut this.ctx.rx).poll_next(cx) {
+                        Poll::Ready(Some(mut env)) => {
+                            #[cfg(feature = "testing")]
+                            this.ctx.rx.dispatch_faults(&env);
+                            env.handle(&mut this.act, &mut this.ctx)
+                        }
+                        // stop once no addresses are left
+                        Poll::Ready(None) | Poll::Pending if this.ctx.rx.connected() => {
+                            return Poll::Pending
+                        }
+                        Poll::Ready(None) | Poll::Pending => this.ctx.stop(),
+                    }
+                }
+                ActorState::Stopping => {
+                    if Actor::stopping(&mut this.act, &mut this.ctx) == Running::Stop {
+                        this.ctx.state = ActorState::Stopped;
+                    } else if this.ctx.state == ActorState::Stopping {
+                        this.ctx.state = ActorState::Running;
+                        this.ctx.addr.publish_state(ActorState::Running);
+
+                        // nothing could reach the actor anymore
+                        if !this.ctx.rx.connected() {
+                            return Poll::Pending;
+                        }
+                    }
+                }
+                ActorState::Stopped => {
+                    Actor::stopped(&mut this.act, &mut this.ctx);
+                    this.ctx.addr.publish_state(ActorState::Stopped);
+                    this.done = true;
+                    return Poll::Ready(());
+                }
+            }
+        }
+    }
+}
+
+impl<A> Drop for MinimalContextFut<A>
+where
+    A: Actor<Context = MinimalContext<A>>,
+{
+    fn drop(&mut self) {
+        // give the actor a chance to stop, its mailbox is discarded
+        if !self.done {
+            self.ctx.stop();
+            let waker = futures_task::noop_waker();
+            let mut cx = task::Context::from_waker(&waker);
+            let _ = Pin::new(&mut *self).poll(&mut cx);
+        }
+        if !self.done {
+            self.act.abandoned();
+        }
+    }
+}
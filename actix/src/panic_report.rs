@@ -0,0 +1,132 @@
+#[cfg(feature = "backtrace")]
+use std::backtrace::Backtrace;
+use std::{any::type_name, cell::RefCell, fmt, sync::Arc};
+
+use crate::clock::{self, Instant};
+
+thread_local!(
+    /// Report of the actor whose resources are being released after a panic.
+    static RELEASING: RefCell<Option<Arc<PanicReport>>> = const { RefCell::new(None) };
+);
+
+#[cfg(feature = "backtrace")]
+thread_local!(
+    /// Backtrace of the last panic on this thread.
+    static LAST_BACKTRACE: RefCell<Option<Backtrace>> = const { RefCell::new(None) };
+);
+
+/// What an actor was running when it panicked, see [`PanicReport`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PanicSource {
+    /// A handler of the message type with this name.
+    Message(&'static str),
+    /// The named timer, set with [`AsyncContext::set_timer()`](crate::AsyncContext::set_timer).
+    Timer(&'static str),
+}
+
+impl fmt::Display for PanicSource {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PanicSource::Message(ty) => write!(fmt, "message {}", ty),
+            PanicSource::Timer(name) => write!(fmt, "timer {:?}", name),
+        }
+    }
+}
+
+/// Message or timer an actor is running, along with the time it was started.
+pub(crate) type Attribution = Option<(PanicSource, Instant)>;
+
+pub(crate) fn attribute(source: PanicSource) -> Attribution {
+    Some((source, clock::now()))
+}
+
+/// Report of a panicked actor, passed to
+/// [`Actor::handler_panicked()`](crate::Actor::handler_panicked) and to the actors linked with
+/// it in their [`LinkedExit`](crate::LinkedExit).
+///
+/// The panic is attributed to the message or named timer the actor was running. Panics raised
+/// anywhere else, e.g. in `started()` or in a spawned future, are not attributed.
+#[derive(Debug)]
+pub struct PanicReport {
+    actor: &'static str,
+    attribution: Attribution,
+    #[cfg(feature = "backtrace")]
+    backtrace: Option<Backtrace>,
+}
+
+impl PanicReport {
+    pub(crate) fn new<A>(attribution: Attribution) -> Self {
+        PanicReport {
+            actor: type_name::<A>(),
+            attribution,
+            #[cfg(feature = "backtrace")]
+            backtrace: LAST_BACKTRACE.with(|b| b.borrow_mut().take()),
+        }
+    }
+
+    /// Returns the type name of the panicked actor.
+    pub fn actor(&self) -> &'static str {
+        self.actor
+    }
+
+    /// Returns the message or timer the actor was running when it panicked.
+    pub fn source(&self) -> Option<PanicSource> {
+        self.attribution.map(|(source, _)| source)
+    }
+
+    /// Returns the time at which the actor started to run the source of the panic.
+    pub fn started_at(&self) -> Option<Instant> {
+        self.attribution.map(|(_, at)| at)
+    }
+
+    /// Returns the backtrace of the panic.
+    ///
+    /// Backtraces are captured by a panic hook, which is installed along with the previous
+    /// hook once the first actor is started.
+    #[cfg(feature = "backtrace")]
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_ref()
+    }
+}
+
+impl fmt::Display for PanicReport {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{} panicked", self.actor)?;
+        if let Some(source) = self.source() {
+            write!(fmt, " in {}", source)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `f`, which releases the resources of the actor of `report`.
+pub(crate) fn releasing<R>(report: &Arc<PanicReport>, f: impl FnOnce() -> R) -> R {
+    RELEASING.with(|r| *r.borrow_mut() = Some(Arc::clone(report)));
+    let res = f();
+    RELEASING.with(|r| r.borrow_mut().take());
+    res
+}
+
+/// Returns the report of the panicked actor whose resources are being released.
+pub(crate) fn current() -> Option<Arc<PanicReport>> {
+    RELEASING.with(|r| r.borrow().clone())
+}
+
+/// Installs the panic hook capturing backtraces, once.
+#[cfg(feature = "backtrace")]
+pub(crate) fn install_hook() {
+    use std::{panic, sync::Once};
+
+    static HOOK: Once = Once::new();
+
+    HOOK.call_once(|| {
+        let prev = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let backtrace = Backtrace::force_capture();
+            // the thread local is gone if the thread exits
+            let _ = LAST_BACKTRACE.try_with(|b| *b.borrow_mut() = Some(backtrace));
+            prev(info);
+        }));
+    });
+}
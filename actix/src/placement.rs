@@ -0,0 +1,239 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+    fmt,
+    future::pending,
+    hash::{Hash, Hasher},
+    sync::{Arc, Weak},
+};
+
+use actix_rt::ArbiterHandle;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::{
+    actor::Actor,
+    address::Addr,
+    arbiter::{ArbiterError, ArbiterHandleExt},
+    context::Context,
+};
+
+/// Number of points of each arbiter on the hash ring of [`PlacementStrategy::ByKey`].
+const RING_POINTS: u64 = 64;
+
+/// Placement of each running system.
+static PLACEMENTS: Lazy<Mutex<HashMap<usize, Placement>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// How a [`Placement`] chooses the arbiter of an actor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlacementStrategy {
+    /// Use the arbiters in turn.
+    RoundRobin,
+
+    /// Use the arbiter running the fewest actors, see [`ArbiterHandleExt::actor_count()`].
+    LeastActors,
+
+    /// Use the arbiter the key hashes to, so actors started with the same key run on the same
+    /// arbiter.
+    ///
+    /// Keys are hashed consistently: as long as the arbiters of the placement do not change, a
+    /// key always maps to the same arbiter, and an arbiter joining or leaving only moves the
+    /// keys it takes over or leaves behind.
+    ByKey(u64),
+}
+
+/// Arbiters of a system on which actors are started according to a [`PlacementStrategy`],
+/// returned by [`SystemExt::placement()`](crate::SystemExt::placement).
+///
+/// Arbiters join the placement with [`add()`](Self::add) and leave it once they stop. Actors
+/// are started on the system arbiter as long as no arbiter joined.
+///
+/// ```
+/// use actix::{prelude::*, PlacementStrategy, SystemExt};
+///
+/// struct Connection;
+///
+/// impl Actor for Connection {
+///     type Context = Context<Self>;
+/// }
+///
+/// # fn main() {
+/// # let sys = System::new();
+/// # sys.block_on(async {
+/// let placement = System::current().placement();
+/// for _ in 0..4 {
+///     placement.add(&Arbiter::new().handle());
+/// }
+///
+/// let _conn = placement
+///     .start(PlacementStrategy::ByKey(42), |_| Connection)
+///     .await
+///     .unwrap();
+/// # System::current().stop();
+/// # });
+/// # sys.run().unwrap();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Placement {
+    fallback: ArbiterHandle,
+    view: Arc<Mutex<View>>,
+}
+
+#[derive(Default)]
+struct View {
+    next_id: u64,
+    next_turn: usize,
+    arbiters: BTreeMap<u64, ArbiterHandle>,
+    /// Points of the hash ring, mapped to the id of their arbiter.
+    ring: BTreeMap<u64, u64>,
+}
+
+/// Membership of an arbiter, held by a task of the arbiter which is dropped once it stops.
+struct Member {
+    view: Weak<Mutex<View>>,
+    id: u64,
+}
+
+impl Placement {
+    /// Returns the placement of the system with the given id.
+    pub(crate) fn of(system: usize, fallback: &ArbiterHandle) -> Placement {
+        PLACEMENTS
+            .lock()
+            .entry(system)
+            .or_insert_with(|| Placement {
+                fallback: fallback.clone(),
+                view: Arc::default(),
+            })
+            .clone()
+    }
+
+    /// Adds an arbiter to the placement, until it stops.
+    ///
+    /// An arbiter added more than once gets a larger share of the actors.
+    pub fn add(&self, arbiter: &ArbiterHandle) {
+        let id = self.view.lock().insert(arbiter.clone());
+        let member = Member {
+            view: Arc::downgrade(&self.view),
+            id,
+        };
+        // the member is dropped right away if the arbiter has already stopped
+        arbiter.spawn(async move {
+            let _member = member;
+            pending::<()>().await
+        });
+    }
+
+    /// Returns the number of arbiters of the placement.
+    pub fn len(&self) -> usize {
+        self.view.lock().arbiters.len()
+    }
+
+    /// Returns whether no arbiter joined the placement, or all of them have stopped.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Starts an actor created by `factory` on the arbiter chosen by `strategy`, resolving with
+    /// its address once it was created.
+    ///
+    /// Fails with [`ArbiterError::Stopped`] if the chosen arbiter stops before starting the
+    /// actor.
+    pub async fn start<A, F>(
+        &self,
+        strategy: PlacementStrategy,
+        factory: F,
+    ) -> Result<Addr<A>, ArbiterError>
+    where
+        A: Actor<Context = Context<A>>,
+        F: FnOnce(&mut Context<A>) -> A + Send + 'static,
+    {
+        let arbiter = match strategy {
+            PlacementStrategy::LeastActors => self.least_actors().await,
+            strategy => self.view.lock().choose(strategy),
+        };
+        arbiter
+            .unwrap_or_else(|| self.fallback.clone())
+            .spawn_actor(factory)
+            .await
+    }
+
+    async fn least_actors(&self) -> Option<ArbiterHandle> {
+        let arbiters: Vec<_> = self.view.lock().arbiters.values().cloned().collect();
+        // all arbiters are asked before awaiting the first answer
+        let counts: Vec<_> = arbiters.iter().map(|arb| arb.actor_count()).collect();
+
+        let mut least = None;
+        for (arbiter, count) in arbiters.into_iter().zip(counts) {
+            if let Ok(count) = count.await {
+                if least.as_ref().map_or(true, |(least, _)| count < *least) {
+                    least = Some((count, arbiter));
+                }
+            }
+        }
+        least.map(|(_, arbiter)| arbiter)
+    }
+}
+
+impl fmt::Debug for Placement {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Placement")
+            .field("arbiters", &self.len())
+            .finish()
+    }
+}
+
+impl View {
+    fn insert(&mut self, arbiter: ArbiterHandle) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.arbiters.insert(id, arbiter);
+        for point in 0..RING_POINTS {
+            self.ring.insert(hash((id, point)), id);
+        }
+        id
+    }
+
+    fn remove(&mut self, id: u64) {
+        self.arbiters.remove(&id);
+        self.ring.retain(|_, arbiter| *arbiter != id);
+    }
+
+    fn choose(&mut self, strategy: PlacementStrategy) -> Option<ArbiterHandle> {
+        let id = match strategy {
+            PlacementStrategy::ByKey(key) => {
+                let point = hash(key);
+                let (_, id) = self
+                    .ring
+                    .range(point..)
+                    .next()
+                    .or_else(|| self.ring.iter().next())?;
+                *id
+            }
+            _ => {
+                let turn = self.next_turn;
+                self.next_turn = self.next_turn.wrapping_add(1);
+                *self
+                    .arbiters
+                    .keys()
+                    .nth(turn % self.arbiters.len().max(1))?
+            }
+        };
+        self.arbiters.get(&id).cloned()
+    }
+}
+
+impl Drop for Member {
+    fn drop(&mut self) {
+        if let Some(view) = self.view.upgrade() {
+            view.lock().remove(self.id);
+        }
+    }
+}
+
+/// Hashes `value` the same way in every process, unlike the randomly seeded `HashMap` hasher.
+fn hash<T: Hash>(value: T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,166 @@
+//! Channels which can be polled directly within an actor's context.
+//!
+//! The receiving halves implement [`ActorFuture`] and [`ActorStream`], so they can be spawned
+//! into a context or chained with actor combinators without wrapping them first. They also
+//! implement [`Future`] and [`Stream`], and can be awaited outside of actors. Senders are plain
+//! tokio senders: [`Sender`] is cheap to clone and both senders are `Send`.
+//!
+//! ```
+//! use actix::{prelude::*, queue};
+//!
+//! struct MyActor;
+//!
+//! impl Actor for MyActor {
+//!     type Context = Context<Self>;
+//!
+//!     fn started(&mut self, ctx: &mut Self::Context) {
+//!         let (tx, rx) = queue::oneshot();
+//!         tx.send(42).unwrap();
+//!
+//!         rx.map(|res, _act: &mut Self, _ctx| {
+//!             assert_eq!(res.unwrap(), 42);
+//!             System::current().stop();
+//!         })
+//!         .spawn(ctx);
+//!     }
+//! }
+//!
+//! # fn main() {
+//! # let sys = System::new();
+//! # sys.block_on(async { MyActor.start() });
+//! # sys.run().unwrap();
+//! # }
+//! ```
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::stream::Stream;
+use tokio::sync::{mpsc, oneshot};
+pub use tokio::sync::{
+    mpsc::{
+        error::{SendError, TryRecvError, TrySendError},
+        Sender,
+    },
+    oneshot::{error::RecvError, Sender as OneshotSender},
+};
+
+use crate::{
+    actor::Actor,
+    fut::{ActorFuture, ActorStream},
+};
+
+/// Creates a oneshot channel whose receiver is an [`ActorFuture`].
+///
+/// The sender is the same type actors use to reply to messages, see
+/// [`MessageResponse`](crate::dev::MessageResponse).
+pub fn oneshot<T>() -> (OneshotSender<T>, OneshotReceiver<T>) {
+    let (tx, rx) = oneshot::channel();
+    (tx, OneshotReceiver { rx })
+}
+
+/// Creates a bounded channel whose receiver is an [`ActorStream`].
+///
+/// # Panics
+///
+/// Panics if `cap` is zero.
+pub fn channel<T>(cap: usize) -> (Sender<T>, Receiver<T>) {
+    let (tx, rx) = mpsc::channel(cap);
+    (tx, Receiver { rx })
+}
+
+/// Receiving half of [`oneshot()`].
+///
+/// Resolves with an error if the sender is dropped without sending a value.
+pub struct OneshotReceiver<T> {
+    rx: oneshot::Receiver<T>,
+}
+
+impl<T> fmt::Debug for OneshotReceiver<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("OneshotReceiver").finish()
+    }
+}
+
+impl<T> OneshotReceiver<T> {
+    /// Attempts to receive the value without waiting.
+    pub fn try_recv(&mut self) -> Option<T> {
+        self.rx.try_recv().ok()
+    }
+
+    /// Prevents the sender from sending a value.
+    pub fn close(&mut self) {
+        self.rx.close()
+    }
+}
+
+impl<A: Actor, T> ActorFuture<A> for OneshotReceiver<T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        _: &mut A,
+        _: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        Pin::new(&mut self.get_mut().rx).poll(task)
+    }
+}
+
+impl<T> Future for OneshotReceiver<T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, task: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.get_mut().rx).poll(task)
+    }
+}
+
+/// Receiving half of [`channel()`].
+///
+/// The stream ends once all senders are dropped and all queued values are received.
+pub struct Receiver<T> {
+    rx: mpsc::Receiver<T>,
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Receiver").finish()
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Attempts to receive the next value without waiting.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        self.rx.try_recv()
+    }
+
+    /// Closes the receiving half without dropping it, queued values can still be received.
+    pub fn close(&mut self) {
+        self.rx.close()
+    }
+}
+
+impl<A: Actor, T> ActorStream<A> for Receiver<T> {
+    type Item = T;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        _: &mut A,
+        _: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.get_mut().rx.poll_recv(task)
+    }
+}
+
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, task: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().rx.poll_recv(task)
+    }
+}
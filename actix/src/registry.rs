@@ -3,15 +3,23 @@
 //! An Actor can register itself as a service. A Service can be defined as an
 //! `ArbiterService`, which is unique per arbiter, or a `SystemService`, which
 //! is unique per system.
+//!
+//! Services are started under a [`Supervisor`], so the address stored in the
+//! registry stays valid when the service actor restarts. If the service does go
+//! away (for example, its task panicked), the stale entry is detected on the
+//! next lookup and a fresh service is started in its place.
 use std::{
-    any::{Any, TypeId},
-    cell::RefCell,
+    any::{type_name, Any, TypeId},
+    cell::{Cell, RefCell},
     collections::HashMap,
     default::Default,
+    fmt,
     rc::Rc,
+    time::SystemTime,
 };
 
 use actix_rt::{ArbiterHandle, System};
+use log::warn;
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
 
@@ -19,10 +27,109 @@ use crate::{
     actor::{Actor, Supervised},
     address::Addr,
     context::Context,
+    logging::arbiter_index,
     supervisor::Supervisor,
 };
 
-type AnyMap = HashMap<TypeId, Box<dyn Any>>;
+type AnyMap = HashMap<TypeId, Entry>;
+
+/// Address stored in a registry.
+trait RegisteredAddr: Send {
+    fn as_any(&self) -> &dyn Any;
+
+    fn connected(&self) -> bool;
+
+    fn clone_box(&self) -> Box<dyn RegisteredAddr>;
+}
+
+impl<A: Actor<Context = Context<A>>> RegisteredAddr for Addr<A> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn connected(&self) -> bool {
+        Addr::connected(self)
+    }
+
+    fn clone_box(&self) -> Box<dyn RegisteredAddr> {
+        Box::new(self.clone())
+    }
+}
+
+/// Registry entry, along with the details reported by `entries()`.
+struct Entry {
+    addr: Box<dyn RegisteredAddr>,
+    type_name: &'static str,
+    started_at: SystemTime,
+    arbiter: usize,
+}
+
+impl Entry {
+    fn new<A: Actor<Context = Context<A>>>(addr: Addr<A>) -> Self {
+        Entry {
+            addr: Box::new(addr),
+            type_name: type_name::<A>(),
+            started_at: SystemTime::now(),
+            arbiter: arbiter_index(),
+        }
+    }
+
+    fn addr<A: Actor<Context = Context<A>>>(&self) -> Option<&Addr<A>> {
+        self.addr.as_any().downcast_ref()
+    }
+
+    /// Copies the entry, so its address can be probed without holding the registry.
+    fn snapshot(&self) -> (Box<dyn RegisteredAddr>, RegistryEntry) {
+        let entry = RegistryEntry {
+            type_name: self.type_name,
+            started_at: self.started_at,
+            arbiter: self.arbiter,
+            connected: false,
+        };
+        (self.addr.clone_box(), entry)
+    }
+}
+
+impl fmt::Debug for Entry {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Entry")
+            .field("type_name", &self.type_name)
+            .field("started_at", &self.started_at)
+            .field("arbiter", &self.arbiter)
+            .finish()
+    }
+}
+
+/// Probes the addresses of registry entries, once the registry is no longer borrowed.
+fn probe(snapshots: Vec<(Box<dyn RegisteredAddr>, RegistryEntry)>) -> Vec<RegistryEntry> {
+    let mut entries: Vec<_> = snapshots
+        .into_iter()
+        .map(|(addr, entry)| RegistryEntry {
+            connected: addr.connected(),
+            ..entry
+        })
+        .collect();
+    entries.sort_by_key(|entry| (entry.started_at, entry.type_name));
+    entries
+}
+
+/// Service registered in a [`Registry`] or [`SystemRegistry`], see [`Registry::entries()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RegistryEntry {
+    /// Type name of the service actor.
+    pub type_name: &'static str,
+    /// Time at which the service was registered.
+    pub started_at: SystemTime,
+    /// Index of the arbiter which registered the service, see
+    /// [`ActorId::arbiter()`](crate::ActorId::arbiter).
+    ///
+    /// Arbiter services run on this arbiter. System services started by the registry run on the
+    /// system arbiter instead.
+    pub arbiter: usize,
+    /// Whether the service actor was still running when the entry was listed.
+    pub connected: bool,
+}
 
 /// Actors registry
 ///
@@ -87,12 +194,14 @@ type AnyMap = HashMap<TypeId, Box<dyn Any>>;
 /// ```
 #[derive(Clone)]
 pub struct Registry {
+    system: Cell<Option<usize>>,
     registry: Rc<RefCell<AnyMap>>,
 }
 
 thread_local! {
     static AREG: Registry = {
         Registry {
+            system: Cell::new(None),
             registry: Rc::new(RefCell::new(AnyMap::new()))
         }
     };
@@ -115,34 +224,57 @@ pub trait ArbiterService: Actor<Context = Context<Self>> + Supervised + Default
 
     /// Get actor's address from arbiter registry
     fn from_registry() -> Addr<Self> {
-        AREG.with(|reg| reg.get())
+        Registry::with_current(|reg| reg.get())
     }
 }
 
 impl Registry {
+    /// Calls `f` with the registry of the current thread.
+    ///
+    /// Entries registered by a system which previously ran on this thread are discarded, so
+    /// systems never see each other's services.
+    fn with_current<R>(f: impl FnOnce(&Registry) -> R) -> R {
+        let id = System::current().id();
+        AREG.with(|reg| {
+            if reg.system.replace(Some(id)) != Some(id) {
+                reg.registry.borrow_mut().clear();
+            }
+            f(reg)
+        })
+    }
+
     /// Query registry for specific actor. Returns address of the actor.
     /// If actor is not registered, starts new actor and
     /// return address of newly created actor.
+    ///
+    /// If the registered actor is no longer running, a new one is started and
+    /// replaces the stale entry.
     pub fn get<A: ArbiterService + Actor<Context = Context<A>>>(&self) -> Addr<A> {
         let id = TypeId::of::<A>();
-        if let Some(addr) = self.registry.borrow().get(&id) {
-            if let Some(addr) = addr.downcast_ref::<Addr<A>>() {
-                return addr.clone();
+        if let Some(entry) = self.registry.borrow().get(&id) {
+            if let Some(addr) = entry.addr::<A>() {
+                if addr.connected() {
+                    return addr.clone();
+                }
+                warn!(
+                    "Arbiter service {} is not running, restarting",
+                    std::any::type_name::<A>()
+                );
             }
         }
         let addr: Addr<A> = A::start_service();
 
         self.registry
             .borrow_mut()
-            .insert(id, Box::new(addr.clone()));
+            .insert(id, Entry::new(addr.clone()));
         addr
     }
 
     /// Check if actor is in registry, if so, return its address
     pub fn query<A: ArbiterService + Actor<Context = Context<A>>>(&self) -> Option<Addr<A>> {
         let id = TypeId::of::<A>();
-        if let Some(addr) = self.registry.borrow().get(&id) {
-            if let Some(addr) = addr.downcast_ref::<Addr<A>>() {
+        if let Some(entry) = self.registry.borrow().get(&id) {
+            if let Some(addr) = entry.addr::<A>() {
                 return Some(addr.clone());
             }
         }
@@ -151,17 +283,44 @@ impl Registry {
 
     /// Add new actor to the registry by address, panic if actor is already running
     pub fn set<A: ArbiterService + Actor<Context = Context<A>>>(addr: Addr<A>) {
-        AREG.with(|reg| {
+        Registry::with_current(|reg| {
             let id = TypeId::of::<A>();
-            if let Some(addr) = reg.registry.borrow().get(&id) {
-                if addr.downcast_ref::<Addr<A>>().is_some() {
+            if let Some(entry) = reg.registry.borrow().get(&id) {
+                if entry.addr::<A>().map_or(false, Addr::connected) {
                     panic!("Actor already started");
                 }
             }
 
-            reg.registry.borrow_mut().insert(id, Box::new(addr));
+            reg.registry.borrow_mut().insert(id, Entry::new(addr));
         })
     }
+
+    /// Lists the services of the current arbiter, ordered by registration time.
+    pub fn entries() -> Vec<RegistryEntry> {
+        let snapshots = Registry::with_current(|reg| {
+            reg.registry
+                .borrow()
+                .values()
+                .map(Entry::snapshot)
+                .collect()
+        });
+        probe(snapshots)
+    }
+
+    /// Removes the service `A` from the registry of the current arbiter, returning its address.
+    ///
+    /// If `stop` is `true`, the service actor is asked to stop once it has handled the messages
+    /// queued so far. Services run under a [`Supervisor`], which restarts them as long as other
+    /// addresses of the service are held.
+    pub fn clear<A: ArbiterService + Actor<Context = Context<A>>>(stop: bool) -> Option<Addr<A>> {
+        let entry =
+            Registry::with_current(|reg| reg.registry.borrow_mut().remove(&TypeId::of::<A>()))?;
+        let addr = entry.addr::<A>()?.clone();
+        if stop {
+            addr.request_stop();
+        }
+        Some(addr)
+    }
 }
 
 /// System wide actors registry
@@ -221,7 +380,7 @@ impl Registry {
 #[derive(Debug)]
 pub struct SystemRegistry {
     system: ArbiterHandle,
-    registry: HashMap<TypeId, Box<dyn Any + Send>>,
+    registry: HashMap<TypeId, Entry>,
 }
 
 static SREG: Lazy<Mutex<HashMap<usize, SystemRegistry>>> = Lazy::new(|| Mutex::new(HashMap::new()));
@@ -250,16 +409,7 @@ pub trait SystemService: Actor<Context = Context<Self>> + Supervised + Default {
             .entry(sys.id())
             .or_insert_with(|| SystemRegistry::new(sys.arbiter().clone()));
 
-        if let Some(addr) = reg.registry.get(&TypeId::of::<Self>()) {
-            if let Some(addr) = addr.downcast_ref::<Addr<Self>>() {
-                return addr.clone();
-            }
-        }
-
-        let addr = Self::start_service(System::current().arbiter());
-        reg.registry
-            .insert(TypeId::of::<Self>(), Box::new(addr.clone()));
-        addr
+        reg.get::<Self>()
     }
 }
 
@@ -273,24 +423,31 @@ impl SystemRegistry {
 
     /// Return address of the service. If service actor is not running
     /// it get started in the system.
+    ///
+    /// A registered service that has since stopped is replaced by a newly
+    /// started one.
     pub fn get<A: SystemService + Actor<Context = Context<A>>>(&mut self) -> Addr<A> {
-        if let Some(addr) = self.registry.get(&TypeId::of::<A>()) {
-            match addr.downcast_ref::<Addr<A>>() {
-                Some(addr) => return addr.clone(),
-                None => panic!("Got unknown value: {:?}", addr),
+        if let Some(entry) = self.registry.get(&TypeId::of::<A>()) {
+            match entry.addr::<A>() {
+                Some(addr) if addr.connected() => return addr.clone(),
+                Some(_) => warn!(
+                    "System service {} is not running, restarting",
+                    std::any::type_name::<A>()
+                ),
+                None => panic!("Got unknown value: {:?}", entry),
             }
         }
 
         let addr = A::start_service(&self.system);
         self.registry
-            .insert(TypeId::of::<A>(), Box::new(addr.clone()));
+            .insert(TypeId::of::<A>(), Entry::new(addr.clone()));
         addr
     }
 
     /// Check if actor is in registry, if so, return its address
     pub fn query<A: SystemService + Actor<Context = Context<A>>>(&self) -> Option<Addr<A>> {
-        if let Some(addr) = self.registry.get(&TypeId::of::<A>()) {
-            match addr.downcast_ref::<Addr<A>>() {
+        if let Some(entry) = self.registry.get(&TypeId::of::<A>()) {
+            match entry.addr::<A>() {
                 Some(addr) => return Some(addr.clone()),
                 None => return None,
             }
@@ -308,12 +465,43 @@ impl SystemRegistry {
             .entry(sys.id())
             .or_insert_with(|| SystemRegistry::new(sys.arbiter().clone()));
 
-        if let Some(addr) = reg.registry.get(&TypeId::of::<A>()) {
-            if addr.downcast_ref::<Addr<A>>().is_some() {
+        if let Some(entry) = reg.registry.get(&TypeId::of::<A>()) {
+            if entry.addr::<A>().map_or(false, Addr::connected) {
                 panic!("Actor already started");
             }
         }
 
-        reg.registry.insert(TypeId::of::<A>(), Box::new(addr));
+        reg.registry.insert(TypeId::of::<A>(), Entry::new(addr));
+    }
+
+    /// Lists the services of the current system, ordered by registration time.
+    ///
+    /// The registry is not locked while the services are probed, so concurrent lookups from
+    /// other arbiters are not blocked.
+    pub fn entries() -> Vec<RegistryEntry> {
+        let snapshots = SREG
+            .lock()
+            .get(&System::current().id())
+            .map(|reg| reg.registry.values().map(Entry::snapshot).collect())
+            .unwrap_or_default();
+        probe(snapshots)
+    }
+
+    /// Removes the service `A` from the registry of the current system, returning its address.
+    ///
+    /// If `stop` is `true`, the service actor is asked to stop once it has handled the messages
+    /// queued so far. Services run under a [`Supervisor`], which restarts them as long as other
+    /// addresses of the service are held.
+    pub fn clear<A: SystemService + Actor<Context = Context<A>>>(stop: bool) -> Option<Addr<A>> {
+        let entry = SREG
+            .lock()
+            .get_mut(&System::current().id())?
+            .registry
+            .remove(&TypeId::of::<A>())?;
+        let addr = entry.addr::<A>()?.clone();
+        if stop {
+            addr.request_stop();
+        }
+        Some(addr)
     }
 }
@@ -0,0 +1,215 @@
+//! Time messages spend in the mailbox of an actor, enabled by the `telemetry` feature.
+
+use std::{
+    cell::RefCell,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+
+use crate::{
+    clock::Instant,
+    logging::{ActorId, ActorLog},
+};
+
+/// Number of buckets of a [`ResidencyHistogram`].
+const BUCKETS: usize = 26;
+
+/// Minimum time between two residency warnings of an actor.
+const WARNING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Residency of the envelope of a request, in nanoseconds plus one, zero until it is dequeued.
+pub(crate) type ResidencySlot = Arc<AtomicU64>;
+
+thread_local!(
+    static SLOT: RefCell<Option<ResidencySlot>> = const { RefCell::new(None) };
+);
+
+/// Runs `send`, returning the residency slot of the request envelope it queued.
+pub(crate) fn tracked<R>(send: impl FnOnce() -> R) -> (R, Option<ResidencySlot>) {
+    let slot = ResidencySlot::default();
+    SLOT.with(|s| *s.borrow_mut() = Some(Arc::clone(&slot)));
+    let res = send();
+    // still set if no envelope was queued
+    let queued = SLOT.with(|s| s.borrow_mut().take()).is_none();
+    (res, queued.then_some(slot))
+}
+
+/// Returns the residency read from `slot`, `None` until its envelope was dequeued.
+pub(crate) fn read(slot: &ResidencySlot) -> Option<Duration> {
+    match slot.load(Ordering::Acquire) {
+        0 => None,
+        nanos => Some(Duration::from_nanos(nanos - 1)),
+    }
+}
+
+/// Time an envelope was queued at, and where its residency is reported.
+pub(crate) struct Stamp {
+    queued_at: Instant,
+    slot: Option<ResidencySlot>,
+}
+
+impl Stamp {
+    pub(crate) fn now() -> Self {
+        Stamp {
+            queued_at: Instant::now(),
+            slot: None,
+        }
+    }
+
+    /// Reports the residency to the request being sent by [`tracked()`], if any.
+    pub(crate) fn track(&mut self) {
+        if let Some(slot) = SLOT.with(|s| s.borrow_mut().take()) {
+            self.slot = Some(slot);
+        }
+    }
+}
+
+/// Histogram of the time messages spent in the mailbox of an actor, see
+/// [`ContextStats`](crate::ContextStats).
+///
+/// Bucket `i` counts the messages which were queued for less than `2^i` microseconds, the
+/// last bucket counts all others.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResidencyHistogram {
+    /// Number of messages taken out of the mailbox.
+    pub count: u64,
+    /// Longest time a message was queued.
+    pub max: Duration,
+    /// Total time the messages were queued.
+    pub total: Duration,
+    buckets: [u64; BUCKETS],
+}
+
+impl ResidencyHistogram {
+    /// Returns the buckets of the histogram, as their upper bound and their count.
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, u64)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .map(|(idx, count)| (upper_bound(idx), *count))
+    }
+
+    /// Returns the mean time messages were queued, `None` if no message was taken out yet.
+    pub fn mean(&self) -> Option<Duration> {
+        let count = u32::try_from(self.count).unwrap_or(u32::MAX);
+        (count > 0).then(|| self.total / count)
+    }
+
+    /// Returns the upper bound of the bucket containing the `pct` percentile, `None` if no
+    /// message was taken out yet.
+    pub fn percentile(&self, pct: u8) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = (self.count * u64::from(pct.min(100)) + 99) / 100;
+        let mut seen = 0;
+        self.buckets().find_map(|(bound, count)| {
+            seen += count;
+            (seen >= rank.max(1)).then_some(bound.min(self.max))
+        })
+    }
+}
+
+fn upper_bound(idx: usize) -> Duration {
+    if idx == BUCKETS - 1 {
+        Duration::MAX
+    } else {
+        Duration::from_micros(1 << idx)
+    }
+}
+
+/// Residency of the messages of a mailbox, recorded as they are taken out.
+pub(crate) struct ResidencyTracker {
+    count: AtomicU64,
+    max: AtomicU64,
+    total: AtomicU64,
+    buckets: [AtomicU64; BUCKETS],
+    /// Residency above which warnings are logged, in nanoseconds, zero if disabled.
+    threshold: AtomicU64,
+    last_warning: Mutex<Option<Instant>>,
+}
+
+impl ResidencyTracker {
+    pub(crate) fn new() -> Self {
+        ResidencyTracker {
+            count: AtomicU64::new(0),
+            max: AtomicU64::new(0),
+            total: AtomicU64::new(0),
+            buckets: Default::default(),
+            threshold: AtomicU64::new(0),
+            last_warning: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn set_threshold(&self, threshold: Option<Duration>) {
+        let nanos = threshold.map_or(0, |t| nanos(t).max(1));
+        self.threshold.store(nanos, Ordering::Relaxed);
+    }
+
+    /// Records the residency of an envelope taken out of the mailbox.
+    ///
+    /// `msg` is the type name of its message, `depth` the number of messages left queued.
+    pub(crate) fn dequeued<A>(
+        &self,
+        stamp: &Stamp,
+        msg: Option<&'static str>,
+        depth: usize,
+        actor: Option<ActorId>,
+    ) {
+        let residency = stamp.queued_at.elapsed();
+        let value = nanos(residency);
+        if let Some(ref slot) = stamp.slot {
+            slot.store(value.saturating_add(1), Ordering::Release);
+        }
+
+        let micros = residency.as_micros();
+        let idx = (u128::BITS - micros.leading_zeros()) as usize;
+        self.buckets[idx.min(BUCKETS - 1)].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total.fetch_add(value, Ordering::Relaxed);
+        self.max.fetch_max(value, Ordering::Relaxed);
+
+        let threshold = self.threshold.load(Ordering::Relaxed);
+        if threshold == 0 || value <= threshold {
+            return;
+        }
+        let now = Instant::now();
+        {
+            let mut last = self.last_warning.lock();
+            if last.map_or(false, |last| now < last + WARNING_INTERVAL) {
+                return;
+            }
+            *last = Some(now);
+        }
+        if let Some(actor) = actor {
+            ActorLog::new::<A>(actor).warn(format_args!(
+                "message {} was queued for {:?}, {} messages left in the mailbox",
+                msg.unwrap_or("<unknown>"),
+                residency,
+                depth
+            ));
+        }
+    }
+
+    pub(crate) fn histogram(&self) -> ResidencyHistogram {
+        let mut buckets = [0; BUCKETS];
+        for (count, bucket) in buckets.iter_mut().zip(&self.buckets) {
+            *count = bucket.load(Ordering::Relaxed);
+        }
+        ResidencyHistogram {
+            count: self.count.load(Ordering::Relaxed),
+            max: Duration::from_nanos(self.max.load(Ordering::Relaxed)),
+            total: Duration::from_nanos(self.total.load(Ordering::Relaxed)),
+            buckets,
+        }
+    }
+}
+
+fn nanos(dur: Duration) -> u64 {
+    u64::try_from(dur.as_nanos()).unwrap_or(u64::MAX)
+}
@@ -0,0 +1,163 @@
+use std::time::Duration;
+
+use crate::{address::RateLimit, config::SystemConfig};
+
+/// Execution settings of an actor, applied when it is started.
+///
+/// Settings are applied before the actor is polled for the first time, so even the first
+/// messages obey them, and can be cloned to start many actors alike. The defaults are taken
+/// from the [`SystemConfig`] of the current system when the settings are created.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use actix::{prelude::*, ActorSettings, StopPolicy};
+///
+/// struct Worker;
+///
+/// impl Actor for Worker {
+///     type Context = Context<Self>;
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let settings = ActorSettings::default()
+///         .mailbox(512)
+///         .budget(32)
+///         .idle_timeout(Duration::from_secs(60))
+///         .stop_policy(StopPolicy::Drain);
+///
+///     let workers: Vec<_> = (0..4).map(|_| Worker.start_with(settings.clone())).collect();
+///     # System::current().stop();
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActorSettings {
+    mailbox_capacity: usize,
+    message_budget: Option<usize>,
+    rate_limit: Option<RateLimit>,
+    finalize_timeout: Duration,
+    idle_timeout: Option<Duration>,
+    stop_policy: StopPolicy,
+}
+
+impl Default for ActorSettings {
+    fn default() -> Self {
+        let config = SystemConfig::current();
+        ActorSettings {
+            mailbox_capacity: config.get_mailbox_capacity(),
+            message_budget: config.get_message_budget(),
+            rate_limit: None,
+            finalize_timeout: config.get_shutdown_timeout(),
+            idle_timeout: None,
+            stop_policy: StopPolicy::Discard,
+        }
+    }
+}
+
+impl ActorSettings {
+    /// Sets the mailbox capacity, see [`Context::set_mailbox_capacity()`].
+    ///
+    /// [`Context::set_mailbox_capacity()`]: crate::Context::set_mailbox_capacity
+    pub fn mailbox(mut self, cap: usize) -> Self {
+        self.mailbox_capacity = cap;
+        self
+    }
+
+    /// Sets the maximum number of messages handled before yielding, `None` if unlimited. See
+    /// [`Context::set_message_budget()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `budget` is zero.
+    ///
+    /// [`Context::set_message_budget()`]: crate::Context::set_message_budget
+    pub fn budget(mut self, budget: impl Into<Option<usize>>) -> Self {
+        let budget = budget.into();
+        assert!(
+            budget != Some(0),
+            "Message budget must be greater than zero"
+        );
+        self.message_budget = budget;
+        self
+    }
+
+    /// Limits the rate of handled messages, see [`Context::set_rate_limit()`].
+    ///
+    /// [`Context::set_rate_limit()`]: crate::Context::set_rate_limit
+    pub fn rate_limit(mut self, limit: impl Into<Option<RateLimit>>) -> Self {
+        self.rate_limit = limit.into();
+        self
+    }
+
+    /// Sets how long [`Actor::finalizing()`](crate::Actor::finalizing) may defer stopping the
+    /// actor.
+    pub fn finalize_timeout(mut self, timeout: Duration) -> Self {
+        self.finalize_timeout = timeout;
+        self
+    }
+
+    /// Stops the actor once it has received no message for `timeout`, see
+    /// [`Context::set_idle_timeout()`].
+    ///
+    /// [`Context::set_idle_timeout()`]: crate::Context::set_idle_timeout
+    pub fn idle_timeout(mut self, timeout: impl Into<Option<Duration>>) -> Self {
+        self.idle_timeout = timeout.into();
+        self
+    }
+
+    /// Sets what happens to queued messages when the actor stops.
+    pub fn stop_policy(mut self, policy: StopPolicy) -> Self {
+        self.stop_policy = policy;
+        self
+    }
+
+    /// Returns the mailbox capacity.
+    pub fn get_mailbox_capacity(&self) -> usize {
+        self.mailbox_capacity
+    }
+
+    /// Returns the message budget, `None` if it is unlimited.
+    pub fn get_message_budget(&self) -> Option<usize> {
+        self.message_budget
+    }
+
+    /// Returns the rate limit, `None` if the rate of messages is not limited.
+    pub fn get_rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limit
+    }
+
+    /// Returns how long `Actor::finalizing()` may defer stopping the actor.
+    pub fn get_finalize_timeout(&self) -> Duration {
+        self.finalize_timeout
+    }
+
+    /// Returns the idle timeout, `None` if the actor is not stopped when it is idle.
+    pub fn get_idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+    }
+
+    /// Returns what happens to queued messages when the actor stops.
+    pub fn get_stop_policy(&self) -> StopPolicy {
+        self.stop_policy
+    }
+}
+
+/// Describes what happens to the messages queued in the mailbox of an actor which stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StopPolicy {
+    /// Fail the queued messages, requests resolve with
+    /// [`MailboxError::Closed`](crate::MailboxError::Closed).
+    #[default]
+    Discard,
+
+    /// Handle the queued messages before stopping.
+    ///
+    /// Once [`Actor::stopping()`](crate::Actor::stopping) agreed to stop, the actor keeps
+    /// running until its mailbox is empty and no wait future is pending. Spawned futures are
+    /// dropped as usual when it stops. The actor stops right away if it is stopped again while
+    /// draining, or if it is terminated.
+    Drain,
+}
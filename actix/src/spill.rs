@@ -0,0 +1,556 @@
+//! Spilling of fire-and-forget messages to disk while an actor's mailbox is full.
+//!
+//! A [`SpillAddr`] sends messages like [`Addr::do_send`] as long as the actor's bounded mailbox
+//! has capacity left. Once the mailbox is full, messages implementing [`PersistentMessage`] are
+//! appended to a segmented log on disk instead, and moved back into the mailbox in their
+//! original order as the actor makes room. Memory use is thereby bounded by the mailbox
+//! capacity, however long the actor is stalled.
+//!
+//! Messages left on disk when the process stops are delivered by [`SpillAddr::recover`] on the
+//! next start. Records are removed from disk once they were queued in the mailbox, so a message
+//! queued but not yet handled before a crash is lost, while a message read from a segment which
+//! was not removed yet is delivered again.
+//!
+//! ```
+//! use std::io;
+//! use actix::{prelude::*, spill::{PersistentMessage, SpillAddr, SpillConfig}};
+//!
+//! #[derive(Message)]
+//! #[rtype(result = "()")]
+//! struct Sample(u32);
+//!
+//! impl PersistentMessage for Sample {
+//!     fn to_bytes(&self) -> Vec<u8> {
+//!         self.0.to_le_bytes().to_vec()
+//!     }
+//!
+//!     fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+//!         let bytes = bytes.try_into().map_err(|_| io::ErrorKind::InvalidData)?;
+//!         Ok(Sample(u32::from_le_bytes(bytes)))
+//!     }
+//! }
+//!
+//! struct Ingest;
+//!
+//! impl Actor for Ingest {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! impl Handler<Sample> for Ingest {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, msg: Sample, _: &mut Context<Self>) {
+//!         if msg.0 == 99 {
+//!             System::current().stop();
+//!         }
+//!     }
+//! }
+//!
+//! # fn main() {
+//! # let sys = System::new();
+//! # sys.block_on(async {
+//! let dir = std::env::temp_dir().join(format!("actix-spill-doc-{}", std::process::id()));
+//! let addr = Ingest::create(|ctx| {
+//!     ctx.set_mailbox_capacity(8);
+//!     Ingest
+//! });
+//! let spill = SpillAddr::new(addr, SpillConfig::new(&dir)).unwrap();
+//! for n in 0..100 {
+//!     spill.do_send(Sample(n)).unwrap();
+//! }
+//! # });
+//! # sys.run().unwrap();
+//! # }
+//! ```
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    fs::{self, File, OpenOptions},
+    future::poll_fn,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use futures_sink::Sink;
+use log::error;
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+
+use crate::{
+    actor::Actor,
+    address::{Addr, AddressSink, MailboxError, Request, SendError, ToEnvelope},
+    handler::{Handler, Message},
+};
+
+/// File extension of the log segments.
+const SEGMENT_EXT: &str = "seg";
+
+/// A message which can be written to disk by a [`SpillAddr`], or to a
+/// [`Journal`](crate::journal::Journal).
+pub trait PersistentMessage: Message + Sized {
+    /// Serializes the message.
+    fn to_bytes(&self) -> Vec<u8>;
+
+    /// Deserializes a message serialized with [`to_bytes()`](Self::to_bytes).
+    ///
+    /// Spilled records which fail to deserialize are logged and skipped.
+    fn from_bytes(bytes: &[u8]) -> io::Result<Self>;
+}
+
+/// Configuration of a [`SpillAddr`].
+#[derive(Debug, Clone)]
+pub struct SpillConfig {
+    dir: PathBuf,
+    segment_bytes: u64,
+}
+
+impl SpillConfig {
+    /// Creates a configuration storing the log segments in `dir`, which must not be shared with
+    /// other spilling addresses.
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        SpillConfig {
+            dir: dir.as_ref().to_owned(),
+            segment_bytes: 16 * 1024 * 1024,
+        }
+    }
+
+    /// Sets the size after which a new segment is started, 16 MiB by default.
+    ///
+    /// Segments are removed from disk once all their messages are queued in the mailbox.
+    pub fn segment_bytes(mut self, bytes: u64) -> Self {
+        self.segment_bytes = bytes.max(1);
+        self
+    }
+}
+
+/// The error returned by [`SpillAddr::do_send`].
+pub enum SpillError<M> {
+    /// The actor has stopped.
+    Closed(M),
+    /// The message was rejected by the actor's [`RateLimit`](crate::RateLimit).
+    RateLimited(M),
+    /// The message could not be written to disk.
+    Io(M, io::Error),
+}
+
+impl<M> SpillError<M> {
+    /// Returns the message which was not sent.
+    pub fn into_inner(self) -> M {
+        match self {
+            SpillError::Closed(msg) | SpillError::RateLimited(msg) | SpillError::Io(msg, _) => msg,
+        }
+    }
+}
+
+impl<M> fmt::Debug for SpillError<M> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpillError::Closed(_) => write!(fmt, "SpillError::Closed(..)"),
+            SpillError::RateLimited(_) => write!(fmt, "SpillError::RateLimited(..)"),
+            SpillError::Io(_, err) => write!(fmt, "SpillError::Io(.., {:?})", err),
+        }
+    }
+}
+
+impl<M> fmt::Display for SpillError<M> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpillError::Closed(_) => write!(fmt, "send failed because receiver is gone"),
+            SpillError::RateLimited(_) => write!(fmt, "send failed because of rate limit"),
+            SpillError::Io(_, err) => write!(fmt, "spilling message to disk failed: {}", err),
+        }
+    }
+}
+
+impl<M> std::error::Error for SpillError<M> {}
+
+/// Address of an actor which spills messages to disk while the actor's mailbox is full.
+///
+/// See the [module documentation](self) for details. Clones share the same disk queue.
+pub struct SpillAddr<A: Actor, M> {
+    addr: Addr<A>,
+    shared: Arc<Shared>,
+    _msg: std::marker::PhantomData<fn(M)>,
+}
+
+struct Shared {
+    log: Mutex<SegmentLog>,
+    notify: Notify,
+    handles: AtomicUsize,
+}
+
+impl<A, M> SpillAddr<A, M>
+where
+    A: Handler<M>,
+    A::Context: ToEnvelope<A, M>,
+    M: PersistentMessage + Send + 'static,
+    M::Result: Send,
+{
+    /// Creates a spilling address of `addr`.
+    ///
+    /// Returns an [`AlreadyExists`](io::ErrorKind::AlreadyExists) error if the directory
+    /// contains messages of a previous run, which are delivered by [`recover()`](Self::recover).
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a running system, where messages are moved from disk to
+    /// the mailbox.
+    pub fn new(addr: Addr<A>, config: SpillConfig) -> io::Result<Self> {
+        Self::open(addr, config, false)
+    }
+
+    /// Creates a spilling address of `addr`, delivering the messages left on disk by a previous
+    /// run before any new message.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a running system.
+    pub fn recover(addr: Addr<A>, config: SpillConfig) -> io::Result<Self> {
+        Self::open(addr, config, true)
+    }
+
+    fn open(addr: Addr<A>, config: SpillConfig, recover: bool) -> io::Result<Self> {
+        let log = SegmentLog::open(config, recover)?;
+        let shared = Arc::new(Shared {
+            log: Mutex::new(log),
+            notify: Notify::new(),
+            handles: AtomicUsize::new(1),
+        });
+        actix_rt::spawn(refill::<A, M>(
+            addr.clone().into_sink(),
+            Arc::clone(&shared),
+        ));
+        Ok(SpillAddr {
+            addr,
+            shared,
+            _msg: std::marker::PhantomData,
+        })
+    }
+
+    /// Sends `msg` without waiting for it to be handled.
+    ///
+    /// The message is queued in the mailbox if it has capacity left and no earlier message is
+    /// waiting on disk, otherwise it is appended to the disk queue.
+    pub fn do_send(&self, msg: M) -> Result<(), SpillError<M>> {
+        let mut log = self.shared.log.lock();
+        if log.is_empty() {
+            match self.addr.try_send(msg) {
+                Ok(()) => Ok(()),
+                Err(SendError::Full(msg)) => self.spill(&mut log, msg),
+                Err(SendError::RateLimited(msg)) => Err(SpillError::RateLimited(msg)),
+                Err(err) => Err(SpillError::Closed(err.into_inner())),
+            }
+        } else {
+            self.spill(&mut log, msg)
+        }
+    }
+
+    fn spill(&self, log: &mut SegmentLog, msg: M) -> Result<(), SpillError<M>> {
+        if !self.addr.connected() {
+            return Err(SpillError::Closed(msg));
+        }
+        match log.push(&msg.to_bytes()) {
+            Ok(()) => {
+                self.shared.notify.notify_one();
+                Ok(())
+            }
+            Err(err) => Err(SpillError::Io(msg, err)),
+        }
+    }
+
+    /// Sends `msg` and waits for its response, like [`Addr::send`].
+    ///
+    /// Requests can not be written to disk, because their response channel can not. While
+    /// messages are waiting on disk, the request is rejected with [`SendError::Full`].
+    pub fn send(&self, msg: M) -> Result<Request<A, M>, SendError<M>> {
+        let log = self.shared.log.lock();
+        if log.is_empty() {
+            Ok(self.addr.send(msg))
+        } else {
+            Err(SendError::Full(msg))
+        }
+    }
+
+    /// Returns the number of messages waiting on disk.
+    pub fn spilled(&self) -> usize {
+        self.shared.log.lock().len()
+    }
+
+    /// Returns the address of the actor.
+    pub fn addr(&self) -> &Addr<A> {
+        &self.addr
+    }
+}
+
+impl<A: Actor, M> Clone for SpillAddr<A, M> {
+    fn clone(&self) -> Self {
+        self.shared.handles.fetch_add(1, Ordering::SeqCst);
+        SpillAddr {
+            addr: self.addr.clone(),
+            shared: Arc::clone(&self.shared),
+            _msg: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<A: Actor, M> Drop for SpillAddr<A, M> {
+    fn drop(&mut self) {
+        if self.shared.handles.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.shared.notify.notify_one();
+        }
+    }
+}
+
+impl<A: Actor, M> fmt::Debug for SpillAddr<A, M> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("SpillAddr")
+            .field("addr", &self.addr)
+            .field("spilled", &self.shared.log.lock().len())
+            .finish()
+    }
+}
+
+/// Moves spilled messages back into the mailbox as it makes room.
+///
+/// Runs until the actor stops, or all spilling addresses are dropped and the disk queue is
+/// empty.
+async fn refill<A, M>(mut sink: AddressSink<A, M>, shared: Arc<Shared>)
+where
+    A: Handler<M>,
+    A::Context: ToEnvelope<A, M>,
+    M: PersistentMessage + Send + 'static,
+    M::Result: Send,
+{
+    loop {
+        if poll_fn(|cx| Pin::new(&mut sink).poll_ready(cx))
+            .await
+            .is_err()
+        {
+            return;
+        }
+
+        let notified = shared.notify.notified();
+        {
+            let mut log = shared.log.lock();
+            match log.pop() {
+                Ok(Some(bytes)) => {
+                    match M::from_bytes(&bytes) {
+                        Ok(msg) => match Pin::new(&mut sink).start_send(msg) {
+                            Ok(()) => {}
+                            Err(MailboxError::RateLimited) => {
+                                error!("Spilled message was rejected by the rate limit")
+                            }
+                            Err(_) => {
+                                // keep the message on disk for recovery
+                                log.unpop(&bytes);
+                                return;
+                            }
+                        },
+                        Err(err) => error!(
+                            "Skipping spilled message which failed to deserialize: {}",
+                            err
+                        ),
+                    }
+                    continue;
+                }
+                Ok(None) => {
+                    if shared.handles.load(Ordering::SeqCst) == 0 {
+                        return;
+                    }
+                }
+                Err(err) => {
+                    error!("Reading spilled messages failed: {}", err);
+                    return;
+                }
+            }
+        }
+        notified.await;
+    }
+}
+
+/// Segmented log of length prefixed records.
+struct SegmentLog {
+    config: SpillConfig,
+    /// Ids of the segments on disk, oldest first.
+    segments: VecDeque<u64>,
+    /// Segment being appended to and its size.
+    writer: Option<(File, u64)>,
+    /// Reader of the oldest segment.
+    reader: Option<File>,
+    len: usize,
+}
+
+impl SegmentLog {
+    fn open(config: SpillConfig, recover: bool) -> io::Result<Self> {
+        fs::create_dir_all(&config.dir)?;
+        let mut segments = Vec::new();
+        for entry in fs::read_dir(&config.dir)? {
+            let path = entry?.path();
+            if path.extension().map_or(false, |ext| ext == SEGMENT_EXT) {
+                if let Some(id) = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| stem.parse().ok())
+                {
+                    segments.push(id);
+                }
+            }
+        }
+        segments.sort_unstable();
+
+        let mut log = SegmentLog {
+            config,
+            segments: segments.into(),
+            writer: None,
+            reader: None,
+            len: 0,
+        };
+        if !log.segments.is_empty() {
+            if !recover {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    "spill directory contains messages of a previous run",
+                ));
+            }
+            for &id in &log.segments {
+                log.len += count_records(&log.path(id))?;
+            }
+            if log.len == 0 {
+                log.clear()?;
+            }
+        }
+        Ok(log)
+    }
+
+    fn path(&self, id: u64) -> PathBuf {
+        self.config.dir.join(format!("{:020}.{}", id, SEGMENT_EXT))
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn push(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let len = u32::try_from(bytes.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "message too large"))?;
+
+        let rotate = match self.writer {
+            Some((_, size)) => size >= self.config.segment_bytes,
+            None => true,
+        };
+        if rotate {
+            let id = self.segments.back().map_or(0, |id| id + 1);
+            let file = OpenOptions::new()
+                .create_new(true)
+                .append(true)
+                .open(self.path(id))?;
+            self.segments.push_back(id);
+            self.writer = Some((file, 0));
+        }
+
+        let (file, size) = self.writer.as_mut().unwrap();
+        // a single write, so a crash leaves at most one truncated record
+        let mut record = Vec::with_capacity(4 + bytes.len());
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(bytes);
+        file.write_all(&record)?;
+        *size += record.len() as u64;
+        self.len += 1;
+        Ok(())
+    }
+
+    fn pop(&mut self) -> io::Result<Option<Vec<u8>>> {
+        while self.len > 0 {
+            let id = match self.segments.front() {
+                Some(&id) => id,
+                None => break,
+            };
+            if self.reader.is_none() {
+                self.reader = Some(File::open(self.path(id))?);
+            }
+            if let Some(bytes) = read_record(self.reader.as_mut().unwrap())? {
+                self.len -= 1;
+                if self.len == 0 {
+                    self.clear()?;
+                }
+                return Ok(Some(bytes));
+            }
+
+            // the oldest segment is exhausted
+            self.reader = None;
+            self.segments.pop_front();
+            if self.segments.is_empty() {
+                self.writer = None;
+            }
+            fs::remove_file(self.path(id))?;
+        }
+        Ok(None)
+    }
+
+    /// Puts back the record returned by the last [`pop()`](Self::pop).
+    fn unpop(&mut self, bytes: &[u8]) {
+        let res = match self.reader {
+            Some(ref mut reader) => reader
+                .seek(SeekFrom::Current(-(4 + bytes.len() as i64)))
+                .map(|_| self.len += 1),
+            // the log was cleared after its last record was read
+            None => self.push(bytes),
+        };
+        if let Err(err) = res {
+            error!("Keeping a spilled message on disk failed: {}", err);
+        }
+    }
+
+    /// Removes all segments once every record was read.
+    fn clear(&mut self) -> io::Result<()> {
+        self.reader = None;
+        self.writer = None;
+        for id in std::mem::take(&mut self.segments) {
+            fs::remove_file(self.path(id))?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the next record, returns `None` at the end of the segment or at a truncated record.
+fn read_record(file: &mut File) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0; 4];
+    if !read_full(file, &mut len)? {
+        return Ok(None);
+    }
+    let mut bytes = vec![0; u32::from_le_bytes(len) as usize];
+    if !read_full(file, &mut bytes)? {
+        return Ok(None);
+    }
+    Ok(Some(bytes))
+}
+
+fn read_full(file: &mut File, buf: &mut [u8]) -> io::Result<bool> {
+    let mut read = 0;
+    while read < buf.len() {
+        match file.read(&mut buf[read..])? {
+            0 => return Ok(false),
+            n => read += n,
+        }
+    }
+    Ok(true)
+}
+
+fn count_records(path: &Path) -> io::Result<usize> {
+    let mut file = File::open(path)?;
+    let mut count = 0;
+    while read_record(&mut file)?.is_some() {
+        count += 1;
+    }
+    Ok(count)
+}
@@ -0,0 +1,695 @@
+//! Building blocks for soak and stress tests of actors, enabled by the `stress` feature.
+//!
+//! Ordering and shutdown races often only show under load. [`Stress::run()`] runs a scenario in
+//! its own [`System`] for a while and collects the broken invariants as [`Violation`]s:
+//!
+//! - [`FloodProducer`] sends messages at a given rate and counts the replies, a request which
+//!   fails or is never answered is a lost reply.
+//! - [`ChaosForwarder`] forwards messages after random delays, reordering them.
+//! - [`RandomStopper`] stops a supervised actor at random intervals, so it is restarted.
+//! - [`OrderChecker`] reports messages of a stream observed out of order.
+//! - [`Scenario::watch()`] reports actors which are still alive once the system has shut down.
+//!
+//! Random choices are made by a [`StressRng`] derived from the seed of the run, which is part of
+//! the [`StressReport`] to reproduce a failing run.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use actix::{prelude::*, stress::{OrderChecker, Stress}};
+//!
+//! struct Ledger {
+//!     order: OrderChecker,
+//! }
+//!
+//! impl Actor for Ledger {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! #[derive(Message)]
+//! #[rtype(result = "()")]
+//! struct Entry(u64);
+//!
+//! impl Handler<Entry> for Ledger {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, Entry(seq): Entry, _: &mut Self::Context) {
+//!         self.order.observe(0, seq);
+//!     }
+//! }
+//!
+//! let report = Stress::new(Duration::from_millis(100)).run(|scenario| {
+//!     let ledger = Ledger { order: scenario.order_checker() }.start();
+//!     scenario.watch(&ledger);
+//!     scenario.flood(ledger.recipient(), 1_000, Entry);
+//! });
+//! report.assert_ok();
+//! assert!(report.replied > 0);
+//! ```
+
+use std::{
+    any::type_name,
+    collections::HashMap,
+    error, fmt,
+    ops::Range,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use actix_rt::System;
+use parking_lot::Mutex;
+
+use crate::{
+    actor::{Actor, AsyncContext, Supervised},
+    address::{Addr, Recipient, WeakAddr},
+    clock::{sleep, Instant},
+    context::Context,
+    fut::{wrap_future, ActorFutureExt},
+    handler::{Handler, Message},
+};
+
+/// Interval between two batches of messages of a [`FloodProducer`], without jitter.
+const FLOOD_TICK: Duration = Duration::from_millis(1);
+
+/// Small pseudo-random generator, reproducible from its seed.
+#[derive(Debug, Clone)]
+pub struct StressRng {
+    state: u64,
+}
+
+impl StressRng {
+    /// Creates a generator from `seed`.
+    pub fn new(seed: u64) -> Self {
+        StressRng {
+            // xorshift never leaves a zero state
+            state: (seed ^ 0x9E37_79B9_7F4A_7C15) | 1,
+        }
+    }
+
+    /// Returns the next random number.
+    pub fn next_u64(&mut self) -> u64 {
+        // xorshift64*
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a random duration within `range`, or its start if it is empty.
+    pub fn duration(&mut self, range: Range<Duration>) -> Duration {
+        let span = range.end.saturating_sub(range.start).as_nanos() as u64;
+        if span == 0 {
+            return range.start;
+        }
+        range.start + Duration::from_nanos(self.next_u64() % span)
+    }
+
+    /// Returns a new generator seeded from this one, for another component.
+    pub fn fork(&mut self) -> Self {
+        StressRng::new(self.next_u64())
+    }
+}
+
+/// An invariant broken during a stress run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Violation {
+    /// Requests of a [`FloodProducer`] which failed or were never answered.
+    LostReplies { sent: u64, lost: u64 },
+    /// A message of an [`OrderChecker`] stream observed after a later one.
+    OutOfOrder { stream: u64, last: u64, seq: u64 },
+    /// An actor watched with [`Scenario::watch()`] was still alive after shutdown.
+    AliveAfterShutdown { actor: &'static str },
+    /// A violation reported by the scenario itself.
+    Custom(String),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::LostReplies { sent, lost } => {
+                write!(fmt, "{} of {} requests lost their reply", lost, sent)
+            }
+            Violation::OutOfOrder { stream, last, seq } => write!(
+                fmt,
+                "stream {} observed message {} after message {}",
+                stream, seq, last
+            ),
+            Violation::AliveAfterShutdown { actor } => {
+                write!(fmt, "actor {} is alive after shutdown", actor)
+            }
+            Violation::Custom(msg) => write!(fmt, "{}", msg),
+        }
+    }
+}
+
+impl error::Error for Violation {}
+
+/// Shared list of the violations of a run.
+#[derive(Debug, Clone, Default)]
+pub struct Violations(Arc<Mutex<Vec<Violation>>>);
+
+impl Violations {
+    /// Records a violation.
+    pub fn report(&self, violation: Violation) {
+        self.0.lock().push(violation);
+    }
+
+    /// Returns the violations recorded so far.
+    pub fn get(&self) -> Vec<Violation> {
+        self.0.lock().clone()
+    }
+}
+
+/// Shared counter of a stress component.
+#[derive(Debug, Clone, Default)]
+pub struct Counter(Arc<AtomicU64>);
+
+impl Counter {
+    /// Returns the current count.
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    fn incr(&self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Checks that the messages of each stream are observed in increasing order.
+#[derive(Debug, Clone, Default)]
+pub struct OrderChecker {
+    last: Arc<Mutex<HashMap<u64, u64>>>,
+    violations: Violations,
+}
+
+impl OrderChecker {
+    /// Creates a checker which reports to `violations`.
+    pub fn new(violations: Violations) -> Self {
+        OrderChecker {
+            last: Arc::default(),
+            violations,
+        }
+    }
+
+    /// Observes the message `seq` of `stream`, reporting it if a later message was observed
+    /// before.
+    pub fn observe(&self, stream: u64, seq: u64) {
+        let mut last = self.last.lock();
+        match last.insert(stream, seq) {
+            Some(prev) if prev >= seq => {
+                last.insert(stream, prev);
+                self.violations.report(Violation::OutOfOrder {
+                    stream,
+                    last: prev,
+                    seq,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Replies counted by a [`FloodProducer`].
+#[derive(Debug, Clone, Default)]
+pub struct FloodStats {
+    sent: Counter,
+    replied: Counter,
+    failed: Counter,
+}
+
+impl FloodStats {
+    /// Returns the number of sent requests.
+    pub fn sent(&self) -> u64 {
+        self.sent.get()
+    }
+
+    /// Returns the number of requests answered by the actor.
+    pub fn replied(&self) -> u64 {
+        self.replied.get()
+    }
+
+    /// Returns the number of requests which failed with a [`MailboxError`](crate::MailboxError).
+    pub fn failed(&self) -> u64 {
+        self.failed.get()
+    }
+}
+
+/// Actor sending requests created by a function at a given rate, with jitter.
+///
+/// The `n`th request is created by calling the function with `n`, starting at 0, and all of
+/// them are sent through the same recipient, so they reach the actor in order.
+pub struct FloodProducer<M>
+where
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    recipient: Recipient<M>,
+    rate: u32,
+    jitter: Duration,
+    make: Box<dyn FnMut(u64) -> M>,
+    rng: StressRng,
+    until: Option<Instant>,
+    start: Instant,
+    stats: FloodStats,
+}
+
+impl<M> FloodProducer<M>
+where
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    /// Creates a producer of `rate` requests per second to `recipient`.
+    pub fn new<F>(recipient: Recipient<M>, rate: u32, make: F) -> Self
+    where
+        F: FnMut(u64) -> M + 'static,
+    {
+        FloodProducer {
+            recipient,
+            rate,
+            jitter: Duration::ZERO,
+            make: Box::new(make),
+            rng: StressRng::new(0),
+            until: None,
+            start: Instant::now(),
+            stats: FloodStats::default(),
+        }
+    }
+
+    /// Delays every batch of requests by up to `jitter`.
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Sets the generator of the jitter.
+    pub fn rng(mut self, rng: StressRng) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    /// Stops sending requests at `deadline`, the replies are still counted afterwards.
+    pub fn until(mut self, deadline: Instant) -> Self {
+        self.until = Some(deadline);
+        self
+    }
+
+    /// Returns the counters of the producer.
+    pub fn stats(&self) -> FloodStats {
+        self.stats.clone()
+    }
+
+    fn produce(&mut self, ctx: &mut Context<Self>) {
+        let now = Instant::now();
+        if self.until.map_or(false, |until| now >= until) {
+            return;
+        }
+
+        let due = (now - self.start).as_secs_f64() * f64::from(self.rate);
+        while (self.stats.sent() as f64) < due {
+            let msg = (self.make)(self.stats.sent());
+            self.stats.sent.incr();
+            let req =
+                wrap_future(self.recipient.send(msg)).map(|res, act: &mut Self, _| match res {
+                    Ok(_) => act.stats.replied.incr(),
+                    Err(_) => act.stats.failed.incr(),
+                });
+            ctx.spawn(req);
+        }
+
+        let delay = FLOOD_TICK + self.rng.duration(Duration::ZERO..self.jitter);
+        ctx.run_later(delay, Self::produce);
+    }
+}
+
+impl<M> Actor for FloodProducer<M>
+where
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.start = Instant::now();
+        self.produce(ctx);
+    }
+}
+
+/// Actor forwarding messages to a recipient after a random delay, reordering them.
+pub struct ChaosForwarder<M>
+where
+    M: Message<Result = ()> + Send + 'static,
+{
+    target: Recipient<M>,
+    delay: Range<Duration>,
+    rng: StressRng,
+}
+
+impl<M> ChaosForwarder<M>
+where
+    M: Message<Result = ()> + Send + 'static,
+{
+    /// Creates a forwarder to `target` which delays every message by a duration within `delay`.
+    pub fn new(target: Recipient<M>, delay: Range<Duration>) -> Self {
+        ChaosForwarder {
+            target,
+            delay,
+            rng: StressRng::new(0),
+        }
+    }
+
+    /// Sets the generator of the delays.
+    pub fn rng(mut self, rng: StressRng) -> Self {
+        self.rng = rng;
+        self
+    }
+}
+
+impl<M> Actor for ChaosForwarder<M>
+where
+    M: Message<Result = ()> + Send + 'static,
+{
+    type Context = Context<Self>;
+}
+
+impl<M> Handler<M> for ChaosForwarder<M>
+where
+    M: Message<Result = ()> + Send + 'static,
+{
+    type Result = ();
+
+    fn handle(&mut self, msg: M, ctx: &mut Self::Context) {
+        let delay = self.rng.duration(self.delay.clone());
+        ctx.run_later(delay, move |act, _| act.target.do_send(msg));
+    }
+}
+
+/// Actor stopping a supervised actor at random intervals, so its supervisor restarts it.
+///
+/// The actor is asked to stop once it has handled the messages queued so far.
+pub struct RandomStopper<A>
+where
+    A: Actor<Context = Context<A>> + Supervised,
+{
+    addr: Addr<A>,
+    every: Range<Duration>,
+    rng: StressRng,
+    until: Option<Instant>,
+    stops: Counter,
+}
+
+impl<A> RandomStopper<A>
+where
+    A: Actor<Context = Context<A>> + Supervised,
+{
+    /// Creates a stopper of the actor at `addr`, waiting for a duration within `every` between
+    /// two stops.
+    pub fn new(addr: Addr<A>, every: Range<Duration>) -> Self {
+        RandomStopper {
+            addr,
+            every,
+            rng: StressRng::new(0),
+            until: None,
+            stops: Counter::default(),
+        }
+    }
+
+    /// Sets the generator of the intervals.
+    pub fn rng(mut self, rng: StressRng) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    /// Stops stopping the actor at `deadline`.
+    pub fn until(mut self, deadline: Instant) -> Self {
+        self.until = Some(deadline);
+        self
+    }
+
+    /// Returns the number of stops requested so far.
+    pub fn stops(&self) -> Counter {
+        self.stops.clone()
+    }
+
+    fn schedule(&mut self, ctx: &mut Context<Self>) {
+        let delay = self.rng.duration(self.every.clone());
+        ctx.run_later(delay, |act, ctx| {
+            if act.until.map_or(false, |until| Instant::now() >= until) {
+                return;
+            }
+            if act.addr.request_stop() {
+                act.stops.incr();
+                act.schedule(ctx);
+            }
+        });
+    }
+}
+
+impl<A> Actor for RandomStopper<A>
+where
+    A: Actor<Context = Context<A>> + Supervised,
+{
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.schedule(ctx);
+    }
+}
+
+/// Result of a [`Stress::run()`].
+#[derive(Debug, Clone)]
+pub struct StressReport {
+    /// Seed of the run, pass it to [`Stress::seed()`] to reproduce the run.
+    pub seed: u64,
+    /// Requests sent by the producers of the scenario.
+    pub sent: u64,
+    /// Requests answered by their actor.
+    pub replied: u64,
+    /// Stops requested by the random stoppers of the scenario.
+    pub stops: u64,
+    pub violations: Vec<Violation>,
+}
+
+impl StressReport {
+    /// Returns `true` if no invariant was broken.
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Panics with the seed and the violations if an invariant was broken.
+    #[track_caller]
+    pub fn assert_ok(&self) {
+        if !self.is_ok() {
+            let violations: Vec<_> = self.violations.iter().map(|v| v.to_string()).collect();
+            panic!(
+                "stress run with seed {} broke invariants: {}",
+                self.seed,
+                violations.join("; ")
+            );
+        }
+    }
+}
+
+/// Actors and components of a stress run, passed to the scenario by [`Stress::run()`].
+#[allow(clippy::type_complexity)]
+pub struct Scenario {
+    seed: u64,
+    rng: StressRng,
+    deadline: Instant,
+    violations: Violations,
+    floods: Vec<FloodStats>,
+    stoppers: Vec<Counter>,
+    /// Watched actors with a check whether they are alive.
+    watched: Vec<(&'static str, Box<dyn Fn() -> bool>)>,
+}
+
+impl Scenario {
+    /// Returns the seed of the run.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns a generator for the scenario's own random choices.
+    pub fn rng(&mut self) -> StressRng {
+        self.rng.fork()
+    }
+
+    /// Returns the instant the run ends at, producers and stoppers are idle afterwards.
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Returns the violations of the run, to report the scenario's own ones.
+    pub fn violations(&self) -> Violations {
+        self.violations.clone()
+    }
+
+    /// Returns an order checker reporting to this run.
+    pub fn order_checker(&self) -> OrderChecker {
+        OrderChecker::new(self.violations())
+    }
+
+    /// Starts a [`FloodProducer`] of `rate` requests per second until the end of the run.
+    ///
+    /// Requests which have failed or are not answered once the run has ended are reported.
+    pub fn flood<M, F>(&mut self, recipient: Recipient<M>, rate: u32, make: F) -> FloodStats
+    where
+        M: Message + Send + 'static,
+        M::Result: Send,
+        F: FnMut(u64) -> M + 'static,
+    {
+        let producer = FloodProducer::new(recipient, rate, make)
+            .jitter(FLOOD_TICK)
+            .rng(self.rng.fork())
+            .until(self.deadline);
+        let stats = producer.stats();
+        producer.start();
+        self.floods.push(stats.clone());
+        stats
+    }
+
+    /// Starts a [`ChaosForwarder`] to `target` and returns a recipient of it.
+    pub fn chaos<M>(&mut self, target: Recipient<M>, delay: Range<Duration>) -> Recipient<M>
+    where
+        M: Message<Result = ()> + Send + 'static,
+    {
+        ChaosForwarder::new(target, delay)
+            .rng(self.rng.fork())
+            .start()
+            .recipient()
+    }
+
+    /// Starts a [`RandomStopper`] of the supervised actor at `addr` until the end of the run.
+    pub fn random_stopper<A>(&mut self, addr: Addr<A>, every: Range<Duration>) -> Counter
+    where
+        A: Actor<Context = Context<A>> + Supervised,
+    {
+        let stopper = RandomStopper::new(addr, every)
+            .rng(self.rng.fork())
+            .until(self.deadline);
+        let stops = stopper.stops();
+        stopper.start();
+        self.stoppers.push(stops.clone());
+        stops
+    }
+
+    /// Reports the actor at `addr` if it is still alive once the system has shut down.
+    pub fn watch<A: Actor>(&mut self, addr: &Addr<A>) {
+        let addr: WeakAddr<A> = addr.downgrade();
+        let alive = move || addr.upgrade().map_or(false, |addr| addr.connected());
+        self.watched.push((type_name::<A>(), Box::new(alive)));
+    }
+}
+
+/// Runner of a stress scenario.
+#[derive(Debug, Clone)]
+pub struct Stress {
+    duration: Duration,
+    grace: Duration,
+    seed: Option<u64>,
+}
+
+impl Stress {
+    /// Creates a runner of scenarios lasting `duration`.
+    pub fn new(duration: Duration) -> Self {
+        Stress {
+            duration,
+            grace: Duration::from_secs(1),
+            seed: None,
+        }
+    }
+
+    /// Sets the seed of the run, by default it is derived from the current time.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets how long pending replies are waited for after the run, and how long actors may take
+    /// to stop after shutdown. Defaults to one second.
+    pub fn grace(mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    /// Runs `scenario` in a new [`System`], then shuts the system down and returns the broken
+    /// invariants.
+    ///
+    /// The scenario starts its actors and components, the run ends `duration` later. Pending
+    /// replies are awaited for up to the grace period before the system is stopped.
+    pub fn run<F>(self, scenario: F) -> StressReport
+    where
+        F: FnOnce(&mut Scenario) + 'static,
+    {
+        let seed = self.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_nanos() as u64)
+        });
+        let violations = Violations::default();
+
+        let sys = System::new();
+        let run_violations = violations.clone();
+        let run = sys.block_on(async move {
+            let mut run = Scenario {
+                seed,
+                rng: StressRng::new(seed),
+                deadline: Instant::now() + self.duration,
+                violations: run_violations,
+                floods: Vec::new(),
+                stoppers: Vec::new(),
+                watched: Vec::new(),
+            };
+            scenario(&mut run);
+            sleep(self.duration).await;
+
+            let pending = |floods: &[FloodStats]| {
+                floods
+                    .iter()
+                    .any(|stats| stats.replied() + stats.failed() < stats.sent())
+            };
+            let grace = Instant::now() + self.grace;
+            while pending(&run.floods) && Instant::now() < grace {
+                sleep(Duration::from_millis(1)).await;
+            }
+
+            for stats in &run.floods {
+                let lost = stats.sent() - stats.replied();
+                if lost > 0 {
+                    run.violations.report(Violation::LostReplies {
+                        sent: stats.sent(),
+                        lost,
+                    });
+                }
+            }
+            System::current().stop();
+            run
+        });
+        sys.run().ok();
+
+        let grace = Instant::now() + self.grace;
+        let mut alive = run.watched;
+        while !alive.is_empty() {
+            alive.retain(|(_, is_alive)| is_alive());
+            if Instant::now() >= grace {
+                break;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        for (actor, _) in alive {
+            violations.report(Violation::AliveAfterShutdown { actor });
+        }
+
+        StressReport {
+            seed,
+            sent: run.floods.iter().map(FloodStats::sent).sum(),
+            replied: run.floods.iter().map(FloodStats::replied).sum(),
+            stops: run.stoppers.iter().map(Counter::get).sum(),
+            violations: violations.get(),
+        }
+    }
+}
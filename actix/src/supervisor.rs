@@ -1,84 +1,188 @@
 use std::{
+    any::type_name,
+    fmt,
     future::Future,
     pin::Pin,
     task::{self, Poll},
 };
 
 use actix_rt::ArbiterHandle;
-use pin_project_lite::pin_project;
 
 use crate::{
     actor::{Actor, AsyncContext, Supervised},
-    address::{channel, Addr},
+    address::{channel, Addr, Recipient},
+    arbiter::spawn_actor,
+    config::SystemConfig,
     context::Context,
     contextimpl::ContextFut,
-    mailbox::DEFAULT_CAPACITY,
+    contextitems::ActorMessageItem,
+    handler::{Handler, Message},
+    settings::ActorSettings,
+    supervisor_group::SupervisionFailed,
 };
 
-pin_project! {
-    /// Actor supervisor
-    ///
-    /// A Supervisor manages incoming messages for an actor. In case of actor failure,
-    /// the supervisor creates a new execution context and restarts the actor's lifecycle.
-    /// A Supervisor does not re-create their actor, it just calls the `restarting()`
-    /// method.
-    ///
-    /// Supervisors have the same lifecycle as actors. If all addresses to
-    /// a supervisor gets dropped and its actor does not execute anything, the supervisor
-    /// terminates.
-    ///
-    /// Supervisors can not guarantee that their actors successfully processes incoming
-    /// messages. If the actor fails during message processing, the message can not be
-    /// recovered. The sender would receive an `Err(Cancelled)` error in this situation.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// # use actix::prelude::*;
-    /// #[derive(Message)]
-    /// #[rtype(result = "()")]
-    /// struct Die;
-    ///
-    /// struct MyActor;
-    ///
-    /// impl Actor for MyActor {
-    ///     type Context = Context<Self>;
-    /// }
-    ///
-    /// // To use actor with supervisor actor has to implement `Supervised` trait
-    /// impl actix::Supervised for MyActor {
-    ///     fn restarting(&mut self, ctx: &mut Context<MyActor>) {
-    ///         println!("restarting");
-    ///     }
-    /// }
-    ///
-    /// impl Handler<Die> for MyActor {
-    ///     type Result = ();
-    ///
-    ///     fn handle(&mut self, _: Die, ctx: &mut Context<MyActor>) {
-    ///         ctx.stop();
-    /// #       System::current().stop();
-    ///     }
-    /// }
-    ///
-    /// fn main() {
-    ///     let mut sys = System::new();
-    ///
-    ///     let addr = sys.block_on(async { actix::Supervisor::start(|_| MyActor) });
-    ///     addr.do_send(Die);
-    ///
-    ///     sys.run();
-    /// }
-    /// ```
-    #[derive(Debug)]
-    pub struct Supervisor<A>
+/// Actor supervisor
+///
+/// A Supervisor manages incoming messages for an actor. In case of actor failure,
+/// the supervisor creates a new execution context and restarts the actor's lifecycle.
+/// A Supervisor does not re-create their actor, it just calls the `restarting()`
+/// method.
+///
+/// Supervisors have the same lifecycle as actors. If all addresses to
+/// a supervisor gets dropped and its actor does not execute anything, the supervisor
+/// terminates.
+///
+/// Use [`SupervisorBuilder`] for lazy creation of the actor, an initial message after every
+/// start, or a custom [`RestartPolicy`].
+///
+/// A panicking factory is not retried by [`Supervisor::start()`], the supervisor stops right
+/// away and the address of the actor is disconnected. See
+/// [`SupervisorBuilder::new_retrying()`] for factories called again until they succeed.
+///
+/// Supervisors can not guarantee that their actors successfully processes incoming
+/// messages. If the actor fails during message processing, the message can not be
+/// recovered, it is never handled twice. A sender waiting for the response of that message
+/// receives [`MailboxError::Closed`](crate::MailboxError::Closed) if the response has not
+/// been sent yet.
+///
+/// Messages still queued in the mailbox are kept across restarts and handled by the restarted
+/// actor, so their senders receive their responses as usual. Queued requests whose sender
+/// stopped waiting for the response are skipped.
+///
+/// # Examples
+///
+/// ```
+/// # use actix::prelude::*;
+/// #[derive(Message)]
+/// #[rtype(result = "()")]
+/// struct Die;
+///
+/// struct MyActor;
+///
+/// impl Actor for MyActor {
+///     type Context = Context<Self>;
+/// }
+///
+/// // To use actor with supervisor actor has to implement `Supervised` trait
+/// impl actix::Supervised for MyActor {
+///     fn restarting(&mut self, ctx: &mut Context<MyActor>) {
+///         println!("restarting");
+///     }
+/// }
+///
+/// impl Handler<Die> for MyActor {
+///     type Result = ();
+///
+///     fn handle(&mut self, _: Die, ctx: &mut Context<MyActor>) {
+///         ctx.stop();
+/// #       System::current().stop();
+///     }
+/// }
+///
+/// fn main() {
+///     let mut sys = System::new();
+///
+///     let addr = sys.block_on(async { actix::Supervisor::start(|_| MyActor) });
+///     addr.do_send(Die);
+///
+///     sys.run();
+/// }
+/// ```
+pub struct Supervisor<A>
+where
+    A: Supervised,
+    A: Actor<Context = Context<A>>,
+{
+    state: SupervisorState<A>,
+    policy: RestartPolicy,
+    restarts: usize,
+    on_start: Option<OnStart<A>>,
+    observer: Option<Recipient<SupervisionFailed>>,
+    /// Set once the factory panicked, the actor is created again on the next poll.
+    recreate: bool,
+}
+
+/// Factory of a supervised actor, called again after a panic if it is repeatable.
+enum Factory<A: Actor<Context = Context<A>>> {
+    Once(Option<OnceFactory<A>>),
+    Repeat(RepeatFactory<A>),
+}
+
+type OnceFactory<A> = Box<dyn FnOnce(&mut Context<A>) -> A>;
+type RepeatFactory<A> = Box<dyn FnMut(&mut Context<A>) -> A>;
+
+impl<A: Actor<Context = Context<A>>> Factory<A> {
+    fn once<F>(f: F) -> Self
     where
-        A: Supervised,
-        A: Actor<Context = Context<A>>
+        F: FnOnce(&mut Context<A>) -> A + 'static,
     {
-        #[pin]
-        fut: ContextFut<A, Context<A>>,
+        Factory::Once(Some(Box::new(f)))
     }
+
+    fn repeat<F>(f: F) -> Self
+    where
+        F: FnMut(&mut Context<A>) -> A + 'static,
+    {
+        Factory::Repeat(Box::new(f))
+    }
+
+    /// Creates the actor, returning `None` if the factory panicked or was already used up.
+    fn create(&mut self, ctx: &mut Context<A>) -> Option<A> {
+        match self {
+            Factory::Once(f) => ctx.create_with(f.take()?),
+            Factory::Repeat(f) => ctx.create_with(f),
+        }
+    }
+
+    fn is_repeatable(&self) -> bool {
+        matches!(self, Factory::Repeat(_))
+    }
+}
+
+type OnStart<A> = Box<dyn Fn(&mut Context<A>) + Send>;
+
+// the running actor is kept in place, a lazy actor is only waiting for its first message
+#[allow(clippy::large_enum_variant)]
+enum SupervisorState<A>
+where
+    A: Supervised + Actor<Context = Context<A>>,
+{
+    /// Actor is created once the first message arrives
+    Lazy(Option<(Context<A>, Factory<A>)>),
+    Running(ContextFut<A, Context<A>>),
+    /// The factory panicked and may not be called again
+    Failed,
+}
+
+impl<A> fmt::Debug for Supervisor<A>
+where
+    A: Supervised + Actor<Context = Context<A>>,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut fmt = fmt.debug_struct("Supervisor");
+        match self.state {
+            SupervisorState::Lazy(_) => fmt.field("state", &"Lazy"),
+            SupervisorState::Running(ref fut) => fmt.field("state", fut),
+            SupervisorState::Failed => fmt.field("state", &"Failed"),
+        };
+        fmt.field("policy", &self.policy)
+            .field("restarts", &self.restarts)
+            .finish()
+    }
+}
+
+/// Restart policy of a [`Supervisor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RestartPolicy {
+    /// Restart the actor every time it stops.
+    #[default]
+    Always,
+
+    /// Never restart the actor, the supervisor stops together with its actor.
+    Never,
+
+    /// Restart the actor at most the given number of times.
+    Limit(usize),
 }
 
 impl<A> Supervisor<A>
@@ -114,12 +218,12 @@ where
     {
         // create actor
         let mut ctx = Context::new();
-        let act = f(&mut ctx);
         let addr = ctx.address();
-        let fut = ctx.into_future(act);
-
-        // create supervisor
-        actix_rt::spawn(Self { fut });
+        match ctx.create_with(f) {
+            // create supervisor
+            Some(act) => spawn_actor(Self::running(ctx.into_future(act))),
+            None => ctx.abandon(),
+        }
 
         addr
     }
@@ -130,18 +234,96 @@ where
         A: Actor<Context = Context<A>>,
         F: FnOnce(&mut Context<A>) -> A + Send + 'static,
     {
-        let (tx, rx) = channel::channel(DEFAULT_CAPACITY);
+        let (tx, rx) = channel::channel(SystemConfig::current().get_mailbox_capacity());
 
         sys.spawn_fn(move || {
             let mut ctx = Context::with_receiver(rx);
-            let act = f(&mut ctx);
-            let fut = ctx.into_future(act);
-
-            actix_rt::spawn(Self { fut });
+            match ctx.create_with(f) {
+                Some(act) => spawn_actor(Self::running(ctx.into_future(act))),
+                None => ctx.abandon(),
+            }
         });
 
         Addr::new(tx)
     }
+
+    fn running(fut: ContextFut<A, Context<A>>) -> Self {
+        Supervisor {
+            state: SupervisorState::Running(fut),
+            policy: RestartPolicy::Always,
+            restarts: 0,
+            on_start: None,
+            observer: None,
+            recreate: false,
+        }
+    }
+
+    fn from_builder(ctx: Context<A>, factory: Factory<A>, cfg: SupervisorConfig<A>) -> Self {
+        let mut sup = Supervisor {
+            state: SupervisorState::Lazy(Some((ctx, factory))),
+            policy: cfg.policy,
+            restarts: 0,
+            on_start: cfg.on_start,
+            observer: cfg.observer,
+            recreate: false,
+        };
+        if !cfg.lazy {
+            sup.create();
+        }
+        sup
+    }
+
+    /// Create the actor of a lazy supervisor.
+    ///
+    /// If the factory panics, it is called again on the next poll as long as it is repeatable
+    /// and the restart policy allows it, otherwise the supervisor fails.
+    fn create(&mut self) {
+        let can_restart = self.can_restart();
+        let (ctx, factory) = match self.state {
+            SupervisorState::Lazy(Some((ref mut ctx, ref mut factory))) => (ctx, factory),
+            _ => return,
+        };
+        if let Some(act) = factory.create(ctx) {
+            if let SupervisorState::Lazy(ref mut item) = self.state {
+                let (ctx, _) = item.take().unwrap();
+                self.state = SupervisorState::Running(ctx.into_future(act));
+                self.started();
+            }
+        } else if factory.is_repeatable() && can_restart {
+            self.restarts += 1;
+            self.recreate = true;
+        } else {
+            ctx.log().error(format_args!(
+                "giving up after {} failed restarts",
+                self.restarts
+            ));
+            ctx.abandon();
+            if let Some(ref observer) = self.observer {
+                observer.do_send(SupervisionFailed {
+                    path: vec![type_name::<A>().to_owned()],
+                    restarts: self.restarts,
+                });
+            }
+            self.state = SupervisorState::Failed;
+        }
+    }
+
+    /// Queue initial message, it is handled before any message in the mailbox.
+    fn started(&mut self) {
+        if let (Some(on_start), SupervisorState::Running(ref mut fut)) =
+            (self.on_start.as_ref(), &mut self.state)
+        {
+            on_start(fut.ctx());
+        }
+    }
+
+    fn can_restart(&self) -> bool {
+        match self.policy {
+            RestartPolicy::Always => true,
+            RestartPolicy::Never => false,
+            RestartPolicy::Limit(max) => self.restarts < max,
+        }
+    }
 }
 
 #[doc(hidden)]
@@ -152,17 +334,248 @@ where
     type Output = ();
 
     fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
-        let mut this = self.project();
+        let this = self.get_mut();
+
+        if let SupervisorState::Lazy(Some((ref mut ctx, _))) = this.state {
+            if !this.recreate {
+                let mb = ctx.mailbox_mut().unwrap();
+                match mb.poll_message(cx) {
+                    Poll::Ready(Some(msg)) => mb.push_front(msg),
+                    Poll::Ready(None) => return Poll::Ready(()),
+                    Poll::Pending => {
+                        // nobody can trigger construction anymore
+                        if !ctx.connected() {
+                            return Poll::Ready(());
+                        }
+                        return Poll::Pending;
+                    }
+                }
+            }
+            this.recreate = false;
+            this.create();
+            if this.recreate {
+                // let the other actors of the arbiter run before the next attempt
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+        }
+
         loop {
-            match this.fut.as_mut().poll(cx) {
+            let can_restart = this.can_restart();
+            let fut = match this.state {
+                SupervisorState::Running(ref mut fut) => fut,
+                SupervisorState::Failed => return Poll::Ready(()),
+                SupervisorState::Lazy(_) => unreachable!(),
+            };
+            match Pin::new(&mut *fut).poll(cx) {
                 Poll::Pending => return Poll::Pending,
                 Poll::Ready(_) => {
+                    if !can_restart {
+                        return Poll::Ready(());
+                    }
                     // stop if context's address is not connected
-                    if !this.fut.restart() {
+                    if !fut.restart() {
                         return Poll::Ready(());
                     }
+                    this.restarts += 1;
+                    this.started();
                 }
             }
         }
     }
 }
+
+struct SupervisorConfig<A: Actor<Context = Context<A>>> {
+    lazy: bool,
+    policy: RestartPolicy,
+    on_start: Option<OnStart<A>>,
+    settings: Option<ActorSettings>,
+    observer: Option<Recipient<SupervisionFailed>>,
+}
+
+/// Builder for a [`Supervisor`] with custom start and restart behavior.
+///
+/// # Examples
+///
+/// ```
+/// # use actix::prelude::*;
+/// # use actix::{RestartPolicy, SupervisorBuilder};
+/// #[derive(Message, Clone)]
+/// #[rtype(result = "()")]
+/// struct Connect;
+///
+/// struct Client;
+///
+/// impl Actor for Client {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Supervised for Client {}
+///
+/// impl Handler<Connect> for Client {
+///     type Result = ();
+///
+///     fn handle(&mut self, _: Connect, _: &mut Context<Self>) {
+///         // (re)connect to the server
+/// #       System::current().stop();
+///     }
+/// }
+///
+/// # fn main() {
+/// # let sys = System::new();
+/// # sys.block_on(async {
+/// // client is created once the first message is sent, and
+/// // receives `Connect` after every (re)start
+/// let addr = SupervisorBuilder::new(|_| Client)
+///     .lazy(true)
+///     .on_start_send(Connect)
+///     .restart_policy(RestartPolicy::Limit(3))
+///     .start();
+/// # addr.do_send(Connect);
+/// # });
+/// # sys.run().unwrap();
+/// # }
+/// ```
+pub struct SupervisorBuilder<A: Actor<Context = Context<A>>, F> {
+    factory: F,
+    into_factory: fn(F) -> Factory<A>,
+    cfg: SupervisorConfig<A>,
+}
+
+impl<A: Actor<Context = Context<A>>, F> fmt::Debug for SupervisorBuilder<A, F> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("SupervisorBuilder")
+            .field("lazy", &self.cfg.lazy)
+            .field("policy", &self.cfg.policy)
+            .finish()
+    }
+}
+
+impl<A, F> SupervisorBuilder<A, F>
+where
+    A: Supervised + Actor<Context = Context<A>>,
+    F: FnOnce(&mut Context<A>) -> A + 'static,
+{
+    /// Creates a builder for a supervised actor created by `factory`.
+    ///
+    /// If `factory` panics, the supervisor stops and the address of the actor is disconnected.
+    pub fn new(factory: F) -> Self {
+        Self::with_factory(factory, Factory::once)
+    }
+
+    fn with_factory(factory: F, into_factory: fn(F) -> Factory<A>) -> Self {
+        SupervisorBuilder {
+            factory,
+            into_factory,
+            cfg: SupervisorConfig {
+                lazy: false,
+                policy: RestartPolicy::Always,
+                on_start: None,
+                settings: None,
+                observer: None,
+            },
+        }
+    }
+
+    /// Delays creation of the actor until the first message arrives.
+    ///
+    /// Messages are queued in the mailbox in the meantime.
+    pub fn lazy(mut self, lazy: bool) -> Self {
+        self.cfg.lazy = lazy;
+        self
+    }
+
+    /// Sends a clone of `msg` to the actor after every start and restart.
+    ///
+    /// The message is handled before any message waiting in the mailbox.
+    pub fn on_start_send<M>(mut self, msg: M) -> Self
+    where
+        A: Handler<M>,
+        M: Message + Clone + Send + 'static,
+    {
+        self.cfg.on_start = Some(Box::new(move |ctx: &mut Context<A>| {
+            ctx.wait(ActorMessageItem::new(msg.clone()))
+        }));
+        self
+    }
+
+    /// Sets the restart policy, by default the actor is always restarted.
+    pub fn restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.cfg.policy = policy;
+        self
+    }
+
+    /// Sends a [`SupervisionFailed`] to `observer` if the factory panicked and may not be
+    /// called again, see [`new_retrying()`](Self::new_retrying).
+    pub fn on_failure(mut self, observer: Recipient<SupervisionFailed>) -> Self {
+        self.cfg.observer = Some(observer);
+        self
+    }
+
+    /// Sets the execution settings of the actor, applied before it is created.
+    ///
+    /// Settings changed by the actor itself are kept across restarts.
+    pub fn settings(mut self, settings: ActorSettings) -> Self {
+        self.cfg.settings = Some(settings);
+        self
+    }
+
+    /// Starts the supervisor in the current arbiter.
+    pub fn start(self) -> Addr<A> {
+        let mut ctx = Context::new();
+        if let Some(ref settings) = self.cfg.settings {
+            ctx.apply_settings(settings);
+        }
+        let addr = ctx.address();
+        spawn_actor(Supervisor::from_builder(
+            ctx,
+            (self.into_factory)(self.factory),
+            self.cfg,
+        ));
+        addr
+    }
+
+    /// Starts the supervisor in the arbiter's thread.
+    pub fn spawn(self, arbiter: &ArbiterHandle) -> Addr<A>
+    where
+        F: Send,
+    {
+        let cap = match self.cfg.settings {
+            Some(ref settings) => settings.get_mailbox_capacity(),
+            None => SystemConfig::current().get_mailbox_capacity(),
+        };
+        let (tx, rx) = channel::channel(cap);
+        let SupervisorBuilder {
+            factory,
+            into_factory,
+            cfg,
+        } = self;
+
+        arbiter.spawn_fn(move || {
+            let mut ctx = Context::with_receiver(rx);
+            if let Some(ref settings) = cfg.settings {
+                ctx.apply_settings(settings);
+            }
+            spawn_actor(Supervisor::from_builder(ctx, into_factory(factory), cfg));
+        });
+
+        Addr::new(tx)
+    }
+}
+
+impl<A, F> SupervisorBuilder<A, F>
+where
+    A: Supervised + Actor<Context = Context<A>>,
+    F: FnMut(&mut Context<A>) -> A + 'static,
+{
+    /// Creates a builder for a supervised actor created by `factory`, which is called again
+    /// if it panics.
+    ///
+    /// Each panic counts as a restart of the actor, so the factory is called again as long as
+    /// the restart policy allows it. Messages sent in the meantime stay queued. Once the policy
+    /// is exhausted the supervisor stops, reporting to the observer set with
+    /// [`on_failure()`](Self::on_failure).
+    pub fn new_retrying(factory: F) -> Self {
+        Self::with_factory(factory, Factory::repeat)
+    }
+}
@@ -0,0 +1,314 @@
+use std::{fmt, rc::Rc};
+
+use crate::{
+    actor::{Actor, ActorContext, AsyncContext, SpawnHandle},
+    address::{Addr, Closed, Recipient},
+    context::Context,
+    fut::{ActorFutureExt, WrapFuture},
+    handler::Message,
+    supervisor::RestartPolicy,
+};
+
+/// How a [`SupervisorGroup`] restarts its children when one of them exits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GroupStrategy {
+    /// Restart only the exited child.
+    #[default]
+    OneForOne,
+
+    /// Stop the other children and restart all of them.
+    OneForAll,
+}
+
+/// Sent to the observer of a [`SupervisorGroup`] when a child has exhausted its restart
+/// budget, right before the failure escalates to the group owning the child.
+///
+/// A [`Supervisor`](crate::Supervisor) sends it to the observer set with
+/// [`SupervisorBuilder::on_failure()`](crate::SupervisorBuilder::on_failure) once its factory
+/// panicked and may not be called again, `path` then only holds the name of the actor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SupervisionFailed {
+    /// Names of the groups from the root group down to the failed child, followed by the name
+    /// of the child.
+    pub path: Vec<String>,
+    /// Number of times the child was restarted before it failed.
+    pub restarts: usize,
+}
+
+impl Message for SupervisionFailed {
+    type Result = ();
+}
+
+/// Group of supervised children, which may be groups themselves.
+///
+/// The group starts its children once it is started and restarts them according to its
+/// [`GroupStrategy`] whenever one of them exits, whether it stopped or panicked. Each child
+/// may be restarted as often as allowed by the [`RestartPolicy`] of the group. A child
+/// exiting once more escalates: the group stops all its children and stops itself, which
+/// counts as an exit of the group for the group owning it. The owning group then restarts
+/// the whole subtree, with fresh restart budgets, or escalates in turn. A root group whose
+/// budget is exhausted just stops.
+///
+/// Children are stopped with [`ActorContext::stop()`] once their queued messages are handled,
+/// so an actor refusing to stop in [`Actor::stopping()`] keeps running after it was replaced.
+///
+/// # Examples
+///
+/// ```
+/// # use actix::prelude::*;
+/// struct Worker;
+///
+/// impl Actor for Worker {
+///     type Context = Context<Self>;
+/// }
+///
+/// # fn main() {
+/// #     System::new().block_on(async {
+/// let workers = SupervisorGroup::new("workers")
+///     .restart_policy(RestartPolicy::Limit(3))
+///     .child("first", || Worker.start())
+///     .child("second", || Worker.start());
+///
+/// let _root = SupervisorGroup::new("root")
+///     .strategy(GroupStrategy::OneForAll)
+///     .group(workers)
+///     .start();
+/// #     });
+/// # }
+/// ```
+pub struct SupervisorGroup {
+    spec: Rc<GroupSpec>,
+    /// Names of the groups from the root down to this group.
+    path: Vec<String>,
+    observer: Option<Recipient<SupervisionFailed>>,
+    children: Vec<RunningChild>,
+}
+
+/// Configuration of a group, shared by its restarts.
+struct GroupSpec {
+    name: String,
+    strategy: GroupStrategy,
+    policy: RestartPolicy,
+    observer: Option<Recipient<SupervisionFailed>>,
+    children: Vec<ChildSpec>,
+}
+
+struct ChildSpec {
+    name: String,
+    start: StartFn,
+}
+
+/// Starts a child of the group with the given path and observer.
+type StartFn = Rc<dyn Fn(&[String], Option<&Recipient<SupervisionFailed>>) -> Box<dyn Child>>;
+
+struct RunningChild {
+    child: Option<Box<dyn Child>>,
+    watch: Option<SpawnHandle>,
+    restarts: usize,
+}
+
+/// Address of a running child, with its actor type erased.
+trait Child {
+    fn stop(&self);
+
+    fn closed(&self) -> Closed;
+}
+
+impl<A> Child for Addr<A>
+where
+    A: Actor,
+    A::Context: AsyncContext<A>,
+{
+    fn stop(&self) {
+        self.request_stop();
+    }
+
+    fn closed(&self) -> Closed {
+        Addr::closed(self)
+    }
+}
+
+impl fmt::Debug for SupervisorGroup {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = self.spec.children.iter().map(|c| &c.name).collect();
+        fmt.debug_struct("SupervisorGroup")
+            .field("path", &self.path)
+            .field("strategy", &self.spec.strategy)
+            .field("policy", &self.spec.policy)
+            .field("children", &names)
+            .finish()
+    }
+}
+
+impl SupervisorGroup {
+    /// Creates an empty group, which restarts its children with
+    /// [`GroupStrategy::OneForOne`] and [`RestartPolicy::Always`].
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = name.into();
+        SupervisorGroup {
+            path: vec![name.clone()],
+            spec: Rc::new(GroupSpec {
+                name,
+                strategy: GroupStrategy::OneForOne,
+                policy: RestartPolicy::Always,
+                observer: None,
+                children: Vec::new(),
+            }),
+            observer: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Sets the strategy used to restart the children.
+    pub fn strategy(mut self, strategy: GroupStrategy) -> Self {
+        self.spec_mut().strategy = strategy;
+        self
+    }
+
+    /// Sets the restart budget of each child, by default children are always restarted.
+    ///
+    /// The budget is counted from the start of the group, a child exiting once it is
+    /// exhausted escalates the failure to the group owning this group.
+    pub fn restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.spec_mut().policy = policy;
+        self
+    }
+
+    /// Sends a [`SupervisionFailed`] to `observer` whenever a child of this group, or of the
+    /// groups nested into it, exhausts its restart budget.
+    ///
+    /// Nested groups without an observer of their own report to the observer of their owner.
+    pub fn on_failure(mut self, observer: Recipient<SupervisionFailed>) -> Self {
+        self.spec_mut().observer = Some(observer);
+        self
+    }
+
+    /// Adds a child started by `factory`, which is called again on every restart.
+    pub fn child<A, F>(mut self, name: impl Into<String>, factory: F) -> Self
+    where
+        A: Actor,
+        A::Context: AsyncContext<A>,
+        F: Fn() -> Addr<A> + 'static,
+    {
+        self.spec_mut().children.push(ChildSpec {
+            name: name.into(),
+            start: Rc::new(move |_, _| Box::new(factory())),
+        });
+        self
+    }
+
+    /// Adds a nested group as a child, which is restarted as a whole with its own children.
+    pub fn group(mut self, group: SupervisorGroup) -> Self {
+        let spec = group.spec;
+        let name = spec.name.clone();
+        self.spec_mut().children.push(ChildSpec {
+            name,
+            start: Rc::new(move |path, observer| {
+                let mut path = path.to_vec();
+                path.push(spec.name.clone());
+                let observer = spec.observer.as_ref().or(observer).cloned();
+                let group = SupervisorGroup {
+                    spec: Rc::clone(&spec),
+                    path,
+                    observer,
+                    children: Vec::new(),
+                };
+                Box::new(group.start())
+            }),
+        });
+        self
+    }
+
+    fn spec_mut(&mut self) -> &mut GroupSpec {
+        Rc::get_mut(&mut self.spec).expect("Group is not started yet")
+    }
+
+    fn start_child(&mut self, idx: usize, ctx: &mut Context<Self>) {
+        let spec = &self.spec.children[idx];
+        let child = (spec.start)(&self.path, self.observer.as_ref());
+        let watch = ctx.spawn(
+            child
+                .closed()
+                .into_actor(self)
+                .map(move |_, act, ctx| act.child_exited(idx, ctx)),
+        );
+        let running = &mut self.children[idx];
+        running.child = Some(child);
+        running.watch = Some(watch);
+    }
+
+    fn stop_child(&mut self, idx: usize, ctx: &mut Context<Self>) {
+        let running = &mut self.children[idx];
+        if let Some(watch) = running.watch.take() {
+            ctx.cancel_future(watch);
+        }
+        if let Some(child) = running.child.take() {
+            child.stop();
+        }
+    }
+
+    fn child_exited(&mut self, idx: usize, ctx: &mut Context<Self>) {
+        let running = &mut self.children[idx];
+        running.watch = None;
+        running.child = None;
+
+        let can_restart = match self.spec.policy {
+            RestartPolicy::Always => true,
+            RestartPolicy::Never => false,
+            RestartPolicy::Limit(max) => running.restarts < max,
+        };
+        if !can_restart {
+            let mut path = self.path.clone();
+            path.push(self.spec.children[idx].name.clone());
+            log::warn!("Supervised child {} failed, escalating", path.join("/"));
+            if let Some(ref observer) = self.observer {
+                observer.do_send(SupervisionFailed {
+                    path,
+                    restarts: running.restarts,
+                });
+            }
+            ctx.stop();
+            return;
+        }
+        running.restarts += 1;
+
+        match self.spec.strategy {
+            GroupStrategy::OneForOne => self.start_child(idx, ctx),
+            GroupStrategy::OneForAll => {
+                for idx in 0..self.children.len() {
+                    self.stop_child(idx, ctx);
+                }
+                for idx in 0..self.children.len() {
+                    self.start_child(idx, ctx);
+                }
+            }
+        }
+    }
+}
+
+impl Actor for SupervisorGroup {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if self.observer.is_none() {
+            self.observer = self.spec.observer.clone();
+        }
+        self.children = (0..self.spec.children.len())
+            .map(|_| RunningChild {
+                child: None,
+                watch: None,
+                restarts: 0,
+            })
+            .collect();
+        for idx in 0..self.children.len() {
+            self.start_child(idx, ctx);
+        }
+    }
+
+    fn stopped(&mut self, ctx: &mut Self::Context) {
+        for idx in 0..self.children.len() {
+            self.stop_child(idx, ctx);
+        }
+    }
+}
@@ -6,21 +6,46 @@
 //! Actor type A and B, sharing the same thread pool. You need to create two
 //! [`SyncArbiter`]s and have A and B spawn on unique `SyncArbiter`s respectively.
 //! For more information and examples, see `SyncArbiter`
-use std::{future::Future, pin::Pin, sync::Arc, task, task::Poll, thread};
+//!
+//! [`Semaphore`] and [`Mutex`] limit the use of a resource shared between actors without
+//! blocking their arbiters.
+use std::{
+    any::{type_name, Any, TypeId},
+    cell::UnsafeCell,
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    task,
+    task::Poll,
+    thread,
+    time::{Duration, Instant},
+};
 
 use actix_rt::System;
 use crossbeam_channel as cb_channel;
-use futures_core::stream::Stream;
+use futures_core::{ready, stream::Stream};
 use log::warn;
-use tokio::sync::oneshot::Sender as SyncSender;
+use tokio::sync::mpsc;
 
 use crate::{
-    actor::{Actor, ActorContext, ActorState, Running},
+    actor::{Actor, ActorContext, ActorState, AsyncContext, ResourceHandle, Running},
     address::{
-        channel, Addr, AddressReceiver, AddressSenderProducer, Envelope, EnvelopeProxy, ToEnvelope,
+        channel, Addr, AddressReceiver, AddressSenderProducer, Envelope, EnvelopeProxy, Recipient,
+        SendError, ToEnvelope,
     },
     context::Context,
-    handler::{Handler, Message, MessageResponse},
+    fut::ActorFuture,
+    handler::{Handler, Message, MessageResponse, MessageResult},
+    queue::{self, OneshotReceiver, OneshotSender as SyncSender, TrySendError},
 };
 
 /// [`SyncArbiter`] provides the resources for a single Sync Actor to run on a dedicated
@@ -96,6 +121,8 @@ where
 {
     queue: Option<cb_channel::Sender<Envelope<A>>>,
     msgs: AddressReceiver<A>,
+    /// State of an autoscaling pool, whose envelopes are timed.
+    pool: Option<Arc<PoolState>>,
 }
 
 impl<A> SyncArbiter<A>
@@ -149,10 +176,96 @@ where
         System::current().arbiter().spawn(Self {
             queue: Some(sender),
             msgs: rx,
+            pool: None,
         });
 
         Addr::new(tx)
     }
+
+    /// Start a new `SyncArbiter` whose number of worker threads follows its load.
+    ///
+    /// The pool starts with `config.min` threads. The time messages wait in the queue of the
+    /// pool is recorded, and every `config.evaluation_interval` the returned
+    /// [`SyncPoolControl`] adds a thread if the 95th percentile of the waits since its last
+    /// evaluation exceeds `config.target_wait`, up to `config.max` threads. Threads which stay
+    /// idle for `config.idle_period` beyond `config.max_idle` are retired, down to
+    /// `config.min` threads. A retired thread finishes the messages queued before its
+    /// retirement, after which its actor is stopped.
+    ///
+    /// The current size and recent waits of the pool are returned by sending [`PoolStats`] to
+    /// the control actor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `config.max` is zero or less than `config.min`.
+    pub fn start_autoscaling<F>(
+        config: AutoscaleConfig,
+        factory: F,
+    ) -> (Addr<A>, Addr<SyncPoolControl>)
+    where
+        F: Fn() -> A + Send + Sync + 'static,
+    {
+        assert!(
+            config.max > 0 && config.min <= config.max,
+            "invalid autoscaling bounds: min {}, max {}",
+            config.min,
+            config.max
+        );
+
+        let factory: Arc<dyn Fn() -> A + Send + Sync> = Arc::new(factory);
+        let (sender, receiver) = cb_channel::unbounded();
+        let (tx, rx) = channel::channel(0);
+        let pool = Arc::new(PoolState::default());
+
+        let spawn = {
+            let sys = System::current();
+            let producer = rx.sender_producer();
+            let pool = Arc::clone(&pool);
+            move || {
+                let f = Arc::clone(&factory);
+                let sys = sys.clone();
+                let actor_queue = receiver.clone();
+                let inner_rx = producer.clone();
+
+                pool.size.fetch_add(1, Ordering::SeqCst);
+                thread::Builder::new()
+                    .spawn(move || {
+                        System::set_current(sys);
+                        SyncContext::new(f, actor_queue, inner_rx).run();
+                    })
+                    .expect("failed to spawn thread");
+            }
+        };
+        let retire = {
+            let queue = sender.clone();
+            move || {
+                let _ = queue.send(Envelope::with_proxy(Box::new(RetireEnvelope)));
+            }
+        };
+
+        for _ in 0..config.min {
+            spawn();
+        }
+
+        let control = SyncPoolControl {
+            config,
+            pool: Arc::clone(&pool),
+            spawn: Box::new(spawn),
+            retire: Box::new(retire),
+            evaluated_at: Instant::now(),
+            idle_since: None,
+        };
+        let arbiter = System::current().arbiter().clone();
+        let control = SyncPoolControl::start_in_arbiter(&arbiter, |_| control);
+
+        arbiter.spawn(Self {
+            queue: Some(sender),
+            msgs: rx,
+            pool: Some(pool),
+        });
+
+        (Addr::new(tx), control)
+    }
 }
 
 impl<A> Actor for SyncArbiter<A>
@@ -174,6 +287,10 @@ where
         loop {
             match Pin::new(&mut this.msgs).poll_next(cx) {
                 Poll::Ready(Some(msg)) => {
+                    let msg = match this.pool {
+                        Some(ref pool) => TimedEnvelope::wrap(msg, pool),
+                        None => msg,
+                    };
                     if let Some(ref queue) = this.queue {
                         assert!(queue.send(msg).is_ok());
                     }
@@ -189,6 +306,9 @@ where
         } else {
             // stop sync arbiters
             this.queue = None;
+            if let Some(ref pool) = this.pool {
+                pool.closed.store(true, Ordering::SeqCst);
+            }
             Poll::Ready(())
         }
     }
@@ -244,6 +364,17 @@ where
     state: ActorState,
     factory: Arc<dyn Fn() -> A>,
     address: AddressSenderProducer<A>,
+    /// Progress recipient of the message being handled.
+    progress: Option<Box<dyn Any>>,
+    /// Reply sender of the message being handled, until it is taken by `chunked_reply()`.
+    reply: Option<Box<dyn Any>>,
+    chunk_capacity: usize,
+    chunk_policy: ChunkPolicy,
+    /// Set when the thread is retired by its autoscaling pool.
+    retiring: bool,
+    scope: Option<Box<dyn EnvelopeScope<A>>>,
+    /// Mappers of scope errors registered with `map_scope_error()`, keyed by message type.
+    scope_mappers: HashMap<TypeId, Box<dyn Any>>,
 }
 
 impl<A> SyncContext<A>
@@ -263,6 +394,13 @@ where
             stopping: false,
             state: ActorState::Started,
             address,
+            progress: None,
+            reply: None,
+            chunk_capacity: DEFAULT_CHUNK_CAPACITY,
+            chunk_policy: ChunkPolicy::Block,
+            retiring: false,
+            scope: None,
+            scope_mappers: HashMap::new(),
         }
     }
 
@@ -289,6 +427,14 @@ where
                 }
             }
 
+            if self.retiring {
+                self.state = ActorState::Stopping;
+                A::stopping(&mut act, self);
+                self.state = ActorState::Stopped;
+                A::stopped(&mut act, self);
+                return;
+            }
+
             if self.stopping {
                 self.stopping = false;
 
@@ -309,6 +455,193 @@ where
     pub fn address(&self) -> Addr<A> {
         Addr::new(self.address.sender())
     }
+
+    /// Returns a sender reporting progress of the message being handled to its caller.
+    ///
+    /// Returns `None` unless the message was sent with [`Addr::send_with_progress()`] and
+    /// progress of type `P`.
+    pub fn progress<P: Send + 'static>(&self) -> Option<ProgressSender<P>> {
+        let rcp = self
+            .progress
+            .as_ref()?
+            .downcast_ref::<Recipient<Progress<P>>>()?;
+        Some(ProgressSender { rcp: rcp.clone() })
+    }
+
+    /// Replies to the message being handled with a stream of chunks, returning the sender of
+    /// the chunks.
+    ///
+    /// The caller receives the [`Chunked`] stream right away and takes the chunks as they are
+    /// sent, so a large result is never held in memory as a whole. At most `capacity` chunks
+    /// set with [`set_chunk_buffer()`](Self::set_chunk_buffer) are buffered before the caller
+    /// takes them. The handler returns [`Response::chunked()`](crate::Response::chunked), the
+    /// stream ends once the sender is dropped.
+    ///
+    /// Returns `None` unless the result of the message is a `Chunked<T>` and the caller waits
+    /// for it, or if the reply was already taken.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use actix::{prelude::*, sync::Chunked};
+    /// use futures_util::stream::StreamExt as _;
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "Chunked<u64>")]
+    /// struct Squares(u64);
+    ///
+    /// struct Worker;
+    ///
+    /// impl Actor for Worker {
+    ///     type Context = SyncContext<Self>;
+    /// }
+    ///
+    /// impl Handler<Squares> for Worker {
+    ///     type Result = Response<Chunked<u64>>;
+    ///
+    ///     fn handle(&mut self, Squares(n): Squares, ctx: &mut Self::Context) -> Self::Result {
+    ///         if let Some(chunks) = ctx.chunked_reply::<u64>() {
+    ///             for i in 1..=n {
+    ///                 if chunks.send(i * i).is_err() {
+    ///                     // the caller is gone
+    ///                     break;
+    ///                 }
+    ///             }
+    ///         }
+    ///         Response::chunked()
+    ///     }
+    /// }
+    ///
+    /// # #[actix::main]
+    /// # async fn main() {
+    /// let worker = SyncArbiter::start(1, || Worker);
+    /// let squares = worker.send(Squares(3)).await.unwrap();
+    /// let squares: Vec<_> = squares.map(Result::unwrap).collect().await;
+    /// assert_eq!(squares, vec![1, 4, 9]);
+    /// # }
+    /// ```
+    pub fn chunked_reply<T: Send + 'static>(&mut self) -> Option<ChunkSender<T>> {
+        let tx = self
+            .reply
+            .take()?
+            .downcast::<SyncSender<Chunked<T>>>()
+            .map_err(|tx| self.reply = Some(tx))
+            .ok()?;
+
+        let (chunks, rx) = queue::channel(self.chunk_capacity);
+        let panicked = Arc::new(AtomicBool::new(false));
+        let _ = tx.send(Chunked {
+            rx,
+            panicked: Arc::clone(&panicked),
+            done: false,
+        });
+        Some(ChunkSender {
+            tx: chunks,
+            policy: self.chunk_policy,
+            panicked,
+        })
+    }
+
+    /// Sets the number of chunks of a [`chunked_reply()`](Self::chunked_reply) buffered before
+    /// the caller takes them, and what the [`ChunkSender`] does once the buffer is full.
+    ///
+    /// Defaults to 16 chunks and [`ChunkPolicy::Block`]. The settings stay set when the actor
+    /// is restarted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn set_chunk_buffer(&mut self, capacity: usize, policy: ChunkPolicy) {
+        assert!(capacity > 0, "chunk buffer capacity must be positive");
+        self.chunk_capacity = capacity;
+        self.chunk_policy = policy;
+    }
+
+    /// Takes back the reply sender of the message being handled, unless it was taken by
+    /// `chunked_reply()`.
+    fn take_reply<R: 'static>(&mut self) -> Option<SyncSender<R>> {
+        self.reply
+            .take()
+            .and_then(|tx| tx.downcast::<SyncSender<R>>().ok())
+            .map(|tx| *tx)
+    }
+
+    /// Sets the scope run around each message handled by this worker, replacing the previous
+    /// one.
+    ///
+    /// The scope stays set when the actor is restarted.
+    pub fn set_envelope_scope(&mut self, scope: Box<dyn EnvelopeScope<A>>) {
+        self.scope = Some(scope);
+    }
+
+    /// Declares how handling `M` fails within an [`EnvelopeScope`].
+    ///
+    /// An `Err` returned by the handler of `M` is passed to
+    /// [`EnvelopeScope::after_err()`] instead of calling [`EnvelopeScope::after_ok()`], and
+    /// an error returned by `after_ok()` is converted by `f` into the error replied to the
+    /// caller. Without a mapper, any result of `M` counts as a success, and the reply is
+    /// dropped if `after_ok()` fails, so the request fails with
+    /// [`MailboxError::Closed`](crate::MailboxError::Closed).
+    pub fn map_scope_error<M, T, E, F>(&mut self, f: F)
+    where
+        M: Message<Result = Result<T, E>> + 'static,
+        T: 'static,
+        E: 'static,
+        F: Fn(ScopeError) -> E + 'static,
+    {
+        let mapper: Box<dyn ScopeMapper<Result<T, E>>> = Box::new(ErrMapper(f));
+        self.scope_mappers
+            .insert(TypeId::of::<M>(), Box::new(mapper));
+    }
+}
+
+/// Error of [`EnvelopeScope::after_ok()`].
+pub type ScopeError = Box<dyn Error + Send + Sync>;
+
+/// Hooks run around each message handled by a sync actor, e.g. to handle every message in
+/// a database transaction, set with [`SyncContext::set_envelope_scope()`].
+///
+/// The hooks run on the worker thread, right before and after the handler.
+pub trait EnvelopeScope<A>: 'static {
+    /// Called before the handler, e.g. to begin a transaction.
+    fn before(&mut self, act: &mut A);
+
+    /// Called once the handler succeeded, e.g. to commit the transaction.
+    ///
+    /// An error fails the request, see [`SyncContext::map_scope_error()`].
+    fn after_ok(&mut self, act: &mut A) -> Result<(), ScopeError>;
+
+    /// Called once the handler failed, e.g. to roll the transaction back.
+    ///
+    /// `cause` is the panic payload if the handler panicked, or the `Err` returned by the
+    /// handler of a message with a mapper registered with
+    /// [`SyncContext::map_scope_error()`]. A panic resumes once this method returns.
+    fn after_err(&mut self, act: &mut A, cause: &dyn Any);
+}
+
+/// Scope hooks of the results of a message type.
+trait ScopeMapper<R> {
+    /// Returns the error of a failed result.
+    fn error<'a>(&self, res: &'a R) -> Option<&'a dyn Any>;
+
+    /// Converts an error of `EnvelopeScope::after_ok()` into a result.
+    fn map(&self, err: ScopeError) -> R;
+}
+
+struct ErrMapper<F>(F);
+
+impl<T, E, F> ScopeMapper<Result<T, E>> for ErrMapper<F>
+where
+    E: 'static,
+    F: Fn(ScopeError) -> E,
+{
+    fn error<'a>(&self, res: &'a Result<T, E>) -> Option<&'a dyn Any> {
+        res.as_ref().err().map(|err| err as &dyn Any)
+    }
+
+    fn map(&self, err: ScopeError) -> Result<T, E> {
+        Err((self.0)(err))
+    }
 }
 
 impl<A> ActorContext for SyncContext<A>
@@ -366,9 +699,1093 @@ where
         }
 
         if let Some(msg) = self.msg.take() {
-            <A as Handler<M>>::handle(act, msg, ctx).handle(ctx, tx)
+            ctx.reply = tx.map(|tx| Box::new(tx) as Box<dyn Any>);
+            match ctx.scope.take() {
+                Some(scope) => handle_scoped(act, ctx, scope, msg),
+                None => {
+                    let res = <A as Handler<M>>::handle(act, msg, ctx);
+                    let tx = ctx.take_reply();
+                    res.handle(ctx, tx);
+                }
+            }
+        }
+    }
+
+    fn message_info(&self) -> Option<(&'static str, bool)> {
+        Some((type_name::<M>(), self.tx.is_some()))
+    }
+}
+
+/// Handles `msg` within `scope`, replying once the scope has run its hooks.
+///
+/// A chunked reply is sent by the handler, before the scope has run its hooks.
+fn handle_scoped<A, M>(
+    act: &mut A,
+    ctx: &mut SyncContext<A>,
+    mut scope: Box<dyn EnvelopeScope<A>>,
+    msg: M,
+) where
+    M: Message + Send + 'static,
+    M::Result: Send,
+    A: Actor<Context = SyncContext<A>> + Handler<M>,
+{
+    scope.before(act);
+
+    let (res_tx, mut res_rx) = queue::oneshot();
+    let handled = panic::catch_unwind(AssertUnwindSafe(|| {
+        let res = <A as Handler<M>>::handle(act, msg, ctx);
+        let tx = ctx.take_reply();
+        res.handle(ctx, Some(res_tx));
+        tx
+    }));
+    let tx = match handled {
+        Ok(tx) => tx,
+        Err(payload) => {
+            scope.after_err(act, &*payload);
+            ctx.scope.get_or_insert(scope);
+            panic::resume_unwind(payload);
+        }
+    };
+
+    let mapper = ctx
+        .scope_mappers
+        .get(&TypeId::of::<M>())
+        .and_then(|mapper| mapper.downcast_ref::<Box<dyn ScopeMapper<M::Result>>>());
+    let res = res_rx.try_recv();
+    let failed = match (&res, mapper) {
+        (Some(res), Some(mapper)) => mapper.error(res),
+        _ => None,
+    };
+    let res = match failed {
+        Some(err) => {
+            scope.after_err(act, err);
+            res
+        }
+        None => match scope.after_ok(act) {
+            Ok(()) => res,
+            Err(err) => mapper.map(|mapper| mapper.map(err)),
+        },
+    };
+
+    // the handler could have set another scope
+    ctx.scope.get_or_insert(scope);
+    if let (Some(tx), Some(res)) = (tx, res) {
+        let _ = tx.send(res);
+    }
+}
+
+/// Progress of a message handled by a sync actor, see [`Addr::send_with_progress()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress<P>(pub P);
+
+impl<P: 'static> Message for Progress<P> {
+    type Result = ();
+}
+
+/// Reports progress of a message to its caller, see [`SyncContext::progress()`].
+///
+/// Progress is queued in the caller's mailbox without blocking the sync actor, regardless of
+/// the mailbox capacity. It is dropped once the caller has stopped.
+pub struct ProgressSender<P: Send + 'static> {
+    rcp: Recipient<Progress<P>>,
+}
+
+impl<P: Send + 'static> ProgressSender<P> {
+    /// Reports progress to the caller.
+    pub fn send(&self, progress: P) {
+        self.rcp.do_send(Progress(progress));
+    }
+
+    /// Returns whether the caller is still running.
+    pub fn connected(&self) -> bool {
+        self.rcp.connected()
+    }
+}
+
+impl<P: Send + 'static> Clone for ProgressSender<P> {
+    fn clone(&self) -> Self {
+        ProgressSender {
+            rcp: self.rcp.clone(),
+        }
+    }
+}
+
+impl<P: Send + 'static> fmt::Debug for ProgressSender<P> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ProgressSender")
+            .field("rcp", &self.rcp)
+            .finish()
+    }
+}
+
+/// Envelope which exposes a progress recipient to the handler through its `SyncContext`.
+pub(crate) struct ProgressEnvelope<M, P>
+where
+    M: Message + Send,
+    P: Send + 'static,
+{
+    env: SyncContextEnvelope<M>,
+    progress: Option<Recipient<Progress<P>>>,
+}
+
+impl<M, P> ProgressEnvelope<M, P>
+where
+    M: Message + Send + 'static,
+    M::Result: Send,
+    P: Send + 'static,
+{
+    pub(crate) fn pack<A>(
+        msg: M,
+        tx: Option<SyncSender<M::Result>>,
+        progress: Recipient<Progress<P>>,
+    ) -> Envelope<A>
+    where
+        A: Actor<Context = SyncContext<A>> + Handler<M>,
+    {
+        Envelope::with_proxy(Box::new(ProgressEnvelope {
+            env: SyncContextEnvelope::new(msg, tx),
+            progress: Some(progress),
+        }))
+    }
+}
+
+impl<A, M, P> EnvelopeProxy<A> for ProgressEnvelope<M, P>
+where
+    M: Message + Send + 'static,
+    M::Result: Send,
+    P: Send + 'static,
+    A: Actor<Context = SyncContext<A>> + Handler<M>,
+{
+    fn handle(&mut self, act: &mut A, ctx: &mut A::Context) {
+        ctx.progress = self
+            .progress
+            .take()
+            .map(|rcp| Box::new(rcp) as Box<dyn Any>);
+        self.env.handle(act, ctx);
+        ctx.progress = None;
+    }
+
+    fn message_info(&self) -> Option<(&'static str, bool)> {
+        EnvelopeProxy::<A>::message_info(&self.env)
+    }
+}
+
+/// Default number of chunks buffered by a chunked reply.
+const DEFAULT_CHUNK_CAPACITY: usize = 16;
+
+/// What a [`ChunkSender`] does once its buffer is full, see
+/// [`SyncContext::set_chunk_buffer()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkPolicy {
+    /// Blocks the thread of the sync actor until the caller takes a chunk.
+    Block,
+    /// Returns the chunk with [`SendError::Full`](crate::prelude::SendError::Full).
+    Fail,
+}
+
+/// Sends the chunks of a reply to its caller, see [`SyncContext::chunked_reply()`].
+///
+/// The reply ends once the sender is dropped. If it is dropped by a panic, the [`Chunked`]
+/// stream of the caller ends with [`ChunkError::Panicked`].
+pub struct ChunkSender<T: Send + 'static> {
+    tx: queue::Sender<T>,
+    policy: ChunkPolicy,
+    panicked: Arc<AtomicBool>,
+}
+
+impl<T: Send + 'static> ChunkSender<T> {
+    /// Sends a chunk to the caller.
+    ///
+    /// Fails with [`SendError::Closed`](crate::prelude::SendError::Closed) once the caller dropped
+    /// the stream, or with [`SendError::Full`](crate::prelude::SendError::Full) if the buffer is full
+    /// and the policy is [`ChunkPolicy::Fail`].
+    pub fn send(&self, chunk: T) -> Result<(), SendError<T>> {
+        match self.policy {
+            ChunkPolicy::Block => self
+                .tx
+                .blocking_send(chunk)
+                .map_err(|err| SendError::Closed(err.0)),
+            ChunkPolicy::Fail => self.tx.try_send(chunk).map_err(|err| match err {
+                TrySendError::Full(chunk) => SendError::Full(chunk),
+                TrySendError::Closed(chunk) => SendError::Closed(chunk),
+            }),
         }
     }
+
+    /// Returns whether the caller still holds the stream.
+    pub fn connected(&self) -> bool {
+        !self.tx.is_closed()
+    }
+}
+
+impl<T: Send + 'static> Drop for ChunkSender<T> {
+    fn drop(&mut self) {
+        // set before the channel closes
+        if thread::panicking() {
+            self.panicked.store(true, Ordering::Release);
+        }
+    }
+}
+
+impl<T: Send + 'static> fmt::Debug for ChunkSender<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ChunkSender")
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+/// Stream of the chunks of a reply sent by a sync actor, see
+/// [`SyncContext::chunked_reply()`].
+///
+/// Dropping the stream makes further sends of the sync actor fail.
+pub struct Chunked<T> {
+    rx: queue::Receiver<T>,
+    panicked: Arc<AtomicBool>,
+    done: bool,
+}
+
+impl<T> fmt::Debug for Chunked<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Chunked")
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+impl<T> Stream for Chunked<T> {
+    type Item = Result<T, ChunkError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+        match ready!(Pin::new(&mut this.rx).poll_next(cx)) {
+            Some(chunk) => Poll::Ready(Some(Ok(chunk))),
+            None => {
+                this.done = true;
+                if this.panicked.load(Ordering::Acquire) {
+                    Poll::Ready(Some(Err(ChunkError::Panicked)))
+                } else {
+                    Poll::Ready(None)
+                }
+            }
+        }
+    }
+}
+
+/// The errors ending a [`Chunked`] stream early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ChunkError {
+    /// The handler panicked before it finished the reply.
+    Panicked,
+}
+
+impl fmt::Display for ChunkError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkError::Panicked => write!(fmt, "Chunked reply panicked"),
+        }
+    }
+}
+
+impl std::error::Error for ChunkError {}
+
+/// Number of waits kept for [`PoolStats`].
+const RECENT_WAITS: usize = 256;
+
+/// Load of an autoscaling [`SyncArbiter`], shared by its threads and its control actor.
+#[derive(Default)]
+struct PoolState {
+    /// Number of threads, not counting retired ones which are still finishing their messages.
+    size: AtomicUsize,
+    busy: AtomicUsize,
+    queued: AtomicUsize,
+    /// Most recent queue waits, with the time their message was dequeued.
+    waits: parking_lot::Mutex<VecDeque<(Instant, Duration)>>,
+    /// Set once all addresses of the pool are dropped.
+    closed: AtomicBool,
+}
+
+impl PoolState {
+    fn record(&self, wait: Duration) {
+        let mut waits = self.waits.lock();
+        if waits.len() == RECENT_WAITS {
+            waits.pop_front();
+        }
+        waits.push_back((Instant::now(), wait));
+    }
+
+    /// Returns the sorted waits of messages dequeued after `since`.
+    fn waits_since(&self, since: Option<Instant>) -> Vec<Duration> {
+        let mut waits: Vec<_> = self
+            .waits
+            .lock()
+            .iter()
+            .filter(|(at, _)| since.map_or(true, |since| *at > since))
+            .map(|(_, wait)| *wait)
+            .collect();
+        waits.sort_unstable();
+        waits
+    }
+}
+
+/// Nearest-rank percentile of sorted `waits`.
+fn percentile(waits: &[Duration], pct: usize) -> Duration {
+    match waits.len() {
+        0 => Duration::ZERO,
+        len => waits[((len * pct + 99) / 100).max(1) - 1],
+    }
+}
+
+/// Envelope recording how long it waited in the queue of an autoscaling pool.
+struct TimedEnvelope<A: Actor> {
+    env: Envelope<A>,
+    queued_at: Instant,
+    pool: Arc<PoolState>,
+}
+
+impl<A> TimedEnvelope<A>
+where
+    A: Actor<Context = SyncContext<A>>,
+{
+    fn wrap(env: Envelope<A>, pool: &Arc<PoolState>) -> Envelope<A> {
+        pool.queued.fetch_add(1, Ordering::SeqCst);
+        Envelope::with_proxy(Box::new(TimedEnvelope {
+            env,
+            queued_at: Instant::now(),
+            pool: Arc::clone(pool),
+        }))
+    }
+}
+
+impl<A> EnvelopeProxy<A> for TimedEnvelope<A>
+where
+    A: Actor<Context = SyncContext<A>>,
+{
+    fn handle(&mut self, act: &mut A, ctx: &mut A::Context) {
+        self.pool.queued.fetch_sub(1, Ordering::SeqCst);
+        self.pool.record(self.queued_at.elapsed());
+
+        self.pool.busy.fetch_add(1, Ordering::SeqCst);
+        self.env.handle(act, ctx);
+        self.pool.busy.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    fn message_info(&self) -> Option<(&'static str, bool)> {
+        self.env.message_info()
+    }
+}
+
+/// Envelope stopping the thread which handles it.
+struct RetireEnvelope;
+
+impl<A> EnvelopeProxy<A> for RetireEnvelope
+where
+    A: Actor<Context = SyncContext<A>>,
+{
+    fn handle(&mut self, _: &mut A, ctx: &mut A::Context) {
+        ctx.retiring = true;
+    }
+}
+
+/// Scaling rules of a pool started with [`SyncArbiter::start_autoscaling()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoscaleConfig {
+    /// Minimum number of threads, started right away.
+    pub min: usize,
+    /// Maximum number of threads.
+    pub max: usize,
+    /// 95th percentile of the queue waits above which a thread is added.
+    pub target_wait: Duration,
+    /// Interval between two scaling decisions.
+    pub evaluation_interval: Duration,
+    /// Number of idle threads which are kept.
+    pub max_idle: usize,
+    /// How long more than `max_idle` threads have to stay idle before the extra ones are
+    /// retired.
+    pub idle_period: Duration,
+}
+
+/// Actor taking the scaling decisions of a pool started with
+/// [`SyncArbiter::start_autoscaling()`].
+///
+/// It stops once all addresses of its pool are dropped, at its next evaluation.
+pub struct SyncPoolControl {
+    config: AutoscaleConfig,
+    pool: Arc<PoolState>,
+    spawn: Box<dyn Fn() + Send>,
+    retire: Box<dyn Fn() + Send>,
+    evaluated_at: Instant,
+    idle_since: Option<Instant>,
+}
+
+impl SyncPoolControl {
+    fn evaluate(&mut self, ctx: &mut Context<Self>) {
+        if self.pool.closed.load(Ordering::SeqCst) {
+            return ctx.stop();
+        }
+
+        let now = Instant::now();
+        let waits = self.pool.waits_since(Some(self.evaluated_at));
+        self.evaluated_at = now;
+
+        let size = self.pool.size.load(Ordering::SeqCst);
+        let busy = self.pool.busy.load(Ordering::SeqCst);
+        // a queue which does not move at all records no waits
+        let stalled =
+            waits.is_empty() && busy >= size && self.pool.queued.load(Ordering::SeqCst) > 0;
+
+        if size < self.config.max && (stalled || percentile(&waits, 95) > self.config.target_wait) {
+            self.idle_since = None;
+            (self.spawn)();
+            return;
+        }
+
+        let idle = size.saturating_sub(busy);
+        if idle <= self.config.max_idle || size <= self.config.min {
+            self.idle_since = None;
+            return;
+        }
+
+        let idle_since = *self.idle_since.get_or_insert(now);
+        if now.duration_since(idle_since) >= self.config.idle_period {
+            self.idle_since = None;
+            let retired = (idle - self.config.max_idle).min(size - self.config.min);
+            self.pool.size.fetch_sub(retired, Ordering::SeqCst);
+            for _ in 0..retired {
+                (self.retire)();
+            }
+        }
+    }
+}
+
+impl Actor for SyncPoolControl {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(self.config.evaluation_interval, Self::evaluate);
+    }
+}
+
+impl fmt::Debug for SyncPoolControl {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("SyncPoolControl")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+/// Message returning the current [`PoolStatus`] of the pool of a [`SyncPoolControl`].
+#[derive(Debug, Clone, Copy)]
+pub struct PoolStats;
+
+impl Message for PoolStats {
+    type Result = PoolStatus;
+}
+
+/// Size and recent queue waits of an autoscaling pool, see [`PoolStats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PoolStatus {
+    /// Number of threads, not counting retired ones which are still finishing their messages.
+    pub size: usize,
+    /// Number of threads handling a message.
+    pub busy: usize,
+    /// Number of messages waiting for a thread.
+    pub queued: usize,
+    /// Number of recent waits the statistics below are computed from.
+    pub samples: usize,
+    /// Median of the recent queue waits.
+    pub wait_p50: Duration,
+    /// 95th percentile of the recent queue waits.
+    pub wait_p95: Duration,
+    /// Longest of the recent queue waits.
+    pub wait_max: Duration,
+}
+
+impl Handler<PoolStats> for SyncPoolControl {
+    type Result = MessageResult<PoolStats>;
+
+    fn handle(&mut self, _: PoolStats, _: &mut Self::Context) -> Self::Result {
+        let waits = self.pool.waits_since(None);
+        MessageResult(PoolStatus {
+            size: self.pool.size.load(Ordering::SeqCst),
+            busy: self.pool.busy.load(Ordering::SeqCst),
+            queued: self.pool.queued.load(Ordering::SeqCst),
+            samples: waits.len(),
+            wait_p50: percentile(&waits, 50),
+            wait_p95: percentile(&waits, 95),
+            wait_max: waits.last().copied().unwrap_or_default(),
+        })
+    }
+}
+
+/// Pool of workers running closures on a [`SyncArbiter`].
+///
+/// Jobs are closures receiving exclusive access to a worker, so no message type needs to be
+/// defined per job. Each worker thread owns one worker, created by the pool's factory.
+///
+/// ```
+/// use actix::sync::Pool;
+///
+/// struct Hasher {
+///     seed: u64,
+/// }
+///
+/// # #[actix::main]
+/// # async fn main() {
+/// let pool = Pool::new(2, || Hasher { seed: 7 });
+/// let res = pool.exec(|hasher| hasher.seed * 6).await;
+/// assert_eq!(res, Ok(42));
+///
+/// pool.shutdown().await;
+/// # }
+/// ```
+pub struct Pool<W: 'static> {
+    addr: Addr<PoolWorker<W>>,
+    ordered: parking_lot::Mutex<Option<OneshotReceiver<()>>>,
+    in_flight: mpsc::Sender<()>,
+    idle: mpsc::Receiver<()>,
+}
+
+impl<W: 'static> Pool<W> {
+    /// Starts a pool of `size` worker threads, each owning a worker created by `factory`.
+    pub fn new<F>(size: usize, factory: F) -> Self
+    where
+        F: Fn() -> W + Send + Sync + 'static,
+    {
+        let (in_flight, idle) = mpsc::channel(1);
+        Pool {
+            addr: SyncArbiter::start(size, move || PoolWorker(Box::new(factory()))),
+            ordered: parking_lot::Mutex::new(None),
+            in_flight,
+            idle,
+        }
+    }
+
+    /// Runs `f` on the next available worker and returns its result.
+    ///
+    /// The job is queued right away, even if the returned future is not polled. Like a request
+    /// sent to an actor, it is skipped if the future was dropped before a worker picked it up.
+    ///
+    /// If `f` panics, the future resolves with [`PoolError::Panicked`] and the worker is
+    /// replaced by a new one.
+    pub fn exec<F, R>(&self, f: F) -> impl Future<Output = Result<R, PoolError>>
+    where
+        F: FnOnce(&mut W) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let req = self.addr.send_queued(ExecJob {
+            f: Box::new(f),
+            _in_flight: self.in_flight.clone(),
+        });
+        async move { req.await.unwrap_or(Err(PoolError::Closed)) }
+    }
+
+    /// Runs `f` on the next available worker, like [`exec()`](Self::exec), but resolves only
+    /// after the jobs previously submitted with `exec_ordered` have resolved.
+    ///
+    /// Jobs still run concurrently, only their results are delivered in submission order.
+    /// Must be called from within a running system.
+    pub fn exec_ordered<F, R>(&self, f: F) -> impl Future<Output = Result<R, PoolError>>
+    where
+        F: FnOnce(&mut W) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (done_tx, done_rx) = queue::oneshot();
+        let prev = self.ordered.lock().replace(done_rx);
+
+        let (tx, rx) = queue::oneshot();
+        let res = self.exec(f);
+        actix_rt::spawn(async move {
+            let res = res.await;
+            if let Some(prev) = prev {
+                let _ = prev.await;
+            }
+            let _ = tx.send(res);
+            let _ = done_tx.send(());
+        });
+
+        async move { rx.await.unwrap_or(Err(PoolError::Closed)) }
+    }
+
+    /// Stops the pool once all submitted jobs have completed.
+    ///
+    /// Jobs which are already queued are still run, following the shutdown of a
+    /// [`SyncArbiter`] whose addresses are dropped.
+    pub async fn shutdown(self) {
+        let Pool {
+            addr,
+            in_flight,
+            mut idle,
+            ..
+        } = self;
+        drop(addr);
+        drop(in_flight);
+
+        // every job holds an `in_flight` sender until it is dropped
+        let _ = idle.recv().await;
+    }
+}
+
+impl<W: 'static> fmt::Debug for Pool<W> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Pool").field("addr", &self.addr).finish()
+    }
+}
+
+/// The errors that can occur when running a job on a [`Pool`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolError {
+    /// The job panicked.
+    Panicked,
+    /// The pool's workers are gone.
+    Closed,
+}
+
+impl fmt::Display for PoolError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PoolError::Panicked => write!(fmt, "Pool job panicked"),
+            PoolError::Closed => write!(fmt, "Pool has closed"),
+        }
+    }
+}
+
+impl std::error::Error for PoolError {}
+
+/// Sync actor owning a worker of a [`Pool`].
+struct PoolWorker<W>(Box<W>);
+
+impl<W: 'static> Actor for PoolWorker<W> {
+    type Context = SyncContext<Self>;
+}
+
+/// Job running a closure on a worker of a [`Pool`].
+struct ExecJob<W, R> {
+    f: Box<dyn FnOnce(&mut W) -> R + Send>,
+    _in_flight: mpsc::Sender<()>,
+}
+
+impl<W: 'static, R: 'static> Message for ExecJob<W, R> {
+    type Result = Result<R, PoolError>;
+}
+
+impl<W: 'static, R: Send + 'static> Handler<ExecJob<W, R>> for PoolWorker<W> {
+    type Result = Result<R, PoolError>;
+
+    fn handle(&mut self, job: ExecJob<W, R>, ctx: &mut Self::Context) -> Self::Result {
+        let worker = &mut *self.0;
+        panic::catch_unwind(AssertUnwindSafe(move || (job.f)(worker))).map_err(|_| {
+            // the worker may have been left in an inconsistent state
+            ctx.stop();
+            PoolError::Panicked
+        })
+    }
+}
+
+/// An asynchronous semaphore limiting how many actors use a shared resource at once.
+///
+/// The semaphore is cheaply cloneable and can be shared between arbiters. Waiting for a permit
+/// does not block the arbiter, and permits are handed out in the order they were requested. A
+/// [`Permit`] is returned to the semaphore when it is dropped; attach it to an actor with
+/// [`acquire_attached()`](Self::acquire_attached) to return it when the actor stops.
+///
+/// # Examples
+///
+/// ```
+/// use actix::{prelude::*, sync::Semaphore};
+///
+/// struct Renderer {
+///     licenses: Semaphore,
+/// }
+///
+/// impl Actor for Renderer {
+///     type Context = Context<Self>;
+/// }
+///
+/// #[derive(Message)]
+/// #[rtype(result = "()")]
+/// struct Render;
+///
+/// impl Handler<Render> for Renderer {
+///     type Result = ResponseActFuture<Self, ()>;
+///
+///     fn handle(&mut self, _: Render, _: &mut Self::Context) -> Self::Result {
+///         Box::pin(self.licenses.acquire_attached().map(|permit, _, _| {
+///             // render with the license, it is released when the permit is detached and
+///             // dropped, or once the actor stops
+///             drop(permit.detach());
+///         }))
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let licenses = Semaphore::new(4);
+///     let renderers: Vec<_> = (0..8)
+///         .map(|_| Renderer { licenses: licenses.clone() }.start())
+///         .collect();
+///     for renderer in &renderers {
+///         renderer.send(Render).await.unwrap();
+///     }
+///     assert_eq!(licenses.available_permits(), 4);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Semaphore(Arc<parking_lot::Mutex<SemaphoreState>>);
+
+struct SemaphoreState {
+    permits: usize,
+    /// Pending acquisitions, in request order.
+    waiters: VecDeque<(u64, task::Waker)>,
+    /// Acquisitions which were handed a permit but have not taken it yet.
+    granted: Vec<u64>,
+    next_id: u64,
+}
+
+impl SemaphoreState {
+    /// Hands the permit to the oldest waiter, returns the waker to call once unlocked.
+    fn release(&mut self) -> Option<task::Waker> {
+        match self.waiters.pop_front() {
+            Some((id, waker)) => {
+                self.granted.push(id);
+                Some(waker)
+            }
+            None => {
+                self.permits += 1;
+                None
+            }
+        }
+    }
+}
+
+impl Semaphore {
+    /// Creates a semaphore with `permits` permits.
+    pub fn new(permits: usize) -> Self {
+        Semaphore(Arc::new(parking_lot::Mutex::new(SemaphoreState {
+            permits,
+            waiters: VecDeque::new(),
+            granted: Vec::new(),
+            next_id: 0,
+        })))
+    }
+
+    /// Returns the number of permits which are not in use.
+    pub fn available_permits(&self) -> usize {
+        self.0.lock().permits
+    }
+
+    /// Takes a permit if one is available and nobody is waiting for one.
+    pub fn try_acquire(&self) -> Option<Permit> {
+        let mut state = self.0.lock();
+        if state.permits == 0 || !state.waiters.is_empty() {
+            return None;
+        }
+        state.permits -= 1;
+        Some(Permit(self.clone()))
+    }
+
+    /// Returns a future which resolves with a permit once one is available.
+    ///
+    /// Dropping the future gives up its place in the queue.
+    pub fn acquire(&self) -> Acquire {
+        Acquire {
+            sem: self.clone(),
+            id: None,
+        }
+    }
+
+    /// Returns an actor future which resolves with a permit attached to the actor.
+    ///
+    /// The permit is released when the actor stops, unless it is detached earlier. See
+    /// [`AsyncContext::attach_resource()`].
+    pub fn acquire_attached<A>(&self) -> AcquireAttached<A>
+    where
+        A: Actor,
+        A::Context: AsyncContext<A>,
+    {
+        AcquireAttached {
+            acquire: self.acquire(),
+            _act: PhantomData,
+        }
+    }
+
+    fn release(&self) {
+        let waker = self.0.lock().release();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl fmt::Debug for Semaphore {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.0.lock();
+        fmt.debug_struct("Semaphore")
+            .field("permits", &state.permits)
+            .field("waiters", &state.waiters.len())
+            .finish()
+    }
+}
+
+/// A permit of a [`Semaphore`], returned to it on drop.
+#[must_use = "the permit is released right away if it is not used"]
+pub struct Permit(Semaphore);
+
+impl fmt::Debug for Permit {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_tuple("Permit").finish()
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.0.release();
+    }
+}
+
+/// Future for the [`Semaphore::acquire()`] method.
+#[must_use = "futures do nothing unless polled"]
+pub struct Acquire {
+    sem: Semaphore,
+    /// Place in the queue, once the future is waiting.
+    id: Option<u64>,
+}
+
+impl fmt::Debug for Acquire {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Acquire")
+            .field("waiting", &self.id.is_some())
+            .finish()
+    }
+}
+
+impl Future for Acquire {
+    type Output = Permit;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Permit> {
+        let this = self.get_mut();
+        let mut state = this.sem.0.lock();
+        match this.id {
+            None if state.permits > 0 && state.waiters.is_empty() => {
+                state.permits -= 1;
+            }
+            None => {
+                let id = state.next_id;
+                state.next_id += 1;
+                state.waiters.push_back((id, cx.waker().clone()));
+                this.id = Some(id);
+                return Poll::Pending;
+            }
+            Some(id) => match state.granted.iter().position(|granted| *granted == id) {
+                Some(idx) => {
+                    state.granted.swap_remove(idx);
+                    this.id = None;
+                }
+                None => {
+                    if let Some((_, waker)) = state.waiters.iter_mut().find(|(w, _)| *w == id) {
+                        if !waker.will_wake(cx.waker()) {
+                            *waker = cx.waker().clone();
+                        }
+                    }
+                    return Poll::Pending;
+                }
+            },
+        }
+        drop(state);
+        Poll::Ready(Permit(this.sem.clone()))
+    }
+}
+
+impl Drop for Acquire {
+    fn drop(&mut self) {
+        let Some(id) = self.id else { return };
+        let mut state = self.sem.0.lock();
+        if let Some(idx) = state.waiters.iter().position(|(w, _)| *w == id) {
+            state.waiters.remove(idx);
+        } else if let Some(idx) = state.granted.iter().position(|granted| *granted == id) {
+            // the permit was handed over already, pass it on
+            state.granted.swap_remove(idx);
+            let waker = state.release();
+            drop(state);
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Actor future for the [`Semaphore::acquire_attached()`] method.
+#[must_use = "futures do nothing unless polled"]
+pub struct AcquireAttached<A> {
+    acquire: Acquire,
+    _act: PhantomData<fn() -> A>,
+}
+
+impl<A> fmt::Debug for AcquireAttached<A> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("AcquireAttached")
+            .field("acquire", &self.acquire)
+            .finish()
+    }
+}
+
+impl<A> ActorFuture<A> for AcquireAttached<A>
+where
+    A: Actor,
+    A::Context: AsyncContext<A>,
+{
+    type Output = ResourceHandle<Permit>;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        _: &mut A,
+        ctx: &mut A::Context,
+        task: &mut task::Context<'_>,
+    ) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let permit = ready!(Pin::new(&mut this.acquire).poll(task));
+        Poll::Ready(ctx.attach_resource(permit))
+    }
+}
+
+/// An asynchronous mutex shared between actors, with the acquisition model of [`Semaphore`].
+///
+/// The mutex is cheaply cloneable and can be shared between arbiters. Waiting for the lock does
+/// not block the arbiter, and the lock is handed out in the order it was requested. The
+/// [`MutexGuard`] is owned, so it can be held across `.await` points and moved into actor
+/// futures.
+///
+/// ```
+/// use actix::sync::Mutex;
+///
+/// #[actix::main]
+/// async fn main() {
+///     let counter = Mutex::new(0);
+///     *counter.lock().await += 1;
+///     assert_eq!(*counter.try_lock().unwrap(), 1);
+/// }
+/// ```
+pub struct Mutex<T>(Arc<MutexInner<T>>);
+
+struct MutexInner<T> {
+    sem: Semaphore,
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: the value is only accessed through a guard, which holds the only permit.
+unsafe impl<T: Send> Send for MutexInner<T> {}
+unsafe impl<T: Send> Sync for MutexInner<T> {}
+
+impl<T> Clone for Mutex<T> {
+    fn clone(&self) -> Self {
+        Mutex(Arc::clone(&self.0))
+    }
+}
+
+impl<T> fmt::Debug for Mutex<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Mutex")
+            .field("locked", &(self.0.sem.available_permits() == 0))
+            .finish()
+    }
+}
+
+impl<T> Mutex<T> {
+    /// Creates an unlocked mutex holding `value`.
+    pub fn new(value: T) -> Self {
+        Mutex(Arc::new(MutexInner {
+            sem: Semaphore::new(1),
+            value: UnsafeCell::new(value),
+        }))
+    }
+
+    /// Returns a future which resolves with the guard once the mutex is unlocked.
+    ///
+    /// Dropping the future gives up its place in the queue.
+    pub fn lock(&self) -> Lock<T> {
+        Lock {
+            acquire: self.0.sem.acquire(),
+            mutex: self.clone(),
+        }
+    }
+
+    /// Locks the mutex if it is unlocked and nobody is waiting for it.
+    pub fn try_lock(&self) -> Option<MutexGuard<T>> {
+        let permit = self.0.sem.try_acquire()?;
+        Some(MutexGuard {
+            mutex: self.clone(),
+            _permit: permit,
+            _value: PhantomData,
+        })
+    }
+}
+
+/// Future for the [`Mutex::lock()`] method.
+#[must_use = "futures do nothing unless polled"]
+pub struct Lock<T> {
+    acquire: Acquire,
+    mutex: Mutex<T>,
+}
+
+impl<T> fmt::Debug for Lock<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Lock")
+            .field("acquire", &self.acquire)
+            .finish()
+    }
+}
+
+impl<T> Future for Lock<T> {
+    type Output = MutexGuard<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let permit = ready!(Pin::new(&mut this.acquire).poll(cx));
+        Poll::Ready(MutexGuard {
+            mutex: this.mutex.clone(),
+            _permit: permit,
+            _value: PhantomData,
+        })
+    }
+}
+
+/// Owned guard of a locked [`Mutex`], which is unlocked when the guard is dropped.
+#[must_use = "the mutex is unlocked right away if the guard is not used"]
+pub struct MutexGuard<T> {
+    mutex: Mutex<T>,
+    _permit: Permit,
+    /// Shared guards give access to the value from several threads.
+    _value: PhantomData<UnsafeCell<T>>,
+}
+
+// SAFETY: a shared guard only gives shared access to the value.
+unsafe impl<T: Send + Sync> Sync for MutexGuard<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for MutexGuard<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, fmt)
+    }
+}
+
+impl<T> Deref for MutexGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the guard holds the only permit of the mutex
+        unsafe { &*self.mutex.0.value.get() }
+    }
+}
+
+impl<T> DerefMut for MutexGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: the guard holds the only permit of the mutex
+        unsafe { &mut *self.mutex.0.value.get() }
+    }
 }
 
 #[cfg(test)]
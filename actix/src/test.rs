@@ -0,0 +1,515 @@
+//! Helpers for testing actors from synchronous code.
+//!
+//! [`run_system`] gives a test its own [`System`], torn down once the test returns or panics,
+//! and [`block_on_call`] sends a message and waits for its response without writing an async
+//! test. [`tap`] records the messages sent to an actor, and [`ManualClock`] makes the timers
+//! of actors deterministic.
+//!
+//! With the `testing` feature, [`inject`] makes the mailbox of an actor drop messages, delay
+//! replies, fail requests or panic in handlers, following a [`FaultPlan`].
+//!
+//! ```
+//! use std::time::Duration;
+//! use actix::prelude::*;
+//!
+//! struct Doubler;
+//!
+//! impl Actor for Doubler {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! #[derive(Message)]
+//! #[rtype(result = "u32")]
+//! struct Double(u32);
+//!
+//! impl Handler<Double> for Doubler {
+//!     type Result = u32;
+//!
+//!     fn handle(&mut self, msg: Double, _: &mut Context<Self>) -> u32 {
+//!         msg.0 * 2
+//!     }
+//! }
+//!
+//! actix::test::run_system(|| {
+//!     let addr = Doubler::start_in_arbiter(&Arbiter::current(), |_| Doubler);
+//!     let res = actix::test::block_on_call(&addr, Double(21), Duration::from_secs(1));
+//!     assert_eq!(res, Ok(42));
+//! });
+//! ```
+use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    collections::HashMap,
+    error, fmt,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Weak},
+    task::{self as std_task, Poll, Waker},
+    time::Duration,
+};
+
+use actix_rt::{Runtime, System, SystemRunner};
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+
+#[cfg(feature = "testing")]
+pub use crate::fault::{clear_type_faults, inject, inject_type, FaultPlan};
+use crate::{
+    actor::Actor,
+    address::{Addr, MailboxError, Tap, ToEnvelope},
+    clock::{self, Clock, ClockSleep, Instant},
+    handler::{Handler, Message},
+};
+
+thread_local!(
+    static RUNNER: RefCell<Option<SystemRunner>> = const { RefCell::new(None) };
+);
+
+/// The error returned by [`block_on_call`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestCallError {
+    /// No response was received within the given timeout.
+    Timeout,
+    /// The message could not be delivered, or the actor dropped the response.
+    Mailbox(MailboxError),
+}
+
+impl fmt::Display for TestCallError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TestCallError::Timeout => write!(fmt, "No response received before the timeout"),
+            TestCallError::Mailbox(err) => write!(fmt, "{}", err),
+        }
+    }
+}
+
+impl error::Error for TestCallError {}
+
+/// Runs `f` with a new [`System`], which is stopped and dropped when `f` returns or panics.
+///
+/// `f` runs outside of the system's event loop, which is only driven while
+/// [`block_on_call`] waits. Actors are therefore started on the system's arbiter with
+/// [`Actor::start_in_arbiter`] and `Arbiter::current()`, rather than with
+/// [`Actor::start`]. All actors of the system are dropped before `run_system` returns, so
+/// each test gets an isolated system.
+///
+/// # Panics
+///
+/// Panics if called from within `f` of another `run_system`, and resumes the panic of `f`
+/// once the system is torn down.
+pub fn run_system<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let runner = System::new();
+    RUNNER.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        assert!(cell.is_none(), "run_system cannot be nested");
+        *cell = Some(runner);
+    });
+
+    let res = panic::catch_unwind(AssertUnwindSafe(f));
+
+    let runner = RUNNER.with(|cell| cell.borrow_mut().take());
+    if let Some(runner) = runner {
+        System::current().stop();
+        let _ = runner.run();
+    }
+
+    match res {
+        Ok(res) => res,
+        Err(panic) => panic::resume_unwind(panic),
+    }
+}
+
+/// Sends `msg` to `addr` and blocks the current thread until the response arrives, or
+/// `timeout` elapses.
+///
+/// Within [`run_system`], the request is driven by the system's event loop, so actors of
+/// that system make progress meanwhile. Otherwise a temporary runtime is created for the
+/// request, and `addr` must belong to an actor running on another thread.
+///
+/// # Panics
+///
+/// Panics if called from within a running runtime, where the request should be awaited
+/// instead.
+pub fn block_on_call<A, M>(
+    addr: &Addr<A>,
+    msg: M,
+    timeout: Duration,
+) -> Result<M::Result, TestCallError>
+where
+    A: Actor + Handler<M>,
+    A::Context: ToEnvelope<A, M>,
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    assert!(
+        tokio::runtime::Handle::try_current().is_err(),
+        "block_on_call cannot be used within a running runtime, await the request instead"
+    );
+
+    // the timer of the timeout can only be created within the runtime
+    let req = async { addr.send(msg).timeout(timeout).await };
+    let res = RUNNER.with(|cell| match &*cell.borrow() {
+        Some(runner) => runner.block_on(req),
+        None => Runtime::new()
+            .expect("failed to create a runtime")
+            .block_on(req),
+    });
+
+    res.map_err(|err| match err {
+        MailboxError::Timeout => TestCallError::Timeout,
+        err => TestCallError::Mailbox(err),
+    })
+}
+
+/// Returns an address of the actor of `addr` which records the messages sent through it.
+///
+/// Messages sent through the returned address, its clones and the recipients created from it
+/// are queued like messages sent through `addr`, so their order and the routing of responses
+/// are unchanged. Each of them is recorded in the returned [`MessageLog`] right before it is
+/// queued. Messages sent through other addresses of the actor are not recorded.
+///
+/// ```
+/// use std::time::Duration;
+/// use actix::prelude::*;
+///
+/// struct Sink;
+///
+/// impl Actor for Sink {
+///     type Context = Context<Self>;
+/// }
+///
+/// #[derive(Debug, Message)]
+/// #[rtype(result = "()")]
+/// struct Event(u32);
+///
+/// impl Handler<Event> for Sink {
+///     type Result = ();
+///
+///     fn handle(&mut self, _: Event, _: &mut Context<Self>) {}
+/// }
+///
+/// # #[actix::main]
+/// # async fn main() {
+/// let (addr, log) = actix::test::tap(Sink.start());
+/// log.format::<Event>();
+///
+/// addr.do_send(Event(7));
+/// let rec = log
+///     .wait_for(|rec| rec.debug.as_deref() == Some("Event(7)"), Duration::from_secs(1))
+///     .await
+///     .unwrap();
+/// assert!(!rec.ask);
+/// # }
+/// ```
+pub fn tap<A: Actor>(addr: Addr<A>) -> (Addr<A>, MessageLog) {
+    let log = MessageLog::default();
+    let tapped = addr.tapped(Arc::clone(&log.inner) as Arc<dyn Tap>);
+    (tapped, log)
+}
+
+/// Message recorded by a [`MessageLog`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MessageRecord {
+    /// Type name of the message.
+    pub type_name: &'static str,
+    /// Debug representation of the message, if its type was registered with
+    /// [`MessageLog::format()`].
+    pub debug: Option<String>,
+    /// Time at which the message was sent.
+    pub sent_at: Instant,
+    /// Whether the message was sent with `send()`, waiting for a response.
+    pub ask: bool,
+}
+
+type Format = Box<dyn Fn(&dyn Any) -> String + Send + Sync>;
+
+#[derive(Default)]
+struct LogInner {
+    records: Mutex<Vec<MessageRecord>>,
+    formats: Mutex<HashMap<TypeId, Format>>,
+    notify: Notify,
+}
+
+impl Tap for LogInner {
+    fn record(&self, msg: &dyn Any, type_name: &'static str, ask: bool) {
+        let debug = self
+            .formats
+            .lock()
+            .get(&msg.type_id())
+            .map(|format| format(msg));
+        self.records.lock().push(MessageRecord {
+            type_name,
+            debug,
+            sent_at: Instant::now(),
+            ask,
+        });
+        self.notify.notify_waiters();
+    }
+}
+
+/// Messages sent through an address returned by [`tap`], in the order they were queued.
+///
+/// The log can be cloned and shared between threads, clones refer to the same records.
+#[derive(Clone, Default)]
+pub struct MessageLog {
+    inner: Arc<LogInner>,
+}
+
+impl fmt::Debug for MessageLog {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("MessageLog")
+            .field("records", &*self.inner.records.lock())
+            .finish()
+    }
+}
+
+impl MessageLog {
+    /// Records the debug representation of messages of type `M` sent from now on.
+    pub fn format<M: fmt::Debug + 'static>(&self) -> &Self {
+        let format: Format = Box::new(|msg| {
+            msg.downcast_ref::<M>()
+                .map_or_else(String::new, |msg| format!("{:?}", msg))
+        });
+        self.inner.formats.lock().insert(TypeId::of::<M>(), format);
+        self
+    }
+
+    /// Returns the recorded messages.
+    pub fn records(&self) -> Vec<MessageRecord> {
+        self.inner.records.lock().clone()
+    }
+
+    /// Returns the number of recorded messages.
+    pub fn len(&self) -> usize {
+        self.inner.records.lock().len()
+    }
+
+    /// Returns `true` if no message was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Discards the recorded messages.
+    pub fn clear(&self) {
+        self.inner.records.lock().clear();
+    }
+
+    /// Waits until a message matching `pred` is recorded, and returns the first one.
+    ///
+    /// Messages recorded before the call are included. Returns `None` if no matching message
+    /// is recorded within `timeout`.
+    pub async fn wait_for<F>(&self, mut pred: F, timeout: Duration) -> Option<MessageRecord>
+    where
+        F: FnMut(&MessageRecord) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+        loop {
+            // registered before searching, so records pushed meanwhile are not missed
+            let notified = self.inner.notify.notified();
+            if let Some(rec) = self.inner.records.lock().iter().find(|rec| pred(rec)) {
+                return Some(rec.clone());
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if clock::timeout(remaining, notified).await.is_err() {
+                return None;
+            }
+        }
+    }
+}
+
+/// Clock which only advances when told to, for deterministic tests of timers.
+///
+/// Set on an arbiter with [`clock::set_arbiter_clock()`], or on a system with
+/// [`SystemConfig::clock()`](crate::SystemConfig::clock), timers of actors such as
+/// [`run_later()`](crate::AsyncContext::run_later),
+/// [`run_interval()`](crate::AsyncContext::run_interval) and request timeouts only fire once
+/// [`advance()`](Self::advance) moved the clock past their deadline. Clones share the same time.
+///
+/// ```
+/// use std::{
+///     sync::{
+///         atomic::{AtomicUsize, Ordering},
+///         Arc,
+///     },
+///     time::Duration,
+/// };
+/// use actix::{prelude::*, test::ManualClock};
+///
+/// struct Ticker(Arc<AtomicUsize>);
+///
+/// impl Actor for Ticker {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, ctx: &mut Self::Context) {
+///         ctx.run_interval(Duration::from_secs(60), |act, _| {
+///             act.0.fetch_add(1, Ordering::SeqCst);
+///         });
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let clock = ManualClock::new();
+///     actix::clock::set_arbiter_clock(clock.clone());
+///
+///     let ticks = Arc::new(AtomicUsize::new(0));
+///     let _addr = Ticker(Arc::clone(&ticks)).start();
+///     actix::clock::sleep(Duration::from_millis(10)).await;
+///     assert_eq!(ticks.load(Ordering::SeqCst), 0);
+///
+///     clock.advance(Duration::from_secs(180)).await;
+///     assert_eq!(ticks.load(Ordering::SeqCst), 3);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ManualClock(Arc<ManualClockInner>);
+
+struct ManualClockInner {
+    start: Instant,
+    state: Mutex<ManualClockState>,
+}
+
+struct ManualClockState {
+    elapsed: Duration,
+    /// Pending sleeps with their deadline.
+    timers: Vec<(Instant, Weak<Mutex<Option<Waker>>>)>,
+}
+
+impl fmt::Debug for ManualClock {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ManualClock")
+            .field("elapsed", &self.elapsed())
+            .finish()
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManualClock {
+    /// Creates a clock standing at the current time.
+    pub fn new() -> Self {
+        ManualClock(Arc::new(ManualClockInner {
+            start: Instant::now(),
+            state: Mutex::new(ManualClockState {
+                elapsed: Duration::ZERO,
+                timers: Vec::new(),
+            }),
+        }))
+    }
+
+    /// Returns how far the clock was advanced since it was created.
+    pub fn elapsed(&self) -> Duration {
+        self.0.state.lock().elapsed
+    }
+
+    /// Moves the clock forward by `dur`, firing the timers which are due, in the order of
+    /// their deadlines.
+    ///
+    /// A timer which reschedules itself within `dur`, like the ticks of an interval, fires again
+    /// before the clock reaches its final time. The returned future resolves once the fired
+    /// timers have been handled by their actors; the clock is advanced even if it is not
+    /// awaited.
+    pub fn advance(&self, dur: Duration) -> impl Future<Output = ()> {
+        let target = self.now() + dur;
+        let clock = self.clone();
+        // fire the timers due right away, later steps run once the actors rescheduled them
+        let mut step = Some(clock.step(target));
+        async move {
+            loop {
+                let fired = match step.take() {
+                    Some(fired) => fired,
+                    None => clock.step(target),
+                };
+                // let the woken actors run and reschedule their timers
+                for _ in 0..4 {
+                    actix_rt::task::yield_now().await;
+                }
+                if !fired {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Advances the clock to the earliest deadline up to `target`, waking the timers due by
+    /// then. Returns `false` once no timer is due before `target`.
+    fn step(&self, target: Instant) -> bool {
+        let mut state = self.0.state.lock();
+        state.timers.retain(|(_, slot)| slot.strong_count() > 0);
+        let next = state
+            .timers
+            .iter()
+            .map(|(deadline, _)| *deadline)
+            .filter(|deadline| *deadline <= target)
+            .min();
+        let now = match next {
+            Some(deadline) => deadline.max(self.0.start + state.elapsed),
+            None => target,
+        };
+        state.elapsed = now - self.0.start;
+
+        let mut fired = false;
+        state.timers.retain(|(deadline, slot)| {
+            if *deadline > now {
+                return true;
+            }
+            if let Some(waker) = slot.upgrade().and_then(|slot| slot.lock().take()) {
+                waker.wake();
+                fired = true;
+            }
+            false
+        });
+        fired || next.is_some()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.0.start + self.0.state.lock().elapsed
+    }
+
+    fn sleep(&self, dur: Duration) -> ClockSleep {
+        Box::pin(ManualSleep {
+            clock: Arc::clone(&self.0),
+            deadline: self.now() + dur,
+            waker: None,
+        })
+    }
+}
+
+/// Sleep of a [`ManualClock`].
+struct ManualSleep {
+    clock: Arc<ManualClockInner>,
+    deadline: Instant,
+    waker: Option<Arc<Mutex<Option<Waker>>>>,
+}
+
+impl Future for ManualSleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut std_task::Context<'_>) -> Poll<()> {
+        let this = &mut *self;
+        let mut state = this.clock.state.lock();
+        if this.clock.start + state.elapsed >= this.deadline {
+            return Poll::Ready(());
+        }
+        match this.waker {
+            Some(ref slot) => *slot.lock() = Some(cx.waker().clone()),
+            None => {
+                let slot = Arc::new(Mutex::new(Some(cx.waker().clone())));
+                state.timers.push((this.deadline, Arc::downgrade(&slot)));
+                this.waker = Some(slot);
+            }
+        }
+        Poll::Pending
+    }
+}
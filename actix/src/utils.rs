@@ -1,18 +1,30 @@
 use std::{
+    cell::RefCell,
+    collections::{hash_map::RandomState, BTreeMap},
+    fmt,
     future::Future,
+    hash::{BuildHasher, Hasher},
+    mem,
     pin::Pin,
-    task::{Context, Poll},
+    rc::Rc,
+    sync::Arc,
+    task::{Context, Poll, Waker},
     time::Duration,
 };
 
-use futures_core::ready;
+use futures_core::{ready, stream::Stream};
+use futures_util::stream::StreamExt as _;
 use pin_project_lite::pin_project;
 use tokio::sync::oneshot;
 
 use crate::{
-    actor::Actor,
-    clock::{sleep, Sleep},
-    fut::{ActorFuture, ActorStream},
+    actor::{Actor, AsyncContext, SpawnHandle},
+    address::{Addr, StateStream},
+    clock::{self, sleep, timeout, Instant, Sleep, Timer},
+    fut::{wrap_future, ActorFuture, ActorFutureExt, ActorStream},
+    handler::{Handler, Message, MessageResponse, ResponseActFuture},
+    queue::{self, OneshotReceiver, OneshotSender},
+    stream::StreamHandler,
 };
 
 #[deprecated(
@@ -96,7 +108,7 @@ pin_project! {
     pub struct TimerFunc<A: Actor> {
         f: Option<Box<dyn FnOnce(&mut A, &mut A::Context)>>,
         #[pin]
-        timeout: Sleep,
+        timeout: Timer,
     }
 }
 
@@ -108,7 +120,7 @@ impl<A: Actor> TimerFunc<A> {
     {
         TimerFunc {
             f: Some(Box::new(f)),
-            timeout: sleep(timeout),
+            timeout: Timer::after(timeout),
         }
     }
 }
@@ -138,6 +150,16 @@ pin_project! {
     ///
     /// Unless you specifically need access to the future, use [`Context::run_interval`] instead.
     ///
+    /// By default ticks are scheduled at a fixed rate, relative to the previous deadline, so
+    /// a late tick is followed by ticks catching up with the schedule. With
+    /// [`fixed_delay`](Self::fixed_delay) the next tick is scheduled relative to the completion of
+    /// the callback instead. [`jitter`](Self::jitter) randomizes every period, which keeps many
+    /// actors from firing in lockstep.
+    ///
+    /// Spawning the [finished](crate::fut::ActorStreamExt::finish) stream returns a
+    /// [`SpawnHandle`](crate::SpawnHandle), cancelling it stops all future ticks. Ticks also stop
+    /// once the actor starts stopping, since its context does not poll spawned futures anymore.
+    ///
     /// [`Context::run_interval`]: ../prelude/trait.AsyncContext.html#method.run_interval
     ///
     /// ```
@@ -176,8 +198,11 @@ pin_project! {
     pub struct IntervalFunc<A: Actor> {
         f: Box<dyn FnMut(&mut A, &mut A::Context)>,
         dur: Duration,
+        jitter: f64,
+        fixed_delay: bool,
+        rng: u64,
         #[pin]
-        timer: Sleep,
+        timer: Timer,
     }
 }
 
@@ -190,9 +215,55 @@ impl<A: Actor> IntervalFunc<A> {
         Self {
             f: Box::new(f),
             dur,
-            timer: sleep(dur),
+            jitter: 0.0,
+            fixed_delay: false,
+            rng: RandomState::new().build_hasher().finish() | 1,
+            timer: Timer::after(dur),
         }
     }
+
+    /// Randomizes every period by up to the given fraction of the interval duration.
+    ///
+    /// For example, `0.1` spreads the ticks of a 10 second interval between 9 and 11 seconds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `jitter` is not within `0.0..=1.0`.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&jitter),
+            "Jitter must be within 0.0 and 1.0"
+        );
+        self.jitter = jitter;
+        self.timer = Timer::after(self.next_period());
+        self
+    }
+
+    /// Schedules ticks relative to the completion of the callback instead of the previous
+    /// deadline.
+    pub fn fixed_delay(mut self, fixed_delay: bool) -> Self {
+        self.fixed_delay = fixed_delay;
+        self
+    }
+
+    fn next_period(&mut self) -> Duration {
+        next_period(self.dur, self.jitter, &mut self.rng)
+    }
+}
+
+/// Randomizes `dur` by up to `jitter` of its length in both directions.
+fn next_period(dur: Duration, jitter: f64, rng: &mut u64) -> Duration {
+    if jitter == 0.0 {
+        return dur;
+    }
+
+    // xorshift64
+    *rng ^= *rng << 13;
+    *rng ^= *rng >> 7;
+    *rng ^= *rng << 17;
+    let unit = (*rng >> 11) as f64 / (1u64 << 53) as f64;
+
+    dur.mul_f64(1.0 + jitter * (unit * 2.0 - 1.0))
 }
 
 impl<A: Actor> ActorStream<A> for IntervalFunc<A> {
@@ -207,9 +278,784 @@ impl<A: Actor> ActorStream<A> for IntervalFunc<A> {
         let mut this = self.project();
         loop {
             ready!(this.timer.as_mut().poll(task));
-            let now = this.timer.deadline();
-            this.timer.as_mut().reset(now + *this.dur);
-            (this.f)(act, ctx);
+            let period = next_period(*this.dur, *this.jitter, this.rng);
+            if *this.fixed_delay {
+                (this.f)(act, ctx);
+                this.timer.as_mut().reset(clock::now() + period);
+            } else {
+                let deadline = this.timer.deadline();
+                this.timer.as_mut().reset(deadline + period);
+                (this.f)(act, ctx);
+            }
+        }
+    }
+}
+
+/// An actor which can be stopped by a [`StopGroup`].
+pub trait StopTarget {
+    /// Asks the actor to stop once it has handled the messages queued so far.
+    fn request_stop(&self);
+
+    /// Returns a stream of the actor's state transitions, see [`Addr::state_stream()`].
+    fn state_stream(&self) -> StateStream;
+}
+
+impl<A> StopTarget for Addr<A>
+where
+    A: Actor<Context = crate::Context<A>>,
+{
+    fn request_stop(&self) {
+        Addr::request_stop(self);
+    }
+
+    fn state_stream(&self) -> StateStream {
+        Addr::state_stream(self)
+    }
+}
+
+/// Stops groups of actors in order of their stage.
+///
+/// All actors of a stage are asked to stop at once. Each actor stops after handling the
+/// messages queued before the request, and the next stage is stopped once all actors of the
+/// current one have stopped, or the stage's deadline has passed.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use actix::prelude::*;
+/// use actix::utils::StopGroup;
+///
+/// struct Stage;
+///
+/// impl Actor for Stage {
+///     type Context = Context<Self>;
+/// }
+///
+/// # #[actix::main]
+/// # async fn main() {
+/// let mut group = StopGroup::new();
+/// group.register(0, Stage.start()); // source
+/// group.register(1, Stage.start()); // sink
+///
+/// let reports = group.shutdown(Duration::from_secs(1)).await;
+/// assert!(reports.iter().all(|report| report.timed_out == 0));
+/// # }
+/// ```
+#[derive(Default)]
+pub struct StopGroup {
+    stages: BTreeMap<usize, Vec<Box<dyn StopTarget>>>,
+}
+
+impl fmt::Debug for StopGroup {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("StopGroup")
+            .field("stages", &self.stages.len())
+            .finish()
+    }
+}
+
+/// Outcome of stopping one stage of a [`StopGroup`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageReport {
+    /// Stage number.
+    pub stage: usize,
+    /// Number of actors in the stage.
+    pub actors: usize,
+    /// Number of actors which did not stop before the deadline.
+    pub timed_out: usize,
+}
+
+impl StopGroup {
+    /// Creates an empty group.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an actor to be stopped in `stage`, lower stages are stopped first.
+    pub fn register<T: StopTarget + 'static>(&mut self, stage: usize, target: T) {
+        self.stages.entry(stage).or_default().push(Box::new(target));
+    }
+
+    /// Stops all registered actors stage by stage.
+    ///
+    /// Each stage is given `deadline_per_stage` to stop before the next stage is stopped
+    /// regardless. Resolves with one report per stage, in stage order.
+    pub async fn shutdown(self, deadline_per_stage: Duration) -> Vec<StageReport> {
+        let mut reports = Vec::with_capacity(self.stages.len());
+
+        for (stage, targets) in self.stages {
+            let actors = targets.len();
+            let states: Vec<_> = targets
+                .into_iter()
+                .map(|target| {
+                    // subscribe first, so the transition to stopped is not missed
+                    let states = target.state_stream();
+                    target.request_stop();
+                    states
+                })
+                .collect();
+
+            let deadline = Instant::now() + deadline_per_stage;
+            let mut timed_out = 0;
+            for mut states in states {
+                let stopped = async { while states.next().await.is_some() {} };
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if timeout(remaining, stopped).await.is_err() {
+                    timed_out += 1;
+                }
+            }
+
+            reports.push(StageReport {
+                stage,
+                actors,
+                timed_out,
+            });
+        }
+
+        reports
+    }
+}
+
+/// Delays between the connection attempts of a [`Reconnector`].
+///
+/// The delay starts at `initial` and is multiplied by the factor after every failed attempt, up
+/// to `max`. A successful connection resets the delay.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    factor: f64,
+    jitter: f64,
+    max_attempts: Option<usize>,
+}
+
+impl Backoff {
+    /// Creates a backoff doubling the delay from `initial` up to `max`.
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Backoff {
+            initial,
+            max,
+            factor: 2.0,
+            jitter: 0.0,
+            max_attempts: None,
+        }
+    }
+
+    /// Sets the factor the delay is multiplied by after every failed attempt.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor` is less than `1.0`.
+    pub fn factor(mut self, factor: f64) -> Self {
+        assert!(factor >= 1.0, "Backoff factor must be at least 1.0");
+        self.factor = factor;
+        self
+    }
+
+    /// Randomizes every delay by up to the given fraction of its length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `jitter` is not within `0.0..=1.0`.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&jitter),
+            "Jitter must be within 0.0 and 1.0"
+        );
+        self.jitter = jitter;
+        self
+    }
+
+    /// Gives up after the given number of consecutive failed attempts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `attempts` is zero.
+    pub fn max_attempts(mut self, attempts: usize) -> Self {
+        assert!(attempts > 0, "Max attempts must be greater than zero");
+        self.max_attempts = Some(attempts);
+        self
+    }
+
+    /// Returns the delay after the given number of consecutive failed attempts, before jitter.
+    pub fn delay(&self, failures: usize) -> Duration {
+        let exp = i32::try_from(failures.saturating_sub(1)).unwrap_or(i32::MAX);
+        let secs = self.initial.as_secs_f64() * self.factor.powi(exp);
+        Duration::from_secs_f64(secs.min(self.max.as_secs_f64()))
+    }
+}
+
+/// Notifies the actor of a [`Reconnector`] about its connection.
+#[derive(Debug)]
+pub enum ReconnectEvent<E> {
+    /// A new stream was connected.
+    Connected,
+
+    /// The stream finished or was dropped by [`Reconnector::trigger_now()`], a new connection
+    /// is attempted right away.
+    Disconnected,
+
+    /// A connection attempt failed.
+    Failed {
+        /// Error returned by the connect function.
+        error: E,
+        /// Number of consecutive failed attempts.
+        attempt: usize,
+        /// Delay until the next attempt, `None` if the reconnector gave up.
+        retry_in: Option<Duration>,
+    },
+}
+
+impl<E: 'static> Message for ReconnectEvent<E> {
+    type Result = ();
+}
+
+/// Keeps a stream connected to an actor, reconnecting with a [`Backoff`].
+///
+/// The connect function is called right away and every time the stream finishes or a connection
+/// attempt fails. Items of connected streams are handled by the actor's [`StreamHandler`],
+/// except that [`StreamHandler::finished`] is not called, and the actor is notified of the
+/// connection state with [`ReconnectEvent`]s.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use actix::prelude::*;
+/// use actix::utils::{Backoff, ReconnectEvent, Reconnector};
+/// use futures_util::stream;
+///
+/// struct Client {
+///     conn: Option<Reconnector>,
+/// }
+///
+/// impl Actor for Client {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, ctx: &mut Self::Context) {
+///         let backoff = Backoff::new(Duration::from_millis(100), Duration::from_secs(10));
+///         let conn = Reconnector::new(
+///             backoff,
+///             |_: &mut Self, _: &mut Self::Context| {
+///                 // connect to the server here
+///                 Box::pin(fut::ready(Ok::<_, ()>(stream::iter(vec![1, 2, 3]))))
+///             },
+///             ctx,
+///         );
+///         self.conn = Some(conn);
+///     }
+/// }
+///
+/// impl StreamHandler<u32> for Client {
+///     fn handle(&mut self, item: u32, _: &mut Self::Context) {
+///         println!("received {item}");
+///     }
+/// }
+///
+/// impl Handler<ReconnectEvent<()>> for Client {
+///     type Result = ();
+///
+///     fn handle(&mut self, event: ReconnectEvent<()>, ctx: &mut Self::Context) {
+///         if let ReconnectEvent::Disconnected = event {
+///             self.conn.take().unwrap().stop();
+///             ctx.stop();
+///         }
+///     }
+/// }
+///
+/// # #[actix::main]
+/// # async fn main() {
+/// Client { conn: None }.start();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Reconnector(Rc<RefCell<ReconnectState>>);
+
+#[derive(Debug, Default)]
+struct ReconnectState {
+    trigger: bool,
+    stopped: bool,
+    handle: SpawnHandle,
+    task: Option<Waker>,
+}
+
+impl Reconnector {
+    /// Spawns the reconnector in the actor's context and connects right away.
+    pub fn new<A, C, S, E, F>(backoff: Backoff, connect: F, ctx: &mut C) -> Self
+    where
+        A: Actor<Context = C> + StreamHandler<S::Item> + Handler<ReconnectEvent<E>>,
+        C: AsyncContext<A>,
+        S: Stream + 'static,
+        E: 'static,
+        F: Fn(&mut A, &mut C) -> ResponseActFuture<A, Result<S, E>> + 'static,
+    {
+        let shared = Rc::new(RefCell::new(ReconnectState::default()));
+        let handle = ctx.spawn(ReconnectFut {
+            shared: Rc::clone(&shared),
+            connect: Box::new(connect),
+            backoff,
+            failures: 0,
+            rng: RandomState::new().build_hasher().finish() | 1,
+            conn: Connection::Idle,
+        });
+        shared.borrow_mut().handle = handle;
+        Reconnector(shared)
+    }
+
+    /// Connects right away, dropping the current stream or skipping the remaining delay.
+    ///
+    /// Does nothing while a connection attempt is in progress.
+    pub fn trigger_now(&self) {
+        self.0.borrow_mut().trigger = true;
+        self.wake();
+    }
+
+    /// Stops reconnecting and drops the current stream.
+    pub fn stop(&self) {
+        self.0.borrow_mut().stopped = true;
+        self.wake();
+    }
+
+    /// Returns the `SpawnHandle` for this reconnector.
+    pub fn handle(&self) -> SpawnHandle {
+        self.0.borrow().handle
+    }
+
+    fn wake(&self) {
+        if let Some(task) = self.0.borrow_mut().task.take() {
+            task.wake();
+        }
+    }
+}
+
+enum Connection<A: Actor, S, E> {
+    Idle,
+    Connecting(ResponseActFuture<A, Result<S, E>>),
+    Connected { stream: Pin<Box<S>>, started: bool },
+    Waiting(Pin<Box<Sleep>>),
+}
+
+#[allow(clippy::type_complexity)]
+struct ReconnectFut<A: Actor, S, E> {
+    shared: Rc<RefCell<ReconnectState>>,
+    connect: Box<dyn Fn(&mut A, &mut A::Context) -> ResponseActFuture<A, Result<S, E>>>,
+    backoff: Backoff,
+    failures: usize,
+    rng: u64,
+    conn: Connection<A, S, E>,
+}
+
+impl<A: Actor, S, E> ReconnectFut<A, S, E> {
+    /// Registers the task, returns `false` if the reconnector was triggered or stopped meanwhile.
+    fn park(&self, task: &mut Context<'_>) -> bool {
+        let mut shared = self.shared.borrow_mut();
+        if shared.interrupted() {
+            false
+        } else {
+            shared.task = Some(task.waker().clone());
+            true
+        }
+    }
+}
+
+impl ReconnectState {
+    fn interrupted(&self) -> bool {
+        self.trigger || self.stopped
+    }
+}
+
+fn notify<A, E>(act: &mut A, event: ReconnectEvent<E>, ctx: &mut A::Context)
+where
+    A: Handler<ReconnectEvent<E>>,
+    E: 'static,
+{
+    let res = <A as Handler<ReconnectEvent<E>>>::handle(act, event, ctx);
+    res.handle(ctx, None);
+}
+
+impl<A, S, E> ActorFuture<A> for ReconnectFut<A, S, E>
+where
+    A: Actor + StreamHandler<S::Item> + Handler<ReconnectEvent<E>>,
+    A::Context: AsyncContext<A>,
+    S: Stream,
+    E: 'static,
+{
+    type Output = ();
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        loop {
+            let (trigger, stopped) = {
+                let mut shared = this.shared.borrow_mut();
+                (mem::take(&mut shared.trigger), shared.stopped)
+            };
+            if stopped {
+                return Poll::Ready(());
+            }
+            if trigger {
+                match this.conn {
+                    Connection::Connecting(_) | Connection::Idle => {}
+                    Connection::Waiting(_) => this.conn = Connection::Idle,
+                    Connection::Connected { .. } => {
+                        this.conn = Connection::Idle;
+                        notify(act, ReconnectEvent::<E>::Disconnected, ctx);
+                        continue;
+                    }
+                }
+            }
+
+            match this.conn {
+                Connection::Idle => {
+                    this.conn = Connection::Connecting((this.connect)(act, ctx));
+                }
+                Connection::Connecting(ref mut fut) => match fut.as_mut().poll(act, ctx, task) {
+                    Poll::Ready(Ok(stream)) => {
+                        this.failures = 0;
+                        this.conn = Connection::Connected {
+                            stream: Box::pin(stream),
+                            started: false,
+                        };
+                        notify(act, ReconnectEvent::<E>::Connected, ctx);
+                    }
+                    Poll::Ready(Err(error)) => {
+                        this.failures += 1;
+                        let retry_in = match this.backoff.max_attempts {
+                            Some(max) if this.failures >= max => None,
+                            _ => Some(next_period(
+                                this.backoff.delay(this.failures),
+                                this.backoff.jitter,
+                                &mut this.rng,
+                            )),
+                        };
+                        let event = ReconnectEvent::Failed {
+                            error,
+                            attempt: this.failures,
+                            retry_in,
+                        };
+                        match retry_in {
+                            Some(delay) => {
+                                this.conn = Connection::Waiting(Box::pin(sleep(delay)));
+                                notify(act, event, ctx);
+                            }
+                            None => {
+                                notify(act, event, ctx);
+                                return Poll::Ready(());
+                            }
+                        }
+                    }
+                    Poll::Pending => {
+                        if this.park(task) {
+                            return Poll::Pending;
+                        }
+                    }
+                },
+                Connection::Waiting(ref mut timer) => {
+                    if timer.as_mut().poll(task).is_ready() {
+                        this.conn = Connection::Idle;
+                    } else if this.park(task) {
+                        return Poll::Pending;
+                    }
+                }
+                Connection::Connected {
+                    ref mut stream,
+                    ref mut started,
+                } => {
+                    if !*started {
+                        *started = true;
+                        <A as StreamHandler<S::Item>>::started(act, ctx);
+                    }
+
+                    let mut polled = 0;
+                    loop {
+                        match stream.as_mut().poll_next(task) {
+                            Poll::Ready(Some(item)) => {
+                                <A as StreamHandler<S::Item>>::handle(act, item, ctx);
+
+                                polled += 1;
+                                if this.shared.borrow().interrupted() {
+                                    break;
+                                } else if ctx.waiting() {
+                                    return Poll::Pending;
+                                } else if polled == 16 {
+                                    // yield to other actor futures, like `add_stream` does
+                                    task.waker().wake_by_ref();
+                                    return Poll::Pending;
+                                }
+                            }
+                            Poll::Ready(None) => {
+                                this.conn = Connection::Idle;
+                                notify(act, ReconnectEvent::<E>::Disconnected, ctx);
+                                break;
+                            }
+                            Poll::Pending => {
+                                if this.park(task) {
+                                    return Poll::Pending;
+                                }
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Value derived from the state of an actor, recomputed at most once per invalidation.
+///
+/// The actor stores the memo next to the state the value is computed from. Handlers changing
+/// that state call [`invalidate()`](Memo::invalidate), queries call [`get()`](Memo::get), which
+/// only computes the value again if it was invalidated since the last call.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use actix::{prelude::*, utils::Memo};
+///
+/// struct Stats {
+///     samples: Vec<u64>,
+///     total: Memo<Stats, u64>,
+/// }
+///
+/// impl Actor for Stats {
+///     type Context = Context<Self>;
+/// }
+///
+/// #[derive(Message)]
+/// #[rtype(result = "()")]
+/// struct Sample(u64);
+///
+/// impl Handler<Sample> for Stats {
+///     type Result = ();
+///
+///     fn handle(&mut self, Sample(value): Sample, _: &mut Self::Context) {
+///         self.samples.push(value);
+///         self.total.invalidate();
+///     }
+/// }
+///
+/// #[derive(Message)]
+/// #[rtype(result = "Arc<u64>")]
+/// struct Total;
+///
+/// impl Handler<Total> for Stats {
+///     type Result = MessageResult<Total>;
+///
+///     fn handle(&mut self, _: Total, _: &mut Self::Context) -> Self::Result {
+///         MessageResult(self.total.get(self))
+///     }
+/// }
+///
+/// # #[actix::main]
+/// # async fn main() {
+/// let total = Memo::new(|stats: &Stats| stats.samples.iter().sum());
+/// let addr = Stats { samples: Vec::new(), total }.start();
+/// addr.do_send(Sample(2));
+/// addr.do_send(Sample(3));
+/// assert_eq!(*addr.send(Total).await.unwrap(), 5);
+/// # }
+/// ```
+pub struct Memo<A, T> {
+    compute: Box<dyn Fn(&A) -> T>,
+    value: RefCell<Option<Arc<T>>>,
+}
+
+impl<A, T> fmt::Debug for Memo<A, T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Memo")
+            .field("cached", &self.value.borrow().is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A, T> Memo<A, T> {
+    /// Creates a memo computing its value with `compute`.
+    pub fn new<F>(compute: F) -> Self
+    where
+        F: Fn(&A) -> T + 'static,
+    {
+        Memo {
+            compute: Box::new(compute),
+            value: RefCell::new(None),
+        }
+    }
+
+    /// Returns the value, computing it from `act` if it was invalidated.
+    pub fn get(&self, act: &A) -> Arc<T> {
+        if let Some(value) = &*self.value.borrow() {
+            return Arc::clone(value);
+        }
+        let value = Arc::new((self.compute)(act));
+        *self.value.borrow_mut() = Some(Arc::clone(&value));
+        value
+    }
+
+    /// Drops the value, so it is computed again by the next call to [`get()`](Memo::get).
+    pub fn invalidate(&self) {
+        self.value.borrow_mut().take();
+    }
+}
+
+/// Value derived from the state of an actor by a future, see [`Memo`].
+///
+/// The future is created from the actor's state and spawned into its context, so the actor keeps
+/// handling messages while the value is computed, e.g. on a blocking thread. Queries made while
+/// the future is in flight wait for it instead of spawning another one. A value which is
+/// invalidated while it is computed is still passed to the queries waiting for it, but not kept.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use actix::{prelude::*, utils::AsyncMemo};
+///
+/// struct Index {
+///     docs: Vec<String>,
+///     words: AsyncMemo<Index, usize>,
+/// }
+///
+/// impl Actor for Index {
+///     type Context = Context<Self>;
+/// }
+///
+/// #[derive(Message)]
+/// #[rtype(result = "Option<Arc<usize>>")]
+/// struct Words;
+///
+/// impl Handler<Words> for Index {
+///     type Result = ResponseFuture<Option<Arc<usize>>>;
+///
+///     fn handle(&mut self, _: Words, ctx: &mut Self::Context) -> Self::Result {
+///         Box::pin(self.words.get(self, ctx))
+///     }
+/// }
+///
+/// # #[actix::main]
+/// # async fn main() {
+/// let words = AsyncMemo::new(|index: &Index| {
+///     let docs = index.docs.clone();
+///     async move { docs.iter().map(|doc| doc.split_whitespace().count()).sum() }
+/// });
+/// let docs = vec!["one two".to_owned(), "three".to_owned()];
+/// let addr = Index { docs, words }.start();
+/// assert_eq!(addr.send(Words).await.unwrap(), Some(Arc::new(3)));
+/// # }
+/// ```
+pub struct AsyncMemo<A, T> {
+    #[allow(clippy::type_complexity)]
+    compute: Box<dyn Fn(&A) -> Pin<Box<dyn Future<Output = T>>>>,
+    state: Rc<RefCell<MemoState<T>>>,
+}
+
+/// Queries waiting for a computation of an [`AsyncMemo`].
+type Waiters<T> = Rc<RefCell<Vec<OneshotSender<Arc<T>>>>>;
+
+struct MemoState<T> {
+    value: Option<Arc<T>>,
+    generation: u64,
+    /// Queries waiting for the value of the current generation, if it is computed.
+    waiters: Option<Waiters<T>>,
+}
+
+impl<A, T> fmt::Debug for AsyncMemo<A, T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.borrow();
+        fmt.debug_struct("AsyncMemo")
+            .field("cached", &state.value.is_some())
+            .field("computing", &state.waiters.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A: Actor, T: 'static> AsyncMemo<A, T> {
+    /// Creates a memo computing its value with the future returned by `compute`.
+    pub fn new<F, Fut>(compute: F) -> Self
+    where
+        F: Fn(&A) -> Fut + 'static,
+        Fut: Future<Output = T> + 'static,
+    {
+        AsyncMemo {
+            compute: Box::new(move |act| Box::pin(compute(act))),
+            state: Rc::new(RefCell::new(MemoState {
+                value: None,
+                generation: 0,
+                waiters: None,
+            })),
+        }
+    }
+
+    /// Returns a future resolving with the value.
+    ///
+    /// If the value was invalidated and is not being computed yet, the future computing it is
+    /// spawned into `ctx`. Resolves with `None` if the actor stops before the value is computed.
+    pub fn get(&self, act: &A, ctx: &mut A::Context) -> MemoFuture<T>
+    where
+        A::Context: AsyncContext<A>,
+    {
+        let mut state = self.state.borrow_mut();
+        if let Some(value) = &state.value {
+            return MemoFuture(MemoFutureState::Ready(Some(Arc::clone(value))));
+        }
+
+        let (tx, rx) = queue::oneshot();
+        if let Some(waiters) = &state.waiters {
+            waiters.borrow_mut().push(tx);
+            return MemoFuture(MemoFutureState::Waiting(rx));
+        }
+
+        let waiters = Rc::new(RefCell::new(vec![tx]));
+        state.waiters = Some(Rc::clone(&waiters));
+        let generation = state.generation;
+        drop(state);
+
+        let shared = Rc::clone(&self.state);
+        ctx.spawn(
+            wrap_future::<_, A>((self.compute)(act)).map(move |value, _, _| {
+                let value = Arc::new(value);
+                let mut state = shared.borrow_mut();
+                if state.generation == generation {
+                    state.value = Some(Arc::clone(&value));
+                    state.waiters = None;
+                }
+                for tx in waiters.borrow_mut().drain(..) {
+                    let _ = tx.send(Arc::clone(&value));
+                }
+            }),
+        );
+        MemoFuture(MemoFutureState::Waiting(rx))
+    }
+
+    /// Drops the value, so it is computed again by the next call to [`get()`](AsyncMemo::get).
+    ///
+    /// A computation in flight still resolves the queries waiting for it.
+    pub fn invalidate(&self) {
+        let mut state = self.state.borrow_mut();
+        state.value = None;
+        state.generation += 1;
+        state.waiters = None;
+    }
+}
+
+/// Future returned by [`AsyncMemo::get()`].
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct MemoFuture<T>(MemoFutureState<T>);
+
+#[derive(Debug)]
+enum MemoFutureState<T> {
+    Ready(Option<Arc<T>>),
+    Waiting(OneshotReceiver<Arc<T>>),
+}
+
+impl<T> Future for MemoFuture<T> {
+    type Output = Option<Arc<T>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match &mut self.0 {
+            MemoFutureState::Ready(value) => Poll::Ready(value.take()),
+            MemoFutureState::Waiting(rx) => Future::poll(Pin::new(rx), cx).map(Result::ok),
         }
     }
 }
@@ -0,0 +1,193 @@
+//! Tracking of what wakes up an actor, enabled by the `telemetry` feature.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+    task::{Wake, Waker},
+};
+
+use crate::actor::SpawnHandle;
+
+/// Number of recent wakeups kept by a context.
+const RECENT_WAKEUPS: usize = 32;
+
+/// Source of a wakeup of an actor, see [`Context::recent_wakeups()`](crate::Context::recent_wakeups).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum WakeupCause {
+    /// A message was queued in the mailbox, or the mailbox yielded after its message budget.
+    Mailbox,
+    /// The actor was interrupted with [`Addr::interrupt()`](crate::Addr::interrupt).
+    Interrupt,
+    /// A timer set with [`AsyncContext::set_timer()`](crate::AsyncContext::set_timer) fired.
+    Timer,
+    /// A spawned future, stream or wait future was woken up.
+    Future,
+    /// Functions deferred with [`AsyncContext::defer_fn()`](crate::AsyncContext::defer_fn) were
+    /// left to run.
+    Deferred,
+    /// The context looped again, without a wakeup, because futures were spawned or cancelled.
+    Modified,
+    /// The actor was polled without any of the above, e.g. for the first time.
+    Other,
+}
+
+const CAUSES: [WakeupCause; 7] = [
+    WakeupCause::Mailbox,
+    WakeupCause::Interrupt,
+    WakeupCause::Timer,
+    WakeupCause::Future,
+    WakeupCause::Deferred,
+    WakeupCause::Modified,
+    WakeupCause::Other,
+];
+
+impl WakeupCause {
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// Number of wakeups of an actor per cause, see [`ContextStats`](crate::ContextStats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct WakeupCounts {
+    /// Wakeups by [`WakeupCause::Mailbox`].
+    pub mailbox: u64,
+    /// Wakeups by [`WakeupCause::Interrupt`].
+    pub interrupt: u64,
+    /// Wakeups by [`WakeupCause::Timer`].
+    pub timer: u64,
+    /// Wakeups by [`WakeupCause::Future`].
+    pub future: u64,
+    /// Wakeups by [`WakeupCause::Deferred`].
+    pub deferred: u64,
+    /// Loops by [`WakeupCause::Modified`].
+    pub modified: u64,
+    /// Polls by [`WakeupCause::Other`].
+    pub other: u64,
+}
+
+impl WakeupCounts {
+    /// Returns the count of `cause`.
+    pub fn get(&self, cause: WakeupCause) -> u64 {
+        match cause {
+            WakeupCause::Mailbox => self.mailbox,
+            WakeupCause::Interrupt => self.interrupt,
+            WakeupCause::Timer => self.timer,
+            WakeupCause::Future => self.future,
+            WakeupCause::Deferred => self.deferred,
+            WakeupCause::Modified => self.modified,
+            WakeupCause::Other => self.other,
+        }
+    }
+
+    fn count(&mut self, cause: WakeupCause) {
+        let count = match cause {
+            WakeupCause::Mailbox => &mut self.mailbox,
+            WakeupCause::Interrupt => &mut self.interrupt,
+            WakeupCause::Timer => &mut self.timer,
+            WakeupCause::Future => &mut self.future,
+            WakeupCause::Deferred => &mut self.deferred,
+            WakeupCause::Modified => &mut self.modified,
+            WakeupCause::Other => &mut self.other,
+        };
+        *count += 1;
+    }
+}
+
+/// Waker of the context task which notes its cause before waking the task.
+struct CauseWaker {
+    pending: Arc<AtomicU8>,
+    bit: u8,
+    task: Waker,
+}
+
+impl Wake for CauseWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.pending.fetch_or(self.bit, Ordering::SeqCst);
+        self.task.wake_by_ref();
+    }
+}
+
+/// Causes of the wakeups of a context, the recent ones and their counts.
+pub(crate) struct WakeupTracker {
+    /// Causes noted since the context was last polled.
+    pending: Arc<AtomicU8>,
+    /// Wakers handed out per cause, with the task waker they wrap.
+    wakers: [Option<(Waker, Waker)>; CAUSES.len()],
+    recent: VecDeque<WakeupCause>,
+    counts: WakeupCounts,
+    /// Item firing the timers of the context.
+    pub(crate) timers: SpawnHandle,
+}
+
+impl WakeupTracker {
+    pub(crate) fn new() -> Self {
+        WakeupTracker {
+            pending: Arc::new(AtomicU8::new(0)),
+            wakers: Default::default(),
+            recent: VecDeque::with_capacity(RECENT_WAKEUPS),
+            counts: WakeupCounts::default(),
+            timers: SpawnHandle::default(),
+        }
+    }
+
+    /// Returns a waker of `task` which notes `cause` when it is woken.
+    pub(crate) fn waker(&mut self, cause: WakeupCause, task: &Waker) -> Waker {
+        let slot = &mut self.wakers[cause as usize];
+        match slot {
+            Some((wrapped, waker)) if wrapped.will_wake(task) => waker.clone(),
+            _ => {
+                let waker = Waker::from(Arc::new(CauseWaker {
+                    pending: Arc::clone(&self.pending),
+                    bit: cause.bit(),
+                    task: task.clone(),
+                }));
+                *slot = Some((task.clone(), waker.clone()));
+                waker
+            }
+        }
+    }
+
+    /// Notes `cause` for a wakeup the context schedules itself.
+    pub(crate) fn note(&self, cause: WakeupCause) {
+        self.pending.fetch_or(cause.bit(), Ordering::SeqCst);
+    }
+
+    /// Records the causes noted since the previous poll, `Other` if there are none.
+    pub(crate) fn polled(&mut self) {
+        let bits = self.pending.swap(0, Ordering::SeqCst);
+        if bits == 0 {
+            self.record(WakeupCause::Other);
+        }
+        for cause in CAUSES {
+            if bits & cause.bit() != 0 {
+                self.record(cause);
+            }
+        }
+    }
+
+    pub(crate) fn record(&mut self, cause: WakeupCause) {
+        if self.recent.len() == RECENT_WAKEUPS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(cause);
+        self.counts.count(cause);
+    }
+
+    pub(crate) fn recent(&self) -> Vec<WakeupCause> {
+        self.recent.iter().copied().collect()
+    }
+
+    pub(crate) fn counts(&self) -> WakeupCounts {
+        self.counts
+    }
+}
@@ -10,6 +10,7 @@ use std::{
 
 use actix::prelude::*;
 use actix_rt::time::{sleep, Instant};
+use futures_util::stream::StreamExt as _;
 
 #[derive(Clone, Debug)]
 struct Num(usize);
@@ -18,7 +19,11 @@ impl Message for Num {
     type Result = ();
 }
 
-struct MyActor(Arc<AtomicUsize>, Arc<AtomicBool>, Running);
+struct MyActor(
+    Arc<AtomicUsize>,
+    Arc<AtomicBool>,
+    #[allow(dead_code)] Running,
+);
 
 impl Actor for MyActor {
     type Context = actix::Context<Self>;
@@ -252,3 +257,137 @@ fn test_run_interval() {
     // We wait 10 intervals by ~100ms
     assert_eq!(result.elapsed().as_secs(), 1);
 }
+
+struct Fallible {
+    started: Arc<AtomicBool>,
+    handled: Arc<AtomicUsize>,
+}
+
+impl Actor for Fallible {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _: &mut Self::Context) {
+        self.started.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Handler<Num> for Fallible {
+    type Result = ();
+
+    fn handle(&mut self, msg: Num, _: &mut Self::Context) {
+        assert!(self.started.load(Ordering::SeqCst));
+        self.handled.fetch_add(msg.0, Ordering::SeqCst);
+    }
+}
+
+#[actix::test]
+async fn test_try_create_error_tears_down_context() {
+    let spawned = Arc::new(AtomicBool::new(false));
+    let spawned2 = Arc::clone(&spawned);
+    let mut addr = None;
+
+    let res = Fallible::try_create(|ctx| {
+        addr = Some(ctx.address());
+        ctx.spawn(actix::fut::wrap_future(async move {
+            spawned2.store(true, Ordering::SeqCst);
+        }));
+        Err::<Fallible, _>("connection refused")
+    });
+
+    assert_eq!(res.unwrap_err(), "connection refused");
+
+    let addr = addr.unwrap();
+    assert!(!addr.connected());
+    assert_eq!(addr.send(Num(1)).await, Err(MailboxError::Closed));
+
+    sleep(Duration::from_millis(20)).await;
+    assert!(!spawned.load(Ordering::SeqCst));
+}
+
+#[actix::test]
+async fn test_try_create_ok() {
+    let started = Arc::new(AtomicBool::new(false));
+    let handled = Arc::new(AtomicUsize::new(0));
+
+    let addr = Fallible::try_create(|_| {
+        Ok::<_, ()>(Fallible {
+            started: Arc::clone(&started),
+            handled: Arc::clone(&handled),
+        })
+    })
+    .unwrap();
+
+    addr.send(Num(2)).await.unwrap();
+    assert!(started.load(Ordering::SeqCst));
+    assert_eq!(handled.load(Ordering::SeqCst), 2);
+}
+
+#[actix::test]
+async fn test_try_create_async_buffers_messages() {
+    let started = Arc::new(AtomicBool::new(false));
+    let handled = Arc::new(AtomicUsize::new(0));
+    let (started2, handled2) = (Arc::clone(&started), Arc::clone(&handled));
+
+    let (addr, created) = Fallible::try_create_async(|_| async move {
+        sleep(Duration::from_millis(20)).await;
+        Ok::<_, ()>(Fallible {
+            started: started2,
+            handled: handled2,
+        })
+    });
+
+    addr.do_send(Num(1));
+    addr.do_send(Num(2));
+    assert!(!started.load(Ordering::SeqCst));
+
+    addr.send(Num(3)).await.unwrap();
+    assert!(started.load(Ordering::SeqCst));
+    assert_eq!(handled.load(Ordering::SeqCst), 6);
+    assert_eq!(created.await.unwrap(), Ok(()));
+}
+
+#[actix::test]
+async fn test_try_create_async_error_disconnects() {
+    let (addr, created) = Fallible::try_create_async(|_| async {
+        sleep(Duration::from_millis(20)).await;
+        Err::<Fallible, _>("connection refused")
+    });
+
+    assert!(addr.connected());
+    assert_eq!(addr.send(Num(1)).await, Err(MailboxError::Closed));
+    assert!(!addr.connected());
+    assert_eq!(created.await.unwrap(), Err("connection refused"));
+    assert_eq!(
+        addr.state_stream().collect::<Vec<_>>().await,
+        vec![ActorState::Stopped]
+    );
+}
+
+struct ThreadReporter;
+
+impl Actor for ThreadReporter {
+    type Context = Context<Self>;
+}
+
+#[derive(Message)]
+#[rtype(result = "std::thread::ThreadId")]
+struct GetThread;
+
+impl Handler<GetThread> for ThreadReporter {
+    type Result = MessageResult<GetThread>;
+
+    fn handle(&mut self, _: GetThread, _: &mut Self::Context) -> Self::Result {
+        MessageResult(std::thread::current().id())
+    }
+}
+
+#[actix::test]
+async fn test_start_in() {
+    let arbiter = Arbiter::new();
+    let addr = ThreadReporter.start_in(&arbiter.handle());
+
+    let id = addr.send(GetThread).await.unwrap();
+    assert_ne!(id, std::thread::current().id());
+
+    arbiter.stop();
+}
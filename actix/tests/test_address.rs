@@ -1,18 +1,23 @@
 use std::{
     collections::HashSet,
+    future::poll_fn,
     mem::drop,
+    pin::Pin,
     sync::{
         atomic::{AtomicUsize, Ordering},
         Arc,
     },
+    task::Poll,
+    thread,
     time::Duration,
 };
 
-use actix::{prelude::*, WeakRecipient};
+use actix::{prelude::*, RateLimit, RateLimitPolicy, UnhandledMessage, WeakRecipient};
 use actix_rt::time::sleep;
+use futures_sink::Sink;
 
 #[derive(Debug)]
-struct Ping(usize);
+struct Ping(#[allow(dead_code)] usize);
 
 impl Message for Ping {
     type Result = ();
@@ -461,6 +466,7 @@ fn test_address_hash() {
         let addr0 = MyActor(count0).start();
         let addr01 = addr0.clone();
 
+        #[allow(clippy::mutable_key_type)]
         let mut addresses = HashSet::new();
         addresses.insert(addr0.clone());
         addresses.insert(addr01.clone());
@@ -543,3 +549,947 @@ fn test_recipient_hash() {
         System::current().stop();
     });
 }
+
+#[derive(Clone)]
+struct Delay(u64);
+
+impl Message for Delay {
+    type Result = u64;
+}
+
+/// Replies with its id after its delay, stops right away on a zero delay.
+struct Delayer {
+    id: u64,
+    delay: u64,
+}
+
+impl Actor for Delayer {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if self.delay == 0 {
+            ctx.stop();
+        }
+    }
+}
+
+impl Handler<Delay> for Delayer {
+    type Result = ResponseFuture<u64>;
+
+    fn handle(&mut self, msg: Delay, _: &mut Self::Context) -> Self::Result {
+        let (id, delay) = (self.id, self.delay * msg.0);
+        Box::pin(async move {
+            sleep(Duration::from_millis(delay)).await;
+            id
+        })
+    }
+}
+
+fn start_delayers(delays: &[u64]) -> Vec<Addr<Delayer>> {
+    delays
+        .iter()
+        .enumerate()
+        .map(|(id, delay)| {
+            Delayer {
+                id: id as u64,
+                delay: *delay,
+            }
+            .start()
+        })
+        .collect()
+}
+
+#[test]
+fn test_send_all_preserves_order() {
+    System::new().block_on(async {
+        let addrs = start_delayers(&[30, 10, 20]);
+        assert_eq!(
+            actix::send_all(&addrs, Delay(1)).await.unwrap(),
+            vec![0, 1, 2]
+        );
+
+        let recipients: Vec<_> = addrs.into_iter().map(Addr::recipient).collect();
+        assert_eq!(
+            actix::send_all_recipients(&recipients, Delay(1))
+                .await
+                .unwrap(),
+            vec![0, 1, 2]
+        );
+
+        let none: &[Addr<Delayer>] = &[];
+        assert!(actix::send_all(none, Delay(1)).await.unwrap().is_empty());
+    });
+}
+
+#[test]
+fn test_send_all_fails_early() {
+    System::new().block_on(async {
+        let addrs = start_delayers(&[10, 0]);
+        let start = actix_rt::time::Instant::now();
+
+        // the second actor stops without replying, the first one is not awaited
+        let res = actix::send_all(&addrs, Delay(100)).await;
+        assert_eq!(res, Err(MailboxError::Closed));
+        assert!(start.elapsed() < Duration::from_millis(500));
+    });
+}
+
+#[test]
+fn test_send_all_settled() {
+    System::new().block_on(async {
+        let addrs = start_delayers(&[10, 0, 20]);
+
+        let res = actix::send_all(&addrs, Delay(1)).settled().await;
+        assert_eq!(res, vec![Ok(0), Err(MailboxError::Closed), Ok(2)]);
+    });
+}
+
+#[test]
+fn test_send_all_timeout() {
+    System::new().block_on(async {
+        let addrs = start_delayers(&[10, 500, 20]);
+
+        let res = actix::send_all(&addrs, Delay(1))
+            .timeout(Duration::from_millis(100))
+            .await;
+        assert_eq!(res, Err(MailboxError::Timeout));
+
+        let res = actix::send_all(&addrs, Delay(1))
+            .settled()
+            .timeout(Duration::from_millis(100))
+            .await;
+        assert_eq!(res, vec![Ok(0), Err(MailboxError::Timeout), Ok(2)]);
+    });
+}
+
+#[test]
+fn test_broadcast() {
+    System::new().block_on(async {
+        let addrs = start_delayers(&[10, 0, 20]);
+        addrs[1].closed().await;
+
+        let mut targets: Vec<_> = addrs.into_iter().map(Addr::recipient).collect();
+        let report = actix::broadcast(&targets, &Delay(1));
+        assert_eq!(report.delivered(), 2);
+        assert_eq!(report.failed(), [(1, MailboxError::Closed)]);
+        assert!(!report.is_complete());
+
+        report.retain_delivered(&mut targets);
+        assert_eq!(targets.len(), 2);
+        assert!(actix::broadcast(&targets, &Delay(1)).is_complete());
+    });
+}
+
+/// Blocks its mailbox of capacity 1 for the given time once started.
+struct Stalled(u64);
+
+impl Actor for Stalled {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.set_mailbox_capacity(1);
+        ctx.wait(sleep(Duration::from_millis(self.0)).into_actor(self));
+    }
+}
+
+impl Handler<Delay> for Stalled {
+    type Result = u64;
+
+    fn handle(&mut self, _: Delay, _: &mut Self::Context) -> u64 {
+        0
+    }
+}
+
+/// Fills the mailbox of the recipient, until it is parked.
+fn fill(rcp: &Recipient<Delay>) {
+    while rcp.try_send(Delay(1)).is_ok() {}
+}
+
+#[test]
+fn test_broadcast_async_waits_for_room() {
+    System::new().block_on(async {
+        let targets = vec![
+            Stalled(50).start().recipient(),
+            Stalled(0).start().recipient(),
+        ];
+        fill(&targets[0]);
+        let start = actix_rt::time::Instant::now();
+
+        let report = actix::broadcast_async(&targets, Delay(1), Duration::from_secs(1)).await;
+        assert_eq!(report.delivered(), 2);
+        assert!(report.is_complete());
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    });
+}
+
+#[test]
+fn test_broadcast_async_timeout() {
+    System::new().block_on(async {
+        let stopped = Delayer { id: 0, delay: 0 }.start();
+        stopped.closed().await;
+        let targets = vec![
+            Stalled(500).start().recipient(),
+            stopped.recipient(),
+            Stalled(0).start().recipient(),
+        ];
+        fill(&targets[0]);
+
+        let report = actix::broadcast_async(&targets, Delay(1), Duration::from_millis(50)).await;
+        assert_eq!(report.delivered(), 1);
+        assert_eq!(
+            report.failed(),
+            [(0, MailboxError::Timeout), (1, MailboxError::Closed)]
+        );
+    });
+}
+
+#[test]
+fn test_revocable_recipient() {
+    let count = Arc::new(AtomicUsize::new(0));
+
+    System::new().block_on(async {
+        let addr = MyActor(Arc::clone(&count)).start();
+        let (rcp, handle) = addr.revocable_recipient::<Ping>();
+        assert!(rcp.connected());
+        assert!(!rcp.is_revoked());
+
+        rcp.send(Ping(0)).await.unwrap();
+        assert_eq!(count.load(Ordering::Relaxed), 1);
+
+        // queued messages are dropped as well
+        rcp.do_send(Ping(1));
+        let req = rcp.send(Ping(2));
+        thread::spawn(move || handle.revoke()).join().unwrap();
+        assert_eq!(req.await, Err(MailboxError::Closed));
+
+        assert!(rcp.is_revoked());
+        assert!(!rcp.connected());
+        assert!(matches!(rcp.try_send(Ping(3)), Err(SendError::Revoked(_))));
+        assert_eq!(rcp.send(Ping(4)).await, Err(MailboxError::Closed));
+
+        // the actor itself keeps running
+        addr.send(Ping(5)).await.unwrap();
+        assert_eq!(count.load(Ordering::Relaxed), 2);
+        assert!(rcp.downgrade().upgrade().unwrap().is_revoked());
+    });
+}
+
+struct Unhandling {
+    count: Arc<AtomicUsize>,
+    unhandled: Vec<(&'static str, bool)>,
+}
+
+impl Actor for Unhandling {
+    type Context = actix::Context<Self>;
+
+    fn unhandled(&mut self, msg: UnhandledMessage, _: &mut Self::Context) {
+        self.unhandled.push((msg.type_name(), msg.expects_reply()));
+        msg.reject();
+    }
+}
+
+impl Handler<Ping> for Unhandling {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Self::Context) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+struct GetUnhandled;
+
+impl Message for GetUnhandled {
+    type Result = Vec<(&'static str, bool)>;
+}
+
+impl Handler<GetUnhandled> for Unhandling {
+    type Result = MessageResult<GetUnhandled>;
+
+    fn handle(&mut self, _: GetUnhandled, _: &mut Self::Context) -> Self::Result {
+        MessageResult(self.unhandled.clone())
+    }
+}
+
+#[test]
+fn test_revocable_recipient_unhandled() {
+    let count = Arc::new(AtomicUsize::new(0));
+
+    System::new().block_on(async {
+        let addr = Unhandling {
+            count: Arc::clone(&count),
+            unhandled: Vec::new(),
+        }
+        .start();
+        let (rcp, handle) = addr.revocable_recipient::<Ping>();
+
+        rcp.do_send(Ping(0));
+        let req = rcp.send(Ping(1));
+        handle.revoke();
+        assert_eq!(req.await, Err(MailboxError::Closed));
+
+        // messages rejected before being queued do not reach the actor
+        assert_eq!(rcp.send(Ping(2)).await, Err(MailboxError::Closed));
+
+        let ping = std::any::type_name::<Ping>();
+        assert_eq!(
+            addr.send(GetUnhandled).await.unwrap(),
+            [(ping, false), (ping, true)]
+        );
+        assert_eq!(count.load(Ordering::Relaxed), 0);
+    });
+}
+
+#[test]
+fn test_revocable_recipient_concurrent_sends() {
+    let count = Arc::new(AtomicUsize::new(0));
+
+    System::new().block_on(async {
+        let addr = SyncArbiter::start(2, {
+            let count = Arc::clone(&count);
+            move || SyncPinger(Arc::clone(&count))
+        });
+        let (rcp, handle) = addr.revocable_recipient::<Ping>();
+
+        let senders: Vec<_> = (0..4)
+            .map(|_| {
+                let rcp = rcp.clone();
+                thread::spawn(move || loop {
+                    match rcp.try_send(Ping(0)) {
+                        Err(SendError::Revoked(_)) => break,
+                        Err(SendError::Closed(_)) => panic!("Should not happen"),
+                        _ => thread::yield_now(),
+                    }
+                })
+            })
+            .collect();
+
+        sleep(Duration::from_millis(10)).await;
+        handle.revoke();
+        let handled = count.load(Ordering::SeqCst);
+        for sender in senders {
+            sender.join().unwrap();
+        }
+
+        // at most the messages in flight on both sync threads are still handled
+        sleep(Duration::from_millis(50)).await;
+        assert!(handled > 0);
+        assert!(count.load(Ordering::SeqCst) <= handled + 2);
+    });
+}
+
+struct SyncPinger(Arc<AtomicUsize>);
+
+impl Actor for SyncPinger {
+    type Context = SyncContext<Self>;
+}
+
+impl Handler<Ping> for SyncPinger {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Self::Context) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn start_rate_limited(count: &Arc<AtomicUsize>, policy: RateLimitPolicy) -> Addr<MyActor> {
+    let count = Arc::clone(count);
+    MyActor::create(move |ctx| {
+        ctx.set_rate_limit(Some(RateLimit {
+            per_second: 20,
+            burst: 2,
+            policy,
+        }));
+        MyActor(count)
+    })
+}
+
+#[test]
+fn test_rate_limit_reject() {
+    let count = Arc::new(AtomicUsize::new(0));
+
+    System::new().block_on(async {
+        let addr = start_rate_limited(&count, RateLimitPolicy::Reject);
+        sleep(Duration::from_millis(10)).await;
+
+        addr.send(Ping(0)).await.unwrap();
+        addr.send(Ping(1)).await.unwrap();
+        assert_eq!(addr.send(Ping(2)).await, Err(MailboxError::RateLimited));
+        assert!(matches!(
+            addr.try_send(Ping(3)),
+            Err(SendError::RateLimited(_))
+        ));
+        addr.do_send(Ping(4));
+
+        // a token is refilled every 50ms
+        sleep(Duration::from_millis(60)).await;
+        addr.send(Ping(5)).await.unwrap();
+        assert_eq!(count.load(Ordering::Relaxed), 3);
+    });
+}
+
+#[test]
+fn test_rate_limit_delay() {
+    let count = Arc::new(AtomicUsize::new(0));
+
+    System::new().block_on(async {
+        let addr = start_rate_limited(&count, RateLimitPolicy::Delay);
+        sleep(Duration::from_millis(10)).await;
+
+        for i in 0..5 {
+            addr.do_send(Ping(i));
+        }
+
+        // burst is handled right away, then one message every 50ms
+        sleep(Duration::from_millis(20)).await;
+        assert_eq!(count.load(Ordering::Relaxed), 2);
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(count.load(Ordering::Relaxed), 3);
+        sleep(Duration::from_millis(120)).await;
+        assert_eq!(count.load(Ordering::Relaxed), 5);
+
+        // delayed requests are answered once handled
+        let start = actix_rt::time::Instant::now();
+        addr.send(Ping(5)).await.unwrap();
+        addr.send(Ping(6)).await.unwrap();
+        addr.send(Ping(7)).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        assert_eq!(count.load(Ordering::Relaxed), 8);
+    });
+}
+
+struct Counter(usize);
+
+impl Actor for Counter {
+    type Context = Context<Self>;
+}
+
+/// Previous version of `AddV2`.
+struct AddV1(u32);
+
+impl Message for AddV1 {
+    type Result = u32;
+}
+
+struct AddV2 {
+    amount: usize,
+    delay: Option<Duration>,
+}
+
+impl Message for AddV2 {
+    type Result = Result<usize, ()>;
+}
+
+impl Handler<AddV2> for Counter {
+    type Result = ResponseActFuture<Self, Result<usize, ()>>;
+
+    fn handle(&mut self, msg: AddV2, _: &mut Self::Context) -> Self::Result {
+        self.0 += msg.amount;
+        let total = self.0;
+        match msg.delay {
+            Some(delay) => Box::pin(sleep(delay).into_actor(self).map(move |_, _, _| Ok(total))),
+            None => Box::pin(fut::ready(Ok(total))),
+        }
+    }
+}
+
+#[test]
+fn test_legacy_recipient() {
+    System::new().block_on(async {
+        let addr = Counter(0).start();
+        let convert = |delay| {
+            move |AddV1(amount)| AddV2 {
+                amount: amount as usize,
+                delay,
+            }
+        };
+        let reply = |res: Result<usize, ()>| res.unwrap() as u32;
+
+        let rcp = addr.legacy_recipient(convert(None), reply);
+        assert_eq!(rcp.send(AddV1(1)).await, Ok(1));
+        rcp.do_send(AddV1(2));
+        assert_eq!(
+            addr.send(AddV2 {
+                amount: 0,
+                delay: None
+            })
+            .await,
+            Ok(Ok(3))
+        );
+
+        // asynchronous responses are converted as well
+        let delayed = addr.legacy_recipient(convert(Some(Duration::from_millis(10))), reply);
+        assert_eq!(delayed.send(AddV1(4)).await, Ok(7));
+        assert!(delayed.downgrade().upgrade().is_some());
+        assert_ne!(rcp, delayed);
+    });
+}
+
+struct Blob {
+    data: Vec<u8>,
+    decoded_on: Option<thread::ThreadId>,
+}
+
+impl Message for Blob {
+    type Result = (usize, Option<thread::ThreadId>);
+}
+
+struct Encoded {
+    data: Vec<u8>,
+    on: thread::ThreadId,
+}
+
+static ENCODED: AtomicUsize = AtomicUsize::new(0);
+
+impl TransformOnSend for Blob {
+    type Encoded = Encoded;
+
+    fn encode(self) -> Encoded {
+        ENCODED.fetch_add(1, Ordering::SeqCst);
+        Encoded {
+            data: self.data,
+            on: thread::current().id(),
+        }
+    }
+
+    fn decode(enc: Encoded) -> Self {
+        assert_ne!(enc.on, thread::current().id());
+        Blob {
+            data: enc.data,
+            decoded_on: Some(thread::current().id()),
+        }
+    }
+}
+
+struct BlobStore(Vec<usize>);
+
+impl Actor for BlobStore {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.notify(Blob {
+            data: vec![0; 3],
+            decoded_on: None,
+        });
+    }
+}
+
+impl Handler<Blob> for BlobStore {
+    type Result = MessageResult<Blob>;
+
+    fn handle(&mut self, msg: Blob, _: &mut Self::Context) -> Self::Result {
+        self.0.push(msg.data.len());
+        MessageResult((msg.data.len(), msg.decoded_on))
+    }
+}
+
+#[test]
+fn test_transform_on_send() {
+    System::new().block_on(async {
+        let arbiter = Arbiter::new();
+        let addr = BlobStore::start_in_arbiter(&arbiter.handle(), |_| BlobStore(Vec::new()));
+
+        // mailbox sends which are not transformed, and notifications, skip the transform
+        let (len, decoded_on) = addr
+            .send(Blob {
+                data: vec![0; 5],
+                decoded_on: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!((len, decoded_on), (5, None));
+        assert_eq!(ENCODED.load(Ordering::SeqCst), 0);
+
+        let (len, decoded_on) = addr
+            .send_transformed(Blob {
+                data: vec![0; 1024],
+                decoded_on: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(len, 1024);
+        assert!(decoded_on.is_some());
+        assert_ne!(decoded_on, Some(thread::current().id()));
+
+        addr.do_send_transformed(Blob {
+            data: vec![0; 7],
+            decoded_on: None,
+        });
+        let (len, _) = addr
+            .send(Blob {
+                data: Vec::new(),
+                decoded_on: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(len, 0);
+        assert_eq!(ENCODED.load(Ordering::SeqCst), 2);
+
+        arbiter.stop();
+    });
+}
+
+#[test]
+fn test_closed() {
+    System::new().block_on(async {
+        let addr = MyActor(Arc::default()).start();
+        assert!(addr.connected());
+
+        let closed = addr.closed();
+        let waiters: Vec<_> = (0..100).map(|_| actix_rt::spawn(addr.closed())).collect();
+        actix_rt::task::yield_now().await;
+
+        addr.do_send(Ping(0));
+        let weak = addr.downgrade();
+        drop(addr);
+        closed.await;
+        for waiter in waiters {
+            waiter.await.unwrap();
+        }
+        assert!(weak.upgrade().is_none());
+
+        // the future resolves immediately once the actor is gone
+        let addr = MyActor::create(|ctx| {
+            ctx.stop();
+            MyActor(Arc::default())
+        });
+        while addr.connected() {
+            sleep(Duration::from_millis(1)).await;
+        }
+        addr.closed().await;
+    });
+}
+
+#[derive(Debug)]
+struct Seq(usize);
+
+impl Message for Seq {
+    type Result = usize;
+}
+
+struct SeqRecorder(Arc<std::sync::Mutex<Vec<usize>>>);
+
+impl Actor for SeqRecorder {
+    type Context = actix::Context<Self>;
+}
+
+impl Handler<Seq> for SeqRecorder {
+    type Result = usize;
+
+    fn handle(&mut self, msg: Seq, _: &mut Self::Context) -> usize {
+        self.0.lock().unwrap().push(msg.0);
+        msg.0
+    }
+}
+
+struct SyncSeqRecorder(Arc<std::sync::Mutex<Vec<usize>>>);
+
+impl Actor for SyncSeqRecorder {
+    type Context = SyncContext<Self>;
+}
+
+impl Handler<Seq> for SyncSeqRecorder {
+    type Result = usize;
+
+    fn handle(&mut self, msg: Seq, _: &mut Self::Context) -> usize {
+        self.0.lock().unwrap().push(msg.0);
+        msg.0
+    }
+}
+
+/// Interleaves `send`, `do_send` and `try_send` through `addr`, returning the sequence numbers
+/// which were accepted, in order.
+///
+/// `send` and `try_send` are rejected once the mailbox and the backlog of the address are full.
+async fn send_interleaved<A>(addr: &Addr<A>, count: usize) -> Vec<usize>
+where
+    A: Actor + Handler<Seq>,
+    A::Context: actix::dev::ToEnvelope<A, Seq>,
+{
+    let mut sent = Vec::new();
+    let mut requests = Vec::new();
+    for n in 0..count {
+        match n % 3 {
+            0 => {
+                requests.push((n, addr.send(Seq(n))));
+                sent.push(n);
+            }
+            1 => {
+                addr.do_send(Seq(n));
+                sent.push(n);
+            }
+            // rejected while the mailbox is full
+            _ => {
+                if addr.try_send(Seq(n)).is_ok() {
+                    sent.push(n);
+                }
+            }
+        }
+        if n % 97 == 0 {
+            actix_rt::task::yield_now().await;
+        }
+    }
+    for (n, req) in requests {
+        match req.await {
+            Ok(res) => assert_eq!(res, n),
+            Err(MailboxError::Full) => sent.retain(|&sent| sent != n),
+            Err(err) => panic!("request {} failed: {}", n, err),
+        }
+    }
+    sent
+}
+
+#[test]
+fn test_send_order() {
+    System::new().block_on(async {
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let addr = SeqRecorder::create({
+            let received = Arc::clone(&received);
+            |ctx| {
+                ctx.set_mailbox_capacity(4);
+                SeqRecorder(received)
+            }
+        });
+
+        let sent = send_interleaved(&addr, 5000).await;
+        addr.send(Seq(usize::MAX)).await.unwrap();
+
+        let mut received = received.lock().unwrap().clone();
+        assert_eq!(received.pop(), Some(usize::MAX));
+        assert!(sent.len() > 3334);
+        assert_eq!(received, sent);
+    });
+}
+
+#[test]
+fn test_send_order_sync() {
+    System::new().block_on(async {
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let addr = SyncArbiter::start(1, {
+            let received = Arc::clone(&received);
+            move || SyncSeqRecorder(Arc::clone(&received))
+        });
+
+        let sent = send_interleaved(&addr, 5000).await;
+        addr.send(Seq(usize::MAX)).await.unwrap();
+
+        let mut received = received.lock().unwrap().clone();
+        assert_eq!(received.pop(), Some(usize::MAX));
+        assert_eq!(received, sent);
+    });
+}
+
+#[test]
+fn test_address_sink() {
+    System::new().block_on(async {
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let addr = SeqRecorder::create({
+            let received = Arc::clone(&received);
+            |ctx| {
+                ctx.set_mailbox_capacity(1);
+                SeqRecorder(received)
+            }
+        });
+        let mut sink = addr.clone().into_sink::<Seq>();
+
+        for n in 0..20 {
+            poll_fn(|cx| Pin::new(&mut sink).poll_ready(cx))
+                .await
+                .unwrap();
+            Pin::new(&mut sink).start_send(Seq(n)).unwrap();
+
+            // the message filled the mailbox, so the sink waits for the actor
+            let ready = poll_fn(|cx| Poll::Ready(Pin::new(&mut sink).poll_ready(cx))).await;
+            assert!(ready.is_pending());
+        }
+        poll_fn(|cx| Pin::new(&mut sink).poll_close(cx))
+            .await
+            .unwrap();
+
+        addr.send(Seq(usize::MAX)).await.unwrap();
+        let mut received = received.lock().unwrap().clone();
+        assert_eq!(received.pop(), Some(usize::MAX));
+        assert_eq!(received, (0..20).collect::<Vec<_>>());
+
+        let addr = SeqRecorder::create(|ctx| {
+            ctx.stop();
+            SeqRecorder(Arc::default())
+        });
+        let mut sink = addr.clone().into_sink::<Seq>();
+        addr.closed().await;
+        let res = poll_fn(|cx| Pin::new(&mut sink).poll_ready(cx)).await;
+        assert_eq!(res, Err(MailboxError::Closed));
+        let res = Pin::new(&mut sink).start_send(Seq(0));
+        assert_eq!(res, Err(MailboxError::Closed));
+    });
+}
+
+#[test]
+fn test_recipient_sink_forward() {
+    use futures_util::StreamExt as _;
+
+    System::new().block_on(async {
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let addr = SeqRecorder::create({
+            let received = Arc::clone(&received);
+            |ctx| {
+                ctx.set_mailbox_capacity(16);
+                SeqRecorder(received)
+            }
+        });
+        let sink = addr.clone().recipient::<Seq>().into_sink();
+
+        futures_util::stream::iter((0..10_000).map(|n| Ok(Seq(n))))
+            .forward(sink)
+            .await
+            .unwrap();
+
+        addr.send(Seq(usize::MAX)).await.unwrap();
+        let mut received = received.lock().unwrap().clone();
+        assert_eq!(received.pop(), Some(usize::MAX));
+        assert_eq!(received, (0..10_000).collect::<Vec<_>>());
+    });
+}
+
+#[derive(Default)]
+struct Worker {
+    handled: usize,
+}
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+}
+
+/// Works for the given time, replying whether the request has a deadline.
+struct Work(Duration);
+
+impl Message for Work {
+    type Result = bool;
+}
+
+impl Handler<Work> for Worker {
+    type Result = bool;
+
+    fn handle(&mut self, Work(dur): Work, ctx: &mut Self::Context) -> bool {
+        self.handled += 1;
+        thread::sleep(dur);
+        ctx.request_deadline().is_some()
+    }
+}
+
+/// Replies with the number of handled and expired requests.
+struct Expired;
+
+impl Message for Expired {
+    type Result = (usize, u64);
+}
+
+impl Handler<Expired> for Worker {
+    type Result = MessageResult<Expired>;
+
+    fn handle(&mut self, _: Expired, ctx: &mut Self::Context) -> Self::Result {
+        MessageResult((self.handled, ctx.stats().expired_requests))
+    }
+}
+
+struct Front {
+    worker: Addr<Worker>,
+}
+
+impl Actor for Front {
+    type Context = Context<Self>;
+}
+
+impl Handler<Work> for Front {
+    type Result = ResponseFuture<bool>;
+
+    fn handle(&mut self, work: Work, ctx: &mut Self::Context) -> Self::Result {
+        let req = ctx.send_downstream(&self.worker, work);
+        Box::pin(async move { req.await.unwrap_or(false) })
+    }
+}
+
+#[test]
+fn test_send_with_deadline() {
+    System::new().block_on(async {
+        let addr = Worker::default().start();
+        let deadline = actix::clock::Instant::now() + Duration::from_secs(1);
+        assert_eq!(addr.send(Work(Duration::ZERO)).await, Ok(false));
+        assert_eq!(
+            addr.send_with_deadline(Work(Duration::ZERO), deadline)
+                .await,
+            Ok(true)
+        );
+
+        // passes while handling
+        let deadline = actix::clock::Instant::now() + Duration::from_millis(20);
+        let res = addr
+            .send_with_deadline(Work(Duration::from_millis(50)), deadline)
+            .await;
+        assert_eq!(res, Err(MailboxError::Timeout));
+        assert_eq!(addr.send(Expired).await.unwrap(), (3, 1));
+
+        // passes in the mailbox, the handler does not run
+        let busy = addr.send(Work(Duration::from_millis(50)));
+        let deadline = actix::clock::Instant::now() + Duration::from_millis(10);
+        let res = addr
+            .send_with_deadline(Work(Duration::ZERO), deadline)
+            .await;
+        assert_eq!(res, Err(MailboxError::Timeout));
+        assert_eq!(busy.await, Ok(false));
+        assert_eq!(addr.send(Expired).await.unwrap(), (4, 2));
+    });
+}
+
+#[test]
+fn test_send_downstream() {
+    System::new().block_on(async {
+        let worker = Worker::default().start();
+        let front = Front { worker }.start();
+        let deadline = actix::clock::Instant::now() + Duration::from_secs(1);
+
+        assert_eq!(
+            front
+                .send_with_deadline(Work(Duration::ZERO), deadline)
+                .await,
+            Ok(true)
+        );
+        assert_eq!(front.send(Work(Duration::ZERO)).await, Ok(false));
+    });
+}
+
+#[test]
+fn test_send_backlog_bounded() {
+    System::new().block_on(async {
+        use std::future::Future as _;
+
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut ctx = Context::new();
+        ctx.set_mailbox_capacity(2);
+        let addr = ctx.address();
+
+        // the actor is not running, requests fill the mailbox and then the backlog of `addr`
+        let mut requests = Vec::new();
+        loop {
+            let mut req = Box::pin(addr.send(Seq(requests.len())));
+            match poll_fn(|cx| Poll::Ready(req.as_mut().poll(cx))).await {
+                Poll::Pending => requests.push(req),
+                Poll::Ready(res) => {
+                    assert_eq!(res, Err(MailboxError::Full));
+                    break;
+                }
+            }
+            assert!(requests.len() <= 8, "backlog is not bounded");
+        }
+        // two in the mailbox, two in the backlog
+        let count = requests.len();
+        assert_eq!(count, 4);
+
+        ctx.run(SeqRecorder(Arc::clone(&received)));
+        for (n, req) in requests.into_iter().enumerate() {
+            assert_eq!(req.await, Ok(n));
+        }
+        // the backlog is drained, so the address accepts messages again
+        assert_eq!(addr.send(Seq(count)).await, Ok(count));
+        assert_eq!(*received.lock().unwrap(), (0..=count).collect::<Vec<_>>());
+    });
+}
@@ -1,13 +1,17 @@
-use std::sync::{
-    atomic::{AtomicUsize, Ordering},
-    Arc,
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
-use actix::prelude::*;
+use actix::{prelude::*, ArbiterBuilder, ArbiterError, ArbiterStats, PanicPolicy, SystemConfig};
 use tokio::sync::oneshot;
 
 #[derive(Debug)]
-struct Ping(usize);
+struct Ping(#[allow(dead_code)] usize);
 
 impl Message for Ping {
     type Result = ();
@@ -59,3 +63,328 @@ fn test_start_actor_message() {
 
     assert_eq!(count.load(Ordering::Relaxed), 1);
 }
+
+struct Panic;
+
+impl Message for Panic {
+    type Result = ();
+}
+
+struct Fragile;
+
+impl Actor for Fragile {
+    type Context = Context<Self>;
+}
+
+impl Handler<Panic> for Fragile {
+    type Result = ();
+
+    fn handle(&mut self, _: Panic, _: &mut Self::Context) {
+        panic!("fragile actor failed");
+    }
+}
+
+impl Handler<Ping> for Fragile {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Self::Context) {}
+}
+
+struct Block(Duration);
+
+impl Message for Block {
+    type Result = ();
+}
+
+impl Handler<Block> for Fragile {
+    type Result = ();
+
+    fn handle(&mut self, msg: Block, _: &mut Self::Context) {
+        thread::sleep(msg.0);
+    }
+}
+
+struct PanicOnDrop;
+
+impl Drop for PanicOnDrop {
+    fn drop(&mut self) {
+        panic!("future dropped");
+    }
+}
+
+struct Poison;
+
+impl Message for Poison {
+    type Result = ();
+}
+
+impl Handler<Poison> for Fragile {
+    type Result = ();
+
+    fn handle(&mut self, _: Poison, ctx: &mut Self::Context) {
+        let guard = PanicOnDrop;
+        ctx.spawn(fut::wrap_future(async move {
+            let _guard = guard;
+            std::future::pending::<()>().await
+        }));
+        panic!("fragile actor failed");
+    }
+}
+
+/// Panics a fresh actor in `arbiter` and checks that its address is closed afterwards.
+async fn panic_actor(arbiter: &ArbiterHandle) {
+    let addr = Fragile::start_in_arbiter(arbiter, |_| Fragile);
+    addr.do_send(Panic);
+    assert_eq!(addr.send(Ping(0)).await, Err(MailboxError::Closed));
+}
+
+#[test]
+fn test_panic_policy_ignore() {
+    let sys = System::new();
+    sys.block_on(async {
+        let arbiter = ArbiterBuilder::new().build();
+        let survivor = Fragile::start_in_arbiter(&arbiter.handle(), |_| Fragile);
+
+        panic_actor(&arbiter.handle()).await;
+        panic_actor(&arbiter.handle()).await;
+        survivor.send(Ping(0)).await.unwrap();
+
+        System::current().stop();
+    });
+    assert_eq!(sys.run_with_code().unwrap(), 0);
+}
+
+#[test]
+fn test_panic_policy_escalate() {
+    let sys = System::new();
+    sys.block_on(async {
+        let arbiter = ArbiterBuilder::new()
+            .panic_policy(PanicPolicy::Escalate)
+            .build();
+        panic_actor(&arbiter.handle()).await;
+    });
+    assert_eq!(sys.run_with_code().unwrap(), 101);
+}
+
+#[test]
+fn test_panic_policy_escalate_panicking_drop() {
+    let sys = System::new();
+    sys.block_on(async {
+        let arbiter = ArbiterBuilder::new()
+            .panic_policy(PanicPolicy::Escalate)
+            .build();
+        // dropping the context of the panicked actor panics again
+        let addr = Fragile::start_in_arbiter(&arbiter.handle(), |_| Fragile);
+        addr.do_send(Poison);
+        assert_eq!(addr.send(Ping(0)).await, Err(MailboxError::Closed));
+    });
+    assert_eq!(sys.run_with_code().unwrap(), 101);
+}
+
+#[test]
+fn test_panic_policy_restart_loop() {
+    let sys = System::new();
+    sys.block_on(async {
+        let arbiter = ArbiterBuilder::new()
+            .panic_policy(PanicPolicy::RestartLoop { max: 1 })
+            .build();
+        let survivor = Fragile::start_in_arbiter(&arbiter.handle(), |_| Fragile);
+
+        // first panic is tolerated, other actors keep running
+        panic_actor(&arbiter.handle()).await;
+        survivor.send(Ping(0)).await.unwrap();
+
+        // second panic stops the system
+        panic_actor(&arbiter.handle()).await;
+    });
+    assert_eq!(sys.run_with_code().unwrap(), 101);
+}
+
+#[test]
+fn test_arbiter_stats() {
+    let sys = System::new();
+    sys.block_on(async {
+        let arbiter = ArbiterBuilder::new()
+            .slow_poll_warning(Duration::from_millis(10))
+            .build();
+        let first = Fragile::start_in_arbiter(&arbiter.handle(), |_| Fragile);
+        let second = Fragile::start_in_arbiter(&arbiter.handle(), |_| Fragile);
+        first.send(Ping(0)).await.unwrap();
+        second.send(Block(Duration::from_millis(20))).await.unwrap();
+
+        let stats = ArbiterStats::query(&arbiter.handle()).await.unwrap();
+        assert_eq!(stats.resident_actors, 2);
+        assert!(stats.polls_last_interval >= 2);
+        assert!(stats.max_poll_last_interval >= Duration::from_millis(20));
+        assert_eq!(stats.total_polls, stats.polls_last_interval);
+
+        // panicked actors are no longer resident, and a snapshot starts a new interval
+        panic_actor(&arbiter.handle()).await;
+        let next = ArbiterStats::query(&arbiter.handle()).await.unwrap();
+        assert_eq!(next.resident_actors, 2);
+        assert!(next.max_poll_last_interval < Duration::from_millis(20));
+        assert_eq!(
+            next.total_polls,
+            stats.total_polls + next.polls_last_interval
+        );
+
+        // disabled by default
+        let plain = Arbiter::new();
+        assert_eq!(ArbiterStats::query(&plain.handle()).await, None);
+        assert_eq!(ArbiterStats::snapshot(), None);
+
+        plain.stop();
+        arbiter.stop();
+        System::current().stop();
+    });
+    sys.run().unwrap();
+}
+
+#[test]
+fn test_arbiter_requests() {
+    let sys = System::new();
+    sys.block_on(async {
+        let target = Arbiter::new();
+        let driver = Arbiter::new();
+        let handle = target.handle();
+        let (tx, rx) = oneshot::channel();
+
+        // requests are sent from another arbiter
+        driver.spawn(async move {
+            let pong = handle.ping(Duration::from_secs(1)).await;
+            let before = handle.actor_count().await;
+            let addr = handle
+                .spawn_actor(|_| MyActor(Arc::new(AtomicUsize::new(0))))
+                .await
+                .unwrap();
+            let after = handle.actor_count().await;
+            drop(addr);
+            let _ = tx.send((pong, before, after));
+        });
+        let (pong, before, after) = rx.await.unwrap();
+        assert!(pong.unwrap() < Duration::from_secs(1));
+        assert_eq!(before, Ok(0));
+        assert_eq!(after, Ok(1));
+
+        // the dropped address stops the actor
+        sleep_until_count(&target.handle(), 0).await;
+
+        // a busy arbiter times out, a stopped one fails
+        let blocked = Fragile::start_in_arbiter(&target.handle(), |_| Fragile);
+        blocked.send(Ping(0)).await.unwrap();
+        blocked.do_send(Block(Duration::from_millis(100)));
+        actix_rt::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(
+            target.handle().ping(Duration::from_millis(10)).await,
+            Err(ArbiterError::Timeout)
+        );
+
+        let handle = target.handle();
+        target.stop();
+        target.join().unwrap();
+        assert_eq!(handle.actor_count().await, Err(ArbiterError::Stopped));
+        assert_eq!(
+            handle.spawn_actor(|_| Fragile).await.map(|_| ()),
+            Err(ArbiterError::Stopped)
+        );
+
+        driver.stop();
+        System::current().stop();
+    });
+    sys.run().unwrap();
+}
+
+#[test]
+fn test_stop_gracefully() {
+    let flushed = Arc::new(AtomicBool::new(false));
+    let stuck = Arc::new(AtomicBool::new(false));
+
+    let sys = SystemConfig::new()
+        .shutdown_timeout(Duration::from_millis(300))
+        .build();
+    let started = Instant::now();
+    let (flushed2, stuck2) = (Arc::clone(&flushed), Arc::clone(&stuck));
+    sys.block_on(async move {
+        let arbiter = Arbiter::new().handle();
+        let flush = arbiter.spawn_tracked(async move {
+            actix_rt::time::sleep(Duration::from_millis(100)).await;
+            flushed2.store(true, Ordering::SeqCst);
+        });
+        let hang = arbiter.spawn_tracked(async move {
+            actix_rt::time::sleep(Duration::from_secs(10)).await;
+            stuck2.store(true, Ordering::SeqCst);
+        });
+
+        // an aborted task does not hold back the shutdown
+        let aborted = arbiter.spawn_tracked(std::future::pending());
+        aborted.abort();
+
+        assert_eq!(actix::stop_gracefully().await, 1);
+        assert!(flush.is_finished());
+        assert!(aborted.is_finished());
+        assert!(!hang.is_finished());
+    });
+    sys.run().unwrap();
+
+    assert!(flushed.load(Ordering::SeqCst));
+    assert!(!stuck.load(Ordering::SeqCst));
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(300));
+    assert!(elapsed < Duration::from_secs(5));
+}
+
+/// Waits in its first handler until the system stops, so later requests stay queued.
+struct Busy;
+
+impl Actor for Busy {
+    type Context = Context<Self>;
+}
+
+impl Handler<Ping> for Busy {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, ctx: &mut Self::Context) {
+        ctx.wait(actix::fut::wrap_future(actix_rt::time::sleep(
+            Duration::from_secs(60),
+        )));
+    }
+}
+
+#[test]
+fn test_stop_gracefully_fails_queued_requests() {
+    let timeout = Duration::from_secs(1);
+    let sys = SystemConfig::new().shutdown_timeout(timeout).build();
+    let (caller, late) = sys.block_on(async {
+        let addr = Busy.start();
+        addr.do_send(Ping(0));
+
+        let queued = addr.clone();
+        let caller = thread::spawn(move || {
+            let started = Instant::now();
+            let res = actix::test::block_on_call(&queued, Ping(1), Duration::from_secs(30));
+            (res, started.elapsed())
+        });
+        actix_rt::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(actix::stop_gracefully().await, 0);
+        // requests sent once the system is stopping fail right away
+        (caller, addr.send(Ping(2)).await)
+    });
+    assert_eq!(late, Err(MailboxError::SystemStopping));
+    sys.run().unwrap();
+
+    let (res, elapsed) = caller.join().unwrap();
+    assert_eq!(
+        res,
+        Err(actix::test::TestCallError::Mailbox(
+            MailboxError::SystemStopping
+        ))
+    );
+    assert!(elapsed < timeout);
+}
+
+async fn sleep_until_count(arbiter: &ArbiterHandle, count: usize) {
+    while arbiter.actor_count().await != Ok(count) {
+        actix_rt::time::sleep(Duration::from_millis(1)).await;
+    }
+}
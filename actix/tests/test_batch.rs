@@ -0,0 +1,140 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix::prelude::*;
+
+#[derive(Message)]
+#[rtype(result = "usize")]
+struct Write(usize);
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Other;
+
+type Events = Arc<Mutex<Vec<String>>>;
+
+struct Writer {
+    events: Events,
+    max_batch: usize,
+    max_delay: Duration,
+}
+
+impl Writer {
+    fn start(events: &Events, max_batch: usize, max_delay: Duration) -> Addr<Self> {
+        Writer {
+            events: Arc::clone(events),
+            max_batch,
+            max_delay,
+        }
+        .start()
+    }
+}
+
+impl Actor for Writer {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.enable_batching::<Write>(self.max_batch, self.max_delay);
+    }
+}
+
+impl Handler<Write> for Writer {
+    type Result = usize;
+
+    fn handle(&mut self, msg: Write, _: &mut Self::Context) -> usize {
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("single {}", msg.0));
+        msg.0 * 2
+    }
+}
+
+impl BatchHandler<Write> for Writer {
+    fn handle_batch(&mut self, msgs: Vec<Write>, _: &mut Self::Context) {
+        let ids: Vec<_> = msgs.iter().map(|msg| msg.0.to_string()).collect();
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("batch {}", ids.join(",")));
+    }
+}
+
+impl Handler<Other> for Writer {
+    type Result = ();
+
+    fn handle(&mut self, _: Other, _: &mut Self::Context) {
+        self.events.lock().unwrap().push("other".to_owned());
+    }
+}
+
+fn events(events: &Events) -> Vec<String> {
+    events.lock().unwrap().clone()
+}
+
+#[actix::test]
+async fn test_batch_max_size() {
+    let log = Events::default();
+    let addr = Writer::start(&log, 4, Duration::from_secs(10));
+
+    for i in 0..10 {
+        addr.do_send(Write(i));
+    }
+    drop(addr);
+    actix_rt::time::sleep(Duration::from_millis(50)).await;
+
+    assert_eq!(
+        events(&log),
+        vec!["batch 0,1,2,3", "batch 4,5,6,7", "batch 8,9"]
+    );
+}
+
+#[actix::test]
+async fn test_batch_preserves_order() {
+    let log = Events::default();
+    let addr = Writer::start(&log, 10, Duration::from_millis(20));
+
+    addr.do_send(Write(1));
+    addr.do_send(Write(2));
+    addr.do_send(Other);
+    addr.do_send(Write(3));
+    actix_rt::time::sleep(Duration::from_millis(200)).await;
+
+    assert_eq!(events(&log), vec!["batch 1,2", "other", "batch 3"]);
+}
+
+#[actix::test]
+async fn test_batch_excludes_requests() {
+    let log = Events::default();
+    let addr = Writer::start(&log, 10, Duration::from_millis(20));
+
+    addr.do_send(Write(1));
+    let res = addr.send(Write(2));
+    addr.do_send(Write(3));
+    assert_eq!(res.await.unwrap(), 4);
+    actix_rt::time::sleep(Duration::from_millis(200)).await;
+
+    assert_eq!(events(&log), vec!["batch 1", "single 2", "batch 3"]);
+}
+
+#[actix::test]
+async fn test_batch_max_delay() {
+    let log = Events::default();
+    let addr = Writer::start(&log, 10, Duration::from_millis(300));
+
+    addr.do_send(Write(1));
+    actix_rt::time::sleep(Duration::from_millis(50)).await;
+    assert!(events(&log).is_empty());
+
+    addr.do_send(Write(2));
+    actix_rt::time::sleep(Duration::from_millis(600)).await;
+    assert_eq!(events(&log), vec!["batch 1,2"]);
+
+    addr.do_send(Write(3));
+    actix_rt::time::sleep(Duration::from_millis(600)).await;
+    assert_eq!(events(&log), vec!["batch 1,2", "batch 3"]);
+}
@@ -0,0 +1,144 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use actix::prelude::*;
+use futures_util::StreamExt as _;
+
+#[derive(Default)]
+struct Ticks {
+    resync: AtomicUsize,
+    metrics: AtomicUsize,
+}
+
+struct Node {
+    ticks: Arc<Ticks>,
+    resync: Option<ComponentHandle>,
+}
+
+impl Actor for Node {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let resync = ctx.component("resync");
+        let ticks = Arc::clone(&self.ticks);
+        resync.run_interval(ctx, Duration::from_millis(5), move |_, _| {
+            ticks.resync.fetch_add(1, Ordering::SeqCst);
+        });
+        self.resync = Some(resync);
+
+        let ticks = Arc::clone(&self.ticks);
+        ctx.component("metrics")
+            .run_interval(ctx, Duration::from_millis(5), move |_, _| {
+                ticks.metrics.fetch_add(1, Ordering::SeqCst);
+            });
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "bool")]
+struct Shutdown;
+
+impl Handler<Shutdown> for Node {
+    type Result = bool;
+
+    fn handle(&mut self, _: Shutdown, _: &mut Self::Context) -> bool {
+        let resync = self.resync.as_ref().unwrap();
+        resync.shutdown();
+        resync.is_empty()
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Restart;
+
+impl Handler<Restart> for Node {
+    type Result = ();
+
+    fn handle(&mut self, _: Restart, ctx: &mut Self::Context) {
+        let ticks = Arc::clone(&self.ticks);
+        let resync = self.resync.as_ref().unwrap();
+        assert_eq!(resync.name(), "resync");
+        resync.run_later(ctx, Duration::from_millis(5), move |_, _| {
+            ticks.resync.fetch_add(100, Ordering::SeqCst);
+        });
+        assert!(!resync.is_empty());
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "bool")]
+struct IsEmpty;
+
+impl Handler<IsEmpty> for Node {
+    type Result = bool;
+
+    fn handle(&mut self, _: IsEmpty, _: &mut Self::Context) -> bool {
+        self.resync.as_ref().unwrap().is_empty()
+    }
+}
+
+#[actix::test]
+async fn test_component_shutdown() {
+    let ticks = Arc::new(Ticks::default());
+    let addr = Node {
+        ticks: Arc::clone(&ticks),
+        resync: None,
+    }
+    .start();
+    actix_rt::time::sleep(Duration::from_millis(30)).await;
+    assert!(addr.send(Shutdown).await.unwrap());
+
+    // only the futures of the component are cancelled
+    let resync = ticks.resync.load(Ordering::SeqCst);
+    let metrics = ticks.metrics.load(Ordering::SeqCst);
+    assert!(resync > 0);
+    actix_rt::time::sleep(Duration::from_millis(30)).await;
+    assert_eq!(ticks.resync.load(Ordering::SeqCst), resync);
+    assert!(ticks.metrics.load(Ordering::SeqCst) > metrics);
+
+    // the component can be used again
+    addr.send(Restart).await.unwrap();
+    actix_rt::time::sleep(Duration::from_millis(30)).await;
+    assert_eq!(ticks.resync.load(Ordering::SeqCst), resync + 100);
+    assert!(addr.send(IsEmpty).await.unwrap());
+}
+
+struct Daemon {
+    essential: bool,
+}
+
+impl Actor for Daemon {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let mut heartbeat = ctx.component("heartbeat");
+        if self.essential {
+            heartbeat = heartbeat.essential();
+        }
+        heartbeat.run_interval(ctx, Duration::from_millis(5), |_, _| {});
+    }
+}
+
+#[actix::test]
+async fn test_component_keeps_actor_alive_if_essential() {
+    // the heartbeat alone does not keep the actor running
+    let addr = Daemon { essential: false }.start();
+    let mut states = addr.state_stream();
+    drop(addr);
+    while states.next().await.is_some() {}
+
+    let addr = Daemon { essential: true }.start();
+    let weak = addr.downgrade();
+    drop(addr);
+    actix_rt::time::sleep(Duration::from_millis(30)).await;
+    let addr = weak.upgrade().unwrap();
+    assert!(addr.connected());
+}
@@ -0,0 +1,159 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix::{prelude::*, SystemConfig};
+use tokio::sync::oneshot;
+
+struct Recorder(Arc<Mutex<Vec<&'static str>>>);
+
+impl Actor for Recorder {
+    type Context = Context<Self>;
+}
+
+struct Record(&'static str);
+
+impl Message for Record {
+    type Result = ();
+}
+
+impl Handler<Record> for Recorder {
+    type Result = ();
+
+    fn handle(&mut self, msg: Record, _: &mut Self::Context) {
+        self.0.lock().unwrap().push(msg.0);
+    }
+}
+
+fn fill_mailbox(addr: &Addr<Recorder>, count: usize) -> usize {
+    (0..count)
+        .take_while(|_| addr.try_send(Record("msg")).is_ok())
+        .count()
+}
+
+#[test]
+fn test_default_config() {
+    let cfg = SystemConfig::new();
+    assert_eq!(cfg.get_mailbox_capacity(), 16);
+    assert_eq!(cfg.get_message_budget(), None);
+    assert_eq!(cfg.get_shutdown_timeout(), Duration::from_secs(30));
+
+    System::new().block_on(async {
+        assert_eq!(SystemConfig::current().get_mailbox_capacity(), 16);
+
+        let addr = Recorder(Arc::default()).start();
+        assert!(fill_mailbox(&addr, 100) < 100);
+    });
+}
+
+#[test]
+fn test_config_shared_by_arbiters() {
+    let sys = SystemConfig::new()
+        .mailbox_capacity(1024)
+        .shutdown_timeout(Duration::from_secs(5))
+        .build();
+
+    sys.block_on(async {
+        let addr = Recorder(Arc::default()).start();
+        assert_eq!(fill_mailbox(&addr, 100), 100);
+
+        let (tx, rx) = oneshot::channel();
+        let arbiter = Arbiter::new();
+        arbiter.spawn_fn(move || {
+            let cfg = SystemConfig::current();
+            let _ = tx.send((cfg.get_mailbox_capacity(), cfg.get_shutdown_timeout()));
+        });
+        assert_eq!(rx.await.unwrap(), (1024, Duration::from_secs(5)));
+
+        arbiter.stop();
+    });
+
+    // other systems are not affected
+    System::new().block_on(async {
+        assert_eq!(SystemConfig::current().get_mailbox_capacity(), 16);
+    });
+}
+
+#[test]
+fn test_message_budget() {
+    fn run(sys: SystemRunner, budget: Option<Option<usize>>) -> Vec<&'static str> {
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        sys.block_on(async {
+            let addr = Recorder::create(|ctx| {
+                if let Some(budget) = budget {
+                    ctx.set_message_budget(budget);
+                }
+                Recorder(Arc::clone(&log))
+            });
+            actix_rt::task::yield_now().await;
+
+            for _ in 0..3 {
+                addr.do_send(Record("msg"));
+            }
+            let other = Arc::clone(&log);
+            actix_rt::spawn(async move { other.lock().unwrap().push("other") });
+
+            actix_rt::time::sleep(Duration::from_millis(10)).await;
+        });
+
+        let log = log.lock().unwrap().clone();
+        log
+    }
+
+    let unlimited = vec!["msg", "msg", "msg", "other"];
+    let limited = vec!["msg", "other", "msg", "msg"];
+
+    assert_eq!(run(System::new(), None), unlimited);
+    assert_eq!(
+        run(SystemConfig::new().message_budget(1).build(), None),
+        limited
+    );
+
+    // actors override the system's budget
+    assert_eq!(run(System::new(), Some(Some(1))), limited);
+    assert_eq!(
+        run(SystemConfig::new().message_budget(1).build(), Some(None)),
+        unlimited
+    );
+}
+
+#[test]
+fn test_system_on_application_runtime() {
+    let rt = Arc::new(
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap(),
+    );
+    let (tx, rx) = oneshot::channel();
+    let task = rt.spawn(async move {
+        rx.await.unwrap();
+        "tokio"
+    });
+
+    let sys = SystemConfig::new()
+        .mailbox_capacity(1024)
+        .build_with_tokio_rt(Arc::clone(&rt));
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let recorder = Arc::clone(&log);
+    sys.block_on(async move {
+        assert_eq!(SystemConfig::current().get_mailbox_capacity(), 1024);
+
+        let addr = Recorder(recorder).start();
+        // timers of actors are driven by the application's runtime
+        actix_rt::time::sleep(Duration::from_millis(10)).await;
+        addr.send(Record("actor")).await.unwrap();
+
+        tx.send(()).unwrap();
+        System::current().stop();
+    });
+    sys.run().unwrap();
+
+    // stopping the system leaves the runtime and its tasks running
+    assert_eq!(rt.block_on(task).unwrap(), "tokio");
+    rt.block_on(async { tokio::time::sleep(Duration::from_millis(1)).await });
+    assert_eq!(*log.lock().unwrap(), ["actor"]);
+}
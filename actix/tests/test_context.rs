@@ -11,7 +11,7 @@ use std::{
     time::Duration,
 };
 
-use actix::prelude::*;
+use actix::{prelude::*, ContextStats, SwapOptions};
 use actix_rt::time::{interval_at, sleep, Instant};
 use futures_core::stream::Stream;
 use futures_util::stream::once;
@@ -560,3 +560,1114 @@ mod scheduled_task_is_cancelled_properly {
         sys.run().unwrap();
     }
 }
+
+type ScopeSetup = Box<dyn FnOnce(&mut ScopedActor, &mut Context<ScopedActor>)>;
+
+struct ScopedActor {
+    completed: Arc<AtomicUsize>,
+    setup: Option<ScopeSetup>,
+    guard: Option<ScopeGuard>,
+}
+
+impl ScopedActor {
+    fn start<F>(completed: &Arc<AtomicUsize>, setup: F) -> Addr<Self>
+    where
+        F: FnOnce(&mut ScopedActor, &mut Context<ScopedActor>) + 'static,
+    {
+        ScopedActor {
+            completed: Arc::clone(completed),
+            setup: Some(Box::new(setup)),
+            guard: None,
+        }
+        .start()
+    }
+
+    /// Future that bumps the completion counter after `ms` milliseconds.
+    fn delayed(&self, ms: u64) -> impl ActorFuture<Self, Output = ()> {
+        let completed = Arc::clone(&self.completed);
+        async move {
+            sleep(Duration::from_millis(ms)).await;
+            completed.fetch_add(1, Ordering::SeqCst);
+        }
+        .into_actor(self)
+    }
+}
+
+impl Actor for ScopedActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let setup = self.setup.take().unwrap();
+        setup(self, ctx);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct DropGuard;
+
+impl Handler<DropGuard> for ScopedActor {
+    type Result = ();
+
+    fn handle(&mut self, _: DropGuard, _: &mut Self::Context) {
+        self.guard = None;
+    }
+}
+
+#[actix::test]
+async fn test_scope_cancel_all() {
+    let completed = Arc::new(AtomicUsize::new(0));
+    let _addr = ScopedActor::start(&completed, |act, ctx| {
+        let scope = ctx.scope();
+        scope.spawn(ctx, act.delayed(50));
+        scope.spawn(ctx, act.delayed(50));
+        ctx.spawn(act.delayed(50));
+
+        ctx.run_later(Duration::from_millis(10), move |_, _| scope.cancel_all());
+    });
+
+    sleep(Duration::from_millis(200)).await;
+    assert_eq!(completed.load(Ordering::SeqCst), 1);
+}
+
+#[actix::test]
+async fn test_scope_nested() {
+    let completed = Arc::new(AtomicUsize::new(0));
+    let _addr = ScopedActor::start(&completed, |act, ctx| {
+        let outer = ctx.scope();
+        let inner = outer.scope();
+        let sibling = outer.scope();
+        outer.spawn(ctx, act.delayed(50));
+        inner.spawn(ctx, act.delayed(50));
+
+        // cancelling a nested scope leaves its parent running
+        sibling.spawn(ctx, act.delayed(50));
+        sibling.cancel_all();
+        assert!(!outer.is_cancelled());
+
+        ctx.run_later(Duration::from_millis(100), move |act, ctx| {
+            // cancelling the parent cancels its nested scopes
+            outer.spawn(ctx, act.delayed(50));
+            inner.spawn(ctx, act.delayed(50));
+            outer.cancel_all();
+            assert!(inner.is_cancelled());
+        });
+    });
+
+    sleep(Duration::from_millis(300)).await;
+    assert_eq!(completed.load(Ordering::SeqCst), 2);
+}
+
+#[actix::test]
+async fn test_scope_cancel_from_own_future() {
+    let completed = Arc::new(AtomicUsize::new(0));
+    let _addr = ScopedActor::start(&completed, |act, ctx| {
+        let scope = ctx.scope();
+        scope.spawn(ctx, act.delayed(100));
+
+        let own = scope.clone();
+        let fut = act.delayed(10).map(move |_, _, _| own.cancel_all());
+        scope.spawn(ctx, fut);
+
+        // scope is cancelled, the future never runs
+        ctx.run_later(Duration::from_millis(50), move |act, ctx| {
+            scope.spawn(ctx, act.delayed(10));
+        });
+    });
+
+    sleep(Duration::from_millis(300)).await;
+    assert_eq!(completed.load(Ordering::SeqCst), 1);
+}
+
+#[actix::test]
+async fn test_scope_guard() {
+    let completed = Arc::new(AtomicUsize::new(0));
+    let addr = ScopedActor::start(&completed, |act, ctx| {
+        let scope = ctx.scope();
+        scope.spawn(ctx, act.delayed(100));
+        act.guard = Some(scope.guard());
+    });
+
+    sleep(Duration::from_millis(10)).await;
+    addr.send(DropGuard).await.unwrap();
+
+    sleep(Duration::from_millis(200)).await;
+    assert_eq!(completed.load(Ordering::SeqCst), 0);
+}
+
+#[actix::test]
+async fn test_scope_dropped_on_stop() {
+    let completed = Arc::new(AtomicUsize::new(0));
+    let addr = ScopedActor::start(&completed, |act, ctx| {
+        let scope = ctx.scope();
+        scope.spawn(ctx, act.delayed(500));
+        act.guard = Some(scope.guard());
+        ctx.run_later(Duration::from_millis(10), |_, ctx| ctx.stop());
+    });
+
+    sleep(Duration::from_millis(100)).await;
+    assert!(!addr.connected());
+    assert_eq!(completed.load(Ordering::SeqCst), 0);
+}
+
+struct WaitQueueActor {
+    log: Arc<std::sync::Mutex<Vec<&'static str>>>,
+    cancel_second: bool,
+}
+
+impl WaitQueueActor {
+    fn entry(&self, name: &'static str) -> impl ActorFuture<Self, Output = ()> {
+        let log = Arc::clone(&self.log);
+        async move {
+            sleep(Duration::from_millis(10)).await;
+            log.lock().unwrap().push(name);
+        }
+        .into_actor(self)
+    }
+}
+
+impl Actor for WaitQueueActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.wait_with_handle(self.entry("first"));
+        let second = ctx.wait_with_handle(self.entry("second"));
+        ctx.wait_with_handle(self.entry("third"));
+        assert_eq!(ctx.wait_queue_len(), 3);
+
+        if self.cancel_second {
+            assert!(ctx.cancel_wait(second));
+            assert!(!ctx.cancel_wait(second));
+            assert_eq!(ctx.wait_queue_len(), 2);
+        } else {
+            // most recent wait future runs first, move "first" in front of it
+            assert!(ctx.promote_wait(2));
+            assert!(!ctx.promote_wait(3));
+        }
+    }
+}
+
+#[actix::test]
+async fn test_wait_queue_promote() {
+    let log = Arc::default();
+    let _addr = WaitQueueActor {
+        log: Arc::clone(&log),
+        cancel_second: false,
+    }
+    .start();
+
+    sleep(Duration::from_millis(200)).await;
+    assert_eq!(*log.lock().unwrap(), vec!["first", "third", "second"]);
+}
+
+#[actix::test]
+async fn test_wait_queue_cancel() {
+    let log = Arc::default();
+    let _addr = WaitQueueActor {
+        log: Arc::clone(&log),
+        cancel_second: true,
+    }
+    .start();
+
+    sleep(Duration::from_millis(200)).await;
+    assert_eq!(*log.lock().unwrap(), vec!["third", "first"]);
+}
+
+/// Future counting its polls, which is woken up right away until it is done.
+struct PollCounter {
+    polls: Arc<AtomicUsize>,
+    limit: usize,
+    cancel_self: bool,
+}
+
+impl PollCounter {
+    fn new(polls: &Arc<AtomicUsize>, limit: usize) -> Self {
+        PollCounter {
+            polls: Arc::clone(polls),
+            limit,
+            cancel_self: false,
+        }
+    }
+}
+
+impl ActorFuture<CancelItems> for PollCounter {
+    type Output = ();
+
+    fn poll(
+        self: Pin<&mut Self>,
+        _: &mut CancelItems,
+        ctx: &mut Context<CancelItems>,
+        task: &mut StdContext<'_>,
+    ) -> Poll<()> {
+        let this = self.get_mut();
+        if this.polls.fetch_add(1, Ordering::SeqCst) + 1 >= this.limit {
+            return Poll::Ready(());
+        }
+        if this.cancel_self {
+            ctx.cancel_future(ctx.handle());
+        }
+        task.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+struct CancelItems {
+    first: Arc<AtomicUsize>,
+    second: Arc<AtomicUsize>,
+    handle: Option<SpawnHandle>,
+}
+
+impl CancelItems {
+    fn new() -> Self {
+        CancelItems {
+            first: Arc::default(),
+            second: Arc::default(),
+            handle: None,
+        }
+    }
+}
+
+impl Actor for CancelItems {
+    type Context = Context<Self>;
+}
+
+impl Handler<CancelMessage> for CancelItems {
+    type Result = ();
+
+    fn handle(&mut self, _: CancelMessage, ctx: &mut Self::Context) {
+        assert!(ctx.cancel_future(self.handle.take().unwrap()));
+    }
+}
+
+#[actix::test]
+async fn test_cancel_self() {
+    let act = CancelItems::new();
+    let (first, second) = (Arc::clone(&act.first), Arc::clone(&act.second));
+
+    let addr = CancelItems::create(move |ctx| {
+        ctx.spawn(PollCounter {
+            cancel_self: true,
+            ..PollCounter::new(&act.first, usize::MAX)
+        });
+        ctx.spawn(PollCounter::new(&act.second, 10));
+        act
+    });
+
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(first.load(Ordering::SeqCst), 1);
+    assert_eq!(second.load(Ordering::SeqCst), 10);
+    assert!(addr.connected());
+}
+
+#[actix::test]
+async fn test_cancel_from_handler() {
+    let act = CancelItems::new();
+    let (first, second) = (Arc::clone(&act.first), Arc::clone(&act.second));
+
+    let addr = CancelItems::create(move |ctx| {
+        let mut act = act;
+        act.handle = Some(ctx.spawn(PollCounter::new(&act.first, usize::MAX)));
+        ctx.spawn(PollCounter::new(&act.second, usize::MAX));
+        act
+    });
+
+    sleep(Duration::from_millis(10)).await;
+    addr.send(CancelMessage).await.unwrap();
+    let (cancelled_at, running_at) = (first.load(Ordering::SeqCst), second.load(Ordering::SeqCst));
+
+    sleep(Duration::from_millis(10)).await;
+    assert_eq!(first.load(Ordering::SeqCst), cancelled_at);
+    assert!(second.load(Ordering::SeqCst) > running_at);
+}
+
+#[actix::test]
+async fn test_cancel_completed() {
+    let act = CancelItems::new();
+    let (first, second) = (Arc::clone(&act.first), Arc::clone(&act.second));
+
+    let addr = CancelItems::create(move |ctx| {
+        let mut act = act;
+        act.handle = Some(ctx.spawn(PollCounter::new(&act.first, 1)));
+        ctx.spawn(PollCounter::new(&act.second, usize::MAX));
+        act
+    });
+
+    sleep(Duration::from_millis(10)).await;
+    addr.send(CancelMessage).await.unwrap();
+    let running_at = second.load(Ordering::SeqCst);
+
+    sleep(Duration::from_millis(10)).await;
+    assert_eq!(first.load(Ordering::SeqCst), 1);
+    assert!(second.load(Ordering::SeqCst) > running_at);
+}
+
+struct Busy(usize);
+
+impl Actor for Busy {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        // stay below the per-poll limit checked with the `mailbox_assert` feature
+        ctx.set_message_budget(Some(128));
+    }
+}
+
+struct Work;
+
+impl Message for Work {
+    type Result = ();
+}
+
+impl Handler<Work> for Busy {
+    type Result = ();
+
+    fn handle(&mut self, _: Work, _: &mut Self::Context) {
+        self.0 += 1;
+    }
+}
+
+struct GetStats;
+
+impl Message for GetStats {
+    type Result = (usize, ContextStats);
+}
+
+impl Handler<GetStats> for Busy {
+    type Result = MessageResult<GetStats>;
+
+    fn handle(&mut self, _: GetStats, ctx: &mut Self::Context) -> Self::Result {
+        MessageResult((self.0, ctx.stats()))
+    }
+}
+
+#[actix::test]
+async fn test_wake_coalescing() {
+    const PRODUCERS: usize = 100;
+    const MESSAGES: usize = 100;
+
+    let addr = Busy(0).start();
+
+    let producers = (0..PRODUCERS)
+        .map(|_| {
+            let addr = addr.clone();
+            std::thread::spawn(move || {
+                for _ in 0..MESSAGES {
+                    addr.do_send(Work);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // the actor keeps handling messages while the producers are running
+    actix_rt::task::spawn_blocking(move || {
+        for producer in producers {
+            producer.join().unwrap();
+        }
+    })
+    .await
+    .unwrap();
+
+    let (handled, stats) = addr.send(GetStats).await.unwrap();
+    assert_eq!(handled, PRODUCERS * MESSAGES);
+    assert!(stats.suppressed_wakeups > 0);
+    assert!(stats.polls < (PRODUCERS * MESSAGES) as u64);
+}
+
+#[derive(Default)]
+struct Deferrer {
+    log: Vec<String>,
+    spinning: bool,
+    spins: usize,
+    stopped: Option<Arc<std::sync::Mutex<Vec<String>>>>,
+}
+
+impl Deferrer {
+    fn nest(&mut self, ctx: &mut Context<Self>, depth: usize) {
+        self.log.push(format!("depth {}", depth));
+        if depth < 20 {
+            ctx.defer_fn(move |act, ctx| act.nest(ctx, depth + 1));
+        }
+    }
+
+    fn spin(&mut self, ctx: &mut Context<Self>) {
+        self.spins += 1;
+        if self.spinning {
+            ctx.defer_fn(Self::spin);
+        }
+    }
+}
+
+impl Actor for Deferrer {
+    type Context = Context<Self>;
+
+    fn stopping(&mut self, ctx: &mut Self::Context) -> Running {
+        ctx.defer_fn(|act, _| act.log.push("deferred by stopping".to_owned()));
+        Running::Stop
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        if let Some(stopped) = self.stopped.take() {
+            *stopped.lock().unwrap() = std::mem::take(&mut self.log);
+        }
+    }
+}
+
+enum DeferOp {
+    Defer,
+    Nest,
+    Spin,
+    StopSpinning,
+    Stop,
+}
+
+impl Message for DeferOp {
+    type Result = (Vec<String>, usize);
+}
+
+impl Handler<DeferOp> for Deferrer {
+    type Result = MessageResult<DeferOp>;
+
+    fn handle(&mut self, op: DeferOp, ctx: &mut Self::Context) -> Self::Result {
+        match op {
+            DeferOp::Defer => {
+                ctx.defer_fn(|act, _| act.log.push("first".to_owned()));
+                ctx.defer_fn(|act, _| act.log.push("second".to_owned()));
+                self.log.push("handler".to_owned());
+            }
+            DeferOp::Nest => self.nest(ctx, 0),
+            DeferOp::Spin => {
+                self.spinning = true;
+                ctx.defer_fn(Self::spin);
+            }
+            DeferOp::StopSpinning => self.spinning = false,
+            DeferOp::Stop => {
+                ctx.defer_fn(|act, _| act.log.push("deferred by handler".to_owned()));
+                ctx.stop();
+            }
+        }
+        MessageResult((std::mem::take(&mut self.log), self.spins))
+    }
+}
+
+#[actix::test]
+async fn test_defer_fn() {
+    let addr = Deferrer::default().start();
+
+    let (log, _) = addr.send(DeferOp::Defer).await.unwrap();
+    assert_eq!(log, ["handler"]);
+    let (log, _) = addr.send(DeferOp::Nest).await.unwrap();
+    assert_eq!(log, ["first", "second", "depth 0"]);
+
+    // nested functions beyond the depth limit run on the next poll
+    sleep(Duration::from_millis(10)).await;
+    let (log, _) = addr.send(DeferOp::Defer).await.unwrap();
+    assert_eq!(
+        log,
+        (1..=20)
+            .map(|depth| format!("depth {}", depth))
+            .chain(["handler".to_owned()])
+            .collect::<Vec<_>>()
+    );
+}
+
+#[actix::test]
+async fn test_defer_fn_livelock() {
+    let addr = Deferrer::default().start();
+
+    addr.send(DeferOp::Spin).await.unwrap();
+    sleep(Duration::from_millis(10)).await;
+
+    // messages are handled while a function keeps deferring itself
+    let (_, spins) = addr.send(DeferOp::StopSpinning).await.unwrap();
+    assert!(spins > 16);
+}
+
+#[actix::test]
+async fn test_defer_fn_stopping() {
+    let stopped = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let addr = Deferrer {
+        stopped: Some(Arc::clone(&stopped)),
+        ..Default::default()
+    }
+    .start();
+
+    addr.send(DeferOp::Stop).await.unwrap();
+    sleep(Duration::from_millis(10)).await;
+    assert_eq!(
+        *stopped.lock().unwrap(),
+        ["deferred by handler", "deferred by stopping"]
+    );
+}
+
+struct Versioned {
+    version: u32,
+    log: Arc<std::sync::Mutex<Vec<(usize, u32)>>>,
+    starts: Arc<AtomicUsize>,
+    ticks: Arc<AtomicUsize>,
+}
+
+impl Versioned {
+    fn next(&self, version: u32) -> Self {
+        Versioned {
+            version,
+            log: Arc::clone(&self.log),
+            starts: Arc::clone(&self.starts),
+            ticks: Arc::clone(&self.ticks),
+        }
+    }
+}
+
+impl Default for Versioned {
+    fn default() -> Self {
+        Versioned {
+            version: 1,
+            log: Arc::default(),
+            starts: Arc::default(),
+            ticks: Arc::default(),
+        }
+    }
+}
+
+impl Actor for Versioned {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.starts.fetch_add(1, Ordering::SeqCst);
+        ctx.run_interval(Duration::from_millis(1), |act, _| {
+            act.ticks.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+}
+
+struct Record(usize);
+
+impl Message for Record {
+    type Result = ();
+}
+
+impl Handler<Record> for Versioned {
+    type Result = ();
+
+    fn handle(&mut self, msg: Record, _: &mut Self::Context) {
+        self.log.lock().unwrap().push((msg.0, self.version));
+    }
+}
+
+struct Swap(u32, SwapOptions);
+
+impl Message for Swap {
+    type Result = u32;
+}
+
+impl Handler<Swap> for Versioned {
+    type Result = ResponseActFuture<Self, u32>;
+
+    fn handle(&mut self, Swap(version, opts): Swap, ctx: &mut Self::Context) -> Self::Result {
+        let next = self.next(version);
+        Box::pin(
+            ctx.swap_actor(next, opts)
+                .map(|old, _, _| old.map_or(0, |old| old.version)),
+        )
+    }
+}
+
+struct SwapDuringAtomic;
+
+impl Message for SwapDuringAtomic {
+    type Result = (u32, u32);
+}
+
+impl Handler<SwapDuringAtomic> for Versioned {
+    type Result = AtomicResponse<Self, (u32, u32)>;
+
+    fn handle(&mut self, _: SwapDuringAtomic, ctx: &mut Self::Context) -> Self::Result {
+        let next = self.next(self.version + 1);
+        ctx.swap_actor(next, SwapOptions::default());
+
+        let started = self.version;
+        AtomicResponse::new(Box::pin(
+            sleep(Duration::from_millis(10))
+                .into_actor(self)
+                .map(move |_, act, _| (started, act.version)),
+        ))
+    }
+}
+
+#[actix::test]
+async fn test_swap_actor_mid_stream() {
+    let log = Arc::default();
+    let addr = Versioned {
+        log: Arc::clone(&log),
+        ..Default::default()
+    }
+    .start();
+
+    let mut swapped = None;
+    for seq in 0..300 {
+        addr.do_send(Record(seq));
+        if seq == 150 {
+            swapped = Some(addr.send(Swap(2, SwapOptions::default())));
+        }
+        if seq % 7 == 0 {
+            actix_rt::task::yield_now().await;
+        }
+    }
+
+    assert_eq!(swapped.unwrap().await.unwrap(), 1);
+    addr.send(Record(300)).await.unwrap();
+
+    // every message is handled by exactly one instance, in order
+    let log = log.lock().unwrap();
+    assert_eq!(
+        *log,
+        (0..=300)
+            .map(|seq| (seq, if seq <= 150 { 1 } else { 2 }))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[actix::test]
+async fn test_swap_actor_after_wait() {
+    let log = Arc::default();
+    let addr = Versioned {
+        log: Arc::clone(&log),
+        ..Default::default()
+    }
+    .start();
+
+    // the atomic response completes on the instance which handled the message
+    assert_eq!(addr.send(SwapDuringAtomic).await.unwrap(), (1, 1));
+    addr.send(Record(0)).await.unwrap();
+    assert_eq!(*log.lock().unwrap(), [(0, 2)]);
+}
+
+#[actix::test]
+async fn test_swap_actor_options() {
+    let act = Versioned::default();
+    let (starts, ticks) = (Arc::clone(&act.starts), Arc::clone(&act.ticks));
+    let addr = act.start();
+    sleep(Duration::from_millis(5)).await;
+    assert_eq!(starts.load(Ordering::SeqCst), 1);
+
+    // futures are kept by default, and `started()` runs again on request
+    let opts = SwapOptions {
+        run_started: true,
+        ..Default::default()
+    };
+    assert_eq!(addr.send(Swap(2, opts)).await.unwrap(), 1);
+    assert_eq!(starts.load(Ordering::SeqCst), 2);
+    let before = ticks.load(Ordering::SeqCst);
+    sleep(Duration::from_millis(10)).await;
+    assert!(ticks.load(Ordering::SeqCst) > before);
+
+    // cancelled futures include the response of the swap request itself
+    let opts = SwapOptions {
+        cancel_futures: true,
+        ..Default::default()
+    };
+    assert_eq!(addr.send(Swap(3, opts)).await, Err(MailboxError::Closed));
+    assert_eq!(starts.load(Ordering::SeqCst), 2);
+    let before = ticks.load(Ordering::SeqCst);
+    sleep(Duration::from_millis(10)).await;
+    assert_eq!(ticks.load(Ordering::SeqCst), before);
+    assert!(addr.connected());
+}
+
+#[derive(Default)]
+struct TimerOwner {
+    fired: Vec<&'static str>,
+}
+
+impl Actor for TimerOwner {
+    type Context = Context<Self>;
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Fired(&'static str);
+
+impl Handler<Fired> for TimerOwner {
+    type Result = ();
+
+    fn handle(&mut self, msg: Fired, _: &mut Self::Context) {
+        self.fired.push(msg.0);
+    }
+}
+
+enum TimerOp {
+    Set(&'static str, u64),
+    Cancel(&'static str),
+    Remaining(&'static str),
+    TakeFired,
+}
+
+#[derive(Debug, PartialEq)]
+enum TimerRes {
+    Cancelled(bool),
+    Remaining(Option<Duration>),
+    Fired(Vec<&'static str>),
+}
+
+impl Message for TimerOp {
+    type Result = TimerRes;
+}
+
+impl Handler<TimerOp> for TimerOwner {
+    type Result = MessageResult<TimerOp>;
+
+    fn handle(&mut self, op: TimerOp, ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(match op {
+            TimerOp::Set(name, ms) => {
+                ctx.set_timer(name, Duration::from_millis(ms), Fired(name));
+                TimerRes::Remaining(ctx.timer_remaining(name))
+            }
+            TimerOp::Cancel(name) => TimerRes::Cancelled(ctx.cancel_timer(name)),
+            TimerOp::Remaining(name) => TimerRes::Remaining(ctx.timer_remaining(name)),
+            TimerOp::TakeFired => TimerRes::Fired(std::mem::take(&mut self.fired)),
+        })
+    }
+}
+
+#[actix::test]
+async fn test_named_timers() {
+    let addr = TimerOwner::default().start();
+
+    addr.send(TimerOp::Set("keepalive", 30)).await.unwrap();
+    addr.send(TimerOp::Set("retransmit", 10)).await.unwrap();
+    addr.send(TimerOp::Set("expiry", 50)).await.unwrap();
+
+    // setting a timer again replaces it
+    let res = addr.send(TimerOp::Set("keepalive", 80)).await.unwrap();
+    assert!(matches!(res, TimerRes::Remaining(Some(left)) if left > Duration::from_millis(50)));
+
+    let res = addr.send(TimerOp::Cancel("expiry")).await.unwrap();
+    assert_eq!(res, TimerRes::Cancelled(true));
+    let res = addr.send(TimerOp::Cancel("expiry")).await.unwrap();
+    assert_eq!(res, TimerRes::Cancelled(false));
+
+    sleep(Duration::from_millis(120)).await;
+    let res = addr.send(TimerOp::TakeFired).await.unwrap();
+    assert_eq!(res, TimerRes::Fired(vec!["retransmit", "keepalive"]));
+    let res = addr.send(TimerOp::Remaining("keepalive")).await.unwrap();
+    assert_eq!(res, TimerRes::Remaining(None));
+
+    // timers can be set again once all of them fired
+    addr.send(TimerOp::Set("late", 5)).await.unwrap();
+    sleep(Duration::from_millis(30)).await;
+    let res = addr.send(TimerOp::TakeFired).await.unwrap();
+    assert_eq!(res, TimerRes::Fired(vec!["late"]));
+}
+
+#[derive(Default)]
+struct Fetcher {
+    results: Vec<Result<u32, &'static str>>,
+}
+
+impl Actor for Fetcher {
+    type Context = Context<Self>;
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Fetched(Result<u32, &'static str>);
+
+impl From<Result<u32, &'static str>> for Fetched {
+    fn from(res: Result<u32, &'static str>) -> Self {
+        Fetched(res)
+    }
+}
+
+impl Handler<Fetched> for Fetcher {
+    type Result = ();
+
+    fn handle(&mut self, msg: Fetched, _: &mut Self::Context) {
+        self.results.push(msg.0);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Vec<Result<u32, &'static str>>")]
+struct Fetch;
+
+impl Handler<Fetch> for Fetcher {
+    type Result = MessageResult<Fetch>;
+
+    fn handle(&mut self, _: Fetch, ctx: &mut Self::Context) -> Self::Result {
+        ctx.spawn_into::<Fetched, _>(
+            async {
+                sleep(Duration::from_millis(20)).await;
+                Err("timed out")
+            }
+            .into_actor(self),
+        );
+        ctx.spawn_into::<Fetched, _>(async { Ok(1) }.into_actor(self));
+        let handle = ctx.spawn_into::<Fetched, _>(async { Ok(2) }.into_actor(self));
+        ctx.cancel_future(handle);
+        MessageResult(std::mem::take(&mut self.results))
+    }
+}
+
+#[actix::test]
+async fn test_spawn_into() {
+    let addr = Fetcher::default().start();
+
+    assert!(addr.send(Fetch).await.unwrap().is_empty());
+    sleep(Duration::from_millis(50)).await;
+
+    // cancelled futures do not send their output
+    let res = addr.send(Fetch).await.unwrap();
+    assert_eq!(res, [Ok(1), Err("timed out")]);
+}
+
+#[derive(Default)]
+struct Interruptee {
+    waited: Option<Result<(), ()>>,
+}
+
+impl Actor for Interruptee {
+    type Context = Context<Self>;
+}
+
+enum InterruptOp {
+    Busy,
+    Wait,
+    Probe,
+    Sticky(bool),
+    Clear,
+}
+
+impl Message for InterruptOp {
+    type Result = (bool, Option<Result<(), ()>>);
+}
+
+impl Handler<InterruptOp> for Interruptee {
+    type Result = MessageResult<InterruptOp>;
+
+    fn handle(&mut self, op: InterruptOp, ctx: &mut Self::Context) -> Self::Result {
+        match op {
+            InterruptOp::Busy => {
+                let start = Instant::now();
+                while !ctx.interrupted() {
+                    assert!(start.elapsed() < Duration::from_secs(5));
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+            }
+            InterruptOp::Wait => ctx.wait(
+                sleep(Duration::from_secs(5))
+                    .into_actor(self)
+                    .interruptible()
+                    .map(|res, act, _| act.waited = Some(res)),
+            ),
+            InterruptOp::Probe => {}
+            InterruptOp::Sticky(sticky) => ctx.set_interrupt_sticky(sticky),
+            InterruptOp::Clear => ctx.clear_interrupt(),
+        }
+        MessageResult((ctx.interrupted(), self.waited.take()))
+    }
+}
+
+#[actix::test]
+async fn test_interrupt_handler() {
+    let arbiter = Arbiter::new();
+    let addr = Interruptee::start_in_arbiter(&arbiter.handle(), |_| Interruptee::default());
+
+    // observed by the running handler
+    let busy = addr.send(InterruptOp::Busy);
+    let interrupter = addr.clone();
+    actix_rt::spawn(async move {
+        sleep(Duration::from_millis(20)).await;
+        interrupter.interrupt();
+    });
+    assert_eq!(busy.await.unwrap(), (true, None));
+
+    // cleared before the next message
+    assert_eq!(addr.send(InterruptOp::Probe).await.unwrap(), (false, None));
+
+    arbiter.stop();
+}
+
+#[actix::test]
+async fn test_interrupt_sticky_and_wait() {
+    let addr = Interruptee::default().start();
+
+    // interrupts a wait future
+    addr.do_send(InterruptOp::Wait);
+    let probe = addr.send(InterruptOp::Probe);
+    sleep(Duration::from_millis(10)).await;
+    addr.interrupt();
+    let res = actix_rt::time::timeout(Duration::from_secs(1), probe)
+        .await
+        .unwrap();
+    assert_eq!(res.unwrap(), (false, Some(Err(()))));
+
+    addr.send(InterruptOp::Sticky(true)).await.unwrap();
+    addr.interrupt();
+    assert_eq!(addr.send(InterruptOp::Probe).await.unwrap(), (true, None));
+    assert_eq!(addr.send(InterruptOp::Probe).await.unwrap(), (true, None));
+    assert_eq!(addr.send(InterruptOp::Clear).await.unwrap(), (false, None));
+    assert_eq!(addr.send(InterruptOp::Probe).await.unwrap(), (false, None));
+}
+
+#[derive(Default)]
+struct Journal {
+    log: Vec<&'static str>,
+}
+
+impl Actor for Journal {
+    type Context = Context<Self>;
+}
+
+#[derive(Message)]
+#[rtype(result = "SpawnHandle")]
+struct Write(u64, &'static str);
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Cancel(SpawnHandle);
+
+#[derive(Message)]
+#[rtype(result = "Vec<&'static str>")]
+struct Flush;
+
+impl Handler<Write> for Journal {
+    type Result = MessageResult<Write>;
+
+    fn handle(&mut self, Write(delay, entry): Write, ctx: &mut Self::Context) -> Self::Result {
+        let write = sleep(Duration::from_millis(delay))
+            .into_actor(self)
+            .map(move |_, act, _| act.log.push(entry));
+        MessageResult(ctx.spawn(write))
+    }
+}
+
+impl Handler<Cancel> for Journal {
+    type Result = ();
+
+    fn handle(&mut self, Cancel(handle): Cancel, ctx: &mut Self::Context) {
+        ctx.cancel_future(handle);
+    }
+}
+
+impl Handler<Flush> for Journal {
+    type Result = ResponseActFuture<Self, Vec<&'static str>>;
+
+    fn handle(&mut self, _: Flush, ctx: &mut Self::Context) -> Self::Result {
+        Box::pin(ctx.barrier().map(|_, act, _| act.log.clone()))
+    }
+}
+
+#[actix::test]
+async fn test_barrier_out_of_order() {
+    let addr = Journal::default().start();
+
+    addr.do_send(Write(40, "a"));
+    addr.do_send(Write(10, "b"));
+    addr.do_send(Write(20, "c"));
+    let flush = addr.send(Flush);
+    // spawned after the barrier
+    addr.do_send(Write(60, "d"));
+    addr.do_send(Write(10_000, "e"));
+
+    let log = actix_rt::time::timeout(Duration::from_secs(1), flush)
+        .await
+        .unwrap();
+    assert_eq!(log.unwrap(), ["b", "c", "a"]);
+
+    // nothing is pending before an empty barrier
+    let journal = Journal::default().start();
+    assert!(journal.send(Flush).await.unwrap().is_empty());
+}
+
+#[actix::test]
+async fn test_barrier_cancelled() {
+    let addr = Journal::default().start();
+
+    addr.do_send(Write(10, "a"));
+    let stuck = addr.send(Write(10_000, "b")).await.unwrap();
+    let flush = addr.send(Flush);
+    addr.do_send(Write(20, "c"));
+    sleep(Duration::from_millis(30)).await;
+    addr.do_send(Cancel(stuck));
+
+    let log = actix_rt::time::timeout(Duration::from_secs(1), flush)
+        .await
+        .unwrap();
+    assert_eq!(log.unwrap(), ["a", "c"]);
+}
+
+/// Resource which is ready once it was checked four times, without a way to wake its poller.
+struct Readiness {
+    dur: Duration,
+    checks: Vec<Instant>,
+    done: Option<tokio::sync::oneshot::Sender<Vec<Instant>>>,
+}
+
+impl ActorFuture<Poller> for Readiness {
+    type Output = ();
+
+    fn poll(
+        self: Pin<&mut Self>,
+        _: &mut Poller,
+        ctx: &mut Context<Poller>,
+        _: &mut StdContext<'_>,
+    ) -> Poll<()> {
+        let this = self.get_mut();
+        this.checks.push(Instant::now());
+        if this.checks.len() < 4 {
+            ctx.backoff(this.dur);
+            return Poll::Pending;
+        }
+        let _ = this.done.take().unwrap().send(this.checks.clone());
+        Poll::Ready(())
+    }
+}
+
+struct Poller;
+
+impl Actor for Poller {
+    type Context = Context<Self>;
+}
+
+struct Check(Duration, tokio::sync::oneshot::Sender<Vec<Instant>>);
+
+impl Message for Check {
+    type Result = ();
+}
+
+impl Handler<Check> for Poller {
+    type Result = ();
+
+    fn handle(&mut self, Check(dur, done): Check, ctx: &mut Self::Context) {
+        ctx.spawn(Readiness {
+            dur,
+            checks: Vec::new(),
+            done: Some(done),
+        });
+    }
+}
+
+#[actix::test]
+async fn test_backoff() {
+    let addr = Poller.start();
+
+    // a slower future shares the timer, without delaying the faster one
+    let (slow_tx, _slow_rx) = tokio::sync::oneshot::channel();
+    addr.send(Check(Duration::from_secs(10), slow_tx))
+        .await
+        .unwrap();
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    addr.send(Check(Duration::from_millis(20), tx))
+        .await
+        .unwrap();
+
+    let checks = actix_rt::time::timeout(Duration::from_secs(1), rx)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(checks.len(), 4);
+    for pair in checks.windows(2) {
+        let gap = pair[1] - pair[0];
+        assert!(gap >= Duration::from_millis(19), "{:?}", gap);
+        assert!(gap < Duration::from_millis(500), "{:?}", gap);
+    }
+}
@@ -0,0 +1,118 @@
+#![cfg(feature = "macros")]
+
+use actix::{prelude::*, DeadLetterReason, SystemConfig};
+
+struct Worker;
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Job;
+
+impl Handler<Job> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: Job, _: &mut Self::Context) {}
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Stop;
+
+impl Handler<Stop> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: Stop, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+}
+
+#[actix::test]
+async fn test_dead_letters() {
+    let sys = System::current();
+    let addr = Worker.start();
+    addr.send(Stop).await.unwrap();
+    addr.closed().await;
+
+    assert!(addr.send(Job).await.is_err());
+    assert!(addr.try_send(Job).is_err());
+    addr.do_send(Job);
+
+    let records = sys.dead_letters();
+    assert_eq!(records.len(), 3);
+    assert_eq!(
+        records.iter().map(|r| r.expects_reply).collect::<Vec<_>>(),
+        [true, false, false]
+    );
+    for record in &records {
+        assert_eq!(record.message_type, std::any::type_name::<Job>());
+        assert_eq!(record.recipient_type, std::any::type_name::<Worker>());
+        assert!(record.recipient.is_some());
+        assert_eq!(record.reason, DeadLetterReason::Closed);
+    }
+    assert!(records.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+
+    // visible from other threads
+    let seen = std::thread::spawn(move || sys.dead_letters().len())
+        .join()
+        .unwrap();
+    assert_eq!(seen, 3);
+
+    System::current().clear_dead_letters();
+    assert!(System::current().dead_letters().is_empty());
+}
+
+#[actix::test]
+async fn test_queued_and_revoked_dead_letters() {
+    let addr = Worker.start();
+
+    // messages queued behind the stop are dropped with the mailbox
+    addr.do_send(Stop);
+    let queued = addr.send(Job);
+    addr.do_send(Job);
+    assert!(queued.await.is_err());
+
+    let (recipient, handle) = addr.revocable_recipient::<Job>();
+    handle.revoke();
+    assert!(recipient.try_send(Job).is_err());
+
+    let reasons: Vec<_> = System::current()
+        .dead_letters()
+        .into_iter()
+        .map(|r| (r.reason, r.expects_reply))
+        .collect();
+    assert_eq!(
+        reasons,
+        [
+            (DeadLetterReason::Dropped, true),
+            (DeadLetterReason::Dropped, false),
+            (DeadLetterReason::Revoked, false),
+        ]
+    );
+}
+
+#[test]
+fn test_dead_letter_capacity() {
+    let sys = SystemConfig::new().dead_letter_capacity(2).build();
+    sys.block_on(async {
+        let addr = Worker.start();
+        addr.send(Stop).await.unwrap();
+        addr.closed().await;
+        for _ in 0..5 {
+            addr.do_send(Job);
+        }
+        assert_eq!(System::current().dead_letters().len(), 2);
+    });
+
+    let sys = SystemConfig::new().dead_letter_capacity(0).build();
+    sys.block_on(async {
+        let addr = Worker.start();
+        addr.send(Stop).await.unwrap();
+        addr.closed().await;
+        addr.do_send(Job);
+        assert!(System::current().dead_letters().is_empty());
+    });
+}
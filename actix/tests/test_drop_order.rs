@@ -0,0 +1,181 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    future::{pending, poll_fn, Future},
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::Poll,
+};
+
+use actix::prelude::*;
+
+type Log = Arc<Mutex<Vec<&'static str>>>;
+
+/// Records when it is dropped, optionally panicking afterwards.
+struct Tracked {
+    name: &'static str,
+    log: Log,
+    panic: bool,
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.log.lock().unwrap().push(self.name);
+        if self.panic {
+            panic!("{} panicked", self.name);
+        }
+    }
+}
+
+struct Tracker {
+    log: Log,
+    stop: bool,
+    panicking_future: bool,
+}
+
+impl Tracker {
+    fn new(log: &Log, stop: bool) -> Self {
+        Tracker {
+            log: Arc::clone(log),
+            stop,
+            panicking_future: false,
+        }
+    }
+
+    fn tracked(&self, name: &'static str, panic: bool) -> Tracked {
+        Tracked {
+            name,
+            log: Arc::clone(&self.log),
+            panic,
+        }
+    }
+}
+
+impl Actor for Tracker {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let first = self.tracked("first spawned dropped", self.panicking_future);
+        let second = self.tracked("second spawned dropped", false);
+        let wait = self.tracked("wait dropped", false);
+
+        ctx.spawn(
+            async move {
+                let _first = first;
+                pending::<()>().await
+            }
+            .into_actor(self),
+        );
+        ctx.spawn(
+            async move {
+                let _second = second;
+                pending::<()>().await
+            }
+            .into_actor(self),
+        );
+        ctx.wait(
+            async move {
+                let _wait = wait;
+                pending::<()>().await
+            }
+            .into_actor(self),
+        );
+    }
+
+    fn stopping(&mut self, _: &mut Self::Context) -> Running {
+        self.log.lock().unwrap().push("stopping");
+        if self.stop {
+            Running::Stop
+        } else {
+            Running::Continue
+        }
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.log.lock().unwrap().push("stopped");
+    }
+
+    fn abandoned(&mut self) {
+        self.log.lock().unwrap().push("abandoned");
+    }
+}
+
+impl Drop for Tracker {
+    fn drop(&mut self) {
+        self.log.lock().unwrap().push("actor dropped");
+    }
+}
+
+/// Runs the context of `act` until it is blocked on its wait future, returning it unfinished.
+async fn start_context(act: Tracker) -> Pin<Box<impl Future<Output = ()>>> {
+    let mut fut = Box::pin(Context::new().into_future(act));
+    assert!(poll_fn(|cx| Poll::Ready(fut.as_mut().poll(cx)))
+        .await
+        .is_pending());
+    fut
+}
+
+#[actix::test]
+async fn test_drop_abandoned() {
+    let log = Log::default();
+    let fut = start_context(Tracker::new(&log, false)).await;
+
+    drop(fut);
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "stopping",
+            "wait dropped",
+            "first spawned dropped",
+            "second spawned dropped",
+            "abandoned",
+            "actor dropped",
+        ]
+    );
+}
+
+#[actix::test]
+async fn test_drop_stopped() {
+    let log = Log::default();
+    let fut = start_context(Tracker::new(&log, true)).await;
+
+    // the actor agrees to stop, so it is not abandoned
+    drop(fut);
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "stopping",
+            "stopped",
+            "wait dropped",
+            "first spawned dropped",
+            "second spawned dropped",
+            "actor dropped",
+        ]
+    );
+}
+
+#[actix::test]
+async fn test_drop_panicking_future() {
+    let log = Log::default();
+    let mut act = Tracker::new(&log, false);
+    act.panicking_future = true;
+    let fut = start_context(act).await;
+
+    let res = panic::catch_unwind(AssertUnwindSafe(|| drop(fut)));
+    assert_eq!(
+        res.unwrap_err().downcast_ref::<String>().unwrap(),
+        "first spawned dropped panicked"
+    );
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "stopping",
+            "wait dropped",
+            "first spawned dropped",
+            "second spawned dropped",
+            "abandoned",
+            "actor dropped",
+        ]
+    );
+}
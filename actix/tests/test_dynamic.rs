@@ -0,0 +1,79 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix::{
+    actors::dynamic::{start_dyn, DynContext, DynHandler},
+    prelude::*,
+};
+use actix_rt::time::sleep;
+
+#[derive(Message)]
+#[rtype(result = "usize")]
+enum PluginMsg {
+    Add(usize),
+    Get,
+    Stop,
+}
+
+struct Counter {
+    count: usize,
+    events: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl DynHandler<PluginMsg> for Counter {
+    fn started(&mut self, ctx: &mut DynContext<'_, PluginMsg>) {
+        self.events.lock().unwrap().push("started");
+        ctx.notify(PluginMsg::Add(1));
+
+        let recipient = ctx.recipient();
+        ctx.spawn(async move {
+            recipient.do_send(PluginMsg::Add(10));
+        });
+    }
+
+    fn handle(&mut self, msg: PluginMsg, ctx: &mut DynContext<'_, PluginMsg>) -> usize {
+        match msg {
+            PluginMsg::Add(n) => self.count += n,
+            PluginMsg::Get => {}
+            PluginMsg::Stop => ctx.stop(),
+        }
+        self.count
+    }
+
+    fn stopping(&mut self, _: &mut DynContext<'_, PluginMsg>) -> Running {
+        self.events.lock().unwrap().push("stopping");
+        Running::Stop
+    }
+
+    fn stopped(&mut self, _: &mut DynContext<'_, PluginMsg>) {
+        self.events.lock().unwrap().push("stopped");
+    }
+}
+
+type Plugin = Box<dyn FnOnce(&mut DynContext<'_, PluginMsg>) -> Box<dyn DynHandler<PluginMsg>>>;
+
+#[actix::test]
+async fn test_start_dyn() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let plugin_events = Arc::clone(&events);
+    let plugin: Plugin = Box::new(move |_| {
+        Box::new(Counter {
+            count: 0,
+            events: plugin_events,
+        })
+    });
+
+    let recipient = start_dyn(plugin);
+    sleep(Duration::from_millis(10)).await;
+    assert_eq!(recipient.send(PluginMsg::Get).await.unwrap(), 11);
+    assert_eq!(recipient.send(PluginMsg::Add(4)).await.unwrap(), 15);
+
+    assert_eq!(recipient.send(PluginMsg::Stop).await.unwrap(), 15);
+    sleep(Duration::from_millis(10)).await;
+    assert!(!recipient.connected());
+    assert_eq!(*events.lock().unwrap(), ["started", "stopping", "stopped"]);
+}
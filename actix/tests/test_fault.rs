@@ -0,0 +1,153 @@
+#![cfg(all(feature = "macros", feature = "testing"))]
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use actix::{
+    prelude::*,
+    test::{self, FaultPlan},
+    ExitReason,
+};
+use actix_rt::time::{sleep, Instant};
+
+struct Worker {
+    handled: Arc<AtomicUsize>,
+}
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+}
+
+#[derive(Message)]
+#[rtype(result = "usize")]
+struct Job;
+
+impl Handler<Job> for Worker {
+    type Result = usize;
+
+    fn handle(&mut self, _: Job, _: &mut Self::Context) -> usize {
+        self.handled.fetch_add(1, Ordering::SeqCst) + 1
+    }
+}
+
+/// Restarts its worker whenever it panics.
+struct Parent {
+    handled: Arc<AtomicUsize>,
+    restarts: Arc<AtomicUsize>,
+    worker: Option<Addr<Worker>>,
+}
+
+impl Parent {
+    fn start_worker(&mut self, ctx: &mut Context<Self>) {
+        let worker = Worker {
+            handled: Arc::clone(&self.handled),
+        }
+        .start();
+        ctx.link(&worker);
+        self.worker = Some(worker);
+    }
+}
+
+impl Actor for Parent {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.start_worker(ctx);
+    }
+
+    fn linked_exit(&mut self, exit: LinkedExit, ctx: &mut Self::Context) {
+        if exit.reason == ExitReason::Panicked {
+            self.restarts.fetch_add(1, Ordering::SeqCst);
+            self.start_worker(ctx);
+        } else {
+            ctx.stop();
+        }
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Option<Addr<Worker>>")]
+struct GetWorker;
+
+impl Handler<GetWorker> for Parent {
+    type Result = Option<Addr<Worker>>;
+
+    fn handle(&mut self, _: GetWorker, _: &mut Self::Context) -> Self::Result {
+        self.worker.clone()
+    }
+}
+
+#[actix::test]
+async fn test_supervisor_restarts_panicked_worker() {
+    // every worker panics on its third message
+    test::inject_type::<Worker>(FaultPlan::new().panic_in_handler_after(2));
+
+    let handled = Arc::new(AtomicUsize::new(0));
+    let restarts = Arc::new(AtomicUsize::new(0));
+    let parent = Parent {
+        handled: Arc::clone(&handled),
+        restarts: Arc::clone(&restarts),
+        worker: None,
+    }
+    .start();
+
+    let mut failed = 0;
+    for _ in 0..9 {
+        let worker = parent.send(GetWorker).await.unwrap().unwrap();
+        if worker.send(Job).await.is_err() {
+            failed += 1;
+            // give the parent time to restart the worker
+            sleep(Duration::from_millis(20)).await;
+        }
+    }
+    test::clear_type_faults::<Worker>();
+
+    assert_eq!(failed, 3);
+    assert_eq!(handled.load(Ordering::SeqCst), 6);
+    assert_eq!(restarts.load(Ordering::SeqCst), 3);
+}
+
+#[actix::test]
+async fn test_inject_into_address() {
+    let handled = Arc::new(AtomicUsize::new(0));
+    let worker = Worker {
+        handled: Arc::clone(&handled),
+    }
+    .start();
+
+    test::inject(&worker, FaultPlan::new().drop_every_nth_send(3));
+    let mut results = Vec::new();
+    for _ in 0..6 {
+        results.push(worker.send(Job).await.is_ok());
+    }
+    assert_eq!(results, [true, true, false, true, true, false]);
+    assert_eq!(handled.load(Ordering::SeqCst), 4);
+
+    test::inject(
+        &worker,
+        FaultPlan::new().fail_calls_with(MailboxError::Timeout),
+    );
+    assert_eq!(worker.send(Job).await, Err(MailboxError::Timeout));
+    worker.do_send(Job);
+    assert_eq!(handled.load(Ordering::SeqCst), 4);
+
+    test::inject(
+        &worker,
+        FaultPlan::new().delay_replies(Duration::from_millis(50)),
+    );
+    let start = Instant::now();
+    assert_eq!(worker.send(Job).await, Ok(6));
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    assert_eq!(
+        worker.send(Job).timeout(Duration::from_millis(10)).await,
+        Err(MailboxError::Timeout)
+    );
+
+    test::inject(&worker, FaultPlan::new());
+    assert_eq!(worker.send(Job).await, Ok(8));
+}
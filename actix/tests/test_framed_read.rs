@@ -0,0 +1,185 @@
+use std::{
+    collections::VecDeque,
+    fmt, io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context as StdContext, Poll},
+};
+
+use actix::{
+    io::{FramedError, FramedReader, ReadHandler},
+    prelude::*,
+};
+use bytes::{Buf, BytesMut};
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio_util::codec::Decoder;
+
+/// Transport returning scripted reads, then the end of the stream.
+struct Scripted(VecDeque<io::Result<&'static [u8]>>);
+
+impl AsyncRead for Scripted {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _: &mut StdContext<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.0.pop_front() {
+            Some(Ok(chunk)) => {
+                buf.put_slice(chunk);
+                Poll::Ready(Ok(()))
+            }
+            Some(Err(err)) => Poll::Ready(Err(err)),
+            None => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum NumberError {
+    Invalid(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for NumberError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumberError::Invalid(line) => write!(fmt, "invalid number {:?}", line),
+            NumberError::Io(err) => write!(fmt, "{}", err),
+        }
+    }
+}
+
+impl From<io::Error> for NumberError {
+    fn from(err: io::Error) -> Self {
+        NumberError::Io(err)
+    }
+}
+
+/// Decodes numbers on separate lines, consuming invalid lines.
+struct NumberCodec;
+
+impl Decoder for NumberCodec {
+    type Item = u32;
+    type Error = NumberError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<u32>, NumberError> {
+        let Some(end) = src.iter().position(|b| *b == b'\n') else {
+            return Ok(None);
+        };
+        let line = String::from_utf8_lossy(&src[..end]).into_owned();
+        src.advance(end + 1);
+        line.parse()
+            .map(Some)
+            .map_err(|_| NumberError::Invalid(line))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Event {
+    Frame(u32),
+    Eof,
+    Io(io::ErrorKind),
+    Decode(String),
+}
+
+struct Session {
+    io: Option<Scripted>,
+    events: Arc<Mutex<Vec<Event>>>,
+    skip_invalid: bool,
+}
+
+impl Actor for Session {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.spawn(FramedReader::new(self.io.take().unwrap(), NumberCodec));
+    }
+}
+
+impl ReadHandler<NumberCodec> for Session {
+    fn handle(&mut self, item: u32, _: &mut Self::Context) {
+        self.events.lock().unwrap().push(Event::Frame(item));
+    }
+
+    fn closed(&mut self, err: Option<FramedError<NumberError>>, _: &mut Self::Context) -> Running {
+        let (event, running) = match err {
+            None => (Event::Eof, Running::Stop),
+            Some(FramedError::Io(err)) => (Event::Io(err.kind()), Running::Stop),
+            Some(FramedError::Decode(NumberError::Invalid(line))) if self.skip_invalid => {
+                (Event::Decode(line), Running::Continue)
+            }
+            Some(FramedError::Decode(err)) => (Event::Decode(err.to_string()), Running::Stop),
+        };
+        self.events.lock().unwrap().push(event);
+        running
+    }
+}
+
+async fn run(script: Vec<io::Result<&'static [u8]>>, skip_invalid: bool) -> Vec<Event> {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let addr = Session {
+        io: Some(Scripted(script.into())),
+        events: Arc::clone(&events),
+        skip_invalid,
+    }
+    .start();
+    addr.closed().await;
+    let events = std::mem::take(&mut *events.lock().unwrap());
+    events
+}
+
+#[test]
+fn test_framed_reader_eof() {
+    System::new().block_on(async {
+        let events = run(vec![Ok(b"1\n2"), Ok(b"\n3\n")], false).await;
+        assert_eq!(
+            events,
+            [
+                Event::Frame(1),
+                Event::Frame(2),
+                Event::Frame(3),
+                Event::Eof
+            ]
+        );
+    });
+}
+
+#[test]
+fn test_framed_reader_io_error() {
+    System::new().block_on(async {
+        let reset = io::Error::from(io::ErrorKind::ConnectionReset);
+        let events = run(vec![Ok(b"1\n"), Err(reset), Ok(b"2\n")], false).await;
+        assert_eq!(
+            events,
+            [Event::Frame(1), Event::Io(io::ErrorKind::ConnectionReset)]
+        );
+    });
+}
+
+#[test]
+fn test_framed_reader_decode_error() {
+    System::new().block_on(async {
+        let script = || vec![Ok(&b"1\nfoo\n2\n"[..])];
+
+        let events = run(script(), false).await;
+        assert_eq!(
+            events,
+            [
+                Event::Frame(1),
+                Event::Decode("invalid number \"foo\"".to_owned())
+            ]
+        );
+
+        // the invalid frame is skipped
+        let events = run(script(), true).await;
+        assert_eq!(
+            events,
+            [
+                Event::Frame(1),
+                Event::Decode("foo".to_owned()),
+                Event::Frame(2),
+                Event::Eof
+            ]
+        );
+    });
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "macros")]
+
+use std::io;
+
+use actix::{
+    io::{FramedWrite, WriteHandler},
+    prelude::*,
+};
+use bytes::Bytes;
+use tokio::io::{AsyncReadExt as _, DuplexStream};
+use tokio_util::codec::{BytesCodec, LinesCodec, LinesCodecError};
+
+struct Upgrader {
+    io: Option<DuplexStream>,
+    writer: Option<FramedWrite<Bytes, DuplexStream, BytesCodec>>,
+}
+
+impl Actor for Upgrader {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let mut lines = FramedWrite::new(self.io.take().unwrap(), LinesCodec::new(), ctx);
+        lines.write("UPGRADE raw".to_owned());
+        lines.write("".to_owned());
+
+        let mut raw = lines.replace_encoder(BytesCodec::new(), ctx);
+        raw.write(Bytes::from_static(b"\x01\x02"));
+        raw.close();
+        self.writer = Some(raw);
+    }
+}
+
+impl WriteHandler<LinesCodecError> for Upgrader {}
+
+impl WriteHandler<io::Error> for Upgrader {}
+
+#[actix::test]
+async fn test_replace_encoder() {
+    let (client, mut server) = tokio::io::duplex(64);
+
+    let addr = Upgrader {
+        io: Some(client),
+        writer: None,
+    }
+    .start();
+
+    let mut received = Vec::new();
+    server.read_to_end(&mut received).await.unwrap();
+    assert_eq!(received, b"UPGRADE raw\n\n\x01\x02");
+
+    // the writer stops the actor once it is closed
+    actix_rt::task::yield_now().await;
+    assert!(!addr.connected());
+}
@@ -257,3 +257,373 @@ fn test_try_future() {
         assert_eq!(res.err().unwrap(), 996u32);
     })
 }
+
+struct Inspector(Vec<String>);
+
+impl Actor for Inspector {
+    type Context = Context<Self>;
+}
+
+struct Check(Result<u32, u32>);
+
+impl Message for Check {
+    type Result = Result<u32, u32>;
+}
+
+impl Handler<Check> for Inspector {
+    type Result = ResponseActFuture<Self, Result<u32, u32>>;
+
+    fn handle(&mut self, msg: Check, _: &mut Self::Context) -> Self::Result {
+        async move { msg.0 }
+            .into_actor(self)
+            .traced("ready")
+            .inspect(|res, act, _| act.0.push(format!("inspect {res:?}")))
+            .inspect_err(|err, act, _| act.0.push(format!("inspect_err {err}")))
+            .traced("inspect")
+            .map(|res, act, _| {
+                act.0.push("map".to_owned());
+                res
+            })
+            .boxed_local()
+    }
+}
+
+struct Log;
+
+impl Message for Log {
+    type Result = Vec<String>;
+}
+
+impl Handler<Log> for Inspector {
+    type Result = MessageResult<Log>;
+
+    fn handle(&mut self, _: Log, _: &mut Self::Context) -> Self::Result {
+        MessageResult(std::mem::take(&mut self.0))
+    }
+}
+
+#[test]
+fn test_inspect() {
+    System::new().block_on(async {
+        let addr = Inspector(Vec::new()).start();
+
+        assert_eq!(addr.send(Check(Ok(1))).await.unwrap(), Ok(1));
+        assert_eq!(addr.send(Log).await.unwrap(), ["inspect Ok(1)", "map"]);
+
+        assert_eq!(addr.send(Check(Err(2))).await.unwrap(), Err(2));
+        assert_eq!(
+            addr.send(Log).await.unwrap(),
+            ["inspect Err(2)", "inspect_err 2", "map"]
+        );
+    })
+}
+
+/// Fails its first attempts, counting attempts which are alive.
+#[derive(Default)]
+struct Flaky {
+    attempts: usize,
+    live: std::rc::Rc<()>,
+    pending: Option<SpawnHandle>,
+}
+
+impl Actor for Flaky {
+    type Context = Context<Self>;
+}
+
+struct Attempt {
+    fail: usize,
+    give_up: usize,
+    delay: Duration,
+}
+
+impl Message for Attempt {
+    type Result = (Result<usize, usize>, usize);
+}
+
+impl Handler<Attempt> for Flaky {
+    type Result = ResponseActFuture<Self, (Result<usize, usize>, usize)>;
+
+    fn handle(&mut self, msg: Attempt, _: &mut Self::Context) -> Self::Result {
+        self.attempts = 0;
+        let live = self.live.clone();
+        Box::pin(
+            fut::retry(
+                move |&err, failures| {
+                    // the failed attempt was dropped before the delay
+                    assert_eq!(std::rc::Rc::strong_count(&live), 2);
+                    assert_eq!(err, failures);
+                    (failures < msg.give_up).then_some(msg.delay)
+                },
+                move |act: &mut Self, _| {
+                    act.attempts += 1;
+                    let attempt = act.attempts;
+                    let guard = act.live.clone();
+                    sleep(Duration::from_millis(1))
+                        .into_actor(act)
+                        .map(move |_, _, _| {
+                            drop(guard);
+                            if attempt > msg.fail {
+                                Ok(attempt)
+                            } else {
+                                Err(attempt)
+                            }
+                        })
+                },
+            )
+            .map(|res, act, _| (res, act.attempts)),
+        )
+    }
+}
+
+#[test]
+fn test_retry() {
+    System::new().block_on(async {
+        let addr = Flaky::default().start();
+        let attempt = |fail, give_up| Attempt {
+            fail,
+            give_up,
+            delay: Duration::from_millis(5),
+        };
+
+        assert_eq!(addr.send(attempt(0, 3)).await.unwrap(), (Ok(1), 1));
+        assert_eq!(addr.send(attempt(2, 3)).await.unwrap(), (Ok(3), 3));
+        assert_eq!(addr.send(attempt(5, 3)).await.unwrap(), (Err(3), 3));
+    })
+}
+
+enum Pending {
+    Spawn,
+    Cancel,
+    Live,
+}
+
+impl Message for Pending {
+    type Result = usize;
+}
+
+impl Handler<Pending> for Flaky {
+    type Result = usize;
+
+    fn handle(&mut self, msg: Pending, ctx: &mut Self::Context) -> usize {
+        match msg {
+            Pending::Spawn => {
+                let handle = ctx.spawn(
+                    fut::retry(
+                        |_: &(), _| Some(Duration::from_millis(1)),
+                        |act: &mut Self, _| {
+                            let guard = act.live.clone();
+                            sleep(Duration::from_secs(10))
+                                .into_actor(act)
+                                .map(move |_, _, _| {
+                                    drop(guard);
+                                    Err(())
+                                })
+                        },
+                    )
+                    .map(|_: Result<(), ()>, _, _| unreachable!()),
+                );
+                self.pending = Some(handle);
+            }
+            Pending::Cancel => {
+                ctx.cancel_future(self.pending.take().unwrap());
+            }
+            Pending::Live => {}
+        }
+        std::rc::Rc::strong_count(&self.live) - 1
+    }
+}
+
+#[test]
+fn test_retry_cancel() {
+    System::new().block_on(async {
+        let addr = Flaky::default().start();
+
+        addr.send(Pending::Spawn).await.unwrap();
+        assert_eq!(addr.send(Pending::Live).await.unwrap(), 1);
+
+        // cancelling drops the pending attempt
+        addr.send(Pending::Cancel).await.unwrap();
+        assert_eq!(addr.send(Pending::Live).await.unwrap(), 0);
+    })
+}
+
+struct Replica {
+    answer: Result<u64, u64>,
+    queries: usize,
+}
+
+impl Actor for Replica {
+    type Context = actix::Context<Self>;
+}
+
+struct Query;
+
+impl Message for Query {
+    type Result = Result<u64, u64>;
+}
+
+impl Handler<Query> for Replica {
+    type Result = Result<u64, u64>;
+
+    fn handle(&mut self, _: Query, _: &mut Self::Context) -> Result<u64, u64> {
+        self.queries += 1;
+        self.answer
+    }
+}
+
+/// Holds back the mailbox of the replica for a while, replies with the number of queries.
+struct Stall(Duration);
+
+impl Message for Stall {
+    type Result = usize;
+}
+
+impl Handler<Stall> for Replica {
+    type Result = usize;
+
+    fn handle(&mut self, Stall(dur): Stall, ctx: &mut Self::Context) -> usize {
+        ctx.wait(sleep(dur).into_actor(self));
+        self.queries
+    }
+}
+
+struct Race(Vec<Addr<Replica>>);
+
+impl Message for Race {
+    type Result = Result<(usize, u64), Vec<u64>>;
+}
+
+impl Handler<Race> for Flaky {
+    type Result = ResponseActFuture<Self, Result<(usize, u64), Vec<u64>>>;
+
+    fn handle(&mut self, Race(replicas): Race, _: &mut Self::Context) -> Self::Result {
+        let queries = replicas.iter().map(|replica| {
+            let query = replica.send(Query);
+            fut::wrap_future(async move { query.await.unwrap() })
+        });
+        Box::pin(fut::race_ok(queries))
+    }
+}
+
+#[test]
+fn test_race_ok() {
+    System::new().block_on(async {
+        let replica = |answer| Replica { answer, queries: 0 }.start();
+        let addr = Flaky::default().start();
+
+        // the query queued behind the stall is dropped before it is handled
+        let slow = replica(Ok(1));
+        slow.do_send(Stall(Duration::from_millis(50)));
+        let fast = replica(Ok(2));
+        let res = addr.send(Race(vec![slow.clone(), fast])).await.unwrap();
+        assert_eq!(res, Ok((1, 2)));
+        assert_eq!(slow.send(Stall(Duration::ZERO)).await.unwrap(), 0);
+
+        // failures are skipped
+        let res = addr
+            .send(Race(vec![replica(Err(1)), replica(Ok(2)), replica(Err(3))]))
+            .await
+            .unwrap();
+        assert_eq!(res, Ok((1, 2)));
+
+        let res = addr
+            .send(Race(vec![replica(Err(1)), replica(Err(2))]))
+            .await
+            .unwrap();
+        assert_eq!(res, Err(vec![1, 2]));
+        assert_eq!(addr.send(Race(Vec::new())).await.unwrap(), Err(Vec::new()));
+    })
+}
+
+/// Records the range of stack addresses its chain stages run at.
+#[derive(Default)]
+struct Pipeline {
+    stack: Option<(usize, usize)>,
+}
+
+impl Pipeline {
+    fn record_stack(&mut self) {
+        let marker = 0u8;
+        let addr = std::ptr::addr_of!(marker) as usize;
+        let (low, high) = self.stack.get_or_insert((addr, addr));
+        *low = (*low).min(addr);
+        *high = (*high).max(addr);
+    }
+}
+
+impl Actor for Pipeline {
+    type Context = Context<Self>;
+}
+
+struct RunChain(u64);
+
+impl Message for RunChain {
+    type Result = Result<u64, u64>;
+}
+
+impl Handler<RunChain> for Pipeline {
+    type Result = ResponseActFuture<Self, Result<u64, u64>>;
+
+    fn handle(&mut self, msg: RunChain, _: &mut Self::Context) -> Self::Result {
+        let chain = (1..=msg.0).fold(fut::ok(0).into_chain(), |chain, stage| {
+            chain.and_then(move |n, act: &mut Self, _| {
+                act.record_stack();
+                let fut = async move {
+                    // some stages are pending before resolving
+                    if stage % 10_000 == 0 {
+                        actix_rt::task::yield_now().await;
+                    }
+                    if stage == 150_000 {
+                        Err(n)
+                    } else {
+                        Ok(n + 1)
+                    }
+                };
+                fut.into_actor(act)
+            })
+        });
+        Box::pin(chain.build())
+    }
+}
+
+struct Stack;
+
+impl Message for Stack {
+    type Result = Option<(usize, usize)>;
+}
+
+impl Handler<Stack> for Pipeline {
+    type Result = Option<(usize, usize)>;
+
+    fn handle(&mut self, _: Stack, _: &mut Self::Context) -> Self::Result {
+        self.stack
+    }
+}
+
+#[test]
+fn test_chain_does_not_recurse() {
+    // nested `then` combinators of this length overflow a stack of this size
+    let stack = std::thread::Builder::new()
+        .stack_size(1024 * 1024)
+        .spawn(|| {
+            System::new().block_on(async {
+                let addr = Pipeline::default().start();
+                assert_eq!(addr.send(RunChain(100_000)).await.unwrap(), Ok(100_000));
+                addr.send(Stack).await.unwrap()
+            })
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    let (low, high) = stack.unwrap();
+    assert!(high - low < 16 * 1024, "stack grew by {} bytes", high - low);
+}
+
+#[test]
+fn test_chain_skips_stages_after_error() {
+    System::new().block_on(async {
+        let addr = Pipeline::default().start();
+        assert_eq!(addr.send(RunChain(200_000)).await.unwrap(), Err(149_999));
+    });
+}
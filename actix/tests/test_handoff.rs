@@ -0,0 +1,212 @@
+#![cfg(feature = "macros")]
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use actix::{prelude::*, MinimalContext};
+
+/// Records the sequence numbers of the messages of each sender, across instances.
+struct Sequencer {
+    /// Last sequence number seen for each sender.
+    last: Vec<u64>,
+    generation: u32,
+    stopped: Arc<AtomicUsize>,
+}
+
+impl Actor for Sequencer {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        // stay below the per-poll limit checked with the `mailbox_assert` feature
+        ctx.set_message_budget(Some(128));
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.stopped.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "u32")]
+struct Next {
+    sender: usize,
+    seq: u64,
+}
+
+impl Handler<Next> for Sequencer {
+    type Result = u32;
+
+    fn handle(&mut self, msg: Next, _: &mut Self::Context) -> u32 {
+        assert!(
+            msg.seq > self.last[msg.sender],
+            "message {} of sender {} overtook {}",
+            msg.seq,
+            msg.sender,
+            self.last[msg.sender]
+        );
+        self.last[msg.sender] = msg.seq;
+        self.generation
+    }
+}
+
+/// Replaces the actor with a new instance, migrating its state.
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Upgrade;
+
+impl Handler<Upgrade> for Sequencer {
+    type Result = ();
+
+    fn handle(&mut self, _: Upgrade, ctx: &mut Self::Context) {
+        let replacement = Sequencer {
+            last: self.last.clone(),
+            generation: self.generation + 1,
+            stopped: Arc::clone(&self.stopped),
+        };
+        ctx.handoff_to(replacement.start());
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "u32")]
+struct Generation;
+
+impl Handler<Generation> for Sequencer {
+    type Result = u32;
+
+    fn handle(&mut self, _: Generation, _: &mut Self::Context) -> u32 {
+        self.generation
+    }
+}
+
+#[actix::test]
+async fn test_handoff_forwards_queued_messages() {
+    let stopped = Arc::new(AtomicUsize::new(0));
+    let addr = Sequencer {
+        last: vec![0],
+        generation: 0,
+        stopped: Arc::clone(&stopped),
+    }
+    .start();
+
+    // replies of the forwarded requests reach their senders
+    let mut replies = Vec::new();
+    for seq in 1..=10 {
+        replies.push(addr.send(Next { sender: 0, seq }));
+        if seq == 3 {
+            addr.do_send(Upgrade);
+        }
+    }
+    let generations: Vec<_> = futures_util::future::join_all(replies)
+        .await
+        .into_iter()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(generations, [0, 0, 0, 1, 1, 1, 1, 1, 1, 1]);
+
+    // the old address keeps working
+    assert!(addr.connected());
+    assert_eq!(addr.send(Generation).await.unwrap(), 1);
+    addr.do_send(Upgrade);
+    assert_eq!(addr.send(Generation).await.unwrap(), 2);
+    assert_eq!(stopped.load(Ordering::SeqCst), 2);
+}
+
+#[actix::test]
+async fn test_handoff_with_concurrent_senders() {
+    const SENDERS: usize = 4;
+    const MESSAGES: u64 = 2_000;
+
+    let stopped = Arc::new(AtomicUsize::new(0));
+    let addr = Sequencer {
+        last: vec![0; SENDERS],
+        generation: 0,
+        stopped: Arc::clone(&stopped),
+    }
+    .start();
+
+    let arbiters: Vec<_> = (0..SENDERS).map(|_| Arbiter::new()).collect();
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    for (sender, arbiter) in arbiters.iter().enumerate() {
+        let (addr, tx) = (addr.clone(), tx.clone());
+        arbiter.spawn(async move {
+            for seq in 1..=MESSAGES {
+                // mixes delivery modes, all of them are redirected
+                let res = match seq % 3 {
+                    0 => addr.send(Next { sender, seq }).await.map(|_| ()),
+                    1 => {
+                        addr.do_send(Next { sender, seq });
+                        Ok(())
+                    }
+                    _ => loop {
+                        match addr.try_send(Next { sender, seq }) {
+                            Err(SendError::Full(_)) => actix_rt::task::yield_now().await,
+                            res => break res.map_err(|_| MailboxError::Closed),
+                        }
+                    },
+                };
+                tx.send(res).unwrap();
+            }
+        });
+    }
+    drop(tx);
+
+    for _ in 0..3 {
+        actix_rt::time::sleep(std::time::Duration::from_millis(1)).await;
+        addr.send(Upgrade).await.unwrap();
+    }
+
+    let mut sent = 0;
+    while let Some(res) = rx.recv().await {
+        res.unwrap();
+        sent += 1;
+    }
+    assert_eq!(sent, SENDERS * MESSAGES as usize);
+    assert_eq!(addr.send(Generation).await.unwrap(), 3);
+    assert_eq!(stopped.load(Ordering::SeqCst), 3);
+
+    for arbiter in arbiters {
+        arbiter.stop();
+    }
+}
+
+struct Slim(u32);
+
+impl Actor for Slim {
+    type Context = MinimalContext<Self>;
+}
+
+#[derive(Message)]
+#[rtype(result = "u32")]
+struct Get;
+
+impl Handler<Get> for Slim {
+    type Result = u32;
+
+    fn handle(&mut self, _: Get, _: &mut Self::Context) -> u32 {
+        self.0
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Replace(u32);
+
+impl Handler<Replace> for Slim {
+    type Result = ();
+
+    fn handle(&mut self, Replace(n): Replace, ctx: &mut Self::Context) {
+        ctx.handoff_to(MinimalContext::new().run(Slim(n)));
+    }
+}
+
+#[actix::test]
+async fn test_minimal_context_handoff() {
+    let addr = MinimalContext::new().run(Slim(1));
+    addr.do_send(Replace(2));
+    let queued = addr.send(Get);
+    assert_eq!(queued.await.unwrap(), 2);
+    assert_eq!(addr.send(Get).await.unwrap(), 2);
+}
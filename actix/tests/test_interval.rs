@@ -0,0 +1,185 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix::{clock::Clock, prelude::*, test::ManualClock, utils::IntervalFunc};
+use actix_rt::time::{sleep, Instant};
+use tokio::time::pause;
+
+type Ticks = Arc<Mutex<Vec<Duration>>>;
+
+struct Ticker {
+    start: Instant,
+    ticks: Ticks,
+    fixed_delay: bool,
+    jitter: f64,
+    block_for: Duration,
+    cancel_after: Option<usize>,
+}
+
+impl Ticker {
+    fn new(ticks: &Ticks) -> Self {
+        Ticker {
+            start: Instant::now(),
+            ticks: Arc::clone(ticks),
+            fixed_delay: false,
+            jitter: 0.0,
+            block_for: Duration::ZERO,
+            cancel_after: None,
+        }
+    }
+
+    fn tick(&mut self, ctx: &mut Context<Self>) {
+        let mut ticks = self.ticks.lock().unwrap();
+        ticks.push(actix::clock::now() - self.start);
+
+        if self.cancel_after == Some(ticks.len()) {
+            ctx.cancel_future(ctx.handle());
+        }
+    }
+}
+
+impl Actor for Ticker {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let interval = IntervalFunc::new(Duration::from_millis(100), Self::tick)
+            .fixed_delay(self.fixed_delay)
+            .jitter(self.jitter);
+        ctx.spawn(interval.finish());
+
+        // keep the actor busy, so the first tick is late
+        if !self.block_for.is_zero() {
+            ctx.wait(sleep(self.block_for).into_actor(self));
+        }
+    }
+}
+
+/// Compares ticks with the expected schedule, allowing for timer resolution.
+fn assert_ticks(ticks: &Ticks, expected: &[u64]) {
+    let ticks = ticks.lock().unwrap();
+    assert_eq!(ticks.len(), expected.len(), "ticks: {:?}", *ticks);
+    for (tick, ms) in ticks.iter().zip(expected) {
+        let ms = Duration::from_millis(*ms);
+        assert!(
+            *tick >= ms && *tick - ms < Duration::from_millis(5),
+            "ticks: {:?}, expected: {:?}",
+            *ticks,
+            expected
+        );
+    }
+}
+
+#[actix::test]
+async fn test_interval_fixed_rate() {
+    pause();
+    let ticks = Ticks::default();
+    let _addr = Ticker {
+        block_for: Duration::from_millis(250),
+        ..Ticker::new(&ticks)
+    }
+    .start();
+
+    sleep(Duration::from_millis(450)).await;
+
+    // late ticks catch up with the schedule
+    assert_ticks(&ticks, &[250, 250, 300, 400]);
+}
+
+#[actix::test]
+async fn test_interval_fixed_delay() {
+    pause();
+    let ticks = Ticks::default();
+    let _addr = Ticker {
+        block_for: Duration::from_millis(250),
+        fixed_delay: true,
+        ..Ticker::new(&ticks)
+    }
+    .start();
+
+    sleep(Duration::from_millis(500)).await;
+
+    // schedule restarts from the late tick
+    assert_ticks(&ticks, &[250, 350, 450]);
+}
+
+#[actix::test]
+async fn test_interval_no_drift() {
+    pause();
+    let ticks = Ticks::default();
+    let _addr = Ticker::new(&ticks).start();
+
+    sleep(Duration::from_millis(1050)).await;
+
+    // every tick is scheduled from the previous deadline
+    let ticks = ticks.lock().unwrap();
+    assert_eq!(ticks.len(), 10);
+    assert!(ticks
+        .windows(2)
+        .all(|pair| pair[1] - pair[0] == Duration::from_millis(100)));
+}
+
+#[actix::test]
+async fn test_interval_jitter() {
+    pause();
+    let ticks = Ticks::default();
+    let _addr = Ticker {
+        jitter: 0.5,
+        ..Ticker::new(&ticks)
+    }
+    .start();
+
+    sleep(Duration::from_secs(3)).await;
+
+    let ticks = ticks.lock().unwrap();
+    let periods: Vec<_> = ticks.windows(2).map(|pair| pair[1] - pair[0]).collect();
+    assert!(periods.len() >= 10);
+    assert!(periods
+        .iter()
+        .all(|p| *p >= Duration::from_millis(50) && *p <= Duration::from_millis(150)));
+    assert!(periods.iter().any(|p| *p != periods[0]));
+}
+
+#[actix::test]
+async fn test_interval_cancel() {
+    pause();
+    let ticks = Ticks::default();
+    let _addr = Ticker {
+        cancel_after: Some(2),
+        ..Ticker::new(&ticks)
+    }
+    .start();
+
+    sleep(Duration::from_millis(1000)).await;
+
+    assert_ticks(&ticks, &[100, 200]);
+}
+
+#[actix::test]
+async fn test_interval_manual_clock() {
+    let clock = ManualClock::new();
+    actix::clock::set_arbiter_clock(clock.clone());
+
+    let ticks = Ticks::default();
+    let _addr = Ticker {
+        start: clock.now(),
+        cancel_after: Some(3),
+        ..Ticker::new(&ticks)
+    }
+    .start();
+    sleep(Duration::from_millis(10)).await;
+    assert!(ticks.lock().unwrap().is_empty());
+
+    // the ticks fire at the exact times of the schedule
+    clock.advance(Duration::from_millis(250)).await;
+    assert_eq!(
+        *ticks.lock().unwrap(),
+        [Duration::from_millis(100), Duration::from_millis(200)]
+    );
+    clock.advance(Duration::from_millis(1000)).await;
+    assert_eq!(ticks.lock().unwrap().len(), 3);
+    assert_eq!(ticks.lock().unwrap()[2], Duration::from_millis(300));
+}
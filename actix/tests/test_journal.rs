@@ -0,0 +1,157 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use actix::{
+    journal::{Journal, MemoryJournal, ReplayError},
+    prelude::*,
+    spill::PersistentMessage,
+};
+
+#[derive(Message)]
+#[rtype(result = "u64")]
+struct Deposit(u64);
+
+impl PersistentMessage for Deposit {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_le_bytes().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let bytes = bytes.try_into().map_err(|_| io::ErrorKind::InvalidData)?;
+        Ok(Deposit(u64::from_le_bytes(bytes)))
+    }
+}
+
+/// Journaled message which stops the actor.
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Close;
+
+impl PersistentMessage for Close {
+    fn to_bytes(&self) -> Vec<u8> {
+        Vec::new()
+    }
+
+    fn from_bytes(_: &[u8]) -> io::Result<Self> {
+        Ok(Close)
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "u64")]
+struct Balance;
+
+struct Account {
+    balance: u64,
+    notified: Arc<AtomicUsize>,
+    started_with: Option<u64>,
+}
+
+impl Actor for Account {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _: &mut Self::Context) {
+        self.started_with = Some(self.balance);
+    }
+}
+
+impl Handler<Deposit> for Account {
+    type Result = u64;
+
+    fn handle(&mut self, Deposit(amount): Deposit, ctx: &mut Self::Context) -> u64 {
+        self.balance += amount;
+        if !ctx.is_replaying() {
+            self.notified.fetch_add(1, Ordering::SeqCst);
+        }
+        self.balance
+    }
+}
+
+impl Handler<Close> for Account {
+    type Result = ();
+
+    fn handle(&mut self, _: Close, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+}
+
+impl Handler<Balance> for Account {
+    type Result = MessageResult<Balance>;
+
+    fn handle(&mut self, _: Balance, _: &mut Self::Context) -> Self::Result {
+        MessageResult(self.started_with.unwrap())
+    }
+}
+
+fn open(
+    journal: &MemoryJournal,
+    notified: &Arc<AtomicUsize>,
+) -> Result<Addr<Account>, ReplayError> {
+    Account::create_replayed(Box::new(journal.clone()), "account", |ctx| {
+        ctx.journal_message::<Deposit>();
+        ctx.journal_message::<Close>();
+        Account {
+            balance: 0,
+            notified: Arc::clone(notified),
+            started_with: None,
+        }
+    })
+}
+
+#[actix::test]
+async fn test_journal_replay() {
+    let journal = MemoryJournal::new();
+    let notified = Arc::new(AtomicUsize::new(0));
+
+    let account = open(&journal, &notified).unwrap();
+    assert_eq!(account.send(Deposit(10)).await.unwrap(), 10);
+    assert_eq!(account.send(Deposit(5)).await.unwrap(), 15);
+    // messages of other types are not journaled
+    account.send(Balance).await.unwrap();
+    assert_eq!(journal.entries("account").len(), 2);
+    assert_eq!(notified.load(Ordering::SeqCst), 2);
+
+    // the state is recovered before the actor starts, without side effects
+    let account = open(&journal, &notified).unwrap();
+    assert_eq!(account.send(Balance).await.unwrap(), 15);
+    assert_eq!(account.send(Deposit(1)).await.unwrap(), 16);
+    assert_eq!(notified.load(Ordering::SeqCst), 3);
+    assert_eq!(journal.entries("account").len(), 3);
+    assert!(journal.entries("other").is_empty());
+}
+
+#[actix::test]
+async fn test_journal_replay_errors() {
+    let notified = Arc::new(AtomicUsize::new(0));
+
+    let mut journal = MemoryJournal::new();
+    journal.append("account", "unknown::Message", &[]).unwrap();
+    assert!(matches!(
+        open(&journal, &notified),
+        Err(ReplayError::UnknownType(name)) if name == "unknown::Message"
+    ));
+
+    let mut journal = MemoryJournal::new();
+    let deposit = std::any::type_name::<Deposit>();
+    journal.append("account", deposit, &[1, 2, 3]).unwrap();
+    assert!(matches!(
+        open(&journal, &notified),
+        Err(ReplayError::Decode { type_name, .. }) if type_name == deposit
+    ));
+
+    // an actor which stops during replay is not started
+    let mut journal = MemoryJournal::new();
+    let close = std::any::type_name::<Close>();
+    journal.append("account", close, &[]).unwrap();
+    assert!(matches!(
+        open(&journal, &notified),
+        Err(ReplayError::Stopped)
+    ));
+}
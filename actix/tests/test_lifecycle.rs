@@ -1,15 +1,18 @@
 #![allow(clippy::let_unit_value)]
 
 use std::{
+    pin::Pin,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc, Mutex,
     },
+    task::{self, Poll},
     time::Duration,
 };
 
 use actix::prelude::*;
 use actix_rt::time::sleep;
+use futures_util::stream::StreamExt as _;
 use tokio::sync::oneshot::{channel, Sender};
 
 struct MyActor {
@@ -277,3 +280,628 @@ fn test_stop_restore_after_stopping() {
     assert!(stopping.load(Ordering::Relaxed), "Not stopping");
     assert!(!stopped.load(Ordering::Relaxed), "Stopped");
 }
+
+struct StopOnRequest {
+    restore_once: bool,
+}
+
+impl Actor for StopOnRequest {
+    type Context = actix::Context<Self>;
+
+    fn stopping(&mut self, _: &mut Self::Context) -> Running {
+        if self.restore_once {
+            self.restore_once = false;
+            Running::Continue
+        } else {
+            Running::Stop
+        }
+    }
+}
+
+struct StopRequest;
+
+impl Message for StopRequest {
+    type Result = ();
+}
+
+impl Handler<StopRequest> for StopOnRequest {
+    type Result = ();
+
+    fn handle(&mut self, _: StopRequest, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+}
+
+#[test]
+fn test_state_stream() {
+    System::new().block_on(async {
+        let addr = StopOnRequest { restore_once: true }.start();
+        let first = addr.state_stream();
+        let second = addr.state_stream();
+
+        sleep(Duration::from_millis(10)).await;
+        let late = addr.state_stream();
+
+        addr.send(StopRequest).await.unwrap();
+        addr.do_send(StopRequest);
+
+        let expected = vec![
+            ActorState::Started,
+            ActorState::Running,
+            ActorState::Stopping,
+            ActorState::Running,
+            ActorState::Stopping,
+            ActorState::Stopped,
+        ];
+        assert_eq!(first.collect::<Vec<_>>().await, expected);
+        assert_eq!(second.collect::<Vec<_>>().await, expected);
+        assert_eq!(late.collect::<Vec<_>>().await, expected[1..]);
+
+        let stopped = addr.state_stream().collect::<Vec<_>>().await;
+        assert_eq!(stopped, vec![ActorState::Stopped]);
+    });
+}
+
+#[test]
+fn test_state_stream_does_not_keep_actor_alive() {
+    System::new().block_on(async {
+        let states = StopOnRequest {
+            restore_once: false,
+        }
+        .start()
+        .state_stream();
+
+        let states = actix_rt::time::timeout(Duration::from_secs(1), states.collect::<Vec<_>>());
+        assert_eq!(
+            states.await.unwrap(),
+            vec![
+                ActorState::Started,
+                ActorState::Running,
+                ActorState::Stopping,
+                ActorState::Stopped,
+            ]
+        );
+    });
+}
+
+struct Resource {
+    name: &'static str,
+    log: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Drop for Resource {
+    fn drop(&mut self) {
+        self.log.lock().unwrap().push(self.name);
+    }
+}
+
+struct ResourceActor {
+    log: Arc<Mutex<Vec<&'static str>>>,
+    terminate: bool,
+}
+
+impl ResourceActor {
+    fn resource(&self, name: &'static str) -> Resource {
+        Resource {
+            name,
+            log: Arc::clone(&self.log),
+        }
+    }
+}
+
+impl Actor for ResourceActor {
+    type Context = actix::Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.attach_resource(self.resource("first"));
+        let detached = ctx.attach_resource(self.resource("detached"));
+        ctx.attach_resource(self.resource("second"));
+
+        let res = detached.detach().unwrap();
+        assert_eq!(res.name, "detached");
+        self.log.lock().unwrap().push("detach");
+        drop(res);
+
+        if self.terminate {
+            ctx.terminate();
+        } else {
+            ctx.stop();
+        }
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.log.lock().unwrap().push("stopped");
+    }
+}
+
+#[test]
+fn test_attached_resources() {
+    for terminate in [false, true] {
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        System::new().block_on(async {
+            let addr = ResourceActor {
+                log: Arc::clone(&log),
+                terminate,
+            }
+            .start();
+
+            sleep(Duration::from_millis(10)).await;
+            assert!(!addr.connected());
+        });
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["detach", "detached", "stopped", "second", "first"]
+        );
+    }
+}
+
+#[test]
+fn test_detach_after_stop() {
+    System::new().block_on(async {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let (tx, rx) = channel();
+
+        ResourceActor::create(|ctx| {
+            let act = ResourceActor {
+                log: Arc::clone(&log),
+                terminate: false,
+            };
+            let _ = tx.send(ctx.attach_resource(act.resource("late")));
+            act
+        });
+
+        let handle = rx.await.unwrap();
+        sleep(Duration::from_millis(10)).await;
+        assert!(!handle.is_attached());
+        assert!(handle.detach().is_none());
+        assert!(log.lock().unwrap().ends_with(&["late"]));
+    });
+}
+
+struct InitActor {
+    log: Arc<Mutex<Vec<String>>>,
+    init: Option<Duration>,
+}
+
+impl Actor for InitActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.log.lock().unwrap().push("started".to_owned());
+
+        if let Some(init) = self.init {
+            ctx.buffer_until_ready();
+            assert!(!ctx.is_ready());
+            sleep(init)
+                .into_actor(self)
+                .map(|_, act, ctx| {
+                    act.log.lock().unwrap().push("ready".to_owned());
+                    ctx.set_ready();
+                })
+                .spawn(ctx);
+        }
+    }
+}
+
+struct Record(&'static str);
+
+impl Message for Record {
+    type Result = ();
+}
+
+impl Handler<Record> for InitActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: Record, _: &mut Self::Context) {
+        self.log.lock().unwrap().push(msg.0.to_owned());
+    }
+}
+
+#[test]
+fn test_started_before_messages() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    System::new().block_on(async {
+        let addr = InitActor::create(|ctx| {
+            ctx.address().do_send(Record("create"));
+            InitActor {
+                log: Arc::clone(&log),
+                init: None,
+            }
+        });
+
+        // sent from another thread while the actor is starting
+        let other = addr.clone();
+        std::thread::spawn(move || other.do_send(Record("thread")))
+            .join()
+            .unwrap();
+
+        addr.send(Record("send")).await.unwrap();
+    });
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        ["started", "create", "thread", "send"]
+    );
+}
+
+#[test]
+fn test_buffer_until_ready() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    System::new().block_on(async {
+        let addr = InitActor::create(|ctx| {
+            ctx.address().do_send(Record("create"));
+            InitActor {
+                log: Arc::clone(&log),
+                init: Some(Duration::from_millis(20)),
+            }
+        });
+
+        sleep(Duration::from_millis(10)).await;
+        addr.do_send(Record("during init"));
+        sleep(Duration::from_millis(1)).await;
+        assert_eq!(*log.lock().unwrap(), ["started"]);
+
+        addr.send(Record("send")).await.unwrap();
+    });
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        ["started", "ready", "create", "during init", "send"]
+    );
+}
+
+/// Actor performing random lifecycle operations on its own context.
+struct Chaos {
+    rng: u64,
+    steps: usize,
+    stopped: Option<Sender<()>>,
+}
+
+impl Chaos {
+    fn next(&mut self, n: u64) -> u64 {
+        // xorshift64
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng % n
+    }
+
+    fn step(&mut self, ctx: &mut Context<Self>) {
+        if self.steps == 0 {
+            return;
+        }
+        self.steps -= 1;
+
+        let before = ctx.state();
+        assert_ne!(before, ActorState::Started);
+        match self.next(6) {
+            0 => {
+                ctx.stop();
+                let expected = match before {
+                    ActorState::Running => ActorState::Stopping,
+                    state => state,
+                };
+                assert_eq!(ctx.state(), expected);
+            }
+            1 => {
+                if self.next(4) == 0 {
+                    ctx.terminate();
+                    assert_eq!(ctx.state(), ActorState::Stopped);
+                }
+            }
+            2 => {
+                ctx.spawn(fut::ready(()).map(|_, act: &mut Self, ctx| act.step(ctx)));
+            }
+            3 => {
+                let handle = ctx.run_later(Duration::ZERO, |act, ctx| act.step(ctx));
+                if self.next(2) == 0 {
+                    ctx.cancel_future(handle);
+                    ctx.notify(Step);
+                }
+            }
+            4 => ctx.notify(Step),
+            _ => {
+                ctx.wait(fut::ready(()).map(|_, act: &mut Self, ctx| act.step(ctx)));
+            }
+        }
+    }
+}
+
+impl Actor for Chaos {
+    type Context = Context<Self>;
+
+    fn stopping(&mut self, ctx: &mut Self::Context) -> Running {
+        assert_eq!(ctx.state(), ActorState::Stopping);
+        match self.next(4) {
+            0 | 1 if self.steps > 0 => {
+                ctx.notify(Step);
+                Running::Continue
+            }
+            // terminating overrides the returned value
+            2 => {
+                ctx.terminate();
+                Running::Continue
+            }
+            _ => Running::Stop,
+        }
+    }
+
+    fn stopped(&mut self, ctx: &mut Self::Context) {
+        assert_eq!(ctx.state(), ActorState::Stopped);
+        let _ = self.stopped.take().unwrap().send(());
+    }
+}
+
+struct Step;
+
+impl Message for Step {
+    type Result = ();
+}
+
+impl Handler<Step> for Chaos {
+    type Result = ();
+
+    fn handle(&mut self, _: Step, ctx: &mut Self::Context) {
+        self.step(ctx);
+        self.step(ctx);
+    }
+}
+
+#[test]
+fn test_random_lifecycle_operations() {
+    // illegal context state transitions panic in debug builds, the actor would never stop
+    for seed in 1..=200 {
+        System::new().block_on(async move {
+            let (tx, rx) = channel();
+            let addr = Chaos {
+                rng: seed,
+                steps: 50,
+                stopped: Some(tx),
+            }
+            .start();
+            addr.do_send(Step);
+            drop(addr);
+
+            actix_rt::time::timeout(Duration::from_secs(1), rx)
+                .await
+                .unwrap_or_else(|_| panic!("actor did not stop, seed {seed}"))
+                .unwrap_or_else(|_| panic!("actor panicked, seed {seed}"));
+        });
+    }
+}
+
+struct Finalizer {
+    log: Arc<Mutex<Vec<&'static str>>>,
+    flush: Duration,
+}
+
+impl Actor for Finalizer {
+    type Context = actix::Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.set_finalize_timeout(Duration::from_millis(50));
+    }
+
+    fn finalizing(&mut self, ctx: &mut Self::Context) -> Option<ResponseActFuture<Self, ()>> {
+        self.log.lock().unwrap().push("finalizing");
+
+        // nothing but the finalizer is polled
+        ctx.spawn(fut::wrap_future(async {}).map(|_, act: &mut Self, _| {
+            act.log.lock().unwrap().push("spawned");
+        }));
+
+        Some(Box::pin(sleep(self.flush).into_actor(self).map(
+            |_, act, ctx| {
+                assert_eq!(ctx.state(), ActorState::Stopping);
+                act.log.lock().unwrap().push("flushed");
+            },
+        )))
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.log.lock().unwrap().push("stopped");
+    }
+}
+
+impl Handler<StopRequest> for Finalizer {
+    type Result = ();
+
+    fn handle(&mut self, _: StopRequest, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+}
+
+#[test]
+fn test_finalizing() {
+    System::new().block_on(async {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let addr = Finalizer {
+            log: Arc::clone(&log),
+            flush: Duration::from_millis(10),
+        }
+        .start();
+        let states = addr.state_stream();
+
+        addr.send(StopRequest).await.unwrap();
+        sleep(Duration::from_millis(1)).await;
+        assert_eq!(*log.lock().unwrap(), ["finalizing"]);
+
+        // the mailbox is closed while finalizing
+        assert_eq!(addr.send(StopRequest).await, Err(MailboxError::Closed));
+        addr.closed().await;
+
+        assert_eq!(
+            states.collect::<Vec<_>>().await.last(),
+            Some(&ActorState::Stopped)
+        );
+        assert_eq!(*log.lock().unwrap(), ["finalizing", "flushed", "stopped"]);
+    });
+}
+
+#[test]
+fn test_finalizing_timeout() {
+    System::new().block_on(async {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let addr = Finalizer {
+            log: Arc::clone(&log),
+            flush: Duration::from_secs(10),
+        }
+        .start();
+        let states = addr.state_stream();
+
+        addr.do_send(StopRequest);
+        let states = actix_rt::time::timeout(Duration::from_secs(1), states.collect::<Vec<_>>())
+            .await
+            .unwrap();
+        assert_eq!(states.last(), Some(&ActorState::Stopped));
+        assert_eq!(*log.lock().unwrap(), ["finalizing", "stopped"]);
+    });
+}
+
+/// Spawned future logging its polls once the actor terminates, until it has polled `remaining`
+/// times.
+struct Cell {
+    name: &'static str,
+    remaining: usize,
+}
+
+impl ActorFuture<Terminator> for Cell {
+    type Output = ();
+
+    fn poll(
+        mut self: Pin<&mut Self>,
+        act: &mut Terminator,
+        ctx: &mut Context<Terminator>,
+        _: &mut task::Context<'_>,
+    ) -> Poll<()> {
+        if !ctx.terminating() {
+            return Poll::Pending;
+        }
+        act.log.lock().unwrap().push(self.name);
+        self.remaining -= 1;
+        if self.remaining == 0 {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+struct Terminator {
+    log: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Actor for Terminator {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.spawn(Cell {
+            name: "flushed",
+            remaining: 1,
+        });
+        ctx.spawn(Cell {
+            name: "stubborn",
+            remaining: usize::MAX,
+        });
+    }
+
+    fn stopping(&mut self, _: &mut Self::Context) -> Running {
+        self.log.lock().unwrap().push("stopping");
+        Running::Stop
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.log.lock().unwrap().push("stopped");
+    }
+}
+
+struct Terminate {
+    hard: bool,
+}
+
+impl Message for Terminate {
+    type Result = ();
+}
+
+impl Handler<Terminate> for Terminator {
+    type Result = ();
+
+    fn handle(&mut self, msg: Terminate, ctx: &mut Self::Context) {
+        if msg.hard {
+            ctx.terminate_hard();
+        } else {
+            ctx.terminate();
+        }
+        assert_eq!(ctx.state(), ActorState::Stopped);
+        assert_eq!(ctx.terminating(), !msg.hard);
+    }
+}
+
+struct Watcher {
+    exits: Arc<Mutex<Vec<ExitReason>>>,
+}
+
+impl Actor for Watcher {
+    type Context = Context<Self>;
+
+    fn linked_exit(&mut self, exit: LinkedExit, _: &mut Self::Context) {
+        self.exits.lock().unwrap().push(exit.reason);
+    }
+}
+
+#[test]
+fn test_terminate() {
+    System::new().block_on(async {
+        for hard in [false, true] {
+            let log = Arc::new(Mutex::new(Vec::new()));
+            let addr = Terminator {
+                log: Arc::clone(&log),
+            }
+            .start();
+            let states = addr.state_stream();
+            let exits = Arc::new(Mutex::new(Vec::new()));
+            let target = addr.clone();
+            let _watcher = Watcher::create(|ctx| {
+                ctx.link(&target);
+                Watcher {
+                    exits: Arc::clone(&exits),
+                }
+            });
+            sleep(Duration::from_millis(1)).await;
+
+            addr.do_send(Terminate { hard });
+            let states =
+                actix_rt::time::timeout(Duration::from_secs(1), states.collect::<Vec<_>>())
+                    .await
+                    .unwrap();
+            assert_eq!(states.last(), Some(&ActorState::Stopped));
+            sleep(Duration::from_millis(1)).await;
+            assert_eq!(*exits.lock().unwrap(), [ExitReason::Stopped]);
+
+            // each future is polled once, a pending one does not delay the termination
+            let expected: &[_] = if hard {
+                &["stopped"]
+            } else {
+                &["flushed", "stubborn", "stopped"]
+            };
+            assert_eq!(*log.lock().unwrap(), expected);
+        }
+    });
+}
+
+#[test]
+fn test_create_panicking_factory() {
+    System::new().block_on(async {
+        let addr = StopOnRequest::create(|_| panic!("factory failed"));
+        assert!(!addr.connected());
+        assert_eq!(addr.send(StopRequest).await, Err(MailboxError::Closed));
+        assert_eq!(
+            addr.state_stream().collect::<Vec<_>>().await,
+            vec![ActorState::Stopped]
+        );
+    });
+}
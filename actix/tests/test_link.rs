@@ -0,0 +1,147 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix::prelude::*;
+use actix_rt::time::sleep;
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+struct Peer {
+    name: &'static str,
+    log: Log,
+    trap: bool,
+}
+
+impl Peer {
+    fn new(name: &'static str, log: &Log) -> Self {
+        Peer {
+            name,
+            log: Arc::clone(log),
+            trap: false,
+        }
+    }
+}
+
+impl Actor for Peer {
+    type Context = Context<Self>;
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("{} stopped", self.name));
+    }
+
+    fn linked_exit(&mut self, exit: LinkedExit, ctx: &mut Self::Context) {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("{} exit {:?}", self.name, exit.reason));
+        if !self.trap {
+            ctx.stop();
+        }
+    }
+}
+
+struct Stop;
+
+impl Message for Stop {
+    type Result = ();
+}
+
+impl Handler<Stop> for Peer {
+    type Result = ();
+
+    fn handle(&mut self, _: Stop, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+}
+
+fn link(name: &'static str, log: &Log, other: &Addr<Peer>) -> Addr<Peer> {
+    let peer = Peer::new(name, log);
+    Peer::create(|ctx| {
+        ctx.link(other);
+        peer
+    })
+}
+
+#[actix::test]
+async fn test_link_stops_both_sides() {
+    // either side stopping stops the other
+    for stop_linked in [false, true] {
+        let log = Log::default();
+        let writer = Peer::new("writer", &log).start();
+        let reader = link("reader", &log, &writer);
+        sleep(Duration::from_millis(10)).await;
+
+        let (stopped, other) = if stop_linked {
+            (&reader, "writer")
+        } else {
+            (&writer, "reader")
+        };
+        stopped.do_send(Stop);
+        sleep(Duration::from_millis(10)).await;
+
+        assert!(!reader.connected() && !writer.connected());
+        let log = log.lock().unwrap();
+        assert_eq!(log.len(), 3);
+        assert!(log.contains(&format!("{other} exit Stopped")));
+    }
+}
+
+#[actix::test]
+async fn test_link_trap_exit() {
+    let log = Log::default();
+    let writer = Peer::new("writer", &log).start();
+    let reader = Peer::create(|ctx| {
+        ctx.link(&writer);
+        Peer {
+            trap: true,
+            ..Peer::new("reader", &log)
+        }
+    });
+    sleep(Duration::from_millis(10)).await;
+
+    writer.do_send(Stop);
+    sleep(Duration::from_millis(10)).await;
+    assert!(reader.connected());
+    assert_eq!(
+        *log.lock().unwrap(),
+        ["writer stopped", "reader exit Stopped"]
+    );
+}
+
+#[actix::test]
+async fn test_link_stopped_actor() {
+    let log = Log::default();
+    let writer = Peer::new("writer", &log).start();
+    writer.do_send(Stop);
+    while writer.connected() {
+        sleep(Duration::from_millis(1)).await;
+    }
+
+    let reader = link("reader", &log, &writer);
+    sleep(Duration::from_millis(10)).await;
+    assert!(!reader.connected());
+    assert_eq!(
+        *log.lock().unwrap(),
+        ["writer stopped", "reader exit NotRunning", "reader stopped"]
+    );
+}
+
+#[actix::test]
+async fn test_link_does_not_keep_alive() {
+    let log = Log::default();
+    let writer = Peer::new("writer", &log).start();
+    let reader = link("reader", &log, &writer);
+    sleep(Duration::from_millis(10)).await;
+
+    let closed = (writer.closed(), reader.closed());
+    drop((writer, reader));
+    closed.0.await;
+    closed.1.await;
+}
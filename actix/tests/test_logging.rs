@@ -0,0 +1,102 @@
+use std::sync::{Mutex, Once};
+
+use actix::prelude::*;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+static RECORDS: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+/// Collects records of this test's actors.
+struct TestLogger;
+
+impl Log for TestLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target().starts_with("test_logging")
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.enabled(record.metadata()) {
+            RECORDS
+                .lock()
+                .unwrap()
+                .push((record.target().to_owned(), record.args().to_string()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn init_logger() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        log::set_logger(&TestLogger).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+    });
+}
+
+struct Ping;
+
+impl Message for Ping {
+    type Result = ActorId;
+}
+
+struct Logger;
+
+impl Actor for Logger {
+    type Context = Context<Self>;
+}
+
+impl Handler<Ping> for Logger {
+    type Result = MessageResult<Ping>;
+
+    fn handle(&mut self, _: Ping, ctx: &mut Self::Context) -> Self::Result {
+        ctx.log().log(Level::Info, format_args!("ping"));
+        MessageResult(ctx.actor_id())
+    }
+}
+
+#[test]
+fn test_actor_ids() {
+    System::new().block_on(async {
+        let first = Logger.start().send(Ping).await.unwrap();
+        let second = Logger.start().send(Ping).await.unwrap();
+        assert_eq!(first.arbiter(), second.arbiter());
+        assert!(first.seq() < second.seq());
+
+        let arbiter = Arbiter::new();
+        let addr = Logger::start_in_arbiter(&arbiter.handle(), |_| Logger);
+        let other = addr.send(Ping).await.unwrap();
+        assert_ne!(first.arbiter(), other.arbiter());
+        assert_ne!(first, other);
+
+        arbiter.stop();
+    });
+}
+
+#[test]
+fn test_actor_log() {
+    init_logger();
+
+    System::new().block_on(async {
+        let addr = Logger.start();
+        let id = addr.send(Ping).await.unwrap();
+        drop(addr);
+        actix_rt::time::sleep(std::time::Duration::from_millis(10)).await;
+
+        let prefix = format!("actor_type=Logger actor_id={} arbiter=", id);
+        let records: Vec<_> = RECORDS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, msg)| msg.starts_with(&prefix))
+            .cloned()
+            .collect();
+
+        assert_eq!(records.len(), 3, "records: {:?}", records);
+        assert!(records
+            .iter()
+            .all(|(target, _)| target == "test_logging::Logger"));
+        assert!(records[0].1.ends_with(": starting"));
+        assert!(records[1].1.ends_with(": ping"));
+        assert!(records[2].1.ends_with(": stopped"));
+    });
+}
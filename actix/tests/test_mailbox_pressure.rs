@@ -0,0 +1,129 @@
+#![cfg(feature = "macros")]
+
+use std::time::Duration;
+
+use actix::{prelude::*, PressureLevel};
+use actix_rt::time::sleep;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Event {
+    Handled(usize, PressureLevel),
+    Pressure(PressureLevel),
+}
+
+#[derive(Default)]
+struct Ingest {
+    log: Vec<Event>,
+}
+
+impl Actor for Ingest {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.set_mailbox_watermarks(2, 5);
+    }
+
+    fn mailbox_pressure(&mut self, level: PressureLevel, _: &mut Self::Context) {
+        self.log.push(Event::Pressure(level));
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Work(usize);
+
+impl Handler<Work> for Ingest {
+    type Result = ();
+
+    fn handle(&mut self, Work(n): Work, ctx: &mut Self::Context) {
+        self.log
+            .push(Event::Handled(n, ctx.stats().mailbox_pressure));
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Vec<Event>")]
+struct TakeLog;
+
+impl Handler<TakeLog> for Ingest {
+    type Result = MessageResult<TakeLog>;
+
+    fn handle(&mut self, _: TakeLog, _: &mut Self::Context) -> Self::Result {
+        MessageResult(std::mem::take(&mut self.log))
+    }
+}
+
+async fn flood(addr: &Addr<Ingest>, n: usize) -> Vec<Event> {
+    for i in 0..n {
+        addr.do_send(Work(i));
+    }
+    sleep(Duration::from_millis(10)).await;
+    addr.send(TakeLog).await.unwrap()
+}
+
+#[actix::test]
+async fn test_mailbox_pressure_transitions() {
+    use Event::*;
+    use PressureLevel::*;
+
+    let addr = Ingest::default().start();
+    sleep(Duration::from_millis(10)).await;
+
+    // the pressure falls once no more than 2 messages are left
+    let log = flood(&addr, 8).await;
+    assert_eq!(
+        log,
+        [
+            Pressure(Rising),
+            Handled(0, Rising),
+            Handled(1, Rising),
+            Handled(2, Rising),
+            Handled(3, Rising),
+            Handled(4, Rising),
+            Handled(5, Falling),
+            Pressure(Falling),
+            Handled(6, Falling),
+            Handled(7, Falling),
+        ]
+    );
+
+    // staying below the high watermark is not reported
+    let log = flood(&addr, 4).await;
+    assert!(log.iter().all(|ev| matches!(ev, Handled(_, Falling))));
+
+    let log = flood(&addr, 5).await;
+    assert_eq!(log[0], Pressure(Rising));
+    assert_eq!(log.iter().filter(|ev| matches!(ev, Pressure(_))).count(), 2);
+}
+
+#[actix::test]
+async fn test_mailbox_pressure_disabled() {
+    struct Quiet(Vec<Event>);
+
+    impl Actor for Quiet {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            ctx.set_mailbox_watermarks(2, 5);
+            ctx.set_mailbox_watermarks(0, 0);
+        }
+
+        fn mailbox_pressure(&mut self, level: PressureLevel, _: &mut Self::Context) {
+            self.0.push(Event::Pressure(level));
+        }
+    }
+
+    impl Handler<TakeLog> for Quiet {
+        type Result = MessageResult<TakeLog>;
+
+        fn handle(&mut self, _: TakeLog, _: &mut Self::Context) -> Self::Result {
+            MessageResult(std::mem::take(&mut self.0))
+        }
+    }
+
+    let addr = Quiet(Vec::new()).start();
+    for _ in 0..10 {
+        addr.do_send(TakeLog);
+    }
+    assert!(addr.send(TakeLog).await.unwrap().is_empty());
+}
@@ -0,0 +1,126 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use actix::{
+    prelude::*,
+    utils::{AsyncMemo, Memo},
+};
+use actix_rt::time::sleep;
+
+struct Totals {
+    values: Vec<u64>,
+    total: Memo<Totals, u64>,
+    slow_total: AsyncMemo<Totals, u64>,
+}
+
+impl Totals {
+    fn new(computed: &Arc<AtomicUsize>) -> Self {
+        let (sync, slow) = (Arc::clone(computed), Arc::clone(computed));
+        Totals {
+            values: Vec::new(),
+            total: Memo::new(move |act: &Totals| {
+                sync.fetch_add(1, Ordering::SeqCst);
+                act.values.iter().sum()
+            }),
+            slow_total: AsyncMemo::new(move |act: &Totals| {
+                slow.fetch_add(1, Ordering::SeqCst);
+                let total = act.values.iter().sum();
+                async move {
+                    sleep(Duration::from_millis(20)).await;
+                    total
+                }
+            }),
+        }
+    }
+}
+
+impl Actor for Totals {
+    type Context = Context<Self>;
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Add(u64);
+
+impl Handler<Add> for Totals {
+    type Result = ();
+
+    fn handle(&mut self, Add(value): Add, _: &mut Self::Context) {
+        self.values.push(value);
+        self.total.invalidate();
+        self.slow_total.invalidate();
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "u64")]
+struct Total;
+
+impl Handler<Total> for Totals {
+    type Result = u64;
+
+    fn handle(&mut self, _: Total, _: &mut Self::Context) -> u64 {
+        *self.total.get(self)
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Option<u64>")]
+struct SlowTotal;
+
+impl Handler<SlowTotal> for Totals {
+    type Result = ResponseFuture<Option<u64>>;
+
+    fn handle(&mut self, _: SlowTotal, ctx: &mut Self::Context) -> Self::Result {
+        let total = self.slow_total.get(self, ctx);
+        Box::pin(async move { total.await.map(|total| *total) })
+    }
+}
+
+#[actix::test]
+async fn test_memo() {
+    let computed = Arc::new(AtomicUsize::new(0));
+    let addr = Totals::new(&computed).start();
+
+    addr.do_send(Add(2));
+    assert_eq!(addr.send(Total).await.unwrap(), 2);
+    assert_eq!(addr.send(Total).await.unwrap(), 2);
+    assert_eq!(computed.load(Ordering::SeqCst), 1);
+
+    addr.do_send(Add(3));
+    assert_eq!(addr.send(Total).await.unwrap(), 5);
+    assert_eq!(computed.load(Ordering::SeqCst), 2);
+}
+
+#[actix::test]
+async fn test_async_memo() {
+    let computed = Arc::new(AtomicUsize::new(0));
+    let addr = Totals::new(&computed).start();
+    addr.do_send(Add(2));
+
+    // concurrent queries wait for the same computation
+    let queries: Vec<_> = (0..3).map(|_| addr.send(SlowTotal)).collect();
+    for query in queries {
+        assert_eq!(query.await.unwrap(), Some(2));
+    }
+    assert_eq!(addr.send(SlowTotal).await.unwrap(), Some(2));
+    assert_eq!(computed.load(Ordering::SeqCst), 1);
+
+    // a value invalidated while it is computed is passed to its queries, but not kept
+    addr.do_send(Add(3));
+    let stale = addr.send(SlowTotal);
+    sleep(Duration::from_millis(5)).await;
+    addr.do_send(Add(4));
+    let fresh = addr.send(SlowTotal);
+    assert_eq!(stale.await.unwrap(), Some(5));
+    assert_eq!(fresh.await.unwrap(), Some(9));
+    assert_eq!(addr.send(SlowTotal).await.unwrap(), Some(9));
+    assert_eq!(computed.load(Ordering::SeqCst), 3);
+}
@@ -0,0 +1,164 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use actix::{prelude::*, DeadLetterReason, MessageMiddleware, Rejection, SystemExt};
+
+#[derive(Message)]
+#[rtype(result = "usize")]
+struct Query(&'static str);
+
+#[derive(Message)]
+#[rtype(result = "usize")]
+struct SlowQuery;
+
+struct Db {
+    handled: Arc<AtomicUsize>,
+}
+
+impl Actor for Db {
+    type Context = Context<Self>;
+}
+
+impl Handler<Query> for Db {
+    type Result = usize;
+
+    fn handle(&mut self, query: Query, _: &mut Self::Context) -> usize {
+        self.handled.fetch_add(1, Ordering::SeqCst);
+        query.0.len()
+    }
+}
+
+impl Handler<SlowQuery> for Db {
+    type Result = ResponseFuture<usize>;
+
+    fn handle(&mut self, _: SlowQuery, _: &mut Self::Context) -> Self::Result {
+        Box::pin(async {
+            actix_rt::time::sleep(Duration::from_millis(20)).await;
+            7
+        })
+    }
+}
+
+#[derive(Default)]
+struct Recorder {
+    results: Mutex<Vec<(usize, Duration)>>,
+}
+
+struct Record(Arc<Recorder>);
+
+impl<M: Message<Result = usize>> MessageMiddleware<M> for Record {
+    fn after(&self, result: &usize, elapsed: Duration) {
+        self.0.results.lock().unwrap().push((*result, elapsed));
+    }
+}
+
+struct RejectEmpty;
+
+impl MessageMiddleware<Query> for RejectEmpty {
+    fn before(&self, query: &Query) -> Option<Rejection> {
+        query.0.is_empty().then(|| Rejection::new("empty query"))
+    }
+}
+
+fn start_db() -> (Addr<Db>, Arc<AtomicUsize>) {
+    let handled = Arc::new(AtomicUsize::new(0));
+    let addr = Db {
+        handled: Arc::clone(&handled),
+    }
+    .start();
+    (addr, handled)
+}
+
+#[actix::test]
+async fn test_after_sees_results() {
+    let recorder = Arc::new(Recorder::default());
+    System::current().register_message_middleware::<Query>(Box::new(Record(Arc::clone(&recorder))));
+
+    let (addr, handled) = start_db();
+    assert_eq!(addr.send(Query("select")).await.unwrap(), 6);
+    addr.do_send(Query("ab"));
+    assert_eq!(addr.send(Query("a")).await.unwrap(), 1);
+
+    assert_eq!(handled.load(Ordering::SeqCst), 3);
+    let results: Vec<_> = recorder
+        .results
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|r| r.0)
+        .collect();
+    assert_eq!(results, [6, 2, 1]);
+}
+
+#[actix::test]
+async fn test_after_sees_async_results() {
+    let recorder = Arc::new(Recorder::default());
+    System::current()
+        .register_message_middleware::<SlowQuery>(Box::new(Record(Arc::clone(&recorder))));
+
+    let (addr, _) = start_db();
+    assert_eq!(addr.send(SlowQuery).await.unwrap(), 7);
+
+    let results = recorder.results.lock().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].0, 7);
+    assert!(results[0].1 >= Duration::from_millis(20));
+}
+
+#[actix::test]
+async fn test_rejection() {
+    let recorder = Arc::new(Recorder::default());
+    let sys = System::current();
+    sys.register_message_middleware::<Query>(Box::new(RejectEmpty));
+    sys.register_message_middleware::<Query>(Box::new(Record(Arc::clone(&recorder))));
+
+    let (addr, handled) = start_db();
+    match addr.send(Query("")).await {
+        Err(MailboxError::Rejected(rejection)) => assert_eq!(rejection.reason(), "empty query"),
+        res => panic!("unexpected result: {:?}", res.map(|_| ())),
+    }
+    match addr.try_send(Query("")) {
+        Err(SendError::Rejected(_, rejection)) => assert_eq!(rejection.reason(), "empty query"),
+        res => panic!("unexpected result: {:?}", res),
+    }
+    addr.do_send(Query(""));
+    assert_eq!(addr.send(Query("abc")).await.unwrap(), 3);
+
+    // rejected messages never reach the handler or the following middleware
+    assert_eq!(handled.load(Ordering::SeqCst), 1);
+    assert_eq!(recorder.results.lock().unwrap().len(), 1);
+
+    let records = sys.dead_letters();
+    assert_eq!(records.len(), 3);
+    assert!(records
+        .iter()
+        .all(|r| r.reason == DeadLetterReason::Rejected));
+}
+
+#[actix::test]
+async fn test_middleware_is_per_system() {
+    System::current().register_message_middleware::<Query>(Box::new(RejectEmpty));
+
+    let other = std::thread::spawn(|| {
+        System::new().block_on(async {
+            let (addr, _) = start_db();
+            addr.send(Query("")).await.unwrap()
+        })
+    })
+    .join()
+    .unwrap();
+    assert_eq!(other, 0);
+
+    let (addr, _) = start_db();
+    assert!(matches!(
+        addr.send(Query("")).await,
+        Err(MailboxError::Rejected(_))
+    ));
+}
@@ -1,9 +1,13 @@
 #![cfg(feature = "macros")]
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet, VecDeque};
 
-use actix::prelude::*;
+use actix::{
+    dev::{to_envelope, EnvelopeProxy, TestEnvelopeSink},
+    prelude::*,
+};
 
+#[allow(dead_code)]
 #[derive(Clone, Debug)]
 struct Num(usize);
 
@@ -90,6 +94,62 @@ impl Handler<GetSessionById> for SessionActor {
     }
 }
 
+#[derive(Message)]
+#[rtype(result = "BTreeMap<usize, char>")]
+struct GetSessionNames;
+
+impl Handler<GetSessionNames> for SessionActor {
+    type Result = BTreeMap<usize, char>;
+
+    fn handle(&mut self, _: GetSessionNames, _: &mut Context<Self>) -> Self::Result {
+        self.sessions
+            .iter()
+            .map(|&id| (id, char::from(b'a' + id as u8)))
+            .collect()
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "VecDeque<Box<usize>>")]
+struct GetBoxedSessions;
+
+impl Handler<GetBoxedSessions> for SessionActor {
+    type Result = VecDeque<Box<usize>>;
+
+    fn handle(&mut self, _: GetBoxedSessions, _: &mut Context<Self>) -> Self::Result {
+        let mut sessions = self.sessions();
+        sessions.sort_unstable();
+        sessions.into_iter().map(Box::new).collect()
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "char")]
+struct GetInitial(usize);
+
+impl Handler<GetInitial> for SessionActor {
+    type Result = char;
+
+    fn handle(&mut self, msg: GetInitial, _: &mut Context<Self>) -> Self::Result {
+        char::from(b'a' + msg.0 as u8)
+    }
+}
+
+#[actix::test]
+async fn test_std_message_result_types() {
+    let actor = SessionActor::new().start();
+    actor.send(AddSession(2)).await.unwrap().unwrap();
+    actor.send(AddSession(1)).await.unwrap().unwrap();
+
+    let names = actor.send(GetSessionNames).await.unwrap();
+    assert_eq!(names, BTreeMap::from([(1, 'b'), (2, 'c')]));
+
+    let boxed = actor.send(GetBoxedSessions).await.unwrap();
+    assert_eq!(boxed, [Box::new(1), Box::new(2)]);
+
+    assert_eq!(actor.send(GetInitial(0)).await.unwrap(), 'a');
+}
+
 #[actix::test]
 async fn test_different_message_result_types() {
     let actor = SessionActor::new().start();
@@ -138,3 +198,25 @@ async fn test_different_message_result_types() {
         "Session with id `1` should already have been inserted"
     );
 }
+
+#[test]
+fn test_handler_without_system() {
+    let mut actor = SessionActor::new();
+    let mut ctx = Context::new();
+
+    let (mut env, mut sink) = TestEnvelopeSink::envelope(AddSession(1));
+    env.handle(&mut actor, &mut ctx);
+    assert_eq!(sink.try_recv(), Some(Ok(1)));
+
+    let (mut env, mut sink) = TestEnvelopeSink::envelope(AddSession(1));
+    env.handle(&mut actor, &mut ctx);
+    assert!(sink.try_recv().unwrap().is_err());
+
+    // fire and forget
+    let mut env = to_envelope(AddSession(2), None);
+    env.handle(&mut actor, &mut ctx);
+
+    let (mut env, mut sink) = TestEnvelopeSink::envelope(GetSessionCount);
+    env.handle(&mut actor, &mut ctx);
+    assert_eq!(sink.try_recv(), Some(2));
+}
@@ -0,0 +1,205 @@
+#![cfg(feature = "macros")]
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use actix::{fut, prelude::*, MinimalContext};
+use futures_util::{FutureExt as _, StreamExt as _};
+
+#[derive(Default)]
+struct Counts {
+    started: AtomicUsize,
+    stopping: AtomicUsize,
+    stopped: AtomicUsize,
+    events: AtomicUsize,
+}
+
+struct Slim {
+    counts: Arc<Counts>,
+    /// Number of times `stopping()` keeps the actor running.
+    refuse: usize,
+}
+
+impl Actor for Slim {
+    type Context = MinimalContext<Self>;
+
+    fn started(&mut self, _: &mut Self::Context) {
+        self.counts.started.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn stopping(&mut self, _: &mut Self::Context) -> Running {
+        self.counts.stopping.fetch_add(1, Ordering::SeqCst);
+        if self.refuse > 0 {
+            self.refuse -= 1;
+            Running::Continue
+        } else {
+            Running::Stop
+        }
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.counts.stopped.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "usize")]
+struct Ping;
+
+impl Handler<Ping> for Slim {
+    type Result = usize;
+
+    fn handle(&mut self, _: Ping, ctx: &mut Self::Context) -> usize {
+        assert_eq!(ctx.state(), ActorState::Running);
+        self.counts.events.fetch_add(1, Ordering::SeqCst)
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Stop;
+
+impl Handler<Stop> for Slim {
+    type Result = ();
+
+    fn handle(&mut self, _: Stop, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+}
+
+#[derive(Message, Clone)]
+#[rtype(result = "()")]
+struct Event;
+
+impl Handler<Event> for Slim {
+    type Result = ();
+
+    fn handle(&mut self, _: Event, _: &mut Self::Context) {
+        self.counts.events.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn slim(counts: &Arc<Counts>, refuse: usize) -> Addr<Slim> {
+    MinimalContext::new().run(Slim {
+        counts: Arc::clone(counts),
+        refuse,
+    })
+}
+
+#[actix::test]
+async fn test_lifecycle() {
+    let counts = Arc::new(Counts::default());
+    let addr = slim(&counts, 1);
+    let mut states = addr.state_stream();
+
+    assert_eq!(addr.send(Ping).await.unwrap(), 0);
+    assert_eq!(addr.send(Ping).await.unwrap(), 1);
+    assert_eq!(counts.started.load(Ordering::SeqCst), 1);
+
+    // the first stop is refused, the actor keeps handling messages
+    addr.send(Stop).await.unwrap();
+    assert_eq!(addr.send(Ping).await.unwrap(), 2);
+    addr.do_send(Stop);
+    addr.closed().await;
+    assert_eq!(counts.stopping.load(Ordering::SeqCst), 2);
+    assert_eq!(counts.stopped.load(Ordering::SeqCst), 1);
+
+    let mut seen = Vec::new();
+    while let Some(state) = states.next().await {
+        seen.push(state);
+    }
+    assert_eq!(seen[..2], [ActorState::Started, ActorState::Running]);
+    assert_eq!(seen.last(), Some(&ActorState::Stopped));
+    assert!(seen.contains(&ActorState::Stopping));
+}
+
+#[actix::test]
+async fn test_stops_without_addresses() {
+    let counts = Arc::new(Counts::default());
+    let addr = slim(&counts, 0);
+    addr.send(Ping).await.unwrap();
+    let weak = addr.downgrade();
+    let mut states = addr.state_stream();
+    drop(addr);
+
+    while states.next().await.is_some() {}
+    assert!(weak.upgrade().is_none());
+    assert_eq!(counts.stopped.load(Ordering::SeqCst), 1);
+}
+
+/// A `Context` based publisher with subscribers running in minimal contexts.
+struct Publisher {
+    subscribers: Vec<Recipient<Event>>,
+}
+
+impl Actor for Publisher {
+    type Context = Context<Self>;
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Subscribe(Recipient<Event>);
+
+impl Handler<Subscribe> for Publisher {
+    type Result = ();
+
+    fn handle(&mut self, Subscribe(recipient): Subscribe, _: &mut Self::Context) {
+        self.subscribers.push(recipient);
+    }
+}
+
+impl Handler<Event> for Publisher {
+    type Result = ResponseFuture<()>;
+
+    fn handle(&mut self, event: Event, _: &mut Self::Context) -> Self::Result {
+        let sends: Vec<_> = self
+            .subscribers
+            .iter()
+            .map(|subscriber| subscriber.send(event.clone()))
+            .collect();
+        Box::pin(async move {
+            for send in sends {
+                send.await.unwrap();
+            }
+        })
+    }
+}
+
+#[actix::test]
+async fn test_subscribers() {
+    let counts = Arc::new(Counts::default());
+    let publisher = Publisher {
+        subscribers: Vec::new(),
+    }
+    .start();
+
+    let subscribers: Vec<_> = (0..100).map(|_| slim(&counts, 0)).collect();
+    for subscriber in &subscribers {
+        publisher
+            .send(Subscribe(subscriber.clone().recipient()))
+            .await
+            .unwrap();
+    }
+    publisher.send(Event).await.unwrap();
+    publisher.send(Event).await.unwrap();
+    assert_eq!(counts.events.load(Ordering::SeqCst), 200);
+    assert_eq!(counts.started.load(Ordering::SeqCst), 100);
+}
+
+struct Spawner;
+
+impl Actor for Spawner {
+    type Context = MinimalContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.spawn(fut::ready(()));
+    }
+}
+
+#[test]
+#[should_panic(expected = "MinimalContext::spawn() is not supported")]
+fn test_spawn_panics() {
+    let _ = MinimalContext::new().into_future(Spawner).now_or_never();
+}
@@ -0,0 +1,178 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    any::type_name,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix::{prelude::*, ExitReason, PanicSource};
+use actix_rt::time::sleep;
+
+/// Sources reported by the panicked worker and by its linked watcher.
+#[derive(Default)]
+struct Reports {
+    worker: Vec<Option<PanicSource>>,
+    watcher: Vec<(ExitReason, Option<PanicSource>)>,
+}
+
+type Shared = Arc<Mutex<Reports>>;
+
+struct Worker(Shared);
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+
+    fn handler_panicked(&mut self, report: &PanicReport) {
+        assert_eq!(report.actor(), type_name::<Worker>());
+        assert!(report.started_at().is_some() == report.source().is_some());
+        self.0.lock().unwrap().worker.push(report.source());
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Crash;
+
+impl Handler<Crash> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: Crash, _: &mut Self::Context) {
+        panic!("crash");
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct CrashLater;
+
+impl Handler<CrashLater> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: CrashLater, ctx: &mut Self::Context) {
+        ctx.set_timer("crash", Duration::from_millis(10), Crash);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct CrashInFuture;
+
+impl Handler<CrashInFuture> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: CrashInFuture, ctx: &mut Self::Context) {
+        ctx.run_later(Duration::from_millis(10), |_, _| panic!("crash"));
+    }
+}
+
+struct Watcher {
+    shared: Shared,
+    worker: Option<Addr<Worker>>,
+}
+
+impl Actor for Watcher {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let worker = Worker(Arc::clone(&self.shared)).start();
+        ctx.link(&worker);
+        self.worker = Some(worker);
+    }
+
+    fn linked_exit(&mut self, exit: LinkedExit, _: &mut Self::Context) {
+        let source = exit.panic.as_ref().and_then(|report| report.source());
+        self.shared
+            .lock()
+            .unwrap()
+            .watcher
+            .push((exit.reason, source));
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Addr<Worker>")]
+struct GetWorker;
+
+impl Handler<GetWorker> for Watcher {
+    type Result = MessageResult<GetWorker>;
+
+    fn handle(&mut self, _: GetWorker, _: &mut Self::Context) -> Self::Result {
+        MessageResult(self.worker.clone().unwrap())
+    }
+}
+
+async fn crash_with<M>(msg: M) -> Reports
+where
+    M: Message<Result = ()> + Send + 'static,
+    Worker: Handler<M>,
+{
+    let shared = Shared::default();
+    let watcher = Watcher {
+        shared: Arc::clone(&shared),
+        worker: None,
+    }
+    .start();
+    let worker = watcher.send(GetWorker).await.unwrap();
+
+    worker.do_send(msg);
+    sleep(Duration::from_millis(100)).await;
+
+    let mut shared = shared.lock().unwrap();
+    std::mem::take(&mut *shared)
+}
+
+#[actix::test]
+async fn test_panic_attributed_to_message() {
+    let reports = crash_with(Crash).await;
+
+    let source = Some(PanicSource::Message(type_name::<Crash>()));
+    assert_eq!(reports.worker, [source]);
+    assert_eq!(reports.watcher, [(ExitReason::Panicked, source)]);
+}
+
+#[actix::test]
+async fn test_panic_attributed_to_timer() {
+    // the message setting the timer is handled before the timer fires
+    let reports = crash_with(CrashLater).await;
+
+    let source = Some(PanicSource::Timer("crash"));
+    assert_eq!(reports.worker, [source]);
+    assert_eq!(reports.watcher, [(ExitReason::Panicked, source)]);
+}
+
+#[actix::test]
+async fn test_panic_without_attribution() {
+    let reports = crash_with(CrashInFuture).await;
+
+    assert_eq!(reports.worker, [None]);
+    assert_eq!(reports.watcher, [(ExitReason::Panicked, None)]);
+}
+
+#[cfg(feature = "backtrace")]
+#[actix::test]
+async fn test_panic_backtrace() {
+    struct Traced(Arc<Mutex<Option<String>>>);
+
+    impl Actor for Traced {
+        type Context = Context<Self>;
+
+        fn handler_panicked(&mut self, report: &PanicReport) {
+            *self.0.lock().unwrap() = report.backtrace().map(|bt| bt.to_string());
+        }
+    }
+
+    impl Handler<Crash> for Traced {
+        type Result = ();
+
+        fn handle(&mut self, _: Crash, _: &mut Self::Context) {
+            panic!("crash");
+        }
+    }
+
+    let backtrace = Arc::default();
+    Traced(Arc::clone(&backtrace)).start().do_send(Crash);
+    sleep(Duration::from_millis(50)).await;
+
+    assert!(backtrace.lock().unwrap().is_some());
+}
@@ -0,0 +1,154 @@
+#![cfg(feature = "macros")]
+
+use std::time::Duration;
+
+use actix::prelude::*;
+use actix_rt::time::sleep;
+
+#[derive(Message)]
+#[rtype(result = "u32")]
+struct Event {
+    key: u64,
+    seq: u32,
+    work: Duration,
+}
+
+impl Keyed for Event {
+    fn key(&self) -> u64 {
+        self.key
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Report")]
+struct GetReport;
+
+#[derive(Debug, Default, MessageResponse)]
+struct Report {
+    /// Started and completed handlers, with their key and sequence number.
+    log: Vec<(u64, u32, bool)>,
+    max_in_flight: usize,
+    lanes: u64,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Stop;
+
+#[derive(Default)]
+struct Ledger {
+    log: Vec<(u64, u32, bool)>,
+    in_flight: usize,
+    max_in_flight: usize,
+}
+
+impl Actor for Ledger {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.enable_partitioning::<Event>(2, Duration::from_millis(20));
+    }
+}
+
+impl Handler<Event> for Ledger {
+    type Result = ResponseActFuture<Self, u32>;
+
+    fn handle(&mut self, event: Event, _: &mut Self::Context) -> Self::Result {
+        let Event { key, seq, work } = event;
+        self.log.push((key, seq, false));
+        self.in_flight += 1;
+        self.max_in_flight = self.max_in_flight.max(self.in_flight);
+
+        Box::pin(sleep(work).into_actor(self).map(move |_, act, _| {
+            act.log.push((key, seq, true));
+            act.in_flight -= 1;
+            seq
+        }))
+    }
+}
+
+impl Handler<GetReport> for Ledger {
+    type Result = Report;
+
+    fn handle(&mut self, _: GetReport, ctx: &mut Self::Context) -> Report {
+        Report {
+            log: self.log.clone(),
+            max_in_flight: self.max_in_flight,
+            lanes: ctx.stats().partition_lanes,
+        }
+    }
+}
+
+impl Handler<Stop> for Ledger {
+    type Result = ();
+
+    fn handle(&mut self, _: Stop, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+}
+
+fn event(key: u64, seq: u32, millis: u64) -> Event {
+    Event {
+        key,
+        seq,
+        work: Duration::from_millis(millis),
+    }
+}
+
+#[actix::test]
+async fn test_partition_order() {
+    let addr = Ledger::default().start();
+
+    let mut replies = Vec::new();
+    for seq in 0..4 {
+        for key in 1..=3 {
+            replies.push(addr.send(event(key, seq, 5 + key * 3)));
+        }
+    }
+    for (idx, reply) in replies.into_iter().enumerate() {
+        assert_eq!(reply.await.unwrap(), idx as u32 / 3);
+    }
+
+    let report = addr.send(GetReport).await.unwrap();
+    assert_eq!(report.max_in_flight, 2);
+    for key in 1..=3 {
+        // messages of a key start once the previous one has completed
+        let lane: Vec<_> = report
+            .log
+            .iter()
+            .filter(|(k, _, _)| *k == key)
+            .map(|(_, seq, done)| (*seq, *done))
+            .collect();
+        let expected: Vec<_> = (0..4).flat_map(|seq| [(seq, false), (seq, true)]).collect();
+        assert_eq!(lane, expected);
+    }
+}
+
+#[actix::test]
+async fn test_partition_idle_lanes() {
+    let addr = Ledger::default().start();
+
+    for key in 1..=3 {
+        addr.send(event(key, 0, 0)).await.unwrap();
+    }
+    assert_eq!(addr.send(GetReport).await.unwrap().lanes, 3);
+
+    sleep(Duration::from_millis(50)).await;
+    addr.send(event(4, 0, 0)).await.unwrap();
+    assert_eq!(addr.send(GetReport).await.unwrap().lanes, 1);
+}
+
+#[actix::test]
+async fn test_partition_stop() {
+    let addr = Ledger::default().start();
+
+    let first = addr.send(event(1, 0, 50));
+    let second = addr.send(event(1, 1, 0));
+    let other = addr.send(event(2, 0, 0));
+    sleep(Duration::from_millis(10)).await;
+    addr.do_send(Stop);
+
+    assert_eq!(other.await, Ok(0));
+    assert_eq!(first.await, Err(MailboxError::Closed));
+    assert_eq!(second.await, Err(MailboxError::Closed));
+}
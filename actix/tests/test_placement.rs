@@ -0,0 +1,155 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    collections::HashMap,
+    thread::{self, ThreadId},
+};
+
+use actix::{prelude::*, ArbiterHandleExt, PlacementStrategy, SystemExt};
+
+struct Worker;
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+}
+
+#[derive(Message)]
+#[rtype(result = "ThreadId")]
+struct WhereAreYou;
+
+impl Handler<WhereAreYou> for Worker {
+    type Result = MessageResult<WhereAreYou>;
+
+    fn handle(&mut self, _: WhereAreYou, _: &mut Self::Context) -> Self::Result {
+        MessageResult(thread::current().id())
+    }
+}
+
+async fn start_on(strategy: PlacementStrategy) -> ThreadId {
+    let addr = System::current()
+        .placement()
+        .start(strategy, |_| Worker)
+        .await
+        .unwrap();
+    addr.send(WhereAreYou).await.unwrap()
+}
+
+async fn arbiter_thread(arbiter: &ArbiterHandle) -> ThreadId {
+    arbiter
+        .spawn_actor(|_| Worker)
+        .await
+        .unwrap()
+        .send(WhereAreYou)
+        .await
+        .unwrap()
+}
+
+#[actix::test]
+async fn test_empty_placement_uses_system_arbiter() {
+    let placement = System::current().placement();
+    assert!(placement.is_empty());
+    assert_eq!(
+        start_on(PlacementStrategy::RoundRobin).await,
+        thread::current().id()
+    );
+}
+
+#[actix::test]
+async fn test_round_robin() {
+    let placement = System::current().placement();
+    let arbiters: Vec<_> = (0..3).map(|_| Arbiter::new().handle()).collect();
+    for arbiter in &arbiters {
+        placement.add(arbiter);
+    }
+    assert_eq!(placement.len(), 3);
+
+    let mut counts = HashMap::new();
+    for _ in 0..6 {
+        *counts
+            .entry(start_on(PlacementStrategy::RoundRobin).await)
+            .or_insert(0) += 1;
+    }
+    assert_eq!(counts.len(), 3);
+    assert!(counts.values().all(|count| *count == 2));
+    for arbiter in &arbiters {
+        assert_eq!(counts[&arbiter_thread(arbiter).await], 2);
+    }
+}
+
+#[actix::test]
+async fn test_least_actors() {
+    let placement = System::current().placement();
+    let busy = Arbiter::new().handle();
+    let idle = Arbiter::new().handle();
+    placement.add(&busy);
+    placement.add(&idle);
+    let mut residents = Vec::new();
+    for _ in 0..3 {
+        residents.push(busy.spawn_actor(|_| Worker).await.unwrap());
+    }
+
+    let idle_thread = arbiter_thread(&idle).await;
+    assert_eq!(start_on(PlacementStrategy::LeastActors).await, idle_thread);
+}
+
+#[actix::test]
+async fn test_by_key_is_consistent() {
+    let placement = System::current().placement();
+    for _ in 0..3 {
+        placement.add(&Arbiter::new().handle());
+    }
+
+    let mut placed = Vec::new();
+    for key in 0..64 {
+        let thread = start_on(PlacementStrategy::ByKey(key)).await;
+        assert_eq!(start_on(PlacementStrategy::ByKey(key)).await, thread);
+        placed.push(thread);
+    }
+    let mut threads = placed.clone();
+    threads.sort_unstable_by_key(|thread| format!("{:?}", thread));
+    threads.dedup();
+    assert_eq!(threads.len(), 3, "keys are spread over the arbiters");
+
+    // a joining arbiter only takes over keys, the others stay where they were
+    let joined = Arbiter::new().handle();
+    placement.add(&joined);
+    let joined = arbiter_thread(&joined).await;
+    let mut moved = 0;
+    for (key, thread) in placed.into_iter().enumerate() {
+        let now = start_on(PlacementStrategy::ByKey(key as u64)).await;
+        if now == joined {
+            moved += 1;
+        } else {
+            assert_eq!(now, thread);
+        }
+    }
+    assert!(moved > 0 && moved < 64);
+}
+
+#[actix::test]
+async fn test_stopped_arbiters_leave() {
+    let placement = System::current().placement();
+    let stopped = Arbiter::new();
+    let running = Arbiter::new().handle();
+    placement.add(&stopped.handle());
+    placement.add(&running);
+    assert_eq!(placement.len(), 2);
+
+    stopped.stop();
+    stopped.join().unwrap();
+    assert_eq!(placement.len(), 1);
+
+    let running = arbiter_thread(&running).await;
+    for key in 0..8 {
+        assert_eq!(start_on(PlacementStrategy::ByKey(key)).await, running);
+        assert_eq!(start_on(PlacementStrategy::RoundRobin).await, running);
+    }
+
+    // arbiters which have already stopped do not join
+    let arbiter = Arbiter::new();
+    let handle = arbiter.handle();
+    arbiter.stop();
+    arbiter.join().unwrap();
+    placement.add(&handle);
+    assert_eq!(placement.len(), 1);
+}
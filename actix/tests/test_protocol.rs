@@ -0,0 +1,102 @@
+#![cfg(feature = "macros")]
+
+use actix::{prelude::*, protocol, ProtoAddr, ProtocolEnd};
+
+mod msgs {
+    use actix::Message;
+
+    #[derive(Message)]
+    #[rtype(result = "u32")]
+    pub struct Hello;
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<(), &'static str>")]
+struct Auth(&'static str);
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Data(u32);
+
+protocol!(
+    /// Handshake of a session.
+    pub type Session = [msgs::Hello -> u32, Auth -> Result<(), &'static str>, Data -> ()]
+);
+
+#[derive(Default)]
+struct Server {
+    received: Vec<u32>,
+}
+
+impl Actor for Server {
+    type Context = Context<Self>;
+}
+
+impl Handler<msgs::Hello> for Server {
+    type Result = u32;
+
+    fn handle(&mut self, _: msgs::Hello, _: &mut Self::Context) -> u32 {
+        1
+    }
+}
+
+impl Handler<Auth> for Server {
+    type Result = Result<(), &'static str>;
+
+    fn handle(&mut self, auth: Auth, ctx: &mut Self::Context) -> Self::Result {
+        if auth.0 == "secret" {
+            Ok(())
+        } else {
+            ctx.stop();
+            Err("denied")
+        }
+    }
+}
+
+impl Handler<Data> for Server {
+    type Result = ();
+
+    fn handle(&mut self, data: Data, _: &mut Self::Context) {
+        self.received.push(data.0);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Vec<u32>")]
+struct Received;
+
+impl Handler<Received> for Server {
+    type Result = MessageResult<Received>;
+
+    fn handle(&mut self, _: Received, _: &mut Self::Context) -> Self::Result {
+        MessageResult(self.received.clone())
+    }
+}
+
+#[actix::test]
+async fn test_protocol_in_order() {
+    let session = ProtoAddr::<_, Session>::new(Server::default().start());
+
+    let (version, session) = session.send(msgs::Hello).await.unwrap();
+    assert_eq!(version, 1);
+    let (auth, session) = session.send(Auth("secret")).await.unwrap();
+    assert_eq!(auth, Ok(()));
+    let ((), done): ((), ProtoAddr<Server, ProtocolEnd>) = session.send(Data(7)).await.unwrap();
+
+    let addr = done.into_inner();
+    assert_eq!(addr.send(Received).await.unwrap(), [7]);
+}
+
+#[actix::test]
+async fn test_protocol_stopped_actor() {
+    let session = ProtoAddr::<_, Session>::new(Server::default().start());
+
+    let (_, session) = session.send(msgs::Hello).await.unwrap();
+    let (auth, session) = session.send(Auth("guess")).await.unwrap();
+    assert_eq!(auth, Err("denied"));
+    session.addr().closed().await;
+    assert_eq!(
+        session.send(Data(7)).await.unwrap_err(),
+        MailboxError::Closed
+    );
+}
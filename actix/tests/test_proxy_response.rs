@@ -0,0 +1,143 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use actix::{clock::sleep, prelude::*};
+
+struct Backend {
+    handled: Arc<AtomicUsize>,
+}
+
+impl Actor for Backend {
+    type Context = Context<Self>;
+}
+
+#[derive(Message)]
+#[rtype(result = "u32")]
+struct Lookup(u32);
+
+impl Handler<Lookup> for Backend {
+    type Result = u32;
+
+    fn handle(&mut self, msg: Lookup, _: &mut Self::Context) -> u32 {
+        self.handled.fetch_add(1, Ordering::SeqCst);
+        msg.0 * 2
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Pause(Duration);
+
+impl Handler<Pause> for Backend {
+    type Result = ();
+
+    fn handle(&mut self, msg: Pause, ctx: &mut Self::Context) {
+        ctx.wait(sleep(msg.0).into_actor(self));
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Stop;
+
+impl Handler<Stop> for Backend {
+    type Result = ();
+
+    fn handle(&mut self, _: Stop, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+}
+
+struct Gateway {
+    backend: Addr<Backend>,
+    mapped: usize,
+}
+
+impl Actor for Gateway {
+    type Context = Context<Self>;
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<String, MailboxError>")]
+struct Get(u32);
+
+impl Handler<Get> for Gateway {
+    type Result = ActorResponse<Self, Result<String, MailboxError>>;
+
+    fn handle(&mut self, msg: Get, _: &mut Self::Context) -> Self::Result {
+        ActorResponse::from_request(
+            self.backend.send(Lookup(msg.0)),
+            |res, act: &mut Self, _| {
+                act.mapped += 1;
+                Ok(format!("{}", res?))
+            },
+        )
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "usize")]
+struct Mapped;
+
+impl Handler<Mapped> for Gateway {
+    type Result = usize;
+
+    fn handle(&mut self, _: Mapped, _: &mut Self::Context) -> usize {
+        self.mapped
+    }
+}
+
+fn start(handled: &Arc<AtomicUsize>) -> (Addr<Backend>, Addr<Gateway>) {
+    let backend = Backend {
+        handled: Arc::clone(handled),
+    }
+    .start();
+    let gateway = Gateway {
+        backend: backend.clone(),
+        mapped: 0,
+    }
+    .start();
+    (backend, gateway)
+}
+
+#[actix::test]
+async fn test_from_request_maps_reply() {
+    let handled = Arc::new(AtomicUsize::new(0));
+    let (backend, gateway) = start(&handled);
+
+    assert_eq!(gateway.send(Get(21)).await, Ok(Ok("42".to_owned())));
+    assert_eq!(gateway.send(Mapped).await, Ok(1));
+
+    // errors of the backend request reach the map function
+    backend.send(Stop).await.unwrap();
+    backend.closed().await;
+    assert_eq!(gateway.send(Get(1)).await, Ok(Err(MailboxError::Closed)));
+    assert_eq!(gateway.send(Mapped).await, Ok(2));
+    assert_eq!(handled.load(Ordering::SeqCst), 1);
+}
+
+#[actix::test]
+async fn test_from_request_cancelled_with_requester() {
+    let handled = Arc::new(AtomicUsize::new(0));
+    let (backend, gateway) = start(&handled);
+
+    // the lookup waits behind the pause until the requester has given up
+    backend.do_send(Pause(Duration::from_millis(100)));
+    let res = gateway
+        .send(Get(1))
+        .timeout(Duration::from_millis(20))
+        .await;
+    assert_eq!(res, Err(MailboxError::Timeout));
+
+    sleep(Duration::from_millis(150)).await;
+    assert_eq!(handled.load(Ordering::SeqCst), 0);
+    assert_eq!(gateway.send(Mapped).await, Ok(0));
+    assert_eq!(gateway.send(Get(2)).await, Ok(Ok("4".to_owned())));
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "macros")]
+
+use actix::prelude::*;
+
+/// Plain message, whose response type is shared by all of its handlers.
+#[derive(Debug, Message)]
+#[rtype(result = "String")]
+struct Lookup(u32);
+
+#[derive(Debug, PartialEq)]
+struct Summary(String);
+
+#[derive(Debug, PartialEq)]
+struct Record {
+    id: u32,
+    name: String,
+}
+
+/// Handles `Lookup` as a plain message and as a query with two response types.
+struct Store;
+
+impl Actor for Store {
+    type Context = Context<Self>;
+}
+
+impl Handler<Lookup> for Store {
+    type Result = String;
+
+    fn handle(&mut self, msg: Lookup, _: &mut Self::Context) -> String {
+        format!("user {}", msg.0)
+    }
+}
+
+impl Handler<Query<Lookup, Summary>> for Store {
+    type Result = MessageResult<Query<Lookup, Summary>>;
+
+    fn handle(&mut self, query: Query<Lookup, Summary>, _: &mut Self::Context) -> Self::Result {
+        MessageResult(Summary(format!("user {}", query.0)))
+    }
+}
+
+impl Handler<Query<Lookup, Record>> for Store {
+    type Result = ResponseFuture<Record>;
+
+    fn handle(&mut self, query: Query<Lookup, Record>, _: &mut Self::Context) -> Self::Result {
+        let id = query.into_inner().0;
+        Box::pin(async move {
+            Record {
+                id,
+                name: format!("user {id}"),
+            }
+        })
+    }
+}
+
+/// Only answers queries, with a single response type.
+struct Cache;
+
+impl Actor for Cache {
+    type Context = Context<Self>;
+}
+
+impl Handler<Query<Lookup, Summary>> for Cache {
+    type Result = MessageResult<Query<Lookup, Summary>>;
+
+    fn handle(&mut self, query: Query<Lookup, Summary>, _: &mut Self::Context) -> Self::Result {
+        MessageResult(Summary(format!("cached {}", query.0)))
+    }
+}
+
+#[actix::test]
+async fn test_query_response_chosen_by_handler() {
+    let store = Store.start();
+    let cache = Cache.start();
+
+    // the only response type of the cache is inferred
+    let summary = cache.query(Lookup(1)).await.unwrap();
+    assert_eq!(summary, Summary("cached 1".to_owned()));
+
+    // the store handles the query with two response types, so they are named
+    let summary: Summary = store.query(Lookup(2)).await.unwrap();
+    assert_eq!(summary, Summary("user 2".to_owned()));
+    let record = store.query::<_, Record>(Lookup(3)).await.unwrap();
+    assert_eq!(
+        record,
+        Record {
+            id: 3,
+            name: "user 3".to_owned()
+        }
+    );
+
+    // the plain message keeps its own response type
+    assert_eq!(store.send(Lookup(4)).await.unwrap(), "user 4");
+}
+
+#[actix::test]
+async fn test_query_recipients() {
+    let recipients: Vec<Recipient<Query<Lookup, Summary>>> =
+        vec![Store.start().recipient(), Cache.start().recipient()];
+
+    let mut summaries = Vec::new();
+    for recipient in &recipients {
+        summaries.push(recipient.send(Query::new(Lookup(5))).await.unwrap());
+    }
+    assert_eq!(
+        summaries,
+        [Summary("user 5".to_owned()), Summary("cached 5".to_owned())]
+    );
+}
@@ -0,0 +1,119 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use actix::{prelude::*, queue};
+
+struct Summer {
+    sum: Arc<AtomicUsize>,
+    rx: Option<queue::Receiver<usize>>,
+}
+
+impl Actor for Summer {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.rx
+            .take()
+            .unwrap()
+            .map(|val, act: &mut Self, _| {
+                act.sum.fetch_add(val, Ordering::SeqCst);
+            })
+            .finish()
+            .map(|_, _, ctx: &mut Context<Self>| ctx.stop())
+            .spawn(ctx);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<usize, ()>")]
+struct Compute(queue::OneshotReceiver<usize>);
+
+struct Waiter;
+
+impl Actor for Waiter {
+    type Context = Context<Self>;
+}
+
+impl Handler<Compute> for Waiter {
+    type Result = ResponseActFuture<Self, Result<usize, ()>>;
+
+    fn handle(&mut self, msg: Compute, _: &mut Self::Context) -> Self::Result {
+        Box::pin(
+            msg.0
+                .map(|res, _, _| res.map(|val| val * 2).map_err(|_| ())),
+        )
+    }
+}
+
+#[actix::test]
+async fn test_channel_actor_stream() {
+    let sum = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = queue::channel(2);
+
+    let addr = Summer {
+        sum: Arc::clone(&sum),
+        rx: Some(rx),
+    }
+    .start();
+
+    let handles: Vec<_> = (1..=3)
+        .map(|i| {
+            let tx = tx.clone();
+            thread::spawn(move || {
+                for _ in 0..10 {
+                    tx.blocking_send(i).unwrap();
+                }
+            })
+        })
+        .collect();
+    drop(tx);
+
+    // stream ends once all senders are gone, which stops the actor
+    while addr.connected() {
+        actix_rt::task::yield_now().await;
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(sum.load(Ordering::SeqCst), 60);
+}
+
+#[actix::test]
+async fn test_oneshot_actor_future() {
+    let addr = Waiter.start();
+
+    let (tx, rx) = queue::oneshot();
+    let res = addr.send(Compute(rx));
+    thread::spawn(move || tx.send(21).unwrap());
+    assert_eq!(res.await.unwrap(), Ok(42));
+
+    let (tx, rx) = queue::oneshot::<usize>();
+    drop(tx);
+    assert_eq!(addr.send(Compute(rx)).await.unwrap(), Err(()));
+}
+
+#[actix::test]
+async fn test_receivers_outside_actor() {
+    use futures_util::stream::StreamExt;
+
+    let (tx, rx) = queue::oneshot();
+    thread::spawn(move || tx.send(7).unwrap());
+    assert_eq!(rx.await, Ok(7));
+
+    let (tx, rx) = queue::channel(2);
+    thread::spawn(move || {
+        for val in 1..=3 {
+            tx.blocking_send(val).unwrap();
+        }
+    });
+    let vals: Vec<usize> = StreamExt::collect(rx).await;
+    assert_eq!(vals, vec![1, 2, 3]);
+}
@@ -0,0 +1,217 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix::{
+    prelude::*,
+    utils::{Backoff, ReconnectEvent, Reconnector},
+};
+use futures_util::stream::{self, BoxStream, StreamExt as _};
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+struct Conn {
+    log: Log,
+    /// Outcomes of the next connection attempts, attempts fail once it is empty.
+    outcomes: VecDeque<Result<BoxStream<'static, u32>, &'static str>>,
+    backoff: Backoff,
+    reconnector: Option<Reconnector>,
+}
+
+impl Conn {
+    fn new(log: &Log, backoff: Backoff) -> Self {
+        Conn {
+            log: Arc::clone(log),
+            outcomes: VecDeque::new(),
+            backoff,
+            reconnector: None,
+        }
+    }
+
+    fn outcome(mut self, outcome: Result<Vec<u32>, &'static str>) -> Self {
+        self.outcomes
+            .push_back(outcome.map(|items| stream::iter(items).boxed()));
+        self
+    }
+
+    fn pending(mut self) -> Self {
+        self.outcomes.push_back(Ok(stream::pending().boxed()));
+        self
+    }
+
+    fn push(&self, entry: String) {
+        self.log.lock().unwrap().push(entry);
+    }
+}
+
+impl Actor for Conn {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let reconnector = Reconnector::new(
+            self.backoff,
+            |act: &mut Self, _: &mut Self::Context| {
+                let outcome = act.outcomes.pop_front().unwrap_or(Err("refused"));
+                Box::pin(fut::ready(outcome))
+            },
+            ctx,
+        );
+        self.reconnector = Some(reconnector);
+    }
+}
+
+impl StreamHandler<u32> for Conn {
+    fn handle(&mut self, item: u32, _: &mut Self::Context) {
+        self.push(format!("item {}", item));
+    }
+
+    fn started(&mut self, _: &mut Self::Context) {
+        self.push("started".to_owned());
+    }
+}
+
+impl Handler<ReconnectEvent<&'static str>> for Conn {
+    type Result = ();
+
+    fn handle(&mut self, event: ReconnectEvent<&'static str>, _: &mut Self::Context) {
+        let entry = match event {
+            ReconnectEvent::Connected => "connected".to_owned(),
+            ReconnectEvent::Disconnected => "disconnected".to_owned(),
+            ReconnectEvent::Failed {
+                error,
+                attempt,
+                retry_in,
+            } => format!("{} {} {:?}", error, attempt, retry_in),
+        };
+        self.push(entry);
+    }
+}
+
+enum Control {
+    Trigger,
+    Stop,
+}
+
+impl Message for Control {
+    type Result = ();
+}
+
+impl Handler<Control> for Conn {
+    type Result = ();
+
+    fn handle(&mut self, msg: Control, _: &mut Self::Context) {
+        let reconnector = self.reconnector.as_ref().unwrap();
+        match msg {
+            Control::Trigger => reconnector.trigger_now(),
+            Control::Stop => reconnector.stop(),
+        }
+    }
+}
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn test_backoff_delay() {
+    let backoff = Backoff::new(ms(10), ms(50));
+    assert_eq!(backoff.delay(1), ms(10));
+    assert_eq!(backoff.delay(2), ms(20));
+    assert_eq!(backoff.delay(3), ms(40));
+    assert_eq!(backoff.delay(4), ms(50));
+    assert_eq!(backoff.delay(usize::MAX), ms(50));
+
+    let backoff = backoff.factor(3.0);
+    assert_eq!(backoff.delay(2), ms(30));
+}
+
+#[actix::test]
+async fn test_reconnect_with_backoff() {
+    let log = Log::default();
+    let addr = Conn::new(&log, Backoff::new(ms(10), ms(100)))
+        .outcome(Err("refused"))
+        .outcome(Err("refused"))
+        .outcome(Ok(vec![1, 2]))
+        .outcome(Err("refused"))
+        .pending()
+        .start();
+
+    actix_rt::time::sleep(ms(100)).await;
+    addr.send(Control::Stop).await.unwrap();
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "refused 1 Some(10ms)",
+            "refused 2 Some(20ms)",
+            "connected",
+            "started",
+            "item 1",
+            "item 2",
+            "disconnected",
+            // the backoff was reset by the successful connection
+            "refused 1 Some(10ms)",
+            "connected",
+            "started",
+        ]
+    );
+}
+
+#[actix::test]
+async fn test_reconnect_gives_up() {
+    let log = Log::default();
+    let _addr = Conn::new(&log, Backoff::new(ms(1), ms(1)).max_attempts(3)).start();
+
+    actix_rt::time::sleep(ms(50)).await;
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "refused 1 Some(1ms)",
+            "refused 2 Some(1ms)",
+            "refused 3 None"
+        ]
+    );
+}
+
+#[actix::test]
+async fn test_reconnect_trigger_and_stop() {
+    let log = Log::default();
+    let addr = Conn::new(
+        &log,
+        Backoff::new(Duration::from_secs(60), Duration::from_secs(60)),
+    )
+    .pending()
+    .outcome(Err("refused"))
+    .pending()
+    .start();
+
+    actix_rt::task::yield_now().await;
+
+    // drops the connected stream
+    addr.send(Control::Trigger).await.unwrap();
+    actix_rt::task::yield_now().await;
+
+    // skips the backoff delay
+    addr.send(Control::Trigger).await.unwrap();
+    actix_rt::task::yield_now().await;
+
+    addr.send(Control::Stop).await.unwrap();
+    addr.send(Control::Trigger).await.unwrap();
+    actix_rt::task::yield_now().await;
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "connected",
+            "started",
+            "disconnected",
+            "refused 1 Some(60s)",
+            "connected",
+            "started",
+        ]
+    );
+}
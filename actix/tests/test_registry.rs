@@ -0,0 +1,229 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use actix::{prelude::*, Registry, SystemRegistry};
+use actix_rt::time::sleep;
+
+static SYSTEM_STARTS: AtomicUsize = AtomicUsize::new(0);
+static ARBITER_STARTS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Panic;
+
+#[derive(Message)]
+#[rtype(result = "usize")]
+struct Ping;
+
+#[derive(Default)]
+struct SysService;
+
+impl Actor for SysService {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _: &mut Self::Context) {
+        SYSTEM_STARTS.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl Supervised for SysService {}
+impl SystemService for SysService {}
+
+impl Handler<Panic> for SysService {
+    type Result = ();
+
+    fn handle(&mut self, _: Panic, _: &mut Self::Context) {
+        panic!("service failure");
+    }
+}
+
+impl Handler<Ping> for SysService {
+    type Result = usize;
+
+    fn handle(&mut self, _: Ping, _: &mut Self::Context) -> usize {
+        SYSTEM_STARTS.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Default)]
+struct ArbService;
+
+impl Actor for ArbService {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _: &mut Self::Context) {
+        ARBITER_STARTS.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl Supervised for ArbService {}
+impl ArbiterService for ArbService {}
+
+impl Handler<Panic> for ArbService {
+    type Result = ();
+
+    fn handle(&mut self, _: Panic, _: &mut Self::Context) {
+        panic!("service failure");
+    }
+}
+
+impl Handler<Ping> for ArbService {
+    type Result = usize;
+
+    fn handle(&mut self, _: Ping, _: &mut Self::Context) -> usize {
+        ARBITER_STARTS.load(Ordering::SeqCst)
+    }
+}
+
+#[actix::test]
+async fn test_system_service_recreated_after_failure() {
+    let addr = SysService::from_registry();
+    assert_eq!(addr.send(Ping).await.unwrap(), 1);
+
+    addr.do_send(Panic);
+    sleep(Duration::from_millis(50)).await;
+    assert!(!addr.connected());
+
+    let addr2 = SysService::from_registry();
+    assert!(addr2.connected());
+    assert_ne!(addr, addr2);
+    assert_eq!(addr2.send(Ping).await.unwrap(), 2);
+    assert_eq!(SysService::from_registry(), addr2);
+}
+
+#[actix::test]
+async fn test_arbiter_service_recreated_after_failure() {
+    let addr = ArbService::from_registry();
+    assert_eq!(addr.send(Ping).await.unwrap(), 1);
+
+    addr.do_send(Panic);
+    sleep(Duration::from_millis(50)).await;
+    assert!(!addr.connected());
+
+    let addr2 = ArbService::from_registry();
+    assert_ne!(addr, addr2);
+    assert_eq!(addr2.send(Ping).await.unwrap(), 2);
+}
+
+#[derive(Default)]
+struct Restartable(Arc<AtomicUsize>);
+
+impl Actor for Restartable {
+    type Context = Context<Self>;
+}
+
+impl Supervised for Restartable {
+    fn restarting(&mut self, _: &mut Self::Context) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl SystemService for Restartable {}
+
+#[derive(Message)]
+#[rtype(result = "usize")]
+struct StopAndCount;
+
+impl Handler<StopAndCount> for Restartable {
+    type Result = usize;
+
+    fn handle(&mut self, _: StopAndCount, ctx: &mut Self::Context) -> usize {
+        ctx.stop();
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+#[actix::test]
+async fn test_system_service_address_survives_restart() {
+    let addr = Restartable::from_registry();
+    assert_eq!(addr.send(StopAndCount).await.unwrap(), 0);
+    assert_eq!(addr.send(StopAndCount).await.unwrap(), 1);
+    assert_eq!(Restartable::from_registry(), addr);
+}
+
+#[derive(Default)]
+struct Listed;
+
+impl Actor for Listed {
+    type Context = Context<Self>;
+}
+
+impl Supervised for Listed {}
+impl SystemService for Listed {}
+impl ArbiterService for Listed {}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct StopListed;
+
+impl Handler<StopListed> for Listed {
+    type Result = ();
+
+    fn handle(&mut self, _: StopListed, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+}
+
+#[actix::test]
+async fn test_registry_entries() {
+    assert!(SystemRegistry::entries().is_empty());
+    assert!(Registry::entries().is_empty());
+
+    let _sys = Restartable::from_registry();
+    let _arb = <Listed as ArbiterService>::from_registry();
+    // not started by the registry, so it is not restarted once it stops
+    let listed = Listed.start();
+    SystemRegistry::set(listed.clone());
+
+    let entries = SystemRegistry::entries();
+    assert_eq!(
+        entries
+            .iter()
+            .map(|entry| (entry.type_name, entry.connected))
+            .collect::<Vec<_>>(),
+        [
+            (std::any::type_name::<Restartable>(), true),
+            (std::any::type_name::<Listed>(), true),
+        ]
+    );
+    assert!(entries[0].started_at <= entries[1].started_at);
+
+    let entries = Registry::entries();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].type_name, std::any::type_name::<Listed>());
+    assert!(entries[0].connected);
+
+    // stopped services are listed until they are looked up again
+    listed.send(StopListed).await.unwrap();
+    listed.closed().await;
+    let entries = SystemRegistry::entries();
+    assert_eq!(entries.len(), 2);
+    assert!(!entries[1].connected);
+}
+
+#[actix::test]
+async fn test_registry_clear() {
+    let listed = Listed.start();
+    SystemRegistry::set(listed.clone());
+    <Listed as ArbiterService>::from_registry();
+
+    let cleared = SystemRegistry::clear::<Listed>(true).unwrap();
+    assert_eq!(cleared, listed);
+    cleared.closed().await;
+    assert!(!listed.connected());
+    assert!(SystemRegistry::entries().is_empty());
+    assert!(SystemRegistry::clear::<Listed>(true).is_none());
+
+    // the arbiter registry is separate
+    assert_eq!(Registry::entries().len(), 1);
+    let arb = Registry::clear::<Listed>(false).unwrap();
+    assert!(arb.connected());
+    assert!(Registry::entries().is_empty());
+}
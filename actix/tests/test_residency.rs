@@ -0,0 +1,146 @@
+#![cfg(feature = "telemetry")]
+
+use std::{
+    sync::{Mutex, Once},
+    time::Duration,
+};
+
+use actix::{prelude::*, ContextStats};
+use log::{LevelFilter, Log, Metadata, Record};
+
+static RECORDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Collects warnings of this test's actors.
+struct TestLogger;
+
+impl Log for TestLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target().starts_with("test_residency")
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.enabled(record.metadata()) {
+            RECORDS.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn init_logger() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        log::set_logger(&TestLogger).unwrap();
+        log::set_max_level(LevelFilter::Warn);
+    });
+}
+
+struct Worker;
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Block(Duration);
+
+#[derive(Message)]
+#[rtype(result = "u32")]
+struct Ping(u32);
+
+#[derive(Message)]
+#[rtype(result = "ContextStats")]
+struct Stats;
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Warn(Option<Duration>);
+
+impl Handler<Block> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, Block(dur): Block, _: &mut Self::Context) {
+        std::thread::sleep(dur);
+    }
+}
+
+impl Handler<Ping> for Worker {
+    type Result = u32;
+
+    fn handle(&mut self, Ping(n): Ping, _: &mut Self::Context) -> u32 {
+        n
+    }
+}
+
+impl Handler<Stats> for Worker {
+    type Result = MessageResult<Stats>;
+
+    fn handle(&mut self, _: Stats, ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(ctx.stats())
+    }
+}
+
+impl Handler<Warn> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, Warn(threshold): Warn, ctx: &mut Self::Context) {
+        ctx.set_residency_warning(threshold);
+    }
+}
+
+#[actix::test]
+async fn test_queued_for() {
+    let addr = Worker.start();
+
+    addr.do_send(Block(Duration::from_millis(50)));
+    let mut req = Box::pin(addr.send(Ping(1)));
+    assert_eq!(req.queued_for(), None);
+    assert_eq!(req.as_mut().await, Ok(1));
+    let queued = req.queued_for().unwrap();
+    assert!(queued >= Duration::from_millis(45), "{:?}", queued);
+
+    // the residency is reported to the request it belongs to
+    let mut next = Box::pin(addr.send(Ping(2)).timeout(Duration::from_secs(1)));
+    addr.do_send(Block(Duration::from_millis(50)));
+    assert_eq!(next.as_mut().await, Ok(2));
+    assert!(next.queued_for().unwrap() < queued);
+
+    let stats = addr.send(Stats).await.unwrap();
+    let residency = stats.residency;
+    // including the `Stats` message itself
+    assert_eq!(residency.count, 5);
+    assert!(residency.max >= Duration::from_millis(45));
+    assert_eq!(residency.buckets().map(|(_, count)| count).sum::<u64>(), 5);
+    assert!(residency.percentile(100).unwrap() >= Duration::from_millis(45));
+    assert!(residency.percentile(25).unwrap() < Duration::from_millis(45));
+    assert!(residency.mean().unwrap() <= residency.max);
+}
+
+#[actix::test]
+async fn test_residency_warning() {
+    init_logger();
+    let addr = Worker.start();
+    addr.send(Warn(Some(Duration::from_millis(20))))
+        .await
+        .unwrap();
+
+    // only the first slow message within a second is reported
+    addr.do_send(Block(Duration::from_millis(40)));
+    addr.do_send(Ping(1));
+    addr.do_send(Block(Duration::from_millis(40)));
+    addr.send(Ping(2)).await.unwrap();
+
+    let records = RECORDS.lock().unwrap().clone();
+    assert_eq!(records.len(), 1, "{:?}", records);
+    assert!(
+        records[0].contains("test_residency::Ping"),
+        "{}",
+        records[0]
+    );
+    assert!(
+        records[0].contains("2 messages left in the mailbox"),
+        "{}",
+        records[0]
+    );
+}
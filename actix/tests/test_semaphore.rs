@@ -0,0 +1,139 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    sync::{Arc, Mutex as StdMutex},
+    time::Duration,
+};
+
+use actix::{
+    prelude::*,
+    sync::{Mutex, Semaphore},
+};
+use actix_rt::time::sleep;
+use futures_util::FutureExt as _;
+
+#[actix::test]
+async fn test_semaphore_fairness() {
+    let sem = Semaphore::new(1);
+    let held = sem.acquire().await;
+    assert!(sem.try_acquire().is_none());
+
+    let order = Arc::new(StdMutex::new(Vec::new()));
+    let mut tasks = Vec::new();
+    for n in 0..4 {
+        let (sem, order) = (sem.clone(), Arc::clone(&order));
+        tasks.push(actix_rt::spawn(async move {
+            let _permit = sem.acquire().await;
+            order.lock().unwrap().push(n);
+            sleep(Duration::from_millis(2)).await;
+        }));
+        sleep(Duration::from_millis(1)).await;
+    }
+
+    // a waiter which gives up leaves the queue
+    let mut gave_up = sem.acquire();
+    assert!((&mut gave_up).now_or_never().is_none());
+    drop(gave_up);
+
+    drop(held);
+    for task in tasks {
+        task.await.unwrap();
+    }
+    assert_eq!(*order.lock().unwrap(), [0, 1, 2, 3]);
+    assert_eq!(sem.available_permits(), 1);
+}
+
+struct Holder {
+    sem: Semaphore,
+}
+
+impl Actor for Holder {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        // keeps the permit attached until the actor stops
+        ctx.wait(self.sem.acquire_attached().map(|_, _, _| ()));
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Terminate;
+
+impl Handler<Terminate> for Holder {
+    type Result = ();
+
+    fn handle(&mut self, _: Terminate, ctx: &mut Self::Context) {
+        ctx.terminate();
+    }
+}
+
+#[actix::test]
+async fn test_permit_released_on_termination() {
+    let sem = Semaphore::new(1);
+    let first = Holder { sem: sem.clone() }.start();
+    let second = Holder { sem: sem.clone() }.start();
+    sleep(Duration::from_millis(10)).await;
+    assert_eq!(sem.available_permits(), 0);
+
+    // the permit passes to the waiting actor, then back to the semaphore
+    first.do_send(Terminate);
+    first.closed().await;
+    sleep(Duration::from_millis(10)).await;
+    assert_eq!(sem.available_permits(), 0);
+
+    second.do_send(Terminate);
+    second.closed().await;
+    assert_eq!(sem.available_permits(), 1);
+}
+
+struct Counter {
+    total: Mutex<u32>,
+}
+
+impl Actor for Counter {
+    type Context = Context<Self>;
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Increment;
+
+impl Handler<Increment> for Counter {
+    type Result = ResponseFuture<()>;
+
+    fn handle(&mut self, _: Increment, _: &mut Self::Context) -> Self::Result {
+        let lock = self.total.lock();
+        Box::pin(async move {
+            let mut total = lock.await;
+            let value = *total;
+            sleep(Duration::from_millis(1)).await;
+            *total = value + 1;
+        })
+    }
+}
+
+#[actix::test]
+async fn test_mutex_across_arbiters() {
+    let total = Mutex::new(0);
+    let arbiters: Vec<_> = (0..2).map(|_| Arbiter::new()).collect();
+    let counters: Vec<_> = arbiters
+        .iter()
+        .map(|arbiter| {
+            let total = total.clone();
+            Counter::start_in_arbiter(&arbiter.handle(), |_| Counter { total })
+        })
+        .collect();
+
+    let replies: Vec<_> = (0..10)
+        .flat_map(|_| counters.iter().map(|counter| counter.send(Increment)))
+        .collect();
+    for reply in replies {
+        reply.await.unwrap();
+    }
+    assert_eq!(*total.lock().await, 20);
+
+    for arbiter in arbiters {
+        arbiter.stop();
+    }
+}
@@ -0,0 +1,175 @@
+#![cfg(feature = "macros")]
+
+use actix::{prelude::*, SequenceGap, SequenceGapPolicy, SequenceStats};
+
+#[derive(Message)]
+#[rtype(result = "u64")]
+struct Chunk(u64);
+
+impl Sequenced for Chunk {
+    fn seq(&self) -> u64 {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Policy {
+    Skip,
+    Notify,
+    Reject,
+}
+
+struct Assembler {
+    max_gap_buffer: usize,
+    policy: Policy,
+    log: Vec<u64>,
+    gaps: Vec<SequenceGap>,
+}
+
+impl Assembler {
+    fn new(max_gap_buffer: usize, policy: Policy) -> Self {
+        Assembler {
+            max_gap_buffer,
+            policy,
+            log: Vec::new(),
+            gaps: Vec::new(),
+        }
+    }
+}
+
+impl Actor for Assembler {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        assert_eq!(ctx.sequence_stats::<Chunk>(), None);
+        ctx.enable_sequencing::<Chunk>(0, self.max_gap_buffer);
+
+        let recipient = ctx.address().recipient();
+        let policy = match self.policy {
+            Policy::Skip => SequenceGapPolicy::Skip,
+            Policy::Notify => SequenceGapPolicy::SkipAndNotify(recipient),
+            Policy::Reject => SequenceGapPolicy::Reject(recipient),
+        };
+        assert!(ctx.set_sequence_gap_policy::<Chunk>(policy));
+    }
+}
+
+impl Handler<Chunk> for Assembler {
+    type Result = u64;
+
+    fn handle(&mut self, chunk: Chunk, _: &mut Self::Context) -> u64 {
+        self.log.push(chunk.0);
+        chunk.0
+    }
+}
+
+impl Handler<SequenceGap> for Assembler {
+    type Result = ();
+
+    fn handle(&mut self, gap: SequenceGap, _: &mut Self::Context) {
+        self.gaps.push(gap);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "(Vec<u64>, Vec<SequenceGap>, SequenceStats)")]
+struct GetState;
+
+impl Handler<GetState> for Assembler {
+    type Result = MessageResult<GetState>;
+
+    fn handle(&mut self, _: GetState, ctx: &mut Self::Context) -> Self::Result {
+        let stats = ctx.sequence_stats::<Chunk>().unwrap();
+        MessageResult((self.log.clone(), self.gaps.clone(), stats))
+    }
+}
+
+fn gap(expected: u64, received: u64) -> SequenceGap {
+    SequenceGap {
+        message: std::any::type_name::<Chunk>(),
+        expected,
+        received,
+    }
+}
+
+#[actix::test]
+async fn test_sequencing_reorders_messages() {
+    let addr = Assembler::new(8, Policy::Skip).start();
+
+    // two producers, each sending its chunks out of order
+    let first = addr.clone();
+    let second = addr.clone();
+    first.do_send(Chunk(3));
+    second.do_send(Chunk(2));
+    first.do_send(Chunk(1));
+
+    let (log, _, stats) = addr.send(GetState).await.unwrap();
+    assert!(log.is_empty());
+    assert_eq!(stats.expected, 0);
+    assert_eq!(stats.buffered, 3);
+
+    // the request is answered once its chunk is handled
+    assert_eq!(second.send(Chunk(0)).await, Ok(0));
+    let (log, gaps, stats) = addr.send(GetState).await.unwrap();
+    assert_eq!(log, [0, 1, 2, 3]);
+    assert!(gaps.is_empty());
+    assert_eq!(stats.expected, 4);
+    assert_eq!(stats.buffered, 0);
+
+    // chunks which were handled already are dropped
+    assert_eq!(first.send(Chunk(2)).await, Err(MailboxError::Closed));
+}
+
+#[actix::test]
+async fn test_sequencing_skips_gap() {
+    let addr = Assembler::new(2, Policy::Notify).start();
+
+    // chunk 0 is missing, chunk 3 does not fit into the buffer
+    addr.do_send(Chunk(1));
+    addr.do_send(Chunk(2));
+    addr.do_send(Chunk(3));
+    addr.do_send(Chunk(0));
+    addr.do_send(Chunk(4));
+
+    // the notification is queued behind the first request
+    addr.send(GetState).await.unwrap();
+    let (log, gaps, stats) = addr.send(GetState).await.unwrap();
+    assert_eq!(log, [1, 2, 3, 4]);
+    assert_eq!(gaps, [gap(0, 3)]);
+    assert_eq!(stats.expected, 5);
+}
+
+#[actix::test]
+async fn test_sequencing_skips_gap_without_notification() {
+    let addr = Assembler::new(0, Policy::Skip).start();
+
+    addr.do_send(Chunk(2));
+    addr.do_send(Chunk(5));
+    addr.do_send(Chunk(6));
+
+    let (log, gaps, stats) = addr.send(GetState).await.unwrap();
+    assert_eq!(log, [2, 5, 6]);
+    assert!(gaps.is_empty());
+    assert_eq!(stats.expected, 7);
+}
+
+#[actix::test]
+async fn test_sequencing_rejects_overflow() {
+    let addr = Assembler::new(1, Policy::Reject).start();
+
+    addr.do_send(Chunk(1));
+    assert_eq!(addr.send(Chunk(2)).await, Err(MailboxError::Closed));
+
+    let (log, gaps, stats) = addr.send(GetState).await.unwrap();
+    assert!(log.is_empty());
+    assert_eq!(gaps, [gap(0, 2)]);
+    assert_eq!(stats.expected, 0);
+    assert_eq!(stats.buffered, 1);
+
+    // the gap is closed once the missing chunk arrives
+    assert_eq!(addr.send(Chunk(0)).await, Ok(0));
+    assert_eq!(addr.send(Chunk(2)).await, Ok(2));
+    let (log, _, stats) = addr.send(GetState).await.unwrap();
+    assert_eq!(log, [0, 1, 2]);
+    assert_eq!(stats.expected, 3);
+}
@@ -0,0 +1,122 @@
+#![cfg(feature = "macros")]
+
+use std::time::Duration;
+
+use actix::{prelude::*, ActorSettings, StopPolicy};
+use actix_rt::time::{sleep, timeout};
+
+#[derive(Default)]
+struct Worker {
+    handled: u32,
+}
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+}
+
+impl Supervised for Worker {}
+
+#[derive(Message)]
+#[rtype(result = "u32")]
+struct Work;
+
+impl Handler<Work> for Worker {
+    type Result = u32;
+
+    fn handle(&mut self, _: Work, _: &mut Self::Context) -> u32 {
+        self.handled += 1;
+        self.handled
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Stop;
+
+impl Handler<Stop> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: Stop, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "ActorSettings")]
+struct GetSettings;
+
+impl Handler<GetSettings> for Worker {
+    type Result = MessageResult<GetSettings>;
+
+    fn handle(&mut self, _: GetSettings, ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(ctx.settings())
+    }
+}
+
+#[actix::test]
+async fn test_start_with_settings() {
+    let settings = ActorSettings::default()
+        .mailbox(2)
+        .budget(4)
+        .idle_timeout(Duration::from_secs(60))
+        .stop_policy(StopPolicy::Drain);
+
+    // the mailbox capacity applies before the actor is polled
+    let addr = Worker::default().start_with(settings.clone());
+    for _ in 0..2 {
+        addr.try_send(Work).unwrap();
+    }
+    assert!(matches!(addr.try_send(Work), Err(SendError::Full(_))));
+    assert_eq!(addr.send(GetSettings).await.unwrap(), settings);
+
+    let addr = Worker::create_with(settings.clone(), |ctx| {
+        ctx.set_message_budget(None);
+        Worker::default()
+    });
+    let effective = addr.send(GetSettings).await.unwrap();
+    assert_eq!(effective, settings.budget(None));
+
+    let addr = SupervisorBuilder::new(|_| Worker::default())
+        .settings(ActorSettings::default().mailbox(3))
+        .start();
+    let effective = addr.send(GetSettings).await.unwrap();
+    assert_eq!(effective.get_mailbox_capacity(), 3);
+}
+
+#[actix::test]
+async fn test_idle_timeout() {
+    let settings = ActorSettings::default().idle_timeout(Duration::from_millis(30));
+    let addr = Worker::default().start_with(settings);
+
+    // received messages keep the actor running
+    for _ in 0..4 {
+        sleep(Duration::from_millis(15)).await;
+        addr.send(Work).await.unwrap();
+    }
+    assert!(addr.connected());
+
+    timeout(Duration::from_millis(200), addr.closed())
+        .await
+        .unwrap();
+    assert_eq!(addr.send(Work).await, Err(MailboxError::Closed));
+}
+
+#[actix::test]
+async fn test_stop_policy() {
+    let run = |policy| async move {
+        let addr = Worker::default().start_with(ActorSettings::default().stop_policy(policy));
+        addr.do_send(Stop);
+        let queued: Vec<_> = (0..3).map(|_| addr.send(Work)).collect();
+        let mut replies = Vec::new();
+        for reply in queued {
+            replies.push(reply.await);
+        }
+        replies
+    };
+
+    assert_eq!(run(StopPolicy::Drain).await, [Ok(1), Ok(2), Ok(3)]);
+    assert_eq!(
+        run(StopPolicy::Discard).await,
+        [Err(MailboxError::Closed); 3]
+    );
+}
@@ -0,0 +1,179 @@
+#![cfg(feature = "macros")]
+
+use std::time::Duration;
+
+use actix::{prelude::*, MinimalContext};
+use actix_rt::time::sleep;
+
+#[derive(Default, Message)]
+#[rtype(result = "()")]
+struct Tick;
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Mark;
+
+#[derive(Message)]
+#[rtype(result = "Vec<&'static str>")]
+struct GetLog;
+
+#[derive(Default)]
+struct Counter {
+    coalesce: bool,
+    log: Vec<&'static str>,
+    batches: Vec<usize>,
+}
+
+impl Actor for Counter {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if self.coalesce {
+            ctx.coalesce_signals::<Tick>();
+        }
+    }
+}
+
+impl Handler<Tick> for Counter {
+    type Result = ();
+
+    fn handle(&mut self, _: Tick, _: &mut Self::Context) {
+        self.log.push("tick");
+    }
+}
+
+impl SignalHandler<Tick> for Counter {
+    fn handle_signals(&mut self, count: usize, _: &mut Self::Context) {
+        self.batches.push(count);
+    }
+}
+
+impl Handler<Mark> for Counter {
+    type Result = ();
+
+    fn handle(&mut self, _: Mark, _: &mut Self::Context) {
+        self.log.push("mark");
+    }
+}
+
+impl Handler<GetLog> for Counter {
+    type Result = MessageResult<GetLog>;
+
+    fn handle(&mut self, _: GetLog, _: &mut Self::Context) -> Self::Result {
+        MessageResult(self.log.clone())
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Vec<usize>")]
+struct GetBatches;
+
+impl Handler<GetBatches> for Counter {
+    type Result = MessageResult<GetBatches>;
+
+    fn handle(&mut self, _: GetBatches, _: &mut Self::Context) -> Self::Result {
+        MessageResult(self.batches.clone())
+    }
+}
+
+#[actix::test]
+async fn test_signal_handled_per_signal() {
+    let addr = Counter::default().start();
+
+    for _ in 0..3 {
+        addr.signal::<Tick>();
+    }
+    assert_eq!(addr.send(GetLog).await.unwrap(), ["tick"; 3]);
+}
+
+#[actix::test]
+async fn test_signal_coalesced() {
+    let addr = Counter {
+        coalesce: true,
+        ..Counter::default()
+    }
+    .start();
+    sleep(Duration::from_millis(10)).await;
+
+    for _ in 0..5 {
+        addr.signal::<Tick>();
+    }
+    sleep(Duration::from_millis(10)).await;
+    addr.signal::<Tick>();
+
+    assert_eq!(addr.send(GetBatches).await.unwrap(), [5, 1]);
+    assert!(addr.send(GetLog).await.unwrap().is_empty());
+}
+
+#[actix::test]
+async fn test_signal_handled_before_queued_messages() {
+    let addr = Counter::default().start();
+
+    // the signal is sent last, but handled once the actor is polled
+    addr.do_send(Mark);
+    addr.signal::<Tick>();
+    addr.do_send(Mark);
+
+    assert_eq!(addr.send(GetLog).await.unwrap(), ["tick", "mark", "mark"]);
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Pause;
+
+impl Handler<Pause> for Counter {
+    type Result = ();
+
+    fn handle(&mut self, _: Pause, ctx: &mut Self::Context) {
+        self.log.push("pause");
+        ctx.wait(
+            async { sleep(Duration::from_millis(20)).await }
+                .into_actor(self)
+                .map(|_, act, _| act.log.push("resume")),
+        );
+    }
+}
+
+#[actix::test]
+async fn test_signal_held_while_waiting() {
+    let addr = Counter::default().start();
+
+    addr.send(Pause).await.unwrap();
+    addr.signal::<Tick>();
+    addr.signal::<Tick>();
+
+    assert_eq!(
+        addr.send(GetLog).await.unwrap(),
+        ["pause", "resume", "tick", "tick"]
+    );
+}
+
+#[actix::test]
+async fn test_signal_to_stopped_actor() {
+    struct Ticks(usize);
+
+    impl Actor for Ticks {
+        type Context = MinimalContext<Self>;
+    }
+
+    impl Handler<Tick> for Ticks {
+        type Result = ();
+
+        fn handle(&mut self, _: Tick, ctx: &mut Self::Context) {
+            self.0 += 1;
+            if self.0 == 2 {
+                ctx.stop();
+            }
+        }
+    }
+
+    let addr = MinimalContext::new().run(Ticks(0));
+    addr.signal::<Tick>();
+    sleep(Duration::from_millis(10)).await;
+    addr.signal::<Tick>();
+    sleep(Duration::from_millis(10)).await;
+    assert!(!addr.connected());
+
+    // dropped without being handled
+    addr.signal::<Tick>();
+}
@@ -0,0 +1,145 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix::{
+    prelude::*,
+    spill::{PersistentMessage, SpillAddr, SpillConfig, SpillError},
+};
+use actix_rt::time::sleep;
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Record(u32);
+
+impl PersistentMessage for Record {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_le_bytes().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let bytes = bytes.try_into().map_err(|_| io::ErrorKind::InvalidData)?;
+        Ok(Record(u32::from_le_bytes(bytes)))
+    }
+}
+
+type Received = Arc<Mutex<Vec<u32>>>;
+
+struct Ingest {
+    received: Received,
+    stop_at: Option<u32>,
+}
+
+impl Ingest {
+    fn start(received: &Received, stop_at: Option<u32>) -> Addr<Self> {
+        let received = Arc::clone(received);
+        Ingest::create(move |ctx| {
+            ctx.set_mailbox_capacity(1);
+            Ingest { received, stop_at }
+        })
+    }
+}
+
+impl Actor for Ingest {
+    type Context = Context<Self>;
+}
+
+impl Handler<Record> for Ingest {
+    type Result = ();
+
+    fn handle(&mut self, msg: Record, ctx: &mut Self::Context) {
+        self.received.lock().unwrap().push(msg.0);
+        if self.stop_at == Some(msg.0) {
+            ctx.stop();
+        }
+    }
+}
+
+fn spill_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("actix-spill-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn segments(dir: &PathBuf) -> usize {
+    fs::read_dir(dir).unwrap().count()
+}
+
+async fn wait_for(received: &Received, len: usize) {
+    for _ in 0..100 {
+        if received.lock().unwrap().len() >= len {
+            return;
+        }
+        sleep(Duration::from_millis(10)).await;
+    }
+    panic!("received {:?}", received.lock().unwrap());
+}
+
+#[actix::test]
+async fn test_spill_in_order() {
+    let dir = spill_dir("order");
+    let received = Received::default();
+    let addr = Ingest::start(&received, None);
+    let spill = SpillAddr::new(addr, SpillConfig::new(&dir).segment_bytes(32)).unwrap();
+
+    for n in 0..50 {
+        spill.do_send(Record(n)).unwrap();
+    }
+    assert_eq!(spill.spilled(), 49);
+    assert!(segments(&dir) > 1);
+
+    // requests can not be spilled
+    assert!(matches!(spill.send(Record(99)), Err(SendError::Full(_))));
+
+    wait_for(&received, 50).await;
+    assert_eq!(*received.lock().unwrap(), (0..50).collect::<Vec<_>>());
+    assert_eq!(spill.spilled(), 0);
+    assert_eq!(segments(&dir), 0);
+
+    spill.send(Record(50)).unwrap().await.unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[actix::test]
+async fn test_spill_recover() {
+    let dir = spill_dir("recover");
+    let received = Received::default();
+    let addr = Ingest::start(&received, Some(0));
+    let spill = SpillAddr::new(addr.clone(), SpillConfig::new(&dir)).unwrap();
+
+    for n in 0..10 {
+        spill.do_send(Record(n)).unwrap();
+    }
+    addr.closed().await;
+    sleep(Duration::from_millis(20)).await;
+    assert!(matches!(
+        spill.do_send(Record(10)),
+        Err(SpillError::Closed(_))
+    ));
+    let left = spill.spilled();
+    drop(spill);
+
+    // messages of the previous run are not silently taken over
+    let received = Received::default();
+    let addr = Ingest::start(&received, None);
+    let err = SpillAddr::new(addr.clone(), SpillConfig::new(&dir)).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+
+    let spill = SpillAddr::recover(addr, SpillConfig::new(&dir)).unwrap();
+    assert_eq!(spill.spilled(), left);
+    spill.do_send(Record(10)).unwrap();
+
+    // a message moved to the stopped actor's mailbox may be lost
+    wait_for(&received, left + 1).await;
+    let first = 10 - left as u32;
+    assert!(first <= 2);
+    assert_eq!(*received.lock().unwrap(), (first..11).collect::<Vec<_>>());
+
+    drop(spill);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,131 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix::{
+    prelude::*,
+    utils::{StageReport, StopGroup},
+};
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+struct Stage {
+    name: &'static str,
+    log: Log,
+    refuse_stop: bool,
+}
+
+impl Stage {
+    fn start(name: &'static str, log: &Log, refuse_stop: bool) -> Addr<Self> {
+        Stage {
+            name,
+            log: Arc::clone(log),
+            refuse_stop,
+        }
+        .start()
+    }
+}
+
+impl Actor for Stage {
+    type Context = Context<Self>;
+
+    fn stopping(&mut self, _: &mut Self::Context) -> Running {
+        if self.refuse_stop {
+            Running::Continue
+        } else {
+            Running::Stop
+        }
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("{} stopped", self.name));
+    }
+}
+
+struct Work;
+
+impl Message for Work {
+    type Result = ();
+}
+
+impl Handler<Work> for Stage {
+    type Result = ();
+
+    fn handle(&mut self, _: Work, _: &mut Self::Context) {
+        self.log.lock().unwrap().push(format!("{} work", self.name));
+    }
+}
+
+#[actix::test]
+async fn test_stop_group_order() {
+    let log = Log::default();
+    let source = Stage::start("source", &log, false);
+    let middle = Stage::start("middle", &log, false);
+    let sink = Stage::start("sink", &log, false);
+
+    // queued messages are handled before stopping
+    source.do_send(Work);
+    source.do_send(Work);
+
+    let mut group = StopGroup::new();
+    group.register(2, sink.clone());
+    group.register(0, source.clone());
+    group.register(1, middle.clone());
+
+    let reports = group.shutdown(Duration::from_secs(1)).await;
+    assert_eq!(
+        reports,
+        (0..3)
+            .map(|stage| StageReport {
+                stage,
+                actors: 1,
+                timed_out: 0,
+            })
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "source work",
+            "source work",
+            "source stopped",
+            "middle stopped",
+            "sink stopped"
+        ]
+    );
+    assert!(!source.connected() && !middle.connected() && !sink.connected());
+}
+
+#[actix::test]
+async fn test_stop_group_timeout() {
+    let log = Log::default();
+
+    let mut group = StopGroup::new();
+    group.register(0, Stage::start("stubborn", &log, true));
+    group.register(0, Stage::start("source", &log, false));
+    group.register(1, Stage::start("sink", &log, false));
+
+    let reports = group.shutdown(Duration::from_millis(50)).await;
+    assert_eq!(
+        reports,
+        [
+            StageReport {
+                stage: 0,
+                actors: 2,
+                timed_out: 1,
+            },
+            StageReport {
+                stage: 1,
+                actors: 1,
+                timed_out: 0,
+            },
+        ]
+    );
+    assert_eq!(*log.lock().unwrap(), ["source stopped", "sink stopped"]);
+}
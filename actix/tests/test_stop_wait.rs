@@ -0,0 +1,153 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use actix::{clock::Instant, prelude::*, StopReason, StopWaitError};
+use actix_rt::time::sleep;
+
+fn in_ms(ms: u64) -> Instant {
+    Instant::now() + Duration::from_millis(ms)
+}
+
+struct Server {
+    veto: bool,
+    released: Arc<AtomicBool>,
+}
+
+impl Server {
+    fn start(veto: bool) -> (Addr<Server>, Arc<AtomicBool>) {
+        let released = Arc::new(AtomicBool::new(false));
+        let server = Server {
+            veto,
+            released: Arc::clone(&released),
+        };
+        (server.start(), released)
+    }
+}
+
+impl Actor for Server {
+    type Context = Context<Self>;
+
+    fn stopping(&mut self, _: &mut Self::Context) -> Running {
+        if self.veto {
+            Running::Continue
+        } else {
+            Running::Stop
+        }
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.released.store(true, Ordering::SeqCst);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Busy(u64);
+
+impl Handler<Busy> for Server {
+    type Result = ();
+
+    fn handle(&mut self, Busy(ms): Busy, ctx: &mut Self::Context) {
+        ctx.wait(sleep(Duration::from_millis(ms)).into_actor(self));
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Quit;
+
+impl Handler<Quit> for Server {
+    type Result = ();
+
+    fn handle(&mut self, _: Quit, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+}
+
+#[actix::test]
+async fn test_stop_and_wait() {
+    let (addr, released) = Server::start(false);
+
+    assert_eq!(
+        addr.stop_and_wait(in_ms(100)).await,
+        Ok(StopReason::Stopped)
+    );
+    assert!(released.load(Ordering::SeqCst));
+    assert!(!addr.connected());
+}
+
+#[actix::test]
+async fn test_stop_and_wait_already_stopped() {
+    let (addr, _) = Server::start(false);
+    addr.do_send(Quit);
+    sleep(Duration::from_millis(10)).await;
+
+    assert_eq!(
+        addr.stop_and_wait(in_ms(100)).await,
+        Ok(StopReason::AlreadyStopped)
+    );
+}
+
+#[actix::test]
+async fn test_stop_and_wait_vetoed() {
+    let (addr, released) = Server::start(true);
+
+    assert_eq!(
+        addr.stop_and_wait(in_ms(100)).await,
+        Err(StopWaitError::Vetoed)
+    );
+    assert!(!released.load(Ordering::SeqCst));
+    assert!(addr.connected());
+}
+
+#[actix::test]
+async fn test_stop_and_wait_timeout() {
+    let (addr, released) = Server::start(false);
+
+    // the stop request is queued behind the wait
+    addr.do_send(Busy(100));
+    assert_eq!(
+        addr.stop_and_wait(in_ms(20)).await,
+        Err(StopWaitError::Timeout)
+    );
+
+    // the request is still handled later
+    sleep(Duration::from_millis(150)).await;
+    assert!(released.load(Ordering::SeqCst));
+}
+
+#[actix::test]
+async fn test_stop_and_wait_from_other_thread() {
+    let (addr, released) = Server::start(false);
+
+    let res = actix_rt::task::spawn_blocking(move || {
+        System::new().block_on(addr.stop_and_wait(in_ms(100)))
+    })
+    .await
+    .unwrap();
+    assert_eq!(res, Ok(StopReason::Stopped));
+    assert!(released.load(Ordering::SeqCst));
+}
+
+#[actix::test]
+async fn test_stop_and_wait_sync_actor() {
+    struct Worker;
+
+    impl Actor for Worker {
+        type Context = SyncContext<Self>;
+    }
+
+    let addr = SyncArbiter::start(1, || Worker);
+    let stopped = addr.stop_and_wait(in_ms(1000));
+
+    // sync actors do not report that they stopped, the arbiter stops once the address is gone
+    drop(addr);
+    assert_eq!(stopped.await, Err(StopWaitError::Disconnected));
+}
@@ -0,0 +1,102 @@
+#![cfg(all(feature = "stress", feature = "macros"))]
+
+use std::time::Duration;
+
+use actix::{
+    prelude::*,
+    stress::{OrderChecker, Stress, Violation},
+};
+
+struct Ledger {
+    order: OrderChecker,
+    stream: u64,
+}
+
+impl Actor for Ledger {
+    type Context = Context<Self>;
+}
+
+impl Supervised for Ledger {}
+
+#[derive(Message)]
+#[rtype(result = "u64")]
+struct Entry(u64);
+
+impl Handler<Entry> for Ledger {
+    type Result = u64;
+
+    fn handle(&mut self, Entry(seq): Entry, _: &mut Self::Context) -> u64 {
+        self.order.observe(self.stream, seq);
+        seq
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Note(u64);
+
+impl Handler<Note> for Ledger {
+    type Result = ();
+
+    fn handle(&mut self, Note(seq): Note, _: &mut Self::Context) {
+        self.order.observe(self.stream, seq);
+    }
+}
+
+#[test]
+fn test_mailbox_order_across_restarts() {
+    let report = Stress::new(Duration::from_millis(300))
+        .seed(7)
+        .run(|scenario| {
+            let order = scenario.order_checker();
+            let ledger = Supervisor::start(move |_| Ledger { order, stream: 0 });
+            scenario.watch(&ledger);
+            scenario.flood(ledger.clone().recipient(), 2_000, Entry);
+            scenario.random_stopper(ledger, Duration::from_millis(5)..Duration::from_millis(20));
+        });
+
+    report.assert_ok();
+    assert!(report.sent > 100);
+    assert_eq!(report.replied, report.sent);
+    assert!(report.stops > 0);
+}
+
+#[test]
+fn test_shutdown_of_arbiters() {
+    let report = Stress::new(Duration::from_millis(200))
+        .seed(11)
+        .run(|scenario| {
+            for stream in 0..4 {
+                let order = scenario.order_checker();
+                let arbiter = Arbiter::new();
+                let ledger =
+                    Ledger::start_in_arbiter(&arbiter.handle(), move |_| Ledger { order, stream });
+                scenario.watch(&ledger);
+                scenario.flood(ledger.recipient(), 500, Entry);
+            }
+        });
+
+    report.assert_ok();
+    assert_eq!(report.replied, report.sent);
+}
+
+#[test]
+fn test_chaos_is_detected() {
+    let report = Stress::new(Duration::from_millis(100))
+        .seed(3)
+        .run(|scenario| {
+            let order = scenario.order_checker();
+            let ledger = Ledger { order, stream: 0 }.start();
+            let chaos = scenario.chaos(
+                ledger.recipient(),
+                Duration::ZERO..Duration::from_millis(10),
+            );
+            scenario.flood(chaos, 1_000, Note);
+        });
+
+    assert!(!report.is_ok());
+    assert!(report
+        .violations
+        .iter()
+        .all(|violation| matches!(violation, Violation::OutOfOrder { stream: 0, .. })));
+}
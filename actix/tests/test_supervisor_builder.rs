@@ -0,0 +1,331 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use actix::prelude::*;
+use actix_rt::time::sleep;
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Die;
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+struct Conn(Log);
+
+impl Conn {
+    fn log(&self, event: impl Into<String>) {
+        self.0.lock().unwrap().push(event.into());
+    }
+}
+
+impl Actor for Conn {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _: &mut Self::Context) {
+        self.log("started");
+    }
+}
+
+impl Supervised for Conn {
+    fn restarting(&mut self, _: &mut Self::Context) {
+        self.log("restarting");
+    }
+}
+
+#[derive(Message, Clone)]
+#[rtype(result = "()")]
+struct Connect;
+
+impl Handler<Connect> for Conn {
+    type Result = ();
+
+    fn handle(&mut self, _: Connect, _: &mut Self::Context) {
+        self.log("connect");
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Work(usize);
+
+impl Handler<Work> for Conn {
+    type Result = ();
+
+    fn handle(&mut self, msg: Work, _: &mut Self::Context) {
+        self.log(format!("work {}", msg.0));
+    }
+}
+
+impl Handler<Die> for Conn {
+    type Result = ();
+
+    fn handle(&mut self, _: Die, ctx: &mut Self::Context) {
+        self.log("die");
+        ctx.stop();
+    }
+}
+
+fn events(log: &Log) -> Vec<String> {
+    log.lock().unwrap().clone()
+}
+
+#[actix::test]
+async fn test_supervisor_builder_lazy() {
+    let log = Log::default();
+    let created = Arc::new(AtomicUsize::new(0));
+
+    let log2 = Arc::clone(&log);
+    let created2 = Arc::clone(&created);
+    let addr = SupervisorBuilder::new(move |_| {
+        created2.fetch_add(1, Ordering::SeqCst);
+        Conn(log2)
+    })
+    .lazy(true)
+    .on_start_send(Connect)
+    .start();
+
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(created.load(Ordering::SeqCst), 0);
+    assert!(events(&log).is_empty());
+
+    addr.send(Work(1)).await.unwrap();
+    addr.send(Work(2)).await.unwrap();
+    assert_eq!(created.load(Ordering::SeqCst), 1);
+    assert_eq!(events(&log), vec!["started", "connect", "work 1", "work 2"]);
+}
+
+#[actix::test]
+async fn test_supervisor_builder_lazy_unused() {
+    let created = Arc::new(AtomicUsize::new(0));
+    let created2 = Arc::clone(&created);
+    let addr = SupervisorBuilder::new(move |_| {
+        created2.fetch_add(1, Ordering::SeqCst);
+        Conn(Log::default())
+    })
+    .lazy(true)
+    .start();
+
+    drop(addr);
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(created.load(Ordering::SeqCst), 0);
+}
+
+#[actix::test]
+async fn test_supervisor_builder_on_start_after_restart() {
+    let log = Log::default();
+    let log2 = Arc::clone(&log);
+    let addr = SupervisorBuilder::new(move |_| Conn(log2))
+        .on_start_send(Connect)
+        .start();
+
+    addr.do_send(Work(1));
+    addr.do_send(Die);
+    addr.do_send(Work(2));
+    addr.do_send(Work(3));
+    sleep(Duration::from_millis(50)).await;
+
+    assert_eq!(
+        events(&log),
+        vec![
+            "started",
+            "connect",
+            "work 1",
+            "die",
+            "restarting",
+            "started",
+            "connect",
+            "work 2",
+            "work 3"
+        ]
+    );
+}
+
+#[actix::test]
+async fn test_supervisor_builder_restart_policy() {
+    let log = Log::default();
+    let log2 = Arc::clone(&log);
+    let addr = SupervisorBuilder::new(move |_| Conn(log2))
+        .restart_policy(RestartPolicy::Limit(1))
+        .start();
+
+    addr.do_send(Die);
+    addr.do_send(Die);
+    sleep(Duration::from_millis(50)).await;
+
+    assert!(!addr.connected());
+    assert_eq!(addr.send(Work(1)).await, Err(MailboxError::Closed));
+    assert_eq!(
+        events(&log),
+        vec!["started", "die", "restarting", "started", "die"]
+    );
+
+    let log = Log::default();
+    let log2 = Arc::clone(&log);
+    let addr = SupervisorBuilder::new(move |_| Conn(log2))
+        .restart_policy(RestartPolicy::Never)
+        .start();
+
+    addr.do_send(Die);
+    sleep(Duration::from_millis(50)).await;
+    assert!(!addr.connected());
+    assert_eq!(events(&log), vec!["started", "die"]);
+}
+
+#[actix::test]
+async fn test_supervisor_builder_spawn_in_arbiter() {
+    let log = Log::default();
+    let log2 = Arc::clone(&log);
+    let arbiter = Arbiter::new();
+    let addr = SupervisorBuilder::new(move |_| Conn(log2))
+        .lazy(true)
+        .on_start_send(Connect)
+        .spawn(&arbiter.handle());
+
+    addr.send(Work(1)).await.unwrap();
+    assert_eq!(events(&log), vec!["started", "connect", "work 1"]);
+
+    arbiter.stop();
+}
+
+#[derive(Message)]
+#[rtype(result = "usize")]
+struct Crash;
+
+impl Handler<Crash> for Conn {
+    type Result = ResponseActFuture<Self, usize>;
+
+    fn handle(&mut self, _: Crash, ctx: &mut Self::Context) -> Self::Result {
+        self.log("crash");
+        ctx.stop();
+        Box::pin(
+            sleep(Duration::from_millis(20))
+                .into_actor(self)
+                .map(|_, _, _| 0),
+        )
+    }
+}
+
+#[actix::test]
+async fn test_supervisor_queued_requests_survive_restart() {
+    let log = Log::default();
+    let log2 = Arc::clone(&log);
+    let addr = SupervisorBuilder::new(move |_| Conn(log2)).start();
+
+    // the request in flight when the actor stops is never answered
+    let crash = addr.send(Crash);
+    let queued = addr.send(Work(1));
+    // nobody waits for the response, so it is skipped
+    drop(addr.send(Work(2)));
+    let last = addr.send(Work(3));
+
+    assert_eq!(crash.await, Err(MailboxError::Closed));
+    assert_eq!(queued.await, Ok(()));
+    assert_eq!(last.await, Ok(()));
+    assert_eq!(
+        events(&log),
+        vec![
+            "started",
+            "crash",
+            "restarting",
+            "started",
+            "work 1",
+            "work 3"
+        ]
+    );
+}
+
+/// Returns a factory panicking `panics` times before it creates the actor.
+fn flaky_factory(
+    log: &Log,
+    calls: &Arc<AtomicUsize>,
+    panics: usize,
+) -> impl FnMut(&mut Context<Conn>) -> Conn {
+    let log = Arc::clone(log);
+    let calls = Arc::clone(calls);
+    move |_| {
+        if calls.fetch_add(1, Ordering::SeqCst) < panics {
+            panic!("connection refused");
+        }
+        Conn(Arc::clone(&log))
+    }
+}
+
+struct Observer(Arc<Mutex<Vec<SupervisionFailed>>>);
+
+impl Actor for Observer {
+    type Context = Context<Self>;
+}
+
+impl Handler<SupervisionFailed> for Observer {
+    type Result = ();
+
+    fn handle(&mut self, msg: SupervisionFailed, _: &mut Self::Context) {
+        self.0.lock().unwrap().push(msg);
+    }
+}
+
+#[actix::test]
+async fn test_supervisor_builder_retrying_factory() {
+    let log = Log::default();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = SupervisorBuilder::new_retrying(flaky_factory(&log, &calls, 2))
+        .restart_policy(RestartPolicy::Limit(2))
+        .on_start_send(Connect)
+        .start();
+
+    addr.send(Work(1)).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+    assert_eq!(events(&log), vec!["started", "connect", "work 1"]);
+
+    // the failed attempts count as restarts
+    addr.send(Die).await.unwrap();
+    sleep(Duration::from_millis(50)).await;
+    assert!(!addr.connected());
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[actix::test]
+async fn test_supervisor_builder_retrying_factory_exhausted() {
+    let failures = Arc::new(Mutex::new(Vec::new()));
+    let observer = Observer(Arc::clone(&failures)).start();
+
+    let log = Log::default();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = SupervisorBuilder::new_retrying(flaky_factory(&log, &calls, 2))
+        .restart_policy(RestartPolicy::Limit(1))
+        .lazy(true)
+        .on_failure(observer.recipient())
+        .start();
+
+    assert_eq!(addr.send(Work(1)).await, Err(MailboxError::Closed));
+    assert!(!addr.connected());
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert!(events(&log).is_empty());
+
+    sleep(Duration::from_millis(20)).await;
+    let failures = failures.lock().unwrap().clone();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].path, vec![std::any::type_name::<Conn>()]);
+    assert_eq!(failures[0].restarts, 1);
+}
+
+#[actix::test]
+async fn test_supervisor_panicking_factory() {
+    let log = Log::default();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = SupervisorBuilder::new(flaky_factory(&log, &calls, 1)).start();
+
+    assert_eq!(addr.send(Work(1)).await, Err(MailboxError::Closed));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let addr = Supervisor::start(|_| -> Conn { panic!("connection refused") });
+    assert!(!addr.connected());
+    assert_eq!(addr.send(Work(1)).await, Err(MailboxError::Closed));
+}
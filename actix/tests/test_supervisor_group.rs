@@ -0,0 +1,161 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix::prelude::*;
+use actix_rt::time::sleep;
+
+/// Latest address and number of starts of each leaf, along with the reported failures.
+#[derive(Default)]
+struct Tree {
+    leaves: HashMap<&'static str, (Addr<Leaf>, usize)>,
+    failures: Vec<SupervisionFailed>,
+}
+
+type Shared = Arc<Mutex<Tree>>;
+
+struct Leaf {
+    name: &'static str,
+    shared: Shared,
+}
+
+impl Actor for Leaf {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let mut tree = self.shared.lock().unwrap();
+        let starts = tree.leaves.get(self.name).map_or(0, |(_, starts)| *starts);
+        tree.leaves.insert(self.name, (ctx.address(), starts + 1));
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Die;
+
+impl Handler<Die> for Leaf {
+    type Result = ();
+
+    fn handle(&mut self, _: Die, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+}
+
+struct Observer(Shared);
+
+impl Actor for Observer {
+    type Context = Context<Self>;
+}
+
+impl Handler<SupervisionFailed> for Observer {
+    type Result = ();
+
+    fn handle(&mut self, msg: SupervisionFailed, _: &mut Self::Context) {
+        self.0.lock().unwrap().failures.push(msg);
+    }
+}
+
+fn leaf(shared: &Shared, name: &'static str) -> impl Fn() -> Addr<Leaf> {
+    let shared = Arc::clone(shared);
+    move || {
+        Leaf {
+            name,
+            shared: Arc::clone(&shared),
+        }
+        .start()
+    }
+}
+
+async fn kill(shared: &Shared, name: &'static str) {
+    let addr = shared.lock().unwrap().leaves[name].0.clone();
+    addr.do_send(Die);
+    sleep(Duration::from_millis(20)).await;
+}
+
+fn starts(shared: &Shared, name: &'static str) -> usize {
+    shared.lock().unwrap().leaves[name].1
+}
+
+#[actix::test]
+async fn test_supervisor_group_escalates() {
+    let shared = Shared::default();
+    let observer = Observer(Arc::clone(&shared)).start();
+
+    let mid = SupervisorGroup::new("mid")
+        .restart_policy(RestartPolicy::Limit(2))
+        .child("leaf", leaf(&shared, "leaf"))
+        .child("sibling", leaf(&shared, "sibling"));
+    let _root = SupervisorGroup::new("root")
+        .on_failure(observer.recipient())
+        .group(mid)
+        .start();
+    sleep(Duration::from_millis(20)).await;
+
+    // the leaf is restarted on its own within its budget
+    kill(&shared, "leaf").await;
+    kill(&shared, "leaf").await;
+    assert_eq!(starts(&shared, "leaf"), 3);
+    assert_eq!(starts(&shared, "sibling"), 1);
+    assert!(shared.lock().unwrap().failures.is_empty());
+
+    // the mid-level group fails and is restarted with both leaves
+    kill(&shared, "leaf").await;
+    assert_eq!(starts(&shared, "leaf"), 4);
+    assert_eq!(starts(&shared, "sibling"), 2);
+
+    let failures = std::mem::take(&mut shared.lock().unwrap().failures);
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].path, ["root", "mid", "leaf"]);
+    assert_eq!(failures[0].restarts, 2);
+
+    // the restarted group has a fresh budget
+    kill(&shared, "leaf").await;
+    assert_eq!(starts(&shared, "leaf"), 5);
+    assert_eq!(starts(&shared, "sibling"), 2);
+}
+
+#[actix::test]
+async fn test_supervisor_group_one_for_all() {
+    let shared = Shared::default();
+
+    let root = SupervisorGroup::new("root")
+        .strategy(GroupStrategy::OneForAll)
+        .child("first", leaf(&shared, "first"))
+        .child("second", leaf(&shared, "second"))
+        .start();
+    sleep(Duration::from_millis(20)).await;
+    let second = shared.lock().unwrap().leaves["second"].0.clone();
+
+    kill(&shared, "first").await;
+    assert_eq!(starts(&shared, "first"), 2);
+    assert_eq!(starts(&shared, "second"), 2);
+
+    // the replaced sibling is stopped, although its address is still held
+    assert!(!second.connected());
+    assert!(root.connected());
+}
+
+#[actix::test]
+async fn test_supervisor_group_root_stops() {
+    let shared = Shared::default();
+    let observer = Observer(Arc::clone(&shared)).start();
+
+    let root = SupervisorGroup::new("root")
+        .restart_policy(RestartPolicy::Never)
+        .on_failure(observer.recipient())
+        .child("leaf", leaf(&shared, "leaf"))
+        .start();
+    sleep(Duration::from_millis(20)).await;
+
+    kill(&shared, "leaf").await;
+    assert_eq!(starts(&shared, "leaf"), 1);
+    assert!(!root.connected());
+
+    let failures = &shared.lock().unwrap().failures;
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].path, ["root", "leaf"]);
+}
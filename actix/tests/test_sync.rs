@@ -1,9 +1,22 @@
-use std::sync::{
-    atomic::{AtomicUsize, Ordering},
-    Arc, Condvar, Mutex,
+use std::{
+    any::Any,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::Duration,
 };
 
-use actix::prelude::*;
+use actix::{
+    prelude::*,
+    sync::{
+        AutoscaleConfig, ChunkError, ChunkPolicy, Chunked, Pool, PoolError, PoolStats, Progress,
+        ProgressSender, ScopeError,
+    },
+    EnvelopeScope,
+};
+use futures_util::stream::StreamExt as _;
 
 struct Fibonacci(pub u32);
 
@@ -100,3 +113,500 @@ fn test_sync() {
         "Wrong number of messages"
     );
 }
+
+struct Worker;
+
+impl Actor for Worker {
+    type Context = SyncContext<Self>;
+}
+
+struct Job(u32);
+
+impl Message for Job {
+    type Result = (u32, bool);
+}
+
+impl Handler<Job> for Worker {
+    type Result = MessageResult<Job>;
+
+    fn handle(&mut self, job: Job, ctx: &mut Self::Context) -> Self::Result {
+        // progress of another type is not available
+        assert!(ctx.progress::<String>().is_none());
+
+        let progress: Option<ProgressSender<u32>> = ctx.progress();
+        if let Some(progress) = &progress {
+            for step in 0..job.0 {
+                progress.send(step);
+            }
+        }
+        let connected = progress.map_or(false, |progress| progress.connected());
+        MessageResult((job.0, connected))
+    }
+}
+
+#[derive(Default)]
+struct Caller(Arc<Mutex<Vec<u32>>>);
+
+impl Actor for Caller {
+    type Context = Context<Self>;
+}
+
+impl Handler<Progress<u32>> for Caller {
+    type Result = ();
+
+    fn handle(&mut self, Progress(step): Progress<u32>, _: &mut Self::Context) {
+        self.0.lock().unwrap().push(step);
+    }
+}
+
+#[test]
+fn test_sync_progress() {
+    System::new().block_on(async {
+        let worker = SyncArbiter::start(1, || Worker);
+        let steps = Arc::new(Mutex::new(Vec::new()));
+        let caller = Caller(Arc::clone(&steps)).start();
+
+        let res = worker.send_with_progress(Job(3), caller.clone().recipient());
+        assert_eq!(res.await, Ok((3, true)));
+        actix_rt::time::sleep(std::time::Duration::from_millis(10)).await;
+        assert_eq!(*steps.lock().unwrap(), [0, 1, 2]);
+
+        // the progress sender is only available to messages sent with progress
+        assert_eq!(worker.send(Job(3)).await, Ok((3, false)));
+
+        // progress is dropped once the caller is gone
+        let gone = Caller::create(|ctx| {
+            ctx.stop();
+            Caller::default()
+        })
+        .recipient();
+        actix_rt::time::sleep(std::time::Duration::from_millis(10)).await;
+        assert!(!gone.connected());
+
+        let res = worker.send_with_progress(Job(2), gone);
+        assert_eq!(res.await, Ok((2, false)));
+    });
+}
+
+struct PoolWorker {
+    id: usize,
+    jobs: usize,
+}
+
+#[test]
+fn test_pool() {
+    System::new().block_on(async {
+        let created = Arc::new(AtomicUsize::new(0));
+        let factory = move || PoolWorker {
+            id: created.fetch_add(1, Ordering::SeqCst),
+            jobs: 0,
+        };
+
+        let single = Pool::new(1, factory.clone());
+        let res = single
+            .exec(|worker| {
+                worker.jobs += 1;
+                (worker.id, worker.jobs)
+            })
+            .await;
+        assert_eq!(res, Ok((0, 1)));
+
+        // a panicking job replaces its worker
+        let res = single.exec(|_| -> usize { panic!("job failure") }).await;
+        assert_eq!(res, Err(PoolError::Panicked));
+        assert_eq!(single.exec(|worker| worker.id).await, Ok(1));
+        single.shutdown().await;
+
+        let pool = Pool::new(4, factory);
+
+        // results are delivered in submission order, although later jobs finish first
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let jobs: Vec<_> = (0..4u64)
+            .map(|n| {
+                let res = pool.exec_ordered(move |worker| {
+                    thread::sleep(Duration::from_millis(40 - n * 10));
+                    (n, worker.id)
+                });
+                let order = Arc::clone(&order);
+                actix_rt::spawn(async move {
+                    let (n, _) = res.await.unwrap();
+                    order.lock().unwrap().push(n);
+                })
+            })
+            .collect();
+        for job in jobs {
+            job.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), [0, 1, 2, 3]);
+
+        // shutdown waits for jobs which are still running
+        let done = Arc::new(AtomicUsize::new(0));
+        let jobs: Vec<_> = (0..8)
+            .map(|_| {
+                let done = Arc::clone(&done);
+                actix_rt::spawn(pool.exec(move |_| {
+                    thread::sleep(Duration::from_millis(10));
+                    done.fetch_add(1, Ordering::SeqCst);
+                }))
+            })
+            .collect();
+        pool.shutdown().await;
+        assert_eq!(done.load(Ordering::SeqCst), 8);
+        for job in jobs {
+            assert_eq!(job.await.unwrap(), Ok(()));
+        }
+    });
+}
+
+struct Sleeper {
+    stopped: Arc<AtomicUsize>,
+}
+
+impl Actor for Sleeper {
+    type Context = SyncContext<Self>;
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.stopped.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+struct Sleep(u64);
+
+impl Message for Sleep {
+    type Result = ();
+}
+
+impl Handler<Sleep> for Sleeper {
+    type Result = ();
+
+    fn handle(&mut self, Sleep(ms): Sleep, _: &mut Self::Context) {
+        thread::sleep(Duration::from_millis(ms));
+    }
+}
+
+#[test]
+fn test_sync_autoscaling() {
+    let stopped = Arc::new(AtomicUsize::new(0));
+
+    System::new().block_on(async {
+        let config = AutoscaleConfig {
+            min: 1,
+            max: 3,
+            target_wait: Duration::from_millis(5),
+            evaluation_interval: Duration::from_millis(20),
+            max_idle: 0,
+            idle_period: Duration::from_millis(60),
+        };
+        let (addr, control) = SyncArbiter::start_autoscaling(config, {
+            let stopped = Arc::clone(&stopped);
+            move || Sleeper {
+                stopped: Arc::clone(&stopped),
+            }
+        });
+        let stats = control.send(PoolStats).await.unwrap();
+        assert_eq!((stats.size, stats.samples), (1, 0));
+
+        // queued messages grow the pool up to its maximum
+        let jobs: Vec<_> = (0..30).map(|_| addr.send(Sleep(20))).collect();
+        actix_rt::time::sleep(Duration::from_millis(150)).await;
+        let stats = control.send(PoolStats).await.unwrap();
+        assert_eq!(stats.size, 3);
+        assert!(stats.wait_p95 > Duration::from_millis(5));
+        for job in jobs {
+            job.await.unwrap();
+        }
+
+        // idle threads are retired down to the minimum
+        actix_rt::time::sleep(Duration::from_millis(200)).await;
+        let stats = control.send(PoolStats).await.unwrap();
+        assert_eq!((stats.size, stats.busy, stats.queued), (1, 0, 0));
+        assert_eq!(stopped.load(Ordering::SeqCst), 2);
+        assert_eq!(addr.send(Sleep(0)).await, Ok(()));
+
+        // the control actor stops with its pool
+        drop(addr);
+        actix_rt::time::sleep(Duration::from_millis(50)).await;
+        assert!(!control.connected());
+        assert_eq!(stopped.load(Ordering::SeqCst), 3);
+    });
+}
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+struct Transaction {
+    log: Log,
+    fail_commit: Arc<AtomicBool>,
+}
+
+impl EnvelopeScope<Ledger> for Transaction {
+    fn before(&mut self, _: &mut Ledger) {
+        self.log.lock().unwrap().push("begin".to_owned());
+    }
+
+    fn after_ok(&mut self, _: &mut Ledger) -> Result<(), ScopeError> {
+        if self.fail_commit.load(Ordering::SeqCst) {
+            return Err("conflict".into());
+        }
+        self.log.lock().unwrap().push("commit".to_owned());
+        Ok(())
+    }
+
+    fn after_err(&mut self, _: &mut Ledger, cause: &dyn Any) {
+        let cause = match (cause.downcast_ref::<String>(), cause.downcast_ref::<&str>()) {
+            (Some(err), _) => err.clone(),
+            (_, Some(payload)) => payload.to_string(),
+            _ => "unknown".to_owned(),
+        };
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("rollback: {}", cause));
+    }
+}
+
+struct Ledger {
+    balance: i64,
+    scope: Option<Transaction>,
+}
+
+impl Actor for Ledger {
+    type Context = SyncContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if let Some(scope) = self.scope.take() {
+            ctx.set_envelope_scope(Box::new(scope));
+            ctx.map_scope_error::<Deposit, _, _, _>(|err| format!("commit failed: {}", err));
+        }
+    }
+}
+
+struct Deposit(i64);
+
+impl Message for Deposit {
+    type Result = Result<i64, String>;
+}
+
+impl Handler<Deposit> for Ledger {
+    type Result = Result<i64, String>;
+
+    fn handle(&mut self, Deposit(amount): Deposit, _: &mut Self::Context) -> Self::Result {
+        if amount < 0 {
+            return Err("negative amount".to_owned());
+        }
+        self.balance += amount;
+        Ok(self.balance)
+    }
+}
+
+struct Crash;
+
+impl Message for Crash {
+    type Result = ();
+}
+
+impl Handler<Crash> for Ledger {
+    type Result = ();
+
+    fn handle(&mut self, _: Crash, _: &mut Self::Context) {
+        panic!("boom");
+    }
+}
+
+#[test]
+fn test_sync_envelope_scope() {
+    let log = Log::default();
+    let fail_commit = Arc::new(AtomicBool::new(false));
+
+    let sys = System::new();
+    let (log_c, fail_commit_c) = (Arc::clone(&log), Arc::clone(&fail_commit));
+    sys.block_on(async move {
+        let scope = Mutex::new(Some(Transaction {
+            log: log_c,
+            fail_commit: Arc::clone(&fail_commit_c),
+        }));
+        let addr = SyncArbiter::start(1, move || Ledger {
+            balance: 0,
+            scope: scope.lock().unwrap().take(),
+        });
+
+        assert_eq!(addr.send(Deposit(5)).await.unwrap(), Ok(5));
+        assert_eq!(
+            addr.send(Deposit(-1)).await.unwrap(),
+            Err("negative amount".to_owned())
+        );
+
+        fail_commit_c.store(true, Ordering::SeqCst);
+        assert_eq!(
+            addr.send(Deposit(1)).await.unwrap(),
+            Err("commit failed: conflict".to_owned())
+        );
+        fail_commit_c.store(false, Ordering::SeqCst);
+
+        // the worker thread dies with the handler, after the rollback
+        assert!(addr.send(Crash).await.is_err());
+    });
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "begin",
+            "commit",
+            "begin",
+            "rollback: negative amount",
+            "begin",
+            "begin",
+            "rollback: boom",
+        ]
+    );
+}
+
+type Events = Arc<Mutex<Vec<String>>>;
+
+struct Streamer(Events);
+
+impl Actor for Streamer {
+    type Context = SyncContext<Self>;
+}
+
+struct Buffer(usize, ChunkPolicy);
+
+impl Message for Buffer {
+    type Result = ();
+}
+
+impl Handler<Buffer> for Streamer {
+    type Result = ();
+
+    fn handle(&mut self, Buffer(capacity, policy): Buffer, ctx: &mut Self::Context) {
+        ctx.set_chunk_buffer(capacity, policy);
+    }
+}
+
+struct Rows {
+    count: usize,
+    panic_at: Option<usize>,
+}
+
+impl Message for Rows {
+    type Result = Chunked<usize>;
+}
+
+impl Rows {
+    fn new(count: usize) -> Self {
+        Rows {
+            count,
+            panic_at: None,
+        }
+    }
+}
+
+impl Handler<Rows> for Streamer {
+    type Result = Response<Chunked<usize>>;
+
+    fn handle(&mut self, rows: Rows, ctx: &mut Self::Context) -> Self::Result {
+        let chunks = match ctx.chunked_reply::<usize>() {
+            Some(chunks) => chunks,
+            None => {
+                self.0.lock().unwrap().push("no reply".to_owned());
+                return Response::chunked();
+            }
+        };
+        for row in 0..rows.count {
+            if rows.panic_at == Some(row) {
+                panic!("row {} is corrupt", row);
+            }
+            let event = match chunks.send(row) {
+                Ok(()) => format!("sent {}", row),
+                Err(SendError::Full(_)) => "full".to_owned(),
+                Err(_) => "closed".to_owned(),
+            };
+            let done = !event.starts_with("sent");
+            self.0.lock().unwrap().push(event);
+            if done {
+                break;
+            }
+        }
+        Response::chunked()
+    }
+}
+
+fn sent(events: &Events) -> usize {
+    let events = events.lock().unwrap();
+    events.iter().filter(|ev| ev.starts_with("sent")).count()
+}
+
+#[test]
+fn test_sync_chunked_reply() {
+    System::new().block_on(async {
+        let events = Events::default();
+        let events2 = Arc::clone(&events);
+        let worker = SyncArbiter::start(1, move || Streamer(Arc::clone(&events2)));
+        worker.do_send(Buffer(2, ChunkPolicy::Block));
+
+        // the worker is blocked until the caller takes a chunk
+        let mut rows = worker.send(Rows::new(10)).await.unwrap();
+        actix_rt::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(sent(&events), 2);
+
+        assert_eq!(rows.next().await, Some(Ok(0)));
+        actix_rt::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(sent(&events), 3);
+
+        let rest: Vec<_> = rows.map(Result::unwrap).collect().await;
+        assert_eq!(rest, (1..10).collect::<Vec<_>>());
+
+        // dropping the stream unblocks the worker
+        events.lock().unwrap().clear();
+        let rows = worker.send(Rows::new(10)).await.unwrap();
+        actix_rt::time::sleep(Duration::from_millis(20)).await;
+        drop(rows);
+        actix_rt::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(
+            *events.lock().unwrap(),
+            ["sent 0", "sent 1", "closed"].map(String::from)
+        );
+
+        // no reply without a caller waiting for it
+        events.lock().unwrap().clear();
+        worker.do_send(Rows::new(10));
+        actix_rt::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(*events.lock().unwrap(), ["no reply".to_owned()]);
+    });
+}
+
+#[test]
+fn test_sync_chunked_reply_full() {
+    System::new().block_on(async {
+        let events = Events::default();
+        let events2 = Arc::clone(&events);
+        let worker = SyncArbiter::start(1, move || Streamer(Arc::clone(&events2)));
+        worker.do_send(Buffer(2, ChunkPolicy::Fail));
+
+        let rows = worker.send(Rows::new(10)).await.unwrap();
+        actix_rt::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(
+            *events.lock().unwrap(),
+            ["sent 0", "sent 1", "full"].map(String::from)
+        );
+        assert_eq!(rows.collect::<Vec<_>>().await, vec![Ok(0), Ok(1)]);
+    });
+}
+
+#[test]
+fn test_sync_chunked_reply_panic() {
+    System::new().block_on(async {
+        let worker = SyncArbiter::start(1, || Streamer(Events::default()));
+
+        let rows = worker
+            .send(Rows {
+                count: 5,
+                panic_at: Some(2),
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            rows.collect::<Vec<_>>().await,
+            vec![Ok(0), Ok(1), Err(ChunkError::Panicked)]
+        );
+    });
+}
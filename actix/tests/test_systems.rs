@@ -0,0 +1,116 @@
+#![cfg(feature = "macros")]
+
+use std::{sync::mpsc, thread, time::Duration};
+
+use actix::{prelude::*, SystemConfig};
+
+#[derive(Message)]
+#[rtype(result = "usize")]
+struct Get;
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Set(usize);
+
+#[derive(Default)]
+struct Value(usize);
+
+impl Actor for Value {
+    type Context = Context<Self>;
+}
+
+impl Supervised for Value {}
+impl SystemService for Value {}
+impl ArbiterService for Value {}
+
+impl Handler<Get> for Value {
+    type Result = usize;
+
+    fn handle(&mut self, _: Get, _: &mut Self::Context) -> usize {
+        self.0
+    }
+}
+
+impl Handler<Set> for Value {
+    type Result = ();
+
+    fn handle(&mut self, msg: Set, _: &mut Self::Context) {
+        self.0 = msg.0;
+    }
+}
+
+#[test]
+fn test_concurrent_systems_are_isolated() {
+    let (ready_tx, ready_rx) = mpsc::channel();
+    let (stop_tx, stop_rx) = mpsc::channel::<()>();
+
+    // first system sets its services and config, then is stopped while the second one runs
+    let first = thread::spawn(move || {
+        SystemConfig::new()
+            .mailbox_capacity(1024)
+            .build()
+            .block_on(async move {
+                <Value as SystemService>::from_registry()
+                    .send(Set(1))
+                    .await
+                    .unwrap();
+                let arbiter = Arbiter::new();
+                arbiter.spawn(async {
+                    <Value as ArbiterService>::from_registry().do_send(Set(1));
+                });
+                ready_tx.send(()).unwrap();
+
+                actix_rt::task::spawn_blocking(move || stop_rx.recv().unwrap())
+                    .await
+                    .unwrap();
+                System::current().stop();
+            });
+    });
+
+    ready_rx.recv().unwrap();
+
+    System::new().block_on(async move {
+        assert_eq!(SystemConfig::current().get_mailbox_capacity(), 16);
+        let sys_value = <Value as SystemService>::from_registry();
+        assert_eq!(sys_value.send(Get).await.unwrap(), 0);
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let arbiter = Arbiter::new();
+        arbiter.spawn(async move {
+            let addr = <Value as ArbiterService>::from_registry();
+            let _ = tx.send(addr.send(Get).await.unwrap());
+        });
+        assert_eq!(rx.await.unwrap(), 0);
+
+        // stopping the first system does not affect this one's actors and arbiters
+        stop_tx.send(()).unwrap();
+        first.join().unwrap();
+
+        sys_value.do_send(Set(2));
+        assert_eq!(sys_value.send(Get).await.unwrap(), 2);
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        assert!(arbiter.spawn(async move {
+            actix_rt::time::sleep(Duration::from_millis(10)).await;
+            let _ = tx.send(());
+        }));
+        rx.await.unwrap();
+        arbiter.stop();
+    });
+}
+
+#[test]
+fn test_sequential_systems_on_one_thread() {
+    System::new().block_on(async {
+        let addr = <Value as ArbiterService>::from_registry();
+        addr.send(Set(1)).await.unwrap();
+        assert!(System::current().arbiter().spawn(async {}));
+    });
+
+    System::new().block_on(async {
+        let addr = <Value as ArbiterService>::from_registry();
+        assert_eq!(addr.send(Get).await.unwrap(), 0);
+        let addr = <Value as SystemService>::from_registry();
+        assert_eq!(addr.send(Get).await.unwrap(), 0);
+    });
+}
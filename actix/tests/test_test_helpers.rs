@@ -0,0 +1,253 @@
+use std::{
+    panic,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use actix::{
+    prelude::*,
+    test::{block_on_call, run_system, tap, ManualClock, TestCallError},
+};
+
+struct Echo {
+    stopped: Arc<AtomicBool>,
+}
+
+impl Echo {
+    fn start(stopped: &Arc<AtomicBool>) -> Addr<Self> {
+        let stopped = Arc::clone(stopped);
+        Echo::start_in_arbiter(&Arbiter::current(), |_| Echo { stopped })
+    }
+}
+
+impl Actor for Echo {
+    type Context = Context<Self>;
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug)]
+struct Ping(u32);
+
+impl Message for Ping {
+    type Result = u32;
+}
+
+impl Handler<Ping> for Echo {
+    type Result = u32;
+
+    fn handle(&mut self, msg: Ping, _: &mut Self::Context) -> u32 {
+        msg.0
+    }
+}
+
+struct Slow;
+
+impl Message for Slow {
+    type Result = ();
+}
+
+impl Handler<Slow> for Echo {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, _: Slow, _: &mut Self::Context) -> Self::Result {
+        Box::pin(actix_rt::time::sleep(Duration::from_secs(10)).into_actor(self))
+    }
+}
+
+#[test]
+fn test_block_on_call() {
+    let stopped = Arc::new(AtomicBool::new(false));
+
+    run_system(|| {
+        let addr = Echo::start(&stopped);
+        assert_eq!(block_on_call(&addr, Ping(1), Duration::from_secs(1)), Ok(1));
+        assert_eq!(block_on_call(&addr, Ping(2), Duration::from_secs(1)), Ok(2));
+        assert_eq!(
+            block_on_call(&addr, Slow, Duration::from_millis(20)),
+            Err(TestCallError::Timeout)
+        );
+    });
+
+    // the system is torn down when the closure returns
+    assert!(stopped.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_block_on_call_without_system() {
+    let (tx, rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        System::new().block_on(async move {
+            let stopped = Arc::new(AtomicBool::new(false));
+            let _ = tx.send(Echo { stopped }.start());
+            actix_rt::time::sleep(Duration::from_millis(200)).await;
+        })
+    });
+
+    let addr = rx.recv().unwrap();
+    assert_eq!(block_on_call(&addr, Ping(3), Duration::from_secs(1)), Ok(3));
+
+    handle.join().unwrap();
+    assert_eq!(
+        block_on_call(&addr, Ping(4), Duration::from_secs(1)),
+        Err(TestCallError::Mailbox(MailboxError::Closed))
+    );
+}
+
+#[test]
+fn test_run_system_panic() {
+    let stopped = Arc::new(AtomicBool::new(false));
+
+    let res = panic::catch_unwind(|| {
+        run_system(|| {
+            let addr = Echo::start(&stopped);
+            assert_eq!(block_on_call(&addr, Ping(5), Duration::from_secs(1)), Ok(5));
+            panic!("test failure");
+        })
+    });
+    assert!(res.is_err());
+    assert!(stopped.load(Ordering::SeqCst));
+
+    // the next system starts from a clean state
+    let res = run_system(|| {
+        let addr = Echo::start(&stopped);
+        block_on_call(&addr, Ping(6), Duration::from_secs(1))
+    });
+    assert_eq!(res, Ok(6));
+}
+
+#[test]
+#[should_panic(expected = "within a running runtime")]
+fn test_block_on_call_in_runtime() {
+    System::new().block_on(async {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let addr = Echo { stopped }.start();
+        let _ = block_on_call(&addr, Ping(7), Duration::from_secs(1));
+    });
+}
+
+#[test]
+fn test_tap() {
+    System::new().block_on(async {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let addr = Echo { stopped }.start();
+        let (tapped, log) = tap(addr.clone());
+        log.format::<Ping>();
+
+        // responses are routed back through the tapped address
+        assert_eq!(tapped.send(Ping(1)).await.unwrap(), 1);
+        tapped.do_send(Slow);
+        tapped.clone().recipient::<Ping>().do_send(Ping(2));
+        assert!(tapped.try_send(Ping(3)).is_ok());
+
+        // messages sent through other addresses are not recorded
+        assert_eq!(addr.send(Ping(4)).await.unwrap(), 4);
+
+        let records = log.records();
+        assert_eq!(
+            records
+                .iter()
+                .map(|rec| (rec.type_name.rsplit("::").next().unwrap(), rec.ask))
+                .collect::<Vec<_>>(),
+            [
+                ("Ping", true),
+                ("Slow", false),
+                ("Ping", false),
+                ("Ping", false)
+            ]
+        );
+        assert_eq!(
+            records
+                .iter()
+                .map(|rec| rec.debug.as_deref())
+                .collect::<Vec<_>>(),
+            [Some("Ping(1)"), None, Some("Ping(2)"), Some("Ping(3)")]
+        );
+        assert!(records.windows(2).all(|w| w[0].sent_at <= w[1].sent_at));
+
+        log.clear();
+        assert!(log.is_empty());
+    });
+}
+
+#[test]
+fn test_tap_wait_for() {
+    System::new().block_on(async {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let (addr, log) = tap(Echo { stopped }.start());
+        log.format::<Ping>();
+
+        // recorded while waiting, from another thread
+        let sender = addr.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            for n in 0..3 {
+                sender.do_send(Ping(n));
+            }
+        });
+
+        let rec = log
+            .wait_for(
+                |rec| rec.debug.as_deref() == Some("Ping(2)"),
+                Duration::from_secs(1),
+            )
+            .await
+            .unwrap();
+        assert!(!rec.ask);
+        assert_eq!(log.len(), 3);
+        handle.join().unwrap();
+
+        // earlier records match as well, and missing ones time out
+        assert!(log
+            .wait_for(|rec| !rec.ask, Duration::from_millis(10))
+            .await
+            .is_some());
+        assert!(log
+            .wait_for(|rec| rec.ask, Duration::from_millis(10))
+            .await
+            .is_none());
+    });
+}
+
+struct Pause;
+
+impl Message for Pause {
+    type Result = ();
+}
+
+impl Handler<Pause> for Echo {
+    type Result = ();
+
+    fn handle(&mut self, _: Pause, ctx: &mut Self::Context) {
+        ctx.wait(actix_rt::time::sleep(Duration::from_secs(3600)).into_actor(self));
+    }
+}
+
+#[test]
+fn test_manual_clock_request_timeout() {
+    System::new().block_on(async {
+        let clock = ManualClock::new();
+        actix::clock::set_arbiter_clock(clock.clone());
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let addr = Echo { stopped }.start();
+        addr.do_send(Pause);
+
+        // the timeout only expires once the clock is advanced past it
+        let req = actix_rt::spawn(addr.send(Ping(1)).timeout(Duration::from_secs(5)));
+        actix_rt::time::sleep(Duration::from_millis(10)).await;
+        clock.advance(Duration::from_secs(4)).await;
+        assert!(!req.is_finished());
+
+        clock.advance(Duration::from_secs(1)).await;
+        assert_eq!(req.await.unwrap(), Err(MailboxError::Timeout));
+        assert_eq!(clock.elapsed(), Duration::from_secs(5));
+        actix::clock::clear_arbiter_clock();
+    });
+}
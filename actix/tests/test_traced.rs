@@ -0,0 +1,99 @@
+#![cfg(feature = "telemetry")]
+
+use std::{
+    sync::{Mutex, Once},
+    time::Duration,
+};
+
+use actix::prelude::*;
+use log::{LevelFilter, Log, Metadata, Record};
+
+static RECORDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Collects records of this test's actors.
+struct TestLogger;
+
+impl Log for TestLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target().starts_with("test_traced")
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.enabled(record.metadata()) {
+            RECORDS.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+struct Pipeline;
+
+impl Actor for Pipeline {
+    type Context = Context<Self>;
+}
+
+struct Run;
+
+impl Message for Run {
+    type Result = u32;
+}
+
+impl Handler<Run> for Pipeline {
+    type Result = ResponseActFuture<Self, u32>;
+
+    fn handle(&mut self, _: Run, _: &mut Self::Context) -> Self::Result {
+        actix_rt::time::sleep(Duration::from_millis(5))
+            .into_actor(self)
+            .traced("sleep")
+            .map(|_, _, _| 1)
+            .traced("one")
+            .then(|n, act, _| async move { n + 1 }.into_actor(act).traced("two"))
+            .traced("then")
+            .map(|n, _, _| n * 2)
+            .traced("pipeline")
+            .boxed_local()
+    }
+}
+
+#[test]
+fn test_traced_stages() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        log::set_logger(&TestLogger).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+    });
+
+    System::new().block_on(async {
+        assert_eq!(Pipeline.start().send(Run).await.unwrap(), 4);
+    });
+
+    // stages log their latency once resolved, besides the context's own records
+    let records = RECORDS.lock().unwrap();
+    let events: Vec<_> = records
+        .iter()
+        .filter(|record| !record.starts_with("actor_type="))
+        .map(|record| match record.split_once(" in ") {
+            Some((event, latency)) => {
+                assert!(latency.ends_with('s'), "{record}");
+                event
+            }
+            None => record,
+        })
+        .collect();
+    assert_eq!(
+        events,
+        [
+            "pipeline: polled",
+            "then: polled",
+            "one: polled",
+            "sleep: polled",
+            "sleep: resolved",
+            "one: resolved",
+            "two: polled",
+            "two: resolved",
+            "then: resolved",
+            "pipeline: resolved",
+        ]
+    );
+}
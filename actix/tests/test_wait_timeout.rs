@@ -0,0 +1,140 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use actix::prelude::*;
+use actix_rt::time::{sleep, Instant};
+
+type Log = Arc<Mutex<Vec<&'static str>>>;
+
+struct Blocked {
+    log: Log,
+    /// Queues a wait future in front of the bounded one.
+    head: Option<Duration>,
+    polled: Arc<AtomicBool>,
+}
+
+impl Actor for Blocked {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        assert_eq!(ctx.waiting_since(), None);
+
+        let log = Arc::clone(&self.log);
+        let polled = Arc::clone(&self.polled);
+        let inner = async move {
+            polled.store(true, Ordering::SeqCst);
+            sleep(Duration::from_secs(1)).await;
+            log.lock().unwrap().push("inner");
+        };
+        ctx.wait_timeout(
+            inner.into_actor(self),
+            Duration::from_millis(20),
+            |act, _| act.log.lock().unwrap().push("timed out"),
+        );
+        let since = ctx.waiting_since().unwrap();
+
+        if let Some(dur) = self.head {
+            // the most recent wait future runs first
+            let log = Arc::clone(&self.log);
+            ctx.wait(
+                async move {
+                    sleep(dur).await;
+                    log.lock().unwrap().push("head");
+                }
+                .into_actor(self),
+            );
+            assert_eq!(ctx.waiting_since(), Some(since));
+        }
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "(Vec<&'static str>, Option<Instant>)")]
+struct Check;
+
+impl Handler<Check> for Blocked {
+    type Result = MessageResult<Check>;
+
+    fn handle(&mut self, _: Check, ctx: &mut Self::Context) -> Self::Result {
+        MessageResult((self.log.lock().unwrap().clone(), ctx.waiting_since()))
+    }
+}
+
+fn start(head: Option<Duration>) -> (Addr<Blocked>, Arc<AtomicBool>) {
+    let polled = Arc::new(AtomicBool::new(false));
+    let addr = Blocked {
+        log: Log::default(),
+        head,
+        polled: Arc::clone(&polled),
+    }
+    .start();
+    (addr, polled)
+}
+
+#[actix::test]
+async fn test_wait_timeout_resumes_mailbox() {
+    let start_time = Instant::now();
+    let (addr, polled) = start(None);
+
+    let (log, waiting_since) = addr.send(Check).await.unwrap();
+    assert_eq!(log, ["timed out"]);
+    assert_eq!(waiting_since, None);
+    assert!(polled.load(Ordering::SeqCst));
+    assert!(start_time.elapsed() < Duration::from_millis(500));
+
+    // the inner future was dropped
+    sleep(Duration::from_millis(1100)).await;
+    assert_eq!(addr.send(Check).await.unwrap().0, ["timed out"]);
+}
+
+#[actix::test]
+async fn test_wait_timeout_expires_behind_other_wait() {
+    let start_time = Instant::now();
+    let (addr, polled) = start(Some(Duration::from_millis(60)));
+
+    // the deadline passes while the head of the queue runs, the bounded future then times out
+    // without being polled
+    let (log, waiting_since) = addr.send(Check).await.unwrap();
+    assert_eq!(log, ["head", "timed out"]);
+    assert_eq!(waiting_since, None);
+    assert!(!polled.load(Ordering::SeqCst));
+
+    let elapsed = start_time.elapsed();
+    assert!(elapsed >= Duration::from_millis(60));
+    assert!(elapsed < Duration::from_millis(500));
+}
+
+#[actix::test]
+async fn test_wait_timeout_completes_in_time() {
+    struct Quick(Log);
+
+    impl Actor for Quick {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            let log = Arc::clone(&self.0);
+            ctx.wait_timeout(
+                async move {
+                    sleep(Duration::from_millis(10)).await;
+                    log.lock().unwrap().push("done");
+                }
+                .into_actor(self),
+                Duration::from_millis(200),
+                |act, _| act.0.lock().unwrap().push("timed out"),
+            );
+        }
+    }
+
+    let log = Log::default();
+    let _addr = Quick(Arc::clone(&log)).start();
+
+    sleep(Duration::from_millis(300)).await;
+    assert_eq!(*log.lock().unwrap(), ["done"]);
+}
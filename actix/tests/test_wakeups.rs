@@ -0,0 +1,100 @@
+#![cfg(feature = "telemetry")]
+
+use std::time::Duration;
+
+use actix::{prelude::*, ContextStats, WakeupCause};
+use actix_rt::time::sleep;
+
+struct Sleeper;
+
+impl Actor for Sleeper {
+    type Context = Context<Self>;
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+enum Op {
+    Timer,
+    Spawn,
+    Defer(usize),
+    Noop,
+}
+
+impl Handler<Op> for Sleeper {
+    type Result = ();
+
+    fn handle(&mut self, op: Op, ctx: &mut Self::Context) {
+        match op {
+            Op::Timer => ctx.set_timer("tick", Duration::from_millis(10), Op::Noop),
+            Op::Spawn => {
+                ctx.spawn(sleep(Duration::from_millis(10)).into_actor(self));
+            }
+            Op::Defer(n) => defer(n, ctx),
+            Op::Noop => {}
+        }
+    }
+}
+
+fn defer(n: usize, ctx: &mut Context<Sleeper>) {
+    if n > 0 {
+        ctx.defer_fn(move |_, ctx| defer(n - 1, ctx));
+    }
+}
+
+struct Wakeups;
+
+impl Message for Wakeups {
+    type Result = (Vec<WakeupCause>, ContextStats);
+}
+
+impl Handler<Wakeups> for Sleeper {
+    type Result = MessageResult<Wakeups>;
+
+    fn handle(&mut self, _: Wakeups, ctx: &mut Self::Context) -> Self::Result {
+        MessageResult((ctx.recent_wakeups(), ctx.stats()))
+    }
+}
+
+async fn woken_by(addr: &Addr<Sleeper>, cause: WakeupCause) -> bool {
+    let (recent, stats) = addr.send(Wakeups).await.unwrap();
+    assert!(recent.len() <= 32);
+    assert!(stats.wakeups.get(cause) > 0, "{:?} {:?}", cause, recent);
+    recent.contains(&cause)
+}
+
+#[actix::test]
+async fn test_wakeup_causes() {
+    let addr = Sleeper.start();
+    sleep(Duration::from_millis(10)).await;
+
+    // polled the first time, then woken up by the request
+    let (recent, stats) = addr.send(Wakeups).await.unwrap();
+    assert_eq!(recent, [WakeupCause::Other, WakeupCause::Mailbox]);
+    assert_eq!(stats.wakeups.mailbox, 1);
+
+    addr.do_send(Op::Timer);
+    sleep(Duration::from_millis(50)).await;
+    assert!(woken_by(&addr, WakeupCause::Timer).await);
+
+    addr.do_send(Op::Spawn);
+    sleep(Duration::from_millis(50)).await;
+    assert!(woken_by(&addr, WakeupCause::Future).await);
+    assert!(woken_by(&addr, WakeupCause::Modified).await);
+
+    addr.interrupt();
+    sleep(Duration::from_millis(10)).await;
+    assert!(woken_by(&addr, WakeupCause::Interrupt).await);
+
+    addr.do_send(Op::Defer(40));
+    sleep(Duration::from_millis(10)).await;
+    assert!(woken_by(&addr, WakeupCause::Deferred).await);
+
+    // only a bounded number of causes is kept
+    for _ in 0..40 {
+        addr.send(Op::Noop).await.unwrap();
+    }
+    let (recent, stats) = addr.send(Wakeups).await.unwrap();
+    assert_eq!(recent.len(), 32);
+    assert!(!recent.contains(&WakeupCause::Timer));
+    assert!(stats.wakeups.mailbox > 40);
+}
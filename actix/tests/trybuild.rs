@@ -0,0 +1,43 @@
+#![cfg(feature = "macros")]
+
+/// Compile tests of the protocols declared with `protocol!`.
+///
+/// Only run on MSRV to ensure stable compile errors.
+#[rustversion::stable(1.68)]
+#[test]
+fn compile_protocols() {
+    let t = trybuild::TestCases::new();
+
+    t.pass("tests/trybuild/protocol.rs");
+    t.compile_fail("tests/trybuild/protocol-fail-out-of-order.rs");
+    t.compile_fail("tests/trybuild/protocol-fail-after-end.rs");
+    t.compile_fail("tests/trybuild/protocol-fail-result.rs");
+}
+
+/// Compile tests of handler return types which don't match the result of their message.
+///
+/// Only run on MSRV to ensure stable compile errors.
+#[rustversion::stable(1.68)]
+#[test]
+fn compile_message_responses() {
+    let t = trybuild::TestCases::new();
+
+    t.compile_fail("tests/trybuild/message-response-fail-option.rs");
+    t.compile_fail("tests/trybuild/message-response-fail-result.rs");
+}
+
+/// Compile tests of the constructors of `Actor`, which resolve with the prelude only and require
+/// the actor to run in a `Context`.
+///
+/// Only run on MSRV to ensure stable compile errors.
+#[rustversion::stable(1.68)]
+#[test]
+fn compile_actor_constructors() {
+    let t = trybuild::TestCases::new();
+
+    t.pass("tests/trybuild/start.rs");
+    t.compile_fail("tests/trybuild/start-self-fail.rs");
+    t.compile_fail("tests/trybuild/start-default-fail.rs");
+    t.compile_fail("tests/trybuild/start-create-fail.rs");
+    t.compile_fail("tests/trybuild/start-in-fail.rs");
+}
@@ -0,0 +1,22 @@
+use actix::prelude::*;
+
+#[derive(Message)]
+#[rtype(result = "u32")]
+struct Count;
+
+struct Counter;
+
+impl Actor for Counter {
+    type Context = Context<Self>;
+}
+
+// the result of the message is not optional
+impl Handler<Count> for Counter {
+    type Result = Option<u32>;
+
+    fn handle(&mut self, _: Count, _: &mut Self::Context) -> Option<u32> {
+        Some(1)
+    }
+}
+
+fn main() {}
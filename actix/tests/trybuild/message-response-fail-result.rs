@@ -0,0 +1,24 @@
+use std::io;
+
+use actix::prelude::*;
+
+#[derive(Message)]
+#[rtype(result = "Result<u32, io::Error>")]
+struct Read;
+
+struct Reader;
+
+impl Actor for Reader {
+    type Context = Context<Self>;
+}
+
+// the error type differs from the one of the message
+impl Handler<Read> for Reader {
+    type Result = Result<u32, String>;
+
+    fn handle(&mut self, _: Read, _: &mut Self::Context) -> Result<u32, String> {
+        Ok(1)
+    }
+}
+
+fn main() {}
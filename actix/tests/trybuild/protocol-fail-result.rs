@@ -0,0 +1,38 @@
+use actix::{prelude::*, protocol, ProtoAddr};
+
+#[derive(Message)]
+#[rtype(result = "bool")]
+struct Hello;
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Data;
+
+protocol!(type Session = [Hello -> u32, Data -> ()]);
+
+struct Server;
+
+impl Actor for Server {
+    type Context = Context<Self>;
+}
+
+impl Handler<Hello> for Server {
+    type Result = bool;
+
+    fn handle(&mut self, _: Hello, _: &mut Self::Context) -> bool {
+        true
+    }
+}
+
+impl Handler<Data> for Server {
+    type Result = ();
+
+    fn handle(&mut self, _: Data, _: &mut Self::Context) {}
+}
+
+#[actix::main]
+async fn main() {
+    let session = ProtoAddr::<_, Session>::new(Server.start());
+    let (_, session) = session.send(Hello).await.unwrap();
+    let _ = session.send(Data).await.unwrap();
+}
@@ -0,0 +1,13 @@
+use actix::prelude::*;
+
+#[derive(Default)]
+struct Worker;
+
+// sync actors are started with SyncArbiter
+impl Actor for Worker {
+    type Context = SyncContext<Self>;
+}
+
+fn main() {
+    let _ = Worker::start_default();
+}
@@ -0,0 +1,20 @@
+use actix::prelude::*;
+
+#[derive(Default)]
+struct MyActor;
+
+impl Actor for MyActor {
+    type Context = Context<Self>;
+}
+
+fn main() {
+    let sys = System::new();
+    sys.block_on(async {
+        let _: Addr<MyActor> = MyActor.start();
+        let _: Addr<MyActor> = MyActor::start_default();
+        let _: Addr<MyActor> = MyActor::create(|_| MyActor);
+        let _: Addr<MyActor> = MyActor.start_in(&Arbiter::current());
+        System::current().stop();
+    });
+    sys.run().unwrap();
+}